@@ -0,0 +1,193 @@
+use bevy::prelude::*;
+
+use crate::{
+    body::{Body, Material},
+    error::{report, ErrorToast},
+    locale::t,
+    profile::{record_result_system, Profile},
+    training::Training,
+    ui::Toast,
+    AppState, BattleSummary, DamageEvent, Game,
+};
+
+#[derive(Default)]
+pub struct BattleStats {
+    pub damage_taken: f32,
+    pub damage_dealt: f32,
+    pub hits_landed: u32,
+    pub combo: u32,
+    pub best_combo: u32,
+}
+
+pub struct AchievementContext<'a> {
+    pub stats: &'a BattleStats,
+    pub body: Option<&'a Body>,
+    // Only set once the battle is over, so predicates about the outcome
+    // never fire mid-battle.
+    pub won: Option<bool>,
+    pub sudden_death: bool,
+    pub wins: u32,
+}
+
+// Names and descriptions live in the language files under `achievement.<id>`.
+pub struct Achievement {
+    pub id: &'static str,
+    pub unlocked: fn(&AchievementContext) -> bool,
+    pub progress: Option<fn(&Profile) -> (u32, u32)>,
+}
+
+impl Achievement {
+    pub fn name(&self) -> String {
+        t!(format!("achievement.{}.name", self.id))
+    }
+
+    pub fn description(&self) -> String {
+        t!(format!("achievement.{}.description", self.id))
+    }
+}
+
+pub const ACHIEVEMENTS: &[Achievement] = &[
+    Achievement {
+        id: "first_win",
+        unlocked: |ctx| ctx.won == Some(true),
+        progress: None,
+    },
+    Achievement {
+        id: "flawless",
+        unlocked: |ctx| ctx.won == Some(true) && ctx.stats.damage_taken == 0.0,
+        progress: None,
+    },
+    Achievement {
+        id: "plastic",
+        unlocked: |ctx| {
+            ctx.won == Some(true)
+                && ctx
+                    .body
+                    .is_some_and(|body| body.materials().all(|m| m == Material::Plastic))
+        },
+        progress: None,
+    },
+    Achievement {
+        id: "combo_5",
+        unlocked: |ctx| ctx.stats.best_combo >= 5,
+        progress: Some(|profile| (profile.best_combo.min(5), 5)),
+    },
+    Achievement {
+        id: "sudden_death",
+        unlocked: |ctx| ctx.won == Some(true) && ctx.sudden_death,
+        progress: None,
+    },
+    Achievement {
+        id: "veteran",
+        unlocked: |ctx| ctx.wins >= 10,
+        progress: Some(|profile| (profile.wins.min(10), 10)),
+    },
+];
+
+fn unlock_achievements(
+    profile: &mut Profile,
+    toasts: &mut EventWriter<Toast>,
+    ctx: &AchievementContext,
+) -> bool {
+    let mut unlocked = false;
+    for achievement in ACHIEVEMENTS {
+        if !profile.achievements.contains(achievement.id) && (achievement.unlocked)(ctx) {
+            profile.achievements.insert(achievement.id.to_string());
+            toasts.send(Toast(t!(
+                "achievements.unlocked",
+                name = achievement.name()
+            )));
+            unlocked = true;
+        }
+    }
+    unlocked
+}
+
+fn reset_stats_system(mut commands: Commands) {
+    commands.insert_resource(BattleStats::default());
+}
+
+fn track_stats_system(
+    game: Res<Game>,
+    mut stats: ResMut<BattleStats>,
+    mut damage: EventReader<DamageEvent>,
+) {
+    for event in damage.iter() {
+        if event.target == game.player {
+            stats.damage_taken += event.amount.max(0.0);
+            if event.source.attacker() == Some(game.enemy) {
+                stats.combo = 0;
+            }
+        } else if event.target == game.enemy && event.source.dealer() == Some(game.player) {
+            stats.damage_dealt += event.amount;
+            if event.source.attacker() == Some(game.player) {
+                stats.hits_landed += 1;
+                stats.combo += 1;
+                stats.best_combo = stats.best_combo.max(stats.combo);
+            }
+        }
+    }
+}
+
+fn battle_achievement_system(
+    stats: Res<BattleStats>,
+    training: Option<Res<Training>>,
+    mut profile: ResMut<Profile>,
+    mut toasts: EventWriter<Toast>,
+    mut errors: EventWriter<ErrorToast>,
+) {
+    // A combo on the training dummy isn't earned.
+    if !stats.is_changed() || training.is_some() {
+        return;
+    }
+    profile.best_combo = profile.best_combo.max(stats.best_combo);
+    let ctx = AchievementContext {
+        stats: &stats,
+        body: None,
+        won: None,
+        sudden_death: false,
+        wins: profile.wins,
+    };
+    if unlock_achievements(&mut profile, &mut toasts, &ctx) {
+        report(&mut errors, profile.save());
+    }
+}
+
+fn end_achievement_system(
+    game: Res<Game>,
+    stats: Res<BattleStats>,
+    summary: Res<BattleSummary>,
+    bodies: Query<&Body>,
+    mut profile: ResMut<Profile>,
+    mut toasts: EventWriter<Toast>,
+    mut errors: EventWriter<ErrorToast>,
+) {
+    profile.best_combo = profile.best_combo.max(stats.best_combo);
+    let ctx = AchievementContext {
+        stats: &stats,
+        body: bodies.get(game.player).ok(),
+        won: Some(summary.winner == Some(game.player)),
+        sudden_death: summary.sudden_death,
+        wins: profile.wins,
+    };
+    unlock_achievements(&mut profile, &mut toasts, &ctx);
+    report(&mut errors, profile.save());
+}
+
+pub struct AchievementPlugin;
+
+impl Plugin for AchievementPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<BattleStats>()
+            .add_system_set(SystemSet::on_enter(AppState::Battle).with_system(reset_stats_system))
+            .add_system_set(
+                SystemSet::on_update(AppState::Battle)
+                    .with_system(track_stats_system.after(crate::battle::animation_system))
+                    .with_system(battle_achievement_system.after(track_stats_system)),
+            )
+            .add_system_set(
+                SystemSet::on_enter(AppState::GameOver)
+                    .with_system(end_achievement_system.after(record_result_system)),
+            );
+    }
+}
@@ -0,0 +1,252 @@
+use crate::{
+    body::{Body, Material, Stats},
+    locale::t,
+};
+
+// Parts of one material a body needs for the set bonus. Every part sharing it
+// is the full set, which counts twice.
+pub const SET_PARTS: usize = 3;
+
+const WOOD_REGEN: f32 = 0.15;
+const STEEL_POISE: f32 = 0.1;
+const PLASTIC_WEIGHT: f32 = 0.15;
+
+// The materials with a set bonus, in the order the editor lists them.
+const BONUSES: [Material; 3] = [Material::Wood, Material::Steel, Material::Plastic];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Tier {
+    Set,
+    Full,
+}
+
+impl Tier {
+    fn scale(self) -> f32 {
+        match self {
+            Tier::Set => 1.0,
+            Tier::Full => 2.0,
+        }
+    }
+}
+
+// How far a body is into one material's set bonus.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Affinity {
+    pub material: Material,
+    pub parts: usize,
+    // Every part on the body, torso and head included.
+    pub total: usize,
+}
+
+impl Affinity {
+    pub fn tier(&self) -> Option<Tier> {
+        if self.parts == 0 {
+            None
+        } else if self.parts == self.total {
+            Some(Tier::Full)
+        } else if self.parts >= SET_PARTS {
+            Some(Tier::Set)
+        } else {
+            None
+        }
+    }
+
+    // Parts the next tier takes, None once it's the full set.
+    pub fn next(&self) -> Option<usize> {
+        match self.tier() {
+            None => Some(SET_PARTS.min(self.total)),
+            Some(Tier::Set) => Some(self.total),
+            Some(Tier::Full) => None,
+        }
+    }
+
+    fn id(&self) -> &'static str {
+        match self.material {
+            Material::Wood => "wood",
+            Material::Steel => "steel",
+            _ => "plastic",
+        }
+    }
+
+    // The bonus as a share, before the full set doubles it.
+    fn share(&self) -> f32 {
+        match self.material {
+            Material::Wood => WOOD_REGEN,
+            Material::Steel => STEEL_POISE,
+            _ => PLASTIC_WEIGHT,
+        }
+    }
+
+    pub fn name(&self) -> String {
+        t!(format!("affinity.{}.name", self.id()))
+    }
+
+    // What it does at its tier, or would do at the set tier.
+    pub fn effect(&self) -> String {
+        let scale = self.tier().map_or(1.0, Tier::scale);
+        t!(
+            format!("affinity.{}.effect", self.id()),
+            percent = self.share() * scale * 100.0
+        )
+    }
+
+    // One line for the editor, "2/3 Steel parts" until the set is on.
+    pub fn describe(&self) -> String {
+        let material = self.material.name();
+        match self.tier() {
+            Some(_) => t!(
+                "affinity.active",
+                name = self.name(),
+                parts = self.parts,
+                total = self.total,
+                material = material,
+                effect = self.effect()
+            ),
+            None => t!(
+                "affinity.progress",
+                name = self.name(),
+                parts = self.parts,
+                next = self.next().unwrap_or(self.total),
+                material = material
+            ),
+        }
+    }
+}
+
+// One entry for every material with a set bonus, whether the body has any of
+// it or not.
+pub fn affinities(body: &Body) -> Vec<Affinity> {
+    let total = body.materials().count();
+    BONUSES
+        .into_iter()
+        .map(|material| Affinity {
+            material,
+            parts: body.materials().filter(|m| *m == material).count(),
+            total,
+        })
+        .collect()
+}
+
+// Folds the bonuses of the sets the body has into its stats, see
+// `compute_stats`.
+pub fn apply(body: &Body, stats: &mut Stats) {
+    for affinity in affinities(body) {
+        let Some(tier) = affinity.tier() else {
+            continue;
+        };
+        let share = affinity.share() * tier.scale();
+        match affinity.material {
+            Material::Wood => stats.regen_bonus += share,
+            Material::Steel => stats.poise_bonus += share,
+            _ => stats.weight *= 1.0 - share,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        body::{compute_stats, BodyPart, BodyPartMeta, PartStats},
+        fixtures::seeded_bodies,
+    };
+
+    fn made_of<M: BodyPartMeta + Clone>(part: &BodyPart<M>, material: Material) -> BodyPart<M> {
+        let stats = part.stats();
+        let stats = PartStats::new(
+            material,
+            stats.size(),
+            stats.weight(),
+            stats.health(),
+            stats.energy(),
+        )
+        .with_skills(stats.skills().to_vec());
+        BodyPart::new(part.name(), stats, part.meta().clone()).with_slot(part.slot())
+    }
+
+    // Torso, head, both arms, then both legs.
+    fn body_of(materials: [Material; 6]) -> Body {
+        let mut body = seeded_bodies(1, 2, 2).remove(0);
+        assert_eq!(body.materials().count(), 6);
+        *body.torso_mut() = made_of(body.torso(), materials[0]);
+        *body.head_mut() = made_of(body.head(), materials[1]);
+        for (arm, material) in body.arms_mut().iter_mut().zip(&materials[2..4]) {
+            *arm = made_of(arm, *material);
+        }
+        for (leg, material) in body.legs_mut().iter_mut().zip(&materials[4..]) {
+            *leg = made_of(leg, *material);
+        }
+        body
+    }
+
+    fn steel(parts: usize) -> Body {
+        let others = [Material::Stone, Material::Bronze, Material::Aluminum];
+        body_of(std::array::from_fn(|i| {
+            if i < parts {
+                Material::Steel
+            } else {
+                others[i % others.len()]
+            }
+        }))
+    }
+
+    fn steel_affinity(body: &Body) -> Affinity {
+        affinities(body)
+            .into_iter()
+            .find(|affinity| affinity.material == Material::Steel)
+            .unwrap()
+    }
+
+    #[test]
+    fn a_third_part_starts_the_set_and_only_every_part_makes_it_full() {
+        let two = steel(2);
+        assert_eq!(steel_affinity(&two).tier(), None);
+        assert_eq!(steel_affinity(&two).next(), Some(3));
+        assert_eq!(compute_stats(&two).poise_bonus, 0.0);
+
+        let three = steel(3);
+        assert_eq!(steel_affinity(&three).tier(), Some(Tier::Set));
+        assert_eq!(steel_affinity(&three).next(), Some(6));
+        assert_eq!(compute_stats(&three).poise_bonus, STEEL_POISE);
+
+        let four = steel(4);
+        assert_eq!(steel_affinity(&four).tier(), Some(Tier::Set));
+        assert_eq!(compute_stats(&four).poise_bonus, STEEL_POISE);
+
+        let full = steel(6);
+        assert_eq!(steel_affinity(&full).tier(), Some(Tier::Full));
+        assert_eq!(compute_stats(&full).poise_bonus, STEEL_POISE * 2.0);
+    }
+
+    #[test]
+    fn a_set_part_rusting_drops_the_bonus_and_the_others_keep_theirs() {
+        let mut body = body_of([
+            Material::Steel,
+            Material::Steel,
+            Material::Steel,
+            Material::Plastic,
+            Material::Plastic,
+            Material::Plastic,
+        ]);
+        let mut stats = Stats {
+            weight: 100.0,
+            ..Default::default()
+        };
+        apply(&body, &mut stats);
+        assert_eq!(stats.poise_bonus, STEEL_POISE);
+        assert!((stats.weight - 85.0).abs() < 1e-3);
+
+        // Plastic can't rust, the Steel all goes at once.
+        while body.materials().any(|material| material == Material::Steel) {
+            body.end_battle(0.0);
+        }
+        let stats = compute_stats(&body);
+        assert_eq!(stats.poise_bonus, 0.0);
+        assert_eq!(stats.regen_bonus, 0.0);
+        let plastic = affinities(&body)[2];
+        assert_eq!(
+            (plastic.material, plastic.tier()),
+            (Material::Plastic, Some(Tier::Set))
+        );
+    }
+}
@@ -0,0 +1,806 @@
+use std::fmt;
+
+use bevy::prelude::*;
+use rand::{Rng, RngCore};
+
+use crate::{
+    ammo, approach,
+    auto::Autopilot,
+    balance::BalanceConfig,
+    battle::{edge_gap, walk_step, Animation, Ground},
+    body::{BodyStats, Facing, Skill, SkillId, Stats},
+    energy,
+    hitbox::BodyGeometry,
+    interrupt::Recovery,
+    pickup::{self, Pickup, PickupKind},
+    projectile::{flight_path, Projectile},
+    runtime::{self, SkillRuntime},
+    timer::SimClock,
+    turn::{Ruleset, Turns},
+    ui::UseSkill,
+    AppState, Game, GameRng,
+};
+
+// Picks what a body does next. The built-in ones are registered in
+// AiRegistry as "random", "aggressive" and "defensive", anything else
+// registered there can be picked by name the same way.
+pub trait Combatant: Send + Sync {
+    // The skill to play, None to hold back. Asked once every think, which
+    // is a reaction time apart in real time and once a turn in turns.
+    fn decide(&mut self, view: &BattleView, rng: &mut dyn RngCore) -> Option<SkillId>;
+
+    // Asked every frame between thinks in real time, for what can't wait.
+    fn react(&mut self, _view: &BattleView) -> Option<SkillId> {
+        None
+    }
+
+    // When `decide` picked walking forward in real time, how close to get to
+    // the opponent in one long walk. None walks a step per think.
+    fn approach(&self, _view: &BattleView) -> Option<f32> {
+        None
+    }
+
+    // What the last pick weighed up for the debug view, what was played
+    // first.
+    fn explain(&self) -> Vec<Candidate> {
+        Vec::new()
+    }
+}
+
+// The opponent as the deciding body sees it on screen.
+pub struct OpponentView<'a> {
+    stats: &'a Stats,
+    x: f32,
+    facing: Facing,
+    reloading: bool,
+}
+
+impl<'a> OpponentView<'a> {
+    pub fn reloading(&self) -> bool {
+        self.reloading
+    }
+}
+
+// Everything a Combatant gets to go by, built fresh for every think. It only
+// hands out copies and shared references, nothing in the battle can be
+// changed through it.
+pub struct BattleView<'a> {
+    balance: &'a BalanceConfig,
+    ground: Ground,
+    stats: &'a Stats,
+    x: f32,
+    facing: Facing,
+    runtime: Option<&'a SkillRuntime>,
+    opponent: Option<OpponentView<'a>>,
+    // Shots at this body, where they are and how fast they fly, once it
+    // has had its reaction time to see them.
+    incoming: Vec<(Vec2, Vec2)>,
+    pickups: &'a [(PickupKind, f32)],
+    hits: &'a dyn Fn(Vec2) -> bool,
+}
+
+impl<'a> BattleView<'a> {
+    pub fn balance(&self) -> &'a BalanceConfig {
+        self.balance
+    }
+
+    pub fn ground(&self) -> Ground {
+        self.ground
+    }
+
+    // Its own, skill cooldowns and costs included.
+    pub fn stats(&self) -> &'a Stats {
+        self.stats
+    }
+
+    pub fn x(&self) -> f32 {
+        self.x
+    }
+
+    pub fn facing(&self) -> Facing {
+        self.facing
+    }
+
+    // Shots and cooldowns of its own skills.
+    pub fn runtime(&self) -> Option<&'a SkillRuntime> {
+        self.runtime
+    }
+
+    // Has the energy, the shots and the rest to play `skill` right now.
+    pub fn playable(&self, skill: &Skill) -> bool {
+        energy::can_afford(self.stats, skill)
+            && ammo::loaded(self.runtime, skill)
+            && runtime::ready(self.runtime, skill)
+    }
+
+    pub fn opponent(&self) -> Option<&OpponentView<'a>> {
+        self.opponent.as_ref()
+    }
+
+    pub fn incoming(&self) -> &[(Vec2, Vec2)] {
+        &self.incoming
+    }
+
+    // Kind and x of everything lying on the ground.
+    pub fn pickups(&self) -> &'a [(PickupKind, f32)] {
+        self.pickups
+    }
+
+    // Whether a point lands on this body where it stands now.
+    pub fn hits(&self, point: Vec2) -> bool {
+        (self.hits)(point)
+    }
+
+    // Gap between the bodies' edges, like `edge_gap`.
+    pub fn gap(&self) -> Option<f32> {
+        self.opponent.as_ref().map(|opponent| {
+            let offset = opponent.x - self.x;
+            let front = Facing::towards(opponent.x, self.x) == opponent.facing;
+            offset.abs()
+                - (self.stats.width + opponent.stats.width) / 2.0
+                - opponent.stats.guard(front)
+        })
+    }
+
+    pub fn facing_opponent(&self) -> bool {
+        self.opponent
+            .as_ref()
+            .is_some_and(|opponent| Facing::towards(self.x, opponent.x) == self.facing)
+    }
+
+    // Like `melee_in_reach`.
+    pub fn in_reach(&self) -> bool {
+        self.facing_opponent()
+            && self
+                .gap()
+                .is_some_and(|gap| self.stats.reach().is_some_and(|reach| gap <= reach))
+    }
+}
+
+type Factory = Box<dyn Fn() -> Box<dyn Combatant> + Send + Sync>;
+
+// Every controller a body can be handed, by name, in the order they're
+// offered on the pre-battle screen.
+pub struct AiRegistry {
+    factories: Vec<(String, Factory)>,
+}
+
+impl Default for AiRegistry {
+    fn default() -> Self {
+        let mut registry = Self {
+            factories: Vec::new(),
+        };
+        registry
+            .register("random", || Box::<RandomAi>::default())
+            .register("aggressive", || Box::<AggressiveAi>::default())
+            .register("defensive", || Box::<DefensiveAi>::default());
+        registry
+    }
+}
+
+impl AiRegistry {
+    // Takes the place of one by the same name.
+    pub fn register(
+        &mut self,
+        name: &str,
+        factory: impl Fn() -> Box<dyn Combatant> + Send + Sync + 'static,
+    ) -> &mut Self {
+        self.factories.retain(|(existing, _)| existing != name);
+        self.factories.push((name.to_string(), Box::new(factory)));
+        self
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.factories.iter().map(|(name, _)| name.as_str())
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.names().any(|registered| registered == name)
+    }
+
+    pub fn create(&self, name: &str) -> Option<AiController> {
+        let (name, factory) = self
+            .factories
+            .iter()
+            .find(|(registered, _)| registered == name)?;
+        Some(AiController::new(name, factory()))
+    }
+}
+
+#[derive(Component)]
+pub struct AiController {
+    name: String,
+    brain: Box<dyn Combatant>,
+    wait: f32,
+    decision: Option<Decision>,
+}
+
+impl Default for AiController {
+    fn default() -> Self {
+        Self::new("random", Box::<RandomAi>::default())
+    }
+}
+
+impl AiController {
+    pub fn new(name: &str, brain: Box<dyn Combatant>) -> Self {
+        Self {
+            name: name.to_string(),
+            brain,
+            wait: 0.0,
+            decision: None,
+        }
+    }
+
+    // What it was registered as.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    // What it thought the last time it picked a skill, for debugging.
+    pub fn decision(&self) -> Option<&Decision> {
+        self.decision.as_ref()
+    }
+}
+
+// Where a candidate came from. They're tried in this order and the first one
+// is played, the AI goes by rules rather than weighing everything up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Reason {
+    Dodge,
+    Pickup,
+    Plan,
+    Random,
+}
+
+impl Reason {
+    fn name(self) -> &'static str {
+        match self {
+            Reason::Dodge => "dodge",
+            Reason::Pickup => "pickup",
+            Reason::Plan => "plan",
+            Reason::Random => "random",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Candidate {
+    pub skill: String,
+    pub reason: Reason,
+    // Only pickups are scored, what they're worth after the walk.
+    pub score: Option<f32>,
+}
+
+impl fmt::Display for Candidate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} ({}", self.skill, self.reason.name())?;
+        if let Some(score) = self.score {
+            write!(f, " {:.2}", score)?;
+        }
+        f.write_str(")")
+    }
+}
+
+// One think of the AI, kept on its controller and in the battle summary.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Decision {
+    // Gap between the bodies' edges, None without a target, and which of the
+    // AI's distances it falls in.
+    pub gap: Option<f32>,
+    pub band: &'static str,
+    pub considered: Vec<Candidate>,
+    // Index into `considered` of what was played, None while it held back or
+    // couldn't play it.
+    pub chosen: Option<usize>,
+    // Skills it couldn't have played right then, and why.
+    pub ruled_out: Vec<(String, &'static str)>,
+}
+
+impl fmt::Display for Decision {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(gap) = self.gap {
+            write!(f, "gap {:.2} ({}), ", gap, self.band)?;
+        }
+        let considered = self
+            .considered
+            .iter()
+            .enumerate()
+            .map(|(i, candidate)| {
+                let mark = if self.chosen == Some(i) { "*" } else { "" };
+                format!("{}{}", mark, candidate)
+            })
+            .collect::<Vec<_>>();
+        if considered.is_empty() {
+            f.write_str("nothing to do")?;
+        } else {
+            f.write_str(&considered.join(", "))?;
+        }
+        if !self.ruled_out.is_empty() {
+            let ruled_out = self
+                .ruled_out
+                .iter()
+                .map(|(skill, why)| format!("{} ({})", skill, why))
+                .collect::<Vec<_>>();
+            write!(f, "; ruled out {}", ruled_out.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+// `reach` is the body's, see `Stats::reach`.
+fn band(reach: Option<f32>, gap: Option<f32>) -> &'static str {
+    match gap {
+        None => "no target",
+        Some(gap) if reach.is_some_and(|reach| gap <= reach) => "melee",
+        Some(gap) if gap < RANGED_BAND.0 => "close",
+        Some(gap) if gap <= RANGED_BAND.1 => "ranged",
+        Some(_) => "far",
+    }
+}
+
+// What keeps each of the body's skills from playing right now.
+fn ruled_out(stats: &Stats, runtime: Option<&SkillRuntime>) -> Vec<(String, &'static str)> {
+    stats
+        .skills
+        .iter()
+        .filter_map(|skill| {
+            let why = if !energy::can_afford(stats, skill) {
+                "no energy"
+            } else if !ammo::loaded(runtime, skill) {
+                "out of shots"
+            } else if !runtime::ready(runtime, skill) {
+                "cooling down"
+            } else {
+                return None;
+            };
+            Some((skill.get_name(), why))
+        })
+        .collect()
+}
+
+// Gap between the bodies' edges a ranged-heavy body tries to shoot from.
+const RANGED_BAND: (f32, f32) = (2.0, 7.0);
+
+fn ranged_heavy(stats: &Stats) -> bool {
+    let (ranged, melee) = stats
+        .skills
+        .iter()
+        .fold((0, 0), |(ranged, melee), skill| match skill {
+            Skill::BasicRanged(_) => (ranged + 1, melee),
+            Skill::BasicMelee(_) | Skill::Slam(_) => (ranged, melee + 1),
+            _ => (ranged, melee),
+        });
+    ranged > melee
+}
+
+fn find(stats: &Stats, f: fn(&Skill) -> bool) -> Option<usize> {
+    stats.skills.iter().position(f)
+}
+
+// Shoots from within RANGED_BAND, walking back into it first. Reloads once
+// every ranged skill is out.
+fn ranged_skill(view: &BattleView, gap: f32) -> Option<usize> {
+    let (stats, runtime) = (view.stats(), view.runtime());
+    let shoot = || {
+        if runtime.is_some_and(|runtime| runtime.empty(stats)) {
+            find(stats, |skill| matches!(skill, Skill::Reload))
+        } else {
+            stats.skills.iter().position(|skill| {
+                matches!(skill, Skill::BasicRanged(_)) && ammo::loaded(runtime, skill)
+            })
+        }
+    };
+    if gap < RANGED_BAND.0 {
+        find(stats, |skill| matches!(skill, Skill::WalkBackward)).or_else(shoot)
+    } else if gap > RANGED_BAND.1 {
+        find(stats, |skill| matches!(skill, Skill::WalkForward)).or_else(shoot)
+    } else {
+        shoot()
+    }
+}
+
+// Attacks when in reach, otherwise closes the distance. Bodies that mostly
+// shoot hold a distance instead, unless the target is busy reloading.
+fn aggressive_skill(view: &BattleView, gap: f32, target_reloading: bool) -> Option<usize> {
+    let stats = view.stats();
+    if !view.facing_opponent() {
+        find(stats, |skill| matches!(skill, Skill::TurnAround))
+    } else if ranged_heavy(stats) && !target_reloading {
+        ranged_skill(view, gap)
+    } else if view.in_reach() {
+        find(stats, |skill| {
+            matches!(skill, Skill::BasicMelee(_) | Skill::Slam(_))
+        })
+        .or_else(|| ranged_skill(view, gap))
+    } else {
+        find(stats, |skill| matches!(skill, Skill::WalkForward))
+    }
+}
+
+// Like aggressive_skill but never walks in, None while there's nothing in reach.
+fn defensive_skill(view: &BattleView, gap: f32) -> Option<usize> {
+    let stats = view.stats();
+    if !view.facing_opponent() {
+        find(stats, |skill| matches!(skill, Skill::TurnAround))
+    } else if ranged_heavy(stats) {
+        ranged_skill(view, gap)
+    } else if view.in_reach() {
+        find(stats, |skill| {
+            matches!(skill, Skill::BasicMelee(_) | Skill::Slam(_))
+        })
+    } else {
+        None
+    }
+}
+
+// A walk that takes the body out of every shot it has seen coming, if standing
+// still gets it hit. A shot is seen once it's been flying for the body's
+// reaction time, so slow bodies are left with less of the flight to get clear.
+fn dodge_skill(view: &BattleView) -> Option<usize> {
+    let (balance, stats) = (view.balance(), view.stats());
+    let walk_time = balance.skill_time();
+    let speed = stats.speed * balance.walk_speed_multiplier;
+    let gets_hit = |mul: f32| {
+        view.incoming().iter().any(|(position, velocity)| {
+            flight_path(balance, view.ground(), *position, *velocity).any(|(t, point)| {
+                let dx = walk_step(t.min(walk_time), speed, view.facing().sign(), mul);
+                view.hits(point - Vec2::X * dx)
+            })
+        })
+    };
+    if !gets_hit(0.0) {
+        return None;
+    }
+    stats.skills.iter().position(|skill| {
+        balance
+            .walk_multiplier(skill)
+            .is_some_and(|mul| energy::can_afford(stats, skill) && !gets_hit(mul))
+    })
+}
+
+// Going for a pickup means not attacking, while there's a hit to land it
+// has to be worth more.
+fn detour_skill(view: &BattleView) -> Option<(usize, f32)> {
+    pickup::detour_skill(
+        view.stats(),
+        view.x(),
+        view.facing(),
+        view.pickups(),
+        view.in_reach(),
+    )
+}
+
+type Pick = Option<(usize, Reason, Option<f32>)>;
+
+// The built-in profiles go by rules rather than weighing everything up, the
+// first of `picks` gets played. Everything is kept so the debug view can
+// show what lost out. Without any, a random skill unless it `holds`.
+fn first_of(
+    view: &BattleView,
+    rng: &mut dyn RngCore,
+    picks: &[Pick],
+    holds: bool,
+    considered: &mut Vec<Candidate>,
+) -> Option<SkillId> {
+    let skills = &view.stats().skills;
+    let mut picks = picks.iter().flatten().copied().collect::<Vec<_>>();
+    // Swapping a limb needs a spare picked in the editor, which no AI has.
+    let random = (0..skills.len())
+        .filter(|index| !matches!(skills[*index], Skill::SwapLimb))
+        .collect::<Vec<_>>();
+    if picks.is_empty() && !holds && !random.is_empty() {
+        let index = random[rng.gen_range(0..random.len())];
+        picks.push((index, Reason::Random, None));
+    }
+    *considered = picks
+        .iter()
+        .map(|(index, reason, score)| Candidate {
+            skill: skills[*index].get_name(),
+            reason: *reason,
+            score: *score,
+        })
+        .collect();
+    picks.first().map(|(index, ..)| skills[*index].id())
+}
+
+#[derive(Default)]
+pub struct RandomAi {
+    considered: Vec<Candidate>,
+}
+
+impl Combatant for RandomAi {
+    fn decide(&mut self, view: &BattleView, rng: &mut dyn RngCore) -> Option<SkillId> {
+        first_of(view, rng, &[], false, &mut self.considered)
+    }
+
+    fn explain(&self) -> Vec<Candidate> {
+        self.considered.clone()
+    }
+}
+
+#[derive(Default)]
+pub struct AggressiveAi {
+    considered: Vec<Candidate>,
+    detoured: bool,
+}
+
+impl Combatant for AggressiveAi {
+    fn decide(&mut self, view: &BattleView, rng: &mut dyn RngCore) -> Option<SkillId> {
+        let detour = detour_skill(view);
+        self.detoured = detour.is_some();
+        let plan = view
+            .opponent()
+            .and_then(|opponent| aggressive_skill(view, view.gap()?, opponent.reloading()));
+        let picks = [
+            detour.map(|(skill, score)| (skill, Reason::Pickup, Some(score))),
+            plan.map(|skill| (skill, Reason::Plan, None)),
+        ];
+        first_of(view, rng, &picks, false, &mut self.considered)
+    }
+
+    // Closing in is one long walk rather than a step per think, unless it's
+    // heading for a pickup.
+    fn approach(&self, view: &BattleView) -> Option<f32> {
+        let opponent = view.opponent().filter(|_| !self.detoured)?;
+        if ranged_heavy(view.stats()) && !opponent.reloading() {
+            Some(RANGED_BAND.1)
+        } else {
+            view.stats().reach()
+        }
+    }
+
+    fn explain(&self) -> Vec<Candidate> {
+        self.considered.clone()
+    }
+}
+
+// Holds its ground, hits back in reach and walks out of shots it sees
+// coming.
+#[derive(Default)]
+pub struct DefensiveAi {
+    considered: Vec<Candidate>,
+}
+
+impl Combatant for DefensiveAi {
+    fn decide(&mut self, view: &BattleView, rng: &mut dyn RngCore) -> Option<SkillId> {
+        let dodge = dodge_skill(view);
+        let detour = dodge.is_none().then(|| detour_skill(view)).flatten();
+        let plan = view.gap().and_then(|gap| defensive_skill(view, gap));
+        let picks = [
+            dodge.map(|skill| (skill, Reason::Dodge, None)),
+            detour.map(|(skill, score)| (skill, Reason::Pickup, Some(score))),
+            plan.map(|skill| (skill, Reason::Plan, None)),
+        ];
+        // Holding its ground until something comes to it.
+        first_of(view, rng, &picks, true, &mut self.considered)
+    }
+
+    // Getting out of the way can't wait for the next think.
+    fn react(&mut self, view: &BattleView) -> Option<SkillId> {
+        let skill = &view.stats().skills[dodge_skill(view)?];
+        self.considered = vec![Candidate {
+            skill: skill.get_name(),
+            reason: Reason::Dodge,
+            score: None,
+        }];
+        Some(skill.id())
+    }
+
+    fn explain(&self) -> Vec<Candidate> {
+        self.considered.clone()
+    }
+}
+
+fn ai_system(
+    mut commands: Commands,
+    clock: Res<SimClock>,
+    game: Res<Game>,
+    balance: Res<BalanceConfig>,
+    ground: Res<Ground>,
+    turns: Option<Res<Turns>>,
+    mut rng: ResMut<GameRng>,
+    mut use_skill: ResMut<UseSkill>,
+    mut controllers: Query<
+        (Entity, &mut AiController, Option<&mut Autopilot>),
+        (Without<Animation>, Without<Recovery>),
+    >,
+    bodies: Query<(&Stats, &Transform, &Facing, Option<&SkillRuntime>)>,
+    animations: Query<&Animation>,
+    projectiles: Query<(&Projectile, &Transform)>,
+    pickups: Query<(&Pickup, &Transform)>,
+    geometry: BodyGeometry,
+) {
+    let pickups = pickups
+        .iter()
+        .map(|(pickup, transform)| (pickup.kind, transform.translation.x))
+        .collect::<Vec<_>>();
+    for (entity, mut controller, mut autopilot) in &mut controllers {
+        // A pick still waiting goes first, the AI's own or a click's.
+        if autopilot.is_some() && use_skill.is_some() {
+            continue;
+        }
+        let Ok((stats, transform, facing, runtime)) = bodies.get(entity) else {
+            continue;
+        };
+        if stats.skills.is_empty() {
+            continue;
+        }
+        let opponent = game.opponent(entity);
+        let target = bodies.get(opponent).ok();
+        let hits = |point| geometry.body_hit_test(entity, point).is_some();
+        let view = BattleView {
+            balance: &balance,
+            ground: *ground,
+            stats,
+            x: transform.translation.x,
+            facing: *facing,
+            runtime,
+            opponent: target.map(|(target_stats, target_transform, target_facing, ..)| {
+                OpponentView {
+                    stats: target_stats,
+                    x: target_transform.translation.x,
+                    facing: *target_facing,
+                    reloading: ammo::reloading(animations.get(opponent).ok()),
+                }
+            }),
+            incoming: projectiles
+                .iter()
+                .filter(|(shot, _)| shot.target == entity && shot.age >= stats.reaction_time)
+                .map(|(shot, transform)| (transform.translation.truncate(), shot.velocity))
+                .collect(),
+            pickups: &pickups,
+            hits: &hits,
+        };
+        let rolls: &mut dyn RngCore = match autopilot.as_deref_mut() {
+            Some(autopilot) => autopilot.rng(),
+            None => &mut **rng,
+        };
+        let picked = if let Some(turns) = turns.as_ref() {
+            if !turns.can_act(entity) {
+                continue;
+            }
+            controller.brain.decide(&view, rolls)
+        } else {
+            controller.wait -= clock.delta();
+            let picked = if controller.wait > 0.0 {
+                let Some(skill) = controller.brain.react(&view) else {
+                    continue;
+                };
+                Some(skill)
+            } else {
+                controller.brain.decide(&view, rolls)
+            };
+            controller.wait = stats.reaction_time + 0.5;
+            picked
+        };
+        let picked = picked.and_then(|id| stats.skill(&id));
+        // Too tired for it or out of shots, think again later.
+        let playable = picked.filter(|skill| view.playable(skill));
+        let mut considered = controller.brain.explain();
+        if considered.is_empty() {
+            considered.extend(picked.map(|skill| Candidate {
+                skill: skill.get_name(),
+                reason: Reason::Plan,
+                score: None,
+            }));
+        }
+        let gap = target.map(|(target_stats, target_transform, ..)| {
+            edge_gap(transform, stats, target_transform, target_stats)
+        });
+        controller.decision = Some(Decision {
+            gap,
+            band: band(stats.reach(), gap),
+            considered,
+            chosen: playable.map(|_| 0),
+            ruled_out: ruled_out(stats, runtime),
+        });
+        let Some(skill) = playable else {
+            continue;
+        };
+        // Turns still take a step at a time.
+        let until = (turns.is_none() && matches!(skill, Skill::WalkForward))
+            .then(|| controller.brain.approach(&view))
+            .flatten();
+        if let (Some(until), Some((target_stats, target_transform, ..))) = (until, target) {
+            let refused = approach::refusal(
+                &balance,
+                stats,
+                transform,
+                *facing,
+                target_stats,
+                target_transform,
+                until,
+            );
+            if refused.is_none() {
+                match autopilot.as_deref_mut() {
+                    Some(autopilot) => {
+                        approach::queue(&mut commands, entity, until);
+                        autopilot.queue(&mut use_skill, skill.id());
+                    }
+                    None => approach::start(&mut commands, entity, until),
+                }
+                continue;
+            }
+        }
+        match autopilot.as_deref_mut() {
+            Some(autopilot) => autopilot.queue(&mut use_skill, skill.id()),
+            None => {
+                commands.entity(entity).insert(Animation::new(skill.id()));
+            }
+        }
+    }
+}
+
+// The enemy is handed the controller picked on the pre-battle screen.
+fn pick_ai_system(
+    game: Res<Game>,
+    ruleset: Res<Ruleset>,
+    registry: Res<AiRegistry>,
+    mut controllers: Query<&mut AiController>,
+) {
+    let Ok(mut controller) = controllers.get_mut(game.enemy) else {
+        return;
+    };
+    if controller.name != ruleset.ai {
+        if let Some(picked) = registry.create(&ruleset.ai) {
+            *controller = picked;
+        }
+    }
+}
+
+pub struct AiPlugin;
+
+impl Plugin for AiPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<AiRegistry>()
+            .add_system_set(SystemSet::on_enter(AppState::Battle).with_system(pick_ai_system))
+            .add_system_set(
+                SystemSet::on_update(AppState::Battle).with_system(ai_system.after(BodyStats)),
+            );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::body::compute_stats;
+
+    #[test]
+    fn the_view_measures_the_gap_and_controllers_register() {
+        let stats = compute_stats(&crate::body::Body::default());
+        let balance = BalanceConfig::default();
+        let hits = |_| false;
+        let view = || BattleView {
+            balance: &balance,
+            ground: Ground::default(),
+            stats: &stats,
+            x: 0.0,
+            facing: Facing::Right,
+            runtime: None,
+            opponent: Some(OpponentView {
+                stats: &stats,
+                x: 3.0,
+                facing: Facing::Left,
+                reloading: false,
+            }),
+            incoming: Vec::new(),
+            pickups: &[],
+            hits: &hits,
+        };
+        // It faces this way, its front arm hangs out into the gap.
+        assert_eq!(view().gap(), Some(3.0 - stats.width - stats.guard(true)));
+
+        let registry = AiRegistry::default();
+        assert_eq!(
+            registry.names().collect::<Vec<_>>(),
+            ["random", "aggressive", "defensive"]
+        );
+        let mut controller = registry.create("defensive").unwrap();
+        assert_eq!(controller.name(), "defensive");
+        // Nothing in reach and nothing coming, it holds its ground.
+        let mut rng = GameRng::new(1);
+        assert_eq!(controller.brain.decide(&view(), &mut *rng), None);
+        assert!(registry.create("scripted").is_none());
+    }
+}
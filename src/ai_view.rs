@@ -0,0 +1,144 @@
+use bevy::prelude::*;
+
+use crate::{
+    ai::{AiController, Decision},
+    body::Body,
+    camera::MainCamera,
+    ui::Fonts,
+};
+
+const WIDTH: f32 = 360.0;
+// Sits on top of the nameplate over the head.
+const ABOVE_HEAD: f32 = 70.0;
+const FONT_SIZE: f32 = 14.0;
+const TEXT_COLOR: Color = Color::rgb(0.85, 0.85, 0.85);
+const CHOSEN_COLOR: Color = Color::rgb(1.0, 0.85, 0.2);
+const RULED_OUT_COLOR: Color = Color::rgb(0.6, 0.45, 0.45);
+
+// Toggled with the `ai` console command, shows the last decision of every
+// AI-driven body over its head.
+#[derive(Default)]
+pub struct ShowAiDecisions(pub bool);
+
+// The readout over the body it belongs to.
+#[derive(Component)]
+struct DecisionText(Entity);
+
+// One section per line and candidate, the candidate that got played stands
+// out.
+fn decision_sections(decision: &Decision) -> Vec<(String, Color)> {
+    let mut sections = vec![(
+        match decision.gap {
+            Some(gap) => format!("gap {:.2} ({})\n", gap, decision.band),
+            None => format!("{}\n", decision.band),
+        },
+        TEXT_COLOR,
+    )];
+    if decision.considered.is_empty() {
+        sections.push(("holding\n".to_string(), TEXT_COLOR));
+    }
+    for (i, candidate) in decision.considered.iter().enumerate() {
+        let chosen = decision.chosen == Some(i);
+        let separator = if i + 1 == decision.considered.len() {
+            "\n"
+        } else {
+            ", "
+        };
+        sections.push(if chosen {
+            (format!("> {}{}", candidate, separator), CHOSEN_COLOR)
+        } else {
+            (format!("{}{}", candidate, separator), TEXT_COLOR)
+        });
+    }
+    if !decision.ruled_out.is_empty() {
+        let ruled_out = decision
+            .ruled_out
+            .iter()
+            .map(|(skill, why)| format!("{} ({})", skill, why))
+            .collect::<Vec<_>>();
+        sections.push((
+            format!("ruled out {}\n", ruled_out.join(", ")),
+            RULED_OUT_COLOR,
+        ));
+    }
+    sections
+}
+
+fn decision_view_system(
+    mut commands: Commands,
+    show: Res<ShowAiDecisions>,
+    fonts: Res<Fonts>,
+    cameras: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    bodies: Query<(Entity, &AiController, &Body, &GlobalTransform)>,
+    mut texts: Query<(Entity, &DecisionText, &mut Text, &mut Style)>,
+) {
+    let camera = cameras.get_single().ok();
+    let mut shown = Vec::new();
+    for (entity, DecisionText(owner), mut text, mut style) in &mut texts {
+        let placed = bodies.get(*owner).ok().filter(|_| show.0).and_then(
+            |(_, controller, body, transform)| {
+                let (camera, camera_transform) = camera?;
+                let head = transform.translation() + Vec3::Y * (body.height() + 0.2);
+                let screen = camera.world_to_viewport(camera_transform, head)?;
+                Some((controller.decision(), screen))
+            },
+        );
+        let Some((decision, screen)) = placed else {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        };
+        shown.push(*owner);
+        style.position = UiRect {
+            left: Val::Px(screen.x - WIDTH / 2.0),
+            bottom: Val::Px(screen.y + ABOVE_HEAD),
+            ..default()
+        };
+        let sections = decision.map(decision_sections).unwrap_or_default();
+        let unchanged = text.sections.len() == sections.len()
+            && text
+                .sections
+                .iter()
+                .zip(&sections)
+                .all(|(section, (value, _))| section.value == *value);
+        if !unchanged {
+            text.sections = sections
+                .into_iter()
+                .map(|(value, color)| {
+                    TextSection::new(
+                        value,
+                        TextStyle {
+                            font: fonts.normal(),
+                            font_size: FONT_SIZE,
+                            color,
+                        },
+                    )
+                })
+                .collect();
+        }
+    }
+    if !show.0 {
+        return;
+    }
+    for (entity, ..) in &bodies {
+        if shown.contains(&entity) {
+            continue;
+        }
+        // Placed on the next frame.
+        commands
+            .spawn_bundle(TextBundle::default().with_style(Style {
+                position_type: PositionType::Absolute,
+                max_size: Size::new(Val::Px(WIDTH), Val::Undefined),
+                ..default()
+            }))
+            .insert(DecisionText(entity));
+    }
+}
+
+pub struct AiViewPlugin;
+
+impl Plugin for AiViewPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ShowAiDecisions>()
+            .add_system_to_stage(CoreStage::PostUpdate, decision_view_system);
+    }
+}
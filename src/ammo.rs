@@ -0,0 +1,67 @@
+use bevy::prelude::*;
+
+use crate::{
+    battle::{animation_system, Animation},
+    body::Skill,
+    projectile::Projectile,
+    runtime::SkillRuntime,
+    AppState, Game,
+};
+
+pub const RELOAD_TIME: f32 = 2.0;
+
+// What a body has shot this battle, for the game-over screen. The shots each
+// ranged skill has left are kept in its SkillRuntime.
+#[derive(Component, Default, Debug)]
+pub struct Ammo {
+    pub fired: u32,
+    pub hit: u32,
+}
+
+// Whether `skill` can start as far as ammo goes, bodies without a
+// SkillRuntime never run out.
+pub fn loaded(runtime: Option<&SkillRuntime>, skill: &Skill) -> bool {
+    runtime.is_none_or(|runtime| runtime.loaded(skill))
+}
+
+pub fn reloading(animation: Option<&Animation>) -> bool {
+    animation.is_some_and(|animation| animation.skill == Skill::Reload.id())
+}
+
+fn reset_ammo_system(game: Res<Game>, mut bodies: Query<&mut Ammo>) {
+    for entity in [game.player, game.enemy] {
+        if let Ok(mut ammo) = bodies.get_mut(entity) {
+            *ammo = Ammo::default();
+        }
+    }
+}
+
+// Only a reload that plays to the end refills, an interrupted one is lost.
+fn reload_system(mut bodies: Query<(&Animation, &mut SkillRuntime)>) {
+    for (animation, mut runtime) in &mut bodies {
+        if reloading(Some(animation)) && animation.progress > 1.0 {
+            runtime.reload();
+        }
+    }
+}
+
+fn fired_system(shots: Query<&Projectile, Added<Projectile>>, mut bodies: Query<&mut Ammo>) {
+    for shot in &shots {
+        if let Ok(mut ammo) = bodies.get_mut(shot.source) {
+            ammo.fired += 1;
+        }
+    }
+}
+
+pub struct AmmoPlugin;
+
+impl Plugin for AmmoPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(SystemSet::on_enter(AppState::Battle).with_system(reset_ammo_system))
+            .add_system_set(
+                SystemSet::on_update(AppState::Battle)
+                    .with_system(reload_system.after(animation_system))
+                    .with_system(fired_system),
+            );
+    }
+}
@@ -0,0 +1,287 @@
+use bevy::prelude::*;
+
+use crate::{
+    balance::BalanceConfig,
+    battle::{
+        animation_system, edge_gap, movement_system, Animation, ExternalInput, Walking,
+        WALK_END_TIME,
+    },
+    body::{Facing, Skill, Stats},
+    rate::AnimationRate,
+    turn::Turns,
+    ui::UseSkill,
+    AppState, Game,
+};
+
+// Less than this moved in a frame of walking and the body is up against the
+// other one.
+const STALL: f32 = 1e-4;
+
+// A walk forward that loops until the body is `until` away from its
+// opponent, instead of stopping after one step. Both the player and the AI
+// close distance with it.
+#[derive(Component)]
+pub struct Approach {
+    until: f32,
+    // Where the body was at the end of the last frame the walk played.
+    last_x: Option<f32>,
+}
+
+// Starts an approach for the body, or cancels the one it's on.
+pub struct ApproachEvent(pub Entity);
+
+// Whether `skill` keeps looping instead of ending, see walking_system.
+pub fn loops(approach: Option<&Approach>, skill: &Skill) -> bool {
+    approach.is_some() && matches!(skill, Skill::WalkForward)
+}
+
+// Why an approach can't start, for the log.
+pub fn refusal(
+    balance: &BalanceConfig,
+    stats: &Stats,
+    transform: &Transform,
+    facing: Facing,
+    target_stats: &Stats,
+    target_transform: &Transform,
+    until: f32,
+) -> Option<&'static str> {
+    let towards = Facing::towards(transform.translation.x, target_transform.translation.x);
+    if !stats.skills.contains(&Skill::WalkForward) {
+        Some("can't walk")
+    } else if towards != facing {
+        Some("facing away")
+    } else if edge_gap(transform, stats, target_transform, target_stats) <= until {
+        Some("already in range")
+    } else if balance.approach_energy > 0.0 && stats.energy <= 0.0 {
+        Some("out of energy")
+    } else {
+        None
+    }
+}
+
+// For bodies that don't go through UseSkill, like the AI's.
+pub fn start(commands: &mut Commands, entity: Entity, until: f32) {
+    commands
+        .entity(entity)
+        .insert(Approach {
+            until,
+            last_x: None,
+        })
+        .insert(Animation::new(Skill::WalkForward.id()));
+}
+
+// The same for the player's body under auto-battle, whose walk is queued in
+// UseSkill like a click.
+pub fn queue(commands: &mut Commands, entity: Entity, until: f32) {
+    commands.entity(entity).insert(Approach {
+        until,
+        last_x: None,
+    });
+}
+
+// Lets the walk play out its last stretch, like a step would end.
+fn stop(commands: &mut Commands, entity: Entity, walk: Option<&mut Animation>) {
+    commands.entity(entity).remove::<Approach>();
+    if let Some(walk) = walk {
+        walk.progress = 1.0 - WALK_END_TIME;
+    }
+}
+
+fn request_system(
+    mut commands: Commands,
+    mut events: EventReader<ApproachEvent>,
+    mut use_skill: ResMut<UseSkill>,
+    game: Res<Game>,
+    balance: Res<BalanceConfig>,
+    turns: Option<Res<Turns>>,
+    mut bodies: Query<(
+        &Stats,
+        &Transform,
+        &Facing,
+        Option<&mut Animation>,
+        Option<&mut Walking>,
+        Option<&Approach>,
+    )>,
+    external: Query<(), With<ExternalInput>>,
+) {
+    let walk_id = Skill::WalkForward.id();
+    for ApproachEvent(entity) in events.iter() {
+        let target = game.opponent(*entity);
+        let Ok([body, target]) = bodies.get_many_mut([*entity, target]) else {
+            continue;
+        };
+        let (stats, transform, facing, animation, walking, approach) = body;
+        if approach.is_some() {
+            let walk = animation
+                .map(Mut::into_inner)
+                .filter(|animation| animation.skill == walk_id)
+                .or_else(|| Some(&mut walking?.into_inner().0));
+            stop(&mut commands, *entity, walk);
+            continue;
+        }
+        let (target_stats, target_transform, ..) = target;
+        let Some(until) = stats.reach() else {
+            info!("Nothing to approach with, no melee skills");
+            continue;
+        };
+        // One skill per turn, a walk that never ends would hold the turn. A
+        // networked match only sends skill picks, the other side would never
+        // know where the walk stops.
+        let refused = turns
+            .is_some()
+            .then_some("turn based")
+            .or_else(|| external.contains(*entity).then_some("networked"))
+            .or_else(|| {
+                refusal(
+                    &balance,
+                    stats,
+                    transform,
+                    *facing,
+                    target_stats,
+                    target_transform,
+                    until,
+                )
+            });
+        if let Some(reason) = refused {
+            info!("Not approaching, {}", reason);
+            continue;
+        }
+        commands.entity(*entity).insert(Approach {
+            until,
+            last_x: None,
+        });
+        if *entity == game.player {
+            **use_skill = Some(walk_id.clone());
+        } else {
+            commands
+                .entity(*entity)
+                .insert(Animation::new(walk_id.clone()));
+        }
+    }
+}
+
+// Pays for the distance walked this frame and ends the approach once the body
+// is in range, stuck against the other one or out of energy. Cancelled from
+// outside, the approach is dropped once its walk isn't playing or waiting to.
+fn approach_system(
+    mut commands: Commands,
+    use_skill: Res<UseSkill>,
+    game: Res<Game>,
+    balance: Res<BalanceConfig>,
+    mut bodies: Query<(
+        Entity,
+        &mut Approach,
+        &Facing,
+        &AnimationRate,
+        Option<&mut Animation>,
+        Option<&mut Walking>,
+    )>,
+    mut stats: Query<&mut Stats>,
+    transforms: Query<&Transform>,
+) {
+    let walk_id = Skill::WalkForward.id();
+    for (entity, mut approach, facing, rate, animation, walking) in &mut bodies {
+        let walk = animation
+            .map(Mut::into_inner)
+            .filter(|animation| animation.skill == walk_id)
+            .or_else(|| Some(&mut walking?.into_inner().0));
+        let Some(walk) = walk else {
+            let pending = entity == game.player && use_skill.picked(&walk_id);
+            if !pending {
+                commands.entity(entity).remove::<Approach>();
+            }
+            approach.last_x = None;
+            continue;
+        };
+        let target = game.opponent(entity);
+        let (Ok([mut stats, target_stats]), Ok([transform, target_transform])) = (
+            stats.get_many_mut([entity, target]),
+            transforms.get_many([entity, target]),
+        ) else {
+            stop(&mut commands, entity, Some(walk));
+            continue;
+        };
+        let x = transform.translation.x;
+        let moved = approach.last_x.map(|last| (x - last).abs());
+        approach.last_x = Some(x);
+        if let Some(moved) = moved {
+            stats.energy = (stats.energy - moved * balance.approach_energy).max(0.0);
+        }
+        let stalled = moved.is_some_and(|moved| moved < STALL && rate.dt() > 0.0);
+        let done = Facing::towards(x, target_transform.translation.x) != *facing
+            || edge_gap(transform, &stats, target_transform, &target_stats) <= approach.until;
+        let tired = balance.approach_energy > 0.0 && stats.energy <= 0.0;
+        if done || stalled || tired {
+            stop(&mut commands, entity, Some(walk));
+        }
+    }
+}
+
+pub struct ApproachPlugin;
+
+impl Plugin for ApproachPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ApproachEvent>().add_system_set(
+            SystemSet::on_update(AppState::Battle)
+                .with_system(request_system)
+                .with_system(
+                    approach_system
+                        .after(movement_system)
+                        .before(animation_system),
+                ),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::body::{compute_stats, Body};
+
+    #[test]
+    fn approaches_refuse_when_already_in_range() {
+        let balance = BalanceConfig::default();
+        let stats = compute_stats(&Body::default());
+        let until = stats.reach().unwrap();
+        let at = |x: f32| Transform::from_xyz(x, 0.0, 0.0);
+        let far = refusal(
+            &balance,
+            &stats,
+            &at(-4.0),
+            Facing::Right,
+            &stats,
+            &at(4.0),
+            until,
+        );
+        assert_eq!(far, None);
+        let close = stats.width + until / 2.0;
+        let near = refusal(
+            &balance,
+            &stats,
+            &at(0.0),
+            Facing::Right,
+            &stats,
+            &at(close),
+            until,
+        );
+        assert_eq!(near, Some("already in range"));
+        let away = refusal(
+            &balance,
+            &stats,
+            &at(-4.0),
+            Facing::Left,
+            &stats,
+            &at(4.0),
+            until,
+        );
+        assert_eq!(away, Some("facing away"));
+        assert!(loops(
+            Some(&Approach {
+                until,
+                last_x: None
+            }),
+            &Skill::WalkForward
+        ));
+        assert!(!loops(None, &Skill::WalkForward));
+    }
+}
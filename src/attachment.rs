@@ -0,0 +1,273 @@
+use std::collections::BTreeMap;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    body::{Ability, Body, Skill, Stats},
+    locale::t,
+};
+
+const KNUCKLE_DAMAGE: f32 = 1.2;
+const KNUCKLE_WEIGHT: f32 = 15.0;
+// The rod makes the arm this much longer, so its tip reaches further.
+pub const ROD_LENGTH: f32 = 0.25;
+const ROD_TIME: f32 = 1.15;
+const CAPACITOR_ENERGY: f32 = 0.7;
+// There's no heat yet, the capacitor runs the arm hot as a longer cooldown
+// instead.
+const CAPACITOR_HEAT: f32 = 0.2;
+const STABILIZER_ACCURACY: f32 = 0.15;
+
+// Mods bought with scrap and socketed onto an arm, one per arm. They change
+// that arm's abilities, see `ArmMeta`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Attachment {
+    SpikedKnuckle,
+    ExtensionRod,
+    Capacitor,
+    Stabilizer,
+}
+
+impl Attachment {
+    pub const ALL: [Attachment; 4] = [
+        Attachment::SpikedKnuckle,
+        Attachment::ExtensionRod,
+        Attachment::Capacitor,
+        Attachment::Stabilizer,
+    ];
+
+    pub fn id(self) -> &'static str {
+        match self {
+            Attachment::SpikedKnuckle => "spiked_knuckle",
+            Attachment::ExtensionRod => "extension_rod",
+            Attachment::Capacitor => "capacitor",
+            Attachment::Stabilizer => "stabilizer",
+        }
+    }
+
+    pub fn name(self) -> String {
+        t!(format!("attachment.{}.name", self.id()))
+    }
+
+    // The line an ability's tooltip shows for it.
+    pub fn describe(self) -> String {
+        let key = format!("attachment.{}.effect", self.id());
+        match self {
+            Attachment::SpikedKnuckle => t!(
+                key,
+                damage = (KNUCKLE_DAMAGE - 1.0) * 100.0,
+                weight = KNUCKLE_WEIGHT
+            ),
+            Attachment::ExtensionRod => t!(
+                key,
+                reach = ROD_LENGTH * 100.0,
+                time = (ROD_TIME - 1.0) * 100.0
+            ),
+            Attachment::Capacitor => t!(
+                key,
+                energy = (1.0 - CAPACITOR_ENERGY) * 100.0,
+                cooldown = CAPACITOR_HEAT
+            ),
+            Attachment::Stabilizer => t!(key, accuracy = STABILIZER_ACCURACY * 100.0),
+        }
+    }
+
+    // Scrap one costs.
+    pub fn price(self) -> u32 {
+        match self {
+            Attachment::SpikedKnuckle => 60,
+            Attachment::ExtensionRod => 50,
+            Attachment::Capacitor => 70,
+            Attachment::Stabilizer => 40,
+        }
+    }
+
+    // Drawn as a small block at the tip of the arm.
+    pub fn color(self) -> Color {
+        match self {
+            Attachment::SpikedKnuckle => Color::rgb(0.75, 0.75, 0.8),
+            Attachment::ExtensionRod => Color::rgb(0.35, 0.35, 0.4),
+            Attachment::Capacitor => Color::rgb(0.3, 0.6, 1.0),
+            Attachment::Stabilizer => Color::rgb(0.95, 0.8, 0.2),
+        }
+    }
+
+    // How much longer the arm is drawn, and reaches, with it on.
+    pub fn length(self) -> f32 {
+        match self {
+            Attachment::ExtensionRod => ROD_LENGTH,
+            _ => 0.0,
+        }
+    }
+
+    pub fn adjust(self, ability: &mut Ability<f32>) {
+        match self {
+            Attachment::SpikedKnuckle => ability.meta *= KNUCKLE_DAMAGE,
+            Attachment::ExtensionRod => ability.time *= ROD_TIME,
+            Attachment::Capacitor => {
+                ability.energy_cost *= CAPACITOR_ENERGY;
+                ability.cooldown += CAPACITOR_HEAT;
+            }
+            Attachment::Stabilizer => {}
+        }
+    }
+
+    pub fn add_to_stats(self, stats: &mut Stats) {
+        match self {
+            Attachment::SpikedKnuckle => stats.weight += KNUCKLE_WEIGHT,
+            Attachment::Stabilizer => {
+                stats.close_accuracy += STABILIZER_ACCURACY;
+                stats.far_accuracy += STABILIZER_ACCURACY;
+            }
+            Attachment::ExtensionRod | Attachment::Capacitor => {}
+        }
+    }
+}
+
+// What an arm has on top of its `PartStats`.
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ArmMeta {
+    attachment: Option<Attachment>,
+}
+
+impl ArmMeta {
+    pub fn attachment(&self) -> Option<Attachment> {
+        self.attachment
+    }
+}
+
+impl crate::body::BodyPartMeta for ArmMeta {
+    fn add_to_stats(&self, stats: &mut Stats) {
+        if let Some(attachment) = self.attachment {
+            attachment.add_to_stats(stats);
+        }
+    }
+
+    fn adjust_skills(&self, skills: &mut [Skill]) {
+        let Some(attachment) = self.attachment else {
+            return;
+        };
+        for ability in skills.iter_mut().filter_map(Skill::ability_mut) {
+            attachment.adjust(ability);
+        }
+    }
+}
+
+impl Body {
+    // Puts `attachment` on the arm in `slot`, what was on it before. Nothing
+    // happens without an arm there.
+    pub fn attach(&mut self, slot: u8, attachment: Option<Attachment>) -> Option<Attachment> {
+        let arm = self.arms_mut().iter_mut().find(|arm| arm.slot() == slot)?;
+        std::mem::replace(&mut arm.meta_mut().attachment, attachment)
+    }
+
+    pub fn attachments(&self) -> impl Iterator<Item = Attachment> + '_ {
+        self.arms().iter().filter_map(|arm| arm.meta().attachment())
+    }
+
+    // Takes off what isn't covered by `owned`, for builds from outside the
+    // profile.
+    pub fn fit_attachments(&mut self, owned: &BTreeMap<Attachment, u32>) {
+        let mut left = owned.clone();
+        for arm in self.arms_mut() {
+            let meta = arm.meta_mut();
+            if let Some(attachment) = meta.attachment {
+                match left.get_mut(&attachment) {
+                    Some(count) if *count > 0 => *count -= 1,
+                    _ => meta.attachment = None,
+                }
+            }
+        }
+    }
+}
+
+// What's in the inventory, every one of `attachment` bought that isn't on
+// one of the body's arms. An arm coming off puts its attachment back.
+pub fn free(owned: &BTreeMap<Attachment, u32>, body: &Body, attachment: Attachment) -> u32 {
+    let on = body.attachments().filter(|on| *on == attachment).count() as u32;
+    owned
+        .get(&attachment)
+        .copied()
+        .unwrap_or(0)
+        .saturating_sub(on)
+}
+
+// The one after what's on the arm in `slot` that's free to put on, none at
+// the end.
+pub fn next(owned: &BTreeMap<Attachment, u32>, body: &Body, slot: u8) -> Option<Attachment> {
+    let current = body.arm(slot)?.meta().attachment();
+    let after = current.map_or(0, |current| {
+        Attachment::ALL.iter().position(|a| *a == current).unwrap() + 1
+    });
+    Attachment::ALL[after..]
+        .iter()
+        .copied()
+        .find(|attachment| free(owned, body, *attachment) > 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::body::{compute_stats, Limb, PartKind};
+    use rand::{rngs::StdRng, SeedableRng};
+
+    fn arm_abilities(stats: &Stats, slot: u8) -> Vec<Ability<f32>> {
+        stats
+            .skills
+            .iter()
+            .filter_map(Skill::ability)
+            .filter(|ability| ability.limb == Limb::Arm(slot))
+            .cloned()
+            .collect()
+    }
+
+    #[test]
+    fn attachments_change_their_arm_only_and_come_back_off() {
+        let mut body = Body::default();
+        let before = compute_stats(&body);
+        assert_eq!(body.attach(0, Some(Attachment::Capacitor)), None);
+        assert_eq!(body.attach(7, Some(Attachment::Stabilizer)), None);
+        let after = compute_stats(&body);
+        for (old, new) in arm_abilities(&before, 0)
+            .iter()
+            .zip(arm_abilities(&after, 0))
+        {
+            assert!((new.energy_cost - old.energy_cost * CAPACITOR_ENERGY).abs() < 1e-4);
+            assert!((new.cooldown - old.cooldown - CAPACITOR_HEAT).abs() < 1e-4);
+        }
+        assert_eq!(arm_abilities(&before, 1), arm_abilities(&after, 1));
+
+        // Saved with the body, in profiles and share codes.
+        let ron = ron::to_string(&body).unwrap();
+        let loaded = ron::from_str::<Body>(&ron).unwrap();
+        let shared = Body::from_share_code(&body.to_share_code()).unwrap();
+        for body in [&loaded, &shared] {
+            assert_eq!(
+                body.arm(0).unwrap().meta().attachment(),
+                Some(Attachment::Capacitor)
+            );
+        }
+
+        // Two bought, one on the arm. Losing the arm frees it, one rolled in
+        // its place comes bare.
+        let owned = BTreeMap::from([(Attachment::Capacitor, 2)]);
+        assert_eq!(free(&owned, &body, Attachment::Capacitor), 1);
+        assert_eq!(next(&owned, &body, 0), None);
+        assert_eq!(next(&owned, &body, 1), Some(Attachment::Capacitor));
+        body.destroy(Limb::Arm(0));
+        body.reroll_part(PartKind::Arm, &mut StdRng::seed_from_u64(3));
+        assert_eq!(body.attachments().count(), 0);
+        assert_eq!(free(&owned, &body, Attachment::Capacitor), 2);
+
+        let mut pasted = Body::default();
+        pasted.attach(0, Some(Attachment::SpikedKnuckle));
+        pasted.attach(1, Some(Attachment::Capacitor));
+        pasted.fit_attachments(&owned);
+        assert_eq!(
+            pasted.attachments().collect::<Vec<_>>(),
+            [Attachment::Capacitor]
+        );
+    }
+}
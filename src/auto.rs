@@ -0,0 +1,242 @@
+use bevy::{prelude::*, ui::FocusPolicy};
+use rand::{rngs::StdRng, SeedableRng};
+
+use crate::{
+    ai::{AiController, AiRegistry},
+    body::SkillId,
+    locale::t,
+    theme::Theme,
+    turn::Ruleset,
+    ui::{Fonts, Scaled, UseSkill},
+    AppState, Game, GameRng,
+};
+
+// The player's body while an AI plays it. Its picks are queued in UseSkill
+// like clicks, so the bar shows them and taking control back keeps the one
+// still waiting.
+#[derive(Component)]
+pub struct Autopilot {
+    // Apart from the game's, the enemy rolls the same whoever plays the
+    // player.
+    rng: StdRng,
+    picked: Option<SkillId>,
+}
+
+impl Autopilot {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            rng: StdRng::seed_from_u64(seed),
+            picked: None,
+        }
+    }
+
+    pub fn rng(&mut self) -> &mut StdRng {
+        &mut self.rng
+    }
+
+    pub fn queue(&mut self, use_skill: &mut UseSkill, skill: SkillId) {
+        **use_skill = Some(skill.clone());
+        self.picked = Some(skill);
+    }
+
+    // Whether what's in UseSkill is the AI's pick, a click takes its place.
+    pub fn queued(&self, use_skill: &UseSkill) -> bool {
+        self.picked
+            .as_ref()
+            .is_some_and(|picked| use_skill.picked(picked))
+    }
+}
+
+pub fn hand_over(commands: &mut Commands, entity: Entity, controller: AiController, seed: u64) {
+    commands
+        .entity(entity)
+        .insert(controller)
+        .insert(Autopilot::new(seed));
+}
+
+pub fn take_back(commands: &mut Commands, entity: Entity) {
+    commands
+        .entity(entity)
+        .remove::<AiController>()
+        .remove::<Autopilot>();
+}
+
+// What the HUD's toggle is set to, kept from one battle to the next.
+pub struct AutoBattle {
+    pub on: bool,
+    // Name the controller is registered under, see AiRegistry.
+    pub ai: String,
+}
+
+impl Default for AutoBattle {
+    fn default() -> Self {
+        Self {
+            on: false,
+            ai: "aggressive".to_string(),
+        }
+    }
+}
+
+#[derive(Component, Clone, Copy, PartialEq, Eq)]
+enum AutoButton {
+    Toggle,
+    Profile,
+}
+
+fn spawn_auto_hud_system(mut commands: Commands, fonts: Res<Fonts>, theme: Res<Theme>) {
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    top: Val::Px(10.0),
+                    right: Val::Px(10.0),
+                    ..default()
+                },
+                ..default()
+            },
+            color: Color::NONE.into(),
+            focus_policy: FocusPolicy::Pass,
+            ..default()
+        })
+        .with_children(|parent| {
+            for (button, width) in [(AutoButton::Toggle, 80.0), (AutoButton::Profile, 110.0)] {
+                parent
+                    .spawn_bundle(ButtonBundle {
+                        style: Style {
+                            size: Size::new(Val::Px(width), Val::Px(30.0)),
+                            margin: UiRect::all(Val::Px(2.0)),
+                            justify_content: JustifyContent::Center,
+                            align_items: AlignItems::Center,
+                            ..default()
+                        },
+                        color: theme.normal_button.into(),
+                        visibility: Visibility { is_visible: false },
+                        ..default()
+                    })
+                    .insert_bundle((button, Scaled::size(width, 30.0)))
+                    .with_children(|parent| {
+                        parent
+                            .spawn_bundle(TextBundle::from_section(
+                                "",
+                                TextStyle {
+                                    font: fonts.bold(),
+                                    font_size: 18.0,
+                                    color: theme.button_text,
+                                },
+                            ))
+                            .insert(Scaled::font(18.0));
+                    });
+            }
+        });
+}
+
+// Q flips it too. Only shown in battles whose rules allow it.
+fn auto_button_system(
+    theme: Res<Theme>,
+    keys: Res<Input<KeyCode>>,
+    state: Res<State<AppState>>,
+    ruleset: Res<Ruleset>,
+    registry: Res<AiRegistry>,
+    mut auto: ResMut<AutoBattle>,
+    mut buttons: Query<(
+        &AutoButton,
+        &Interaction,
+        &mut UiColor,
+        &mut Visibility,
+        &Children,
+    )>,
+    mut texts: Query<&mut Text>,
+) {
+    let shown = *state.current() == AppState::Battle && ruleset.auto_battle;
+    if shown && keys.just_pressed(KeyCode::Q) {
+        auto.on = !auto.on;
+    }
+    for (button, interaction, mut color, mut visibility, children) in &mut buttons {
+        visibility.is_visible = shown;
+        if !shown {
+            continue;
+        }
+        match *interaction {
+            Interaction::Clicked => {
+                if color.0 != theme.pressed_button {
+                    match button {
+                        AutoButton::Toggle => auto.on = !auto.on,
+                        AutoButton::Profile => {
+                            let names = registry.names().collect::<Vec<_>>();
+                            let next = names
+                                .iter()
+                                .position(|name| *name == auto.ai)
+                                .map_or(0, |i| (i + 1) % names.len());
+                            auto.ai = names[next].to_string();
+                        }
+                    }
+                }
+                color.0 = theme.pressed_button;
+            }
+            Interaction::Hovered => color.0 = theme.hovered_button,
+            Interaction::None if *button == AutoButton::Toggle && auto.on => {
+                color.0 = theme.auto_button
+            }
+            Interaction::None => color.0 = theme.normal_button,
+        }
+        let label = match button {
+            AutoButton::Toggle if auto.on => t!("auto.on"),
+            AutoButton::Toggle => t!("auto.off"),
+            AutoButton::Profile => auto.ai.clone(),
+        };
+        for child in children {
+            if let Ok(mut text) = texts.get_mut(*child) {
+                if text.sections[0].value != label {
+                    text.sections[0].value = label.clone();
+                }
+            }
+        }
+    }
+}
+
+// Gives the player's body to the picked AI or takes it back to match the
+// toggle, from the next frame on.
+fn apply_auto_system(
+    mut commands: Commands,
+    auto: Res<AutoBattle>,
+    ruleset: Res<Ruleset>,
+    registry: Res<AiRegistry>,
+    game: Res<Game>,
+    rng: Res<GameRng>,
+    players: Query<Option<&AiController>, With<Autopilot>>,
+) {
+    let wanted = (auto.on && ruleset.auto_battle).then_some(auto.ai.as_str());
+    let current = players
+        .get(game.player)
+        .ok()
+        .map(|controller| controller.map_or("", AiController::name));
+    if wanted == current {
+        return;
+    }
+    match wanted.and_then(|ai| registry.create(ai)) {
+        Some(controller) => hand_over(&mut commands, game.player, controller, rng.seed()),
+        None if current.is_some() => take_back(&mut commands, game.player),
+        None => {}
+    }
+}
+
+// Between battles the player's body is theirs again.
+fn leave_auto_system(mut commands: Commands, game: Res<Game>, players: Query<(), With<Autopilot>>) {
+    if players.contains(game.player) {
+        take_back(&mut commands, game.player);
+    }
+}
+
+// Only the windowed game has the toggle, the sim's bodies are all AI anyway.
+pub struct AutoPlugin;
+
+impl Plugin for AutoPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<AutoBattle>()
+            .add_startup_system(spawn_auto_hud_system)
+            .add_system_to_stage(CoreStage::PostUpdate, auto_button_system)
+            .add_system_set(SystemSet::on_update(AppState::Battle).with_system(apply_auto_system))
+            .add_system_set(SystemSet::on_exit(AppState::Battle).with_system(leave_auto_system));
+    }
+}
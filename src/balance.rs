@@ -0,0 +1,278 @@
+use bevy::{
+    asset::{AssetLoader, BoxedFuture, LoadContext, LoadedAsset},
+    prelude::*,
+    reflect::TypeUuid,
+};
+use serde::Deserialize;
+
+use crate::body::{Skill, Stats};
+
+// Loaders are picked by the extension after the first dot, hence the double
+// extension like the boss files.
+pub const BALANCE_PATH: &str = "combat.balance.ron";
+
+// Every number the combat formulas are tuned with. The defaults match
+// assets/combat.balance.ron, which the windowed game loads and reloads on change.
+#[derive(Clone, Debug, PartialEq, Deserialize, TypeUuid)]
+#[uuid = "3f9a6b0e-58c4-4d0b-a1f3-6e2d7c9b4a15"]
+#[serde(default)]
+pub struct BalanceConfig {
+    // Animation progress per second, 1 means every skill takes a second.
+    pub animation_speed: f32,
+    // Length of the turn played before a skill when auto-face is on.
+    pub quick_turn: f32,
+    // Closest two bodies' edges get to each other.
+    pub body_gap: f32,
+    pub damage_multiplier: f32,
+    pub walk_speed_multiplier: f32,
+    // Share of that speed each walk goes at, backwards is slower.
+    pub walk_forward: f32,
+    pub walk_backward: f32,
+    // Energy a looping approach costs per unit walked.
+    pub approach_energy: f32,
+    pub recovery_time: f32,
+    // Heavier bodies take more damage before their poise breaks.
+    pub poise_per_weight: f32,
+    pub poise_regen: f32,
+    pub sudden_death_interval: f32,
+    pub sudden_death_damage: f32,
+    // Share of its maximum energy a body regains per second.
+    pub energy_regen: f32,
+    // Seconds without a hit before an overshield starts coming back, and the
+    // shield it regains per second for each point of energy its parts draw.
+    pub overshield_delay: f32,
+    pub overshield_recharge: f32,
+    // Sideways speed of a ranged shot, the time it takes to arrive is what a
+    // body gets to dodge it.
+    pub projectile_speed: f32,
+    pub projectile_gravity: f32,
+    // Chance an item drops in the arena every ten seconds, see pickup.rs.
+    pub pickup_chance: f32,
+}
+
+impl Default for BalanceConfig {
+    fn default() -> Self {
+        Self {
+            animation_speed: 1.0,
+            quick_turn: 0.3,
+            body_gap: 0.1,
+            damage_multiplier: 1.0,
+            walk_speed_multiplier: 1.0,
+            walk_forward: 1.0,
+            walk_backward: 0.5,
+            approach_energy: 0.5,
+            recovery_time: 0.4,
+            poise_per_weight: 0.15,
+            poise_regen: 5.0,
+            sudden_death_interval: 1.0,
+            sudden_death_damage: 2.0,
+            energy_regen: 0.1,
+            overshield_delay: 4.0,
+            overshield_recharge: 0.5,
+            projectile_speed: 8.0,
+            projectile_gravity: 10.0,
+            pickup_chance: 0.35,
+        }
+    }
+}
+
+impl BalanceConfig {
+    // Lists every value outside what the formulas can cope with.
+    pub fn validate(&self) -> Result<(), String> {
+        let mut problems = Vec::new();
+        let mut check = |name: &str, value: f32, min: f32, max: f32| {
+            if !(min..=max).contains(&value) {
+                problems.push(format!(
+                    "{} is {}, expected {} to {}",
+                    name, value, min, max
+                ));
+            }
+        };
+        // Speeds and intervals are divided by, they can't be zero.
+        check("animation_speed", self.animation_speed, 0.05, 20.0);
+        check("projectile_speed", self.projectile_speed, 0.5, 100.0);
+        check(
+            "sudden_death_interval",
+            self.sudden_death_interval,
+            0.05,
+            60.0,
+        );
+        check("quick_turn", self.quick_turn, 0.0, 5.0);
+        check("body_gap", self.body_gap, 0.0, 5.0);
+        check("damage_multiplier", self.damage_multiplier, 0.0, 10.0);
+        check(
+            "walk_speed_multiplier",
+            self.walk_speed_multiplier,
+            0.0,
+            10.0,
+        );
+        check("walk_forward", self.walk_forward, 0.0, 10.0);
+        check("walk_backward", self.walk_backward, 0.0, 10.0);
+        check("approach_energy", self.approach_energy, 0.0, 100.0);
+        check("recovery_time", self.recovery_time, 0.0, 10.0);
+        check("poise_per_weight", self.poise_per_weight, 0.0, 10.0);
+        check("poise_regen", self.poise_regen, 0.0, 1000.0);
+        check("sudden_death_damage", self.sudden_death_damage, 0.0, 1000.0);
+        check("energy_regen", self.energy_regen, 0.0, 10.0);
+        check("overshield_delay", self.overshield_delay, 0.0, 60.0);
+        check("overshield_recharge", self.overshield_recharge, 0.0, 100.0);
+        check("projectile_gravity", self.projectile_gravity, 0.0, 100.0);
+        check("pickup_chance", self.pickup_chance, 0.0, 1.0);
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(problems.join("\n"))
+        }
+    }
+
+    // Fraction of the body's speed a walk goes at, signed along its facing.
+    pub fn walk_multiplier(&self, skill: &Skill) -> Option<f32> {
+        match skill {
+            Skill::WalkForward => Some(self.walk_forward),
+            Skill::WalkBackward => Some(-self.walk_backward),
+            Skill::Dash(ability) => Some(ability.meta),
+            _ => None,
+        }
+    }
+
+    // Seconds a skill's animation takes at the normal speed, the walks and
+    // the turn-around included.
+    pub fn skill_time(&self) -> f32 {
+        1.0 / self.animation_speed
+    }
+
+    // How far one whole walk moves the body if nothing is in its way, signed
+    // like the multiplier.
+    pub fn walk_distance(&self, stats: &Stats, skill: &Skill) -> Option<f32> {
+        let speed = stats.speed * self.walk_speed_multiplier;
+        Some(speed * self.walk_multiplier(skill)? * self.skill_time())
+    }
+
+    pub fn parse(contents: &str) -> Result<Self, String> {
+        let balance = ron::from_str::<Self>(contents).map_err(|err| err.to_string())?;
+        balance.validate()?;
+        Ok(balance)
+    }
+}
+
+#[derive(Default)]
+struct BalanceLoader;
+
+impl AssetLoader for BalanceLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let balance = ron::de::from_bytes::<BalanceConfig>(bytes)?;
+            load_context.set_default_asset(LoadedAsset::new(balance));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["balance.ron"]
+    }
+}
+
+struct BalanceHandle(Handle<BalanceConfig>);
+
+fn load_balance_system(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(BalanceHandle(asset_server.load(BALANCE_PATH)));
+}
+
+#[cfg(debug_assertions)]
+fn report(console: &mut Option<ResMut<crate::console::Console>>, text: &str) {
+    if let Some(console) = console {
+        console.print(text);
+    }
+}
+
+// Takes the file on every load and save. A file that doesn't validate keeps
+// the last good numbers.
+fn reload_balance_system(
+    handle: Res<BalanceHandle>,
+    assets: Res<Assets<BalanceConfig>>,
+    mut events: EventReader<AssetEvent<BalanceConfig>>,
+    mut balance: ResMut<BalanceConfig>,
+    #[cfg(debug_assertions)] mut console: Option<ResMut<crate::console::Console>>,
+) {
+    for event in events.iter() {
+        let (AssetEvent::Created { handle: changed } | AssetEvent::Modified { handle: changed }) =
+            event
+        else {
+            continue;
+        };
+        let Some(loaded) = assets.get(changed).filter(|_| *changed == handle.0) else {
+            continue;
+        };
+        match loaded.validate() {
+            Ok(()) => {
+                if *balance != *loaded {
+                    *balance = loaded.clone();
+                    info!("Balance reloaded");
+                    #[cfg(debug_assertions)]
+                    report(&mut console, "balance reloaded");
+                }
+            }
+            Err(err) => {
+                error!("Invalid {}: {}", BALANCE_PATH, err);
+                #[cfg(debug_assertions)]
+                report(&mut console, &format!("invalid {}:\n{}", BALANCE_PATH, err));
+            }
+        }
+    }
+}
+
+// Loads and hot reloads assets/combat.balance.ron, BattlePlugin alone runs on the
+// defaults or whatever the sim inserted.
+pub struct BalancePlugin;
+
+impl Plugin for BalancePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_asset::<BalanceConfig>()
+            .init_asset_loader::<BalanceLoader>()
+            .add_startup_system(load_balance_system)
+            .add_system(reload_balance_system);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::body::{compute_stats, Body};
+
+    #[test]
+    fn balance_asset_matches_the_defaults() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/assets/combat.balance.ron");
+        let contents = std::fs::read_to_string(path).unwrap();
+        assert_eq!(
+            BalanceConfig::parse(&contents),
+            Ok(BalanceConfig::default())
+        );
+    }
+
+    #[test]
+    fn nonsense_numbers_are_rejected() {
+        let err = BalanceConfig::parse("(animation_speed: 0.0, body_gap: -1.0)").unwrap_err();
+        assert!(err.contains("animation_speed"));
+        assert!(err.contains("body_gap"));
+        assert!(BalanceConfig::parse("(body_gap: 1.5)").is_ok());
+    }
+
+    #[test]
+    fn walks_go_their_share_of_the_speed() {
+        let balance = BalanceConfig {
+            animation_speed: 2.0,
+            walk_backward: 0.25,
+            ..default()
+        };
+        let stats = compute_stats(&Body::default());
+        let forward = balance.walk_distance(&stats, &Skill::WalkForward).unwrap();
+        assert!((forward - stats.speed / 2.0).abs() < 1e-5);
+        let backward = balance.walk_distance(&stats, &Skill::WalkBackward).unwrap();
+        assert!((backward + forward * 0.25).abs() < 1e-5);
+        assert_eq!(balance.walk_distance(&stats, &Skill::TurnAround), None);
+    }
+}
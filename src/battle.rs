@@ -0,0 +1,1255 @@
+use std::f32::consts::{FRAC_PI_2, PI};
+
+use bevy::{ecs::entity::Entities, prelude::*, sprite::Anchor};
+use smallmap::Map;
+
+use crate::{
+    ai::{self, AiController},
+    ammo::{self, Ammo, RELOAD_TIME},
+    approach::{self, Approach},
+    balance::BalanceConfig,
+    body::{
+        self, random_body, Ability, Body, BodyStats, Facing, Limb, Skill, SkillId, SpawnBody, Stats,
+    },
+    booster::{self, Boosters},
+    churn, effects, energy,
+    hitbox::BodyGeometry,
+    interrupt::{self, Recovery},
+    knowledge::{self, Knowledge},
+    layer::RenderLayer,
+    limb_swap::{self, Sidearm, SWAP_LIMB_TIME},
+    locks::{self, LimbLocks, Track},
+    mutators, periodic, pickup, projectile,
+    rate::{self, AnimationRate},
+    runtime::{self, SkillRuntime},
+    shield::{self, ShieldTimer},
+    survival::SurvivalRun,
+    swap,
+    timer::{self, BattleTimer},
+    turn::{self, Ruleset, Turns},
+    ui::UseSkill,
+    weather, AppState, BattleSummary, DamageEvent, DamageSource, Game, GameRng, Impact,
+    Invulnerable,
+};
+
+pub struct BodyTransforms<'a, 'world, 'state, 'inner> {
+    transforms: &'a mut Query<'world, 'state, &'inner mut Transform>,
+    legs: Map<u8, Entity>,
+    arms: Map<u8, Entity>,
+}
+
+impl<'a, 'world, 'state, 'inner> BodyTransforms<'a, 'world, 'state, 'inner> {
+    fn get_entity(&self, limb: Limb) -> Option<Entity> {
+        match &limb {
+            Limb::Arm(i) => self.arms.get(i).copied(),
+            Limb::Leg(i) => self.legs.get(i).copied(),
+        }
+    }
+
+    pub fn get(&self, limb: Limb) -> Option<&Transform> {
+        self.transforms.get(self.get_entity(limb)?).ok()
+    }
+
+    pub fn get_mut(&mut self, limb: Limb) -> Option<Mut<'_, Transform>> {
+        self.transforms.get_mut(self.get_entity(limb)?).ok()
+    }
+
+    pub fn for_legs(&mut self, mut f: impl FnMut(u8, &mut Transform)) {
+        for (i, e) in self.legs.iter() {
+            if let Ok(mut transform) = self.transforms.get_mut(*e) {
+                f(*i, &mut transform);
+            }
+        }
+    }
+
+    pub fn for_arms(&mut self, mut f: impl FnMut(u8, &mut Transform)) {
+        for (i, e) in self.arms.iter() {
+            if let Ok(mut transform) = self.transforms.get_mut(*e) {
+                f(*i, &mut transform);
+            }
+        }
+    }
+}
+
+// A body whose skills come from somewhere other than this machine's player or
+// AI, like the other side of a networked match.
+#[cfg_attr(not(feature = "net"), allow(dead_code))]
+#[derive(Component)]
+pub struct ExternalInput;
+
+#[derive(Component)]
+pub struct Animation {
+    // The `Skill::id` being played, looked up in the body's current skills
+    // each frame so a rebuilt body can't play the wrong one.
+    pub skill: SkillId,
+    pub progress: f32,
+    // Time left on the quick turn played before the skill itself starts.
+    pub lead_in: f32,
+}
+
+impl Animation {
+    pub fn new(skill: SkillId) -> Self {
+        Self {
+            skill,
+            progress: 0.0,
+            lead_in: 0.0,
+        }
+    }
+
+    // Horizontal scale of the body while it turns, zero where facing flips.
+    pub fn turn_scale(&self, skill: &Skill, balance: &BalanceConfig) -> f32 {
+        if self.lead_in > 0.0 {
+            (2.0 * self.lead_in / balance.quick_turn - 1.0).abs()
+        } else if matches!(skill, Skill::TurnAround) {
+            (1.0 - 2.0 * self.progress).abs().min(1.0)
+        } else {
+            1.0
+        }
+    }
+}
+
+// A walk playing on the legs under the action in `Animation`, so a body can
+// jab on the move. Movement is free and never interrupting, so nothing but
+// the battle systems needs to know about it.
+#[derive(Component)]
+pub struct Walking(pub Animation);
+
+// Sorts a body's limb sprites into arms and legs by index.
+pub fn limb_entities(
+    limbs: impl IntoIterator<Item = (Entity, Limb)>,
+) -> (Map<u8, Entity>, Map<u8, Entity>) {
+    let (mut arms, mut legs) = (Map::new(), Map::new());
+    for (entity, limb) in limbs {
+        match limb {
+            Limb::Arm(i) => arms.insert(i, entity),
+            Limb::Leg(i) => legs.insert(i, entity),
+        };
+    }
+    (arms, legs)
+}
+
+pub const WALK_END_TIME: f32 = 0.1;
+// Seconds a body's limbs hold their swing after a walk, a walk started
+// within it carries on from there instead of restarting the gait.
+const GAIT_GRACE: f32 = 0.1;
+// Seconds the limbs then take to blend back to rest.
+const GAIT_BLEND: f32 = 0.1;
+
+// Where a body is in its gait, carried over from one walk to the next so
+// chained steps swing on without a hitch between them. Only the limbs go by
+// it, how far a walk moves the body doesn't.
+#[derive(Component, Default)]
+pub struct GaitPhase {
+    // What `swing_angle` takes, it grows with the distance walked.
+    phase: f32,
+    // Seconds since a walk last played.
+    idle: f32,
+}
+
+impl GaitPhase {
+    #[cfg(test)]
+    pub fn phase(&self) -> f32 {
+        self.phase
+    }
+}
+
+// How far a walk moves the body in `dt`.
+pub fn walk_step(dt: f32, speed: f32, direction: f32, mul: f32) -> f32 {
+    dt * speed * direction * mul
+}
+
+// How far into the gait `progress` more of a walk takes the limbs.
+pub fn gait_advance(progress: f32, speed: f32, mul: f32) -> f32 {
+    speed * progress * mul
+}
+
+// A limb's rotation at `phase` into the gait. Neighbouring legs swing against
+// each other and arms against the legs, gait_rest_system brings them back to
+// rest once the body stops walking.
+pub fn walk_swing(limb: Limb, phase: f32) -> Quat {
+    Quat::from_rotation_z(swing_angle(limb, phase))
+}
+
+fn swing_angle(limb: Limb, phase: f32) -> f32 {
+    let sign = match limb {
+        Limb::Leg(i) => (i % 2) as f32 * 2.0 - 1.0,
+        Limb::Arm(i) => -((i % 2) as f32 * 2.0 - 1.0),
+    };
+    (sign * phase).sin()
+}
+
+// A foot hit the ground, sent once per stride for footstep sounds and dust.
+pub struct StrideEvent {
+    pub entity: Entity,
+    pub leg_index: u8,
+    pub position: Vec2,
+}
+
+// The legs whose foot lands while the gait goes from `from` to `to`. A foot
+// lands where its swing goes from above zero to zero or below, the swing
+// can't be above zero again on the next frame, so a stride only fires once.
+pub fn strides(legs: impl IntoIterator<Item = u8>, from: f32, to: f32) -> impl Iterator<Item = u8> {
+    legs.into_iter().filter(move |i| {
+        swing_angle(Limb::Leg(*i), from) > 0.0 && swing_angle(Limb::Leg(*i), to) <= 0.0
+    })
+}
+
+// A skill asks to move its body by `dx`, movement_system decides how far it
+// actually gets.
+pub struct MoveEvent {
+    pub entity: Entity,
+    pub dx: f32,
+}
+
+// Facing after a turn-around advances by `step`, it flips halfway through.
+fn turn_around(progress: f32, step: f32, facing: Facing) -> Facing {
+    if progress < 0.5 && progress + step >= 0.5 {
+        facing.flipped()
+    } else {
+        facing
+    }
+}
+
+// The striking arm swings out and back once.
+fn melee_pose(progress: f32) -> Quat {
+    Quat::from_rotation_z((progress * PI).sin())
+}
+
+// Three blows, the arm comes back up between each.
+fn slam_pose(progress: f32) -> Quat {
+    Quat::from_rotation_z((progress * 3.0 * PI).sin().abs() * 1.5)
+}
+
+// Raises the arm to point straight ahead at the release.
+fn aim_pose(progress: f32) -> Quat {
+    Quat::from_rotation_z((progress * PI).sin() * FRAC_PI_2)
+}
+
+// Lowers the arm to load it and brings it back up.
+fn reload_pose(progress: f32) -> Quat {
+    Quat::from_rotation_z(-(progress * PI).sin() * 0.6)
+}
+
+// Holds the arms out while one is unbolted.
+fn swap_pose(progress: f32) -> Quat {
+    Quat::from_rotation_z((progress * PI).sin() * 0.3)
+}
+
+// Where a skill holds its limbs `progress` of the way through. Walks and the
+// turn move the whole body and are left to skill_system.
+pub fn skill_pose(skill: &Skill, stats: &Stats, progress: f32) -> Vec<(Limb, Quat)> {
+    match skill {
+        Skill::BasicMelee(ability) => vec![(ability.limb, melee_pose(progress))],
+        Skill::Slam(ability) => vec![(ability.limb, slam_pose(progress))],
+        Skill::BasicRanged(ability) | Skill::Scan(ability) => {
+            vec![(ability.limb, aim_pose(progress))]
+        }
+        Skill::Reload => stats
+            .skills
+            .iter()
+            .filter_map(Skill::release)
+            .map(|ability| (ability.0.limb, reload_pose(progress)))
+            .collect(),
+        Skill::SwapLimb => stats
+            .skills
+            .iter()
+            .filter_map(Skill::ability)
+            .filter(|ability| matches!(ability.limb, Limb::Arm(_)))
+            .map(|ability| (ability.limb, swap_pose(progress)))
+            .collect(),
+        Skill::WalkBackward | Skill::WalkForward | Skill::Dash(_) | Skill::TurnAround => Vec::new(),
+    }
+}
+
+// How much of the skill's animation plays per second. Everything takes the
+// same time except a reload.
+pub fn animation_speed(balance: &BalanceConfig, skill: &Skill) -> f32 {
+    match skill {
+        Skill::Reload => 1.0 / RELOAD_TIME,
+        Skill::SwapLimb => 1.0 / SWAP_LIMB_TIME,
+        _ => balance.animation_speed,
+    }
+}
+
+// Swings the limbs, moves the gait along and returns how far the body moves,
+// sending a StrideEvent for every foot that lands on the way.
+fn walk(
+    balance: &BalanceConfig,
+    skill: &Skill,
+    stats: &Stats,
+    entity: Entity,
+    facing: Facing,
+    dt: f32,
+    gait: &mut GaitPhase,
+    locks: Option<&LimbLocks>,
+    body_parts: &mut BodyTransforms,
+    stride_events: &mut EventWriter<StrideEvent>,
+) -> f32 {
+    let mul = balance.walk_multiplier(skill).unwrap_or_default();
+    let speed = stats.speed * balance.walk_speed_multiplier;
+    let phase = gait.phase;
+    body_parts.for_legs(|i, transform| {
+        transform.rotation = walk_swing(Limb::Leg(i), phase);
+    });
+    // Arms busy with an action don't swing along.
+    let id = skill.id();
+    body_parts.for_arms(|i, transform| {
+        let held = locks
+            .and_then(|locks| locks.holder(Limb::Arm(i)))
+            .is_some_and(|holder| *holder != id);
+        if !held {
+            transform.rotation = walk_swing(Limb::Arm(i), phase);
+        }
+    });
+    let next = phase + gait_advance(dt * balance.animation_speed, speed, mul);
+    gait.phase = next;
+    gait.idle = 0.0;
+    let landed = strides(body_parts.legs.keys().copied(), phase, next);
+    if let Ok(body) = body_parts.transforms.get(entity) {
+        for leg_index in landed {
+            let Some(leg) = body_parts.get(Limb::Leg(leg_index)) else {
+                continue;
+            };
+            let x = body.translation.x + leg.translation.x * facing.sign();
+            stride_events.send(StrideEvent {
+                entity,
+                leg_index,
+                position: Vec2::new(x, body.translation.y),
+            });
+        }
+    }
+    walk_step(dt, speed, facing.sign(), mul)
+}
+
+fn pose_limb(body_parts: &mut BodyTransforms, limb: Limb, rotation: Quat) {
+    if let Some(mut transform) = body_parts.get_mut(limb) {
+        transform.rotation = rotation;
+    }
+}
+
+// Poses every animated body for the current point of its skills. The quick
+// turn before a skill is left to animation_system. A walk under an action
+// goes first, the action's arm wins if they ever disagree.
+fn skill_system(
+    balance: Res<BalanceConfig>,
+    game: Res<Game>,
+    mut animations: Query<
+        (
+            Entity,
+            Option<&Animation>,
+            Option<&Walking>,
+            Option<&LimbLocks>,
+            &mut GaitPhase,
+            &AnimationRate,
+            &mut Facing,
+            &Children,
+        ),
+        Or<(With<Animation>, With<Walking>)>,
+    >,
+    stats: Query<&Stats>,
+    limbs: Query<&Limb>,
+    mut transforms: Query<&mut Transform>,
+    mut moves: EventWriter<MoveEvent>,
+    mut stride_events: EventWriter<StrideEvent>,
+) {
+    for (entity, animation, walking, locks, mut gait, rate, mut facing, children) in &mut animations
+    {
+        let dt = rate.dt();
+        let Ok([stats, _]) = stats.get_many([entity, game.opponent(entity)]) else {
+            continue;
+        };
+        let tracks = [
+            walking.map(|walking| &walking.0),
+            animation.filter(|animation| animation.lead_in <= 0.0),
+        ]
+        .into_iter()
+        .flatten()
+        .filter_map(|animation| Some((animation.progress, stats.skill(&animation.skill)?)))
+        .collect::<Vec<_>>();
+        if tracks.is_empty() {
+            continue;
+        }
+        let (arms, legs) = limb_entities(
+            children
+                .iter()
+                .filter_map(|e| Some((*e, *limbs.get(*e).ok()?))),
+        );
+        let mut body_parts = BodyTransforms {
+            transforms: &mut transforms,
+            legs,
+            arms,
+        };
+
+        let mut dx = 0.0;
+        for (progress, skill) in tracks {
+            match skill {
+                Skill::WalkBackward | Skill::WalkForward | Skill::Dash(_) => {
+                    dx += walk(
+                        &balance,
+                        skill,
+                        stats,
+                        entity,
+                        *facing,
+                        dt,
+                        &mut gait,
+                        locks,
+                        &mut body_parts,
+                        &mut stride_events,
+                    );
+                }
+                Skill::TurnAround => {
+                    *facing = turn_around(progress, dt * balance.animation_speed, *facing);
+                }
+                _ => {
+                    for (limb, rotation) in skill_pose(skill, stats, progress) {
+                        pose_limb(&mut body_parts, limb, rotation);
+                    }
+                }
+            }
+        }
+        // Standing still still gets clamped, an overlapping body is pushed
+        // out while it animates.
+        moves.send(MoveEvent { entity, dx });
+    }
+}
+
+// Brings the limbs back to rest once no walk has played for GAIT_GRACE, the
+// next walk starts the gait over. Limbs an action holds are left to it.
+fn gait_rest_system(
+    mut bodies: Query<(
+        &mut GaitPhase,
+        &AnimationRate,
+        Option<&LimbLocks>,
+        &Children,
+    )>,
+    limbs: Query<&Limb>,
+    mut transforms: Query<&mut Transform>,
+) {
+    for (mut gait, rate, locks, children) in &mut bodies {
+        if gait.phase == 0.0 {
+            continue;
+        }
+        gait.idle += rate.dt();
+        if gait.idle < GAIT_GRACE {
+            continue;
+        }
+        let t = ((gait.idle - GAIT_GRACE) / GAIT_BLEND).min(1.0);
+        for child in children {
+            let Ok(limb) = limbs.get(*child) else {
+                continue;
+            };
+            if locks.and_then(|locks| locks.holder(*limb)).is_some() {
+                continue;
+            }
+            if let Ok(mut transform) = transforms.get_mut(*child) {
+                transform.rotation = transform.rotation.lerp(Quat::IDENTITY, t);
+            }
+        }
+        if t >= 1.0 {
+            gait.phase = 0.0;
+        }
+    }
+}
+
+// Applies the moves asked for this frame, a body can't walk through the
+// other one.
+pub fn movement_system(
+    balance: Res<BalanceConfig>,
+    game: Res<Game>,
+    mut moves: EventReader<MoveEvent>,
+    stats: Query<&Stats>,
+    mut transforms: Query<&mut Transform>,
+) {
+    for event in moves.iter() {
+        let other = game.opponent(event.entity);
+        let Ok([stats, other_stats]) = stats.get_many([event.entity, other]) else {
+            continue;
+        };
+        if let Ok([mut transform, other]) = transforms.get_many_mut([event.entity, other]) {
+            let from = transform.translation.x;
+            transform.translation.x = keep_apart(
+                &balance,
+                from,
+                from + event.dx,
+                stats,
+                other.translation.x,
+                other_stats,
+            );
+        }
+    }
+}
+
+// The player's pick is done with once its animation ends, one that's still
+// waiting for its limbs stays.
+fn finished(use_skill: &mut UseSkill, game: &Game, entity: Entity, skill: &SkillId) {
+    if entity == game.player && use_skill.picked(skill) {
+        **use_skill = None;
+    }
+}
+
+// Walks under an action only ever move the body, skill_system does that.
+fn walking_system(
+    mut commands: Commands,
+    mut use_skill: ResMut<UseSkill>,
+    game: Res<Game>,
+    balance: Res<BalanceConfig>,
+    mut walks: Query<(
+        Entity,
+        &mut Walking,
+        &Stats,
+        &AnimationRate,
+        Option<&Approach>,
+    )>,
+) {
+    for (entity, mut walking, stats, rate, approach) in &mut walks {
+        let Walking(animation) = &mut *walking;
+        let looping = match stats.skill(&animation.skill) {
+            Some(skill) => {
+                animation.progress += rate.dt() * animation_speed(&balance, skill);
+                approach::loops(approach, skill)
+            }
+            // Lost along with the part that had it.
+            None => {
+                animation.progress = f32::INFINITY;
+                false
+            }
+        };
+        if animation.progress > 1.0 && !looping {
+            commands.entity(entity).remove::<Walking>();
+            finished(&mut use_skill, &game, entity, &animation.skill);
+        }
+    }
+}
+
+// Moves every animation along, lands the hits it passes and ends it once it's
+// done.
+pub fn animation_system(
+    mut commands: Commands,
+    mut use_skill: ResMut<UseSkill>,
+    game: Res<Game>,
+    balance: Res<BalanceConfig>,
+    stats: Query<&Stats>,
+    geometry: BodyGeometry,
+    bodies: Query<&Transform, With<Body>>,
+    targets: Query<&Body>,
+    mut animations: Query<(
+        Entity,
+        &mut Animation,
+        &AnimationRate,
+        &mut Facing,
+        Option<&Approach>,
+    )>,
+    mut damage: EventWriter<DamageEvent>,
+) {
+    for (entity, mut animation, rate, mut facing, approach) in &mut animations {
+        let dt = rate.dt();
+        let target = game.opponent(entity);
+        let before = animation.progress;
+        let Ok(attacker_stats) = stats.get(entity) else {
+            continue;
+        };
+        // The body changed under the animation and lost the skill, ending it
+        // is all that's left to do.
+        let Some(skill) = attacker_stats.skill(&animation.skill) else {
+            commands.entity(entity).remove::<Animation>();
+            finished(&mut use_skill, &game, entity, &animation.skill);
+            continue;
+        };
+        if animation.lead_in > 0.0 {
+            let half = balance.quick_turn / 2.0;
+            if animation.lead_in > half && animation.lead_in - dt <= half {
+                *facing = facing.flipped();
+            }
+            animation.lead_in -= dt;
+        } else {
+            animation.progress += dt * animation_speed(&balance, skill);
+        }
+        if let Some((ability, hits)) = skill.hits() {
+            for hit in hits {
+                // The strike lands if the tip of the striking limb ends up
+                // inside any part of the target.
+                if before >= *hit || animation.progress < *hit {
+                    continue;
+                }
+                let landed = geometry
+                    .limb_world_endpoint(entity, ability.limb)
+                    .and_then(|tip| Some((geometry.body_hit_test(target, tip)?, tip)));
+                if let Some((part, tip)) = landed {
+                    damage.send(DamageEvent {
+                        target,
+                        source: DamageSource::Attack {
+                            attacker: entity,
+                            skill: animation.skill.clone(),
+                        },
+                        amount: hit_damage(&balance, ability),
+                        emp: ability.emp,
+                        impact: targets
+                            .get(target)
+                            .ok()
+                            .and_then(|body| Impact::on(body, part, tip)),
+                    });
+                }
+            }
+        }
+        if let Some((ability, release)) = skill.release() {
+            if before < release && animation.progress >= release {
+                let tip = geometry.limb_world_endpoint(entity, ability.limb);
+                if let (Some(tip), Ok(aim_at)) = (tip, bodies.get(target)) {
+                    projectile::launch(
+                        &mut commands,
+                        &balance,
+                        entity,
+                        animation.skill.clone(),
+                        target,
+                        hit_damage(&balance, ability),
+                        ability.emp,
+                        tip,
+                        aim_at.translation.x,
+                    );
+                }
+            }
+        }
+        if animation.progress > 1.0 && !approach::loops(approach, skill) {
+            commands.entity(entity).remove::<Animation>();
+            finished(&mut use_skill, &game, entity, &animation.skill);
+        }
+    }
+}
+
+pub fn keep_apart(
+    balance: &BalanceConfig,
+    from: f32,
+    to: f32,
+    stats: &Stats,
+    other: f32,
+    other_stats: &Stats,
+) -> f32 {
+    let min_distance = (stats.width + other_stats.width) / 2.0 + balance.body_gap;
+    if from < other {
+        to.min(other - min_distance)
+    } else {
+        to.max(other + min_distance)
+    }
+}
+
+// Where a walk started now ends up, what `do_animation` moves the body by
+// over the whole animation.
+pub fn walk_destination(
+    balance: &BalanceConfig,
+    transform: &Transform,
+    facing: Facing,
+    stats: &Stats,
+    skill: &Skill,
+    other: &Transform,
+    other_stats: &Stats,
+) -> Option<f32> {
+    let x = transform.translation.x;
+    let distance = balance.walk_distance(stats, skill)?;
+    Some(keep_apart(
+        balance,
+        x,
+        x + facing.sign() * distance,
+        stats,
+        other.translation.x,
+        other_stats,
+    ))
+}
+
+// Pushes overlapping bodies apart. Bodies at exactly the same spot always
+// split with the player on the left so the result stays deterministic.
+fn separation_system(
+    game: Res<Game>,
+    balance: Res<BalanceConfig>,
+    mut bodies: Query<(&Stats, &mut Transform)>,
+) {
+    let Ok([(player_stats, mut player), (enemy_stats, mut enemy)]) =
+        bodies.get_many_mut([game.player, game.enemy])
+    else {
+        return;
+    };
+    let min_distance = (player_stats.width + enemy_stats.width) / 2.0 + balance.body_gap;
+    let offset = enemy.translation.x - player.translation.x;
+    if offset.abs() >= min_distance {
+        return;
+    }
+    let side = if offset < 0.0 { -1.0 } else { 1.0 };
+    let push = (min_distance - offset.abs()) / 2.0;
+    player.translation.x -= side * push;
+    enemy.translation.x += side * push;
+}
+
+fn reject_self_target_system(game: Res<Game>, mut state: ResMut<State<AppState>>) {
+    if game.targets_self() {
+        error!(
+            "player and enemy are the same entity {:?}, refusing to start the battle",
+            game.player
+        );
+        let _ = state.set(AppState::PreBattle);
+    }
+}
+
+// Facing is the source of truth, the sprite flip only mirrors it.
+fn facing_system(
+    balance: Res<BalanceConfig>,
+    mut bodies: Query<(&Facing, &Stats, Option<&Animation>, &mut Transform)>,
+) {
+    for (facing, stats, animation, mut transform) in &mut bodies {
+        let width = animation
+            .and_then(|animation| {
+                let skill = stats.skill(&animation.skill)?;
+                Some(animation.turn_scale(skill, &balance))
+            })
+            .unwrap_or(1.0);
+        transform.scale.x = facing.sign() * width;
+    }
+}
+
+// Starting an attack while facing away from the target turns around first.
+// A body on the move can't, the walk would turn with it.
+fn auto_face_system(
+    ruleset: Res<Ruleset>,
+    balance: Res<BalanceConfig>,
+    game: Res<Game>,
+    mut started: Query<
+        (Entity, &mut Animation, &Stats, &Facing),
+        (Added<Animation>, Without<Walking>),
+    >,
+    transforms: Query<&Transform>,
+) {
+    if !ruleset.auto_face {
+        return;
+    }
+    for (entity, mut animation, stats, facing) in &mut started {
+        let attacking = stats
+            .skill(&animation.skill)
+            .is_some_and(|skill| skill.hits().is_some());
+        if let Ok([transform, target]) = transforms.get_many([entity, game.opponent(entity)]) {
+            let towards = Facing::towards(transform.translation.x, target.translation.x);
+            if attacking && towards != *facing {
+                animation.lead_in = balance.quick_turn;
+            }
+        }
+    }
+}
+
+// Starts the player's pick once its limbs are free. A walk and an action
+// play together, anything else waits for the body to be done.
+pub fn use_skill_system(
+    mut commands: Commands,
+    mut use_skill: ResMut<UseSkill>,
+    game: Res<Game>,
+    turns: Option<Res<Turns>>,
+    bodies: Query<(
+        &Stats,
+        &Body,
+        Option<&SkillRuntime>,
+        Option<&Animation>,
+        Option<&Walking>,
+        &LimbLocks,
+    )>,
+    recovering: Query<(), With<Recovery>>,
+    external: Query<(), With<ExternalInput>>,
+    sidearms: Query<&Sidearm>,
+) {
+    let Some(skill) = &**use_skill else {
+        return;
+    };
+    let may_act = turns
+        .as_ref()
+        .is_none_or(|turns| turns.can_act(game.player));
+    if !may_act || recovering.contains(game.player) || external.contains(game.player) {
+        return;
+    }
+    let Ok((stats, body, runtime, animation, walking, locks)) = bodies.get(game.player) else {
+        return;
+    };
+    // The body changed between the click and now and the skill went with it,
+    // dropping the selection gives the buttons back.
+    let Some(found) = stats.skill(skill) else {
+        warn!("{} is no longer on the body, dropping it", skill);
+        **use_skill = None;
+        return;
+    };
+    // Turns are taken one skill at a time.
+    let Some(track) = locks::track_for(found, body, stats, locks, animation, walking)
+        .filter(|track| turns.is_none() || *track == Track::Main)
+    else {
+        return;
+    };
+    if !energy::can_afford(stats, found) {
+        info!("Not enough energy for {}", skill);
+        **use_skill = None;
+        return;
+    }
+    if !ammo::loaded(runtime, found) {
+        info!("{} is out of ammo", skill);
+        **use_skill = None;
+        return;
+    }
+    if !runtime::ready(runtime, found) {
+        info!("{} is cooling down", skill);
+        **use_skill = None;
+        return;
+    }
+    if !limb_swap::stocked(sidearms.get(game.player).ok(), found) {
+        info!("No spare arm to swap in");
+        **use_skill = None;
+        return;
+    }
+    let mut player = commands.entity(game.player);
+    match (track, animation) {
+        (Track::Walk, _) => {
+            player.insert(Walking(Animation::new(skill.clone())));
+        }
+        // Removed first so the action counts as just started.
+        (Track::Swap, Some(walk)) => {
+            player
+                .remove::<Animation>()
+                .insert(Walking(Animation {
+                    skill: walk.skill.clone(),
+                    ..*walk
+                }))
+                .insert(Animation::new(skill.clone()));
+        }
+        _ => {
+            player.insert(Animation::new(skill.clone()));
+        }
+    }
+}
+
+// Space between the attacker's edge and the nearest of the target, arms
+// hanging out included, which is what reach is measured in.
+pub fn edge_gap(
+    attacker: &Transform,
+    attacker_stats: &Stats,
+    target: &Transform,
+    target_stats: &Stats,
+) -> f32 {
+    let offset = target.translation.x - attacker.translation.x;
+    // The flip mirrors the target's Facing, see facing_system.
+    let front = offset * target.scale.x < 0.0;
+    offset.abs() - (attacker_stats.width + target_stats.width) / 2.0 - target_stats.guard(front)
+}
+
+// Whether `skill` lands from where the bodies stand, see `Stats::melee_reach`.
+pub fn melee_connects(
+    skill: &Skill,
+    attacker: &Transform,
+    attacker_facing: Facing,
+    attacker_stats: &Stats,
+    target: &Transform,
+    target_stats: &Stats,
+) -> bool {
+    Facing::towards(attacker.translation.x, target.translation.x) == attacker_facing
+        && attacker_stats
+            .melee_reach(skill)
+            .is_some_and(|reach| edge_gap(attacker, attacker_stats, target, target_stats) <= reach)
+}
+
+// Whether any of the attacker's melee skills lands.
+pub fn melee_in_reach(
+    attacker: &Transform,
+    attacker_facing: Facing,
+    attacker_stats: &Stats,
+    target: &Transform,
+    target_stats: &Stats,
+) -> bool {
+    attacker_stats.skills.iter().any(|skill| {
+        melee_connects(
+            skill,
+            attacker,
+            attacker_facing,
+            attacker_stats,
+            target,
+            target_stats,
+        )
+    })
+}
+
+fn hit_damage(balance: &BalanceConfig, ability: &Ability<f32>) -> f32 {
+    ability.meta * balance.damage_multiplier
+}
+
+pub fn health_after(health: f32, amount: f32) -> f32 {
+    (health - amount).max(0.0)
+}
+
+// Damage empties the overshield before it gets to health, EMP damage counts
+// double against the shield. Healing skips the shield. Returns the
+// overshield and health left.
+pub fn absorb(overshield: f32, health: f32, amount: f32, emp: bool) -> (f32, f32) {
+    if amount <= 0.0 {
+        return (overshield, health_after(health, amount));
+    }
+    let rate = if emp { 2.0 } else { 1.0 };
+    let blocked = (amount * rate).min(overshield.max(0.0));
+    (
+        overshield - blocked,
+        health_after(health, amount - blocked / rate),
+    )
+}
+
+// Damage `skill` deals if it's started now and the target stays put. Every
+// hit lands while in reach, nothing is random yet.
+pub fn expected_damage(
+    balance: &BalanceConfig,
+    skill: &Skill,
+    attacker: &Transform,
+    attacker_facing: Facing,
+    attacker_stats: &Stats,
+    target: &Transform,
+    target_stats: &Stats,
+) -> Option<f32> {
+    let (ability, hits) = skill.hits()?;
+    melee_connects(
+        skill,
+        attacker,
+        attacker_facing,
+        attacker_stats,
+        target,
+        target_stats,
+    )
+    .then(|| hit_damage(balance, ability) * hits.len() as f32)
+}
+
+// What an event takes off its target once the rules have had their say.
+pub fn dealt_damage(ruleset: &Ruleset, event: &DamageEvent) -> f32 {
+    match event.source.dealer() {
+        Some(_) => ruleset.mutators.scale_damage(event.amount),
+        None => event.amount,
+    }
+}
+
+// What last took a body from some health to none, for the game over screen.
+// A body brought back up and knocked down again only keeps the later one.
+#[derive(Default)]
+pub struct FinishingBlows(Vec<(Entity, DamageSource)>);
+
+pub fn damage_system(
+    ruleset: Res<Ruleset>,
+    game: Res<Game>,
+    boosters: Res<Boosters>,
+    mut blows: ResMut<FinishingBlows>,
+    mut events: EventReader<DamageEvent>,
+    mut stats: Query<&mut Stats, Without<Invulnerable>>,
+) {
+    for event in events.iter() {
+        if let Ok(mut stats) = stats.get_mut(event.target) {
+            let mut amount = dealt_damage(&ruleset, event);
+            // Heals come in as negative damage and stay whole.
+            if event.target == game.player && amount > 0.0 {
+                amount = boosters.resist(amount);
+            }
+            // Negative amounts heal, never past full.
+            let (overshield, health) = absorb(stats.overshield, stats.health, amount, event.emp);
+            stats.overshield = overshield;
+            if stats.health > 0.0 && health <= 0.0 {
+                blows.0.retain(|(entity, _)| *entity != event.target);
+                blows.0.push((event.target, event.source.clone()));
+            }
+            stats.health = health.min(stats.max_health);
+        }
+    }
+}
+
+pub fn defeat_system(
+    mut commands: Commands,
+    game: Res<Game>,
+    ruleset: Res<Ruleset>,
+    timer: Res<BattleTimer>,
+    survival: Option<Res<SurvivalRun>>,
+    boosters: Res<Boosters>,
+    blows: Res<FinishingBlows>,
+    stats: Query<&Stats>,
+    controllers: Query<(Entity, &AiController)>,
+    mut state: ResMut<State<AppState>>,
+) {
+    if let Ok([player, enemy]) = stats.get_many([game.player, game.enemy]) {
+        let winner = match (player.health <= 0.0, enemy.health <= 0.0) {
+            (false, false) => return,
+            (true, true) => None,
+            (true, false) => Some(game.enemy),
+            (false, true) => Some(game.player),
+        };
+        commands.insert_resource(BattleSummary {
+            winner,
+            sudden_death: timer.sudden_death,
+            mutators: ruleset.mutators,
+            boosters: boosters.active().to_vec(),
+            player_health: if player.max_health > 0.0 {
+                (player.health / player.max_health).clamp(0.0, 1.0)
+            } else {
+                0.0
+            },
+            decisions: controllers
+                .iter()
+                .filter_map(|(entity, controller)| Some((entity, controller.decision()?.clone())))
+                .collect(),
+            finishing_blows: blows
+                .0
+                .iter()
+                .filter(|(entity, _)| stats.get(*entity).is_ok_and(|stats| stats.health <= 0.0))
+                .cloned()
+                .collect(),
+        });
+        if survival.is_some() && winner == Some(game.player) {
+            let _ = state.set(AppState::Intermission);
+        } else {
+            let _ = state.set(AppState::GameOver);
+        }
+    }
+}
+
+pub fn reset_battle_system(
+    mut commands: Commands,
+    game: Res<Game>,
+    entities: &Entities,
+    mut use_skill: ResMut<UseSkill>,
+    mut bodies: Query<(&mut Stats, &mut Transform, &mut Facing)>,
+) {
+    **use_skill = None;
+    commands.insert_resource(FinishingBlows::default());
+    for (entity, x) in [(game.player, -SPAWN_X), (game.enemy, SPAWN_X)] {
+        if entities.contains(entity) {
+            commands
+                .entity(entity)
+                .remove::<Animation>()
+                .remove::<Walking>()
+                .remove::<Approach>();
+        }
+        if let Ok((mut stats, mut transform, mut facing)) = bodies.get_mut(entity) {
+            stats.health = stats.max_health;
+            stats.energy = stats.max_energy;
+            stats.overshield = stats.max_overshield;
+            transform.translation.x = x;
+            *facing = Facing::towards(x, 0.0);
+        }
+    }
+}
+
+// How far either side of the middle the bodies start out.
+pub const SPAWN_X: f32 = 4.0;
+
+// Moves the floor and the bodies standing on it when the ground changes, and
+// puts new bodies on it.
+fn ground_system(
+    ground: Res<Ground>,
+    game: Option<Res<Game>>,
+    mut sprites: Query<&mut Transform, With<GroundSprite>>,
+    mut bodies: Query<&mut Transform, (With<Body>, Without<GroundSprite>)>,
+) {
+    let spawned = game.as_ref().is_some_and(|game| game.is_changed());
+    if !(ground.is_changed() || spawned) {
+        return;
+    }
+    for mut transform in &mut sprites {
+        transform.translation.y = ground.y;
+    }
+    let Some(game) = game else {
+        return;
+    };
+    for entity in [game.player, game.enemy] {
+        if let Ok(mut transform) = bodies.get_mut(entity) {
+            transform.translation.y = ground.y;
+        }
+    }
+}
+
+pub fn spawn_combatants(commands: &mut Commands, player: Body, enemy: Body) -> Game {
+    let player = commands.spawn_body(
+        player,
+        Transform::from_translation(Vec3::new(-SPAWN_X, 0.0, RenderLayer::NearBody.z())),
+    );
+    commands
+        .entity(player)
+        .insert(RenderLayer::NearBody)
+        .insert(AnimationRate::default())
+        .insert(Ammo::default())
+        .insert(SkillRuntime::default())
+        .insert(Knowledge::default())
+        .insert(LimbLocks::default())
+        .insert(GaitPhase::default())
+        .insert(ShieldTimer::default());
+
+    let enemy = commands.spawn_body(
+        enemy,
+        Transform::from_translation(Vec3::new(SPAWN_X, 0.0, RenderLayer::FarBody.z())),
+    );
+    commands
+        .entity(enemy)
+        .insert(Facing::Left)
+        .insert(RenderLayer::FarBody)
+        .insert(AiController::default())
+        .insert(AnimationRate::default())
+        .insert(Ammo::default())
+        .insert(SkillRuntime::default())
+        .insert(Knowledge::default())
+        .insert(LimbLocks::default())
+        .insert(GaitPhase::default())
+        .insert(ShieldTimer::default());
+
+    Game { player, enemy }
+}
+
+// Height of the floor everything stands on. Bodies are kept on it and
+// anything falling stops there, an arena can put it where it likes.
+#[derive(Clone, Copy, Default)]
+pub struct Ground {
+    pub y: f32,
+}
+
+impl Ground {
+    // Whether something at `y` has come down to the floor.
+    pub fn grounded(&self, y: f32) -> bool {
+        y <= self.y
+    }
+}
+
+// The floor's sprite, its top edge is `Ground::y`.
+#[derive(Component)]
+pub struct GroundSprite;
+
+pub fn arena_setup_system(mut commands: Commands, ground: Res<Ground>, mut rng: ResMut<GameRng>) {
+    let size = 40.0;
+    commands
+        .spawn_bundle(SpriteBundle {
+            transform: Transform::from_xyz(0.0, ground.y, RenderLayer::Ground.z())
+                .with_scale(Vec3::new(size, size, 1.0)),
+            sprite: Sprite {
+                color: Color::BLACK,
+                anchor: Anchor::TopCenter,
+                ..default()
+            },
+            ..default()
+        })
+        .insert(GroundSprite);
+    let game = spawn_combatants(&mut commands, Body::default(), random_body(&mut **rng));
+    commands.insert_resource(game);
+}
+
+pub struct BattlePlugin;
+
+impl Plugin for BattlePlugin {
+    fn build(&self, app: &mut App) {
+        // The windowed game adds the state itself to start out loading.
+        if !app.world.contains_resource::<State<AppState>>() {
+            app.add_state(AppState::PreBattle);
+        }
+        app.add_event::<DamageEvent>()
+            .add_event::<MoveEvent>()
+            .add_event::<StrideEvent>()
+            .init_resource::<GameRng>()
+            .init_resource::<BalanceConfig>()
+            .init_resource::<UseSkill>()
+            .init_resource::<Ground>()
+            .add_plugin(body::BodyPlugin)
+            .add_plugin(churn::ChurnPlugin)
+            .add_plugin(booster::BoosterPlugin)
+            .add_plugin(effects::EffectsPlugin)
+            .add_plugin(energy::EnergyPlugin)
+            .add_plugin(periodic::PeriodicPlugin)
+            .add_plugin(rate::RatePlugin)
+            .add_plugin(projectile::ProjectilePlugin)
+            .add_plugin(pickup::PickupPlugin)
+            .add_plugin(ammo::AmmoPlugin)
+            .add_plugin(runtime::RuntimePlugin)
+            .add_plugin(knowledge::KnowledgePlugin)
+            .add_plugin(locks::LocksPlugin)
+            .add_plugin(approach::ApproachPlugin)
+            .add_plugin(swap::SwapPlugin)
+            .add_plugin(shield::ShieldPlugin)
+            .add_plugin(weather::WeatherPlugin)
+            .add_plugin(mutators::MutatorsPlugin)
+            .add_plugin(turn::TurnPlugin)
+            .add_plugin(ai::AiPlugin)
+            .add_plugin(timer::TimerPlugin)
+            .add_plugin(interrupt::InterruptPlugin)
+            .add_system(facing_system.after(animation_system))
+            .add_system(separation_system.after(animation_system))
+            .add_system(ground_system)
+            .init_resource::<FinishingBlows>()
+            .add_system_set(SystemSet::on_enter(AppState::Battle).with_system(reset_battle_system))
+            .add_system_set(
+                SystemSet::on_update(AppState::Battle)
+                    .with_system(reject_self_target_system)
+                    .with_system(use_skill_system.after(BodyStats))
+                    .with_system(auto_face_system.before(skill_system))
+                    .with_system(
+                        skill_system
+                            .after(rate::animation_rate_system)
+                            .before(movement_system),
+                    )
+                    .with_system(gait_rest_system.after(skill_system))
+                    .with_system(movement_system.before(animation_system))
+                    .with_system(walking_system.after(movement_system))
+                    .with_system(
+                        animation_system
+                            .after(rate::animation_rate_system)
+                            .before(damage_system),
+                    )
+                    .with_system(damage_system)
+                    .with_system(defeat_system.after(damage_system)),
+            );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn turning_flips_once_halfway() {
+        let mut facing = Facing::Right;
+        let mut flips = 0;
+        let mut progress = 0.0;
+        while progress < 1.0 {
+            let next = turn_around(progress, 0.1, facing);
+            if next != facing {
+                flips += 1;
+            }
+            facing = next;
+            progress += 0.1;
+        }
+        assert_eq!(flips, 1);
+        assert_eq!(facing, Facing::Left);
+    }
+
+    #[test]
+    fn arms_end_where_they_started() {
+        for pose in [melee_pose, slam_pose] {
+            assert!(pose(0.0).angle_between(Quat::IDENTITY) < 1e-3);
+            assert!(pose(1.0).angle_between(Quat::IDENTITY) < 1e-3);
+            assert!(pose(0.5).angle_between(Quat::IDENTITY) > 0.5);
+        }
+    }
+
+    #[test]
+    fn a_biped_lands_four_strides_in_two_cycles() {
+        // Fast enough that two full swings fit before the walk winds down.
+        let (speed, mul) = (20.0, 1.0);
+        let step = gait_advance(1.0 / 600.0, speed, mul);
+        let two_cycles = 4.0 * PI;
+        let mut landed = Vec::new();
+        let mut phase = 0.0;
+        while phase < two_cycles + step {
+            landed.extend(strides([0, 1], phase, phase + step));
+            phase += step;
+        }
+        landed.sort();
+        assert_eq!(landed, [0, 0, 1, 1]);
+    }
+    #[test]
+    fn overshields_soak_damage_before_health() {
+        // Soaked entirely, then spilling over, then nothing left to soak.
+        assert_eq!(absorb(10.0, 50.0, 4.0, false), (6.0, 50.0));
+        assert_eq!(absorb(10.0, 50.0, 15.0, false), (0.0, 45.0));
+        assert_eq!(absorb(0.0, 50.0, 15.0, false), (0.0, 35.0));
+        // EMP damage takes the shield down twice as fast, what's left over
+        // hits health at its normal size.
+        assert_eq!(absorb(10.0, 50.0, 4.0, true), (2.0, 50.0));
+        assert_eq!(absorb(10.0, 50.0, 15.0, true), (0.0, 40.0));
+        // Healing never goes to the shield, and health bottoms out at zero.
+        assert_eq!(absorb(3.0, 40.0, -5.0, false), (3.0, 45.0));
+        assert_eq!(absorb(5.0, 10.0, 100.0, true), (0.0, 0.0));
+    }
+}
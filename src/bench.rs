@@ -0,0 +1,133 @@
+use std::{hint::black_box, str::FromStr, time::Instant};
+
+use bevy::prelude::*;
+use rand::{rngs::StdRng, SeedableRng};
+
+use crate::{
+    balance::BalanceConfig,
+    battle::{gait_advance, keep_apart, limb_entities, walk_step, walk_swing},
+    body::{compute_stats, random_body_with_slots, Limb, Stats},
+    fixtures::seeded_bodies,
+};
+
+const FRAME: f32 = 1.0 / 60.0;
+
+pub struct BenchOptions {
+    pub iterations: u32,
+}
+
+impl BenchOptions {
+    pub fn from_args(args: impl Iterator<Item = String>) -> Option<Self> {
+        let args = args.collect::<Vec<_>>();
+        if !args.iter().any(|arg| arg == "--bench") {
+            return None;
+        }
+        let mut options = BenchOptions { iterations: 1000 };
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--bench" => {}
+                "--iterations" => {
+                    options.iterations = value(args.next()).unwrap_or(options.iterations)
+                }
+                _ => eprintln!("Unknown argument `{}`", arg),
+            }
+        }
+        Some(options)
+    }
+}
+
+fn value<T: FromStr>(arg: Option<String>) -> Option<T> {
+    let value = arg.and_then(|value| value.parse().ok());
+    if value.is_none() {
+        eprintln!("--iterations expects a valid value");
+    }
+    value
+}
+
+fn measure(name: &str, iterations: u32, mut f: impl FnMut(u32)) {
+    // One untimed round so the first allocations don't count.
+    f(0);
+    let start = Instant::now();
+    for i in 0..iterations {
+        f(i);
+    }
+    let per_iteration = start.elapsed() / iterations.max(1);
+    println!("{:<32} {:>10.2?}/iter", name, per_iteration);
+}
+
+// One walking body as do_animation sees it, without the ECS around it.
+struct Walker {
+    stats: Stats,
+    limbs: Vec<(Entity, Limb)>,
+    rotations: Vec<Quat>,
+    x: f32,
+}
+
+fn animation_step(
+    balance: &BalanceConfig,
+    walkers: &mut [Walker],
+    opponent: &Stats,
+    progress: f32,
+) {
+    for walker in walkers {
+        let (arms, legs) = limb_entities(walker.limbs.iter().copied());
+        black_box((&arms, &legs));
+        let to = walker.x + walk_step(FRAME, walker.stats.speed, 1.0, 1.0);
+        for ((_, limb), rotation) in walker.limbs.iter().zip(&mut walker.rotations) {
+            *rotation = walk_swing(*limb, gait_advance(progress, walker.stats.speed, 1.0));
+        }
+        walker.x = keep_apart(balance, walker.x, to, &walker.stats, 100.0, opponent);
+    }
+}
+
+// Times the hot paths with plain wall clock numbers. Run with `--release` for
+// numbers worth comparing.
+pub fn run(options: BenchOptions) {
+    let iterations = options.iterations;
+
+    for (arms, legs) in [(1, 2), (2, 2), (4, 4)] {
+        measure(
+            &format!("random_body {} arms {} legs", arms, legs),
+            iterations,
+            |i| {
+                let mut rng = StdRng::seed_from_u64(i as u64);
+                black_box(random_body_with_slots(&mut rng, arms, legs));
+            },
+        );
+    }
+
+    let bodies = seeded_bodies(64, 4, 4);
+    measure("compute_stats 8 limbs", iterations, |i| {
+        black_box(compute_stats(&bodies[i as usize % bodies.len()]));
+    });
+
+    let mut next = 0;
+    let mut walkers = seeded_bodies(100, 2, 2)
+        .into_iter()
+        .map(|body| {
+            let limbs = body
+                .arms()
+                .iter()
+                .map(|arm| Limb::Arm(arm.slot()))
+                .chain(body.legs().iter().map(|leg| Limb::Leg(leg.slot())))
+                .map(|limb| {
+                    next += 1;
+                    (Entity::from_raw(next), limb)
+                })
+                .collect::<Vec<_>>();
+            Walker {
+                stats: compute_stats(&body),
+                rotations: vec![Quat::IDENTITY; limbs.len()],
+                limbs,
+                x: 0.0,
+            }
+        })
+        .collect::<Vec<_>>();
+    let balance = BalanceConfig::default();
+    let opponent = compute_stats(&seeded_bodies(1, 2, 2)[0]);
+    measure("walk step 100 bodies", iterations, |i| {
+        let progress = (i % 60) as f32 / 60.0;
+        animation_step(&balance, &mut walkers, &opponent, progress);
+    });
+}
@@ -1,50 +1,319 @@
-use std::ops::{Range, RangeInclusive};
+use std::{fmt, ops::RangeInclusive, str::FromStr};
 
-use bevy::{prelude::*, sprite::Anchor};
+use bevy::{ecs::schedule::SystemLabel, prelude::*, sprite::Anchor};
 use rand::{seq::SliceRandom, Rng};
-
-trait BodyPartMeta {
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    affinity,
+    attachment::{ArmMeta, Attachment},
+    battle,
+    bounds::{bounded_stats, finalize_system, Capped, StatModifiers},
+    churn::{DeferredSpawner, Piece, Priority},
+    error::GameError,
+    layer::RenderLayer,
+    locale::t,
+    runtime::SkillRuntime,
+    Game,
+};
+
+// What a part adds to the body's stats on top of its `PartStats`.
+pub trait BodyPartMeta {
     fn add_to_stats(&self, stats: &mut Stats);
-}
 
-impl BodyPartMeta for () {
-    fn add_to_stats(&self, stats: &mut Stats) {}
+    // Changes the part's own skills before they join the body's.
+    fn adjust_skills(&self, _skills: &mut [Skill]) {}
 }
 
-#[derive(Clone)]
-struct PartStats {
+// The stats every kind of part has. Read-only from outside, build new ones
+// with `PartStats::new`.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct PartStats {
     skills: Vec<Skill>,
     material: Material,
     weight: f32,
 
     health: f32,
     energy: f32,
+    // A separate pool soaking up damage before health, see shield.rs.
+    #[serde(default)]
+    overshield: f32,
 
     size: f32,
     color: Color,
+    // Battles in a row the part ended with the body below LOW_HEALTH, it
+    // rusts at RUST_AFTER.
+    #[serde(default)]
+    low_battles: u8,
+    // Battles a Rust part has decayed for, see `end_battle`.
+    #[serde(default)]
+    decay: u8,
+}
+
+// A Rust part loses RUST_DECAY of its max health after every battle, for up
+// to MAX_DECAY battles. Other parts turn to Rust after ending RUST_AFTER
+// battles in a row below LOW_HEALTH, except Plastic which can't rust.
+const RUST_DECAY: f32 = 0.03;
+const MAX_DECAY: u8 = 10;
+pub const LOW_HEALTH: f32 = 0.25;
+const RUST_AFTER: u8 = 3;
+// Scrap a replaced part is worth, Rust is mostly scrap already.
+const SALVAGE_SCRAP: u32 = 5;
+const RUST_SALVAGE: u32 = 3;
+
+impl PartStats {
+    // No skills and the material's own color, see `with_skills` and
+    // `with_color`.
+    pub fn new(material: Material, size: f32, weight: f32, health: f32, energy: f32) -> Self {
+        Self {
+            skills: Vec::new(),
+            material,
+            weight,
+            health,
+            energy,
+            overshield: 0.0,
+            size,
+            color: material.color(PaletteMode::Default),
+            low_battles: 0,
+            decay: 0,
+        }
+    }
+
+    pub fn with_skills(mut self, skills: Vec<Skill>) -> Self {
+        self.skills = skills;
+        self
+    }
+
+    pub fn with_color(mut self, color: Color) -> Self {
+        self.color = color;
+        self
+    }
+
+    pub fn with_overshield(mut self, overshield: f32) -> Self {
+        self.overshield = overshield;
+        self
+    }
+
+    // Keeps the rust count and decay of `worn`, for a part rebuilt from it.
+    pub fn with_wear_of(mut self, worn: &PartStats) -> Self {
+        self.low_battles = worn.low_battles;
+        self.decay = worn.decay;
+        self
+    }
+
+    pub fn skills(&self) -> &[Skill] {
+        &self.skills
+    }
+
+    pub fn material(&self) -> Material {
+        self.material
+    }
+
+    pub fn weight(&self) -> f32 {
+        self.weight
+    }
+
+    pub fn health(&self) -> f32 {
+        self.health
+    }
+
+    // Negative for parts that drain energy.
+    pub fn energy(&self) -> f32 {
+        self.energy
+    }
+
+    pub fn overshield(&self) -> f32 {
+        self.overshield
+    }
+
+    pub fn size(&self) -> f32 {
+        self.size
+    }
+
+    pub fn color(&self) -> Color {
+        self.color
+    }
+
+    pub fn salvage(&self) -> u32 {
+        match self.material {
+            Material::Rust => SALVAGE_SCRAP * RUST_SALVAGE,
+            _ => SALVAGE_SCRAP,
+        }
+    }
+
+    fn can_rust(&self) -> bool {
+        !matches!(self.material, Material::Plastic | Material::Rust)
+    }
+
+    // Whether ending one more battle below LOW_HEALTH turns the part to Rust.
+    pub fn rusts_next(&self) -> bool {
+        self.can_rust() && self.low_battles + 1 >= RUST_AFTER
+    }
+
+    // Counts a battle the body ended on `health_fraction` of its health.
+    // Health is kept for the whole body, so every part counts as ending on
+    // it. True when the part rusted over.
+    fn end_battle(&mut self, health_fraction: f32) -> bool {
+        if self.material == Material::Rust {
+            if self.decay < MAX_DECAY {
+                self.decay += 1;
+                self.health *= 1.0 - RUST_DECAY;
+            }
+            return false;
+        }
+        if !self.can_rust() || health_fraction >= LOW_HEALTH {
+            self.low_battles = 0;
+            return false;
+        }
+        self.low_battles += 1;
+        if self.low_battles < RUST_AFTER {
+            return false;
+        }
+        self.rust();
+        true
+    }
+
+    // Health, energy and weight follow the material's base numbers, whatever
+    // the part rolled on top of them stays.
+    fn rust(&mut self) {
+        let (from, to) = (self.material, Material::Rust);
+        self.health *= to.base_hp() / from.base_hp();
+        if from.base_energy() != 0.0 {
+            self.energy *= to.base_energy() / from.base_energy();
+        }
+        self.weight *= to.density() / from.density();
+        self.color = to.color(PaletteMode::Default);
+        self.material = to;
+        self.low_battles = 0;
+    }
+
+    fn part_material(&self) -> PartMaterial {
+        PartMaterial {
+            material: self.material,
+            color: self.color,
+        }
+    }
+
+    fn scale(&mut self, factor: f32) {
+        self.health *= factor;
+        self.overshield *= factor;
+        for skill in &mut self.skills {
+            if let Skill::BasicMelee(ability) | Skill::BasicRanged(ability) | Skill::Slam(ability) =
+                skill
+            {
+                ability.meta *= factor;
+            }
+        }
+    }
 }
 
-#[derive(Clone)]
-struct BodyPart<M: BodyPartMeta> {
+#[derive(Clone, Serialize, Deserialize)]
+pub struct BodyPart<M: BodyPartMeta> {
     name: String,
     stats: PartStats,
     meta: M,
+    // Times the part has been upgraded, see upgrade.rs. Only shows in the
+    // name.
+    #[serde(default)]
+    tier: u8,
+    // The torso slot a limb is attached to. It never changes while the limb
+    // is on the body, losing another limb leaves a gap instead of moving it.
+    // Always 0 for the torso and head.
+    #[serde(default)]
+    slot: u8,
 }
 
 impl<M: BodyPartMeta> BodyPart<M> {
+    pub fn new(name: impl Into<String>, stats: PartStats, meta: M) -> Self {
+        Self {
+            name: name.into(),
+            stats,
+            meta,
+            tier: 0,
+            slot: 0,
+        }
+    }
+
+    pub fn with_tier(mut self, tier: u8) -> Self {
+        self.tier = tier;
+        self
+    }
+
+    pub fn tier(&self) -> u8 {
+        self.tier
+    }
+
+    pub fn with_slot(mut self, slot: u8) -> Self {
+        self.slot = slot;
+        self
+    }
+
+    pub fn slot(&self) -> u8 {
+        self.slot
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn stats(&self) -> &PartStats {
+        &self.stats
+    }
+
+    pub fn meta(&self) -> &M {
+        &self.meta
+    }
+
+    pub fn meta_mut(&mut self) -> &mut M {
+        &mut self.meta
+    }
+
     fn add_to_stats(&self, stats: &mut Stats) {
+        let first = stats.skills.len();
         stats.add_part_stats(&self.stats);
+        self.meta.adjust_skills(&mut stats.skills[first..]);
         self.meta.add_to_stats(stats);
     }
+
+    fn end_battle(&mut self, health_fraction: f32) -> bool {
+        let rusted = self.stats.end_battle(health_fraction);
+        if rusted && !self.name.contains("Rusty") {
+            self.name = format!("Rusty {}", self.name);
+        }
+        rusted
+    }
 }
 
-#[derive(Clone)]
-struct HeadMeta {
+#[derive(Clone, Serialize, Deserialize)]
+pub struct HeadMeta {
     refresh_rate: f32,
     close_vision: f32,
     far_vision: f32,
 }
 
+impl HeadMeta {
+    pub fn new(refresh_rate: f32, close_vision: f32, far_vision: f32) -> Self {
+        Self {
+            refresh_rate,
+            close_vision,
+            far_vision,
+        }
+    }
+
+    // Becomes the body's reaction time, lower is better.
+    pub fn refresh_rate(&self) -> f32 {
+        self.refresh_rate
+    }
+
+    pub fn close_vision(&self) -> f32 {
+        self.close_vision
+    }
+
+    pub fn far_vision(&self) -> f32 {
+        self.far_vision
+    }
+}
+
 impl BodyPartMeta for HeadMeta {
     fn add_to_stats(&self, stats: &mut Stats) {
         stats.close_accuracy = self.close_vision.max(stats.close_accuracy);
@@ -53,12 +322,30 @@ impl BodyPartMeta for HeadMeta {
     }
 }
 
-#[derive(Clone)]
-struct LegMeta {
+#[derive(Clone, Serialize, Deserialize)]
+pub struct LegMeta {
     max_speed: f32,
     jump_force: f32,
 }
 
+impl LegMeta {
+    pub fn new(max_speed: f32, jump_force: f32) -> Self {
+        Self {
+            max_speed,
+            jump_force,
+        }
+    }
+
+    // The body walks as fast as its slowest leg.
+    pub fn max_speed(&self) -> f32 {
+        self.max_speed
+    }
+
+    pub fn jump_force(&self) -> f32 {
+        self.jump_force
+    }
+}
+
 impl BodyPartMeta for LegMeta {
     fn add_to_stats(&self, stats: &mut Stats) {
         stats.speed = self.max_speed.min(stats.speed);
@@ -66,122 +353,268 @@ impl BodyPartMeta for LegMeta {
     }
 }
 
-#[derive(Clone)]
-struct TorsoMeta {
+#[derive(Clone, Serialize, Deserialize)]
+pub struct TorsoMeta {
     arm_slots: usize,
     leg_slots: usize,
+    // A Modular Chassis, it can swap an arm mid-battle, see `limb_swap`.
+    #[serde(default)]
+    modular: bool,
+}
+
+impl TorsoMeta {
+    pub fn new(arm_slots: usize, leg_slots: usize) -> Self {
+        Self {
+            arm_slots,
+            leg_slots,
+            modular: false,
+        }
+    }
+
+    pub fn with_modular(mut self, modular: bool) -> Self {
+        self.modular = modular;
+        self
+    }
+
+    pub fn modular(&self) -> bool {
+        self.modular
+    }
+
+    pub fn arm_slots(&self) -> usize {
+        self.arm_slots
+    }
+
+    pub fn leg_slots(&self) -> usize {
+        self.leg_slots
+    }
 }
 
 impl BodyPartMeta for TorsoMeta {
-    fn add_to_stats(&self, stats: &mut Stats) {}
+    fn add_to_stats(&self, stats: &mut Stats) {
+        if self.modular {
+            stats.skills.push(Skill::SwapLimb);
+        }
+    }
 }
 
-type Torso = BodyPart<TorsoMeta>;
+pub type Torso = BodyPart<TorsoMeta>;
+
+pub type Head = BodyPart<HeadMeta>;
 
-type Head = BodyPart<HeadMeta>;
+pub type Arm = BodyPart<ArmMeta>;
 
-type Arm = BodyPart<()>;
+impl Arm {
+    // This arm in slot `left` and a copy of it in the slot after. Their
+    // abilities get told apart by name as well as limb.
+    pub fn mirrored(self, left: u8) -> [Arm; 2] {
+        let mut pair = [self.clone(), self];
+        for (slot, (arm, side)) in pair.iter_mut().zip(["Left", "Right"]).enumerate() {
+            arm.slot = left + slot as u8;
+            for skill in &mut arm.stats.skills {
+                if let Some(ability) = skill.ability_mut() {
+                    ability.name = format!("{} {}", side, ability.name);
+                    ability.limb = Limb::Arm(left + slot as u8);
+                }
+            }
+        }
+        pair
+    }
+}
 
-type Leg = BodyPart<LegMeta>;
+pub type Leg = BodyPart<LegMeta>;
 
-#[derive(Component)]
+// Arms and legs are kept in slot order, with gaps where one was lost.
+#[derive(Component, Clone, Serialize, Deserialize)]
+#[serde(from = "BodyData")]
 pub struct Body {
     torso: Torso,
     head: Head,
     arms: Vec<Arm>,
     legs: Vec<Leg>,
+    // Given by the player, the torso's generated name is shown otherwise.
+    #[serde(default)]
+    name: Option<String>,
+}
+
+// A body as it's saved, see `From<BodyData> for Body`.
+#[derive(Deserialize)]
+struct BodyData {
+    torso: Torso,
+    head: Head,
+    arms: Vec<Arm>,
+    legs: Vec<Leg>,
+    #[serde(default)]
+    name: Option<String>,
+}
+
+// Profiles from before limbs kept their slot have every limb in slot 0, they
+// go back into the slots they were in by their order.
+impl From<BodyData> for Body {
+    fn from(data: BodyData) -> Self {
+        let mut body = Body {
+            torso: data.torso,
+            head: data.head,
+            arms: data.arms,
+            legs: data.legs,
+            name: data.name,
+        };
+        if misplaced(&body.arms, usize::MAX).is_some() {
+            for (index, arm) in body.arms.iter_mut().enumerate() {
+                bind_to_slot(arm, index as u8);
+            }
+        }
+        if misplaced(&body.legs, usize::MAX).is_some() {
+            for (index, leg) in body.legs.iter_mut().enumerate() {
+                leg.slot = index as u8;
+            }
+        }
+        body
+    }
 }
 
+// The first limb outside of `slots` or in the same slot as one before it.
+fn misplaced<M: BodyPartMeta>(parts: &[BodyPart<M>], slots: usize) -> Option<&BodyPart<M>> {
+    parts.iter().enumerate().find_map(|(i, part)| {
+        let taken = parts[..i].iter().any(|other| other.slot == part.slot);
+        (taken || part.slot as usize >= slots).then_some(part)
+    })
+}
+
+pub const MAX_NAME_LENGTH: usize = 24;
+// Hips stand this high off the ground plus this much per unit of leg size,
+// a default leg of size 1 puts them at 0.7.
+const HIP_BASE: f32 = 0.35;
+const LEG_LENGTH: f32 = 0.35;
+
+// The starter body, kept with the assets so designers can tune it.
+const STARTER: &str = include_str!("../assets/parts/starter.body.ron");
+
 impl Default for Body {
     fn default() -> Self {
-        let material = Material::Rust;
-        let color = material.color();
-        let create_arm = |index| {
-            let skills = vec![Skill::BasicMelee(Ability {
-                meta: 5.0,
-                time: 1.0,
-                cooldown: 0.2,
-                energy_cost: 3.0,
-                limb: Limb::Arm(index),
-                name: "Jab".to_string(),
-            })];
-            Arm {
-                name: "Typical Rusty Arm - V0".to_string(),
-                stats: PartStats {
-                    skills,
-                    material,
-                    weight: 16.0,
-                    health: 1.0,
-                    energy: -2.0,
-                    size: 1.0,
-                    color,
-                },
-                meta: (),
+        ron::from_str::<Body>(STARTER).expect("the starter body parses")
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum BodyError {
+    MissingTorso,
+    MissingHead,
+    TooManyArms { slots: usize, found: usize },
+    WrongLegCount { slots: usize, found: usize },
+    // Outside the torso's slots or sharing one with another limb.
+    BadSlot(Limb),
+}
+
+// Meant for whoever assembles the body, players only see share code errors.
+impl std::fmt::Display for BodyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BodyError::MissingTorso => write!(f, "the body has no torso"),
+            BodyError::MissingHead => write!(f, "the body has no head"),
+            BodyError::TooManyArms { slots, found } => {
+                write!(
+                    f,
+                    "{} arms don't fit a torso with {} arm slots",
+                    found, slots
+                )
             }
+            BodyError::WrongLegCount { slots, found } => write!(
+                f,
+                "{} legs don't fill a torso with {} leg slots, at least 2 are needed",
+                found, slots
+            ),
+            BodyError::BadSlot(limb) => write!(f, "{:?} doesn't have a slot of its own", limb),
+        }
+    }
+}
+
+// Assembles a body part by part, `build` checks that everything fits.
+#[derive(Default)]
+pub struct BodyBuilder {
+    torso: Option<Torso>,
+    head: Option<Head>,
+    arms: Vec<Arm>,
+    legs: Vec<Leg>,
+    name: Option<String>,
+}
+
+impl BodyBuilder {
+    pub fn torso(mut self, torso: Torso) -> Self {
+        self.torso = Some(torso);
+        self
+    }
+
+    pub fn head(mut self, head: Head) -> Self {
+        self.head = Some(head);
+        self
+    }
+
+    // Arms fill the torso's slots in order, their attacks are bound to the
+    // slot they end up in.
+    pub fn arm(mut self, arm: Arm) -> Self {
+        self.arms.push(arm);
+        self
+    }
+
+    pub fn leg(mut self, leg: Leg) -> Self {
+        self.legs.push(leg);
+        self
+    }
+
+    pub fn name(mut self, name: &str) -> Self {
+        self.name = Some(name.to_string());
+        self
+    }
+
+    pub fn build(self) -> Result<Body, BodyError> {
+        let mut body = Body {
+            torso: self.torso.ok_or(BodyError::MissingTorso)?,
+            head: self.head.ok_or(BodyError::MissingHead)?,
+            arms: self.arms,
+            legs: self.legs,
+            name: None,
         };
-        let leg = Leg {
-            name: "Normal Rusty Leg - V0".to_string(),
-            stats: PartStats {
-                skills: vec![Skill::WalkForward, Skill::WalkBackward],
-                material,
-                weight: 26.0,
-                health: 5.0,
-                energy: -2.0,
-                size: 1.0,
-                color,
-            },
-            meta: LegMeta {
-                max_speed: 5.0,
-                jump_force: 15.0,
-            },
-        };
-        Self {
-            torso: Torso {
-                name: "Basic Rusty Torso - V0".to_string(),
-                stats: PartStats {
-                    skills: vec![],
-                    material,
-                    weight: 50.0,
-                    health: 10.0,
-                    energy: -12.0,
-                    size: 1.0,
-                    color,
-                },
-                meta: TorsoMeta {
-                    arm_slots: 2,
-                    leg_slots: 2,
-                },
-            },
-            head: Head {
-                name: "Ordinary Rusty Head - V0".to_string(),
-                stats: PartStats {
-                    skills: vec![],
-                    material,
-                    weight: 12.0,
-                    health: 2.0,
-                    energy: -4.0,
-                    size: 1.0,
-                    color,
-                },
-                meta: HeadMeta {
-                    refresh_rate: 1.0,
-                    far_vision: 1.0,
-                    close_vision: 1.0,
-                },
-            },
-            arms: vec![create_arm(0), create_arm(1)],
-            legs: vec![leg; 2],
+        for (index, arm) in body.arms.iter_mut().enumerate() {
+            bind_to_slot(arm, index as u8);
+        }
+        for (index, leg) in body.legs.iter_mut().enumerate() {
+            leg.slot = index as u8;
+        }
+        if let Some(name) = self.name {
+            body.set_name(&name);
+        }
+        body.validate()?;
+        Ok(body)
+    }
+}
+
+fn bind_to_slot(arm: &mut Arm, slot: u8) {
+    arm.slot = slot;
+    for skill in &mut arm.stats.skills {
+        if let Some(ability) = skill.ability_mut() {
+            ability.limb = Limb::Arm(slot);
         }
     }
 }
 
-#[derive(Debug, Clone, Copy, Component)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Component, Serialize, Deserialize)]
 pub enum Limb {
     Arm(u8),
     Leg(u8),
 }
 
-#[derive(Debug, Clone)]
+impl Limb {
+    // Short label like A0 or L1 for skill buttons.
+    pub fn badge(&self) -> String {
+        match self {
+            Limb::Arm(i) => t!("limb.arm_badge", index = *i),
+            Limb::Leg(i) => t!("limb.leg_badge", index = *i),
+        }
+    }
+}
+
+#[allow(dead_code)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Ability<T> {
     pub meta: T,
     pub time: f32,
@@ -189,15 +622,38 @@ pub struct Ability<T> {
     pub energy_cost: f32,
     pub limb: Limb,
     pub name: String,
+    // Heavy attacks that can knock the target out of its own animation.
+    #[serde(default)]
+    pub interrupting: bool,
+    // Shots a ranged skill has before it needs a reload, None for anything
+    // that doesn't use ammo.
+    #[serde(default)]
+    pub ammo: Option<u8>,
+    // Hits twice as hard on an overshield.
+    #[serde(default)]
+    pub emp: bool,
+}
+
+// Names a skill across body rebuilds, saves and the network, see `Skill::id`.
+// Serialized as the plain string so stats saved before it existed still load.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct SkillId(String);
+
+impl SkillId {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
 }
 
-impl<T> PartialEq for Ability<T> {
-    fn eq(&self, other: &Self) -> bool {
-        false
+impl fmt::Display for SkillId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
     }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[allow(dead_code)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum Skill {
     WalkBackward,
     WalkForward,
@@ -205,37 +661,150 @@ pub enum Skill {
     BasicMelee(Ability<f32>),
     BasicRanged(Ability<f32>),
     Scan(Ability<f32>),
+    // Three heavy blows in a row, only found on boss parts.
+    Slam(Ability<f32>),
+    // Refills every ranged skill, bodies get it along with their first one.
+    Reload,
+    // A quick burst forward on its leg, `meta` is the share of the body's
+    // speed it goes at.
+    Dash(Ability<f32>),
+    // Puts the spare arm on in place of one, only a Modular Chassis has it.
+    SwapLimb,
 }
 
 impl Skill {
-    pub fn get_name(&self) -> &str {
+    // Ability names are generated proper nouns and stay untranslated.
+    pub fn get_name(&self) -> String {
         match self {
-            Skill::WalkBackward => "Walk backward",
-            Skill::WalkForward => "Walk forward",
-            Skill::TurnAround => "Turn around",
-            Skill::BasicMelee(a) | Skill::BasicRanged(a) | Skill::Scan(a) => &a.name,
+            Skill::WalkBackward => t!("skill.walk_backward"),
+            Skill::WalkForward => t!("skill.walk_forward"),
+            Skill::TurnAround => t!("skill.turn_around"),
+            Skill::Reload => t!("skill.reload"),
+            Skill::SwapLimb => t!("skill.swap_limb"),
+            Skill::BasicMelee(a)
+            | Skill::BasicRanged(a)
+            | Skill::Scan(a)
+            | Skill::Slam(a)
+            | Skill::Dash(a) => a.name.clone(),
         }
     }
 
-    fn order(&self) -> usize {
+    // Category the skill bar groups by, movement first.
+    pub fn order(&self) -> usize {
         match self {
             Skill::WalkBackward => 0,
             Skill::WalkForward => 1,
-            Skill::TurnAround => 2,
-            Skill::BasicMelee(_) => 3,
-            Skill::BasicRanged(_) => 3,
-            Skill::Scan(_) => 4,
+            // Apart from the walks, so the legs' walks still come together
+            // to be deduplicated.
+            Skill::Dash(_) => 2,
+            Skill::TurnAround => 3,
+            Skill::BasicMelee(_) => 4,
+            Skill::BasicRanged(_) => 4,
+            Skill::Slam(_) => 4,
+            Skill::Reload => 4,
+            Skill::Scan(_) => 5,
+            Skill::SwapLimb => 5,
+        }
+    }
+
+    // Stays the same as long as the skill's limb, name and kind do, so it can
+    // key persistent statistics. The numbers are left out, upgrades and
+    // attachments change them and it's still the same skill.
+    pub fn id(&self) -> SkillId {
+        let id = match self {
+            Skill::WalkBackward => "walk_backward".to_string(),
+            Skill::WalkForward => "walk_forward".to_string(),
+            Skill::TurnAround => "turn_around".to_string(),
+            Skill::Reload => "reload".to_string(),
+            Skill::SwapLimb => "swap_limb".to_string(),
+            Skill::BasicMelee(a)
+            | Skill::BasicRanged(a)
+            | Skill::Scan(a)
+            | Skill::Slam(a)
+            | Skill::Dash(a) => {
+                let kind = match self {
+                    Skill::BasicMelee(_) => "melee",
+                    Skill::BasicRanged(_) => "ranged",
+                    Skill::Scan(_) => "scan",
+                    Skill::Dash(_) => "dash",
+                    _ => "slam",
+                };
+                format!("{:?}:{}:{}", a.limb, a.name, kind)
+            }
+        };
+        SkillId(id)
+    }
+
+    // The points in the animation where the skill deals damage.
+    pub fn hits(&self) -> Option<(&Ability<f32>, &'static [f32])> {
+        match self {
+            Skill::BasicMelee(ability) => Some((ability, &[0.5])),
+            Skill::Slam(ability) => Some((ability, &[0.25, 0.5, 0.75])),
+            _ => None,
+        }
+    }
+
+    // The point in the animation a ranged skill lets its shot go.
+    pub fn release(&self) -> Option<(&Ability<f32>, f32)> {
+        match self {
+            Skill::BasicRanged(ability) => Some((ability, 0.5)),
+            _ => None,
+        }
+    }
+
+    // How fast each walk goes is tuned in `BalanceConfig::walk_multiplier`.
+    pub fn walks(&self) -> bool {
+        matches!(
+            self,
+            Skill::WalkBackward | Skill::WalkForward | Skill::Dash(_)
+        )
+    }
+
+    pub fn ability(&self) -> Option<&Ability<f32>> {
+        match self {
+            Skill::BasicMelee(a)
+            | Skill::BasicRanged(a)
+            | Skill::Scan(a)
+            | Skill::Slam(a)
+            | Skill::Dash(a) => Some(a),
+            _ => None,
+        }
+    }
+
+    pub fn ability_mut(&mut self) -> Option<&mut Ability<f32>> {
+        match self {
+            Skill::BasicMelee(a)
+            | Skill::BasicRanged(a)
+            | Skill::Scan(a)
+            | Skill::Slam(a)
+            | Skill::Dash(a) => Some(a),
+            _ => None,
         }
     }
 }
 
-#[derive(Component, Default, Debug)]
+// Where an arm hangs, from the body's front edge when facing right, and how
+// long and thick it is, see `Stats::melee_reach` and `Stats::guard`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ArmReach {
+    pub slot: u8,
+    pub shoulder: f32,
+    pub length: f32,
+    pub width: f32,
+}
+
+#[derive(Component, Clone, Default, Debug)]
 pub struct Stats {
     pub health: f32,
     pub energy: f32,
 
     pub max_health: f32,
     pub max_energy: f32,
+    pub overshield: f32,
+    pub max_overshield: f32,
+    // Energy the overshield parts draw, it sets how fast the shield comes
+    // back, see `shield::recharge_per_second`.
+    pub overshield_draw: f32,
     pub weight: f32,
     pub width: f32,
     pub speed: f32,
@@ -244,19 +813,166 @@ pub struct Stats {
     pub far_accuracy: f32,
     pub jump_force: f32,
     pub skills: Vec<Skill>,
+    // Extra energy regen and poise as a share of the usual, from material set
+    // bonuses, see `affinity`.
+    pub regen_bonus: f32,
+    pub poise_bonus: f32,
+    // Every arm's shoulder and size, what melee reach comes from.
+    pub arm_reach: Vec<ArmReach>,
+    // What `bounds::finalize` had to hold back, for the debug overlay and
+    // tooltips.
+    pub capped: Vec<Capped>,
 }
 
 impl Stats {
+    // Looks a skill up by its `Skill::id`, which survives the list being
+    // rebuilt or reordered where an index wouldn't.
+    pub fn skill(&self, id: &SkillId) -> Option<&Skill> {
+        self.skills.iter().find(|skill| skill.id() == *id)
+    }
+
+    pub fn class(&self) -> WeightClass {
+        WeightClass::from_weight(self.weight)
+    }
+
+    // How far past the body's front edge the tip of the striking arm gets at
+    // the skill's furthest hit. The arm is posed the way the animation poses
+    // it, hits land where that tip ends up. None for skills that don't hit.
+    pub fn melee_reach(&self, skill: &Skill) -> Option<f32> {
+        let (ability, hits) = skill.hits()?;
+        let Limb::Arm(slot) = ability.limb else {
+            return None;
+        };
+        let arm = self.arm_reach.iter().find(|arm| arm.slot == slot)?;
+        hits.iter()
+            .filter_map(|hit| {
+                let (_, pose) = battle::skill_pose(skill, self, *hit)
+                    .into_iter()
+                    .find(|(limb, _)| *limb == ability.limb)?;
+                Some(arm.shoulder + (pose * Vec3::new(0.0, -arm.length, 0.0)).x)
+            })
+            .reduce(f32::max)
+    }
+
+    // The furthest any of the body's melee skills reaches.
+    pub fn reach(&self) -> Option<f32> {
+        self.skills
+            .iter()
+            .filter_map(|skill| self.melee_reach(skill))
+            .reduce(f32::max)
+    }
+
+    // How far the arms on one side hang out past the body's edge. Odd slots
+    // are on the front, a blow from that side lands on them first.
+    pub fn guard(&self, front: bool) -> f32 {
+        self.arm_reach
+            .iter()
+            .filter(|arm| (arm.slot % 2 == 1) == front)
+            .map(|arm| arm.width)
+            .fold(0.0, f32::max)
+    }
+
+    // Where the skill sits in the list right now, only good until the body
+    // changes.
+    pub fn skill_index(&self, id: &SkillId) -> Option<usize> {
+        self.skills.iter().position(|skill| skill.id() == *id)
+    }
+
     fn add_part_stats(&mut self, part_stats: &PartStats) {
         self.max_health += part_stats.health;
         self.max_energy += part_stats.energy;
+        if part_stats.overshield > 0.0 {
+            self.max_overshield += part_stats.overshield;
+            self.overshield_draw += part_stats.energy.abs();
+        }
 
         self.weight += part_stats.weight;
         self.skills.extend(part_stats.skills.iter().cloned());
     }
 }
 
-#[derive(Copy, Clone)]
+// Bodies are matched against others in the same class, see
+// `random_body_in`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum WeightClass {
+    Feather,
+    Middle,
+    Heavy,
+}
+
+impl WeightClass {
+    pub const ALL: [WeightClass; 3] = [
+        WeightClass::Feather,
+        WeightClass::Middle,
+        WeightClass::Heavy,
+    ];
+    // Where each class ends and the next one starts.
+    const FEATHER_LIMIT: f32 = 150.0;
+    const MIDDLE_LIMIT: f32 = 400.0;
+    // How far inside a class a fitted body lands, so it doesn't sit on the
+    // edge of the next one.
+    const FIT_MARGIN: f32 = 0.05;
+
+    pub fn from_weight(weight: f32) -> Self {
+        if weight < Self::FEATHER_LIMIT {
+            WeightClass::Feather
+        } else if weight < Self::MIDDLE_LIMIT {
+            WeightClass::Middle
+        } else {
+            WeightClass::Heavy
+        }
+    }
+
+    // The closest weight to `weight` that's in the class.
+    fn fit(self, weight: f32) -> f32 {
+        if WeightClass::from_weight(weight) == self {
+            return weight;
+        }
+        let (low, high) = match self {
+            WeightClass::Feather => (0.0, Self::FEATHER_LIMIT),
+            WeightClass::Middle => (Self::FEATHER_LIMIT, Self::MIDDLE_LIMIT),
+            WeightClass::Heavy => (Self::MIDDLE_LIMIT, f32::INFINITY),
+        };
+        weight.clamp(
+            low * (1.0 + Self::FIT_MARGIN),
+            high * (1.0 - Self::FIT_MARGIN),
+        )
+    }
+
+    pub fn name(self) -> String {
+        t!(match self {
+            WeightClass::Feather => "weight_class.feather",
+            WeightClass::Middle => "weight_class.middle",
+            WeightClass::Heavy => "weight_class.heavy",
+        })
+    }
+
+    pub fn color(self) -> Color {
+        match self {
+            WeightClass::Feather => Color::rgb(0.55, 0.85, 1.0),
+            WeightClass::Middle => Color::rgb(0.95, 0.85, 0.4),
+            WeightClass::Heavy => Color::rgb(1.0, 0.55, 0.35),
+        }
+    }
+}
+
+impl FromStr for WeightClass {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "feather" => Ok(WeightClass::Feather),
+            "middle" => Ok(WeightClass::Middle),
+            "heavy" => Ok(WeightClass::Heavy),
+            _ => Err(format!(
+                "unknown weight class `{}`, expected feather, middle or heavy",
+                s
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub enum Material {
     Wood,
     Stone,
@@ -321,22 +1037,113 @@ impl Material {
         }
     }
 
-    fn color(&self) -> Color {
+    pub fn color(&self, palette: PaletteMode) -> Color {
+        match palette {
+            PaletteMode::Default => match self {
+                Material::Wood => Color::rgb_u8(202, 164, 114),
+                Material::Stone => Color::rgb_u8(136, 140, 141),
+                Material::Plastic => Color::rgb_u8(228, 200, 98),
+                Material::Bronze => Color::rgb_u8(205, 127, 50),
+                Material::Aluminum => Color::rgb_u8(208, 213, 219),
+                Material::Steel => Color::rgb_u8(122, 127, 128),
+                Material::Carbon => Color::rgb_u8(13, 17, 21),
+                Material::Rust => Color::rgb_u8(183, 65, 14),
+            },
+            // Okabe-Ito colors, which stay apart for red-green color blindness.
+            PaletteMode::Deuteranopia => match self {
+                Material::Wood => Color::rgb_u8(230, 159, 0),
+                Material::Stone => Color::rgb_u8(153, 153, 153),
+                Material::Plastic => Color::rgb_u8(240, 228, 66),
+                Material::Bronze => Color::rgb_u8(213, 94, 0),
+                Material::Aluminum => Color::rgb_u8(86, 180, 233),
+                Material::Steel => Color::rgb_u8(0, 114, 178),
+                Material::Carbon => Color::rgb_u8(204, 121, 167),
+                Material::Rust => Color::rgb_u8(0, 158, 115),
+            },
+            PaletteMode::HighContrast => match self {
+                Material::Wood => Color::rgb_u8(255, 170, 0),
+                Material::Stone => Color::rgb_u8(255, 255, 255),
+                Material::Plastic => Color::rgb_u8(255, 255, 0),
+                Material::Bronze => Color::rgb_u8(255, 60, 0),
+                Material::Aluminum => Color::rgb_u8(0, 255, 255),
+                Material::Steel => Color::rgb_u8(40, 90, 255),
+                Material::Carbon => Color::rgb_u8(255, 0, 255),
+                Material::Rust => Color::rgb_u8(0, 255, 0),
+            },
+        }
+    }
+
+    pub fn name(&self) -> String {
+        t!(match self {
+            Material::Wood => "material.wood",
+            Material::Stone => "material.stone",
+            Material::Plastic => "material.plastic",
+            Material::Bronze => "material.bronze",
+            Material::Aluminum => "material.aluminum",
+            Material::Steel => "material.steel",
+            Material::Carbon => "material.carbon",
+            Material::Rust => "material.rust",
+        })
+    }
+
+    // Drawn next to the material so it never depends on color alone.
+    pub fn pattern(&self) -> &'static str {
         match self {
-            Material::Wood => Color::rgb_u8(202, 164, 114),
-            Material::Stone => Color::rgb_u8(136, 140, 141),
-            Material::Plastic => Color::rgb_u8(228, 200, 98),
-            Material::Bronze => Color::rgb_u8(205, 127, 50),
-            Material::Aluminum => Color::rgb_u8(208, 213, 219),
-            Material::Steel => Color::rgb_u8(122, 127, 128),
-            Material::Carbon => Color::rgb_u8(13, 17, 21),
-            Material::Rust => Color::rgb_u8(183, 65, 14),
+            Material::Wood => "===",
+            Material::Stone => ": :",
+            Material::Plastic => "ooo",
+            Material::Bronze => "///",
+            Material::Aluminum => "###",
+            Material::Steel => "|||",
+            Material::Carbon => "xxx",
+            Material::Rust => "~~~",
         }
     }
 }
 
-const ADJECTIVES: &[&str] = &[
-    "Rad",
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum PaletteMode {
+    #[default]
+    Default,
+    Deuteranopia,
+    HighContrast,
+}
+
+impl PaletteMode {
+    pub const ALL: &[PaletteMode] = &[
+        PaletteMode::Default,
+        PaletteMode::Deuteranopia,
+        PaletteMode::HighContrast,
+    ];
+
+    pub fn name(&self) -> String {
+        t!(match self {
+            PaletteMode::Default => "palette.default",
+            PaletteMode::Deuteranopia => "palette.deuteranopia",
+            PaletteMode::HighContrast => "palette.high_contrast",
+        })
+    }
+}
+
+// Tags every part sprite so the palette can re-tint it in place.
+#[derive(Component, Clone, Copy)]
+pub struct PartMaterial {
+    pub material: Material,
+    // The part's own color, shown with the default palette.
+    pub color: Color,
+}
+
+impl PartMaterial {
+    pub fn color(&self, palette: PaletteMode) -> Color {
+        match palette {
+            PaletteMode::Default => self.color,
+            _ => self.material.color(palette),
+        }
+    }
+}
+
+const ADJECTIVES: &[&str] = &[
+    "Rad",
     "Cool",
     "Examplar",
     "Energetic",
@@ -408,14 +1215,55 @@ fn gen_name(rng: &mut impl Rng, part_name: &str) -> String {
     name
 }
 
+// Every part is this size or in between.
+const PART_SIZES: RangeInclusive<f32> = 0.5..=2.0;
+// Heads are drawn this much of the torso's height, limbs about as long or
+// thick as the torso is.
+const HEAD_RATIO: RangeInclusive<f32> = 0.3..=0.7;
+const LIMB_RATIO: RangeInclusive<f32> = 0.75..=1.25;
+
+// The part sizes of a body, rolled before anything else so the parts fit
+// together. Every arm and every leg gets the same size.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Silhouette {
+    pub torso: f32,
+    pub head: f32,
+    pub arm: f32,
+    pub leg: f32,
+}
+
+impl Silhouette {
+    // Kept within PART_SIZES, which can only pull a head towards the middle
+    // of HEAD_RATIO.
+    pub fn roll(rng: &mut impl Rng) -> Self {
+        let torso = rng.gen_range(PART_SIZES);
+        let fit = |size: f32| size.clamp(*PART_SIZES.start(), *PART_SIZES.end());
+        // The head sprite is half its size tall, the torso its full size.
+        let head = fit(torso * rng.gen_range(HEAD_RATIO) * 2.0);
+        let leg = fit(torso * rng.gen_range(LIMB_RATIO));
+        let arm = fit(torso * rng.gen_range(LIMB_RATIO));
+        Self {
+            torso,
+            head,
+            arm,
+            leg,
+        }
+    }
+}
+
+// Weight is the size times the material's density scaled by `density_range`,
+// and health and energy are the material's base scaled by 0.2^0.3 to 5^0.3,
+// the square root of the size and the multiplier. Without a size from a
+// silhouette one is rolled from PART_SIZES.
 fn randomize_part(
     rng: &mut impl Rng,
+    size: Option<f32>,
     skills: Vec<Skill>,
     density_range: RangeInclusive<f32>,
     hp_mul: f32,
     energy_mul: f32,
 ) -> PartStats {
-    let size = rng.gen_range(0.5..=2.0);
+    let size = size.unwrap_or_else(|| rng.gen_range(PART_SIZES));
     let material = Material::choose(rng);
     let density = material.density() * rng.gen_range(density_range);
     let weight = size * density;
@@ -425,7 +1273,7 @@ fn randomize_part(
     let energy =
         material.base_energy() * rng.gen_range(0.2..=5.0f32).powf(0.3) * size.sqrt() * energy_mul;
 
-    let color = randomize_color(material.color(), rng, 0.04);
+    let color = randomize_color(material.color(PaletteMode::Default), rng, 0.04);
 
     PartStats {
         skills,
@@ -433,43 +1281,91 @@ fn randomize_part(
         weight,
         health,
         energy,
+        overshield: 0.0,
         size,
         color,
+        low_battles: 0,
+        decay: 0,
     }
 }
 
-fn random_head(rng: &mut impl Rng) -> Head {
+// Rare on heads and torsos. The shield is paid for out of the part, it keeps
+// half its health and its energy becomes a drain.
+const OVERSHIELD_CHANCE: f64 = 0.05;
+
+fn roll_overshield(rng: &mut impl Rng, stats: &mut PartStats) {
+    if rng.gen_bool(OVERSHIELD_CHANCE) {
+        stats.overshield = stats.health * rng.gen_range(0.6..=1.0);
+        stats.health *= 0.5;
+        stats.energy = -stats.energy.abs();
+    }
+}
+
+fn random_head(rng: &mut impl Rng, size: Option<f32>) -> Head {
     let part_name = ["head", "skull", "noggin"].choose(rng).unwrap();
+    let name = gen_name(rng, part_name);
+    let mut stats = randomize_part(rng, size, vec![], 0.6..=1.0, 0.1, 0.3);
+    roll_overshield(rng, &mut stats);
     Head {
-        name: gen_name(rng, part_name),
-        stats: randomize_part(rng, vec![], 0.6..=1.0, 0.1, 0.3),
+        name,
+        stats,
         meta: HeadMeta {
             refresh_rate: rng.gen_range(0.1..=1.0f32).powi(2),
             close_vision: rng.gen_range(0.1..=1.0f32).powi(2),
             far_vision: rng.gen_range(0.1..=1.0f32).powi(2),
         },
+        tier: 0,
+        slot: 0,
     }
 }
 
-fn random_arm(rng: &mut impl Rng, i: u8) -> Arm {
-    let skills = vec![Skill::BasicMelee(Ability {
-        meta: rng.gen_range(100.0..=1000.0f32).sqrt(),
+const HEAVY_HIT: f32 = 25.0;
+const RANGED_ARM_CHANCE: f64 = 0.25;
+const EMP_CHANCE: f64 = 0.1;
+
+pub fn random_arm(rng: &mut impl Rng, i: u8) -> Arm {
+    random_arm_sized(rng, i, None)
+}
+
+fn random_arm_sized(rng: &mut impl Rng, i: u8, size: Option<f32>) -> Arm {
+    let meta = rng.gen_range(100.0..=1000.0f32).sqrt();
+    let mut ability = Ability {
+        meta,
         time: rng.gen_range(0.5..=1.5),
         cooldown: rng.gen_range(0.0..=0.5f32).powi(2),
         energy_cost: rng.gen_range(1.0..=4.0f32).powi(2),
         limb: Limb::Arm(i),
         name: "Jab".to_string(),
-    })];
+        interrupting: meta >= HEAVY_HIT,
+        ammo: None,
+        emp: rng.gen_bool(EMP_CHANCE),
+    };
+    let skills = if rng.gen_bool(RANGED_ARM_CHANCE) {
+        ability.name = "Shot".to_string();
+        ability.interrupting = false;
+        ability.ammo = Some(rng.gen_range(3..=8));
+        if ability.emp {
+            ability.name = "Pulse".to_string();
+        }
+        vec![Skill::BasicRanged(ability)]
+    } else {
+        if ability.emp {
+            ability.name = "Zap".to_string();
+        }
+        vec![Skill::BasicMelee(ability)]
+    };
 
     let part_name = ["arm", "grabber", "limb"].choose(rng).unwrap();
     Arm {
         name: gen_name(rng, part_name),
-        stats: randomize_part(rng, skills, 0.6..=1.0, 0.1, 0.3),
-        meta: (),
+        stats: randomize_part(rng, size, skills, 0.6..=1.0, 0.1, 0.3),
+        meta: ArmMeta::default(),
+        tier: 0,
+        slot: i,
     }
 }
 
-fn random_leg(rng: &mut impl Rng) -> Leg {
+fn random_leg(rng: &mut impl Rng, size: Option<f32>) -> Leg {
     let mut skills = vec![Skill::WalkForward, Skill::TurnAround];
 
     if rng.gen_bool(0.95) {
@@ -479,11 +1375,13 @@ fn random_leg(rng: &mut impl Rng) -> Leg {
     let part_name = ["leg", "thigh", "walker"].choose(rng).unwrap();
     Leg {
         name: gen_name(rng, part_name),
-        stats: randomize_part(rng, skills, 0.6..=1.0, 0.3, 0.7),
+        stats: randomize_part(rng, size, skills, 0.6..=1.0, 0.3, 0.7),
         meta: LegMeta {
             max_speed: rng.gen_range(0.2..=5.0f32).powf(0.2) * rng.gen_range(5.0..=15.0),
             jump_force: rng.gen_range(0.2..=5.0f32).powf(0.2) * rng.gen_range(20.0..=25.0),
         },
+        tier: 0,
+        slot: 0,
     }
 }
 
@@ -495,136 +1393,960 @@ fn randomize_color(color: Color, rng: &mut impl Rng, amount: f32) -> Color {
     Color::rgb(i.next().unwrap(), i.next().unwrap(), i.next().unwrap())
 }
 
-fn random_torso(rng: &mut impl Rng) -> Torso {
+fn random_torso(rng: &mut impl Rng, size: Option<f32>) -> Torso {
     let part_name = ["torso", "body", "trunk", "thorax", "midsection"]
         .choose(rng)
         .unwrap();
+    let name = gen_name(rng, part_name);
+    let mut stats = randomize_part(rng, size, vec![], 0.8..=1.2, 1.0, 1.0);
+    roll_overshield(rng, &mut stats);
 
     Torso {
-        name: gen_name(rng, part_name),
-        stats: randomize_part(rng, vec![], 0.8..=1.2, 1.0, 1.0),
-        meta: TorsoMeta {
+        name,
+        stats,
+        meta: TorsoMeta::new(2, 2),
+        tier: 0,
+        slot: 0,
+    }
+}
+
+// How random bodies are rolled.
+#[derive(Clone, Debug)]
+pub struct GenerationConfig {
+    pub arm_slots: usize,
+    pub leg_slots: usize,
+    // Rolls an even number of arms as mirrored pairs, so a body doesn't end up
+    // with one great arm and one useless one.
+    pub symmetric_arms: bool,
+    // Chance a pair is rolled as two unrelated arms anyway.
+    pub asymmetry_chance: f64,
+    // Rerolls until the body lands in this class, see `fit_class`.
+    pub class: Option<WeightClass>,
+    // Rolls a `Silhouette` first so the parts fit together. Off, every part's
+    // size is rolled on its own, tiny torsos on stilts and all.
+    pub proportional: bool,
+}
+
+impl Default for GenerationConfig {
+    fn default() -> Self {
+        Self {
             arm_slots: 2,
             leg_slots: 2,
-        },
+            symmetric_arms: true,
+            asymmetry_chance: 0.25,
+            class: None,
+            proportional: true,
+        }
     }
 }
 
 pub fn random_body(rng: &mut impl Rng) -> Body {
-    let torso = random_torso(rng);
-    let head = random_head(rng);
+    random_body_with(rng, &GenerationConfig::default())
+}
+
+pub fn random_body_with_slots(rng: &mut impl Rng, arm_slots: usize, leg_slots: usize) -> Body {
+    random_body_with(
+        rng,
+        &GenerationConfig {
+            arm_slots,
+            leg_slots,
+            ..default()
+        },
+    )
+}
+
+pub fn random_body_in(rng: &mut impl Rng, class: WeightClass) -> Body {
+    random_body_with(
+        rng,
+        &GenerationConfig {
+            class: Some(class),
+            ..default()
+        },
+    )
+}
+
+// Rolls this many bodies looking for one in the class before settling for
+// the closest.
+const CLASS_ROLLS: usize = 16;
+
+pub fn random_body_with(rng: &mut impl Rng, config: &GenerationConfig) -> Body {
+    let Some(class) = config.class else {
+        return roll_body(rng, config);
+    };
+    let mut closest: Option<(f32, Body)> = None;
+    for _ in 0..CLASS_ROLLS {
+        let body = roll_body(rng, config);
+        let weight = compute_stats(&body).weight;
+        let miss = (class.fit(weight) - weight).abs();
+        if miss == 0.0 {
+            return body;
+        }
+        if closest.as_ref().is_none_or(|(closest, _)| miss < *closest) {
+            closest = Some((miss, body));
+        }
+    }
+    let mut body = closest.map(|(_, body)| body).unwrap();
+    body.fit_class(class);
+    body
+}
+
+fn roll_body(rng: &mut impl Rng, config: &GenerationConfig) -> Body {
+    let silhouette = config.proportional.then(|| Silhouette::roll(rng));
+    let size = |part: fn(&Silhouette) -> f32| silhouette.as_ref().map(part);
+    let mut torso = random_torso(rng, size(|s| s.torso));
+    torso.meta = TorsoMeta::new(config.arm_slots, config.leg_slots);
+    let head = random_head(rng, size(|s| s.head));
+    let arm_size = size(|s| s.arm);
 
     let min_arms = (torso.meta.arm_slots as f32 * 0.2).ceil() as usize;
     let max_arms = torso.meta.arm_slots;
     let num_arms = rng.gen_range(min_arms..=max_arms);
-    let arms = (0..num_arms as u8).map(|i| random_arm(rng, i)).collect();
-
-    let legs = (0..torso.meta.leg_slots).map(|_| random_leg(rng)).collect();
+    let arms = if config.symmetric_arms && num_arms.is_multiple_of(2) {
+        (0..num_arms as u8)
+            .step_by(2)
+            .flat_map(|left| {
+                if rng.gen_bool(config.asymmetry_chance) {
+                    [
+                        random_arm_sized(rng, left, arm_size),
+                        random_arm_sized(rng, left + 1, arm_size),
+                    ]
+                } else {
+                    random_arm_sized(rng, left, arm_size).mirrored(left)
+                }
+            })
+            .collect()
+    } else {
+        (0..num_arms as u8)
+            .map(|i| random_arm_sized(rng, i, arm_size))
+            .collect()
+    };
+
+    let legs = (0..torso.meta.leg_slots as u8)
+        .map(|slot| random_leg(rng, size(|s| s.leg)).with_slot(slot))
+        .collect();
 
     Body {
         torso,
         head,
         arms,
         legs,
+        name: None,
     }
 }
 
-fn update_body_system(
-    mut commands: Commands,
-    mut bodies: Query<(Entity, &Body, &mut Stats), Changed<Body>>,
-) {
-    for (entity, body, mut stats) in bodies.iter_mut() {
-        let stats = &mut *stats;
-        *stats = Stats::default();
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PartKind {
+    Torso,
+    Head,
+    Arm,
+    Leg,
+}
 
-        stats.speed = f32::INFINITY;
+impl PartKind {
+    pub const ALL: &'static [PartKind] = &[
+        PartKind::Torso,
+        PartKind::Head,
+        PartKind::Arm,
+        PartKind::Leg,
+    ];
+}
 
-        stats.max_energy = 100.0;
+impl std::str::FromStr for PartKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "torso" => Ok(PartKind::Torso),
+            "head" => Ok(PartKind::Head),
+            "arm" => Ok(PartKind::Arm),
+            "leg" => Ok(PartKind::Leg),
+            _ => Err(format!(
+                "unknown part `{}`, expected torso, head, arm or leg",
+                s
+            )),
+        }
+    }
+}
+
+impl Body {
+    pub fn builder() -> BodyBuilder {
+        BodyBuilder::default()
+    }
 
-        body.torso.add_to_stats(stats);
-        body.head.add_to_stats(stats);
+    pub fn torso(&self) -> &Torso {
+        &self.torso
+    }
+
+    pub fn head(&self) -> &Head {
+        &self.head
+    }
+
+    pub fn arms(&self) -> &[Arm] {
+        &self.arms
+    }
+
+    // Swapping parts in place keeps the layout, so these can't break it.
+    pub fn torso_mut(&mut self) -> &mut Torso {
+        &mut self.torso
+    }
+
+    pub fn head_mut(&mut self) -> &mut Head {
+        &mut self.head
+    }
+
+    pub fn arms_mut(&mut self) -> &mut [Arm] {
+        &mut self.arms
+    }
+
+    pub fn legs_mut(&mut self) -> &mut [Leg] {
+        &mut self.legs
+    }
+
+    pub fn legs(&self) -> &[Leg] {
+        &self.legs
+    }
+
+    pub fn arm(&self, slot: u8) -> Option<&Arm> {
+        self.arms.iter().find(|arm| arm.slot == slot)
+    }
+
+    pub fn leg(&self, slot: u8) -> Option<&Leg> {
+        self.legs.iter().find(|leg| leg.slot == slot)
+    }
+
+    fn free_arm_slot(&self) -> Option<u8> {
+        (0..self.torso.meta.arm_slots as u8).find(|slot| self.arm(*slot).is_none())
+    }
 
-        for leg in &body.legs {
-            leg.add_to_stats(stats);
+    fn free_leg_slot(&self) -> Option<u8> {
+        (0..self.torso.meta.leg_slots as u8).find(|slot| self.leg(*slot).is_none())
+    }
+
+    // Puts `arm` in the slot, in place of the arm there if there is one. Every
+    // other limb and its skills stay where they are. The arm taken off.
+    pub fn replace_arm(&mut self, slot: u8, mut arm: Arm) -> Result<Option<Arm>, BodyError> {
+        if slot as usize >= self.torso.meta.arm_slots {
+            return Err(BodyError::BadSlot(Limb::Arm(slot)));
         }
-        for arm in &body.arms {
-            arm.add_to_stats(stats);
+        bind_to_slot(&mut arm, slot);
+        let replaced = self
+            .arms
+            .iter()
+            .position(|arm| arm.slot == slot)
+            .map(|index| self.arms.remove(index));
+        let at = self
+            .arms
+            .iter()
+            .position(|arm| arm.slot > slot)
+            .unwrap_or(self.arms.len());
+        self.arms.insert(at, arm);
+        Ok(replaced)
+    }
+
+    // Takes the limb off the body and leaves its slot empty, every other
+    // limb and its skills stay where they are. The lost part's name.
+    pub fn destroy(&mut self, limb: Limb) -> Option<String> {
+        let removed = match limb {
+            Limb::Arm(slot) => {
+                let index = self.arms.iter().position(|arm| arm.slot == slot)?;
+                self.arms.remove(index).name
+            }
+            Limb::Leg(slot) => {
+                let index = self.legs.iter().position(|leg| leg.slot == slot)?;
+                self.legs.remove(index).name
+            }
+        };
+        Some(removed)
+    }
+
+    pub fn name(&self) -> &str {
+        self.custom_name().unwrap_or_else(|| self.generated_name())
+    }
+
+    // The torso carries the model name the body was generated with.
+    pub fn generated_name(&self) -> &str {
+        &self.torso.name
+    }
+
+    // Blank names count as unset, even in hand-edited profiles.
+    pub fn custom_name(&self) -> Option<&str> {
+        self.name
+            .as_deref()
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+    }
+
+    pub fn set_name(&mut self, name: &str) {
+        let name = name.trim();
+        self.name = (!name.is_empty()).then(|| name.chars().take(MAX_NAME_LENGTH).collect());
+    }
+
+    // Height of the hips above the feet, the longest leg holds the body up.
+    pub fn hip_height(&self) -> f32 {
+        let longest = self
+            .legs
+            .iter()
+            .map(|leg| leg.stats.size)
+            .fold(0.0, f32::max);
+        HIP_BASE + LEG_LENGTH * longest
+    }
+
+    // Top of the head above the body's origin, matches the sprite layout.
+    pub fn height(&self) -> f32 {
+        self.hip_height() + self.torso.stats.size + self.head.stats.size * 0.5
+    }
+
+    // The box the body's sprites fill around its origin, as wide on both
+    // sides so it holds whichever way the body faces.
+    pub fn bounds(&self) -> (Vec2, Vec2) {
+        let (mut min, mut max) = (Vec2::ZERO, Vec2::ZERO);
+        for sprite in part_sprites(self) {
+            let size = sprite.scale.truncate();
+            let low = sprite.translation.truncate() - (sprite.anchor.as_vec() + 0.5) * size;
+            min = min.min(low);
+            max = max.max(low + size);
         }
-        stats.skills.sort_by_key(|skill| skill.order());
-        stats.skills.dedup();
+        let half = max.x.max(-min.x);
+        (Vec2::new(-half, min.y), Vec2::new(half, max.y))
+    }
 
-        commands.entity(entity).despawn_descendants();
-        commands.entity(entity).add_children(|parent| {
-            let root = Vec3::new(0.0, 0.7, 0.0);
-            let torso_scale = Vec3::new(0.3, 1.0, 1.0) * body.torso.stats.size;
-            stats.width = torso_scale.x;
-            parent
-                .spawn_bundle(SpriteBundle {
-                    sprite: Sprite {
-                        color: body.torso.stats.color,
-                        anchor: Anchor::BottomCenter,
-                        ..default()
-                    },
-                    transform: Transform::from_translation(root).with_scale(torso_scale),
-                    ..default()
-                });
-            parent
-                .spawn_bundle(SpriteBundle {
-                    sprite: Sprite {
-                        color: body.head.stats.color,
-                        anchor: Anchor::BottomCenter,
-                        ..default()
-                    },
-                    transform: Transform::from_translation(
-                        root + Vec3::new(0.0, torso_scale.y, 0.0),
-                    )
-                    .with_scale(Vec3::splat(body.head.stats.size * 0.5)),
-                    ..default()
-                });
-
-            for (i, leg) in body.legs.iter().enumerate() {
-                let p = (i as f32 / (body.legs.len() - 1) as f32 * torso_scale.x
-                    - torso_scale.x / 2.0)
-                    * 0.8;
-                parent
-                    .spawn_bundle(SpriteBundle {
-                        sprite: Sprite {
-                            color: leg.stats.color,
-                            anchor: Anchor::TopCenter,
-                            ..default()
-                        },
-                        transform: Transform::from_translation(root + Vec3::new(p, 0.0, 0.0))
-                            .with_scale(Vec3::new(leg.stats.size * 0.2, root.y, 1.0)),
-                        ..default()
-                    })
-                    .insert(Limb::Leg(i as u8));
+    // Whether an arm can go into both of a pair of slots, see `equip_pair`.
+    pub fn pairable(&self) -> bool {
+        self.torso.meta.arm_slots >= 2
+    }
+
+    // Puts `arm` in the first two arm slots as a mirrored pair, anything in
+    // them already is dropped. False when the torso has no pair of slots.
+    pub fn equip_pair(&mut self, arm: Arm) -> bool {
+        if !self.pairable() {
+            return false;
+        }
+        self.arms.retain(|arm| arm.slot >= 2);
+        self.arms.extend(arm.mirrored(0));
+        self.arms.sort_by_key(|arm| arm.slot);
+        true
+    }
+
+    // Fills an empty slot if there is one, otherwise replaces a random part.
+    // The new part's name and the scrap the one it replaced salvages for,
+    // nothing when it went into an empty slot.
+    pub fn reroll_part(&mut self, kind: PartKind, rng: &mut impl Rng) -> (String, u32) {
+        match kind {
+            PartKind::Torso => {
+                let old = std::mem::replace(&mut self.torso, random_torso(rng, None));
+                (self.torso.name.clone(), old.stats.salvage())
+            }
+            PartKind::Head => {
+                let old = std::mem::replace(&mut self.head, random_head(rng, None));
+                (self.head.name.clone(), old.stats.salvage())
+            }
+            PartKind::Arm => match self.free_arm_slot() {
+                Some(slot) => {
+                    let arm = random_arm(rng, slot);
+                    let name = arm.name.clone();
+                    self.arms.push(arm);
+                    self.arms.sort_by_key(|arm| arm.slot);
+                    (name, 0)
+                }
+                None => {
+                    let index = rng.gen_range(0..self.arms.len());
+                    let arm = random_arm(rng, self.arms[index].slot);
+                    let name = arm.name.clone();
+                    let old = std::mem::replace(&mut self.arms[index], arm);
+                    (name, old.stats.salvage())
+                }
+            },
+            PartKind::Leg => {
+                let leg = random_leg(rng, None);
+                let name = leg.name.clone();
+                match self.free_leg_slot() {
+                    Some(slot) => {
+                        self.legs.push(leg.with_slot(slot));
+                        self.legs.sort_by_key(|leg| leg.slot);
+                        (name, 0)
+                    }
+                    None => {
+                        let index = rng.gen_range(0..self.legs.len());
+                        let slot = self.legs[index].slot;
+                        let old = std::mem::replace(&mut self.legs[index], leg.with_slot(slot));
+                        (name, old.stats.salvage())
+                    }
+                }
+            }
+        }
+    }
+
+    // Counts a battle the body ended on `health_fraction` of its health
+    // towards every part's rust, see `PartStats::end_battle`. The names of the
+    // parts that rusted over.
+    pub fn end_battle(&mut self, health_fraction: f32) -> Vec<String> {
+        let mut rusted = Vec::new();
+        let mut end = |rusts: bool, name: &str| {
+            if rusts {
+                rusted.push(name.to_string());
+            }
+        };
+        end(self.torso.end_battle(health_fraction), &self.torso.name);
+        end(self.head.end_battle(health_fraction), &self.head.name);
+        for arm in &mut self.arms {
+            end(arm.end_battle(health_fraction), &arm.name);
+        }
+        for leg in &mut self.legs {
+            end(leg.end_battle(health_fraction), &leg.name);
+        }
+        rusted
+    }
+
+    // Parts that rust if the body ends its next battle below LOW_HEALTH.
+    pub fn rusting_parts(&self) -> Vec<&str> {
+        let mut parts = vec![
+            (self.torso.name(), self.torso.stats()),
+            (self.head.name(), self.head.stats()),
+        ];
+        parts.extend(self.arms.iter().map(|arm| (arm.name(), arm.stats())));
+        parts.extend(self.legs.iter().map(|leg| (leg.name(), leg.stats())));
+        parts
+            .into_iter()
+            .filter(|(_, stats)| stats.rusts_next())
+            .map(|(name, _)| name)
+            .collect()
+    }
+}
+
+const SHARE_CODE_VERSION: u8 = 8;
+
+#[derive(Debug)]
+pub enum ShareCodeError {
+    Encoding,
+    Empty,
+    Malformed,
+}
+
+impl std::fmt::Display for ShareCodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let message = match self {
+            ShareCodeError::Encoding => t!("share.encoding"),
+            ShareCodeError::Empty => t!("share.empty"),
+            ShareCodeError::Malformed => t!("share.malformed"),
+        };
+        f.write_str(&message)
+    }
+}
+
+impl Body {
+    pub fn limb_material(&self, limb: Limb) -> Option<Material> {
+        match limb {
+            Limb::Arm(slot) => self.arm(slot).map(|arm| arm.stats.material),
+            Limb::Leg(slot) => self.leg(slot).map(|leg| leg.stats.material),
+        }
+    }
+
+    // The name and stats of whatever part a hit test landed on.
+    pub fn part(&self, part: HitPart) -> Option<(&str, &PartStats)> {
+        match part {
+            HitPart::Torso => Some((self.torso.name(), self.torso.stats())),
+            HitPart::Head => Some((self.head.name(), self.head.stats())),
+            HitPart::Limb(Limb::Arm(slot)) => {
+                let arm = self.arm(slot)?;
+                Some((arm.name(), arm.stats()))
+            }
+            HitPart::Limb(Limb::Leg(slot)) => {
+                let leg = self.leg(slot)?;
+                Some((leg.name(), leg.stats()))
             }
+        }
+    }
+
+    pub fn materials(&self) -> impl Iterator<Item = Material> + '_ {
+        [&self.torso.stats, &self.head.stats]
+            .into_iter()
+            .chain(self.arms.iter().map(|arm| &arm.stats))
+            .chain(self.legs.iter().map(|leg| &leg.stats))
+            .map(|stats| stats.material)
+    }
+
+    pub fn to_share_code(&self) -> String {
+        let mut bytes = vec![SHARE_CODE_VERSION];
+        bytes.extend(bincode::serialize(self).expect("body is always serializable"));
+        base64::encode_config(bytes, base64::URL_SAFE_NO_PAD)
+    }
+
+    pub fn from_share_code(code: &str) -> Result<Body, GameError> {
+        let bytes = base64::decode_config(code.trim(), base64::URL_SAFE_NO_PAD)
+            .map_err(|_| ShareCodeError::Encoding)?;
+        let (&version, data) = bytes.split_first().ok_or(ShareCodeError::Empty)?;
+        if version != SHARE_CODE_VERSION {
+            return Err(GameError::IncompatibleVersion {
+                found: version,
+                expected: SHARE_CODE_VERSION,
+            });
+        }
+        let body: Body = bincode::deserialize(data).map_err(|_| ShareCodeError::Malformed)?;
+        let problems = body.problems();
+        if !problems.is_empty() {
+            return Err(GameError::Validation(problems));
+        }
+        Ok(body)
+    }
+
+    // Copies a part from `donor`, weakened by `factor`. Arms are re-bound to
+    // the slot they end up in, an empty one if there is one, otherwise the
+    // last.
+    pub fn graft(&mut self, donor: &Body, kind: PartKind, factor: f32) -> String {
+        match kind {
+            PartKind::Torso => {
+                // Keep our own slots so the limbs we have still fit.
+                let meta = self.torso.meta.clone();
+                self.torso = donor.torso.clone();
+                self.torso.meta = meta;
+                self.torso.stats.scale(factor);
+                self.torso.name.clone()
+            }
+            PartKind::Head => {
+                self.head = donor.head.clone();
+                self.head.stats.scale(factor);
+                self.head.name.clone()
+            }
+            PartKind::Arm => {
+                let mut arm = match donor.arms.first() {
+                    Some(arm) => arm.clone(),
+                    None => return String::new(),
+                };
+                let slot = self
+                    .free_arm_slot()
+                    .unwrap_or(self.torso.meta.arm_slots.max(1) as u8 - 1);
+                arm.stats.scale(factor);
+                bind_to_slot(&mut arm, slot);
+                let name = arm.name.clone();
+                self.arms.retain(|arm| arm.slot != slot);
+                self.arms.push(arm);
+                self.arms.sort_by_key(|arm| arm.slot);
+                name
+            }
+            PartKind::Leg => {
+                let mut leg = match donor.legs.first() {
+                    Some(leg) => leg.clone(),
+                    None => return String::new(),
+                };
+                leg.stats.scale(factor);
+                let name = leg.name.clone();
+                match self.legs.first_mut() {
+                    Some(first) => *first = leg.with_slot(first.slot),
+                    None => self.legs.push(leg.with_slot(0)),
+                }
+                name
+            }
+        }
+    }
+
+    // Makes every part sturdier and every attack hit harder by `factor`.
+    // Scales every part's weight so the body lands in `class`, for when no
+    // roll did. Nothing else about the parts changes.
+    pub fn fit_class(&mut self, class: WeightClass) {
+        let weight = compute_stats(self).weight;
+        if weight <= 0.0 {
+            return;
+        }
+        let factor = class.fit(weight) / weight;
+        let parts = [&mut self.torso.stats, &mut self.head.stats]
+            .into_iter()
+            .chain(self.arms.iter_mut().map(|arm| &mut arm.stats))
+            .chain(self.legs.iter_mut().map(|leg| &mut leg.stats));
+        for stats in parts {
+            stats.weight *= factor;
+        }
+    }
+
+    pub fn scale_power(&mut self, factor: f32) {
+        let parts = [&mut self.torso.stats, &mut self.head.stats]
+            .into_iter()
+            .chain(self.arms.iter_mut().map(|arm| &mut arm.stats))
+            .chain(self.legs.iter_mut().map(|leg| &mut leg.stats));
+        for stats in parts {
+            stats.scale(factor);
+        }
+    }
 
-            for (i, arm) in body.arms.iter().enumerate() {
-                let x = ((i % 2) as f32 * 2.0 - 1.0) * torso_scale.x / 2.0;
-                let y =
-                    torso_scale.y * (1.0 - (i / 2) as f32 * 2.0 / ((body.legs.len()) - 1) as f32);
-                parent
-                    .spawn_bundle(SpriteBundle {
+    // Grows or shrinks every part so the torso is `size`, the body keeps its
+    // proportions.
+    pub fn resize(&mut self, size: f32) {
+        let factor = size / self.torso.stats.size;
+        let parts = [&mut self.torso.stats, &mut self.head.stats]
+            .into_iter()
+            .chain(self.arms.iter_mut().map(|arm| &mut arm.stats))
+            .chain(self.legs.iter_mut().map(|leg| &mut leg.stats));
+        for stats in parts {
+            stats.size *= factor;
+        }
+    }
+
+    // Bodies read from outside the game may not fit their torso.
+    pub fn validate(&self) -> Result<(), BodyError> {
+        self.problems().into_iter().next().map_or(Ok(()), Err)
+    }
+
+    // Everything `validate` would complain about, first to last.
+    pub fn problems(&self) -> Vec<BodyError> {
+        let (arm_slots, leg_slots) = (self.torso.meta.arm_slots, self.torso.meta.leg_slots);
+        let mut problems = Vec::new();
+        if self.arms.len() > arm_slots {
+            problems.push(BodyError::TooManyArms {
+                slots: arm_slots,
+                found: self.arms.len(),
+            });
+        }
+        if self.legs.len() != leg_slots || self.legs.len() < 2 {
+            problems.push(BodyError::WrongLegCount {
+                slots: leg_slots,
+                found: self.legs.len(),
+            });
+        }
+        if let Some(arm) = misplaced(&self.arms, arm_slots) {
+            problems.push(BodyError::BadSlot(Limb::Arm(arm.slot)));
+        }
+        if let Some(leg) = misplaced(&self.legs, leg_slots) {
+            problems.push(BodyError::BadSlot(Limb::Leg(leg.slot)));
+        }
+        problems
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.validate().is_ok()
+    }
+}
+
+// Energy a body has before any of its parts, which can add or drain it.
+pub const BASE_ENERGY: f32 = 100.0;
+
+// What one part adds to the body's maximum health and energy.
+pub struct Contribution<'a> {
+    pub part: &'a str,
+    pub health: f32,
+    pub energy: f32,
+}
+
+// Every part's share of the body's stats, in the order compute_stats adds
+// them. With BASE_ENERGY they sum to the body's maximums.
+pub fn stat_breakdown(body: &Body) -> Vec<Contribution<'_>> {
+    fn of<M: BodyPartMeta>(part: &BodyPart<M>) -> Contribution<'_> {
+        Contribution {
+            part: &part.name,
+            health: part.stats.health,
+            energy: part.stats.energy,
+        }
+    }
+    let mut parts = vec![of(&body.torso), of(&body.head)];
+    parts.extend(body.legs.iter().map(of));
+    parts.extend(body.arms.iter().map(of));
+    parts
+}
+
+// Everything a body adds up to. Shared by update_body_system and anything that
+// needs the numbers without spawning the body.
+pub fn compute_stats(body: &Body) -> Stats {
+    let mut stats = Stats {
+        speed: f32::INFINITY,
+        reaction_time: f32::INFINITY,
+        max_energy: BASE_ENERGY,
+        ..default()
+    };
+
+    body.torso.add_to_stats(&mut stats);
+    body.head.add_to_stats(&mut stats);
+
+    for leg in &body.legs {
+        leg.add_to_stats(&mut stats);
+    }
+    for arm in &body.arms {
+        arm.add_to_stats(&mut stats);
+    }
+    let uses_ammo = stats.skills.iter().any(|skill| {
+        skill
+            .ability()
+            .is_some_and(|ability| ability.ammo.is_some())
+    });
+    if uses_ammo {
+        stats.skills.push(Skill::Reload);
+    }
+    stats.skills.sort_by_key(|skill| skill.order());
+    stats.skills.dedup();
+
+    stats.width = 0.3 * body.torso.stats.size;
+    stats.arm_reach = part_sprites(body)
+        .into_iter()
+        .filter_map(|sprite| match sprite.part {
+            HitPart::Limb(Limb::Arm(slot)) => Some(ArmReach {
+                slot,
+                shoulder: sprite.translation.x - stats.width / 2.0,
+                length: sprite.scale.y,
+                width: sprite.scale.x,
+            }),
+            _ => None,
+        })
+        .collect();
+    affinity::apply(body, &mut stats);
+    stats.health = stats.max_health;
+    stats.energy = stats.max_energy;
+    stats.overshield = stats.max_overshield;
+    stats
+}
+
+// Legs are spread evenly under the torso, a lone leg stands in the middle.
+fn leg_x(i: usize, count: usize, torso_width: f32) -> f32 {
+    if count < 2 {
+        return 0.0;
+    }
+    (i as f32 / (count - 1) as f32 * torso_width - torso_width / 2.0) * 0.8
+}
+
+// Where one part's sprite sits, relative to the body's origin at its feet. Its
+// z only orders the parts, see `RenderLayer::part`.
+pub struct PartSprite {
+    pub part: HitPart,
+    pub material: PartMaterial,
+    pub translation: Vec3,
+    pub scale: Vec3,
+    pub anchor: Anchor,
+    // Drawn at the tip of an arm.
+    pub attachment: Option<Attachment>,
+}
+
+// The body's sprites at rest, torso first. Anything drawing a body outside
+// the arena uses it too so the two can't drift apart.
+pub fn part_sprites(body: &Body) -> Vec<PartSprite> {
+    let root = Vec3::new(0.0, body.hip_height(), 0.0);
+    let torso_scale = Vec3::new(0.3, 1.0, 1.0) * body.torso.stats.size;
+    let mut sprites = vec![
+        PartSprite {
+            part: HitPart::Torso,
+            material: body.torso.stats.part_material(),
+            translation: root,
+            scale: torso_scale,
+            anchor: Anchor::BottomCenter,
+            attachment: None,
+        },
+        PartSprite {
+            part: HitPart::Head,
+            material: body.head.stats.part_material(),
+            translation: root + Vec3::new(0.0, torso_scale.y, 0.0),
+            scale: Vec3::splat(body.head.stats.size * 0.5),
+            anchor: Anchor::BottomCenter,
+            attachment: None,
+        },
+    ];
+    // Limbs stay where their slot is, even with a neighbour gone.
+    let leg_slots = body.torso.meta.leg_slots;
+    for leg in &body.legs {
+        let p = leg_x(leg.slot as usize, leg_slots, torso_scale.x);
+        sprites.push(PartSprite {
+            part: HitPart::Limb(Limb::Leg(leg.slot)),
+            material: leg.stats.part_material(),
+            translation: root + Vec3::new(p, 0.0, 0.0),
+            scale: Vec3::new(leg.stats.size * 0.2, root.y, 1.0),
+            anchor: Anchor::TopCenter,
+            attachment: None,
+        });
+    }
+    for arm in &body.arms {
+        let i = arm.slot as usize;
+        let x = ((i % 2) as f32 * 2.0 - 1.0) * torso_scale.x / 2.0;
+        let y = torso_scale.y * (1.0 - (i / 2) as f32 * 2.0 / (leg_slots.max(2) - 1) as f32);
+        // An extension rod makes the arm longer, its tip is where hits land.
+        let attachment = arm.meta.attachment();
+        let length = 0.8 * (1.0 + attachment.map_or(0.0, Attachment::length));
+        sprites.push(PartSprite {
+            part: HitPart::Limb(Limb::Arm(arm.slot)),
+            material: arm.stats.part_material(),
+            translation: root + Vec3::new(x, y, 0.0),
+            scale: Vec3::new(arm.stats.size * 0.15, length, 1.0),
+            anchor: if i.is_multiple_of(2) {
+                Anchor::TopRight
+            } else {
+                Anchor::TopLeft
+            },
+            attachment,
+        });
+    }
+    for sprite in &mut sprites {
+        sprite.translation.z = RenderLayer::part(sprite.part);
+    }
+    sprites
+}
+
+// A body's sprites at rest for showing it off. Nothing on them is anything
+// the battle or the hit tests look for, spawn them under a parent of their
+// own.
+pub fn still_sprites(body: &Body, palette: PaletteMode) -> Vec<SpriteBundle> {
+    part_sprites(body)
+        .into_iter()
+        .map(|sprite| SpriteBundle {
+            sprite: Sprite {
+                color: sprite.material.color(palette),
+                anchor: sprite.anchor,
+                ..default()
+            },
+            transform: Transform::from_translation(sprite.translation).with_scale(sprite.scale),
+            ..default()
+        })
+        .collect()
+}
+
+// Where the `index`th of bodies shown `columns` to a row stands, the rows
+// centred on x = 0 and going up from y = 0.
+pub fn grid_position(index: usize, columns: usize, spacing: Vec2) -> Vec2 {
+    let column = (index % columns) as f32 - (columns - 1) as f32 / 2.0;
+    let row = (index / columns) as f32;
+    Vec2::new(column, row) * spacing
+}
+
+// The sprites go up through the DeferredSpawner, the old ones stay until
+// all of the new ones are there.
+pub fn update_body_system(
+    game: Option<Res<Game>>,
+    palette: Res<PaletteMode>,
+    mut spawner: ResMut<DeferredSpawner>,
+    mut bodies: Query<
+        (
+            Entity,
+            &Body,
+            &mut Stats,
+            Option<&mut SkillRuntime>,
+            Option<&PartEntities>,
+        ),
+        Changed<Body>,
+    >,
+) {
+    for (entity, body, mut stats, runtime, shown) in bodies.iter_mut() {
+        let stats = &mut *stats;
+        *stats = compute_stats(body);
+        if let Some(mut runtime) = runtime {
+            runtime.reconcile(stats);
+        }
+
+        let priority = match game.as_deref() {
+            Some(game) if game.player == entity || game.enemy == entity => Priority::Combatant,
+            _ => Priority::Background,
+        };
+        let sprites = part_sprites(body);
+        let parts = sprites.iter().map(|sprite| sprite.part).collect::<Vec<_>>();
+        let palette = *palette;
+        let pieces = sprites
+            .into_iter()
+            .map(|sprite| -> Piece {
+                Box::new(move |world: &mut World| {
+                    let tip = sprite
+                        .attachment
+                        .map(|attachment| attachment_sprite(attachment, &sprite.anchor));
+                    let mut part = world.spawn();
+                    part.insert_bundle(SpriteBundle {
                         sprite: Sprite {
-                            color: arm.stats.color,
-                            anchor: if i % 2 == 0 {
-                                Anchor::TopRight
-                            } else {
-                                Anchor::TopLeft
-                            },
+                            color: sprite.material.color(palette),
+                            anchor: sprite.anchor,
                             ..default()
                         },
-                        transform: Transform::from_translation(root + Vec3::new(x, y, 0.0))
-                            .with_scale(Vec3::new(arm.stats.size * 0.15, 0.8, 1.0)),
+                        transform: Transform::from_translation(sprite.translation)
+                            .with_scale(sprite.scale),
                         ..default()
-                    })
-                    .insert(Limb::Arm(i as u8));
-            }
-        });
+                    });
+                    part.insert_bundle((sprite.material, sprite.part));
+                    if let HitPart::Limb(limb) = sprite.part {
+                        part.insert(limb);
+                    }
+                    if let Some(tip) = tip {
+                        part.with_children(|arm| {
+                            arm.spawn_bundle(tip);
+                        });
+                    }
+                    part.id()
+                })
+            })
+            .collect();
+        let replaces = shown.map_or(Vec::new(), |shown| shown.iter().map(|(_, e)| e).collect());
+        spawner.rebuild(
+            entity,
+            priority,
+            replaces,
+            pieces,
+            Box::new(move |body, staged| {
+                body.insert(PartEntities(
+                    parts.into_iter().zip(staged.iter().copied()).collect(),
+                ));
+            }),
+        );
+    }
+}
 
-        stats.health = stats.max_health;
-        stats.energy = stats.max_energy;
+// A small block over the tip of an arm anchored at `anchor`, in the arm's own
+// units. It's only drawn, hits still test the arm.
+fn attachment_sprite(attachment: Attachment, anchor: &Anchor) -> SpriteBundle {
+    let x = match anchor {
+        Anchor::TopRight => -0.5,
+        _ => 0.5,
+    };
+    SpriteBundle {
+        sprite: Sprite {
+            color: attachment.color(),
+            ..default()
+        },
+        transform: Transform::from_xyz(x, -0.95, 0.01).with_scale(Vec3::new(1.4, 0.12, 1.0)),
+        ..default()
+    }
+}
+
+// The part of a body a point lands on.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HitPart {
+    Torso,
+    Head,
+    Limb(Limb),
+}
+
+// Which child draws each part, kept on the body and rebuilt with it so
+// nothing has to search the children.
+#[derive(Component, Default)]
+pub struct PartEntities(Vec<(HitPart, Entity)>);
+
+impl PartEntities {
+    pub fn get(&self, part: HitPart) -> Option<Entity> {
+        self.0
+            .iter()
+            .find_map(|(p, entity)| (*p == part).then_some(*entity))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (HitPart, Entity)> + '_ {
+        self.0.iter().copied()
+    }
+}
+
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Facing {
+    Left,
+    #[default]
+    Right,
+}
+
+impl Facing {
+    pub fn sign(self) -> f32 {
+        match self {
+            Facing::Left => -1.0,
+            Facing::Right => 1.0,
+        }
+    }
+
+    pub fn flipped(self) -> Self {
+        match self {
+            Facing::Left => Facing::Right,
+            Facing::Right => Facing::Left,
+        }
+    }
+
+    // The facing that looks from `from` towards `to`.
+    pub fn towards(from: f32, to: f32) -> Self {
+        if to < from {
+            Facing::Left
+        } else {
+            Facing::Right
+        }
     }
 }
 
@@ -632,16 +2354,660 @@ fn update_body_system(
 pub struct BodyBundle {
     pub body: Body,
     pub stats: Stats,
+    pub facing: Facing,
     pub transform: Transform,
     pub global_transform: GlobalTransform,
     pub visibility: Visibility,
     pub computed_visibility: ComputedVisibility,
 }
 
+// A changed body's stats being rebuilt, update_body_system through
+// finalize_system. Anything reading Stats the frame a body changes goes
+// after it.
+#[derive(SystemLabel, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct BodyStats;
+
+// Spawns a body with its Stats already worked out, anything can query them
+// the frame it's spawned. Only the sprites wait for update_body_system, which
+// picks the new body up like any changed one.
+pub trait SpawnBody {
+    fn spawn_body(&mut self, body: Body, transform: Transform) -> Entity;
+}
+
+fn spawned_body(body: Body, transform: Transform) -> BodyBundle {
+    BodyBundle {
+        stats: bounded_stats(&body),
+        body,
+        transform,
+        ..default()
+    }
+}
+
+impl SpawnBody for Commands<'_, '_> {
+    fn spawn_body(&mut self, body: Body, transform: Transform) -> Entity {
+        self.spawn_bundle(spawned_body(body, transform)).id()
+    }
+}
+
+impl SpawnBody for World {
+    fn spawn_body(&mut self, body: Body, transform: Transform) -> Entity {
+        self.spawn()
+            .insert_bundle(spawned_body(body, transform))
+            .id()
+    }
+}
+
 pub struct BodyPlugin;
 
 impl Plugin for BodyPlugin {
     fn build(&self, app: &mut App) {
-        app.add_system(update_body_system);
+        app.init_resource::<PaletteMode>()
+            .add_system(update_body_system.label(BodyStats))
+            .add_system(
+                finalize_system
+                    .label(BodyStats)
+                    .after(update_body_system)
+                    .after(StatModifiers),
+            );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{fixtures::seeded_bodies, upgrade::upgraded};
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn the_starter_body_shows_off_every_kind_of_skill() {
+        let body = Body::default();
+        assert!(body.is_valid());
+        assert!(body.materials().all(|material| material == Material::Rust));
+        let stats = compute_stats(&body);
+        let kinds: [fn(&Skill) -> bool; 8] = [
+            |skill| matches!(skill, Skill::WalkForward),
+            |skill| matches!(skill, Skill::WalkBackward),
+            |skill| matches!(skill, Skill::TurnAround),
+            |skill| matches!(skill, Skill::Dash(_)),
+            |skill| matches!(skill, Skill::BasicMelee(_)),
+            |skill| matches!(skill, Skill::BasicRanged(_)),
+            |skill| matches!(skill, Skill::Reload),
+            |skill| matches!(skill, Skill::Scan(_)),
+        ];
+        for (i, kind) in kinds.iter().enumerate() {
+            assert_eq!(
+                stats.skills.iter().filter(|skill| kind(skill)).count(),
+                1,
+                "{}",
+                i
+            );
+        }
+        let ranged = stats.skills.iter().find(|skill| kinds[5](skill)).unwrap();
+        assert_eq!(ranged.ability().unwrap().ammo, Some(3));
+        let scan = stats.skills.iter().find(|skill| kinds[7](skill)).unwrap();
+        assert_eq!(
+            crate::knowledge::detail(scan),
+            Some(crate::knowledge::NUMBERS)
+        );
+    }
+
+    #[test]
+    fn names_survive_round_trips() {
+        for input in ["Clanky", "  Clanky  ", "", "   "] {
+            let mut body = Body::default();
+            body.set_name(input);
+            let expected = match input.trim() {
+                "" => body.generated_name().to_string(),
+                name => name.to_string(),
+            };
+            let ron = ron::to_string(&body).unwrap();
+            let from_ron = ron::from_str::<Body>(&ron).unwrap();
+            let from_code = Body::from_share_code(&body.to_share_code()).unwrap();
+            for body in [&body, &from_ron, &from_code] {
+                assert_eq!(body.name(), expected, "input {:?}", input);
+            }
+        }
+    }
+
+    #[test]
+    fn blank_stored_names_fall_back() {
+        let mut body = Body {
+            name: Some(" \t ".to_string()),
+            ..default()
+        };
+        assert_eq!(body.name(), body.generated_name());
+        body.set_name(&"x".repeat(40));
+        assert_eq!(body.name().chars().count(), MAX_NAME_LENGTH);
+    }
+
+    #[test]
+    fn losing_an_arm_leaves_the_others_in_their_slots() {
+        let ids = |body: &Body| {
+            compute_stats(body)
+                .skills
+                .iter()
+                .map(Skill::id)
+                .collect::<Vec<_>>()
+        };
+        let arm_at = |body: &Body, limb| {
+            part_sprites(body)
+                .into_iter()
+                .find(|sprite| sprite.part == HitPart::Limb(limb))
+                .map(|sprite| sprite.translation)
+        };
+        let mut body = Body::default();
+        let before = ids(&body);
+        let right = arm_at(&body, Limb::Arm(1));
+        assert!(body.destroy(Limb::Arm(0)).is_some());
+        assert!(body.destroy(Limb::Arm(0)).is_none());
+        assert!(body.validate().is_ok());
+        // The right arm's skills keep their ids, only the left arm's are gone.
+        let left = compute_stats(&Body::default())
+            .skills
+            .iter()
+            .filter(|skill| skill.ability().is_some_and(|a| a.limb == Limb::Arm(0)))
+            .map(Skill::id)
+            .collect::<Vec<_>>();
+        assert!(!left.is_empty());
+        let kept = before
+            .into_iter()
+            .filter(|id| !left.contains(id))
+            .collect::<Vec<_>>();
+        assert_eq!(ids(&body), kept);
+        assert_eq!(body.arm(1).map(|arm| arm.slot()), Some(1));
+        assert_eq!(arm_at(&body, Limb::Arm(1)), right);
+        assert_eq!(arm_at(&body, Limb::Arm(0)), None);
+        assert_eq!(body.free_arm_slot(), Some(0));
+
+        // Saved before limbs had slots, every one of them in slot 0.
+        let legacy = ron::to_string(&Body::default())
+            .unwrap()
+            .replace("slot:1", "slot:0");
+        let loaded = ron::from_str::<Body>(&legacy).unwrap();
+        assert!(loaded.validate().is_ok());
+        assert_eq!(ids(&loaded), ids(&Body::default()));
+    }
+
+    #[test]
+    fn long_legs_stand_taller() {
+        let mut body = Body::default();
+        assert!((body.hip_height() - 0.7).abs() < 1e-6);
+        let (hips, height) = (body.hip_height(), body.height());
+        body.legs_mut()[0].stats.size *= 2.0;
+        let lift = body.hip_height() - hips;
+        assert!(lift > 0.0);
+        assert!((body.height() - height - lift).abs() < 1e-6);
+        // Torso and head go up with the hips, the legs reach down to the feet.
+        let sprites = part_sprites(&body);
+        assert!((sprites[0].translation.y - body.hip_height()).abs() < 1e-6);
+        assert!(
+            (sprites[1].translation.y - sprites[0].translation.y - sprites[0].scale.y).abs() < 1e-6
+        );
+        for leg in sprites
+            .iter()
+            .filter(|sprite| matches!(sprite.part, HitPart::Limb(Limb::Leg(_))))
+        {
+            assert!((leg.translation.y - leg.scale.y).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn parts_rust_after_three_bad_battles() {
+        let mut arm = Arm::new(
+            "Shiny Arm",
+            PartStats::new(Material::Steel, 1.0, 70.0, 30.0, 2.0),
+            ArmMeta::default(),
+        );
+        assert!(!arm.end_battle(0.1) && !arm.end_battle(0.1));
+        assert!(arm.stats.rusts_next());
+        // A battle ended in good shape starts the count over.
+        assert!(!arm.end_battle(0.5));
+        assert!(!arm.stats.rusts_next());
+        assert!(!arm.end_battle(0.1) && !arm.end_battle(0.1));
+        assert!(arm.end_battle(0.1));
+        assert_eq!(arm.name(), "Rusty Shiny Arm");
+        let stats = arm.stats();
+        assert_eq!(stats.material(), Material::Rust);
+        assert_eq!(stats.color(), Material::Rust.color(PaletteMode::Default));
+        assert!((stats.health() - 10.0).abs() < 1e-4);
+        assert_eq!(stats.energy(), 0.0);
+        assert!((stats.weight() - 70.0).abs() < 1e-4);
+        assert_eq!(stats.salvage(), SALVAGE_SCRAP * RUST_SALVAGE);
+
+        let mut plastic = Arm::new(
+            "Toy Arm",
+            PartStats::new(Material::Plastic, 1.0, 30.0, 5.0, 0.0),
+            ArmMeta::default(),
+        );
+        for _ in 0..RUST_AFTER * 2 {
+            assert!(!plastic.end_battle(0.0));
+        }
+        assert!(!plastic.stats.rusts_next());
+    }
+
+    #[test]
+    fn rust_decays_a_little_each_battle() {
+        let mut body = Body::default();
+        let health = compute_stats(&body).max_health;
+        assert!(body.end_battle(0.0).is_empty());
+        let once = compute_stats(&body).max_health;
+        assert!((once - health * (1.0 - RUST_DECAY)).abs() < 1e-3);
+        for _ in 0..MAX_DECAY * 2 {
+            body.end_battle(1.0);
+        }
+        let floor = health * (1.0 - RUST_DECAY).powi(MAX_DECAY as i32);
+        assert!((compute_stats(&body).max_health - floor).abs() < 1e-3);
+        // Already Rust, nothing left to warn about.
+        assert!(body.rusting_parts().is_empty());
+        let decayed = Body::from_share_code(&body.to_share_code()).unwrap();
+        assert!((compute_stats(&decayed).max_health - floor).abs() < 1e-3);
+    }
+
+    #[test]
+    fn upgrades_keep_the_wear() {
+        // Two bad battles in, an upgrade doesn't start the count over.
+        let mut arm = Arm::new(
+            "Shiny Arm",
+            PartStats::new(Material::Steel, 1.0, 70.0, 30.0, 2.0),
+            ArmMeta::default(),
+        );
+        assert!(!arm.end_battle(0.1) && !arm.end_battle(0.1));
+        let mut arm = upgraded(&arm).unwrap();
+        assert!(arm.stats.rusts_next());
+        assert!(arm.end_battle(0.1));
+
+        // Fully decayed Rust stays at its floor, an upgrade doesn't buy more
+        // decay.
+        for _ in 0..MAX_DECAY {
+            arm.end_battle(1.0);
+        }
+        let mut arm = upgraded(&arm).unwrap();
+        let health = arm.stats.health();
+        arm.end_battle(1.0);
+        assert_eq!(arm.stats.health(), health);
+    }
+
+    #[test]
+    fn breakdown_adds_up_to_the_stats() {
+        for body in seeded_bodies(8, 2, 2) {
+            let stats = compute_stats(&body);
+            let parts = stat_breakdown(&body);
+            assert_eq!(parts.len(), 2 + body.legs().len() + body.arms().len());
+            let health = parts.iter().map(|part| part.health).sum::<f32>();
+            let energy = BASE_ENERGY + parts.iter().map(|part| part.energy).sum::<f32>();
+            assert!((health - stats.max_health).abs() < 1e-3);
+            assert!((energy - stats.max_energy).abs() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn symmetric_arms_are_two_skills_on_two_limbs() {
+        let body = (0..)
+            .map(|seed| random_body(&mut StdRng::seed_from_u64(seed)))
+            .find(|body| {
+                body.arms().len() == 2 && body.arms()[0].stats.weight == body.arms()[1].stats.weight
+            })
+            .unwrap();
+        let skills = compute_stats(&body)
+            .skills
+            .into_iter()
+            .filter_map(|skill| skill.ability().cloned())
+            .collect::<Vec<_>>();
+        assert_eq!(skills.len(), 2);
+        assert_eq!(skills[0].limb, Limb::Arm(0));
+        assert_eq!(skills[1].limb, Limb::Arm(1));
+        assert!(skills[0].name.starts_with("Left ") && skills[1].name.starts_with("Right "));
+        assert_eq!(skills[0].name[5..], skills[1].name[6..]);
+        assert_eq!(skills[0].meta, skills[1].meta);
+
+        // Never paired when every pair is meant to be asymmetric.
+        let config = GenerationConfig {
+            asymmetry_chance: 1.0,
+            ..default()
+        };
+        for seed in 0..8 {
+            let body = random_body_with(&mut StdRng::seed_from_u64(seed), &config);
+            let skills = compute_stats(&body).skills;
+            assert!(skills
+                .iter()
+                .filter_map(Skill::ability)
+                .all(|ability| !ability.name.starts_with("Left")));
+        }
+    }
+
+    fn leg() -> Leg {
+        Body::default().legs()[0].clone()
+    }
+
+    #[test]
+    fn weight_classes_split_at_their_limits() {
+        assert_eq!(WeightClass::from_weight(0.0), WeightClass::Feather);
+        assert_eq!(WeightClass::from_weight(149.99), WeightClass::Feather);
+        assert_eq!(WeightClass::from_weight(150.0), WeightClass::Middle);
+        assert_eq!(WeightClass::from_weight(399.99), WeightClass::Middle);
+        assert_eq!(WeightClass::from_weight(400.0), WeightClass::Heavy);
+        assert_eq!(WeightClass::from_weight(5000.0), WeightClass::Heavy);
+        // Feather bodies hardly ever roll, they still come out of the
+        // generator in class.
+        for class in WeightClass::ALL {
+            for seed in 0..4 {
+                let body = random_body_in(&mut StdRng::seed_from_u64(seed), class);
+                assert!(body.is_valid());
+                assert_eq!(WeightClass::from_weight(compute_stats(&body).weight), class);
+            }
+        }
+    }
+
+    #[test]
+    fn skill_ids_survive_saves_and_losing_an_arm() {
+        let body = seeded_bodies(1, 2, 2).remove(0);
+        let arm = body.arms()[0].clone();
+        let with_arms = |arms: usize| {
+            let mut builder = Body::builder()
+                .torso(body.torso().clone())
+                .head(body.head().clone());
+            for _ in 0..arms {
+                builder = builder.arm(arm.clone());
+            }
+            for leg in body.legs() {
+                builder = builder.leg(leg.clone());
+            }
+            builder.build().unwrap()
+        };
+        let ids = |body: &Body| {
+            compute_stats(body)
+                .skills
+                .iter()
+                .map(Skill::id)
+                .collect::<Vec<_>>()
+        };
+        // The same arm in two slots is two skills.
+        let two = with_arms(2);
+        let attacks = compute_stats(&two)
+            .skills
+            .into_iter()
+            .filter(|skill| skill.ability().is_some())
+            .collect::<Vec<_>>();
+        assert_eq!(attacks.len(), 2);
+        assert_ne!(attacks[0].id(), attacks[1].id());
+
+        let ron = ron::to_string(&two).unwrap();
+        assert_eq!(ids(&ron::from_str::<Body>(&ron).unwrap()), ids(&two));
+        let code = Body::from_share_code(&two.to_share_code()).unwrap();
+        assert_eq!(ids(&code), ids(&two));
+        // Saved as the plain string, like profiles from before the type.
+        let id = attacks[0].id();
+        assert_eq!(ron::to_string(&id).unwrap(), format!("{:?}", id.as_str()));
+
+        // What's left keeps its id, what went with the arm is gone.
+        let one = compute_stats(&with_arms(1));
+        assert!(one.skill(&attacks[0].id()).is_some());
+        assert!(one.skill_index(&attacks[1].id()).is_none());
+    }
+
+    #[test]
+    fn builder_checks_the_layout() {
+        let default = Body::default();
+        let builder = || {
+            Body::builder()
+                .torso(default.torso().clone())
+                .head(default.head().clone())
+        };
+        assert_eq!(
+            Body::builder().head(default.head().clone()).build().err(),
+            Some(BodyError::MissingTorso)
+        );
+        assert_eq!(
+            builder().leg(leg()).build().err(),
+            Some(BodyError::WrongLegCount { slots: 2, found: 1 })
+        );
+        let arm = default.arms()[0].clone();
+        assert_eq!(
+            builder()
+                .arm(arm.clone())
+                .arm(arm.clone())
+                .arm(arm.clone())
+                .leg(leg())
+                .leg(leg())
+                .build()
+                .err(),
+            Some(BodyError::TooManyArms { slots: 2, found: 3 })
+        );
+
+        // Both arms were the first arm, the second one now attacks with its
+        // own slot.
+        let body = builder()
+            .arm(arm.clone())
+            .arm(arm)
+            .leg(leg())
+            .leg(leg())
+            .name("Clanky")
+            .build()
+            .unwrap();
+        assert_eq!(body.name(), "Clanky");
+        let limbs = body
+            .arms()
+            .iter()
+            .flat_map(|arm| arm.stats().skills())
+            .filter_map(|skill| Some(skill.ability()?.limb))
+            .collect::<Vec<_>>();
+        assert_eq!(limbs, [Limb::Arm(0), Limb::Arm(1)]);
+    }
+
+    fn within(value: f32, a: f32, b: f32) -> bool {
+        let (low, high) = (a.min(b), a.max(b));
+        value >= low - 1e-3 && value <= high + 1e-3
+    }
+
+    #[test]
+    fn generated_bodies_keep_their_proportions() {
+        for seed in 0..200 {
+            let body = random_body(&mut StdRng::seed_from_u64(seed));
+            let torso = body.torso.stats.size;
+            assert!(within(body.head.stats.size * 0.5 / torso, 0.3, 0.7));
+            let leg = body.legs[0].stats.size;
+            assert!(within(leg / torso, 0.75, 1.25), "seed {seed}");
+            assert!(body.legs.iter().all(|other| other.stats.size == leg));
+            for arm in &body.arms {
+                assert!(within(arm.stats.size / torso, 0.75, 1.25), "seed {seed}");
+            }
+        }
+        // Without a silhouette nothing holds the head down.
+        let chaos = GenerationConfig {
+            proportional: false,
+            ..default()
+        };
+        assert!((0..200).any(|seed| {
+            let body = random_body_with(&mut StdRng::seed_from_u64(seed), &chaos);
+            body.head.stats.size * 0.5 / body.torso.stats.size > 0.7
+        }));
+    }
+
+    #[test]
+    fn random_parts_stay_in_range() {
+        // The density range and multipliers each kind of part is made with.
+        let kinds = [
+            (0.6..=1.0, 0.1, 0.3),
+            (0.6..=1.0, 0.3, 0.7),
+            (0.8..=1.2, 1.0, 1.0),
+        ];
+        let spread = (0.2f32.powf(0.3), 5.0f32.powf(0.3));
+        let mut seen = Vec::new();
+        for seed in 0..500 {
+            let mut rng = StdRng::seed_from_u64(seed);
+            for (density, hp_mul, energy_mul) in kinds.clone() {
+                let part =
+                    randomize_part(&mut rng, None, vec![], density.clone(), hp_mul, energy_mul);
+                let material = part.material;
+                assert!(Material::ALL.contains(&material));
+                assert!(within(part.size, 0.5, 2.0), "seed {seed}");
+                let base = part.size * material.density();
+                assert!(
+                    within(part.weight, base * density.start(), base * density.end()),
+                    "seed {seed}"
+                );
+                let hp = material.base_hp() * part.size.sqrt() * hp_mul;
+                assert!(
+                    within(part.health, hp * spread.0, hp * spread.1),
+                    "seed {seed}"
+                );
+                let energy = material.base_energy() * part.size.sqrt() * energy_mul;
+                assert!(
+                    within(part.energy, energy * spread.0, energy * spread.1),
+                    "seed {seed}"
+                );
+                if !seen.contains(&material) {
+                    seen.push(material);
+                }
+            }
+        }
+        assert_eq!(seen.len(), Material::ALL.len());
+    }
+
+    #[test]
+    fn generated_names_have_the_right_shape() {
+        for seed in 0..500 {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let name = gen_name(&mut rng, "leg");
+            let (prefix, code) = name.split_once(" - ").unwrap();
+            let (adjective, part) = prefix.split_once(' ').unwrap();
+            assert!(ADJECTIVES.contains(&adjective), "{name}");
+            assert_eq!(part, "leg");
+
+            let letters = code.chars().take_while(char::is_ascii_uppercase).count();
+            let digits = code[letters..]
+                .chars()
+                .take_while(char::is_ascii_digit)
+                .count();
+            let rest = &code[letters + digits..];
+            assert!((1..=4).contains(&letters), "{name}");
+            assert!((2..=5).contains(&digits), "{name}");
+            assert!(
+                rest.is_empty()
+                    || (rest.len() == 1 && rest.chars().all(|c| c.is_ascii_uppercase())),
+                "{name}"
+            );
+        }
+    }
+
+    #[test]
+    fn stats_add_up_the_parts() {
+        for (arms, legs) in [(2, 2), (1, 2), (4, 4)] {
+            for body in seeded_bodies(100, arms, legs) {
+                let name = body.name().to_string();
+                assert_eq!(body.legs.len(), legs);
+                assert!(body.arms.len() <= arms);
+                let stats = compute_stats(&body);
+
+                let slowest = body
+                    .legs
+                    .iter()
+                    .map(|leg| leg.meta.max_speed)
+                    .fold(f32::INFINITY, f32::min);
+                assert_eq!(stats.speed, slowest);
+                assert_eq!(stats.close_accuracy, body.head.meta.close_vision);
+                assert_eq!(stats.far_accuracy, body.head.meta.far_vision);
+                assert_eq!(stats.reaction_time, body.head.meta.refresh_rate);
+
+                let weight = body.torso.stats.weight
+                    + body.head.stats.weight
+                    + body.arms.iter().map(|arm| arm.stats.weight).sum::<f32>()
+                    + body.legs.iter().map(|leg| leg.stats.weight).sum::<f32>();
+                // Less any Plastic set's share.
+                let mut sets = Stats {
+                    weight,
+                    ..default()
+                };
+                affinity::apply(&body, &mut sets);
+                assert!((stats.weight - sets.weight).abs() < 1e-3, "{name}");
+                assert_eq!(stats.health, stats.max_health);
+                assert_eq!(stats.energy, stats.max_energy);
+
+                // Every leg brings the same walks, they're only listed once.
+                let orders = stats.skills.iter().map(Skill::order).collect::<Vec<_>>();
+                assert!(orders.windows(2).all(|w| w[0] <= w[1]), "{name}");
+                for (i, skill) in stats.skills.iter().enumerate() {
+                    assert!(!stats.skills[i + 1..].contains(skill), "{name}");
+                }
+                let walks = stats
+                    .skills
+                    .iter()
+                    .filter(|skill| **skill == Skill::WalkForward)
+                    .count();
+                assert_eq!(walks, 1, "{name}");
+            }
+        }
+    }
+
+    #[test]
+    fn single_leg_stands_in_the_middle() {
+        assert_eq!(leg_x(0, 1, 0.5), 0.0);
+        assert!((leg_x(0, 2, 0.5) + 0.2).abs() < 1e-6);
+        assert!((leg_x(1, 2, 0.5) - 0.2).abs() < 1e-6);
+
+        let mut body = Body::default();
+        body.legs.truncate(1);
+        body.legs[0].meta.max_speed = 3.0;
+        let stats = compute_stats(&body);
+        assert_eq!(stats.speed, 3.0);
+        assert!(stats.speed.is_finite());
+    }
+
+    #[test]
+    fn equal_abilities_compare_equal() {
+        let arm = Body::default().arms()[0].clone();
+        let jab = arm.stats().skills()[0].clone();
+        assert_eq!(jab, jab.clone());
+
+        let mut other = arm.clone();
+        bind_to_slot(&mut other, 1);
+        assert_ne!(jab, other.stats().skills()[0]);
+
+        // Two copies of the same arm in the same slot only give one jab.
+        let body = Body {
+            arms: vec![arm.clone(), arm],
+            ..default()
+        };
+        let jabs = compute_stats(&body)
+            .skills
+            .iter()
+            .filter(|skill| matches!(skill, Skill::BasicMelee(_)))
+            .count();
+        assert_eq!(jabs, 1);
+    }
+
+    // Read in the stage after the one it's spawned in, before
+    // update_body_system has seen it.
+    #[test]
+    fn a_spawned_body_has_its_skills_the_same_frame() {
+        #[derive(Default)]
+        struct Seen(Option<usize>);
+
+        let body = seeded_bodies(1, 2, 2).remove(0);
+        let expected = bounded_stats(&body).skills.len();
+        assert!(expected > 0);
+        let mut app = App::new();
+        app.insert_resource(Some(body))
+            .init_resource::<Seen>()
+            .add_plugin(crate::churn::ChurnPlugin)
+            .add_plugin(BodyPlugin)
+            .add_system_to_stage(
+                CoreStage::PreUpdate,
+                |mut commands: Commands, mut body: ResMut<Option<Body>>| {
+                    if let Some(body) = body.take() {
+                        commands.spawn_body(body, Transform::default());
+                    }
+                },
+            )
+            .add_system(
+                (|stats: Query<&Stats>, mut seen: ResMut<Seen>| {
+                    seen.0 = stats.iter().next().map(|stats| stats.skills.len());
+                })
+                .before(BodyStats),
+            );
+        app.update();
+        assert_eq!(app.world.resource::<Seen>().0, Some(expected));
+        app.update();
+        assert_eq!(app.world.resource::<Seen>().0, Some(expected));
     }
 }
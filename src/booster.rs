@@ -0,0 +1,281 @@
+use std::collections::BTreeMap;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    body::{update_body_system, Body, Stats},
+    bounds::StatModifiers,
+    effects::{ActiveEffect, Polarity},
+    locale::t,
+    profile::Profile,
+    AppState, Game,
+};
+
+// There's no heat to dissipate, coolant lets the energy come back faster
+// instead.
+const COOLANT_REGEN: f32 = 1.5;
+const SERVO_GREASE_SPEED: f32 = 1.15;
+const ARMOR_WAX_RESIST: f32 = 0.1;
+
+// Consumables bought with scrap and kept in the profile. One armed before a
+// battle is used up when it starts and lasts until it ends.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Booster {
+    Coolant,
+    ServoGrease,
+    ArmorWax,
+}
+
+// Only one booster of each can be armed at a time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BoosterCategory {
+    Cooling,
+    Actuators,
+    Plating,
+}
+
+impl Booster {
+    pub const ALL: [Booster; 3] = [Booster::Coolant, Booster::ServoGrease, Booster::ArmorWax];
+
+    // Also picks the `effect.<id>.name` strings, armed boosters show in the
+    // battle with the status effects.
+    pub fn id(self) -> &'static str {
+        match self {
+            Booster::Coolant => "coolant",
+            Booster::ServoGrease => "servo_grease",
+            Booster::ArmorWax => "armor_wax",
+        }
+    }
+
+    pub fn name(self) -> String {
+        t!(format!("effect.{}.name", self.id()))
+    }
+
+    pub fn category(self) -> BoosterCategory {
+        match self {
+            Booster::Coolant => BoosterCategory::Cooling,
+            Booster::ServoGrease => BoosterCategory::Actuators,
+            Booster::ArmorWax => BoosterCategory::Plating,
+        }
+    }
+
+    // Scrap one costs.
+    pub fn price(self) -> u32 {
+        match self {
+            Booster::Coolant => 30,
+            Booster::ServoGrease => 40,
+            Booster::ArmorWax => 50,
+        }
+    }
+}
+
+// What the player has armed for the next battle and what the current one
+// used up. Only the player has boosters, the enemy's AI never buys any.
+#[derive(Default)]
+pub struct Boosters {
+    armed: Vec<Booster>,
+    active: Vec<Booster>,
+}
+
+impl Boosters {
+    pub fn is_armed(&self, booster: Booster) -> bool {
+        self.armed.contains(&booster)
+    }
+
+    pub fn active(&self) -> &[Booster] {
+        &self.active
+    }
+
+    #[cfg(test)]
+    pub fn with_active(active: &[Booster]) -> Self {
+        Self {
+            armed: Vec::new(),
+            active: active.to_vec(),
+        }
+    }
+
+    // The armed booster `booster` would replace, arming it over one of the
+    // same category needs a confirmation.
+    pub fn replaces(&self, booster: Booster) -> Option<Booster> {
+        self.armed
+            .iter()
+            .copied()
+            .find(|armed| *armed != booster && armed.category() == booster.category())
+    }
+
+    pub fn arm(&mut self, booster: Booster) {
+        self.armed
+            .retain(|armed| armed.category() != booster.category());
+        self.armed.push(booster);
+    }
+
+    pub fn disarm(&mut self, booster: Booster) {
+        self.armed.retain(|armed| *armed != booster);
+    }
+
+    // Takes one of every armed booster out of `stock`, the ones that are
+    // left without any are dropped.
+    fn consume(&mut self, stock: &mut BTreeMap<Booster, u32>) -> Vec<Booster> {
+        self.armed
+            .drain(..)
+            .filter(|booster| match stock.get_mut(booster) {
+                Some(count) if *count > 0 => {
+                    *count -= 1;
+                    true
+                }
+                _ => false,
+            })
+            .collect()
+    }
+
+    fn has(&self, booster: Booster) -> bool {
+        self.active.contains(&booster)
+    }
+
+    // Energy regen of the boosted body, drains aren't sped up.
+    pub fn regen(&self, regen: f32) -> f32 {
+        if self.has(Booster::Coolant) && regen > 0.0 {
+            regen * COOLANT_REGEN
+        } else {
+            regen
+        }
+    }
+
+    // Damage the boosted body takes, healing goes through untouched.
+    pub fn resist(&self, amount: f32) -> f32 {
+        if self.has(Booster::ArmorWax) && amount > 0.0 {
+            amount * (1.0 - ARMOR_WAX_RESIST)
+        } else {
+            amount
+        }
+    }
+
+    pub fn apply(&self, stats: &mut Stats) {
+        if self.has(Booster::ServoGrease) {
+            stats.speed *= SERVO_GREASE_SPEED;
+        }
+    }
+}
+
+// Sent by the confirmation dialog when an armed booster is replaced.
+pub struct ArmBooster(pub Booster);
+
+fn arm_system(mut events: EventReader<ArmBooster>, mut boosters: ResMut<Boosters>) {
+    for ArmBooster(booster) in events.iter() {
+        boosters.arm(*booster);
+    }
+}
+
+// Rebuilds the player's stats like the weather does, so the last battle's
+// boosters come off them.
+fn consume_system(
+    game: Res<Game>,
+    mut boosters: ResMut<Boosters>,
+    profile: Option<ResMut<Profile>>,
+    mut bodies: Query<&mut Body>,
+) {
+    let active = match profile {
+        Some(mut profile) => boosters.consume(&mut profile.boosters),
+        None => Vec::new(),
+    };
+    if !active.is_empty() {
+        info!("Boosters used up for this battle: {:?}", active);
+    }
+    if boosters.active != active {
+        boosters.active = active;
+        if let Ok(mut body) = bodies.get_mut(game.player) {
+            body.set_changed();
+        }
+    }
+}
+
+// Once the battle is over the boosters have run out.
+fn expire_system(game: Res<Game>, mut boosters: ResMut<Boosters>, mut bodies: Query<&mut Body>) {
+    if boosters.active.is_empty() {
+        return;
+    }
+    boosters.active.clear();
+    if let Ok(mut body) = bodies.get_mut(game.player) {
+        body.set_changed();
+    }
+}
+
+fn booster_stats_system(
+    game: Res<Game>,
+    boosters: Res<Boosters>,
+    mut bodies: Query<&mut Stats, Changed<Body>>,
+) {
+    if let Ok(mut stats) = bodies.get_mut(game.player) {
+        boosters.apply(&mut stats);
+    }
+}
+
+fn booster_effect_system(
+    game: Res<Game>,
+    boosters: Res<Boosters>,
+    mut effects: EventWriter<ActiveEffect>,
+) {
+    for booster in boosters.active() {
+        effects.send(ActiveEffect {
+            target: game.player,
+            id: booster.id(),
+            polarity: Polarity::Buff,
+            remaining: None,
+            stacks: 1,
+        });
+    }
+}
+
+pub struct BoosterPlugin;
+
+impl Plugin for BoosterPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Boosters>()
+            .add_event::<ArmBooster>()
+            .add_system(arm_system)
+            .add_system(
+                booster_stats_system
+                    .after(update_body_system)
+                    .label(StatModifiers),
+            )
+            .add_system_set(SystemSet::on_enter(AppState::Battle).with_system(consume_system))
+            .add_system_set(SystemSet::on_enter(AppState::PreBattle).with_system(expire_system))
+            .add_system_set(SystemSet::on_enter(AppState::Intermission).with_system(expire_system))
+            .add_system_set(
+                SystemSet::on_update(AppState::Battle).with_system(booster_effect_system),
+            );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn boosters_are_used_up_once_per_battle() {
+        let mut boosters = Boosters::default();
+        boosters.arm(Booster::Coolant);
+        boosters.arm(Booster::ArmorWax);
+        boosters.arm(Booster::Coolant);
+        assert_eq!(boosters.armed, [Booster::ArmorWax, Booster::Coolant]);
+        assert_eq!(boosters.replaces(Booster::ServoGrease), None);
+        assert_eq!(boosters.replaces(Booster::Coolant), None);
+
+        // Armed without one in stock, it's dropped instead of used.
+        let mut stock = BTreeMap::from([(Booster::Coolant, 2)]);
+        boosters.active = boosters.consume(&mut stock);
+        assert_eq!(boosters.active(), [Booster::Coolant]);
+        assert_eq!(stock[&Booster::Coolant], 1);
+        assert!(!boosters.is_armed(Booster::Coolant));
+        assert_eq!(boosters.consume(&mut stock), []);
+        assert_eq!(stock[&Booster::Coolant], 1);
+
+        assert_eq!(boosters.regen(2.0), 2.0 * COOLANT_REGEN);
+        assert_eq!(boosters.regen(-2.0), -2.0);
+        assert_eq!(boosters.resist(10.0), 10.0);
+        boosters.active = vec![Booster::ArmorWax];
+        assert!((boosters.resist(10.0) - 9.0).abs() < 1e-5);
+        assert_eq!(boosters.resist(-10.0), -10.0);
+    }
+}
@@ -0,0 +1,238 @@
+use bevy::{
+    asset::{AssetLoader, BoxedFuture, LoadContext, LoadedAsset},
+    prelude::*,
+    reflect::TypeUuid,
+};
+use rand::{seq::SliceRandom, Rng};
+use serde::Deserialize;
+
+use crate::{
+    body::{Body, PartKind, Stats},
+    ui::{Fonts, Scaled},
+    AppState, Game,
+};
+
+#[derive(Deserialize, TypeUuid)]
+#[uuid = "8c0f5e36-6c2b-4a55-9d5e-2f1f0a9b7c41"]
+pub struct BossDef {
+    pub name: String,
+    // The part the player gets for defeating the boss.
+    pub loot: PartKind,
+    pub body: Body,
+}
+
+#[derive(Default)]
+struct BossLoader;
+
+impl AssetLoader for BossLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let boss = ron::de::from_bytes::<BossDef>(bytes)?;
+            if let Err(err) = boss.body.validate() {
+                return Err(bevy::asset::Error::msg(format!(
+                    "invalid boss body: {}",
+                    err
+                )));
+            }
+            load_context.set_default_asset(LoadedAsset::new(boss));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["boss.ron"]
+    }
+}
+
+#[derive(Default)]
+pub struct BossLibrary {
+    handles: Vec<Handle<BossDef>>,
+}
+
+impl BossLibrary {
+    pub fn choose<'a>(
+        &self,
+        assets: &'a Assets<BossDef>,
+        rng: &mut impl Rng,
+    ) -> Option<&'a BossDef> {
+        let loaded = self
+            .handles
+            .iter()
+            .filter_map(|handle| assets.get(handle))
+            .collect::<Vec<_>>();
+        loaded.choose(rng).copied()
+    }
+}
+
+#[derive(Component)]
+pub struct Boss {
+    pub name: String,
+    pub loot: PartKind,
+}
+
+#[derive(Component)]
+struct BossBar;
+
+#[derive(Component)]
+struct BossName;
+
+#[derive(Component)]
+struct BossHealth;
+
+#[cfg(not(target_arch = "wasm32"))]
+fn load_bosses_system(asset_server: Res<AssetServer>, mut library: ResMut<BossLibrary>) {
+    match asset_server.load_folder("bosses") {
+        Ok(handles) => library.handles = handles.into_iter().map(|h| h.typed()).collect(),
+        Err(err) => error!("Failed to load bosses: {:?}", err),
+    }
+}
+
+// Folders can't be listed over http, so the web build names every boss.
+#[cfg(target_arch = "wasm32")]
+const BOSSES: &[&str] = &["bosses/foundry_golem.boss.ron"];
+
+#[cfg(target_arch = "wasm32")]
+fn load_bosses_system(asset_server: Res<AssetServer>, mut library: ResMut<BossLibrary>) {
+    library.handles = BOSSES.iter().map(|path| asset_server.load(*path)).collect();
+}
+
+fn boss_bar_system(
+    game: Res<Game>,
+    state: Res<State<AppState>>,
+    bosses: Query<(&Boss, &Stats)>,
+    mut bars: Query<&mut Visibility, With<BossBar>>,
+    mut names: Query<&mut Text, With<BossName>>,
+    mut health: Query<&mut Style, With<BossHealth>>,
+) {
+    let in_battle = matches!(state.current(), AppState::Battle | AppState::Paused);
+    let boss = bosses.get(game.enemy).ok().filter(|_| in_battle);
+    for mut visibility in &mut bars {
+        visibility.is_visible = boss.is_some();
+    }
+    if let Some((boss, stats)) = boss {
+        for mut text in &mut names {
+            text.sections[0].value = boss.name.clone();
+        }
+        for mut style in &mut health {
+            let fraction = (stats.health / stats.max_health.max(1.0)).clamp(0.0, 1.0);
+            style.size.width = Val::Percent(fraction * 100.0);
+        }
+    }
+}
+
+// Children don't inherit visibility in this version, so every node of the bar
+// is tagged and toggled together.
+fn boss_bar_startup_system(mut commands: Commands, fonts: Res<Fonts>) {
+    let hidden = Visibility { is_visible: false };
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(50.0), Val::Auto),
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    left: Val::Percent(25.0),
+                    top: Val::Px(110.0),
+                    ..default()
+                },
+                flex_direction: FlexDirection::ColumnReverse,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            color: Color::NONE.into(),
+            visibility: hidden.clone(),
+            ..default()
+        })
+        .insert_bundle((
+            BossBar,
+            Scaled {
+                top: Some(110.0),
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn_bundle(TextBundle {
+                    text: Text::from_section(
+                        "",
+                        TextStyle {
+                            font: fonts.bold(),
+                            font_size: 32.0,
+                            color: Color::ORANGE_RED,
+                        },
+                    ),
+                    visibility: hidden.clone(),
+                    ..default()
+                })
+                .insert_bundle((BossBar, BossName, Scaled::font(32.0)));
+            parent
+                .spawn_bundle(NodeBundle {
+                    style: Style {
+                        size: Size::new(Val::Percent(100.0), Val::Px(18.0)),
+                        padding: UiRect::all(Val::Px(3.0)),
+                        ..default()
+                    },
+                    color: Color::rgb(0.15, 0.05, 0.05).into(),
+                    visibility: hidden.clone(),
+                    ..default()
+                })
+                .insert_bundle((
+                    BossBar,
+                    Scaled {
+                        height: Some(18.0),
+                        ..default()
+                    },
+                ))
+                .with_children(|parent| {
+                    parent
+                        .spawn_bundle(NodeBundle {
+                            style: Style {
+                                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                                ..default()
+                            },
+                            color: Color::rgb(0.8, 0.1, 0.1).into(),
+                            visibility: hidden.clone(),
+                            ..default()
+                        })
+                        .insert_bundle((BossBar, BossHealth));
+                });
+        });
+}
+
+pub struct BossPlugin;
+
+impl Plugin for BossPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_asset::<BossDef>()
+            .init_asset_loader::<BossLoader>()
+            .init_resource::<BossLibrary>()
+            .add_startup_system(load_bosses_system)
+            .add_startup_system_to_stage(StartupStage::PostStartup, boss_bar_startup_system)
+            .add_system_to_stage(CoreStage::PostUpdate, boss_bar_system);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn boss_assets_parse() {
+        let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/assets/bosses");
+        let mut count = 0;
+        for entry in std::fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            let contents = std::fs::read_to_string(&path).unwrap();
+            let boss = ron::from_str::<BossDef>(&contents)
+                .unwrap_or_else(|err| panic!("{}: {}", path.display(), err));
+            if let Err(err) = boss.body.validate() {
+                panic!("{}: {}", path.display(), err);
+            }
+            count += 1;
+        }
+        assert!(count > 0);
+    }
+}
@@ -0,0 +1,309 @@
+use bevy::{ecs::schedule::SystemLabel, prelude::*};
+
+use crate::{
+    body::{compute_stats, Body, Stats},
+    locale::t,
+};
+
+// Shortest a skill can take, a zero would finish it the frame it starts.
+pub const MIN_SKILL_TIME: f32 = 0.05;
+
+// Systems folding a modifier into freshly computed stats, like mutators,
+// weather and boosters. `finalize_system` runs after all of them.
+#[derive(SystemLabel, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct StatModifiers;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Stat {
+    MaxHealth,
+    MaxEnergy,
+    MaxOvershield,
+    OvershieldDraw,
+    Weight,
+    Width,
+    Speed,
+    ReactionTime,
+    CloseAccuracy,
+    FarAccuracy,
+    JumpForce,
+}
+
+// Where a stat has to end up. Above the knee every extra point counts for
+// less and less, the value closes in on `max` without jumping to it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct StatBounds {
+    pub min: f32,
+    pub max: f32,
+    pub knee: Option<f32>,
+}
+
+impl StatBounds {
+    const fn hard(min: f32, max: f32) -> Self {
+        Self {
+            min,
+            max,
+            knee: None,
+        }
+    }
+
+    const fn soft(min: f32, knee: f32, max: f32) -> Self {
+        Self {
+            min,
+            max,
+            knee: Some(knee),
+        }
+    }
+
+    // NaN ends up at the minimum, infinity at the maximum.
+    pub fn apply(&self, value: f32) -> f32 {
+        if value.is_nan() {
+            return self.min;
+        }
+        let value = match self.knee {
+            // Same slope as the uncapped value at the knee.
+            Some(knee) if value > knee => {
+                let room = self.max - knee;
+                knee + room * (1.0 - (-(value - knee) / room).exp())
+            }
+            _ => value,
+        };
+        value.clamp(self.min, self.max)
+    }
+}
+
+impl Stat {
+    pub const ALL: [Stat; 11] = [
+        Stat::MaxHealth,
+        Stat::MaxEnergy,
+        Stat::MaxOvershield,
+        Stat::OvershieldDraw,
+        Stat::Weight,
+        Stat::Width,
+        Stat::Speed,
+        Stat::ReactionTime,
+        Stat::CloseAccuracy,
+        Stat::FarAccuracy,
+        Stat::JumpForce,
+    ];
+
+    // Also picks the `stat.<id>` strings.
+    pub fn id(self) -> &'static str {
+        match self {
+            Stat::MaxHealth => "max_health",
+            Stat::MaxEnergy => "max_energy",
+            Stat::MaxOvershield => "max_overshield",
+            Stat::OvershieldDraw => "overshield_draw",
+            Stat::Weight => "weight",
+            Stat::Width => "width",
+            Stat::Speed => "speed",
+            Stat::ReactionTime => "reaction_time",
+            Stat::CloseAccuracy => "close_accuracy",
+            Stat::FarAccuracy => "far_accuracy",
+            Stat::JumpForce => "jump_force",
+        }
+    }
+
+    // Drained parts can take max energy below zero, that's what makes a
+    // body lose energy over time, see `energy::regen_per_second`.
+    pub fn bounds(self) -> StatBounds {
+        match self {
+            Stat::MaxHealth => StatBounds::hard(1.0, 5000.0),
+            Stat::MaxEnergy => StatBounds::soft(-1000.0, 400.0, 1000.0),
+            Stat::MaxOvershield => StatBounds::hard(0.0, 2000.0),
+            Stat::OvershieldDraw => StatBounds::hard(0.0, 1000.0),
+            Stat::Weight => StatBounds::hard(1.0, 5000.0),
+            Stat::Width => StatBounds::hard(0.05, 10.0),
+            Stat::Speed => StatBounds::soft(0.0, 15.0, 30.0),
+            Stat::ReactionTime => StatBounds::hard(0.01, 5.0),
+            Stat::CloseAccuracy | Stat::FarAccuracy => StatBounds::hard(0.0, 1.0),
+            Stat::JumpForce => StatBounds::soft(0.0, 80.0, 150.0),
+        }
+    }
+
+    pub fn value(self, stats: &Stats) -> f32 {
+        match self {
+            Stat::MaxHealth => stats.max_health,
+            Stat::MaxEnergy => stats.max_energy,
+            Stat::MaxOvershield => stats.max_overshield,
+            Stat::OvershieldDraw => stats.overshield_draw,
+            Stat::Weight => stats.weight,
+            Stat::Width => stats.width,
+            Stat::Speed => stats.speed,
+            Stat::ReactionTime => stats.reaction_time,
+            Stat::CloseAccuracy => stats.close_accuracy,
+            Stat::FarAccuracy => stats.far_accuracy,
+            Stat::JumpForce => stats.jump_force,
+        }
+    }
+
+    fn value_mut(self, stats: &mut Stats) -> &mut f32 {
+        match self {
+            Stat::MaxHealth => &mut stats.max_health,
+            Stat::MaxEnergy => &mut stats.max_energy,
+            Stat::MaxOvershield => &mut stats.max_overshield,
+            Stat::OvershieldDraw => &mut stats.overshield_draw,
+            Stat::Weight => &mut stats.weight,
+            Stat::Width => &mut stats.width,
+            Stat::Speed => &mut stats.speed,
+            Stat::ReactionTime => &mut stats.reaction_time,
+            Stat::CloseAccuracy => &mut stats.close_accuracy,
+            Stat::FarAccuracy => &mut stats.far_accuracy,
+            Stat::JumpForce => &mut stats.jump_force,
+        }
+    }
+}
+
+// A stat `finalize` moved, with what it would have been.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Capped {
+    pub stat: Stat,
+    pub uncapped: f32,
+}
+
+impl Capped {
+    // "speed 14.2, soft-capped from 19.8".
+    pub fn describe(&self, stats: &Stats) -> String {
+        let key = match self.stat.bounds().knee {
+            Some(knee) if self.uncapped > knee => "stat.soft_capped",
+            _ => "stat.capped",
+        };
+        t!(
+            key,
+            stat = t!(format!("stat.{}", self.stat.id())),
+            value = self.stat.value(stats),
+            uncapped = self.uncapped
+        )
+    }
+}
+
+// The lines for every stat `finalize` held back, for tooltips.
+pub fn describe_capped(stats: &Stats) -> impl Iterator<Item = String> + '_ {
+    stats.capped.iter().map(|capped| capped.describe(stats))
+}
+
+// The one place stats are held to their bounds, once the parts and every
+// modifier are folded in. Running it twice would soft-cap twice, so it only
+// runs on stats fresh from `compute_stats`.
+pub fn finalize(stats: &mut Stats) {
+    stats.capped.clear();
+    for stat in Stat::ALL {
+        let value = stat.value_mut(stats);
+        let uncapped = *value;
+        *value = stat.bounds().apply(uncapped);
+        if *value != uncapped {
+            stats.capped.push(Capped { stat, uncapped });
+        }
+    }
+    let full = |value: f32, max: f32| if value.is_nan() { max } else { value.min(max) };
+    stats.health = full(stats.health, stats.max_health).max(0.0);
+    stats.energy = full(stats.energy, stats.max_energy.max(0.0)).max(0.0);
+    stats.overshield = full(stats.overshield, stats.max_overshield).max(0.0);
+    for ability in stats
+        .skills
+        .iter_mut()
+        .filter_map(|skill| skill.ability_mut())
+    {
+        let sane = |value: f32| if value.is_nan() { 0.0 } else { value };
+        ability.time = sane(ability.time).max(MIN_SKILL_TIME);
+        ability.cooldown = sane(ability.cooldown).max(0.0);
+        ability.energy_cost = sane(ability.energy_cost);
+        ability.meta = sane(ability.meta);
+    }
+}
+
+// What the editor shows for a body, the battle ends up with the same numbers
+// before the mutators, weather and boosters come in.
+pub fn bounded_stats(body: &Body) -> Stats {
+    let mut stats = compute_stats(body);
+    finalize(&mut stats);
+    stats
+}
+
+pub fn finalize_system(mut bodies: Query<&mut Stats, Changed<Body>>) {
+    for mut stats in &mut bodies {
+        finalize(&mut stats);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        body::Limb,
+        booster::{Booster, Boosters},
+        fixtures::seeded_bodies,
+        mutators::{Mutator, Mutators},
+        weather::{self, Weather},
+    };
+
+    #[test]
+    fn soft_caps_bend_and_hard_caps_hold() {
+        let speed = Stat::Speed.bounds();
+        assert_eq!(speed.apply(10.0), 10.0);
+        let soft = speed.apply(19.8);
+        assert!(soft > 15.0 && soft < 19.8, "{}", soft);
+        assert!(speed.apply(25.0) > soft);
+        assert_eq!(speed.apply(f32::INFINITY), 30.0);
+        assert_eq!(speed.apply(-3.0), 0.0);
+        assert_eq!(speed.apply(f32::NAN), 0.0);
+    }
+
+    // Every generated body under every combination of modifiers there is.
+    #[test]
+    fn no_body_and_modifiers_break_the_bounds() {
+        let bodies = [(1, 1), (2, 2), (4, 4), (8, 8)]
+            .into_iter()
+            .flat_map(|(arms, legs)| seeded_bodies(25, arms, legs));
+        let mutator_sets = (0..1 << Mutator::ALL.len()).map(|bits: u32| {
+            let mut mutators = Mutators::default();
+            for (i, mutator) in Mutator::ALL.into_iter().enumerate() {
+                if bits & 1 << i != 0 {
+                    mutators.toggle(mutator);
+                }
+            }
+            mutators
+        });
+        let mutator_sets = mutator_sets.collect::<Vec<_>>();
+        let boosters = [Boosters::default(), Boosters::with_active(&Booster::ALL)];
+        let weathers = [
+            Weather::Clear,
+            Weather::Rain,
+            Weather::Night,
+            Weather::Sandstorm,
+        ];
+        for mut body in bodies {
+            // Torn down to the torso and head as well.
+            let whole = body.clone();
+            for slot in 0..8 {
+                body.destroy(Limb::Arm(slot));
+                body.destroy(Limb::Leg(slot));
+            }
+            for body in [whole, body] {
+                let raw = compute_stats(&body);
+                for mutators in &mutator_sets {
+                    for weather in weathers {
+                        for boosters in &boosters {
+                            let mut stats = raw.clone();
+                            mutators.apply(&mut stats);
+                            weather::apply(weather, &mut stats);
+                            boosters.apply(&mut stats);
+                            finalize(&mut stats);
+                            for stat in Stat::ALL {
+                                let value = stat.value(&stats);
+                                let bounds = stat.bounds();
+                                assert!(!value.is_nan(), "{} is NaN", stat.id());
+                                assert!(value >= bounds.min && value <= bounds.max);
+                            }
+                            assert!(stats.max_health > 0.0);
+                            assert!(stats.health >= 0.0 && stats.health <= stats.max_health);
+                            for ability in stats.skills.iter().filter_map(|skill| skill.ability()) {
+                                assert!(ability.time >= MIN_SKILL_TIME);
+                                assert!(!ability.energy_cost.is_nan());
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,384 @@
+use bevy::{
+    input::mouse::{MouseScrollUnit, MouseWheel},
+    prelude::*,
+    render::camera::ScalingMode,
+    utils::Instant,
+};
+
+use crate::{body::Body, layer::CAMERA_Z, locale::t, ui::Toast, AppState, Game};
+
+// Seconds a mode switch takes to blend from the old view to the new one.
+const BLEND_TIME: f32 = 0.3;
+// Zoom the follow modes hold, close enough to see the limbs.
+const FOLLOW_SCALE: f32 = 5.0;
+// Visible width at a scale of 1, see camera_setup_system.
+const VIEW_WIDTH: f32 = 5.0;
+// Share of the view the auto framing leaves free around the bodies, with more
+// above them for the nameplates.
+const MARGIN: f32 = 0.08;
+const LABEL_ROOM: f32 = 0.15;
+// Bodies shorter than this are framed closer up, down to this share of the
+// usual zoom.
+const CLOSE_UP_HEIGHT: f32 = 2.0;
+const MAX_CLOSE_UP: f32 = 0.5;
+// World units per second at a scale of 1, panning speeds up as the camera
+// zooms out so it feels the same at any zoom.
+const PAN_SPEED: f32 = 2.5;
+const ZOOM_STEP: f32 = 0.9;
+const MIN_SCALE: f32 = 1.5;
+const MAX_SCALE: f32 = 20.0;
+// Keeps free look over the arena floor, see arena_setup_system.
+const MAX_X: f32 = 20.0;
+const MIN_Y: f32 = -2.0;
+const MAX_Y: f32 = 15.0;
+// Furthest a shake throws the view at a scale of 1, and the share of a full
+// shake that wears off every second.
+const MAX_SHAKE: f32 = 0.04;
+const SHAKE_DECAY: f32 = 2.5;
+
+// What the camera shows, C cycles through them during a battle.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CameraMode {
+    // Keeps both bodies in view.
+    #[default]
+    Auto,
+    FollowPlayer,
+    FollowEnemy,
+    // Panned with WASD and zoomed with the scroll wheel.
+    Free,
+}
+
+impl CameraMode {
+    fn next(self) -> Self {
+        match self {
+            CameraMode::Auto => CameraMode::FollowPlayer,
+            CameraMode::FollowPlayer => CameraMode::FollowEnemy,
+            CameraMode::FollowEnemy => CameraMode::Free,
+            CameraMode::Free => CameraMode::Auto,
+        }
+    }
+
+    fn name(self) -> String {
+        t!(match self {
+            CameraMode::Auto => "camera.auto",
+            CameraMode::FollowPlayer => "camera.follow_player",
+            CameraMode::FollowEnemy => "camera.follow_enemy",
+            CameraMode::Free => "camera.free",
+        })
+    }
+}
+
+// A view is where the camera looks and how far out it's zoomed.
+type View = (Vec2, f32);
+
+// Runs on wall time like the intro, so the camera still moves while the
+// battle clock is paused or driven by netplay.
+pub struct CameraRig {
+    // Where free look is.
+    free: View,
+    // What the camera showed when the mode last switched, and how far the
+    // blend away from it has got.
+    from: View,
+    blend: f32,
+    last_frame: Option<Instant>,
+    // From 0 to 1, the view is thrown about by its square so small knocks
+    // barely show.
+    shake: f32,
+    shaken_for: f32,
+}
+
+impl Default for CameraRig {
+    fn default() -> Self {
+        Self {
+            free: (Vec2::ZERO, 8.0),
+            from: (Vec2::ZERO, 8.0),
+            blend: 1.0,
+            last_frame: None,
+            shake: 0.0,
+            shaken_for: 0.0,
+        }
+    }
+}
+
+impl CameraRig {
+    pub fn shake(&mut self, amount: f32) {
+        self.shake = (self.shake + amount).clamp(0.0, 1.0);
+    }
+
+    // Wobbles on a few sines rather than random jumps, so it reads as one
+    // rattle instead of a flicker.
+    fn shake_offset(&mut self, dt: f32) -> Vec2 {
+        self.shake = (self.shake - SHAKE_DECAY * dt).max(0.0);
+        self.shaken_for += dt;
+        let t = self.shaken_for;
+        Vec2::new((t * 47.0).sin(), (t * 61.0 + 1.3).sin()) * self.shake * self.shake * MAX_SHAKE
+    }
+}
+
+// The arena camera, set apart from the ones rendering to images.
+#[derive(Component)]
+pub struct MainCamera;
+
+fn camera_setup_system(mut commands: Commands) {
+    commands
+        .spawn_bundle(Camera2dBundle {
+            // Zooming only scales x and y, the layers stay in view at any zoom.
+            transform: Transform::from_scale(Vec3::new(5.0, 5.0, 1.0))
+                .with_translation(Vec3::new(0.0, 0.0, CAMERA_Z)),
+            projection: OrthographicProjection {
+                scaling_mode: ScalingMode::FixedHorizontal(VIEW_WIDTH),
+                ..default()
+            },
+            ..default()
+        })
+        .insert(MainCamera);
+}
+
+// Width over height of the window, the camera's view has the same shape.
+pub fn aspect(windows: &Windows) -> f32 {
+    windows
+        .get_primary()
+        .filter(|window| window.height() > 0.0)
+        .map_or(16.0 / 9.0, |window| window.width() / window.height())
+}
+
+// Visible height at `scale` in a window of `aspect`.
+pub fn view_height(scale: f32, aspect: f32) -> f32 {
+    VIEW_WIDTH * scale / aspect
+}
+
+// Where the camera looks and how far out it's zoomed to keep every one of
+// `bodies` in view, however big or small, with room for their nameplates.
+pub fn framing(bodies: &[(&Body, Vec3)], aspect: f32) -> View {
+    let mut min = Vec2::splat(f32::INFINITY);
+    let mut max = Vec2::splat(f32::NEG_INFINITY);
+    let (mut left, mut right) = (f32::INFINITY, f32::NEG_INFINITY);
+    let (mut ground, mut tallest) = (0.0, 0.0f32);
+    for (body, at) in bodies {
+        let (low, high) = body.bounds();
+        min = min.min(at.truncate() + low);
+        max = max.max(at.truncate() + high);
+        left = left.min(at.x);
+        right = right.max(at.x);
+        ground += at.y / bodies.len() as f32;
+        tallest = tallest.max(body.height());
+    }
+    if bodies.is_empty() {
+        return (Vec2::ZERO, 8.0);
+    }
+    let view_height = |scale: f32| view_height(scale, aspect);
+    let close_up = (tallest / CLOSE_UP_HEIGHT).clamp(MAX_CLOSE_UP, 1.0);
+    let scale = ((right - left) / 6.0 + 8.0 * close_up)
+        .max((max.x - min.x) / (VIEW_WIDTH * (1.0 - 2.0 * MARGIN)))
+        .max((max.y - min.y) / (view_height(1.0) * (1.0 - 2.0 * MARGIN - LABEL_ROOM)));
+    // Looks at the ground between them unless that cuts a body off.
+    let height = view_height(scale);
+    let y = ground
+        .max(max.y - height * (0.5 - MARGIN - LABEL_ROOM))
+        .min(min.y + height * (0.5 - MARGIN));
+    (Vec2::new((min.x + max.x) / 2.0, y), scale)
+}
+
+// Where in the world the window's cursor points, the inverse of
+// `Camera::world_to_viewport`.
+pub fn cursor_world(camera: &Camera, transform: &GlobalTransform, window: &Window) -> Option<Vec2> {
+    let size = Vec2::new(window.width(), window.height());
+    let ndc = window.cursor_position()? / size * 2.0 - Vec2::ONE;
+    let to_world = transform.compute_matrix() * camera.projection_matrix().inverse();
+    Some(to_world.project_point3(ndc.extend(0.0)).truncate())
+}
+
+// Looks at the middle of the body, however tall it stands.
+fn follow(body: Vec3, height: f32) -> View {
+    (body.truncate() + Vec2::Y * height / 2.0, FOLLOW_SCALE)
+}
+
+fn clamp_free((centre, scale): View) -> View {
+    (
+        Vec2::new(centre.x.clamp(-MAX_X, MAX_X), centre.y.clamp(MIN_Y, MAX_Y)),
+        scale.clamp(MIN_SCALE, MAX_SCALE),
+    )
+}
+
+// Eased part of the way from one view to the other.
+fn blend((from, from_scale): View, (to, to_scale): View, t: f32) -> View {
+    let t = t.clamp(0.0, 1.0);
+    let t = t * t * (3.0 - 2.0 * t);
+    (from.lerp(to, t), from_scale + (to_scale - from_scale) * t)
+}
+
+fn reset_mode_system(mut mode: ResMut<CameraMode>, mut rig: ResMut<CameraRig>) {
+    *mode = CameraMode::Auto;
+    rig.blend = 1.0;
+}
+
+// Only while a battle is on screen, where there's something to look at and no
+// text field taking the keys.
+fn camera_input_system(
+    state: Res<State<AppState>>,
+    keys: Res<Input<KeyCode>>,
+    mut wheel: EventReader<MouseWheel>,
+    mut mode: ResMut<CameraMode>,
+    mut rig: ResMut<CameraRig>,
+    mut toasts: EventWriter<Toast>,
+    cameras: Query<&Transform, With<MainCamera>>,
+) {
+    let scrolled = wheel
+        .iter()
+        .map(|event| match event.unit {
+            MouseScrollUnit::Line => event.y,
+            MouseScrollUnit::Pixel => event.y / 100.0,
+        })
+        .sum::<f32>();
+    if !matches!(state.current(), AppState::Battle | AppState::Paused) {
+        return;
+    }
+    let Ok(camera) = cameras.get_single() else {
+        return;
+    };
+    let shown = (camera.translation.truncate(), camera.scale.x);
+    if keys.just_pressed(KeyCode::C) {
+        *mode = mode.next();
+        rig.from = shown;
+        rig.blend = 0.0;
+        if *mode == CameraMode::Free {
+            rig.free = clamp_free(shown);
+        }
+        toasts.send(Toast(t!("camera.switched", mode = mode.name())));
+    }
+    if *mode != CameraMode::Free {
+        return;
+    }
+    let now = Instant::now();
+    let dt = rig.last_frame.map_or(0.0, |last| {
+        now.saturating_duration_since(last).as_secs_f32()
+    });
+    let mut pan = Vec2::ZERO;
+    for (key, direction) in [
+        (KeyCode::W, Vec2::Y),
+        (KeyCode::A, -Vec2::X),
+        (KeyCode::S, -Vec2::Y),
+        (KeyCode::D, Vec2::X),
+    ] {
+        if keys.pressed(key) {
+            pan += direction;
+        }
+    }
+    let (centre, scale) = rig.free;
+    rig.free = clamp_free((
+        centre + pan * PAN_SPEED * scale * dt,
+        scale * ZOOM_STEP.powf(scrolled),
+    ));
+}
+
+// Moves the camera to what the mode shows, blending after a switch.
+pub fn dynamic_camera(
+    game: Res<Game>,
+    windows: Res<Windows>,
+    mode: Res<CameraMode>,
+    mut rig: ResMut<CameraRig>,
+    mut camera_transform: Query<&mut Transform, With<MainCamera>>,
+    bodies: Query<(&Body, &Transform), Without<MainCamera>>,
+) {
+    let now = Instant::now();
+    let dt = rig.last_frame.map_or(0.0, |last| {
+        now.saturating_duration_since(last).as_secs_f32()
+    });
+    rig.last_frame = Some(now);
+    let Ok(mut camera_transform) = camera_transform.get_single_mut() else {
+        return;
+    };
+    let Ok([(player_body, player), (enemy_body, enemy)]) =
+        bodies.get_many([game.player, game.enemy])
+    else {
+        return;
+    };
+    let (player_height, enemy_height) = (player_body.height(), enemy_body.height());
+    let target = match *mode {
+        CameraMode::Auto => framing(
+            &[
+                (player_body, player.translation),
+                (enemy_body, enemy.translation),
+            ],
+            aspect(&windows),
+        ),
+        CameraMode::FollowPlayer => follow(player.translation, player_height),
+        CameraMode::FollowEnemy => follow(enemy.translation, enemy_height),
+        CameraMode::Free => rig.free,
+    };
+    rig.blend = (rig.blend + dt / BLEND_TIME).min(1.0);
+    let (look_at_pos, scale) = blend(rig.from, target, rig.blend);
+    let look_at_pos = look_at_pos + rig.shake_offset(dt) * scale;
+    camera_transform.translation.x = look_at_pos.x;
+    camera_transform.translation.y = look_at_pos.y;
+    camera_transform.scale = Vec3::new(scale, scale, 1.0);
+}
+
+pub struct CameraPlugin;
+
+impl Plugin for CameraPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CameraMode>()
+            .init_resource::<CameraRig>()
+            .add_startup_system(camera_setup_system)
+            .add_system_set(SystemSet::on_enter(AppState::Battle).with_system(reset_mode_system))
+            .add_system(camera_input_system.before(dynamic_camera))
+            .add_system(dynamic_camera);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn switches_blend_without_jumping() {
+        let from = (Vec2::new(-3.0, 1.0), 8.0);
+        let to = (Vec2::new(5.0, 2.0), 4.0);
+        assert_eq!(blend(from, to, 0.0), from);
+        assert_eq!(blend(from, to, 1.0), to);
+        assert_eq!(blend(from, to, 2.0), to);
+        let mut last = from;
+        for frame in 1..=18 {
+            let next = blend(from, to, frame as f32 / 60.0 / BLEND_TIME);
+            assert!(next.0.distance(last.0) < 1.0 && (next.1 - last.1).abs() < 0.5);
+            last = next;
+        }
+        assert_eq!(last, to);
+        // Free look can't leave the arena or zoom out forever.
+        let (centre, scale) = clamp_free((Vec2::new(100.0, -50.0), 0.1));
+        assert_eq!(centre, Vec2::new(MAX_X, MIN_Y));
+        assert_eq!(scale, MIN_SCALE);
+    }
+
+    #[test]
+    fn framing_keeps_tiny_and_huge_bodies_in_view() {
+        let (player, enemy) = (Vec3::new(-4.0, 0.0, 0.0), Vec3::new(4.0, 0.0, 0.0));
+        let aspect = 16.0 / 9.0;
+        let sized = |size| {
+            let mut body = Body::default();
+            body.resize(size);
+            body
+        };
+        let (tiny, huge, default) = (sized(0.5), sized(3.0), Body::default());
+        let in_view = |(look_at, scale): View, body: &Body, at: Vec3| {
+            let half = Vec2::new(VIEW_WIDTH, VIEW_WIDTH / aspect) * scale / 2.0;
+            let (low, high) = body.bounds();
+            let (low, high) = (at.truncate() + low, at.truncate() + high);
+            low.cmpge(look_at - half).all() && high.cmple(look_at + half).all()
+        };
+        let normal = framing(&[(&default, player), (&default, enemy)], aspect);
+        assert_eq!(normal, (Vec2::ZERO, 8.0 + 8.0 / 6.0));
+        let mixed = framing(&[(&tiny, player), (&huge, enemy)], aspect);
+        assert!(in_view(mixed, &tiny, player) && in_view(mixed, &huge, enemy));
+        assert!(mixed.1 >= normal.1);
+        // Past what fits, a giant is looked up at and pulled back from.
+        let giant = sized(10.0);
+        let towering = framing(&[(&default, player), (&giant, enemy)], aspect);
+        assert!(in_view(towering, &giant, enemy) && in_view(towering, &default, player));
+        assert!(towering.0.y > normal.0.y && towering.1 > normal.1);
+        // Two tiny ones are framed closer up than two of the usual size.
+        let close = framing(&[(&tiny, player), (&tiny, enemy)], aspect);
+        assert!(close.1 < normal.1 && in_view(close, &tiny, player));
+    }
+}
@@ -0,0 +1,754 @@
+use std::collections::{BTreeSet, HashMap};
+
+use bevy::{
+    asset::{AssetLoader, BoxedFuture, LoadContext, LoadedAsset},
+    ecs::entity::Entities,
+    prelude::*,
+    reflect::TypeUuid,
+    ui::FocusPolicy,
+};
+use rand::{rngs::StdRng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    attachment::Attachment,
+    body::{random_body_with, Body, GenerationConfig, WeightClass},
+    booster::Booster,
+    error::{report, ErrorToast, GameError},
+    focus::FocusScope,
+    locale::t,
+    mutators::{Mutator, Mutators},
+    profile::{record_result_system, Profile},
+    swap::SwapBody,
+    theme::Theme,
+    turn::Ruleset,
+    ui::{Fonts, Scaled, Toast},
+    weather::Weather,
+    AppState, BattleSummary, Game,
+};
+
+pub const CAMPAIGN_FILE: &str = "campaign/foundry.campaign.ron";
+
+// The campaign file as it's written, see assets/campaign.
+#[derive(Deserialize)]
+struct CampaignDef {
+    id: String,
+    // Body file of the archetype clearing every encounter unlocks.
+    archetype: String,
+    encounters: Vec<EncounterDef>,
+}
+
+#[derive(Deserialize)]
+struct EncounterDef {
+    id: String,
+    enemy: EnemyDef,
+    #[serde(default)]
+    arena: Weather,
+    #[serde(default)]
+    mutators: Vec<Mutator>,
+    #[serde(default)]
+    before: Vec<Line>,
+    #[serde(default)]
+    after: Vec<Line>,
+    #[serde(default)]
+    reward: Reward,
+}
+
+#[derive(Deserialize)]
+enum EnemyDef {
+    // A body file under assets/.
+    File(String),
+    // Rolled from the seed, the same body every time.
+    Generate {
+        class: Option<WeightClass>,
+        seed: u64,
+    },
+}
+
+impl EnemyDef {
+    fn file(&self) -> Option<&str> {
+        match self {
+            EnemyDef::File(path) => Some(path),
+            EnemyDef::Generate { .. } => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum Speaker {
+    Player,
+    Enemy,
+}
+
+// One text box, `text` is a key into assets/lang.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Line {
+    pub speaker: Speaker,
+    pub text: String,
+}
+
+// Paid out the first time an encounter is cleared.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct Reward {
+    pub scrap: u32,
+    pub boosters: Vec<Booster>,
+    pub attachments: Vec<Attachment>,
+}
+
+impl Reward {
+    fn pay(&self, profile: &mut Profile) {
+        profile.scrap += self.scrap;
+        for booster in &self.boosters {
+            *profile.boosters.entry(*booster).or_default() += 1;
+        }
+        for attachment in &self.attachments {
+            *profile.attachments.entry(*attachment).or_default() += 1;
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        let scrap = (self.scrap > 0).then(|| t!("campaign.scrap", scrap = self.scrap));
+        let items = self
+            .boosters
+            .iter()
+            .map(|booster| booster.name())
+            .chain(self.attachments.iter().map(|attachment| attachment.name()));
+        scrap
+            .into_iter()
+            .chain(items)
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+pub struct Encounter {
+    pub id: String,
+    pub enemy: Body,
+    pub arena: Weather,
+    pub mutators: Mutators,
+    pub before: Vec<Line>,
+    pub after: Vec<Line>,
+    pub reward: Reward,
+}
+
+impl Encounter {
+    pub fn title(&self) -> String {
+        t!(format!("campaign.{}.title", self.id))
+    }
+}
+
+pub struct Campaign {
+    pub id: String,
+    pub encounters: Vec<Encounter>,
+    pub archetype: Body,
+}
+
+impl Campaign {
+    pub fn status(&self, progress: &CampaignProgress, index: usize) -> NodeStatus {
+        let cleared = |encounter: &Encounter| progress.cleared.contains(&encounter.id);
+        if cleared(&self.encounters[index]) {
+            NodeStatus::Cleared
+        } else if self.encounters[..index].iter().all(cleared) {
+            NodeStatus::Open
+        } else {
+            NodeStatus::Locked
+        }
+    }
+
+    pub fn completed(&self, progress: &CampaignProgress) -> bool {
+        progress.completed.contains(&self.id)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NodeStatus {
+    Cleared,
+    // The next one to fight, every one before it is cleared.
+    Open,
+    Locked,
+}
+
+// A file that's missing or broken is named with what needed it.
+fn read_body(
+    files: &HashMap<String, Result<Vec<u8>, String>>,
+    path: &str,
+    needed_by: &str,
+) -> Result<Body, GameError> {
+    let bytes = match files.get(path) {
+        Some(Ok(bytes)) => bytes,
+        Some(Err(reason)) => {
+            return Err(GameError::io(path, format!("{}: {}", needed_by, reason)));
+        }
+        None => return Err(GameError::io(path, format!("{}: not read", needed_by))),
+    };
+    let body = ron::de::from_bytes::<Body>(bytes).map_err(|err| GameError::parse(path, err))?;
+    let problems = body.problems();
+    if !problems.is_empty() {
+        return Err(GameError::Validation(problems));
+    }
+    Ok(body)
+}
+
+// Every body file the campaign names, `files` holds what reading each gave.
+fn resolve(
+    def: CampaignDef,
+    files: &HashMap<String, Result<Vec<u8>, String>>,
+) -> Result<Campaign, GameError> {
+    let encounters = def
+        .encounters
+        .into_iter()
+        .map(|encounter| {
+            let needed_by = format!("enemy of encounter `{}`", encounter.id);
+            let enemy = match &encounter.enemy {
+                EnemyDef::File(path) => read_body(files, path, &needed_by)?,
+                EnemyDef::Generate { class, seed } => random_body_with(
+                    &mut StdRng::seed_from_u64(*seed),
+                    &GenerationConfig {
+                        class: *class,
+                        ..default()
+                    },
+                ),
+            };
+            let mut mutators = Mutators::default();
+            for mutator in encounter.mutators {
+                if !mutators.contains(mutator) {
+                    mutators.toggle(mutator);
+                }
+            }
+            Ok(Encounter {
+                id: encounter.id,
+                enemy,
+                arena: encounter.arena,
+                mutators,
+                before: encounter.before,
+                after: encounter.after,
+                reward: encounter.reward,
+            })
+        })
+        .collect::<Result<Vec<_>, GameError>>()?;
+    let archetype = read_body(files, &def.archetype, "campaign archetype")?;
+    Ok(Campaign {
+        id: def.id,
+        encounters,
+        archetype,
+    })
+}
+
+// What loading the campaign file gave. Broken references are caught here,
+// the campaign screen shows what's wrong instead of failing mid-campaign.
+#[derive(TypeUuid)]
+#[uuid = "4f7d2c1a-93b5-4e0f-a6c8-1d2e3f4a5b6c"]
+pub struct CampaignFile(pub Result<Campaign, GameError>);
+
+#[derive(Default)]
+struct CampaignLoader;
+
+impl AssetLoader for CampaignLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let file = load_context.path().display().to_string();
+            let campaign = match ron::de::from_bytes::<CampaignDef>(bytes) {
+                Ok(def) => {
+                    let paths = def
+                        .encounters
+                        .iter()
+                        .filter_map(|encounter| encounter.enemy.file())
+                        .chain([def.archetype.as_str()])
+                        .map(str::to_string)
+                        .collect::<BTreeSet<_>>();
+                    let mut files = HashMap::new();
+                    for path in paths {
+                        let read = load_context.read_asset_bytes(&path).await;
+                        files.insert(path, read.map_err(|err| err.to_string()));
+                    }
+                    resolve(def, &files)
+                }
+                Err(err) => Err(GameError::parse(&file, err)),
+            };
+            load_context.set_default_asset(LoadedAsset::new(CampaignFile(campaign)));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["campaign.ron"]
+    }
+}
+
+pub struct CampaignHandle(pub Handle<CampaignFile>);
+
+fn load_campaign_system(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(CampaignHandle(asset_server.load(CAMPAIGN_FILE)));
+}
+
+// Kept in the profile, by id so reordering the file doesn't lose any.
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CampaignProgress {
+    pub cleared: BTreeSet<String>,
+    // Campaigns with every encounter cleared, their archetype is unlocked.
+    pub completed: BTreeSet<String>,
+}
+
+// What clearing an encounter did.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Cleared {
+    // Only the first clear pays out.
+    pub paid: bool,
+    pub completed: bool,
+}
+
+// Marks the encounter cleared, paying its reward the first time and
+// unlocking the archetype once every one is.
+pub fn clear(profile: &mut Profile, campaign: &Campaign, index: usize) -> Cleared {
+    let encounter = &campaign.encounters[index];
+    let paid = profile.campaign.cleared.insert(encounter.id.clone());
+    if paid {
+        encounter.reward.pay(profile);
+    }
+    let all = campaign
+        .encounters
+        .iter()
+        .all(|encounter| profile.campaign.cleared.contains(&encounter.id));
+    let completed = all && profile.campaign.completed.insert(campaign.id.clone());
+    Cleared { paid, completed }
+}
+
+// An encounter in progress. The enemy and rules from before it are put back
+// once the player is back on the pre-battle screen.
+pub struct CampaignRun {
+    index: usize,
+    enemy: Body,
+    ruleset: Ruleset,
+}
+
+// Swaps the encounter's enemy and rules in, the caller shows the dialogue
+// and starts the battle.
+pub fn begin(
+    commands: &mut Commands,
+    ruleset: &mut Ruleset,
+    swaps: &mut EventWriter<SwapBody>,
+    game: &Game,
+    enemy: Body,
+    (index, encounter): (usize, &Encounter),
+) {
+    let rules = Ruleset {
+        survival: false,
+        arena: Some(encounter.arena),
+        mutators: encounter.mutators,
+        ai: ruleset.ai.clone(),
+        ..*ruleset
+    };
+    commands.insert_resource(CampaignRun {
+        index,
+        enemy,
+        ruleset: std::mem::replace(ruleset, rules),
+    });
+    swaps.send(SwapBody {
+        entity: game.enemy,
+        body: encounter.enemy.clone(),
+    });
+}
+
+// Before the profile is saved with the result. A win is followed by the
+// encounter's closing lines.
+fn record_campaign_system(
+    mut commands: Commands,
+    game: Res<Game>,
+    summary: Res<BattleSummary>,
+    run: Option<Res<CampaignRun>>,
+    handle: Res<CampaignHandle>,
+    files: Res<Assets<CampaignFile>>,
+    mut profile: ResMut<Profile>,
+    mut toasts: EventWriter<Toast>,
+    bodies: Query<&Body>,
+) {
+    let Some(run) = run else {
+        return;
+    };
+    let Some(CampaignFile(Ok(campaign))) = files.get(&handle.0) else {
+        return;
+    };
+    if summary.winner != Some(game.player) {
+        return;
+    }
+    let Some(encounter) = campaign.encounters.get(run.index) else {
+        return;
+    };
+    let cleared = clear(&mut profile, campaign, run.index);
+    if cleared.paid {
+        toasts.send(Toast(t!(
+            "campaign.reward",
+            reward = encounter.reward.describe()
+        )));
+    }
+    if cleared.completed {
+        toasts.send(Toast(t!(
+            "campaign.unlocked",
+            name = campaign.archetype.name()
+        )));
+    }
+    if let Ok([player, enemy]) = bodies.get_many([game.player, game.enemy]) {
+        open_dialogue(&mut commands, &encounter.after, [player, enemy], None);
+    }
+}
+
+fn leave_campaign_system(
+    mut commands: Commands,
+    game: Res<Game>,
+    entities: &Entities,
+    run: Option<ResMut<CampaignRun>>,
+    mut ruleset: ResMut<Ruleset>,
+    mut swaps: EventWriter<SwapBody>,
+) {
+    let Some(mut run) = run else {
+        return;
+    };
+    *ruleset = std::mem::take(&mut run.ruleset);
+    commands.remove_resource::<CampaignRun>();
+    if entities.contains(game.enemy) {
+        swaps.send(SwapBody {
+            entity: game.enemy,
+            body: std::mem::take(&mut run.enemy),
+        });
+    }
+}
+
+// Text boxes shown one after the other over whatever screen is up, each with
+// who's talking and a swatch of their torso's color.
+pub struct Dialogue {
+    lines: Vec<(String, Color, String)>,
+    shown: usize,
+    // Where to go once the last line is read.
+    then: Option<AppState>,
+}
+
+// `bodies` are the player and the enemy, in that order. Nothing opens without
+// lines, the state is set right away instead.
+pub fn open_dialogue(
+    commands: &mut Commands,
+    lines: &[Line],
+    bodies: [&Body; 2],
+    then: Option<AppState>,
+) -> bool {
+    if lines.is_empty() {
+        return false;
+    }
+    let lines = lines
+        .iter()
+        .map(|line| {
+            let body = match line.speaker {
+                Speaker::Player => bodies[0],
+                Speaker::Enemy => bodies[1],
+            };
+            (
+                body.name().to_string(),
+                body.torso().stats().color(),
+                t!(&line.text),
+            )
+        })
+        .collect();
+    commands.insert_resource(Dialogue {
+        lines,
+        shown: 0,
+        then,
+    });
+    true
+}
+
+#[derive(Component)]
+struct DialogueRoot;
+
+#[derive(Component)]
+struct DialogueSwatch;
+
+#[derive(Component)]
+struct DialogueText;
+
+fn spawn_dialogue_system(
+    mut commands: Commands,
+    fonts: Res<Fonts>,
+    theme: Res<Theme>,
+    dialogue: Option<Res<Dialogue>>,
+    roots: Query<(), With<DialogueRoot>>,
+) {
+    if dialogue.is_none() || !roots.is_empty() {
+        return;
+    }
+    // Covers the screen so clicks reach the box and nothing under it.
+    commands
+        .spawn_bundle(ButtonBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                position_type: PositionType::Absolute,
+                flex_direction: FlexDirection::ColumnReverse,
+                justify_content: JustifyContent::FlexStart,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            color: theme.backdrop(0.3).into(),
+            focus_policy: FocusPolicy::Block,
+            ..default()
+        })
+        // Keyboard and gamepad focus stays on the dialogue too.
+        .insert_bundle((DialogueRoot, FocusScope))
+        .with_children(|parent| {
+            parent
+                .spawn_bundle(NodeBundle {
+                    style: Style {
+                        size: Size::new(Val::Percent(70.0), Val::Auto),
+                        margin: UiRect::all(Val::Px(30.0)),
+                        padding: UiRect::all(Val::Px(15.0)),
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    color: Color::rgba(0.0, 0.0, 0.0, 0.85).into(),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent
+                        .spawn_bundle(NodeBundle {
+                            style: Style {
+                                size: Size::new(Val::Px(64.0), Val::Px(64.0)),
+                                margin: UiRect::all(Val::Px(10.0)),
+                                flex_shrink: 0.0,
+                                ..default()
+                            },
+                            ..default()
+                        })
+                        .insert_bundle((DialogueSwatch, Scaled::size(64.0, 64.0)));
+                    parent
+                        .spawn_bundle(TextBundle::from_sections([
+                            TextSection::new(
+                                "",
+                                TextStyle {
+                                    font: fonts.bold(),
+                                    font_size: 24.0,
+                                    color: Color::GOLD,
+                                },
+                            ),
+                            TextSection::new(
+                                "",
+                                TextStyle {
+                                    font: fonts.normal(),
+                                    font_size: 24.0,
+                                    color: Color::WHITE,
+                                },
+                            ),
+                            TextSection::new(
+                                format!("\n{}", t!("campaign.next_line")),
+                                TextStyle {
+                                    font: fonts.normal(),
+                                    font_size: 16.0,
+                                    color: Color::GRAY,
+                                },
+                            ),
+                        ]))
+                        .insert_bundle((DialogueText, Scaled::font(24.0)));
+                });
+        });
+}
+
+// Also when the box is new, it's spawned the frame after the lines are set.
+fn show_line_system(
+    dialogue: Option<Res<Dialogue>>,
+    added: Query<(), Added<DialogueText>>,
+    mut swatches: Query<&mut UiColor, With<DialogueSwatch>>,
+    mut texts: Query<&mut Text, With<DialogueText>>,
+) {
+    let Some(dialogue) = dialogue.filter(|dialogue| dialogue.is_changed() || !added.is_empty())
+    else {
+        return;
+    };
+    let Some((name, color, text)) = dialogue.lines.get(dialogue.shown) else {
+        return;
+    };
+    for mut swatch in &mut swatches {
+        *swatch = (*color).into();
+    }
+    for mut text_sections in &mut texts {
+        text_sections.sections[0].value = format!("{}\n", name);
+        text_sections.sections[1].value = text.clone();
+    }
+}
+
+// A click, Enter or Space moves on a line, past the last one the box closes.
+fn advance_dialogue_system(
+    mut commands: Commands,
+    keys: Res<Input<KeyCode>>,
+    dialogue: Option<ResMut<Dialogue>>,
+    mut state: ResMut<State<AppState>>,
+    clicks: Query<&Interaction, (Changed<Interaction>, With<DialogueRoot>)>,
+    roots: Query<Entity, With<DialogueRoot>>,
+) {
+    let Some(mut dialogue) = dialogue else {
+        return;
+    };
+    let clicked = clicks
+        .iter()
+        .any(|interaction| *interaction == Interaction::Clicked);
+    if !clicked && !keys.any_just_pressed([KeyCode::Return, KeyCode::Space]) {
+        return;
+    }
+    dialogue.shown += 1;
+    if dialogue.shown < dialogue.lines.len() {
+        return;
+    }
+    for root in &roots {
+        commands.entity(root).despawn_recursive();
+    }
+    if let Some(then) = dialogue.then {
+        let _ = state.set(then);
+    }
+    commands.remove_resource::<Dialogue>();
+}
+
+// Lines left over when the screen changes under them aren't shown any more.
+fn close_dialogue_system(mut commands: Commands, roots: Query<Entity, With<DialogueRoot>>) {
+    for root in &roots {
+        commands.entity(root).despawn_recursive();
+    }
+    commands.remove_resource::<Dialogue>();
+}
+
+// The archetype unlocked by finishing the campaign goes into the player's
+// slot and is kept as their body.
+pub fn pilot_archetype(
+    campaign: &Campaign,
+    game: &Game,
+    profile: &mut Profile,
+    swaps: &mut EventWriter<SwapBody>,
+    errors: &mut EventWriter<ErrorToast>,
+) {
+    let mut body = campaign.archetype.clone();
+    body.fit_attachments(&profile.attachments);
+    swaps.send(SwapBody {
+        entity: game.player,
+        body: body.clone(),
+    });
+    profile.body = Some(body);
+    report(errors, profile.save());
+}
+
+pub struct CampaignPlugin;
+
+impl Plugin for CampaignPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_asset::<CampaignFile>()
+            .init_asset_loader::<CampaignLoader>()
+            .add_startup_system(load_campaign_system)
+            .add_system_set(
+                SystemSet::on_enter(AppState::GameOver)
+                    .with_system(record_campaign_system.before(record_result_system)),
+            )
+            .add_system_set(
+                SystemSet::on_enter(AppState::PreBattle).with_system(leave_campaign_system),
+            )
+            .add_system_set(
+                SystemSet::on_exit(AppState::Campaign).with_system(close_dialogue_system),
+            )
+            .add_system_set(
+                SystemSet::on_exit(AppState::GameOver).with_system(close_dialogue_system),
+            )
+            .add_system(spawn_dialogue_system)
+            .add_system(show_line_system.after(spawn_dialogue_system))
+            .add_system(advance_dialogue_system.before(show_line_system));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shipped() -> (CampaignDef, HashMap<String, Result<Vec<u8>, String>>) {
+        let assets = concat!(env!("CARGO_MANIFEST_DIR"), "/assets/");
+        let contents = std::fs::read_to_string(format!("{}{}", assets, CAMPAIGN_FILE)).unwrap();
+        let def = ron::from_str::<CampaignDef>(&contents).unwrap();
+        let files = def
+            .encounters
+            .iter()
+            .filter_map(|encounter| encounter.enemy.file())
+            .chain([def.archetype.as_str()])
+            .map(|path| {
+                let read = std::fs::read(format!("{}{}", assets, path));
+                (path.to_string(), read.map_err(|err| err.to_string()))
+            })
+            .collect();
+        (def, files)
+    }
+
+    #[test]
+    fn the_campaign_loads_unlocks_in_order_and_pays_once() {
+        let (def, files) = shipped();
+        let keys = def
+            .encounters
+            .iter()
+            .flat_map(|encounter| {
+                let lines = encounter.before.iter().chain(&encounter.after);
+                lines
+                    .map(|line| line.text.clone())
+                    .chain([format!("campaign.{}.title", encounter.id)])
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        let en = include_str!("../assets/lang/en.ron");
+        for key in keys {
+            assert!(en.contains(&format!("\"{}\":", key)), "{} missing", key);
+        }
+        let campaign = resolve(def, &files).unwrap_or_else(|err| panic!("{}", err));
+        assert_eq!(campaign.encounters.len(), 6);
+
+        // A missing enemy file names the file and the encounter.
+        let (def, mut files) = shipped();
+        files.insert(
+            "campaign/enemies/foreman.body.ron".to_string(),
+            Err("not found".to_string()),
+        );
+        match resolve(def, &files) {
+            Err(GameError::Io { file, reason }) => {
+                assert_eq!(file, "campaign/enemies/foreman.body.ron");
+                assert!(reason.contains("`foreman`"), "{}", reason);
+            }
+            _ => panic!("loaded without its enemy"),
+        }
+
+        let mut profile = Profile::default();
+        let status = |profile: &Profile| {
+            (0..campaign.encounters.len())
+                .map(|index| campaign.status(&profile.campaign, index))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            status(&profile)[..2],
+            [NodeStatus::Open, NodeStatus::Locked]
+        );
+        let cleared = clear(&mut profile, &campaign, 0);
+        assert_eq!(
+            cleared,
+            Cleared {
+                paid: true,
+                completed: false
+            }
+        );
+        assert_eq!(profile.scrap, campaign.encounters[0].reward.scrap);
+        assert_eq!(
+            status(&profile)[..2],
+            [NodeStatus::Cleared, NodeStatus::Open]
+        );
+        // Fought again it pays nothing.
+        assert!(!clear(&mut profile, &campaign, 0).paid);
+        assert_eq!(profile.scrap, campaign.encounters[0].reward.scrap);
+        for index in 1..campaign.encounters.len() {
+            let cleared = clear(&mut profile, &campaign, index);
+            assert_eq!(cleared.completed, index == campaign.encounters.len() - 1);
+        }
+        assert!(campaign.completed(&profile.campaign));
+        assert_eq!(profile.boosters.get(&Booster::Coolant), Some(&1));
+        assert_eq!(profile.attachments.get(&Attachment::Capacitor), Some(&1));
+    }
+}
@@ -0,0 +1,271 @@
+use std::collections::VecDeque;
+
+use bevy::{ecs::world::EntityMut, prelude::*};
+
+// Entities the spawner spawns and despawns in one frame, the rest waits for
+// the next. Projectiles, damage numbers and the like don't go through it.
+pub const CHURN_BUDGET: usize = 64;
+
+// Combatants are rebuilt before anything else waiting.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Priority {
+    Combatant,
+    Background,
+}
+
+// Spawns one entity of a rebuild. It's kept hidden and without a parent
+// until the whole rebuild is spawned.
+pub type Piece = Box<dyn FnOnce(&mut World) -> Entity + Send + Sync>;
+// Runs on the target once the pieces are attached, given them in order.
+pub type Finish = Box<dyn FnOnce(&mut EntityMut, &[Entity]) + Send + Sync>;
+
+struct Rebuild {
+    target: Entity,
+    priority: Priority,
+    pieces: VecDeque<Piece>,
+    staged: Vec<Entity>,
+    // Children shown until the staged pieces take their place.
+    replaces: Vec<Entity>,
+    finish: Finish,
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ChurnStats {
+    pub spawned: usize,
+    pub despawned: usize,
+    pub queued: usize,
+}
+
+// Spreads large rebuilds over several frames. What a rebuild replaces stays
+// up until all of its pieces are there, then comes down within the budget.
+pub struct DeferredSpawner {
+    pub budget: usize,
+    queue: VecDeque<Rebuild>,
+    graveyard: VecDeque<Entity>,
+    // Last frame's work, for the debug overlay.
+    stats: ChurnStats,
+}
+
+impl Default for DeferredSpawner {
+    fn default() -> Self {
+        Self {
+            budget: CHURN_BUDGET,
+            queue: default(),
+            graveyard: default(),
+            stats: default(),
+        }
+    }
+}
+
+impl DeferredSpawner {
+    // A rebuild still waiting for the same target is dropped, along with
+    // whatever it had spawned.
+    pub fn rebuild(
+        &mut self,
+        target: Entity,
+        priority: Priority,
+        replaces: Vec<Entity>,
+        pieces: Vec<Piece>,
+        finish: Finish,
+    ) {
+        if let Some(i) = self.queue.iter().position(|job| job.target == target) {
+            let job = self.queue.remove(i).unwrap();
+            self.graveyard.extend(job.staged);
+        }
+        let at = self
+            .queue
+            .iter()
+            .position(|job| job.priority > priority)
+            .unwrap_or(self.queue.len());
+        self.queue.insert(
+            at,
+            Rebuild {
+                target,
+                priority,
+                pieces: pieces.into(),
+                staged: Vec::new(),
+                replaces,
+                finish,
+            },
+        );
+    }
+
+    pub fn stats(&self) -> ChurnStats {
+        self.stats
+    }
+
+    fn run(&mut self, world: &mut World) {
+        let budget = self.budget;
+        let mut stats = ChurnStats::default();
+        // Something bigger than the whole budget gets a frame to itself.
+        let fits = |stats: &ChurnStats, cost: usize| {
+            let spent = stats.spawned + stats.despawned;
+            spent == 0 || spent + cost <= budget
+        };
+        // Targets despawned in the meantime took what they showed with them.
+        let (gone, queue) = std::mem::take(&mut self.queue)
+            .into_iter()
+            .partition::<VecDeque<_>, _>(|job| world.get_entity(job.target).is_none());
+        self.queue = queue;
+        for job in gone {
+            self.graveyard.extend(job.staged);
+        }
+
+        'jobs: while let Some(job) = self.queue.front_mut() {
+            while !job.pieces.is_empty() {
+                if !fits(&stats, 1) {
+                    break 'jobs;
+                }
+                let piece = job.pieces.pop_front().unwrap();
+                let entity = piece(world);
+                world
+                    .entity_mut(entity)
+                    .insert(Visibility { is_visible: false });
+                job.staged.push(entity);
+                stats.spawned += 1;
+            }
+            let job = self.queue.pop_front().unwrap();
+            let replaces = job
+                .replaces
+                .into_iter()
+                .filter(|entity| world.get_entity(*entity).is_some())
+                .collect::<Vec<_>>();
+            for entity in replaces.iter().chain(&job.staged) {
+                let shown = job.staged.contains(entity);
+                world
+                    .entity_mut(*entity)
+                    .insert(Visibility { is_visible: shown });
+            }
+            let mut target = world.entity_mut(job.target);
+            target.remove_children(&replaces);
+            target.push_children(&job.staged);
+            (job.finish)(&mut target, &job.staged);
+            self.graveyard.extend(replaces);
+        }
+
+        while let Some(&entity) = self.graveyard.front() {
+            if world.get_entity(entity).is_none() {
+                self.graveyard.pop_front();
+                continue;
+            }
+            let cost = tree_size(world, entity);
+            if !fits(&stats, cost) {
+                break;
+            }
+            self.graveyard.pop_front();
+            despawn_with_children_recursive(world, entity);
+            stats.despawned += cost;
+        }
+        stats.queued = self.queue.len();
+        self.stats = stats;
+    }
+}
+
+fn tree_size(world: &World, entity: Entity) -> usize {
+    let children = world.get::<Children>(entity).map_or(0, |children| {
+        children.iter().map(|child| tree_size(world, *child)).sum()
+    });
+    1 + children
+}
+
+// After everything that submits rebuilds, so they're started the frame
+// they come in.
+fn deferred_spawn_system(world: &mut World) {
+    world.resource_scope(|world, mut spawner: Mut<DeferredSpawner>| spawner.run(world));
+}
+
+pub struct ChurnPlugin;
+
+impl Plugin for ChurnPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<DeferredSpawner>()
+            .add_system(deferred_spawn_system.exclusive_system().at_end());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        body::{part_sprites, update_body_system, Body, BodyBundle, PaletteMode, PartEntities},
+        fixtures::seeded_bodies,
+    };
+
+    // Every part the body shows right now, all attached and visible.
+    fn shown(app: &App, entity: Entity) -> Vec<Entity> {
+        let Some(parts) = app.world.get::<PartEntities>(entity) else {
+            return Vec::new();
+        };
+        let children = app.world.get::<Children>(entity).unwrap();
+        parts
+            .iter()
+            .map(|(_, part)| {
+                assert!(children.contains(&part));
+                assert!(app.world.get::<Visibility>(part).unwrap().is_visible);
+                part
+            })
+            .collect()
+    }
+
+    #[test]
+    fn twenty_bodies_stay_within_the_budget() {
+        let mut app = App::new();
+        app.init_resource::<PaletteMode>()
+            .add_plugin(ChurnPlugin)
+            .add_system(update_body_system);
+        let bodies = seeded_bodies(20, 8, 8);
+        let parts = bodies
+            .iter()
+            .map(|body| part_sprites(body).len())
+            .sum::<usize>();
+        let entities = bodies
+            .into_iter()
+            .map(|body| {
+                app.world
+                    .spawn()
+                    .insert_bundle(BodyBundle { body, ..default() })
+                    .id()
+            })
+            .collect::<Vec<_>>();
+        let settle = |app: &mut App, check: &dyn Fn(&App)| {
+            let (mut frames, mut spawned, mut despawned) = (0, 0, 0);
+            loop {
+                app.update();
+                frames += 1;
+                let spawner = app.world.resource::<DeferredSpawner>();
+                let stats = spawner.stats();
+                assert!(stats.spawned + stats.despawned <= CHURN_BUDGET);
+                spawned += stats.spawned;
+                despawned += stats.despawned;
+                check(app);
+                if stats.queued == 0 && spawner.graveyard.is_empty() {
+                    return (frames, spawned, despawned);
+                }
+            }
+        };
+
+        let (frames, spawned, despawned) = settle(&mut app, &|_| {});
+        assert!(frames > 1);
+        assert_eq!((spawned, despawned), (parts, 0));
+        let before = entities
+            .iter()
+            .map(|entity| shown(&app, *entity))
+            .collect::<Vec<_>>();
+        assert_eq!(before.iter().map(Vec::len).sum::<usize>(), parts);
+
+        // Until its new parts are ready a body keeps showing the old ones.
+        for entity in &entities {
+            let mut body = app.world.get_mut::<Body>(*entity).unwrap();
+            body.set_name("Renamed");
+        }
+        let check = |app: &App| {
+            for (entity, old) in entities.iter().zip(&before) {
+                let now = shown(app, *entity);
+                assert_eq!(now.len(), old.len());
+                assert!(now == *old || now.iter().all(|part| !old.contains(part)));
+            }
+        };
+        let (_, spawned, despawned) = settle(&mut app, &check);
+        assert_eq!((spawned, despawned), (parts, parts));
+    }
+}
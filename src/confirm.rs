@@ -0,0 +1,189 @@
+use bevy::{ecs::event::Event, input::InputSystem, prelude::*};
+
+use crate::{focus::FocusScope, locale::t, theme::Theme, ui::Fonts};
+
+// Modal yes/no prompt for actions that can't be undone. The action itself is
+// an event the caller hands over, it's only sent if the player confirms.
+#[derive(Default)]
+pub struct ConfirmDialog {
+    pending: Option<Pending>,
+    choice: Option<bool>,
+}
+
+struct Pending {
+    text: String,
+    confirm: Box<dyn FnOnce(&mut World) + Send + Sync>,
+}
+
+impl ConfirmDialog {
+    // Replaces a prompt that is already open.
+    pub fn open<E: Event>(&mut self, text: impl Into<String>, on_confirm: E) {
+        self.pending = Some(Pending {
+            text: text.into(),
+            confirm: Box::new(move |world| world.send_event(on_confirm)),
+        });
+        self.choice = None;
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.pending.is_some()
+    }
+}
+
+#[derive(Component)]
+struct DialogRoot;
+
+#[derive(Component, Clone, Copy)]
+struct DialogButton(bool);
+
+// Swallows every key while the dialog is open so hotkeys underneath stay
+// quiet, Escape cancels.
+fn dialog_input_system(mut dialog: ResMut<ConfirmDialog>, mut keys: ResMut<Input<KeyCode>>) {
+    if !dialog.is_open() {
+        return;
+    }
+    if keys.just_pressed(KeyCode::Escape) {
+        dialog.choice = Some(false);
+    }
+    keys.reset_all();
+}
+
+fn dialog_button_system(
+    theme: Res<Theme>,
+    mut dialog: ResMut<ConfirmDialog>,
+    mut buttons: Query<(&Interaction, &DialogButton, &mut UiColor), Changed<Interaction>>,
+) {
+    for (interaction, button, mut color) in &mut buttons {
+        color.0 = match interaction {
+            Interaction::Clicked => {
+                dialog.choice = Some(button.0);
+                theme.pressed_button
+            }
+            Interaction::Hovered => theme.hovered_button,
+            Interaction::None => theme.normal_button,
+        };
+    }
+}
+
+fn resolve_dialog_system(world: &mut World) {
+    // Checked first so idle frames don't flag the dialog as changed.
+    if world.resource::<ConfirmDialog>().choice.is_none() {
+        return;
+    }
+    let mut dialog = world.resource_mut::<ConfirmDialog>();
+    let choice = dialog.choice.take();
+    let pending = dialog.pending.take();
+    if let (Some(true), Some(pending)) = (choice, pending) {
+        (pending.confirm)(world);
+    }
+}
+
+fn dialog_ui_system(
+    theme: Res<Theme>,
+    mut commands: Commands,
+    fonts: Res<Fonts>,
+    dialog: Res<ConfirmDialog>,
+    roots: Query<Entity, With<DialogRoot>>,
+) {
+    if !dialog.is_changed() {
+        return;
+    }
+    for root in &roots {
+        commands.entity(root).despawn_recursive();
+    }
+    let Some(pending) = &dialog.pending else {
+        return;
+    };
+    // Covers the whole screen and blocks focus, so nothing underneath can be
+    // clicked until the dialog closes.
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                position_type: PositionType::Absolute,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            color: theme.backdrop(0.5).into(),
+            ..default()
+        })
+        // Keyboard and gamepad focus stays on the dialog's buttons too.
+        .insert_bundle((DialogRoot, FocusScope))
+        .with_children(|parent| {
+            parent
+                .spawn_bundle(NodeBundle {
+                    style: Style {
+                        max_size: Size::new(Val::Px(500.0), Val::Undefined),
+                        flex_direction: FlexDirection::ColumnReverse,
+                        align_items: AlignItems::Center,
+                        padding: UiRect::all(Val::Px(20.0)),
+                        ..default()
+                    },
+                    color: Color::rgb(0.15, 0.15, 0.15).into(),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn_bundle(
+                        TextBundle::from_section(
+                            pending.text.clone(),
+                            TextStyle {
+                                font: fonts.bold(),
+                                font_size: 28.0,
+                                color: Color::WHITE,
+                            },
+                        )
+                        .with_style(Style {
+                            margin: UiRect::all(Val::Px(10.0)),
+                            ..default()
+                        }),
+                    );
+                    parent
+                        .spawn_bundle(NodeBundle {
+                            color: Color::NONE.into(),
+                            ..default()
+                        })
+                        .with_children(|parent| {
+                            for (confirm, label) in
+                                [(true, t!("confirm.confirm")), (false, t!("confirm.cancel"))]
+                            {
+                                parent
+                                    .spawn_bundle(ButtonBundle {
+                                        style: Style {
+                                            size: Size::new(Val::Px(180.0), Val::Px(50.0)),
+                                            margin: UiRect::all(Val::Px(5.0)),
+                                            justify_content: JustifyContent::Center,
+                                            align_items: AlignItems::Center,
+                                            ..default()
+                                        },
+                                        color: theme.normal_button.into(),
+                                        ..default()
+                                    })
+                                    .insert(DialogButton(confirm))
+                                    .with_children(|parent| {
+                                        parent.spawn_bundle(TextBundle::from_section(
+                                            label,
+                                            TextStyle {
+                                                font: fonts.bold(),
+                                                font_size: 26.0,
+                                                color: theme.button_text,
+                                            },
+                                        ));
+                                    });
+                            }
+                        });
+                });
+        });
+}
+
+pub struct ConfirmPlugin;
+
+impl Plugin for ConfirmPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ConfirmDialog>()
+            .add_system_to_stage(CoreStage::PreUpdate, dialog_input_system.after(InputSystem))
+            .add_system(dialog_button_system)
+            .add_system(resolve_dialog_system.exclusive_system().at_end())
+            .add_system_to_stage(CoreStage::PostUpdate, dialog_ui_system);
+    }
+}
@@ -0,0 +1,669 @@
+use std::{
+    collections::{BTreeMap, VecDeque},
+    fmt::{Display, Write},
+    str::FromStr,
+};
+
+use bevy::{input::InputSystem, prelude::*};
+
+use crate::{
+    ai_view::ShowAiDecisions,
+    battle::Ground,
+    body::{
+        grid_position, random_arm, random_body_with, still_sprites, Body, Facing, GenerationConfig,
+        Limb, PaletteMode, PartKind, SpawnBody, Stats,
+    },
+    hitbox_view::ShowHitboxes,
+    layer::RenderLayer,
+    periodic::{PeriodicEffect, TickPayload},
+    rate::{animation_rate_system, RateModifier},
+    swap::SwapBody,
+    ui::Fonts,
+    upgrade::{upgrade, UPGRADE_COST},
+    DamageEvent, DamageSource, Game, GameRng, Invulnerable,
+};
+
+const SCROLLBACK: usize = 200;
+const VISIBLE_LINES: usize = 16;
+
+#[derive(Default)]
+pub struct Console {
+    pub open: bool,
+    input: String,
+    scrollback: VecDeque<String>,
+    pending: Vec<String>,
+}
+
+impl Console {
+    pub fn print(&mut self, text: &str) {
+        for line in text.lines() {
+            if self.scrollback.len() == SCROLLBACK {
+                self.scrollback.pop_front();
+            }
+            self.scrollback.push_back(line.to_string());
+        }
+    }
+}
+
+pub struct Args<'a> {
+    words: std::slice::Iter<'a, &'a str>,
+}
+
+impl<'a> Args<'a> {
+    pub fn next<T: FromStr>(&mut self, name: &str) -> Result<T, String>
+    where
+        T::Err: Display,
+    {
+        let word = self
+            .words
+            .next()
+            .ok_or_else(|| format!("missing argument <{}>", name))?;
+        word.parse()
+            .map_err(|err| format!("invalid <{}> `{}`: {}", name, word, err))
+    }
+
+    pub fn optional<T: FromStr>(&mut self, name: &str) -> Result<Option<T>, String>
+    where
+        T::Err: Display,
+    {
+        if self.words.len() == 0 {
+            Ok(None)
+        } else {
+            self.next(name).map(Some)
+        }
+    }
+}
+
+type Handler = Box<dyn Fn(&mut Args, &mut World) -> Result<String, String> + Send + Sync>;
+
+struct ConsoleCommand {
+    usage: &'static str,
+    handler: Handler,
+}
+
+#[derive(Default)]
+pub struct ConsoleCommands {
+    commands: BTreeMap<&'static str, ConsoleCommand>,
+}
+
+impl ConsoleCommands {
+    pub fn register(
+        &mut self,
+        name: &'static str,
+        usage: &'static str,
+        handler: impl Fn(&mut Args, &mut World) -> Result<String, String> + Send + Sync + 'static,
+    ) -> &mut Self {
+        self.commands.insert(
+            name,
+            ConsoleCommand {
+                usage,
+                handler: Box::new(handler),
+            },
+        );
+        self
+    }
+
+    fn help(&self) -> String {
+        let mut s = String::new();
+        for (name, command) in &self.commands {
+            let _ = writeln!(s, "  {} {}", name, command.usage);
+        }
+        s
+    }
+
+    fn run(&self, line: &str, world: &mut World) -> Result<String, String> {
+        let words = line.split_whitespace().collect::<Vec<_>>();
+        let (name, rest) = match words.split_first() {
+            Some((name, rest)) => (*name, rest),
+            None => return Ok(String::new()),
+        };
+        if name == "help" {
+            return Ok(self.help());
+        }
+        let command = self
+            .commands
+            .get(name)
+            .ok_or_else(|| format!("unknown command `{}`, try `help`", name))?;
+        let mut args = Args { words: rest.iter() };
+        let mut output = (command.handler)(&mut args, world)
+            .map_err(|err| format!("{}\nusage: {} {}", err, name, command.usage))?;
+        let extra = args.words.copied().collect::<Vec<_>>();
+        if !extra.is_empty() {
+            let _ = write!(output, "\nignored extra arguments: {}", extra.join(" "));
+        }
+        Ok(output)
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Target {
+    Player,
+    Enemy,
+}
+
+impl FromStr for Target {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "player" => Ok(Target::Player),
+            "enemy" => Ok(Target::Enemy),
+            _ => Err("expected player or enemy".to_string()),
+        }
+    }
+}
+
+impl Target {
+    fn entity(self, world: &World) -> Result<Entity, String> {
+        let game = world
+            .get_resource::<Game>()
+            .ok_or("no combatants spawned")?;
+        Ok(match self {
+            Target::Player => game.player,
+            Target::Enemy => game.enemy,
+        })
+    }
+}
+
+fn stat_mut<'a>(stats: &'a mut Stats, stat: &str) -> Result<&'a mut f32, String> {
+    Ok(match stat {
+        "health" => &mut stats.health,
+        "energy" => &mut stats.energy,
+        "max_health" => &mut stats.max_health,
+        "max_energy" => &mut stats.max_energy,
+        "weight" => &mut stats.weight,
+        "speed" => &mut stats.speed,
+        "reaction_time" => &mut stats.reaction_time,
+        "close_accuracy" => &mut stats.close_accuracy,
+        "far_accuracy" => &mut stats.far_accuracy,
+        "jump_force" => &mut stats.jump_force,
+        _ => return Err(format!("unknown stat `{}`", stat)),
+    })
+}
+
+fn register_default_commands(commands: &mut ConsoleCommands) {
+    commands
+        .register(
+            "spawn_part",
+            "<torso|head|arm|leg> [rarity]",
+            |args, world| {
+                let kind = args.next::<PartKind>("part")?;
+                let rarity = args.optional::<String>("rarity")?;
+                let player = Target::Player.entity(world)?;
+                let mut body = world
+                    .get::<Body>(player)
+                    .ok_or("player has no body")?
+                    .clone();
+                let (name, _) = body.reroll_part(kind, &mut **world.resource_mut::<GameRng>());
+                world.send_event(SwapBody {
+                    entity: player,
+                    body,
+                });
+                let mut output = format!("player equipped {}", name);
+                if let Some(rarity) = rarity {
+                    let _ = write!(
+                        output,
+                        "\nrarity `{}` ignored, parts have no rarity yet",
+                        rarity
+                    );
+                }
+                Ok(output)
+            },
+        )
+        .register("spawn_pair", "", |_, world| {
+            let player = Target::Player.entity(world)?;
+            let mut body = world
+                .get::<Body>(player)
+                .ok_or("player has no body")?
+                .clone();
+            let arm = random_arm(&mut **world.resource_mut::<GameRng>(), 0);
+            let name = arm.name().to_string();
+            if !body.equip_pair(arm) {
+                return Err("player's torso has no pair of arm slots".into());
+            }
+            world.send_event(SwapBody {
+                entity: player,
+                body,
+            });
+            Ok(format!("player equipped {} to both arm slots", name))
+        })
+        .register("upgrade", "<torso|head|arm|leg> [slot]", |args, world| {
+            let kind = args.next::<PartKind>("part")?;
+            let slot = args.optional::<u8>("slot")?.unwrap_or(0);
+            let player = Target::Player.entity(world)?;
+            let mut body = world.get_mut::<Body>(player).ok_or("player has no body")?;
+            // Free here, the part pays for its own duplicate.
+            let mut scrap = UPGRADE_COST;
+            let result = match kind {
+                PartKind::Torso => {
+                    let part = body.torso_mut();
+                    upgrade(part, part.clone(), &mut scrap).map(|_| part.name().to_string())
+                }
+                PartKind::Head => {
+                    let part = body.head_mut();
+                    upgrade(part, part.clone(), &mut scrap).map(|_| part.name().to_string())
+                }
+                PartKind::Arm => {
+                    let part = body
+                        .arms_mut()
+                        .iter_mut()
+                        .find(|arm| arm.slot() == slot)
+                        .ok_or("no arm in that slot")?;
+                    upgrade(part, part.clone(), &mut scrap).map(|_| part.name().to_string())
+                }
+                PartKind::Leg => {
+                    let part = body
+                        .legs_mut()
+                        .iter_mut()
+                        .find(|leg| leg.slot() == slot)
+                        .ok_or("no leg in that slot")?;
+                    upgrade(part, part.clone(), &mut scrap).map(|_| part.name().to_string())
+                }
+            };
+            let name = result.map_err(|err| err.to_string())?;
+            Ok(format!("upgraded to {}", name))
+        })
+        .register("destroy", "<arm|leg> [slot]", |args, world| {
+            let limb = match args.next::<PartKind>("part")? {
+                PartKind::Arm => Limb::Arm,
+                PartKind::Leg => Limb::Leg,
+                _ => return Err("only arms and legs come off".into()),
+            };
+            let limb = limb(args.optional::<u8>("slot")?.unwrap_or(0));
+            let player = Target::Player.entity(world)?;
+            let mut body = world
+                .get::<Body>(player)
+                .ok_or("player has no body")?
+                .clone();
+            let name = body.destroy(limb).ok_or("nothing in that slot")?;
+            world.send_event(SwapBody {
+                entity: player,
+                body,
+            });
+            Ok(format!("player lost {}", name))
+        })
+        .register("set", "<player|enemy> <stat> <value>", |args, world| {
+            let target = args.next::<Target>("target")?;
+            let stat = args.next::<String>("stat")?;
+            let value = args.next::<f32>("value")?;
+            let entity = target.entity(world)?;
+            let mut stats = world
+                .get_mut::<Stats>(entity)
+                .ok_or("target has no stats")?;
+            *stat_mut(&mut stats, &stat)? = value;
+            Ok(format!("{} = {}", stat, value))
+        })
+        .register("damage", "<player|enemy> <amount>", |args, world| {
+            let target = args.next::<Target>("target")?;
+            let amount = args.next::<f32>("amount")?;
+            let target = target.entity(world)?;
+            world.send_event(DamageEvent {
+                target,
+                source: DamageSource::Console,
+                amount,
+                emp: false,
+                impact: None,
+            });
+            Ok(format!("dealt {} damage", amount))
+        })
+        .register(
+            "tick",
+            "<player|enemy> <health|energy> <amount> <seconds> [interval]",
+            |args, world| {
+                let target = args.next::<Target>("target")?;
+                let kind = args.next::<String>("kind")?;
+                let amount = args.next::<f32>("amount")?;
+                let seconds = args.next::<f32>("seconds")?;
+                let interval = args.optional::<f32>("interval")?.unwrap_or(1.0);
+                if seconds <= 0.0 || interval <= 0.0 {
+                    return Err("seconds and interval must be positive".to_string());
+                }
+                // Positive amounts are what the body gains, like `set`.
+                let (id, payload) = match kind.as_str() {
+                    "health" => ("periodic_health", TickPayload::Damage(-amount)),
+                    "energy" => ("periodic_energy", TickPayload::Energy(amount)),
+                    _ => return Err(format!("unknown kind `{}`", kind)),
+                };
+                let target = target.entity(world)?;
+                world.spawn().insert(PeriodicEffect::lasting(
+                    target, id, seconds, interval, payload,
+                ));
+                Ok(format!(
+                    "{} {} every {}s for {}s",
+                    kind, amount, interval, seconds
+                ))
+            },
+        )
+        .register("rate", "<player|enemy> <multiplier>", |args, world| {
+            let target = args.next::<Target>("target")?;
+            let multiplier = args.next::<f32>("multiplier")?;
+            let target = target.entity(world)?;
+            let mut entity = world
+                .get_entity_mut(target)
+                .ok_or("target does not exist")?;
+            if multiplier == 1.0 {
+                entity.remove::<ConsoleRate>();
+                Ok("animation rate modifier removed".to_string())
+            } else {
+                entity.insert(ConsoleRate(multiplier));
+                Ok(format!("animations play at x{}", multiplier))
+            }
+        })
+        .register("seed", "<seed>", |args, world| {
+            let seed = args.next::<u64>("seed")?;
+            world.insert_resource(GameRng::new(seed));
+            Ok(format!("rng seeded with {}", seed))
+        })
+        .register("ground", "<y>", |args, world| {
+            let y = args.next::<f32>("y")?;
+            world.resource_mut::<Ground>().y = y;
+            Ok(format!("ground at y = {}", y))
+        })
+        .register("hitboxes", "", |_, world| {
+            let mut show = world.resource_mut::<ShowHitboxes>();
+            show.0 = !show.0;
+            Ok(format!("hitboxes {}", if show.0 { "on" } else { "off" }))
+        })
+        .register("ai", "", |_, world| {
+            let mut show = world.resource_mut::<ShowAiDecisions>();
+            show.0 = !show.0;
+            Ok(format!(
+                "AI decisions {}",
+                if show.0 { "on" } else { "off" }
+            ))
+        })
+        .register("lineup", "[chaos]", |args, world| {
+            let mut lineup = world.query_filtered::<Entity, With<Lineup>>();
+            let shown = lineup.iter(world).collect::<Vec<_>>();
+            if !shown.is_empty() {
+                for entity in shown {
+                    despawn_with_children_recursive(world, entity);
+                }
+                return Ok("lineup cleared".to_string());
+            }
+            let proportional = match args.optional::<String>("mode")?.as_deref() {
+                None => true,
+                Some("chaos") => false,
+                Some(mode) => return Err(format!("unknown mode `{}`, expected chaos", mode)),
+            };
+            spawn_lineup(world, proportional);
+            Ok(format!(
+                "{} {} bodies, run again to clear them",
+                LINEUP_SIZE * LINEUP_SIZE,
+                if proportional {
+                    "proportional"
+                } else {
+                    "chaos"
+                }
+            ))
+        })
+        .register("depth", "", |_, world| {
+            let mut check = world.query_filtered::<Entity, With<DepthCheck>>();
+            let shown = check.iter(world).collect::<Vec<_>>();
+            if !shown.is_empty() {
+                for entity in shown {
+                    despawn_with_children_recursive(world, entity);
+                }
+                return Ok("depth check cleared".to_string());
+            }
+            spawn_depth_check(world);
+            Ok(format!(
+                "{} overlapping bodies, run again to clear them",
+                DEPTH_CHECK_SIZE
+            ))
+        })
+        // Checks the camera and nameplates keep up with the extremes.
+        .register("sizes", "[player size] [enemy size]", |args, world| {
+            let small = args.optional::<f32>("player size")?.unwrap_or(0.5);
+            let large = args.optional::<f32>("enemy size")?.unwrap_or(3.0);
+            if small <= 0.0 || large <= 0.0 {
+                return Err("sizes must be positive".into());
+            }
+            for (target, size) in [(Target::Player, small), (Target::Enemy, large)] {
+                let mut body = Body::default();
+                body.resize(size);
+                world.send_event(SwapBody {
+                    entity: target.entity(world)?,
+                    body,
+                });
+            }
+            Ok(format!(
+                "size {} player against a size {} enemy",
+                small, large
+            ))
+        })
+        .register("god", "", |_, world| {
+            let player = Target::Player.entity(world)?;
+            let mut entity = world
+                .get_entity_mut(player)
+                .ok_or("player does not exist")?;
+            if entity.contains::<Invulnerable>() {
+                entity.remove::<Invulnerable>();
+                Ok("god mode off".to_string())
+            } else {
+                entity.insert(Invulnerable);
+                Ok("god mode on".to_string())
+            }
+        });
+}
+
+// Generated bodies standing in a grid over the arena to eyeball their
+// proportions, spawned by `lineup`. Only their sprites, nothing in the
+// battle sees them.
+#[derive(Component)]
+struct Lineup;
+
+const LINEUP_SIZE: usize = 5;
+const LINEUP_SPACING: Vec2 = Vec2::new(3.0, 4.5);
+
+fn spawn_lineup(world: &mut World, proportional: bool) {
+    let config = GenerationConfig {
+        proportional,
+        ..default()
+    };
+    let palette = *world.resource::<PaletteMode>();
+    for i in 0..LINEUP_SIZE * LINEUP_SIZE {
+        let body = random_body_with(&mut **world.resource_mut::<GameRng>(), &config);
+        let position = grid_position(i, LINEUP_SIZE, LINEUP_SPACING);
+        world
+            .spawn()
+            .insert_bundle(SpatialBundle::from_transform(Transform::from_translation(
+                // In front of the bodies, the free camera can pan over the whole grid.
+                position.extend(RenderLayer::Overlay.z()),
+            )))
+            .insert(Lineup)
+            .with_children(|parent| {
+                for sprite in still_sprites(&body, palette) {
+                    parent.spawn_bundle(sprite);
+                }
+            });
+    }
+}
+
+// Real bodies piled up in the middle of the arena, both layers and both
+// facings mixed and one in the air, to check nothing flickers or draws out
+// of order. Spawned by `depth`, the battle ignores them.
+#[derive(Component)]
+struct DepthCheck;
+
+const DEPTH_CHECK_SIZE: usize = 4;
+
+fn spawn_depth_check(world: &mut World) {
+    let ground = world.resource::<Ground>().y;
+    for i in 0..DEPTH_CHECK_SIZE {
+        let body = random_body_with(&mut **world.resource_mut::<GameRng>(), &default());
+        let layer = if i % 2 == 0 {
+            RenderLayer::FarBody
+        } else {
+            RenderLayer::NearBody
+        };
+        let facing = if i < DEPTH_CHECK_SIZE / 2 {
+            Facing::Right
+        } else {
+            Facing::Left
+        };
+        let y = if i == DEPTH_CHECK_SIZE - 1 { 1.5 } else { 0.0 };
+        let transform = Transform::from_xyz(i as f32 * 0.3, ground + y, layer.z());
+        let entity = world.spawn_body(body, transform);
+        world
+            .entity_mut(entity)
+            .insert_bundle((facing, layer, DepthCheck));
+    }
+}
+
+// Set with `rate`, stays on the body until set back to 1.
+#[derive(Component)]
+struct ConsoleRate(f32);
+
+fn console_rate_system(
+    bodies: Query<(Entity, &ConsoleRate)>,
+    mut rates: EventWriter<RateModifier>,
+) {
+    for (target, rate) in &bodies {
+        rates.send(RateModifier {
+            target,
+            source: "console",
+            multiplier: rate.0,
+        });
+    }
+}
+
+#[derive(Component)]
+struct ConsoleRoot;
+
+#[derive(Component)]
+struct ConsoleText;
+
+// Runs right after input is collected and swallows every key while the
+// console is open, so gameplay hotkeys never see them.
+fn console_input_system(
+    mut console: ResMut<Console>,
+    mut keys: ResMut<Input<KeyCode>>,
+    mut chars: EventReader<ReceivedCharacter>,
+) {
+    if keys.just_pressed(KeyCode::Grave) {
+        console.open = !console.open;
+        keys.reset_all();
+        chars.clear();
+        return;
+    }
+    if !console.open {
+        chars.clear();
+        return;
+    }
+    for event in chars.iter() {
+        match event.char {
+            '\r' | '\n' => {
+                let line = std::mem::take(&mut console.input);
+                console.print(&format!("> {}", line));
+                console.pending.push(line);
+            }
+            '\u{8}' => {
+                console.input.pop();
+            }
+            '`' => {}
+            c if !c.is_control() => console.input.push(c),
+            _ => {}
+        }
+    }
+    keys.reset_all();
+}
+
+fn console_exec_system(world: &mut World) {
+    let pending = std::mem::take(&mut world.resource_mut::<Console>().pending);
+    if pending.is_empty() {
+        return;
+    }
+    world.resource_scope(|world, commands: Mut<ConsoleCommands>| {
+        for line in pending {
+            let output = match commands.run(&line, world) {
+                Ok(output) => output,
+                Err(err) => format!("error: {}", err),
+            };
+            world.resource_mut::<Console>().print(&output);
+        }
+    });
+}
+
+fn console_ui_system(
+    console: Res<Console>,
+    mut roots: Query<&mut Visibility, With<ConsoleRoot>>,
+    mut texts: Query<(&mut Text, &mut Visibility), (With<ConsoleText>, Without<ConsoleRoot>)>,
+) {
+    if !console.is_changed() {
+        return;
+    }
+    for mut visibility in &mut roots {
+        visibility.is_visible = console.open;
+    }
+    for (mut text, mut visibility) in &mut texts {
+        visibility.is_visible = console.open;
+        let mut s = String::new();
+        let skip = console.scrollback.len().saturating_sub(VISIBLE_LINES);
+        for line in console.scrollback.iter().skip(skip) {
+            let _ = writeln!(s, "{}", line);
+        }
+        let _ = write!(s, "> {}_", console.input);
+        text.sections[0].value = s;
+    }
+}
+
+fn console_startup_system(mut commands: Commands, fonts: Res<Fonts>) {
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.0), Val::Percent(40.0)),
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    left: Val::Px(0.0),
+                    top: Val::Px(0.0),
+                    ..default()
+                },
+                flex_direction: FlexDirection::ColumnReverse,
+                justify_content: JustifyContent::FlexEnd,
+                padding: UiRect::all(Val::Px(8.0)),
+                ..default()
+            },
+            color: Color::rgba(0.0, 0.0, 0.0, 0.85).into(),
+            visibility: Visibility { is_visible: false },
+            ..default()
+        })
+        .insert(ConsoleRoot)
+        .with_children(|parent| {
+            parent
+                .spawn_bundle(TextBundle {
+                    text: Text::from_section(
+                        "",
+                        TextStyle {
+                            font: fonts.normal(),
+                            font_size: 16.0,
+                            color: Color::rgb(0.8, 1.0, 0.8),
+                        },
+                    ),
+                    visibility: Visibility { is_visible: false },
+                    ..default()
+                })
+                .insert(ConsoleText);
+        });
+}
+
+pub struct ConsolePlugin;
+
+impl Plugin for ConsolePlugin {
+    fn build(&self, app: &mut App) {
+        let mut commands = ConsoleCommands::default();
+        register_default_commands(&mut commands);
+        let mut console = Console::default();
+        console.print("type `help` for a list of commands");
+        app.insert_resource(console)
+            .insert_resource(commands)
+            .add_startup_system_to_stage(StartupStage::PostStartup, console_startup_system)
+            .add_system_to_stage(
+                CoreStage::PreUpdate,
+                console_input_system.after(InputSystem),
+            )
+            .add_system(console_exec_system.exclusive_system().at_start())
+            .add_system(console_rate_system.before(animation_rate_system))
+            .add_system_to_stage(CoreStage::PostUpdate, console_ui_system);
+    }
+}
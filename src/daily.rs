@@ -0,0 +1,374 @@
+use bevy::{ecs::entity::Entities, prelude::*};
+use rand::{rngs::StdRng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    body::{random_body, Body},
+    error::{report, ErrorToast, GameError},
+    locale::t,
+    profile::{record_result_system, Profile},
+    swap::SwapBody,
+    timer::SimClock,
+    turn::Ruleset,
+    AppState, BattleSummary, Game, GameRng,
+};
+
+// Older days are dropped past this many.
+pub const MAX_DAYS: usize = 30;
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+// Days since 1970-01-01 in UTC, so everyone is on the same challenge at the
+// same time whatever their time zone.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn today() -> Result<u32, String> {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|since| (since.as_secs() / SECONDS_PER_DAY) as u32)
+        .map_err(|err| err.to_string())
+}
+
+// The wall clock isn't reachable from the browser yet, daily challenges stay
+// desktop-only for now.
+#[cfg(target_arch = "wasm32")]
+pub fn today() -> Result<u32, String> {
+    Err("no clock in the browser".to_string())
+}
+
+// The day as YYYY-MM-DD.
+pub fn date_label(day: u32) -> String {
+    // Counted from 0000-03-01 so the leap day ends the year.
+    let days = day as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let of_era = days.rem_euclid(146_097);
+    let year_of_era = (of_era - of_era / 1460 + of_era / 36_524 - of_era / 146_096) / 365;
+    let of_year = of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_from_march = (5 * of_year + 2) / 153;
+    let day_of_month = of_year - (153 * month_from_march + 2) / 5 + 1;
+    let month = if month_from_march < 10 {
+        month_from_march + 3
+    } else {
+        month_from_march - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!("{:04}-{:02}-{:02}", year, month, day_of_month)
+}
+
+// SplitMix64, written out so the seeds stay the same whatever the standard
+// library's hashers do between versions.
+fn mix(value: u64) -> u64 {
+    let mut z = value.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+pub fn daily_seed(day: u32) -> u64 {
+    mix(day as u64)
+}
+
+// What a daily seed decides, each drawn from its own generator.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Stream {
+    Body,
+    Enemy,
+    Offer,
+}
+
+// One day's challenge. Every field has a default so results saved by older
+// versions still load.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DailyResult {
+    pub day: u32,
+    pub rounds_cleared: u32,
+    // Seconds spent fighting, intermissions and pauses left out.
+    pub time: f32,
+    // Lost, rather than left or cut short by the game closing.
+    pub finished: bool,
+}
+
+impl DailyResult {
+    // More rounds, and then less time for them.
+    fn beats(&self, other: &DailyResult) -> bool {
+        self.rounds_cleared
+            .cmp(&other.rounds_cleared)
+            .then(other.time.total_cmp(&self.time))
+            .is_gt()
+    }
+}
+
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DailyHistory {
+    // Oldest first, one per day played.
+    results: Vec<DailyResult>,
+}
+
+impl DailyHistory {
+    pub fn results(&self) -> &[DailyResult] {
+        &self.results
+    }
+
+    pub fn played(&self, day: u32) -> bool {
+        self.results.iter().any(|result| result.day == day)
+    }
+
+    // Ties go to the earlier day, it got there first.
+    pub fn best(&self) -> Option<&DailyResult> {
+        self.results
+            .iter()
+            .reduce(|best, result| if result.beats(best) { result } else { best })
+    }
+
+    // The attempt counts from the start, leaving early doesn't give another.
+    fn start(&mut self, day: u32) {
+        self.results.push(DailyResult { day, ..default() });
+        let excess = self.results.len().saturating_sub(MAX_DAYS);
+        self.results.drain(..excess);
+    }
+
+    fn update(&mut self, result: DailyResult) {
+        if let Some(known) = self
+            .results
+            .iter_mut()
+            .find(|known| known.day == result.day)
+        {
+            *known = result;
+        }
+    }
+}
+
+pub enum DailyError {
+    NoClock(String),
+    Played,
+    Unsaved(GameError),
+}
+
+impl std::fmt::Display for DailyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let message = match self {
+            DailyError::NoClock(err) => t!("daily.no_clock", error = err),
+            DailyError::Played => t!("daily.played"),
+            DailyError::Unsaved(err) => err.to_string(),
+        };
+        f.write_str(&message)
+    }
+}
+
+// A daily challenge in progress. The player's own body and rules are put
+// back once they're on the pre-battle screen again.
+pub struct DailyRun {
+    pub day: u32,
+    pub seed: u64,
+    // Nothing is recorded and it can be played any number of times.
+    pub practice: bool,
+    rounds_cleared: u32,
+    time: f32,
+    // Lent out for the run, it's what the profile keeps meanwhile.
+    pub body: Body,
+    ruleset: Ruleset,
+}
+
+impl DailyRun {
+    pub fn result(&self) -> DailyResult {
+        DailyResult {
+            day: self.day,
+            rounds_cleared: self.rounds_cleared,
+            time: self.time,
+            finished: true,
+        }
+    }
+
+    // A generator of its own for every stream and wave, so how long the
+    // battles ran or what the AI rolled doesn't shift what comes later.
+    pub fn rng(&self, stream: Stream, wave: u32) -> StdRng {
+        StdRng::seed_from_u64(mix(self.seed ^ mix(((stream as u64) << 32) | wave as u64)))
+    }
+}
+
+// The same for everyone. Weather and pickups are rolled as the battle plays
+// out, they stay out of it, and the player plays it themselves.
+fn daily_rules() -> Ruleset {
+    Ruleset {
+        survival: true,
+        weather: false,
+        pickups: false,
+        auto_battle: false,
+        ..default()
+    }
+}
+
+// Swaps the day's enemy in and records the attempt. The caller has the player
+// pick a body from the day's lineup, see `starter`, which starts the battle.
+pub fn begin(
+    commands: &mut Commands,
+    ruleset: &mut Ruleset,
+    profile: &mut Profile,
+    swaps: &mut EventWriter<SwapBody>,
+    game: &Game,
+    body: Body,
+    practice: bool,
+) -> Result<(), DailyError> {
+    let day = today().map_err(DailyError::NoClock)?;
+    if !practice {
+        if profile.daily.played(day) {
+            return Err(DailyError::Played);
+        }
+        profile.daily.start(day);
+        // An attempt that isn't on disk could be had again after a restart.
+        profile.save().map_err(DailyError::Unsaved)?;
+    }
+    let seed = daily_seed(day);
+    let run = DailyRun {
+        day,
+        seed,
+        practice,
+        rounds_cleared: 0,
+        time: 0.0,
+        body,
+        ruleset: std::mem::replace(ruleset, daily_rules()),
+    };
+    swaps.send(SwapBody {
+        entity: game.enemy,
+        body: random_body(&mut run.rng(Stream::Enemy, 1)),
+    });
+    commands.insert_resource(GameRng::new(seed));
+    commands.insert_resource(run);
+    Ok(())
+}
+
+// On the battle's own clock, like everything else timed in a battle.
+fn track_time_system(clock: Res<SimClock>, run: Option<ResMut<DailyRun>>) {
+    if let Some(mut run) = run {
+        run.time += clock.delta();
+    }
+}
+
+// After every battle, so a run cut short still has what it got to.
+fn record_daily_system(
+    game: Res<Game>,
+    summary: Res<BattleSummary>,
+    state: Res<State<AppState>>,
+    run: Option<ResMut<DailyRun>>,
+    mut profile: ResMut<Profile>,
+    mut errors: EventWriter<ErrorToast>,
+) {
+    let Some(mut run) = run else {
+        return;
+    };
+    if summary.winner == Some(game.player) {
+        run.rounds_cleared += 1;
+    }
+    if !run.practice {
+        let result = DailyResult {
+            finished: *state.current() == AppState::GameOver,
+            ..run.result()
+        };
+        profile.daily.update(result);
+        report(&mut errors, profile.save());
+    }
+}
+
+fn leave_daily_system(
+    mut commands: Commands,
+    game: Res<Game>,
+    entities: &Entities,
+    run: Option<ResMut<DailyRun>>,
+    mut ruleset: ResMut<Ruleset>,
+    mut swaps: EventWriter<SwapBody>,
+) {
+    let Some(mut run) = run else {
+        return;
+    };
+    *ruleset = std::mem::take(&mut run.ruleset);
+    commands.remove_resource::<DailyRun>();
+    commands.insert_resource(GameRng::default());
+    if entities.contains(game.player) {
+        swaps.send(SwapBody {
+            entity: game.player,
+            body: std::mem::take(&mut run.body),
+        });
+    }
+}
+
+pub struct DailyPlugin;
+
+impl Plugin for DailyPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(SystemSet::on_update(AppState::Battle).with_system(track_time_system))
+            .add_system_set(
+                SystemSet::on_enter(AppState::Intermission).with_system(record_daily_system),
+            )
+            .add_system_set(
+                SystemSet::on_enter(AppState::GameOver)
+                    .with_system(record_daily_system.before(record_result_system)),
+            )
+            .add_system_set(
+                SystemSet::on_enter(AppState::PreBattle).with_system(leave_daily_system),
+            );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_day_plays_out_the_same_and_is_played_once() {
+        assert_eq!(date_label(0), "1970-01-01");
+        assert_eq!(date_label(20_741), "2026-10-15");
+        assert_eq!(date_label(11_016), "2000-02-29");
+
+        let run = |day| DailyRun {
+            day,
+            seed: daily_seed(day),
+            practice: false,
+            rounds_cleared: 0,
+            time: 0.0,
+            body: Body::default(),
+            ruleset: Ruleset::default(),
+        };
+        let code =
+            |run: &DailyRun, stream, wave| random_body(&mut run.rng(stream, wave)).to_share_code();
+        let (today, again, tomorrow) = (run(20_741), run(20_741), run(20_742));
+        for stream in [Stream::Body, Stream::Enemy, Stream::Offer] {
+            for wave in 0..5 {
+                assert_eq!(code(&today, stream, wave), code(&again, stream, wave));
+                assert_ne!(code(&today, stream, wave), code(&tomorrow, stream, wave));
+            }
+        }
+        assert_ne!(
+            code(&today, Stream::Enemy, 1),
+            code(&today, Stream::Enemy, 2)
+        );
+        assert_ne!(
+            code(&today, Stream::Body, 0),
+            code(&today, Stream::Enemy, 0)
+        );
+
+        let mut history = DailyHistory::default();
+        assert!(!history.played(20_741));
+        history.start(20_741);
+        assert!(history.played(20_741));
+        let result = |day, time| DailyResult {
+            day,
+            rounds_cleared: 4,
+            time,
+            finished: true,
+        };
+        history.update(result(20_741, 90.0));
+        history.start(20_742);
+        history.update(result(20_742, 60.0));
+        assert_eq!(history.best().unwrap().day, 20_742);
+        for day in 0..MAX_DAYS as u32 {
+            history.start(day);
+        }
+        assert_eq!(history.results().len(), MAX_DAYS);
+        assert!(!history.played(20_741));
+
+        // Saved before most of the fields existed.
+        let old = ron::from_str::<DailyHistory>("(results: [(day: 3)])").unwrap();
+        assert_eq!(old.results()[0].rounds_cleared, 0);
+    }
+}
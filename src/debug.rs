@@ -0,0 +1,267 @@
+use std::{collections::VecDeque, fmt::Write};
+
+use bevy::{
+    diagnostic::{Diagnostics, FrameTimeDiagnosticsPlugin},
+    prelude::*,
+};
+
+use crate::{
+    battle::Animation,
+    body::Stats,
+    churn::{ChurnStats, DeferredSpawner},
+    performance::PerformanceProfile,
+    rate::AnimationRate,
+    ui::Fonts,
+    DamageEvent, Game, GameRng,
+};
+
+const EVENT_HISTORY: usize = 5;
+
+#[derive(Default)]
+pub struct DebugInfo {
+    pub enabled: bool,
+    fps: f64,
+    frame_time: f64,
+    seed: u64,
+    churn: ChurnStats,
+    churn_budget: usize,
+    bodies: Vec<String>,
+    events: VecDeque<String>,
+}
+
+impl DebugInfo {
+    pub fn push_event(&mut self, event: String) {
+        if self.events.len() == EVENT_HISTORY {
+            self.events.pop_front();
+        }
+        self.events.push_back(event);
+    }
+}
+
+#[derive(Component)]
+struct DebugOverlay;
+
+fn describe_body(
+    name: &str,
+    stats: &Stats,
+    animation: Option<&Animation>,
+    rate: Option<&AnimationRate>,
+) -> String {
+    let mut s = String::new();
+    let _ = writeln!(s, "{}", name);
+    let _ = writeln!(
+        s,
+        "  health {:.1}/{:.1}  energy {:.1}/{:.1}",
+        stats.health, stats.max_health, stats.energy, stats.max_energy
+    );
+    let _ = writeln!(
+        s,
+        "  weight {:.1}  width {:.2}  speed {:.2}  jump {:.1}",
+        stats.weight, stats.width, stats.speed, stats.jump_force
+    );
+    let _ = writeln!(
+        s,
+        "  reaction {:.3}  close acc {:.3}  far acc {:.3}",
+        stats.reaction_time, stats.close_accuracy, stats.far_accuracy
+    );
+    for capped in &stats.capped {
+        let bounds = capped.stat.bounds();
+        let _ = writeln!(
+            s,
+            "  {} {:.2} capped from {:.2} (min {} max {} knee {:?})",
+            capped.stat.id(),
+            capped.stat.value(stats),
+            capped.uncapped,
+            bounds.min,
+            bounds.max,
+            bounds.knee
+        );
+    }
+    let skills = stats
+        .skills
+        .iter()
+        .map(|skill| skill.get_name())
+        .collect::<Vec<_>>()
+        .join(", ");
+    let _ = writeln!(s, "  skills [{}]", skills);
+    match animation {
+        Some(animation) => {
+            let skill = stats
+                .skill(&animation.skill)
+                .map_or("<invalid>".to_string(), |skill| skill.get_name());
+            let _ = writeln!(
+                s,
+                "  animating {} ({}) {:.2}",
+                skill, animation.skill, animation.progress
+            );
+        }
+        None => {
+            let _ = writeln!(s, "  idle");
+        }
+    }
+    if let Some(rate) = rate {
+        let modifiers = rate
+            .modifiers()
+            .iter()
+            .map(|(source, multiplier)| format!("{} x{:.2}", source, multiplier))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let _ = writeln!(s, "  rate x{:.2} [{}]", rate.rate(), modifiers);
+    }
+    s
+}
+
+fn toggle_debug_system(keys: Res<Input<KeyCode>>, mut info: ResMut<DebugInfo>) {
+    if keys.just_pressed(KeyCode::F3) {
+        info.enabled = !info.enabled;
+    }
+}
+
+fn collect_debug_info_system(
+    mut info: ResMut<DebugInfo>,
+    diagnostics: Res<Diagnostics>,
+    game: Option<Res<Game>>,
+    rng: Res<GameRng>,
+    spawner: Res<DeferredSpawner>,
+    bodies: Query<(&Stats, Option<&Animation>, Option<&AnimationRate>)>,
+) {
+    if !info.enabled {
+        return;
+    }
+    if let Some(fps) = diagnostics
+        .get(FrameTimeDiagnosticsPlugin::FPS)
+        .and_then(|fps| fps.average())
+    {
+        info.fps = fps;
+    }
+    if let Some(frame_time) = diagnostics
+        .get(FrameTimeDiagnosticsPlugin::FRAME_TIME)
+        .and_then(|frame_time| frame_time.average())
+    {
+        info.frame_time = frame_time;
+    }
+    info.seed = rng.seed();
+    info.churn = spawner.stats();
+    info.churn_budget = spawner.budget;
+    info.bodies.clear();
+    if let Some(game) = game {
+        for (name, entity) in [("Player", game.player), ("Enemy", game.enemy)] {
+            if let Ok((stats, animation, rate)) = bodies.get(entity) {
+                info.bodies
+                    .push(describe_body(name, stats, animation, rate));
+            }
+        }
+    }
+}
+
+fn record_events_system(
+    mut info: ResMut<DebugInfo>,
+    game: Option<Res<Game>>,
+    mut damage: EventReader<DamageEvent>,
+    started: Query<(Entity, &Animation, &Stats), Added<Animation>>,
+) {
+    if !info.enabled {
+        damage.clear();
+        return;
+    }
+    let name = |entity: Entity| match game.as_ref() {
+        Some(game) if game.player == entity => "Player",
+        Some(game) if game.enemy == entity => "Enemy",
+        _ => "Unknown",
+    };
+    for (entity, animation, stats) in &started {
+        let skill = stats
+            .skill(&animation.skill)
+            .map_or("<invalid>".to_string(), |skill| skill.get_name());
+        info.push_event(format!("{} uses {}", name(entity), skill));
+    }
+    for event in damage.iter() {
+        info.push_event(format!(
+            "{} takes {:.1} damage",
+            name(event.target),
+            event.amount
+        ));
+    }
+}
+
+// The text is rebuilt at most every `debug_refresh` seconds.
+fn debug_overlay_system(
+    info: Res<DebugInfo>,
+    time: Res<Time>,
+    performance: Res<PerformanceProfile>,
+    mut since_refresh: Local<f32>,
+    mut overlay: Query<(&mut Text, &mut Visibility), With<DebugOverlay>>,
+) {
+    *since_refresh += time.delta_seconds();
+    let refresh = *since_refresh >= performance.debug_refresh;
+    if refresh {
+        *since_refresh = 0.0;
+    }
+    for (mut text, mut visibility) in &mut overlay {
+        visibility.is_visible = info.enabled;
+        if !info.enabled || !refresh {
+            continue;
+        }
+        let mut s = String::new();
+        let _ = writeln!(
+            s,
+            "FPS {:.0}  frame {:.2}ms  seed {}",
+            info.fps,
+            info.frame_time * 1000.0,
+            info.seed
+        );
+        let _ = writeln!(
+            s,
+            "churn {} spawned  {} despawned  budget {}  {} rebuilds queued",
+            info.churn.spawned, info.churn.despawned, info.churn_budget, info.churn.queued
+        );
+        for body in &info.bodies {
+            s.push_str(body);
+        }
+        let _ = writeln!(s, "Events");
+        for event in &info.events {
+            let _ = writeln!(s, "  {}", event);
+        }
+        text.sections[0].value = s;
+    }
+}
+
+fn debug_startup_system(mut commands: Commands, fonts: Res<Fonts>) {
+    commands
+        .spawn_bundle(TextBundle {
+            text: Text::from_section(
+                "",
+                TextStyle {
+                    font: fonts.normal(),
+                    font_size: 14.0,
+                    color: Color::WHITE,
+                },
+            ),
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    left: Val::Px(10.0),
+                    top: Val::Px(10.0),
+                    ..default()
+                },
+                ..default()
+            },
+            visibility: Visibility { is_visible: false },
+            ..default()
+        })
+        .insert(DebugOverlay);
+}
+
+pub struct DebugPlugin;
+
+impl Plugin for DebugPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugin(FrameTimeDiagnosticsPlugin)
+            .init_resource::<DebugInfo>()
+            .add_startup_system_to_stage(StartupStage::PostStartup, debug_startup_system)
+            .add_system(toggle_debug_system)
+            .add_system(collect_debug_info_system.after(toggle_debug_system))
+            .add_system(record_events_system.after(toggle_debug_system))
+            .add_system_to_stage(CoreStage::PostUpdate, debug_overlay_system);
+    }
+}
@@ -0,0 +1,26 @@
+use bevy::prelude::*;
+
+use crate::{body::Body, AppState, BattleSummary, Game};
+
+// Counts the battle that just ended towards the player's parts rusting, see
+// `Body::end_battle`. The enemy is a new body every battle, only the
+// player's parts last long enough to rust.
+fn decay_system(game: Res<Game>, summary: Res<BattleSummary>, mut bodies: Query<&mut Body>) {
+    let Ok(mut body) = bodies.get_mut(game.player) else {
+        return;
+    };
+    for part in body.end_battle(summary.player_health) {
+        info!("{} rusted over", part);
+    }
+}
+
+pub struct DecayPlugin;
+
+impl Plugin for DecayPlugin {
+    // After the game over screen rather than on it, the rebuilt stats would
+    // refill the health bars behind it.
+    fn build(&self, app: &mut App) {
+        app.add_system_set(SystemSet::on_exit(AppState::GameOver).with_system(decay_system))
+            .add_system_set(SystemSet::on_enter(AppState::Intermission).with_system(decay_system));
+    }
+}
@@ -0,0 +1,83 @@
+use bevy::prelude::*;
+
+use crate::{
+    battle::StrideEvent,
+    body::Body,
+    layer::RenderLayer,
+    performance::{ParticlePool, PerformanceProfile, PooledParticle},
+};
+
+const PUFF_TIME: f32 = 0.35;
+const PUFF_COLOR: Color = Color::rgba(0.6, 0.55, 0.5, 0.8);
+
+// A cloud kicked up by a footstep, it grows and fades until it's gone.
+#[derive(Component)]
+struct DustPuff {
+    remaining: f32,
+    size: f32,
+}
+
+fn spawn_dust_system(
+    mut commands: Commands,
+    performance: Res<PerformanceProfile>,
+    mut pool: ResMut<ParticlePool>,
+    mut strides: EventReader<StrideEvent>,
+    bodies: Query<&Body>,
+) {
+    for stride in strides.iter() {
+        if pool.take(&performance, 1) == 0 {
+            continue;
+        }
+        // Bigger feet kick up more dust.
+        let size = bodies
+            .get(stride.entity)
+            .ok()
+            .and_then(|body| body.leg(stride.leg_index))
+            .map_or(1.0, |leg| leg.stats().size());
+        commands
+            .spawn_bundle(SpriteBundle {
+                transform: Transform::from_translation(
+                    stride.position.extend(RenderLayer::Particle.z()),
+                )
+                .with_scale(Vec3::splat(0.15 * size)),
+                sprite: Sprite {
+                    color: PUFF_COLOR,
+                    ..default()
+                },
+                ..default()
+            })
+            .insert_bundle((
+                DustPuff {
+                    remaining: PUFF_TIME,
+                    size,
+                },
+                PooledParticle,
+            ));
+    }
+}
+
+fn fade_dust_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut puffs: Query<(Entity, &mut DustPuff, &mut Transform, &mut Sprite)>,
+) {
+    for (entity, mut puff, mut transform, mut sprite) in &mut puffs {
+        puff.remaining -= time.delta_seconds();
+        if puff.remaining <= 0.0 {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        let t = puff.remaining / PUFF_TIME;
+        transform.scale = Vec3::splat((0.15 + (1.0 - t) * 0.2) * puff.size);
+        sprite.color.set_a(PUFF_COLOR.a() * t);
+    }
+}
+
+pub struct DustPlugin;
+
+impl Plugin for DustPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(spawn_dust_system)
+            .add_system(fade_dust_system);
+    }
+}
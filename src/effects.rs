@@ -0,0 +1,59 @@
+use bevy::prelude::*;
+
+use crate::{locale::t, Invulnerable};
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Polarity {
+    Buff,
+    Debuff,
+}
+
+// Sent by whatever owns an effect, every frame it applies to a body. Displays
+// only read these, so they don't need to know the effect, and an effect that
+// stops being sent disappears the same frame.
+pub struct ActiveEffect {
+    pub target: Entity,
+    // Picks the `effect.<id>.name` and `effect.<id>.description` strings.
+    pub id: &'static str,
+    pub polarity: Polarity,
+    // None for effects that last until removed.
+    pub remaining: Option<f32>,
+    pub stacks: u32,
+}
+
+impl ActiveEffect {
+    pub fn name(&self) -> String {
+        t!(format!("effect.{}.name", self.id))
+    }
+
+    pub fn description(&self) -> String {
+        t!(
+            format!("effect.{}.description", self.id),
+            stacks = self.stacks
+        )
+    }
+}
+
+fn invulnerable_effect_system(
+    bodies: Query<Entity, With<Invulnerable>>,
+    mut effects: EventWriter<ActiveEffect>,
+) {
+    for target in &bodies {
+        effects.send(ActiveEffect {
+            target,
+            id: "invulnerable",
+            polarity: Polarity::Buff,
+            remaining: None,
+            stacks: 1,
+        });
+    }
+}
+
+pub struct EffectsPlugin;
+
+impl Plugin for EffectsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ActiveEffect>()
+            .add_system(invulnerable_effect_system);
+    }
+}
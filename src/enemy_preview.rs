@@ -0,0 +1,333 @@
+use bevy::{prelude::*, ui::FocusPolicy};
+
+use crate::{
+    body::{part_sprites, Body, PaletteMode, PartSprite, Stats},
+    knowledge::{self, Scouted},
+    locale::t,
+    profile::Profile,
+    theme::Theme,
+    ui::{Fonts, Toast},
+    AppState, Game,
+};
+
+// Scrap a scout costs, each one shows one more knowledge tier.
+pub const SCOUT_COST: u32 = 25;
+// The miniature is fit into this many pixels.
+const MINIATURE_SIZE: Vec2 = Vec2::new(140.0, 180.0);
+
+// The pre-battle panel showing who the player is about to fight. It's all UI
+// nodes, nothing in it is a body the battle could pick up.
+#[derive(Component)]
+struct EnemyPreview;
+
+// Rebuilt whenever the enemy or what's known about it changes.
+#[derive(Component)]
+struct Miniature;
+
+#[derive(Component)]
+struct PreviewText;
+
+#[derive(Component)]
+struct ScoutButton;
+
+// Pixel rects for the body's sprites at rest as their bottom left and top
+// right corners, fit into `size` with the feet at the bottom. Mirrored for a
+// body facing left like the enemy.
+fn miniature(sprites: &[PartSprite], size: Vec2, mirrored: bool) -> Vec<(Vec2, Vec2)> {
+    let rects = sprites
+        .iter()
+        .map(|sprite| {
+            let extent = sprite.scale.truncate().abs();
+            let mut min = sprite.translation.truncate() - (sprite.anchor.as_vec() + 0.5) * extent;
+            if mirrored {
+                min.x = -(min.x + extent.x);
+            }
+            (min, min + extent)
+        })
+        .collect::<Vec<_>>();
+    let (low, high) = rects.iter().fold(
+        (Vec2::splat(f32::INFINITY), Vec2::splat(f32::NEG_INFINITY)),
+        |(low, high), (min, max)| (low.min(*min), high.max(*max)),
+    );
+    let scale = (size / (high - low)).min_element();
+    let offset = Vec2::new((size.x - (high.x - low.x) * scale) / 2.0, 0.0);
+    rects
+        .into_iter()
+        .map(|(min, max)| ((min - low) * scale + offset, (max - low) * scale + offset))
+        .collect()
+}
+
+// What the panel says about the enemy at knowledge tier `known`, one line
+// each.
+fn preview_lines(body: &Body, stats: &Stats, known: u8) -> Vec<String> {
+    let mut lines = vec![
+        body.name().to_string(),
+        t!(
+            "preview.weight",
+            weight = stats.weight,
+            class = stats.class().name()
+        ),
+    ];
+    if known >= knowledge::NUMBERS {
+        lines.push(t!(
+            "preview.numbers",
+            health = stats.max_health,
+            energy = stats.max_energy,
+            speed = stats.speed
+        ));
+    }
+    if known >= knowledge::SKILLS {
+        let skills = stats
+            .skills
+            .iter()
+            .filter(|skill| skill.ability().is_some())
+            .map(|skill| skill.get_name())
+            .collect::<Vec<_>>();
+        lines.push(t!("preview.skills", skills = skills.join(", ")));
+    }
+    if known >= knowledge::PARTS {
+        let mut materials = Vec::new();
+        for sprite in part_sprites(body) {
+            let name = sprite.material.material.name();
+            if !materials.contains(&name) {
+                materials.push(name);
+            }
+        }
+        lines.push(t!("preview.parts", parts = materials.join(", ")));
+    } else {
+        lines.push(t!("preview.unknown"));
+    }
+    lines
+}
+
+fn spawn_preview_system(mut commands: Commands, fonts: Res<Fonts>, theme: Res<Theme>) {
+    let style = |size, color| TextStyle {
+        font: fonts.bold(),
+        font_size: size,
+        color,
+    };
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    right: Val::Px(20.0),
+                    top: Val::Px(20.0),
+                    ..default()
+                },
+                flex_direction: FlexDirection::ColumnReverse,
+                align_items: AlignItems::Center,
+                padding: UiRect::all(Val::Px(10.0)),
+                ..default()
+            },
+            color: theme.backdrop(0.6).into(),
+            ..default()
+        })
+        .insert(EnemyPreview)
+        .with_children(|parent| {
+            parent.spawn_bundle(TextBundle::from_section(
+                t!("preview.title"),
+                style(24.0, Color::WHITE),
+            ));
+            parent
+                .spawn_bundle(NodeBundle {
+                    style: Style {
+                        size: Size::new(Val::Px(MINIATURE_SIZE.x), Val::Px(MINIATURE_SIZE.y)),
+                        margin: UiRect::all(Val::Px(10.0)),
+                        ..default()
+                    },
+                    color: Color::NONE.into(),
+                    focus_policy: FocusPolicy::Pass,
+                    ..default()
+                })
+                .insert(Miniature);
+            parent
+                .spawn_bundle(TextBundle::default().with_style(Style {
+                    max_size: Size::new(Val::Px(260.0), Val::Undefined),
+                    ..default()
+                }))
+                .insert(PreviewText);
+            parent
+                .spawn_bundle(ButtonBundle {
+                    style: Style {
+                        padding: UiRect::all(Val::Px(6.0)),
+                        margin: UiRect::all(Val::Px(6.0)),
+                        ..default()
+                    },
+                    color: theme.normal_button.into(),
+                    ..default()
+                })
+                .insert(ScoutButton)
+                .with_children(|parent| {
+                    parent.spawn_bundle(TextBundle::from_section(
+                        t!("preview.scout", cost = SCOUT_COST),
+                        style(18.0, theme.button_text),
+                    ));
+                });
+        });
+}
+
+// A new enemy hasn't been scouted, whatever was paid for the last one. The
+// body is compared by share code, toggling a mutator rebuilds the same one.
+fn forget_scouting_system(
+    game: Res<Game>,
+    mut scouted: ResMut<Scouted>,
+    mut seen: Local<Option<String>>,
+    bodies: Query<&Body, Changed<Body>>,
+) {
+    let Ok(body) = bodies.get(game.enemy) else {
+        return;
+    };
+    let code = body.to_share_code();
+    if seen.as_ref() != Some(&code) {
+        *seen = Some(code);
+        if scouted.0 > 0 {
+            scouted.0 = 0;
+        }
+    }
+}
+
+fn refresh_preview_system(
+    mut commands: Commands,
+    game: Res<Game>,
+    fonts: Res<Fonts>,
+    palette: Res<PaletteMode>,
+    scouted: Res<Scouted>,
+    bodies: Query<(&Body, &Stats, ChangeTrackers<Stats>)>,
+    miniatures: Query<(Entity, ChangeTrackers<Miniature>), With<Miniature>>,
+    mut texts: Query<&mut Text, With<PreviewText>>,
+) {
+    let Ok((body, stats, stats_changes)) = bodies.get(game.enemy) else {
+        return;
+    };
+    for (entity, added) in &miniatures {
+        if !(added.is_added() || stats_changes.is_changed() || palette.is_changed()) {
+            continue;
+        }
+        let sprites = part_sprites(body);
+        let rects = miniature(&sprites, MINIATURE_SIZE, true);
+        commands.entity(entity).despawn_descendants();
+        commands.entity(entity).with_children(|parent| {
+            for (sprite, (min, max)) in sprites.iter().zip(rects) {
+                parent.spawn_bundle(NodeBundle {
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        position: UiRect {
+                            left: Val::Px(min.x),
+                            bottom: Val::Px(min.y),
+                            ..default()
+                        },
+                        size: Size::new(Val::Px(max.x - min.x), Val::Px(max.y - min.y)),
+                        ..default()
+                    },
+                    color: sprite.material.material.color(*palette).into(),
+                    focus_policy: FocusPolicy::Pass,
+                    ..default()
+                });
+            }
+        });
+    }
+    for mut text in &mut texts {
+        if !(text.sections.is_empty() || stats_changes.is_changed() || scouted.is_changed()) {
+            continue;
+        }
+        text.sections = preview_lines(body, stats, scouted.0)
+            .into_iter()
+            .map(|line| {
+                TextSection::new(
+                    line + "\n",
+                    TextStyle {
+                        font: fonts.bold(),
+                        font_size: 18.0,
+                        color: Color::WHITE,
+                    },
+                )
+            })
+            .collect();
+    }
+}
+
+fn scout_button_system(
+    theme: Res<Theme>,
+    mut profile: ResMut<Profile>,
+    mut scouted: ResMut<Scouted>,
+    mut toasts: EventWriter<Toast>,
+    mut buttons: Query<(&Interaction, &mut UiColor), (Changed<Interaction>, With<ScoutButton>)>,
+) {
+    for (interaction, mut color) in &mut buttons {
+        *color = match interaction {
+            Interaction::Clicked => theme.pressed_button,
+            Interaction::Hovered => theme.hovered_button,
+            Interaction::None => theme.normal_button,
+        }
+        .into();
+        if *interaction != Interaction::Clicked {
+            continue;
+        }
+        if scouted.0 >= knowledge::PARTS {
+            toasts.send(Toast(t!("preview.scouted")));
+        } else if profile.scrap < SCOUT_COST {
+            toasts.send(Toast(t!(
+                "preview.no_scrap",
+                cost = SCOUT_COST,
+                scrap = profile.scrap
+            )));
+        } else {
+            profile.scrap -= SCOUT_COST;
+            scouted.0 += 1;
+        }
+    }
+}
+
+fn despawn_preview_system(mut commands: Commands, previews: Query<Entity, With<EnemyPreview>>) {
+    for entity in &previews {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+pub struct EnemyPreviewPlugin;
+
+impl Plugin for EnemyPreviewPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(
+            SystemSet::on_enter(AppState::PreBattle).with_system(spawn_preview_system),
+        )
+        .add_system_set(
+            SystemSet::on_update(AppState::PreBattle)
+                .with_system(forget_scouting_system.before(scout_button_system))
+                .with_system(refresh_preview_system)
+                .with_system(scout_button_system),
+        )
+        .add_system_set(
+            SystemSet::on_exit(AppState::PreBattle).with_system(despawn_preview_system),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn miniatures_fit_their_box() {
+        let body = Body::default();
+        let sprites = part_sprites(&body);
+        let rects = miniature(&sprites, MINIATURE_SIZE, true);
+        assert_eq!(rects.len(), sprites.len());
+        let (low, high) = rects.iter().fold(
+            (Vec2::splat(f32::INFINITY), Vec2::splat(f32::NEG_INFINITY)),
+            |(low, high), (min, max)| (low.min(*min), high.max(*max)),
+        );
+        assert!(low.cmpge(Vec2::splat(-1e-3)).all());
+        assert!(high.cmple(MINIATURE_SIZE + 1e-3).all());
+        // As big as it fits, standing on the bottom edge.
+        assert!(low.y.abs() < 1e-3);
+        assert!(((high - low) - MINIATURE_SIZE).abs().min_element() < 1e-3);
+        // Torso first, the head sits on top of it.
+        assert!((rects[1].0.y - rects[0].1.y).abs() < 1e-3);
+        // Mirroring only flips the sides.
+        let unmirrored = miniature(&sprites, MINIATURE_SIZE, false);
+        let flip = |x: f32| MINIATURE_SIZE.x - x;
+        assert!((flip(unmirrored[2].1.x) - rects[2].0.x).abs() < 1e-3);
+    }
+}
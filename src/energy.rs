@@ -0,0 +1,153 @@
+use bevy::prelude::*;
+
+use crate::{
+    balance::BalanceConfig,
+    battle::Animation,
+    body::{Skill, Stats},
+    booster::Boosters,
+    mutators::Mutators,
+    timer::SimClock,
+    turn::Ruleset,
+    AppState, Game,
+};
+
+// Every energy number the game uses comes from here, so the editor's preview
+// and the battle can't disagree.
+
+// Energy a body gets back per second. Parts that drain energy lower the
+// maximum and with it the regen, a body drained below zero loses energy.
+pub fn regen_per_second(balance: &BalanceConfig, mutators: Mutators, stats: &Stats) -> f32 {
+    mutators.regen(balance.energy_regen * stats.max_energy * (1.0 + stats.regen_bonus))
+}
+
+// Energy taken when the skill starts, moving around is free.
+pub fn skill_cost(skill: &Skill) -> f32 {
+    skill.ability().map_or(0.0, |ability| ability.energy_cost)
+}
+
+pub fn can_afford(stats: &Stats, skill: &Skill) -> bool {
+    stats.energy >= skill_cost(skill)
+}
+
+// A skill costing more than the body can ever hold never plays.
+pub fn usable(stats: &Stats, skill: &Skill) -> bool {
+    skill_cost(skill) <= stats.max_energy
+}
+
+// How often a body starting out full could play `skill` back to back in
+// `seconds`, limited by how long it takes and what it costs.
+pub fn actions_per(
+    seconds: f32,
+    balance: &BalanceConfig,
+    mutators: Mutators,
+    stats: &Stats,
+    skill: &Skill,
+) -> f32 {
+    if !usable(stats, skill) {
+        return 0.0;
+    }
+    let by_time = seconds * balance.animation_speed;
+    let cost = skill_cost(skill);
+    if cost <= 0.0 {
+        return by_time;
+    }
+    let energy = stats.max_energy + regen_per_second(balance, mutators, stats).max(0.0) * seconds;
+    by_time.min((energy / cost).floor())
+}
+
+fn regen_system(
+    clock: Res<SimClock>,
+    balance: Res<BalanceConfig>,
+    ruleset: Res<Ruleset>,
+    game: Res<Game>,
+    boosters: Res<Boosters>,
+    mut bodies: Query<(Entity, &mut Stats)>,
+) {
+    let dt = clock.delta();
+    for (entity, mut stats) in &mut bodies {
+        let mut regen = regen_per_second(&balance, ruleset.mutators, &stats);
+        if entity == game.player {
+            regen = boosters.regen(regen);
+        }
+        let regen = regen * dt;
+        stats.energy = (stats.energy + regen).clamp(0.0, stats.max_energy.max(0.0));
+    }
+}
+
+// Energy a body actually paid to start a skill.
+pub struct SpendEvent {
+    pub entity: Entity,
+    pub amount: f32,
+}
+
+// Pays for every skill the frame it starts, whoever started it.
+fn spend_system(
+    mut started: Query<(Entity, &Animation, &mut Stats), Added<Animation>>,
+    mut spent: EventWriter<SpendEvent>,
+) {
+    for (entity, animation, mut stats) in &mut started {
+        if let Some(cost) = stats.skill(&animation.skill).map(skill_cost) {
+            let before = stats.energy;
+            stats.energy = (stats.energy - cost).max(0.0);
+            spent.send(SpendEvent {
+                entity,
+                amount: before - stats.energy,
+            });
+        }
+    }
+}
+
+pub struct EnergyPlugin;
+
+impl Plugin for EnergyPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<SpendEvent>().add_system_set(
+            SystemSet::on_update(AppState::Battle)
+                .with_system(regen_system)
+                .with_system(spend_system),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::body::{compute_stats, Body};
+
+    #[test]
+    fn expensive_skills_are_limited_by_energy() {
+        let balance = BalanceConfig::default();
+        let mut stats = compute_stats(&Body::default());
+        let jab = stats
+            .skills
+            .iter()
+            .find(|skill| matches!(skill, Skill::BasicMelee(_)))
+            .unwrap()
+            .clone();
+        let cost = skill_cost(&jab);
+        assert!(cost > 0.0);
+        assert_eq!(skill_cost(&Skill::WalkForward), 0.0);
+        assert_eq!(
+            actions_per(
+                10.0,
+                &balance,
+                Mutators::default(),
+                &stats,
+                &Skill::WalkForward
+            ),
+            10.0
+        );
+
+        // Regen can't keep up with a skill costing most of the tank.
+        stats.max_energy = cost * 2.5;
+        let actions = actions_per(10.0, &balance, Mutators::default(), &stats, &jab);
+        assert!((2.0..10.0).contains(&actions), "{}", actions);
+
+        stats.max_energy = cost / 2.0;
+        assert!(!usable(&stats, &jab));
+        assert_eq!(
+            actions_per(10.0, &balance, Mutators::default(), &stats, &jab),
+            0.0
+        );
+    }
+}
@@ -0,0 +1,282 @@
+use bevy::{prelude::*, ui::FocusPolicy};
+
+use crate::{
+    body::{BodyError, ShareCodeError},
+    locale::t,
+    ui::{Fonts, Scaled},
+};
+
+// Seconds an error stays up unless it's opened.
+const ERROR_TOAST_TIME: f32 = 5.0;
+
+// Whatever can go wrong reading or writing something from outside the game.
+// The game carries on after any of them, see ErrorToast.
+#[derive(Debug)]
+pub enum GameError {
+    // `file` couldn't be read or written, `reason` is what the system said.
+    Io {
+        file: String,
+        reason: String,
+    },
+    // `file` was read but isn't what the game writes. Lines count from 1.
+    Parse {
+        file: String,
+        line: usize,
+        reason: String,
+    },
+    // A body that doesn't fit together, every problem with it.
+    Validation(Vec<BodyError>),
+    // Written by a version of the game this one can't read.
+    IncompatibleVersion {
+        found: u8,
+        expected: u8,
+    },
+    // Not a build code at all.
+    ShareCode(ShareCodeError),
+    // Files under assets/ that didn't load, stand-ins took their place.
+    MissingAssets(Vec<String>),
+    Clipboard(String),
+}
+
+impl GameError {
+    pub fn io(file: &str, reason: impl ToString) -> Self {
+        GameError::Io {
+            file: file.to_string(),
+            reason: reason.to_string(),
+        }
+    }
+
+    pub fn parse(file: &str, err: ron::error::SpannedError) -> Self {
+        GameError::Parse {
+            file: file.to_string(),
+            line: err.position.line,
+            reason: err.code.to_string(),
+        }
+    }
+
+    // What's shown once the toast is opened, None if the message says it
+    // all.
+    pub fn details(&self) -> Option<String> {
+        let lines = match self {
+            GameError::Io { reason, .. } | GameError::Parse { reason, .. } => vec![reason.clone()],
+            GameError::Validation(problems) => problems.iter().map(ToString::to_string).collect(),
+            GameError::MissingAssets(paths) => paths.clone(),
+            GameError::IncompatibleVersion { .. }
+            | GameError::ShareCode(_)
+            | GameError::Clipboard(_) => Vec::new(),
+        };
+        (!lines.is_empty()).then(|| lines.join("\n"))
+    }
+}
+
+impl std::fmt::Display for GameError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let message = match self {
+            GameError::Io { file, .. } => t!("error.io", file = file),
+            GameError::Parse { file, line, .. } => t!("error.parse", file = file, line = *line),
+            GameError::Validation(problems) => t!("error.validation", count = problems.len()),
+            GameError::IncompatibleVersion { found, expected } => {
+                t!("error.version", found = *found, expected = *expected)
+            }
+            GameError::ShareCode(err) => return err.fmt(f),
+            GameError::MissingAssets(paths) => t!("error.missing_assets", count = paths.len()),
+            GameError::Clipboard(err) => t!("share.clipboard_error", error = err),
+        };
+        f.write_str(&message)
+    }
+}
+
+impl From<ShareCodeError> for GameError {
+    fn from(err: ShareCodeError) -> Self {
+        GameError::ShareCode(err)
+    }
+}
+
+// Puts an error up in the top right corner.
+pub struct ErrorToast(pub GameError);
+
+// Sends a failed load or save to the toasts, what it would have returned
+// otherwise.
+pub fn report<T>(errors: &mut EventWriter<ErrorToast>, result: Result<T, GameError>) -> Option<T> {
+    result
+        .map_err(|err| {
+            error!("{}", err);
+            errors.send(ErrorToast(err));
+        })
+        .ok()
+}
+
+#[derive(Component)]
+struct ErrorToastRoot;
+
+#[derive(Component)]
+struct ErrorToastEntry {
+    remaining: f32,
+    details: Option<String>,
+    // Opened with a click, it stays up until the next one.
+    open: bool,
+}
+
+fn spawn_error_root_system(mut commands: Commands) {
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    // Under the auto-battle toggle.
+                    top: Val::Px(50.0),
+                    right: Val::Px(10.0),
+                    ..default()
+                },
+                flex_direction: FlexDirection::ColumnReverse,
+                align_items: AlignItems::FlexEnd,
+                max_size: Size::new(Val::Px(420.0), Val::Auto),
+                ..default()
+            },
+            color: Color::NONE.into(),
+            focus_policy: FocusPolicy::Pass,
+            ..default()
+        })
+        .insert(ErrorToastRoot);
+}
+
+fn error_toast_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    fonts: Res<Fonts>,
+    mut errors: EventReader<ErrorToast>,
+    roots: Query<Entity, With<ErrorToastRoot>>,
+    mut entries: Query<(Entity, &mut ErrorToastEntry)>,
+) {
+    for (entity, mut entry) in &mut entries {
+        if entry.open {
+            continue;
+        }
+        entry.remaining -= time.delta_seconds();
+        if entry.remaining <= 0.0 {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+    let Ok(root) = roots.get_single() else {
+        return;
+    };
+    for ErrorToast(err) in errors.iter() {
+        let details = err.details();
+        let mut sections = vec![TextSection::new(
+            err.to_string(),
+            TextStyle {
+                font: fonts.bold(),
+                font_size: 20.0,
+                color: Color::WHITE,
+            },
+        )];
+        if details.is_some() {
+            sections.push(TextSection::new(
+                format!("\n{}", t!("error.more")),
+                TextStyle {
+                    font: fonts.normal(),
+                    font_size: 20.0,
+                    color: Color::rgb(0.8, 0.8, 0.8),
+                },
+            ));
+        }
+        let entry = commands
+            .spawn_bundle(ButtonBundle {
+                style: Style {
+                    padding: UiRect::all(Val::Px(10.0)),
+                    margin: UiRect::all(Val::Px(5.0)),
+                    ..default()
+                },
+                color: Color::rgba(0.45, 0.05, 0.05, 0.9).into(),
+                ..default()
+            })
+            .insert(ErrorToastEntry {
+                remaining: ERROR_TOAST_TIME,
+                details,
+                open: false,
+            })
+            .with_children(|parent| {
+                parent
+                    .spawn_bundle(TextBundle::from_sections(sections).with_style(Style {
+                        max_size: Size::new(Val::Px(400.0), Val::Auto),
+                        ..default()
+                    }))
+                    .insert(Scaled::font(20.0));
+            })
+            .id();
+        commands.entity(root).add_child(entry);
+    }
+}
+
+// A click opens the details, the next one closes the toast.
+fn error_click_system(
+    mut commands: Commands,
+    mut entries: Query<
+        (Entity, &Interaction, &mut ErrorToastEntry, &Children),
+        Changed<Interaction>,
+    >,
+    mut texts: Query<&mut Text>,
+) {
+    for (entity, interaction, mut entry, children) in &mut entries {
+        if *interaction != Interaction::Clicked {
+            continue;
+        }
+        let Some(details) = entry.details.clone().filter(|_| !entry.open) else {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        };
+        entry.open = true;
+        for child in children {
+            if let Ok(mut text) = texts.get_mut(*child) {
+                if let Some(section) = text.sections.get_mut(1) {
+                    section.value = format!("\n{}", details);
+                }
+            }
+        }
+    }
+}
+
+// Only the windowed game shows them, the sim and the command line tools log
+// what goes wrong and stop.
+pub struct ErrorPlugin;
+
+impl Plugin for ErrorPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ErrorToast>()
+            .add_startup_system(spawn_error_root_system)
+            .add_system(error_toast_system)
+            .add_system(error_click_system);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::body::Body;
+
+    #[test]
+    fn errors_read_without_their_details() {
+        let Err(garbage) = Body::from_share_code("not a build code") else {
+            panic!("garbage decoded");
+        };
+        assert!(matches!(
+            garbage,
+            GameError::ShareCode(ShareCodeError::Encoding)
+        ));
+        assert!(garbage.details().is_none());
+
+        let Err(err) = ron::from_str::<Body>("(\n  torso: 3,\n)") else {
+            panic!("garbage parsed");
+        };
+        let parse = GameError::parse("profile.ron", err);
+        assert!(matches!(parse, GameError::Parse { line: 2, .. }));
+        assert!(parse.to_string().contains("profile.ron"));
+        assert!(parse.details().is_some());
+
+        let validation = GameError::Validation(vec![
+            BodyError::MissingHead,
+            BodyError::TooManyArms { slots: 2, found: 3 },
+        ]);
+        assert_eq!(validation.details().unwrap().lines().count(), 2);
+    }
+}
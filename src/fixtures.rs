@@ -0,0 +1,11 @@
+use rand::{rngs::StdRng, SeedableRng};
+
+use crate::body::{random_body_with_slots, Body};
+
+// Generated bodies shared by the tests and the benchmarks. The same arguments
+// always give the same bodies.
+pub fn seeded_bodies(count: usize, arm_slots: usize, leg_slots: usize) -> Vec<Body> {
+    (0..count as u64)
+        .map(|seed| random_body_with_slots(&mut StdRng::seed_from_u64(seed), arm_slots, leg_slots))
+        .collect()
+}
@@ -0,0 +1,242 @@
+use bevy::{input::InputSystem, prelude::*, ui::FocusPolicy, ui::UiSystem};
+
+use crate::{text_input::TextInput, theme::Theme};
+
+// Buttons outside of it can't be picked while one is on screen, like the
+// ones behind a dialog.
+#[derive(Component)]
+pub struct FocusScope;
+
+// The button picked with Tab or the gamepad's d-pad, Enter, Space or the
+// gamepad's south button press it.
+#[derive(Default)]
+pub struct KeyboardFocus {
+    entity: Option<Entity>,
+    // Pressed last frame and let go this one.
+    pressed: Option<Entity>,
+}
+
+#[derive(Component)]
+struct FocusOutline;
+
+// The next button after `current` going `step` along `buttons`, which are in
+// reading order. Starts from either end without one.
+fn cycle(buttons: &[Entity], current: Option<Entity>, step: isize) -> Option<Entity> {
+    if buttons.is_empty() {
+        return None;
+    }
+    let len = buttons.len() as isize;
+    let next = match current.and_then(|current| buttons.iter().position(|b| *b == current)) {
+        Some(i) => (i as isize + step).rem_euclid(len),
+        None if step < 0 => len - 1,
+        None => 0,
+    };
+    Some(buttons[next as usize])
+}
+
+fn in_scope(entity: Entity, scope: &[Entity], parents: &Query<&Parent>) -> bool {
+    let mut current = entity;
+    loop {
+        if scope.contains(&current) {
+            return true;
+        }
+        match parents.get(current) {
+            Ok(parent) => current = parent.get(),
+            Err(_) => return false,
+        }
+    }
+}
+
+// Runs right after the mouse has had its say, so a press lasts exactly one
+// frame for the systems in the update stage, like a click.
+fn navigate_system(
+    keys: Res<Input<KeyCode>>,
+    mouse: Res<Input<MouseButton>>,
+    gamepads: Res<Gamepads>,
+    pads: Res<Input<GamepadButton>>,
+    mut focus: ResMut<KeyboardFocus>,
+    inputs: Query<&TextInput>,
+    scopes: Query<Entity, With<FocusScope>>,
+    parents: Query<&Parent>,
+    buttons: Query<
+        (Entity, &Node, &GlobalTransform, &ComputedVisibility),
+        (With<Button>, Without<TextInput>),
+    >,
+    mut interactions: Query<&mut Interaction>,
+) {
+    // Only touched when there's something to change, the outline redraws
+    // on every change.
+    if let Some(pressed) = focus.pressed {
+        focus.pressed = None;
+        if let Ok(mut interaction) = interactions.get_mut(pressed) {
+            if *interaction == Interaction::Clicked {
+                *interaction = Interaction::None;
+            }
+        }
+    }
+    if mouse.get_just_pressed().next().is_some() {
+        if focus.entity.is_some() {
+            focus.entity = None;
+        }
+        return;
+    }
+    // Tab and Enter belong to the field being typed in.
+    if inputs.iter().any(TextInput::focused) {
+        return;
+    }
+    let pad = |button| {
+        gamepads
+            .iter()
+            .any(|gamepad| pads.just_pressed(GamepadButton::new(*gamepad, button)))
+    };
+    let shift = keys.any_pressed([KeyCode::LShift, KeyCode::RShift]);
+    let step = if keys.just_pressed(KeyCode::Tab) {
+        if shift {
+            -1
+        } else {
+            1
+        }
+    } else if pad(GamepadButtonType::DPadDown) || pad(GamepadButtonType::DPadRight) {
+        1
+    } else if pad(GamepadButtonType::DPadUp) || pad(GamepadButtonType::DPadLeft) {
+        -1
+    } else {
+        0
+    };
+    let press =
+        keys.any_just_pressed([KeyCode::Return, KeyCode::Space]) || pad(GamepadButtonType::South);
+    if step == 0 && !press {
+        return;
+    }
+    let scope = scopes.iter().collect::<Vec<_>>();
+    let mut focusable = buttons
+        .iter()
+        .filter(|(entity, node, _, visibility)| {
+            visibility.is_visible_in_hierarchy()
+                && node.size != Vec2::ZERO
+                && (scope.is_empty() || in_scope(*entity, &scope, &parents))
+        })
+        .map(|(entity, _, transform, _)| {
+            // UI positions count up from the bottom, top rows come first.
+            let at = transform.translation();
+            ((-at.y).round() as i32, at.x.round() as i32, entity)
+        })
+        .collect::<Vec<_>>();
+    focusable.sort();
+    let focusable = focusable
+        .into_iter()
+        .map(|(_, _, entity)| entity)
+        .collect::<Vec<_>>();
+    if focus
+        .entity
+        .is_some_and(|entity| !focusable.contains(&entity))
+    {
+        focus.entity = None;
+    }
+    if step != 0 {
+        focus.entity = cycle(&focusable, focus.entity, step);
+    }
+    if let Some(entity) = focus.entity.filter(|_| press) {
+        if let Ok(mut interaction) = interactions.get_mut(entity) {
+            *interaction = Interaction::Clicked;
+            focus.pressed = Some(entity);
+        }
+    }
+}
+
+// Four thin bars along the edges of the focused button, drawn in its own
+// children so they follow it around.
+fn outline_system(
+    mut commands: Commands,
+    theme: Res<Theme>,
+    focus: Res<KeyboardFocus>,
+    entities: Query<(), With<Node>>,
+    outlines: Query<(Entity, &Parent), With<FocusOutline>>,
+) {
+    if !focus.is_changed() && !theme.is_changed() {
+        return;
+    }
+    let target = focus.entity.filter(|entity| entities.contains(*entity));
+    let mut outlined = false;
+    for (entity, parent) in &outlines {
+        if Some(parent.get()) == target && !theme.is_changed() {
+            outlined = true;
+        } else {
+            commands.entity(entity).despawn();
+        }
+    }
+    let Some(button) = target.filter(|_| !outlined) else {
+        return;
+    };
+    let full = Val::Percent(100.0);
+    let edge = Val::Px(theme.outline_width);
+    let zero = Val::Px(0.0);
+    commands.entity(button).with_children(|parent| {
+        for (size, position) in [
+            (
+                Size::new(full, edge),
+                UiRect::new(zero, Val::Auto, zero, Val::Auto),
+            ),
+            (
+                Size::new(full, edge),
+                UiRect::new(zero, Val::Auto, Val::Auto, zero),
+            ),
+            (
+                Size::new(edge, full),
+                UiRect::new(zero, Val::Auto, zero, Val::Auto),
+            ),
+            (
+                Size::new(edge, full),
+                UiRect::new(Val::Auto, zero, zero, Val::Auto),
+            ),
+        ] {
+            parent
+                .spawn_bundle(NodeBundle {
+                    style: Style {
+                        size,
+                        position,
+                        position_type: PositionType::Absolute,
+                        ..default()
+                    },
+                    color: theme.outline.into(),
+                    focus_policy: FocusPolicy::Pass,
+                    ..default()
+                })
+                .insert(FocusOutline);
+        }
+    });
+}
+
+pub struct FocusPlugin;
+
+impl Plugin for FocusPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<KeyboardFocus>()
+            .add_system_to_stage(
+                CoreStage::PreUpdate,
+                navigate_system.after(InputSystem).after(UiSystem::Focus),
+            )
+            .add_system_to_stage(CoreStage::PostUpdate, outline_system);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn focus_wraps_around_the_buttons() {
+        let buttons = [0, 1, 2].map(Entity::from_raw);
+        assert_eq!(cycle(&[], None, 1), None);
+        assert_eq!(cycle(&buttons, None, 1), Some(buttons[0]));
+        assert_eq!(cycle(&buttons, None, -1), Some(buttons[2]));
+        assert_eq!(cycle(&buttons, Some(buttons[2]), 1), Some(buttons[0]));
+        assert_eq!(cycle(&buttons, Some(buttons[0]), -1), Some(buttons[2]));
+        assert_eq!(cycle(&buttons, Some(buttons[1]), 1), Some(buttons[2]));
+        // Gone since, it starts over.
+        assert_eq!(
+            cycle(&buttons, Some(Entity::from_raw(7)), 1),
+            Some(buttons[0])
+        );
+    }
+}
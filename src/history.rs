@@ -0,0 +1,258 @@
+use std::collections::BTreeMap;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    achievements::BattleStats,
+    body::{Body, Material},
+    error::{report, ErrorToast},
+    profile::{record_result_system, Profile},
+    survival::SurvivalRun,
+    AppState, BattleSummary, Game,
+};
+
+// Older runs are dropped past this many.
+pub const MAX_RUNS: usize = 20;
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PartSwap {
+    // The round the part was first fought with.
+    pub round: u32,
+    pub part: String,
+}
+
+// One survival run. Every field has a default so runs saved by older
+// versions still load.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RunRecord {
+    pub rounds_cleared: u32,
+    pub damage_dealt: f32,
+    pub damage_taken: f32,
+    pub scrap_earned: u32,
+    pub scrap_spent: u32,
+    pub swaps: Vec<PartSwap>,
+    // Parts of each material the body fought with, once per round.
+    pub materials: BTreeMap<Material, u32>,
+    // The game was closed or the run left before it was lost.
+    pub partial: bool,
+}
+
+impl RunRecord {
+    // Share of the parts fought with for every material used, most used
+    // first.
+    pub fn material_shares(&self) -> Vec<(Material, f32)> {
+        let total = self.materials.values().sum::<u32>();
+        if total == 0 {
+            return Vec::new();
+        }
+        let mut shares = self
+            .materials
+            .iter()
+            .map(|(material, count)| (*material, *count as f32 / total as f32))
+            .collect::<Vec<_>>();
+        shares.sort_by(|a, b| b.1.total_cmp(&a.1));
+        shares
+    }
+
+    fn fought_with(&mut self, body: &Body) {
+        for material in body.materials() {
+            *self.materials.entry(material).or_default() += 1;
+        }
+    }
+}
+
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RunHistory {
+    // Oldest first.
+    runs: Vec<RunRecord>,
+    // Saved with the profile as it goes, a run still here on startup was
+    // cut short by the game closing.
+    current: Option<RunRecord>,
+    // Battles won in a row in any mode, survival waves included.
+    pub streak: u32,
+    pub best_streak: u32,
+}
+
+impl RunHistory {
+    pub fn runs(&self) -> &[RunRecord] {
+        &self.runs
+    }
+
+    pub fn last(&self) -> Option<&RunRecord> {
+        self.runs.last()
+    }
+
+    // Most rounds cleared, ties go to the most damage dealt and then the
+    // latest run.
+    pub fn best(&self) -> Option<&RunRecord> {
+        self.runs.iter().max_by(|a, b| {
+            a.rounds_cleared
+                .cmp(&b.rounds_cleared)
+                .then(a.damage_dealt.total_cmp(&b.damage_dealt))
+        })
+    }
+
+    fn start(&mut self) {
+        self.finish(true);
+        self.current = Some(RunRecord::default());
+    }
+
+    fn finish(&mut self, partial: bool) {
+        if let Some(mut run) = self.current.take() {
+            run.partial = partial;
+            self.runs.push(run);
+            let excess = self.runs.len().saturating_sub(MAX_RUNS);
+            self.runs.drain(..excess);
+        }
+    }
+
+    fn record_battle(&mut self, won: bool) {
+        self.streak = if won { self.streak + 1 } else { 0 };
+        self.best_streak = self.best_streak.max(self.streak);
+    }
+
+    // Only counted during a run, `round` is the one the part is first
+    // fought in.
+    pub fn record_swap(&mut self, round: u32, part: &str) {
+        if let Some(run) = &mut self.current {
+            run.swaps.push(PartSwap {
+                round,
+                part: part.to_string(),
+            });
+        }
+    }
+}
+
+fn recover_run_system(mut profile: ResMut<Profile>, mut errors: EventWriter<ErrorToast>) {
+    if profile.history.current.is_some() {
+        profile.history.finish(true);
+        report(&mut errors, profile.save());
+    }
+}
+
+// Starts a record with every survival run, and counts the scrap that comes
+// and goes while it lasts whatever it's spent on.
+fn track_run_system(
+    run: Option<Res<SurvivalRun>>,
+    mut profile: ResMut<Profile>,
+    mut scrap: Local<u32>,
+) {
+    if run.is_some_and(|run| run.is_added()) {
+        profile.history.start();
+        *scrap = profile.scrap;
+        return;
+    }
+    if profile.history.current.is_none() || profile.scrap == *scrap {
+        return;
+    }
+    let (before, now) = (*scrap, profile.scrap);
+    *scrap = now;
+    let Some(run) = &mut profile.history.current else {
+        return;
+    };
+    if now > before {
+        run.scrap_earned += now - before;
+    } else {
+        run.scrap_spent += before - now;
+    }
+}
+
+pub fn end_battle_system(
+    game: Res<Game>,
+    summary: Res<BattleSummary>,
+    stats: Res<BattleStats>,
+    state: Res<State<AppState>>,
+    mut profile: ResMut<Profile>,
+    bodies: Query<&Body>,
+    mut errors: EventWriter<ErrorToast>,
+) {
+    let won = summary.winner == Some(game.player);
+    let history = &mut profile.history;
+    history.record_battle(won);
+    if let Some(run) = &mut history.current {
+        run.damage_dealt += stats.damage_dealt;
+        run.damage_taken += stats.damage_taken;
+        if won {
+            run.rounds_cleared += 1;
+        }
+        if let Ok(body) = bodies.get(game.player) {
+            run.fought_with(body);
+        }
+        if *state.current() == AppState::GameOver {
+            history.finish(false);
+        }
+    }
+    report(&mut errors, profile.save());
+}
+
+// Going back to the menu without losing leaves the run behind.
+fn leave_run_system(mut profile: ResMut<Profile>, mut errors: EventWriter<ErrorToast>) {
+    if profile.history.current.is_some() {
+        profile.history.finish(true);
+        report(&mut errors, profile.save());
+    }
+}
+
+pub struct HistoryPlugin;
+
+impl Plugin for HistoryPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_startup_system(recover_run_system)
+            .add_system(track_run_system)
+            .add_system_set(
+                SystemSet::on_enter(AppState::Intermission).with_system(end_battle_system),
+            )
+            .add_system_set(
+                SystemSet::on_enter(AppState::GameOver)
+                    .with_system(end_battle_system.after(record_result_system)),
+            )
+            .add_system_set(SystemSet::on_enter(AppState::PreBattle).with_system(leave_run_system));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn runs_are_capped_and_cut_short_ones_flagged() {
+        let mut history = RunHistory::default();
+        for rounds in 0..MAX_RUNS as u32 + 3 {
+            history.start();
+            history.current.as_mut().unwrap().rounds_cleared = rounds;
+            history.finish(false);
+        }
+        assert_eq!(history.runs().len(), MAX_RUNS);
+        assert_eq!(history.runs()[0].rounds_cleared, 3);
+        assert_eq!(history.best().unwrap().rounds_cleared, MAX_RUNS as u32 + 2);
+
+        // Starting over with one still going keeps it as a partial run.
+        history.start();
+        history.record_swap(2, "Rusty Arm");
+        history.start();
+        let partial = &history.runs()[MAX_RUNS - 1];
+        assert!(partial.partial);
+        assert_eq!(partial.swaps[0].round, 2);
+
+        history.record_battle(true);
+        history.record_battle(true);
+        history.record_battle(false);
+        history.record_battle(true);
+        assert_eq!((history.streak, history.best_streak), (1, 2));
+
+        let mut run = RunRecord::default();
+        run.fought_with(&Body::default());
+        let shares = run.material_shares();
+        assert!((shares.iter().map(|(_, share)| share).sum::<f32>() - 1.0).abs() < 1e-6);
+        assert!(shares.windows(2).all(|pair| pair[0].1 >= pair[1].1));
+
+        // Saved before most of the fields existed.
+        let old = ron::from_str::<RunHistory>("(runs: [(rounds_cleared: 4)])").unwrap();
+        assert_eq!(old.runs()[0].rounds_cleared, 4);
+        assert!(old.runs()[0].swaps.is_empty() && old.current.is_none());
+    }
+}
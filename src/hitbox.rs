@@ -0,0 +1,171 @@
+use bevy::{ecs::system::SystemParam, prelude::*, sprite::Anchor};
+
+use crate::body::{HitPart, Limb, PartEntities};
+
+// Parts are positioned relative to their body and GlobalTransform lags a frame
+// behind, so everything here is worked out from the local transforms.
+
+// Takes a point from the world into `transform`'s space. None while the space
+// is squashed flat, like halfway through a turn.
+fn to_local(transform: &Transform, point: Vec3) -> Option<Vec3> {
+    let matrix = transform.compute_matrix();
+    (matrix.determinant() != 0.0).then(|| matrix.inverse().transform_point3(point))
+}
+
+// The far end of a limb in the world. Limbs hang from their anchor, so that's
+// `scale.y` down the limb's own axis, mirrored along with the body's facing.
+pub fn limb_endpoint(body: &Transform, limb: &Transform) -> Vec2 {
+    let tip = limb.translation + limb.rotation * Vec3::new(0.0, -limb.scale.y, 0.0);
+    body.mul_vec3(tip).truncate()
+}
+
+// Whether a world point lies on a part drawn as a unit sprite with `anchor`.
+pub fn part_contains(body: &Transform, part: &Transform, anchor: &Anchor, point: Vec2) -> bool {
+    let Some(local) = to_local(body, point.extend(0.0)).and_then(|p| to_local(part, p)) else {
+        return false;
+    };
+    let center = -anchor.as_vec();
+    (local.x - center.x).abs() <= 0.5 && (local.y - center.y).abs() <= 0.5
+}
+
+// Corners of the rectangle `part_contains` tests against, in the world. Goes
+// through the full matrices, a mirrored body with a rotated limb isn't a
+// plain rotation and scale any more.
+pub fn part_corners(body: &Transform, part: &Transform, anchor: &Anchor) -> [Vec2; 4] {
+    let matrix = body.compute_matrix() * part.compute_matrix();
+    let center = -anchor.as_vec();
+    [(-0.5, -0.5), (0.5, -0.5), (0.5, 0.5), (-0.5, 0.5)].map(|(x, y)| {
+        let corner = center + Vec2::new(x, y);
+        matrix.transform_point3(corner.extend(0.0)).truncate()
+    })
+}
+
+// Limbs are drawn over the torso, so they're checked first.
+fn hit_order(part: HitPart) -> u8 {
+    match part {
+        HitPart::Limb(Limb::Arm(_)) => 0,
+        HitPart::Head => 1,
+        HitPart::Torso => 2,
+        HitPart::Limb(Limb::Leg(_)) => 3,
+    }
+}
+
+// Where the parts of spawned bodies are right now.
+#[derive(SystemParam)]
+pub struct BodyGeometry<'w, 's> {
+    parts: Query<'w, 's, &'static PartEntities>,
+    transforms: Query<'w, 's, &'static Transform>,
+    sprites: Query<'w, 's, &'static Sprite>,
+}
+
+impl<'w, 's> BodyGeometry<'w, 's> {
+    pub fn limb_world_endpoint(&self, body_entity: Entity, limb: Limb) -> Option<Vec2> {
+        let entity = self.parts.get(body_entity).ok()?.get(HitPart::Limb(limb))?;
+        let [body, limb] = self.transforms.get_many([body_entity, entity]).ok()?;
+        Some(limb_endpoint(body, limb))
+    }
+
+    // The part of the body `point` lands on, if any.
+    pub fn body_hit_test(&self, body_entity: Entity, point: Vec2) -> Option<HitPart> {
+        let body = self.transforms.get(body_entity).ok()?;
+        let mut parts = self.parts.get(body_entity).ok()?.iter().collect::<Vec<_>>();
+        parts.sort_by_key(|(part, _)| hit_order(*part));
+        parts.into_iter().find_map(|(part, entity)| {
+            let transform = self.transforms.get(entity).ok()?;
+            let sprite = self.sprites.get(entity).ok()?;
+            part_contains(body, transform, &sprite.anchor, point).then_some(part)
+        })
+    }
+
+    // Every rectangle `body_hit_test` checks, see `part_corners`.
+    pub fn part_outlines(&self, body_entity: Entity) -> Vec<(HitPart, [Vec2; 4])> {
+        let (Ok(body), Ok(parts)) = (
+            self.transforms.get(body_entity),
+            self.parts.get(body_entity),
+        ) else {
+            return Vec::new();
+        };
+        parts
+            .iter()
+            .filter_map(|(part, entity)| {
+                let transform = self.transforms.get(entity).ok()?;
+                let sprite = self.sprites.get(entity).ok()?;
+                Some((part, part_corners(body, transform, &sprite.anchor)))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::FRAC_PI_2;
+
+    use super::*;
+
+    fn body(x: f32, facing: f32) -> Transform {
+        Transform::from_xyz(x, 0.0, 0.0).with_scale(Vec3::new(facing, 1.0, 1.0))
+    }
+
+    // An arm hanging from the right shoulder of a torso one unit tall.
+    fn arm(rotation: f32) -> Transform {
+        Transform::from_xyz(0.15, 1.7, 0.0)
+            .with_rotation(Quat::from_rotation_z(rotation))
+            .with_scale(Vec3::new(0.15, 0.8, 1.0))
+    }
+
+    fn torso() -> Transform {
+        Transform::from_xyz(0.0, 0.7, 0.0).with_scale(Vec3::new(0.3, 1.0, 1.0))
+    }
+
+    #[test]
+    fn raised_arms_point_where_the_body_faces() {
+        let hanging = limb_endpoint(&body(2.0, 1.0), &arm(0.0));
+        assert!(hanging.abs_diff_eq(Vec2::new(2.15, 0.9), 1e-4));
+        let right = limb_endpoint(&body(2.0, 1.0), &arm(FRAC_PI_2));
+        assert!(right.abs_diff_eq(Vec2::new(2.95, 1.7), 1e-4));
+        let left = limb_endpoint(&body(2.0, -1.0), &arm(FRAC_PI_2));
+        assert!(left.abs_diff_eq(Vec2::new(1.05, 1.7), 1e-4));
+    }
+
+    #[test]
+    fn points_land_inside_the_drawn_rectangle() {
+        let anchor = Anchor::BottomCenter;
+        let inside = |x, y| part_contains(&body(4.0, -1.0), &torso(), &anchor, Vec2::new(x, y));
+        assert!(inside(4.0, 1.2));
+        assert!(inside(3.86, 0.71));
+        assert!(!inside(3.8, 1.2));
+        assert!(!inside(4.0, 0.6));
+        assert!(!inside(4.0, 1.8));
+        // The drawn outline is exactly what's hit, even for a raised arm on
+        // a body facing left.
+        for (body, part, anchor) in [
+            (body(4.0, -1.0), torso(), anchor.clone()),
+            (body(4.0, -1.0), arm(1.0), Anchor::TopCenter),
+        ] {
+            let corners = part_corners(&body, &part, &anchor);
+            let center = corners.iter().sum::<Vec2>() / 4.0;
+            for corner in corners {
+                assert!(part_contains(
+                    &body,
+                    &part,
+                    &anchor,
+                    corner.lerp(center, 0.01)
+                ));
+                assert!(!part_contains(
+                    &body,
+                    &part,
+                    &anchor,
+                    center.lerp(corner, 1.01)
+                ));
+            }
+        }
+        // A body squashed flat mid-turn can't be hit.
+        let flat = body(4.0, 0.0);
+        assert!(!part_contains(
+            &flat,
+            &torso(),
+            &anchor,
+            Vec2::new(4.0, 1.2)
+        ));
+    }
+}
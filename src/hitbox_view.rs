@@ -0,0 +1,187 @@
+use bevy::{prelude::*, transform::TransformSystem};
+
+use crate::{
+    battle::{Animation, GroundSprite},
+    body::{Facing, HitPart, Stats},
+    hitbox::{part_corners, BodyGeometry},
+    layer::RenderLayer,
+    projectile::Projectile,
+    Game,
+};
+
+// Only debug builds and builds with the `hitboxes` feature draw anything, the
+// toggle stays but does nothing elsewhere.
+pub const ENABLED: bool = cfg!(any(debug_assertions, feature = "hitboxes"));
+
+// What gets hit is blue, limbs a lighter shade. What does the hitting is red,
+// shots are orange and the floor is grey.
+const BODY_COLOR: Color = Color::rgb(0.2, 0.5, 1.0);
+const LIMB_COLOR: Color = Color::rgb(0.4, 0.85, 1.0);
+const STRIKE_COLOR: Color = Color::rgb(1.0, 0.2, 0.2);
+const REACH_COLOR: Color = Color::rgba(1.0, 0.85, 0.2, 0.6);
+const SHOT_COLOR: Color = Color::rgb(1.0, 0.6, 0.1);
+const ARENA_COLOR: Color = Color::rgb(0.6, 0.6, 0.6);
+const LINE_WIDTH: f32 = 0.03;
+const MARKER_SIZE: f32 = 0.12;
+const REACH_HEIGHT: f32 = 0.1;
+// How far from a hit point in its animation an attack counts as striking.
+const STRIKE_WINDOW: f32 = 0.05;
+// Toggled with F4, the `hitboxes` console command or from the training room.
+#[derive(Default)]
+pub struct ShowHitboxes(pub bool);
+
+#[derive(Component)]
+struct HitboxSprite;
+
+fn spawn_line(commands: &mut Commands, from: Vec2, to: Vec2, color: Color) {
+    let offset = to - from;
+    let transform =
+        Transform::from_translation(((from + to) / 2.0).extend(RenderLayer::Overlay.z()))
+            .with_rotation(Quat::from_rotation_z(offset.y.atan2(offset.x)))
+            .with_scale(Vec3::new(offset.length() + LINE_WIDTH, LINE_WIDTH, 1.0));
+    commands
+        .spawn_bundle(SpriteBundle {
+            sprite: Sprite { color, ..default() },
+            transform,
+            // Set up front, the sprites only live for the frame they're
+            // spawned in.
+            global_transform: transform.into(),
+            ..default()
+        })
+        .insert(HitboxSprite);
+}
+
+fn spawn_outline(commands: &mut Commands, corners: [Vec2; 4], color: Color) {
+    for (i, from) in corners.iter().enumerate() {
+        spawn_line(commands, *from, corners[(i + 1) % 4], color);
+    }
+}
+
+fn spawn_box(commands: &mut Commands, center: Vec2, size: Vec2, color: Color) {
+    let half = size / 2.0;
+    let corners = [
+        center - half,
+        center + Vec2::new(half.x, -half.y),
+        center + half,
+        center + Vec2::new(-half.x, half.y),
+    ];
+    spawn_outline(commands, corners, color);
+}
+
+// Whether `progress` is close to where the skill strikes or lets go.
+fn striking(progress: f32, points: &[f32]) -> bool {
+    points
+        .iter()
+        .any(|point| (progress - point).abs() <= STRIKE_WINDOW)
+}
+
+fn toggle_system(keys: Res<Input<KeyCode>>, mut show: ResMut<ShowHitboxes>) {
+    if keys.just_pressed(KeyCode::F4) {
+        show.0 = !show.0;
+    }
+}
+
+// Redrawn from scratch every frame from the same geometry the hit tests use,
+// after the battle has moved everything for the frame.
+fn draw_hitboxes_system(
+    mut commands: Commands,
+    show: Res<ShowHitboxes>,
+    game: Res<Game>,
+    geometry: BodyGeometry,
+    bodies: Query<(&Stats, &Facing, &Transform, Option<&Animation>)>,
+    projectiles: Query<(&Projectile, &Transform)>,
+    grounds: Query<(&Transform, &Sprite), With<GroundSprite>>,
+    drawn: Query<Entity, With<HitboxSprite>>,
+) {
+    for entity in &drawn {
+        commands.entity(entity).despawn();
+    }
+    if !show.0 {
+        return;
+    }
+    for (transform, sprite) in &grounds {
+        let corners = part_corners(&Transform::identity(), transform, &sprite.anchor);
+        spawn_outline(&mut commands, corners, ARENA_COLOR);
+    }
+    for entity in [game.player, game.enemy] {
+        let Ok((stats, facing, transform, animation)) = bodies.get(entity) else {
+            continue;
+        };
+        for (part, corners) in geometry.part_outlines(entity) {
+            let color = match part {
+                HitPart::Limb(_) => LIMB_COLOR,
+                HitPart::Torso | HitPart::Head => BODY_COLOR,
+            };
+            spawn_outline(&mut commands, corners, color);
+        }
+        // `melee_in_reach` measures from the body's front edge, as far as the
+        // longest-reaching melee skill gets.
+        if let Some(reach) = stats.reach() {
+            let edge = transform.translation.x + facing.sign() * stats.width / 2.0;
+            spawn_box(
+                &mut commands,
+                Vec2::new(edge + facing.sign() * reach / 2.0, REACH_HEIGHT / 2.0),
+                Vec2::new(reach, REACH_HEIGHT),
+                REACH_COLOR,
+            );
+        }
+        let Some((animation, skill)) =
+            animation.and_then(|animation| Some((animation, stats.skill(&animation.skill)?)))
+        else {
+            continue;
+        };
+        let strike = match (skill.hits(), skill.release()) {
+            (Some((ability, hits)), _) => Some((ability.limb, hits.to_vec())),
+            (None, Some((ability, release))) => Some((ability.limb, vec![release])),
+            (None, None) => None,
+        };
+        let limb = strike
+            .filter(|(_, points)| animation.lead_in <= 0.0 && striking(animation.progress, points))
+            .map(|(limb, _)| limb);
+        if let Some(limb) = limb {
+            if let Some(tip) = geometry.limb_world_endpoint(entity, limb) {
+                spawn_box(&mut commands, tip, Vec2::splat(MARKER_SIZE), STRIKE_COLOR);
+            }
+        }
+    }
+    // Shots are points, checked all along the way they came this frame.
+    for (projectile, transform) in &projectiles {
+        let position = transform.translation.truncate();
+        spawn_line(&mut commands, projectile.previous(), position, SHOT_COLOR);
+        spawn_box(
+            &mut commands,
+            position,
+            Vec2::splat(MARKER_SIZE),
+            SHOT_COLOR,
+        );
+    }
+}
+
+pub struct HitboxViewPlugin;
+
+impl Plugin for HitboxViewPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ShowHitboxes>();
+        if ENABLED {
+            app.add_system(toggle_system).add_system_to_stage(
+                CoreStage::PostUpdate,
+                draw_hitboxes_system.before(TransformSystem::TransformPropagate),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strikes_show_around_their_hit_points() {
+        let slam = [0.25, 0.5, 0.75];
+        assert!(striking(0.24, &slam));
+        assert!(striking(0.78, &slam));
+        assert!(!striking(0.1, &slam));
+        assert!(!striking(0.4, &slam));
+        assert!(!striking(0.9, &slam));
+    }
+}
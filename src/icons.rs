@@ -0,0 +1,128 @@
+use std::collections::HashMap;
+
+use bevy::{
+    prelude::*,
+    render::render_resource::{Extent3d, TextureDimension, TextureFormat},
+};
+
+use crate::body::{Material, PaletteMode, Skill};
+
+pub const MOVEMENT_TEXTURES: &[&str] = &[
+    "textures/arrow_left.png",
+    "textures/arrow_right.png",
+    "textures/round_arrow.png",
+];
+
+const SIZE: u32 = 64;
+const OUTLINE: f32 = 2.5;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+enum Glyph {
+    Melee,
+    Slam,
+    Ranged,
+    Scan,
+    Reload,
+    Swap,
+}
+
+impl Glyph {
+    fn of(skill: &Skill) -> Option<Self> {
+        match skill {
+            Skill::BasicMelee(_) => Some(Glyph::Melee),
+            Skill::Slam(_) => Some(Glyph::Slam),
+            Skill::BasicRanged(_) => Some(Glyph::Ranged),
+            Skill::Scan(_) => Some(Glyph::Scan),
+            Skill::Reload => Some(Glyph::Reload),
+            Skill::SwapLimb => Some(Glyph::Swap),
+            Skill::WalkBackward | Skill::WalkForward | Skill::Dash(_) | Skill::TurnAround => None,
+        }
+    }
+
+    // Signed distance to the glyph's edge, negative inside. Coordinates are
+    // centered on the icon and run from -1 to 1.
+    fn distance(self, x: f32, y: f32) -> f32 {
+        let circle = |cx: f32, cy: f32, r: f32| ((x - cx).powi(2) + (y - cy).powi(2)).sqrt() - r;
+        let rect = |cx: f32, cy: f32, hw: f32, hh: f32| {
+            let dx = (x - cx).abs() - hw;
+            let dy = (y - cy).abs() - hh;
+            Vec2::new(dx.max(0.0), dy.max(0.0)).length() + dx.max(dy).min(0.0)
+        };
+        match self {
+            // A fist on a wrist.
+            Glyph::Melee => circle(0.0, 0.15, 0.45).min(rect(0.0, -0.55, 0.2, 0.25)),
+            // A hammer head on a handle.
+            Glyph::Slam => rect(0.0, 0.35, 0.6, 0.25).min(rect(0.0, -0.3, 0.1, 0.45)),
+            // A bullet with a trail.
+            Glyph::Ranged => circle(0.45, 0.0, 0.25).min(rect(-0.3, 0.0, 0.45, 0.08)),
+            Glyph::Scan => circle(0.0, 0.0, 0.6).abs() - 0.12,
+            // A magazine with a round poking out the top.
+            Glyph::Reload => rect(0.0, -0.2, 0.3, 0.55).min(circle(0.0, 0.5, 0.18)),
+            // Two arms, the spare held up beside the one it replaces.
+            Glyph::Swap => rect(-0.35, -0.15, 0.14, 0.5)
+                .min(rect(0.35, 0.15, 0.14, 0.5))
+                .min(rect(0.0, 0.0, 0.35, 0.07)),
+        }
+    }
+}
+
+fn render(glyph: Glyph, color: Color) -> Image {
+    let [r, g, b, _] = color.as_rgba_f32().map(|channel| (channel * 255.0) as u8);
+    let pixel_size = 2.0 / SIZE as f32;
+    let mut data = Vec::with_capacity((SIZE * SIZE * 4) as usize);
+    for py in 0..SIZE {
+        for px in 0..SIZE {
+            let x = (px as f32 + 0.5) * pixel_size - 1.0;
+            let y = 1.0 - (py as f32 + 0.5) * pixel_size;
+            let distance = glyph.distance(x, y) / pixel_size;
+            let pixel = if distance <= 0.0 {
+                [r, g, b, 255]
+            } else if distance <= OUTLINE {
+                [235, 235, 235, 255]
+            } else {
+                [0, 0, 0, 0]
+            };
+            data.extend(pixel);
+        }
+    }
+    Image::new(
+        Extent3d {
+            width: SIZE,
+            height: SIZE,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+    )
+}
+
+// Ability icons drawn on demand, one per glyph, material and palette.
+#[derive(Default)]
+pub struct SkillIcons {
+    generated: HashMap<(Glyph, Material, PaletteMode), Handle<Image>>,
+}
+
+impl SkillIcons {
+    pub fn get(
+        &mut self,
+        images: &mut Assets<Image>,
+        asset_server: &AssetServer,
+        skill: &Skill,
+        material: Material,
+        palette: PaletteMode,
+    ) -> Handle<Image> {
+        match Glyph::of(skill) {
+            Some(glyph) => self
+                .generated
+                .entry((glyph, material, palette))
+                .or_insert_with(|| images.add(render(glyph, material.color(palette))))
+                .clone(),
+            None => asset_server.load(match skill {
+                Skill::WalkBackward => MOVEMENT_TEXTURES[0],
+                Skill::WalkForward | Skill::Dash(_) => MOVEMENT_TEXTURES[1],
+                _ => MOVEMENT_TEXTURES[2],
+            }),
+        }
+    }
+}
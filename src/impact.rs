@@ -0,0 +1,297 @@
+use std::collections::BTreeMap;
+
+use bevy::{
+    asset::{AssetLoader, BoxedFuture, LoadContext, LoadedAsset},
+    prelude::*,
+    reflect::TypeUuid,
+};
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+use serde::Deserialize;
+
+use crate::{
+    body::Material,
+    camera::{dynamic_camera, CameraRig},
+    layer::RenderLayer,
+    performance::{ParticlePool, PerformanceProfile, PooledParticle},
+    sound::SynthSound,
+    DamageEvent, GameRng,
+};
+
+pub const IMPACTS_PATH: &str = "materials.impacts.ron";
+
+// Shake a hit of FULL_SHAKE_DAMAGE or more adds at a profile's shake of 1,
+// smaller hits add their share of it.
+const SHAKE_PER_HIT: f32 = 0.5;
+const FULL_SHAKE_DAMAGE: f32 = 10.0;
+const PARTICLE_GRAVITY: f32 = 9.0;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+pub enum ParticleShape {
+    // Small bright specks that fly fast and die quickly.
+    #[default]
+    Spark,
+    // Long thin bits that tumble as they fall.
+    Splinter,
+    // Flat chips that spin and glint.
+    Shard,
+    // Slow clouds that grow as they fade.
+    Dust,
+}
+
+impl ParticleShape {
+    // Size, speed and seconds a particle lasts.
+    fn motion(self) -> (Vec2, f32, f32) {
+        match self {
+            ParticleShape::Spark => (Vec2::splat(0.04), 4.0, 0.25),
+            ParticleShape::Splinter => (Vec2::new(0.14, 0.03), 2.5, 0.5),
+            ParticleShape::Shard => (Vec2::new(0.07, 0.05), 3.0, 0.45),
+            ParticleShape::Dust => (Vec2::splat(0.09), 0.8, 0.6),
+        }
+    }
+}
+
+// How a blow on one material sounds and looks.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct MaterialImpactProfile {
+    // Paths of the `.sound.ron` variants, one is picked per hit.
+    pub sounds: Vec<String>,
+    pub volume: f32,
+    pub color: [f32; 3],
+    pub shape: ParticleShape,
+    pub particles: u32,
+    // Times the usual camera shake for a hit of the same damage.
+    pub shake: f32,
+}
+
+impl Default for MaterialImpactProfile {
+    fn default() -> Self {
+        Self {
+            sounds: Vec::new(),
+            volume: 0.6,
+            color: [1.0, 0.85, 0.5],
+            shape: ParticleShape::Spark,
+            particles: 6,
+            shake: 1.0,
+        }
+    }
+}
+
+// Loaded from assets/materials.impacts.ron. A material missing from it gets
+// `fallback`, so a new one sounds like something before it has an entry.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, TypeUuid)]
+#[uuid = "5e7d1c92-8a40-4b6f-b3d1-0f9c2e6a7d58"]
+#[serde(default)]
+pub struct ImpactTable {
+    pub fallback: MaterialImpactProfile,
+    pub materials: BTreeMap<Material, MaterialImpactProfile>,
+}
+
+impl ImpactTable {
+    pub fn profile(&self, material: Material) -> &MaterialImpactProfile {
+        self.materials.get(&material).unwrap_or(&self.fallback)
+    }
+}
+
+#[derive(Default)]
+struct ImpactLoader;
+
+impl AssetLoader for ImpactLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let table = ron::de::from_bytes::<ImpactTable>(bytes)?;
+            load_context.set_default_asset(LoadedAsset::new(table));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["impacts.ron"]
+    }
+}
+
+struct ImpactHandle(Handle<ImpactTable>);
+
+fn load_impacts_system(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(ImpactHandle(asset_server.load(IMPACTS_PATH)));
+}
+
+fn reload_impacts_system(
+    handle: Res<ImpactHandle>,
+    assets: Res<Assets<ImpactTable>>,
+    mut events: EventReader<AssetEvent<ImpactTable>>,
+    mut table: ResMut<ImpactTable>,
+) {
+    for event in events.iter() {
+        let (AssetEvent::Created { handle: changed } | AssetEvent::Modified { handle: changed }) =
+            event
+        else {
+            continue;
+        };
+        if let Some(loaded) = assets.get(changed).filter(|_| *changed == handle.0) {
+            *table = loaded.clone();
+        }
+    }
+}
+
+#[derive(Component)]
+struct ImpactParticle {
+    velocity: Vec2,
+    spin: f32,
+    remaining: f32,
+    lifetime: f32,
+    shape: ParticleShape,
+}
+
+// Variants are picked from a generator of their own, seeded from the game's,
+// so what plays is repeatable without drawing from the battle's rolls.
+fn impact_system(
+    mut commands: Commands,
+    table: Res<ImpactTable>,
+    performance: Res<PerformanceProfile>,
+    mut pool: ResMut<ParticlePool>,
+    game_rng: Res<GameRng>,
+    asset_server: Res<AssetServer>,
+    audio: Res<Audio<SynthSound>>,
+    mut rig: ResMut<CameraRig>,
+    mut damage: EventReader<DamageEvent>,
+    mut variants: Local<Option<StdRng>>,
+) {
+    let variants = variants.get_or_insert_with(|| StdRng::seed_from_u64(game_rng.seed()));
+    let mut sparks = rand::thread_rng();
+    for event in damage.iter() {
+        let Some(impact) = event.impact else {
+            continue;
+        };
+        let profile = table.profile(impact.material);
+        if let Some(path) = profile.sounds.choose(variants) {
+            audio.play_with_settings(
+                asset_server.load(path.as_str()),
+                PlaybackSettings::ONCE.with_volume(profile.volume),
+            );
+        }
+        rig.shake(profile.shake * SHAKE_PER_HIT * (event.amount / FULL_SHAKE_DAMAGE).min(1.0));
+        let [r, g, b] = profile.color;
+        let (size, speed, lifetime) = profile.shape.motion();
+        for _ in 0..pool.take(&performance, profile.particles as usize) {
+            let angle = sparks.gen_range(0.0..std::f32::consts::TAU);
+            let velocity = Vec2::from_angle(angle) * speed * sparks.gen_range(0.5..=1.0);
+            commands
+                .spawn_bundle(SpriteBundle {
+                    transform: Transform::from_translation(
+                        impact.point.extend(RenderLayer::Particle.z()),
+                    )
+                    .with_rotation(Quat::from_rotation_z(angle))
+                    .with_scale(size.extend(1.0)),
+                    sprite: Sprite {
+                        color: Color::rgb(r, g, b),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .insert_bundle((
+                    ImpactParticle {
+                        velocity,
+                        spin: sparks.gen_range(-12.0..=12.0),
+                        remaining: lifetime,
+                        lifetime,
+                        shape: profile.shape,
+                    },
+                    PooledParticle,
+                ));
+        }
+    }
+}
+
+fn particle_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut particles: Query<(Entity, &mut ImpactParticle, &mut Transform, &mut Sprite)>,
+) {
+    let dt = time.delta_seconds();
+    for (entity, mut particle, mut transform, mut sprite) in &mut particles {
+        particle.remaining -= dt;
+        if particle.remaining <= 0.0 {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        let t = particle.remaining / particle.lifetime;
+        match particle.shape {
+            ParticleShape::Dust => {
+                particle.velocity *= 1.0 - 3.0 * dt;
+                transform.scale *= 1.0 + 1.5 * dt;
+            }
+            ParticleShape::Splinter | ParticleShape::Shard => {
+                particle.velocity.y -= PARTICLE_GRAVITY * dt;
+                transform.rotate_z(particle.spin * dt);
+            }
+            ParticleShape::Spark => particle.velocity.y -= PARTICLE_GRAVITY * dt,
+        }
+        transform.translation += (particle.velocity * dt).extend(0.0);
+        sprite.color.set_a(t);
+    }
+}
+
+// The sounds, sparks and camera shake of a blow landing. Only the windowed
+// game has it, after SoundPlugin.
+pub struct ImpactPlugin;
+
+impl Plugin for ImpactPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ImpactTable>()
+            .add_asset::<ImpactTable>()
+            .init_asset_loader::<ImpactLoader>()
+            .add_startup_system(load_impacts_system)
+            .add_system(reload_impacts_system)
+            .add_system(impact_system.before(dynamic_camera))
+            .add_system(particle_system);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_material_has_sounds_that_exist() {
+        let assets = concat!(env!("CARGO_MANIFEST_DIR"), "/assets/");
+        let contents = std::fs::read_to_string(format!("{}{}", assets, IMPACTS_PATH)).unwrap();
+        let table = ron::from_str::<ImpactTable>(&contents).unwrap();
+        for material in [
+            Material::Wood,
+            Material::Stone,
+            Material::Plastic,
+            Material::Bronze,
+            Material::Aluminum,
+            Material::Steel,
+            Material::Carbon,
+            Material::Rust,
+        ] {
+            assert!(table.materials.contains_key(&material), "{:?}", material);
+            let profile = table.profile(material);
+            assert!(
+                (2..=3).contains(&profile.sounds.len()),
+                "{:?} needs 2 or 3 variants",
+                material
+            );
+            for sound in &profile.sounds {
+                assert!(
+                    std::path::Path::new(&format!("{}{}", assets, sound)).is_file(),
+                    "{} is missing",
+                    sound
+                );
+            }
+        }
+        // Anything left out of the file falls back.
+        let sparse = ron::from_str::<ImpactTable>("(materials: {Steel: (shake: 2.0)})").unwrap();
+        assert_eq!(sparse.profile(Material::Steel).shake, 2.0);
+        assert_eq!(
+            *sparse.profile(Material::Wood),
+            MaterialImpactProfile::default()
+        );
+    }
+}
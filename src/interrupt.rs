@@ -0,0 +1,186 @@
+use bevy::{ecs::entity::Entities, prelude::*};
+
+use crate::{
+    balance::BalanceConfig,
+    battle::{Animation, Walking},
+    body::{Limb, Skill, Stats},
+    effects::{ActiveEffect, Polarity},
+    locks::LimbLocks,
+    timer::SimClock,
+    turn::Ruleset,
+    ui::UseSkill,
+    AppState, DamageEvent, DamageSource, Game, Invulnerable,
+};
+
+// Lockout after an interrupt, the limbs blend back to rest while it runs.
+#[derive(Component)]
+pub struct Recovery(pub f32);
+
+// Damage taken towards breaking poise.
+#[derive(Component, Default)]
+pub struct Poise(pub f32);
+
+// Damage it takes to break a body's poise, more for heavier bodies.
+pub fn poise_threshold(balance: &BalanceConfig, stats: &Stats) -> f32 {
+    stats.weight * balance.poise_per_weight * (1.0 + stats.poise_bonus)
+}
+
+fn interrupt_system(
+    mut commands: Commands,
+    ruleset: Res<Ruleset>,
+    balance: Res<BalanceConfig>,
+    game: Res<Game>,
+    mut use_skill: ResMut<UseSkill>,
+    mut events: EventReader<DamageEvent>,
+    animations: Query<&Animation>,
+    stats: Query<&Stats>,
+    invulnerable: Query<(), With<Invulnerable>>,
+    mut poise: Query<&mut Poise>,
+) {
+    if !ruleset.interrupts {
+        events.clear();
+        return;
+    }
+    for event in events.iter() {
+        let DamageSource::Attack { attacker, skill } = &event.source else {
+            continue;
+        };
+        let interrupting = stats
+            .get(*attacker)
+            .ok()
+            .and_then(|stats| stats.skill(skill))
+            .and_then(|skill| skill.hits())
+            .is_some_and(|(ability, _)| ability.interrupting);
+        let (animation, target_stats) =
+            match (animations.get(event.target), stats.get(event.target)) {
+                (Ok(animation), Ok(stats)) => (animation, stats),
+                _ => continue,
+            };
+        // Only attacks and reloads can be interrupted, movement always
+        // finishes.
+        let attacking = target_stats
+            .skill(&animation.skill)
+            .is_some_and(|skill| skill.hits().is_some() || matches!(skill, Skill::Reload));
+        if !interrupting || !attacking || invulnerable.contains(event.target) {
+            continue;
+        }
+        if ruleset.poise {
+            let threshold = poise_threshold(&balance, target_stats);
+            match poise.get_mut(event.target) {
+                Ok(mut poise) => {
+                    poise.0 += event.amount;
+                    if poise.0 < threshold {
+                        continue;
+                    }
+                    poise.0 = 0.0;
+                }
+                Err(_) => {
+                    commands.entity(event.target).insert(Poise(event.amount));
+                    if event.amount < threshold {
+                        continue;
+                    }
+                }
+            }
+        }
+        // A walk under the broken action carries on, it's movement.
+        commands
+            .entity(event.target)
+            .remove::<Animation>()
+            .insert(Recovery(balance.recovery_time));
+        if event.target == game.player {
+            **use_skill = None;
+        }
+    }
+}
+
+// Limbs a walk still holds are left to it.
+fn recovery_system(
+    mut commands: Commands,
+    clock: Res<SimClock>,
+    mut bodies: Query<(
+        Entity,
+        &mut Recovery,
+        &Children,
+        Option<&Walking>,
+        Option<&LimbLocks>,
+    )>,
+    mut limbs: Query<(&Limb, &mut Transform)>,
+) {
+    let dt = clock.delta();
+    for (entity, mut recovery, children, walking, locks) in &mut bodies {
+        let t = (dt / recovery.0).min(1.0);
+        for child in children {
+            let Ok((limb, mut transform)) = limbs.get_mut(*child) else {
+                continue;
+            };
+            let walked = walking
+                .zip(locks)
+                .is_some_and(|(walking, locks)| locks.holder(*limb) == Some(&walking.0.skill));
+            if !walked {
+                transform.rotation = transform.rotation.lerp(Quat::IDENTITY, t);
+            }
+        }
+        recovery.0 -= dt;
+        if recovery.0 <= 0.0 {
+            commands.entity(entity).remove::<Recovery>();
+        }
+    }
+}
+
+fn recovery_effect_system(
+    bodies: Query<(Entity, &Recovery)>,
+    mut effects: EventWriter<ActiveEffect>,
+) {
+    for (target, recovery) in &bodies {
+        // Already over, the component goes away with this frame's commands.
+        if recovery.0 <= 0.0 {
+            continue;
+        }
+        effects.send(ActiveEffect {
+            target,
+            id: "recovery",
+            polarity: Polarity::Debuff,
+            remaining: Some(recovery.0),
+            stacks: 1,
+        });
+    }
+}
+
+fn poise_regen_system(
+    clock: Res<SimClock>,
+    balance: Res<BalanceConfig>,
+    mut poise: Query<&mut Poise>,
+) {
+    let regen = balance.poise_regen * clock.delta();
+    for mut poise in &mut poise {
+        poise.0 = (poise.0 - regen).max(0.0);
+    }
+}
+
+fn reset_interrupts_system(mut commands: Commands, game: Res<Game>, entities: &Entities) {
+    for entity in [game.player, game.enemy] {
+        if entities.contains(entity) {
+            commands
+                .entity(entity)
+                .remove::<Recovery>()
+                .remove::<Poise>();
+        }
+    }
+}
+
+pub struct InterruptPlugin;
+
+impl Plugin for InterruptPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(
+            SystemSet::on_enter(AppState::Battle).with_system(reset_interrupts_system),
+        )
+        .add_system_set(
+            SystemSet::on_update(AppState::Battle)
+                .with_system(interrupt_system.after(crate::battle::animation_system))
+                .with_system(recovery_system)
+                .with_system(recovery_effect_system.after(recovery_system))
+                .with_system(poise_regen_system),
+        );
+    }
+}
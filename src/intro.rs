@@ -0,0 +1,304 @@
+use bevy::{input::mouse::MouseButton, prelude::*, ui::FocusPolicy, utils::Instant};
+
+use crate::{
+    body::{Body, Stats},
+    camera::{aspect, dynamic_camera, framing},
+    knowledge::{self, Knowledge},
+    locale::t,
+    ui::{Fonts, Scaled, UiScale},
+    weather::Weather,
+    AppState, Game,
+};
+
+// The intro is a state pushed on top of the battle, so nothing in the battle
+// runs until it pops and pausing works the same as during the fight.
+const INTRO_TIME: f32 = 3.0;
+// Holds on the enemy until this far in, then pans over to the player.
+const ENEMY_SHOT: f32 = 1.0;
+const PAN_END: f32 = 2.0;
+const BANNER_AT: f32 = 2.2;
+const SLAM_TIME: f32 = 0.2;
+// The click that started the battle shouldn't also skip its intro.
+const SKIP_GRACE: f32 = 0.2;
+const CLOSE_UP: f32 = 4.0;
+const BANNER_FONT: f32 = 96.0;
+
+// Runs on wall time rather than `Time`, so it plays out the same however the
+// battle clock is being driven.
+#[derive(Default)]
+struct Intro {
+    elapsed: f32,
+    // None on the first frame and the first frame after a pause.
+    last_frame: Option<Instant>,
+}
+
+#[derive(Component)]
+struct IntroRoot;
+
+#[derive(Component)]
+struct StatCard;
+
+#[derive(Component)]
+struct FightBanner;
+
+fn ease(from: f32, to: f32, t: f32) -> f32 {
+    let x = ((t - from) / (to - from)).clamp(0.0, 1.0);
+    x * x * (3.0 - 2.0 * x)
+}
+
+// Where the camera looks and how far out it's zoomed `t` seconds in. Ends up
+// on `framing`, what the battle camera shows once the intro is over.
+fn camera_at(t: f32, enemy: Vec2, player: Vec2, framing: (Vec2, f32)) -> (Vec2, f32) {
+    if t < PAN_END {
+        (enemy.lerp(player, ease(ENEMY_SHOT, PAN_END, t)), CLOSE_UP)
+    } else {
+        let blend = ease(PAN_END, INTRO_TIME, t);
+        (
+            player.lerp(framing.0, blend),
+            CLOSE_UP + (framing.1 - CLOSE_UP) * blend,
+        )
+    }
+}
+
+fn start_intro_system(mut intro: ResMut<Intro>, mut state: ResMut<State<AppState>>) {
+    *intro = Intro::default();
+    let _ = state.push(AppState::Intro);
+}
+
+fn resume_intro_system(mut intro: ResMut<Intro>) {
+    intro.last_frame = None;
+}
+
+fn spawn_intro_system(mut commands: Commands, fonts: Res<Fonts>, weather: Res<Weather>) {
+    // Announced under everything else, clear skies go without saying.
+    let forecast = match *weather {
+        Weather::Clear => String::new(),
+        weather => t!(
+            "intro.weather",
+            weather = weather.name(),
+            effect = weather.description()
+        ),
+    };
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                position_type: PositionType::Absolute,
+                flex_direction: FlexDirection::ColumnReverse,
+                justify_content: JustifyContent::SpaceBetween,
+                align_items: AlignItems::Center,
+                padding: UiRect::all(Val::Px(40.0)),
+                ..default()
+            },
+            color: Color::NONE.into(),
+            focus_policy: FocusPolicy::Pass,
+            ..default()
+        })
+        .insert(IntroRoot)
+        .with_children(|parent| {
+            parent
+                .spawn_bundle(TextBundle::from_sections([
+                    TextSection::new(
+                        forecast,
+                        TextStyle {
+                            font: fonts.bold(),
+                            font_size: 18.0,
+                            color: Color::WHITE,
+                        },
+                    ),
+                    TextSection::new(
+                        t!("intro.skip"),
+                        TextStyle {
+                            font: fonts.normal(),
+                            font_size: 18.0,
+                            color: Color::GRAY,
+                        },
+                    ),
+                ]))
+                .insert(Scaled::font(18.0));
+            parent
+                .spawn_bundle(TextBundle {
+                    text: Text::from_section(
+                        t!("intro.fight"),
+                        TextStyle {
+                            font: fonts.bold(),
+                            font_size: BANNER_FONT,
+                            color: Color::ORANGE,
+                        },
+                    ),
+                    visibility: Visibility { is_visible: false },
+                    ..default()
+                })
+                .insert(FightBanner);
+            parent
+                .spawn_bundle(TextBundle::from_sections([
+                    TextSection::new(
+                        "",
+                        TextStyle {
+                            font: fonts.bold(),
+                            font_size: 32.0,
+                            color: Color::WHITE,
+                        },
+                    ),
+                    TextSection::new(
+                        "",
+                        TextStyle {
+                            font: fonts.normal(),
+                            font_size: 22.0,
+                            color: Color::WHITE,
+                        },
+                    ),
+                ]))
+                .insert(StatCard);
+        });
+}
+
+fn intro_system(
+    game: Res<Game>,
+    scale: Res<UiScale>,
+    keys: Res<Input<KeyCode>>,
+    mouse: Res<Input<MouseButton>>,
+    mut intro: ResMut<Intro>,
+    mut state: ResMut<State<AppState>>,
+    bodies: Query<(&Body, &Stats, Option<&Knowledge>)>,
+    mut cards: Query<&mut Text, (With<StatCard>, Without<FightBanner>)>,
+    mut banners: Query<(&mut Text, &mut Visibility), With<FightBanner>>,
+) {
+    let now = Instant::now();
+    if let Some(last_frame) = intro.last_frame {
+        intro.elapsed += now.saturating_duration_since(last_frame).as_secs_f32();
+    }
+    intro.last_frame = Some(now);
+    // P still pauses, see pause_system.
+    let skipped = intro.elapsed > SKIP_GRACE
+        && (keys.get_just_pressed().any(|key| *key != KeyCode::P)
+            || mouse.get_just_pressed().next().is_some());
+    if skipped || intro.elapsed >= INTRO_TIME {
+        let _ = state.pop();
+        return;
+    }
+
+    // The card follows the camera, it switches halfway through the pan.
+    let shown = if intro.elapsed < (ENEMY_SHOT + PAN_END) / 2.0 {
+        game.enemy
+    } else {
+        game.player
+    };
+    let learned = bodies
+        .get(game.player)
+        .ok()
+        .and_then(|(_, _, knowledge)| knowledge);
+    if let Ok((body, stats, _)) = bodies.get(shown) {
+        // The card says as much as the enemy's skill bar would.
+        let known = knowledge::known(&game, learned, shown);
+        for mut text in &mut cards {
+            text.sections[0].value = format!("{}\n", body.name());
+            text.sections[0].style.font_size = 32.0 * scale.factor();
+            text.sections[1].value = if known < knowledge::SKILLS {
+                t!("intro.card_unknown")
+            } else {
+                t!(
+                    "intro.card",
+                    weight = stats.weight,
+                    class = stats.class().name(),
+                    speed = stats.speed,
+                    skills = stats.skills.len()
+                )
+            };
+            text.sections[1].style.font_size = 22.0 * scale.factor();
+        }
+    }
+    // Comes in huge and slams down to size.
+    let slam = 1.0 + 2.0 * (1.0 - ease(BANNER_AT, BANNER_AT + SLAM_TIME, intro.elapsed));
+    for (mut text, mut visibility) in &mut banners {
+        visibility.is_visible = intro.elapsed >= BANNER_AT;
+        text.sections[0].style.font_size = BANNER_FONT * slam * scale.factor();
+    }
+}
+
+fn intro_camera_system(
+    intro: Res<Intro>,
+    game: Res<Game>,
+    windows: Res<Windows>,
+    mut cameras: Query<&mut Transform, With<Camera>>,
+    bodies: Query<(&Body, &Transform), Without<Camera>>,
+) {
+    let Ok(mut camera) = cameras.get_single_mut() else {
+        return;
+    };
+    let Ok([(player_body, player), (enemy_body, enemy)]) =
+        bodies.get_many([game.player, game.enemy])
+    else {
+        return;
+    };
+    let centre = |body: &Body, transform: &Transform| {
+        transform.translation.truncate() + Vec2::Y * body.height() / 2.0
+    };
+    let (look_at, scale) = camera_at(
+        intro.elapsed,
+        centre(enemy_body, enemy),
+        centre(player_body, player),
+        framing(
+            &[
+                (player_body, player.translation),
+                (enemy_body, enemy.translation),
+            ],
+            aspect(&windows),
+        ),
+    );
+    camera.translation.x = look_at.x;
+    camera.translation.y = look_at.y;
+    camera.scale = Vec3::splat(scale);
+}
+
+fn despawn_intro_system(mut commands: Commands, roots: Query<Entity, With<IntroRoot>>) {
+    for root in &roots {
+        commands.entity(root).despawn_recursive();
+    }
+}
+
+pub struct IntroPlugin;
+
+impl Plugin for IntroPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Intro>()
+            .add_system_set(SystemSet::on_enter(AppState::Battle).with_system(start_intro_system))
+            .add_system_set(SystemSet::on_enter(AppState::Intro).with_system(spawn_intro_system))
+            .add_system_set(SystemSet::on_resume(AppState::Intro).with_system(resume_intro_system))
+            .add_system_set(
+                SystemSet::on_update(AppState::Intro)
+                    .with_system(intro_system)
+                    .with_system(intro_camera_system.after(dynamic_camera)),
+            )
+            .add_system_set(SystemSet::on_exit(AppState::Intro).with_system(despawn_intro_system));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn camera_pans_from_the_enemy_to_the_battle_view() {
+        let enemy = Vec2::new(4.0, 1.0);
+        let player = Vec2::new(-4.0, 1.0);
+        let battle = (Vec2::ZERO, 9.3);
+        assert_eq!(camera_at(0.0, enemy, player, battle), (enemy, CLOSE_UP));
+        assert_eq!(
+            camera_at(ENEMY_SHOT, enemy, player, battle),
+            (enemy, CLOSE_UP)
+        );
+        assert_eq!(
+            camera_at(PAN_END, enemy, player, battle),
+            (player, CLOSE_UP)
+        );
+        assert_eq!(camera_at(INTRO_TIME, enemy, player, battle), battle);
+        // Never jumps, every frame is close to the one before.
+        let mut last = camera_at(0.0, enemy, player, battle);
+        for frame in 1..=180 {
+            let next = camera_at(frame as f32 / 60.0, enemy, player, battle);
+            assert!(next.0.distance(last.0) < 0.3 && (next.1 - last.1).abs() < 0.3);
+            last = next;
+        }
+    }
+}
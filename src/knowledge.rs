@@ -0,0 +1,116 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+
+use crate::{
+    battle::{animation_system, Animation},
+    body::{Skill, Stats},
+    AppState, Game,
+};
+
+// How much a body knows about another. Nothing but the name and a rough
+// health bar without a Scan, each tier shows more.
+pub const NUMBERS: u8 = 1;
+pub const SKILLS: u8 = 2;
+pub const PARTS: u8 = 3;
+
+// What this body has found out about others by scanning them, keyed by the
+// scanned body. Lasts the battle.
+#[derive(Component, Default, Debug)]
+pub struct Knowledge(HashMap<Entity, u8>);
+
+impl Knowledge {
+    pub fn tier(&self, target: Entity) -> u8 {
+        self.0.get(&target).copied().unwrap_or(0)
+    }
+
+    // A weaker scan never takes back what a stronger one showed.
+    pub fn learn(&mut self, target: Entity, tier: u8) {
+        let known = self.0.entry(target).or_default();
+        *known = (*known).max(tier.min(PARTS));
+    }
+
+    pub fn forget(&mut self, target: Entity) {
+        self.0.remove(&target);
+    }
+}
+
+// Knowledge tiers the player bought on the enemy before the fight, see
+// enemy_preview.rs. Handed to the player's Knowledge as the battle starts.
+#[derive(Default)]
+pub struct Scouted(pub u8);
+
+// What the player gets to see about `target`. Their own body is no secret.
+pub fn known(game: &Game, knowledge: Option<&Knowledge>, target: Entity) -> u8 {
+    if target == game.player {
+        PARTS
+    } else {
+        knowledge.map_or(0, |knowledge| knowledge.tier(target))
+    }
+}
+
+// A scan's ability meta is its detail, the tier it reveals.
+pub fn detail(skill: &Skill) -> Option<u8> {
+    match skill {
+        Skill::Scan(ability) => Some(ability.meta.round().clamp(1.0, PARTS as f32) as u8),
+        _ => None,
+    }
+}
+
+fn reset_knowledge_system(
+    game: Res<Game>,
+    scouted: Res<Scouted>,
+    mut bodies: Query<&mut Knowledge>,
+) {
+    for mut knowledge in &mut bodies {
+        knowledge.0.clear();
+    }
+    if let Ok(mut knowledge) = bodies.get_mut(game.player) {
+        knowledge.learn(game.enemy, scouted.0);
+    }
+}
+
+// Only a scan that plays to the end reveals anything.
+fn scan_system(game: Res<Game>, mut bodies: Query<(Entity, &Animation, &Stats, &mut Knowledge)>) {
+    for (entity, animation, stats, mut knowledge) in &mut bodies {
+        if animation.progress <= 1.0 {
+            continue;
+        }
+        if let Some(tier) = stats.skill(&animation.skill).and_then(detail) {
+            knowledge.learn(game.opponent(entity), tier);
+        }
+    }
+}
+
+pub struct KnowledgePlugin;
+
+impl Plugin for KnowledgePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Scouted>()
+            .add_system_set(
+                SystemSet::on_enter(AppState::Battle).with_system(reset_knowledge_system),
+            )
+            .add_system_set(
+                SystemSet::on_update(AppState::Battle)
+                    .with_system(scan_system.after(animation_system)),
+            );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scans_only_ever_reveal_more() {
+        let enemy = Entity::from_raw(1);
+        let mut knowledge = Knowledge::default();
+        assert_eq!(knowledge.tier(enemy), 0);
+        knowledge.learn(enemy, SKILLS);
+        knowledge.learn(enemy, NUMBERS);
+        assert_eq!(knowledge.tier(enemy), SKILLS);
+        knowledge.learn(enemy, 9);
+        assert_eq!(knowledge.tier(enemy), PARTS);
+        assert_eq!(knowledge.tier(Entity::from_raw(2)), 0);
+    }
+}
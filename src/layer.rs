@@ -0,0 +1,98 @@
+use bevy::prelude::*;
+
+use crate::body::{HitPart, Limb};
+
+// Depth between two layers, everything drawn inside one stays within it.
+const STEP: f32 = 1.0;
+// Depth between a body's parts, room is left for the palette badge on top
+// of each.
+const PART_STEP: f32 = 0.1;
+
+// Above every layer, the camera only sees what's below it.
+pub const CAMERA_Z: f32 = 100.0;
+
+// What is drawn over what in the arena, back to front. Sprites take their z
+// from here instead of picking a number, so two kinds of thing never share a
+// depth and flicker, and the order doesn't hang on the order they spawned in.
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum RenderLayer {
+    Ground,
+    Shadow,
+    // Lying on the ground, bodies walk over them.
+    Pickup,
+    FarBody,
+    NearBody,
+    Projectile,
+    // Dust, sparkles and weather.
+    Particle,
+    // Markers floating over the fight, the walk ghost and the ruler.
+    Floater,
+    // Debug views, over everything else.
+    Overlay,
+}
+
+impl RenderLayer {
+    pub fn z(self) -> f32 {
+        self as u8 as f32 * STEP
+    }
+
+    // For a child drawn at this layer under a parent drawn at `parent`.
+    pub fn relative_to(self, parent: RenderLayer) -> f32 {
+        self.z() - parent.z()
+    }
+
+    // Where a body's part sits within the body's layer. Limbs are drawn over
+    // the torso, the hitbox checks them in the same order.
+    pub fn part(part: HitPart) -> f32 {
+        let order = match part {
+            HitPart::Limb(Limb::Leg(_)) => 1.0,
+            HitPart::Torso => 2.0,
+            HitPart::Head => 3.0,
+            HitPart::Limb(Limb::Arm(_)) => 4.0,
+        };
+        order * PART_STEP
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::body::{part_sprites, Body};
+
+    // Parts in the world for a body drawn at `layer` facing either way.
+    fn part_depths(layer: RenderLayer, facing: f32) -> Vec<(HitPart, f32)> {
+        let body = Transform::from_xyz(1.0, 0.5, layer.z()).with_scale(Vec3::new(facing, 1.0, 1.0));
+        part_sprites(&Body::default())
+            .into_iter()
+            .map(|sprite| (sprite.part, body.mul_vec3(sprite.translation).z))
+            .collect()
+    }
+
+    #[test]
+    fn layers_keep_their_order_whichever_way_bodies_face() {
+        let shadow =
+            RenderLayer::Shadow.relative_to(RenderLayer::FarBody) + RenderLayer::FarBody.z();
+        assert_eq!(shadow, RenderLayer::Shadow.z());
+        for facing in [1.0, -1.0] {
+            let far = part_depths(RenderLayer::FarBody, facing);
+            let near = part_depths(RenderLayer::NearBody, -facing);
+            let far_top = far.iter().map(|(_, z)| *z).fold(f32::MIN, f32::max);
+            let near_bottom = near.iter().map(|(_, z)| *z).fold(f32::MAX, f32::min);
+            assert!(RenderLayer::Pickup.z() < far.iter().map(|(_, z)| *z).fold(f32::MAX, f32::min));
+            assert!(far_top < near_bottom);
+            assert!(near.iter().all(|(_, z)| *z < RenderLayer::Projectile.z()));
+            // The torso is under every limb whichever way the body faces.
+            let torso = far
+                .iter()
+                .find(|(part, _)| *part == HitPart::Torso)
+                .unwrap()
+                .1;
+            for (part, z) in &far {
+                if let HitPart::Limb(Limb::Arm(_)) = part {
+                    assert!(*z > torso);
+                }
+            }
+        }
+        assert!(RenderLayer::Overlay.z() < CAMERA_Z);
+    }
+}
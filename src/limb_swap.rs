@@ -0,0 +1,378 @@
+use bevy::prelude::*;
+
+use crate::{
+    balance::BalanceConfig,
+    battle::{animation_system, Animation, ExternalInput, Walking},
+    body::{Arm, Body, BodyStats, Limb, Material, Skill, Stats},
+    interrupt::Recovery,
+    locale::t,
+    profile::Profile,
+    theme::Theme,
+    ui::{Fonts, Toast, UseSkill},
+    AppState, DamageEvent, Game,
+};
+
+pub const SWAP_LIMB_TIME: f32 = 3.0;
+
+// The spare arm a Modular Chassis carries into battle, picked in the editor
+// and kept in the profile until it's swapped in.
+#[derive(Component, Default)]
+pub struct Sidearm {
+    pub spare: Option<Arm>,
+    // The slot picked during the channel, `default_target` until then.
+    pub target: Option<u8>,
+    // Health, energy and overshield from just before the swap, put back once
+    // the rebuilt stats have refilled them.
+    pools: Option<[f32; 3]>,
+}
+
+// Whether `skill` can start as far as the spare goes, only SwapLimb needs one.
+pub fn stocked(sidearm: Option<&Sidearm>, skill: &Skill) -> bool {
+    !matches!(skill, Skill::SwapLimb) || sidearm.is_some_and(|sidearm| sidearm.spare.is_some())
+}
+
+pub fn swapping(animation: Option<&Animation>) -> bool {
+    animation.is_some_and(|animation| animation.skill == Skill::SwapLimb.id())
+}
+
+// Where the spare goes unless the player picks a slot: an empty one, then a
+// rusted arm, then the first.
+pub fn default_target(body: &Body) -> Option<u8> {
+    let slots = body.torso().meta().arm_slots() as u8;
+    let arm = |slot| body.arms().iter().find(|arm| arm.slot() == slot);
+    (0..slots)
+        .find(|slot| arm(*slot).is_none())
+        .or_else(|| {
+            (0..slots)
+                .find(|slot| arm(*slot).is_some_and(|arm| arm.stats().material() == Material::Rust))
+        })
+        .or_else(|| (slots > 0).then_some(0))
+}
+
+// Only the player picks a spare, it goes into battle if the torso can use it.
+// A networked match has no swaps, see net.rs.
+fn stock_system(
+    mut commands: Commands,
+    game: Res<Game>,
+    profile: Res<Profile>,
+    bodies: Query<(&Body, Option<&ExternalInput>)>,
+) {
+    let Ok((body, external)) = bodies.get(game.player) else {
+        return;
+    };
+    let spare = profile
+        .spare_arm
+        .clone()
+        .filter(|_| body.torso().meta().modular() && external.is_none());
+    commands
+        .entity(game.player)
+        .insert(Sidearm { spare, ..default() });
+}
+
+// The channel leaves the body open, any blow breaks it and the spare stays
+// where it was.
+fn break_channel_system(
+    mut commands: Commands,
+    balance: Res<BalanceConfig>,
+    game: Res<Game>,
+    mut use_skill: ResMut<UseSkill>,
+    mut events: EventReader<DamageEvent>,
+    animations: Query<&Animation>,
+) {
+    for event in events.iter() {
+        if event.amount <= 0.0 || !swapping(animations.get(event.target).ok()) {
+            continue;
+        }
+        commands
+            .entity(event.target)
+            .remove::<Animation>()
+            .remove::<Walking>()
+            .insert(Recovery(balance.recovery_time));
+        if event.target == game.player {
+            **use_skill = None;
+        }
+    }
+}
+
+// Only a channel that plays to the end swaps. The body is rebuilt like any
+// changed body, skills on the other limbs keep their cooldowns and shots.
+fn complete_system(
+    mut profile: ResMut<Profile>,
+    mut toasts: EventWriter<Toast>,
+    mut bodies: Query<(&Animation, &mut Body, &Stats, &mut Sidearm)>,
+) {
+    for (animation, mut body, stats, mut sidearm) in &mut bodies {
+        if !swapping(Some(animation)) || animation.progress <= 1.0 {
+            continue;
+        }
+        let (Some(spare), Some(slot)) = (
+            sidearm.spare.clone(),
+            sidearm.target.or_else(|| default_target(&body)),
+        ) else {
+            continue;
+        };
+        let name = spare.name().to_string();
+        match body.replace_arm(slot, spare) {
+            Ok(_) => {
+                sidearm.spare = None;
+                sidearm.target = None;
+                sidearm.pools = Some([stats.health, stats.energy, stats.overshield]);
+                profile.spare_arm = None;
+                toasts.send(Toast(t!(
+                    "limb_swap.swapped",
+                    part = name,
+                    arm = Limb::Arm(slot).badge()
+                )));
+            }
+            Err(err) => warn!("Couldn't swap in {}: {}", name, err),
+        }
+    }
+}
+
+fn restore_pools_system(mut bodies: Query<(&mut Stats, &mut Sidearm), Changed<Body>>) {
+    for (mut stats, mut sidearm) in &mut bodies {
+        if let Some([health, energy, overshield]) = sidearm.pools.take() {
+            stats.health = health.min(stats.max_health);
+            stats.energy = energy.min(stats.max_energy);
+            stats.overshield = overshield.min(stats.max_overshield);
+        }
+    }
+}
+
+#[derive(Component)]
+struct PickerRoot;
+
+#[derive(Component)]
+struct SlotButton(u8);
+
+// Up while the player channels, one button per arm slot on the torso.
+fn picker_system(
+    mut commands: Commands,
+    fonts: Res<Fonts>,
+    theme: Res<Theme>,
+    game: Res<Game>,
+    bodies: Query<(&Body, Option<&Animation>)>,
+    roots: Query<Entity, With<PickerRoot>>,
+) {
+    let player = bodies.get(game.player).ok();
+    let shown = player.filter(|(_, animation)| swapping(*animation));
+    let Some((body, _)) = shown else {
+        for root in &roots {
+            commands.entity(root).despawn_recursive();
+        }
+        return;
+    };
+    if !roots.is_empty() {
+        return;
+    }
+    let text = |value: String, size: f32| {
+        TextBundle::from_section(
+            value,
+            TextStyle {
+                font: fonts.normal(),
+                font_size: size,
+                color: Color::WHITE,
+            },
+        )
+    };
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    top: Val::Px(80.0),
+                    left: Val::Percent(35.0),
+                    ..default()
+                },
+                size: Size::new(Val::Percent(30.0), Val::Auto),
+                flex_direction: FlexDirection::ColumnReverse,
+                align_items: AlignItems::Center,
+                padding: UiRect::all(Val::Px(10.0)),
+                ..default()
+            },
+            color: theme.backdrop(0.85).into(),
+            ..default()
+        })
+        .insert(PickerRoot)
+        .with_children(|parent| {
+            parent.spawn_bundle(text(t!("limb_swap.pick"), 20.0));
+            for slot in 0..body.torso().meta().arm_slots() as u8 {
+                let arm = body
+                    .arms()
+                    .iter()
+                    .find(|arm| arm.slot() == slot)
+                    .map_or_else(|| t!("limb_swap.empty"), |arm| arm.name().to_string());
+                parent
+                    .spawn_bundle(ButtonBundle {
+                        style: Style {
+                            margin: UiRect::all(Val::Px(4.0)),
+                            padding: UiRect::all(Val::Px(6.0)),
+                            ..default()
+                        },
+                        color: theme.normal_button.into(),
+                        ..default()
+                    })
+                    .insert(SlotButton(slot))
+                    .with_children(|parent| {
+                        parent.spawn_bundle(text(
+                            format!("{}: {}", Limb::Arm(slot).badge(), arm),
+                            18.0,
+                        ));
+                    });
+            }
+        });
+}
+
+fn slot_button_system(
+    theme: Res<Theme>,
+    game: Res<Game>,
+    mut sidearms: Query<(&Body, &mut Sidearm)>,
+    mut buttons: Query<(&Interaction, &SlotButton, &mut UiColor)>,
+) {
+    let Ok((body, mut sidearm)) = sidearms.get_mut(game.player) else {
+        return;
+    };
+    for (interaction, button, _) in &buttons {
+        if *interaction == Interaction::Clicked && sidearm.target != Some(button.0) {
+            sidearm.target = Some(button.0);
+        }
+    }
+    let target = sidearm.target.or_else(|| default_target(body));
+    for (interaction, button, mut color) in &mut buttons {
+        *color = if target == Some(button.0) {
+            theme.pressed_button
+        } else if *interaction == Interaction::Hovered {
+            theme.hovered_button
+        } else {
+            theme.normal_button
+        }
+        .into();
+    }
+}
+
+// Needs the profile and the battle UI, so it's left out of headless battles
+// where no body carries a spare.
+pub struct LimbSwapPlugin;
+
+impl Plugin for LimbSwapPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(SystemSet::on_enter(AppState::Battle).with_system(stock_system))
+            .add_system_set(
+                SystemSet::on_update(AppState::Battle)
+                    .with_system(break_channel_system)
+                    .with_system(complete_system.after(animation_system).before(BodyStats))
+                    .with_system(picker_system)
+                    .with_system(slot_button_system),
+            )
+            .add_system(restore_pools_system.after(BodyStats))
+            .add_system_set(SystemSet::on_exit(AppState::Battle).with_system(close_picker_system));
+    }
+}
+
+fn close_picker_system(mut commands: Commands, roots: Query<Entity, With<PickerRoot>>) {
+    for root in &roots {
+        commands.entity(root).despawn_recursive();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        body::{compute_stats, update_body_system, BodyBundle, PaletteMode, SkillId},
+        bounds::finalize_system,
+        churn::ChurnPlugin,
+        fixtures::seeded_bodies,
+        runtime::SkillRuntime,
+        DamageSource,
+    };
+
+    fn arm_skills(body: &Body, slot: u8) -> Vec<SkillId> {
+        compute_stats(body)
+            .skills
+            .iter()
+            .filter(|skill| skill.ability().is_some_and(|a| a.limb == Limb::Arm(slot)))
+            .map(Skill::id)
+            .collect()
+    }
+
+    #[test]
+    fn a_finished_channel_swaps_one_arm_and_a_broken_one_keeps_the_spare() {
+        let mut bodies = seeded_bodies(2, 2, 2);
+        let spare = bodies.pop().unwrap().arms()[0].clone();
+        let mut body = bodies.pop().unwrap();
+        let meta = body.torso_mut().meta_mut();
+        *meta = meta.clone().with_modular(true);
+        let kept = arm_skills(&body, 1);
+        assert!(!kept.is_empty());
+
+        let mut app = App::new();
+        app.init_resource::<PaletteMode>()
+            .init_resource::<BalanceConfig>()
+            .init_resource::<UseSkill>()
+            .init_resource::<Profile>()
+            .add_event::<DamageEvent>()
+            .add_event::<Toast>()
+            .add_plugin(ChurnPlugin)
+            .add_system(break_channel_system)
+            .add_system(complete_system.before(update_body_system))
+            .add_system(update_body_system)
+            .add_system(finalize_system.after(update_body_system))
+            .add_system(restore_pools_system.after(finalize_system));
+        let player = app
+            .world
+            .spawn()
+            .insert_bundle(BodyBundle { body, ..default() })
+            .insert(SkillRuntime::default())
+            .insert(Sidearm {
+                spare: Some(spare.clone()),
+                ..default()
+            })
+            .id();
+        app.insert_resource(Game {
+            player,
+            enemy: player,
+        });
+        app.update();
+        let skills = &app.world.get::<Stats>(player).unwrap().skills;
+        assert!(skills.iter().any(|skill| matches!(skill, Skill::SwapLimb)));
+
+        // A blow mid-channel ends it with the spare still there.
+        app.world
+            .entity_mut(player)
+            .insert(Animation::new(Skill::SwapLimb.id()));
+        app.world.send_event(DamageEvent {
+            target: player,
+            source: DamageSource::Console,
+            amount: 1.0,
+            emp: false,
+            impact: None,
+        });
+        app.update();
+        assert!(app.world.get::<Animation>(player).is_none());
+        assert!(app.world.get::<Recovery>(player).is_some());
+        assert!(app.world.get::<Sidearm>(player).unwrap().spare.is_some());
+
+        // Played to the end, the spare goes on slot 0 and slot 1 is left be.
+        let mut stats = app.world.get_mut::<Stats>(player).unwrap();
+        stats.health = 1.0;
+        stats.energy = 2.0;
+        app.world.entity_mut(player).remove::<Recovery>();
+        app.world.entity_mut(player).insert(Animation {
+            progress: 1.1,
+            ..Animation::new(Skill::SwapLimb.id())
+        });
+        app.world.get_mut::<Sidearm>(player).unwrap().target = Some(0);
+        app.update();
+        let body = app.world.get::<Body>(player).unwrap();
+        assert_eq!(body.arms()[0].name(), spare.name());
+        assert_eq!(arm_skills(body, 1), kept);
+        let runtime = app.world.get::<SkillRuntime>(player).unwrap();
+        for id in &kept {
+            assert!(runtime.state(id).is_some());
+        }
+        let stats = app.world.get::<Stats>(player).unwrap();
+        assert_eq!((stats.health, stats.energy), (1.0, 2.0));
+        assert!(app.world.get::<Sidearm>(player).unwrap().spare.is_none());
+    }
+}
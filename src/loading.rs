@@ -0,0 +1,158 @@
+use bevy::{
+    asset::LoadState,
+    prelude::*,
+    render::render_resource::{Extent3d, TextureDimension, TextureFormat},
+};
+
+use crate::{
+    error::{ErrorToast, GameError},
+    icons::MOVEMENT_TEXTURES,
+    locale::t,
+    ui::{FONT_BOLD, FONT_NORMAL},
+    AppState,
+};
+
+// Built into the binary so text still renders without an assets directory.
+const FALLBACK_FONT: &[u8] = include_bytes!("../assets/fonts/FiraMono-Medium.ttf");
+const FALLBACK_COLOR: [u8; 4] = [255, 0, 255, 255];
+
+struct Preload {
+    fonts: Vec<(&'static str, Handle<Font>)>,
+    images: Vec<(&'static str, Handle<Image>)>,
+}
+
+#[derive(Component)]
+struct LoadingScreen;
+
+fn fallback_font() -> Font {
+    Font::try_from_bytes(FALLBACK_FONT.to_vec()).expect("built-in font is valid")
+}
+
+fn fallback_image() -> Image {
+    Image::new_fill(
+        Extent3d {
+            width: 1,
+            height: 1,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        &FALLBACK_COLOR,
+        TextureFormat::Rgba8UnormSrgb,
+    )
+}
+
+fn start_loading_system(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut fonts: ResMut<Assets<Font>>,
+) {
+    let fallback_font = fonts.add(fallback_font());
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                position_type: PositionType::Absolute,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            color: Color::BLACK.into(),
+            ..default()
+        })
+        .insert(LoadingScreen)
+        .with_children(|parent| {
+            parent.spawn_bundle(TextBundle::from_section(
+                t!("loading.title"),
+                TextStyle {
+                    font: fallback_font,
+                    font_size: 40.0,
+                    color: Color::WHITE,
+                },
+            ));
+        });
+    commands.insert_resource(Preload {
+        fonts: [FONT_NORMAL, FONT_BOLD]
+            .into_iter()
+            .map(|path| (path, asset_server.load(path)))
+            .collect(),
+        images: MOVEMENT_TEXTURES
+            .iter()
+            .map(|path| (*path, asset_server.load(*path)))
+            .collect(),
+    });
+}
+
+fn finished(state: LoadState) -> bool {
+    matches!(state, LoadState::Loaded | LoadState::Failed)
+}
+
+// Waits for every preloaded asset, then swaps a fallback in under the handle
+// of each one that failed so everything holding that handle keeps working.
+fn loading_system(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    preload: Option<Res<Preload>>,
+    mut fonts: ResMut<Assets<Font>>,
+    mut images: ResMut<Assets<Image>>,
+    mut state: ResMut<State<AppState>>,
+    mut errors: EventWriter<ErrorToast>,
+) {
+    let preload = match preload {
+        Some(preload) => preload,
+        None => return,
+    };
+    let font_states = preload
+        .fonts
+        .iter()
+        .map(|(_, handle)| asset_server.get_load_state(handle));
+    let image_states = preload
+        .images
+        .iter()
+        .map(|(_, handle)| asset_server.get_load_state(handle));
+    if !font_states.chain(image_states).all(finished) {
+        return;
+    }
+
+    let mut missing = Vec::new();
+    for (path, handle) in &preload.fonts {
+        if asset_server.get_load_state(handle) == LoadState::Failed {
+            error!("Failed to load font `{}`, using the built-in font", path);
+            fonts.set_untracked(handle, fallback_font());
+            missing.push(path.to_string());
+        }
+    }
+    for (path, handle) in &preload.images {
+        if asset_server.get_load_state(handle) == LoadState::Failed {
+            error!("Failed to load image `{}`, using a placeholder", path);
+            images.set_untracked(handle, fallback_image());
+            missing.push(path.to_string());
+        }
+    }
+    // One toast for the lot, a missing assets directory fails all of them.
+    if !missing.is_empty() {
+        errors.send(ErrorToast(GameError::MissingAssets(missing)));
+    }
+    commands.remove_resource::<Preload>();
+    let _ = state.set(AppState::PreBattle);
+}
+
+fn finish_loading_system(mut commands: Commands, screens: Query<Entity, With<LoadingScreen>>) {
+    for entity in &screens {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+pub struct LoadingPlugin;
+
+impl Plugin for LoadingPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_state(AppState::Loading)
+            .add_system_set(
+                SystemSet::on_enter(AppState::Loading).with_system(start_loading_system),
+            )
+            .add_system_set(SystemSet::on_update(AppState::Loading).with_system(loading_system))
+            .add_system_set(
+                SystemSet::on_exit(AppState::Loading).with_system(finish_loading_system),
+            );
+    }
+}
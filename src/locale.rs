@@ -0,0 +1,241 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Write,
+    sync::{LazyLock, Mutex, RwLock},
+};
+
+use bevy::prelude::*;
+
+use crate::profile::Profile;
+
+// Built into the binary like the fallback font, so every table is available
+// in headless runs too. Each file maps keys to templates, `{name}` is
+// replaced by the argument of that name and `{name:.1}` rounds a number.
+pub const LANGUAGES: &[(&str, &str)] = &[
+    ("en", include_str!("../assets/lang/en.ron")),
+    ("es", include_str!("../assets/lang/es.ron")),
+];
+const FALLBACK: &str = "en";
+
+type Table = HashMap<String, String>;
+
+static TABLES: LazyLock<HashMap<&'static str, Table>> = LazyLock::new(|| {
+    LANGUAGES
+        .iter()
+        .filter_map(|(code, source)| match ron::from_str::<Table>(source) {
+            Ok(table) => Some((*code, table)),
+            Err(err) => {
+                error!("Failed to parse language `{}`: {}", code, err);
+                None
+            }
+        })
+        .collect()
+});
+static ACTIVE: RwLock<&'static str> = RwLock::new(FALLBACK);
+static WARNED: LazyLock<Mutex<HashSet<String>>> = LazyLock::new(default);
+
+// The language strings are shown in, `t!` reads whichever one was applied last.
+pub struct Locale {
+    pub language: String,
+}
+
+impl Default for Locale {
+    fn default() -> Self {
+        Self {
+            language: FALLBACK.to_string(),
+        }
+    }
+}
+
+impl Locale {
+    // Applies right away, for screens that rebuild in the same frame.
+    pub fn set(&mut self, language: &str) {
+        self.language = language.to_string();
+        self.apply();
+    }
+
+    fn apply(&self) {
+        match LANGUAGES.iter().find(|(code, _)| *code == self.language) {
+            Some((code, _)) => *ACTIVE.write().unwrap() = code,
+            None => warn!("Unknown language `{}`", self.language),
+        }
+    }
+}
+
+pub enum Arg {
+    Int(i64),
+    Float(f32),
+    Text(String),
+}
+
+macro_rules! impl_arg {
+    ($variant:ident, $cast:ty: $($ty:ty),*) => {
+        $(impl From<$ty> for Arg {
+            fn from(value: $ty) -> Self {
+                Arg::$variant(value as $cast)
+            }
+        })*
+    };
+}
+
+impl_arg!(Int, i64: u8, u32, u64, usize, i32, i64);
+impl_arg!(Float, f32: f32, f64);
+
+impl From<&str> for Arg {
+    fn from(value: &str) -> Self {
+        Arg::Text(value.to_string())
+    }
+}
+
+impl From<String> for Arg {
+    fn from(value: String) -> Self {
+        Arg::Text(value)
+    }
+}
+
+impl From<&String> for Arg {
+    fn from(value: &String) -> Self {
+        Arg::Text(value.clone())
+    }
+}
+
+fn format(template: &str, args: &[(&str, Arg)]) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find(['{', '}']) {
+        out.push_str(&rest[..start]);
+        let brace = rest.as_bytes()[start];
+        rest = &rest[start + 1..];
+        // Doubled braces are literal.
+        if rest.as_bytes().first() == Some(&brace) {
+            out.push(brace as char);
+            rest = &rest[1..];
+            continue;
+        }
+        let end = match (brace, rest.find('}')) {
+            (b'{', Some(end)) => end,
+            _ => {
+                out.push(brace as char);
+                continue;
+            }
+        };
+        let (name, precision) = match rest[..end].split_once(":.") {
+            Some((name, precision)) => (name, precision.parse::<usize>().ok()),
+            None => (&rest[..end], None),
+        };
+        match args
+            .iter()
+            .find(|(arg, _)| *arg == name)
+            .map(|(_, value)| value)
+        {
+            Some(Arg::Int(value)) => write!(out, "{}", value).unwrap(),
+            Some(Arg::Float(value)) => match precision {
+                Some(precision) => write!(out, "{:.*}", precision, value).unwrap(),
+                None => write!(out, "{}", value).unwrap(),
+            },
+            Some(Arg::Text(value)) => out.push_str(value),
+            None => write!(out, "{{{}}}", name).unwrap(),
+        }
+        rest = &rest[end + 1..];
+    }
+    out.push_str(rest);
+    out
+}
+
+// A language's own name for itself, for the language picker.
+pub fn language_name(code: &str) -> String {
+    TABLES
+        .get(code)
+        .and_then(|table| table.get("language.name"))
+        .cloned()
+        .unwrap_or_else(|| code.to_string())
+}
+
+pub fn translate(key: &str, args: &[(&str, Arg)]) -> String {
+    let active = *ACTIVE.read().unwrap();
+    let template = [active, FALLBACK]
+        .iter()
+        .find_map(|code| TABLES.get(code).and_then(|table| table.get(key)));
+    match template {
+        Some(template) => format(template, args),
+        None => {
+            if WARNED.lock().unwrap().insert(key.to_string()) {
+                warn!("Missing translation for `{}`", key);
+            }
+            key.to_string()
+        }
+    }
+}
+
+// `t!("menu.fight")` or `t!("menu.record", wins = profile.wins, ...)`.
+macro_rules! t {
+    ($key:expr $(, $name:ident = $value:expr)* $(,)?) => {
+        $crate::locale::translate(
+            &$key,
+            &[$((stringify!($name), $crate::locale::Arg::from($value))),*],
+        )
+    };
+}
+pub(crate) use t;
+
+fn sync_locale_system(profile: Res<Profile>, mut locale: ResMut<Locale>) {
+    if profile.is_changed() && locale.language != profile.settings.language {
+        locale.language = profile.settings.language.clone();
+    }
+}
+
+fn apply_locale_system(locale: Res<Locale>) {
+    if locale.is_changed() {
+        locale.apply();
+    }
+}
+
+pub struct LocalePlugin;
+
+impl Plugin for LocalePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Locale>()
+            .add_system_to_stage(CoreStage::PreUpdate, sync_locale_system)
+            .add_system_to_stage(
+                CoreStage::PreUpdate,
+                apply_locale_system.after(sync_locale_system),
+            );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn templates_fill_named_arguments() {
+        let args = [
+            ("wins", Arg::from(3u32)),
+            ("speed", Arg::from(2.26f32)),
+            ("name", Arg::from("Jab")),
+        ];
+        assert_eq!(
+            format("{name}: {wins} wins at {speed:.1} {{m/s}}", &args),
+            "Jab: 3 wins at 2.3 {m/s}"
+        );
+        assert_eq!(format("{missing}", &args), "{missing}");
+    }
+
+    #[test]
+    fn every_language_has_every_key() {
+        let fallback = &TABLES[FALLBACK];
+        for (code, _) in LANGUAGES {
+            let table = TABLES
+                .get(code)
+                .unwrap_or_else(|| panic!("`{}` doesn't parse", code));
+            for key in fallback.keys() {
+                assert!(table.contains_key(key), "`{}` is missing `{}`", code, key);
+            }
+        }
+    }
+
+    #[test]
+    fn missing_keys_render_as_the_key() {
+        assert_eq!(translate("no.such.key", &[]), "no.such.key");
+    }
+}
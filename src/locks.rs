@@ -0,0 +1,213 @@
+use bevy::prelude::*;
+
+use crate::{
+    battle::{use_skill_system, Animation, Walking},
+    body::{Body, Limb, Skill, SkillId, Stats},
+    AppState,
+};
+
+// Which running skill holds each limb. A skill only starts alongside another
+// when none of its limbs are held, see `track_for`.
+#[derive(Component, Default, Debug, PartialEq)]
+pub struct LimbLocks(Vec<(Limb, SkillId)>);
+
+impl LimbLocks {
+    pub fn holder(&self, limb: Limb) -> Option<&SkillId> {
+        self.0
+            .iter()
+            .find_map(|(held, skill)| (*held == limb).then_some(skill))
+    }
+
+    pub fn free(&self, limbs: &[Limb]) -> bool {
+        limbs.iter().all(|limb| self.holder(*limb).is_none())
+    }
+
+    // All or nothing, `skill` gets none of the limbs if any is taken. Limbs
+    // it already holds count as free.
+    pub fn claim(&mut self, skill: &SkillId, limbs: &[Limb]) -> bool {
+        if limbs
+            .iter()
+            .any(|limb| self.holder(*limb).is_some_and(|holder| holder != skill))
+        {
+            return false;
+        }
+        for limb in limbs {
+            if self.holder(*limb).is_none() {
+                self.0.push((*limb, skill.clone()));
+            }
+        }
+        true
+    }
+
+    pub fn release(&mut self, skill: &SkillId) {
+        self.0.retain(|(_, holder)| holder != skill);
+    }
+}
+
+// The limbs a skill moves. Walks take the legs, attacks their own arm and
+// turning around takes the whole body.
+pub fn claimed_limbs(skill: &Skill, body: &Body) -> Vec<Limb> {
+    let arms = body.arms().iter().map(|arm| Limb::Arm(arm.slot()));
+    let legs = body.legs().iter().map(|leg| Limb::Leg(leg.slot()));
+    match skill {
+        Skill::WalkForward | Skill::WalkBackward | Skill::Dash(_) => legs.collect(),
+        Skill::TurnAround => arms.chain(legs).collect(),
+        // Every arm is held while one of them is swapped.
+        Skill::SwapLimb => arms.collect(),
+        // Every arm with a ranged skill is lowered to load it.
+        Skill::Reload => body
+            .arms()
+            .iter()
+            .zip(arms)
+            .filter(|(arm, _)| arm.stats().skills().iter().any(|s| s.release().is_some()))
+            .map(|(_, limb)| limb)
+            .collect(),
+        _ => skill
+            .ability()
+            .map(|ability| ability.limb)
+            .into_iter()
+            .collect(),
+    }
+}
+
+fn moves(skill: &Skill) -> bool {
+    skill.walks()
+}
+
+// Where a skill started now would play.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Track {
+    // As the body's Animation, nothing else but maybe a walk is playing.
+    Main,
+    // As a Walking under the action already playing.
+    Walk,
+    // As the Animation, with the walk that was playing moved to Walking so
+    // the action is the one started.
+    Swap,
+}
+
+// None while the skill has to wait for its limbs or for a track.
+pub fn track_for(
+    skill: &Skill,
+    body: &Body,
+    stats: &Stats,
+    locks: &LimbLocks,
+    animation: Option<&Animation>,
+    walking: Option<&Walking>,
+) -> Option<Track> {
+    if !locks.free(&claimed_limbs(skill, body)) {
+        return None;
+    }
+    let Some(animation) = animation else {
+        return Some(Track::Main);
+    };
+    let running = stats.skill(&animation.skill)?;
+    match (walking, moves(skill), moves(running)) {
+        (None, true, false) => Some(Track::Walk),
+        (None, false, true) => Some(Track::Swap),
+        _ => None,
+    }
+}
+
+// Whether `skill` is playing on either track.
+pub fn playing(skill: &SkillId, animation: Option<&Animation>, walking: Option<&Walking>) -> bool {
+    animation.is_some_and(|animation| animation.skill == *skill)
+        || walking.is_some_and(|walking| walking.0.skill == *skill)
+}
+
+// Locks follow what's playing, so anything that ends an animation, finished,
+// cancelled or interrupted, gives its limbs back.
+fn lock_system(
+    mut bodies: Query<(
+        &Body,
+        &Stats,
+        Option<&Animation>,
+        Option<&Walking>,
+        &mut LimbLocks,
+    )>,
+) {
+    for (body, stats, animation, walking, mut locks) in &mut bodies {
+        let running = [animation, walking.map(|walking| &walking.0)]
+            .into_iter()
+            .flatten()
+            .filter_map(|animation| stats.skill(&animation.skill))
+            .collect::<Vec<_>>();
+        let mut next = LimbLocks(locks.0.clone());
+        for (_, holder) in &locks.0 {
+            if !running.iter().any(|skill| skill.id() == *holder) {
+                next.release(holder);
+            }
+        }
+        for skill in running {
+            next.claim(&skill.id(), &claimed_limbs(skill, body));
+        }
+        if *locks != next {
+            *locks = next;
+        }
+    }
+}
+
+pub struct LocksPlugin;
+
+impl Plugin for LocksPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(
+            SystemSet::on_update(AppState::Battle)
+                .with_system(lock_system.before(use_skill_system)),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::body::compute_stats;
+
+    #[test]
+    fn walks_and_jabs_share_a_body() {
+        let body = Body::default();
+        let stats = compute_stats(&body);
+        let find = |f: fn(&Skill) -> bool| stats.skills.iter().find(|skill| f(skill)).unwrap();
+        let walk = find(|skill| matches!(skill, Skill::WalkForward));
+        let back = find(|skill| matches!(skill, Skill::WalkBackward));
+        let jab = find(|skill| matches!(skill, Skill::BasicMelee(_)));
+        let turn = Skill::TurnAround;
+
+        let mut locks = LimbLocks::default();
+        assert!(locks.claim(&walk.id(), &claimed_limbs(walk, &body)));
+        assert!(locks.claim(&jab.id(), &claimed_limbs(jab, &body)));
+        assert_eq!(locks.holder(Limb::Leg(1)), Some(&walk.id()));
+        // A second walk or a turn needs limbs that are taken.
+        assert!(!locks.claim(&back.id(), &claimed_limbs(back, &body)));
+        assert!(!locks.claim(&turn.id(), &claimed_limbs(&turn, &body)));
+        assert_eq!(locks.holder(Limb::Arm(1)), None);
+
+        locks.release(&walk.id());
+        assert!(locks.free(&claimed_limbs(back, &body)));
+        assert!(!locks.free(&claimed_limbs(&turn, &body)));
+        locks.release(&jab.id());
+        assert_eq!(locks, LimbLocks::default());
+
+        // Walking, a jab swaps in as the action. Jabbing, a walk goes under it.
+        let walking = Animation::new(walk.id());
+        let mut locks = LimbLocks::default();
+        locks.claim(&walk.id(), &claimed_limbs(walk, &body));
+        let track = |skill, locks: &LimbLocks, animation, walking| {
+            track_for(skill, &body, &stats, locks, animation, walking)
+        };
+        assert_eq!(track(jab, &locks, Some(&walking), None), Some(Track::Swap));
+        assert_eq!(track(back, &locks, Some(&walking), None), None);
+        let jabbing = Animation::new(jab.id());
+        let mut locks = LimbLocks::default();
+        locks.claim(&jab.id(), &claimed_limbs(jab, &body));
+        assert_eq!(track(walk, &locks, Some(&jabbing), None), Some(Track::Walk));
+        // Two actions never play at once, even on different arms.
+        let other = find(|skill| matches!(skill, Skill::BasicRanged(_)));
+        assert_ne!(other.ability().unwrap().limb, jab.ability().unwrap().limb);
+        assert_eq!(track(other, &locks, Some(&jabbing), None), None);
+        assert_eq!(
+            track(jab, &LimbLocks::default(), None, None),
+            Some(Track::Main)
+        );
+    }
+}
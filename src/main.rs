@@ -1,283 +1,361 @@
+#![allow(clippy::too_many_arguments, clippy::type_complexity)]
+
+mod achievements;
+mod affinity;
+mod ai;
+mod ai_view;
+mod ammo;
+mod approach;
+mod attachment;
+mod auto;
+mod balance;
+mod battle;
+mod bench;
 mod body;
+mod booster;
+mod boss;
+mod bounds;
+mod camera;
+mod campaign;
+mod churn;
+mod confirm;
+#[cfg(debug_assertions)]
+mod console;
+mod daily;
+mod debug;
+mod decay;
+mod dust;
+mod effects;
+mod enemy_preview;
+mod energy;
+mod error;
+mod fixtures;
+mod focus;
+mod history;
+mod hitbox;
+mod hitbox_view;
+mod icons;
+mod impact;
+mod interrupt;
+mod intro;
+mod knowledge;
+mod layer;
+mod limb_swap;
+mod loading;
+mod locale;
+mod locks;
+mod menu;
+mod mutators;
+mod narration;
+#[cfg(feature = "net")]
+mod net;
+mod palette;
+mod performance;
+mod periodic;
+mod pickup;
+mod preview;
+mod profile;
+mod projectile;
+mod rate;
+mod rating;
+mod rivals;
+mod ruler;
+mod runtime;
+mod shadow;
+mod shield;
+mod sim;
+mod snapshot;
+mod sound;
+mod starter;
+mod storage;
+mod survival;
+mod swap;
+mod telemetry;
+mod text_input;
+mod theme;
+mod timeline;
+mod timer;
+mod tips;
+mod tooltip_preview;
+mod tournament;
+mod training;
+mod turn;
+mod tutorial;
 mod ui;
+mod undo;
+mod upgrade;
+mod weather;
 
-use std::f32::consts::PI;
+use std::ops::{Deref, DerefMut};
 
-use bevy::{prelude::*, render::camera::ScalingMode, sprite::Anchor};
-use body::{random_body, BodyBundle, Limb, Stats};
-use smallmap::Map;
-use ui::UseSkill;
+use bevy::{asset::AssetServerSettings, prelude::*};
+use rand::{rngs::StdRng, Rng, SeedableRng};
 
-struct Game {
-    player: Entity,
-    enemy: Entity,
-}
+use locale::t;
 
-pub struct BodyTransforms<'a, 'world, 'state, 'inner> {
-    transforms: &'a mut Query<'world, 'state, &'inner mut Transform>,
-    legs: Map<u8, Entity>,
-    arms: Map<u8, Entity>,
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AppState {
+    Loading,
+    PreBattle,
+    Battle,
+    // Pushed on top of Battle while the bodies are shown off.
+    Intro,
+    Paused,
+    GameOver,
+    Achievements,
+    Intermission,
+    Statistics,
+    RunHistory,
+    Rivals,
+    DailyHistory,
+    Settings,
+    // Picking a body out of a freshly rolled lineup.
+    Starter,
+    Campaign,
 }
 
-impl<'a, 'world, 'state, 'inner> BodyTransforms<'a, 'world, 'state, 'inner> {
-    fn get_entity(&self, limb: Limb) -> Entity {
-        match &limb {
-            Limb::Arm(i) => *self.arms.get(i).unwrap(),
-            Limb::Leg(i) => *self.legs.get(i).unwrap(),
-        }
-    }
-
-    pub fn get(&self, limb: Limb) -> &Transform {
-        self.transforms.get(self.get_entity(limb)).unwrap()
-    }
-    pub fn get_mut(&mut self, limb: Limb) -> Mut<Transform> {
-        self.transforms.get_mut(self.get_entity(limb)).unwrap()
-    }
+#[derive(Clone, Copy)]
+pub struct Game {
+    pub player: Entity,
+    pub enemy: Entity,
+}
 
-    pub fn for_legs(&mut self, mut f: impl FnMut(u8, &mut Transform)) {
-        for (i, e) in self.legs.iter() {
-            f(*i, self.transforms.get_mut(*e).unwrap().as_mut());
-        }
+impl Game {
+    pub fn targets_self(&self) -> bool {
+        self.player == self.enemy
     }
 
-    pub fn for_arms(&mut self, mut f: impl FnMut(u8, &mut Transform)) {
-        for (i, e) in self.arms.iter() {
-            f(*i, self.transforms.get_mut(*e).unwrap().as_mut());
+    pub fn opponent(&self, entity: Entity) -> Entity {
+        if entity == self.player {
+            self.enemy
+        } else {
+            self.player
         }
     }
 }
 
-struct Animation {
-    skill: usize,
-    progress: f32,
+pub struct DamageEvent {
+    pub target: Entity,
+    pub source: DamageSource,
+    pub amount: f32,
+    // Dealt by an EMP ability, it hits overshields twice as hard.
+    pub emp: bool,
+    // `None` for damage that doesn't land on a part, like ticks or sudden
+    // death.
+    pub impact: Option<Impact>,
 }
 
-fn do_animation(
-    entity: Entity,
-    enemy: Entity,
-    stats: Query<(&Stats, &Children)>,
-    animation: &mut Animation,
-    limbs: Query<&Limb>,
-    mut transforms: Query<&mut Transform>,
-    time: &Time,
-) {
-    let [(stats, children), (enemy_stats, _)] = stats.get_many([entity, enemy]).unwrap();
+// What dealt a DamageEvent.
+#[derive(Clone, Debug, PartialEq)]
+pub enum DamageSource {
+    // A hit or a shot from one of a combatant's skills.
+    Attack {
+        attacker: Entity,
+        skill: body::SkillId,
+    },
+    // A ticking effect by its id, see `periodic`. `from` is whoever put it
+    // on, if anyone.
+    Status {
+        id: &'static str,
+        from: Option<Entity>,
+    },
+    SuddenDeath,
+    // The debug console's `damage`.
+    Console,
+}
 
-    let (mut position, mut direction) = {
-        let transform = transforms.get(entity).unwrap();
+impl DamageSource {
+    // The combatant that landed it as a blow, ticks and the like aren't hits.
+    pub fn attacker(&self) -> Option<Entity> {
+        match self {
+            DamageSource::Attack { attacker, .. } => Some(*attacker),
+            _ => None,
+        }
+    }
 
-        (transform.translation.x, transform.scale.x)
-    };
+    // The combatant the damage counts for, if any.
+    pub fn dealer(&self) -> Option<Entity> {
+        match self {
+            DamageSource::Attack { attacker, .. } => Some(*attacker),
+            DamageSource::Status { from, .. } => *from,
+            DamageSource::SuddenDeath | DamageSource::Console => None,
+        }
+    }
 
-    let mut body_parts = BodyTransforms {
-        transforms: &mut transforms,
-        legs: Map::new(),
-        arms: Map::new(),
-    };
-    for e in children {
-        if let Ok(limb) = limbs.get(*e) {
-            match limb {
-                Limb::Arm(i) => body_parts.arms.insert(*i, *e),
-                Limb::Leg(i) => body_parts.legs.insert(*i, *e),
-            };
+    // Puts the source in words for the narration and the game over screen.
+    // `name` and `skill` look up the attacker's, a skill it no longer has
+    // leaves just its name.
+    pub fn describe(
+        &self,
+        name: impl Fn(Entity) -> String,
+        skill: impl Fn(Entity, &body::SkillId) -> Option<String>,
+    ) -> String {
+        match self {
+            DamageSource::Attack {
+                attacker,
+                skill: id,
+            } => match skill(*attacker, id) {
+                Some(skill) => t!(
+                    "damage_source.attack",
+                    attacker = name(*attacker),
+                    skill = skill
+                ),
+                None => name(*attacker),
+            },
+            DamageSource::Status { id, .. } => t!(format!("effect.{}.name", id)),
+            DamageSource::SuddenDeath => t!(format!("effect.{}.name", timer::SUDDEN_DEATH)),
+            DamageSource::Console => t!("damage_source.console"),
         }
     }
+}
 
-    let dt = time.delta_seconds();
+// Where a blow landed and what it landed on, for the sounds and sparks.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Impact {
+    pub material: body::Material,
+    pub point: Vec2,
+}
 
-    fn walk(
-        position: &mut f32,
-        direction: f32,
-        mul: f32,
-        dt: f32,
-        stats: &Stats,
-        animation: &Animation,
-        body_parts: &mut BodyTransforms,
-    ) {
-        *position += dt * stats.speed * direction * mul;
-        const END_TIME: f32 = 0.1;
-        if animation.progress < 1.0 - END_TIME {
-            let distance_moved = stats.speed * animation.progress * mul;
-            body_parts.for_legs(|i, transform| {
-                let sign = (i % 2) as f32 * 2.0 - 1.0;
+impl Impact {
+    pub fn on(body: &body::Body, part: body::HitPart, point: Vec2) -> Option<Self> {
+        let (_, stats) = body.part(part)?;
+        Some(Self {
+            material: stats.material(),
+            point,
+        })
+    }
+}
 
-                let a = (sign * distance_moved).sin();
+#[derive(Component)]
+pub struct Invulnerable;
 
-                transform.rotation = Quat::from_rotation_z(a);
-            });
-            body_parts.for_arms(|i, transform| {
-                let sign = -((i % 2) as f32 * 2.0 - 1.0);
+pub struct BattleSummary {
+    pub winner: Option<Entity>,
+    pub sudden_death: bool,
+    pub mutators: mutators::Mutators,
+    // Used up by the player for the battle.
+    pub boosters: Vec<booster::Booster>,
+    // Share of the player's max health left at the end.
+    pub player_health: f32,
+    // What every AI-driven body thought last, for looking into a battle
+    // afterwards.
+    pub decisions: Vec<(Entity, ai::Decision)>,
+    // What took each defeated body's last health, both of them for a draw.
+    pub finishing_blows: Vec<(Entity, DamageSource)>,
+}
 
-                let a = (sign * distance_moved).sin();
+pub struct GameRng {
+    seed: u64,
+    rng: StdRng,
+}
 
-                transform.rotation = Quat::from_rotation_z(a);
-            });
-        } else {
-            let t = (animation.progress - 1.0 + END_TIME) / END_TIME;
-            body_parts.for_legs(|_, transform| {
-                transform.rotation = transform.rotation.lerp(Quat::IDENTITY, t);
-            });
-            body_parts.for_arms(|_, transform| {
-                transform.rotation = transform.rotation.lerp(Quat::IDENTITY, t);
-            });
+impl GameRng {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            rng: StdRng::seed_from_u64(seed),
         }
     }
 
-    match &stats.skills[animation.skill] {
-        body::Skill::WalkBackward => {
-            walk(
-                &mut position,
-                direction,
-                -0.5,
-                dt,
-                stats,
-                animation,
-                &mut body_parts,
-            );
-        }
-        body::Skill::WalkForward => {
-            walk(
-                &mut position,
-                direction,
-                1.0,
-                dt,
-                stats,
-                animation,
-                &mut body_parts,
-            );
-        }
-        body::Skill::TurnAround => {
-            if animation.progress <= 0.5 {
-                let t = 1.0 - animation.progress * 2.0;
-                direction = direction * t - direction.signum() * 0.0001;
-            } else {
-                let t = animation.progress * 2.0 - 1.0;
-                direction = direction * (1.0 - t) + t * direction.signum();
-            }
-        }
-        body::Skill::BasicMelee(ability) => {
-            let mut transform = body_parts.get_mut(ability.limb);
-
-            let a = (animation.progress * PI).sin();
-            transform.rotation = Quat::from_rotation_z(a);
-        }
-        body::Skill::BasicRanged(_) => todo!(),
-        body::Skill::Scan(_) => todo!(),
+    pub fn seed(&self) -> u64 {
+        self.seed
     }
+}
 
-    {
-        let [mut transform, enemy] = transforms.get_many_mut([entity, enemy]).unwrap();
-        if transform.translation.x < enemy.translation.x {
-            transform.translation.x =
-                position.min(enemy.translation.x - (stats.width + enemy_stats.width) / 2.0 - 0.1);
-        } else {
-            transform.translation.x =
-                position.max(enemy.translation.x + (stats.width + enemy_stats.width) / 2.0 + 0.1);
-        }
-        transform.scale.x = direction;
+impl Default for GameRng {
+    fn default() -> Self {
+        Self::new(rand::thread_rng().gen())
     }
+}
+
+impl Deref for GameRng {
+    type Target = StdRng;
 
-    const ANIMATION_SPEED: f32 = 1.0;
-    animation.progress += dt * ANIMATION_SPEED;
+    fn deref(&self) -> &Self::Target {
+        &self.rng
+    }
 }
 
-fn use_skill_system(
-    mut use_skill: ResMut<UseSkill>,
-    game: Res<Game>,
-    time: Res<Time>,
-    stats: Query<(&Stats, &Children)>,
-    limbs: Query<&Limb>,
-    transforms: Query<&mut Transform>,
-    mut maybe_animation: Local<Option<Animation>>,
-) {
-    if let Some(animation) = maybe_animation.as_mut() {
-        do_animation(
-            game.player,
-            game.enemy,
-            stats,
-            animation,
-            limbs,
-            transforms,
-            &time,
-        );
-        if animation.progress > 1.0 {
-            *maybe_animation = None;
-            **use_skill = None;
-        }
-    } else if let Some(skill) = (*use_skill).as_ref() {
-        *maybe_animation = Some(Animation {
-            skill: *skill,
-            progress: 0.0,
-        });
+impl DerefMut for GameRng {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.rng
     }
 }
 
-fn scene_setup_system(mut commands: Commands) {
-    commands.spawn_bundle(Camera2dBundle {
-        transform: Transform::from_scale(Vec3::splat(5.0))
-            .with_translation(Vec3::new(0.0, 0.0, 0.0)),
-        projection: OrthographicProjection {
-            scaling_mode: ScalingMode::FixedHorizontal(5.0),
-            ..default()
-        },
+fn main() {
+    if let Some(options) = bench::BenchOptions::from_args(std::env::args().skip(1)) {
+        bench::run(options);
+        return;
+    }
+    if let Some(options) = sim::SimOptions::from_args(std::env::args().skip(1)) {
+        sim::run(options);
+        return;
+    }
+
+    let mut app = App::new();
+    // Fills the page's canvas on the web, see index.html.
+    #[cfg(target_arch = "wasm32")]
+    app.insert_resource(WindowDescriptor {
+        canvas: Some("#bevy".to_string()),
+        fit_canvas_to_parent: true,
         ..default()
     });
-
-    let size = 40.0;
-    commands.spawn_bundle(SpriteBundle {
-        transform: Transform::from_scale(Vec3::new(size, size, 1.0)),
-        sprite: Sprite {
-            color: Color::BLACK,
-            anchor: Anchor::TopCenter,
-            ..default()
-        },
+    // Lets combat.balance.ron changes apply to the running game.
+    app.insert_resource(AssetServerSettings {
+        watch_for_changes: !cfg!(target_arch = "wasm32"),
         ..default()
     });
-    let player = commands
-        .spawn_bundle(BodyBundle {
-            // body: random_body(&mut rand::thread_rng()),
-            transform: Transform::from_translation(Vec3::new(-4.0, 0.0, 0.0)),
-            ..default()
-        })
-        .id();
-
-    let enemy = commands
-        .spawn_bundle(BodyBundle {
-            body: random_body(&mut rand::thread_rng()),
-            transform: Transform::from_translation(Vec3::new(4.0, 0.0, 0.0)),
-            ..default()
-        })
-        .id();
-
-    commands.insert_resource(Game { player, enemy });
-}
-
-fn dynamic_camera(
-    game: Res<Game>,
-    mut camera_transform: Query<&mut Transform, With<Camera>>,
-    transforms: Query<&Transform, Without<Camera>>,
-) {
-    let player = game.player;
-    let enemy = game.enemy;
-    let mut camera_transform = camera_transform.single_mut();
-    let player_transform = transforms.get(player).unwrap();
-    let enemy_transform = transforms.get(enemy).unwrap();
-    let vector_between = enemy_transform.translation - player_transform.translation;
-    let distance_between = vector_between.length();
-    let look_at_pos = player_transform.translation + vector_between / 2.0;
-    camera_transform.translation.x = look_at_pos.x;
-    camera_transform.translation.y = look_at_pos.y;
-    camera_transform.scale = Vec3::splat(distance_between / 6.0 + 8.0);
-}
-
-fn main() {
-    App::new()
-        .add_plugins(DefaultPlugins)
+    app.add_plugins(DefaultPlugins)
+        .add_plugin(locale::LocalePlugin)
+        .add_plugin(loading::LoadingPlugin)
+        .add_plugin(battle::BattlePlugin)
+        .add_plugin(performance::PerformancePlugin)
+        .add_plugin(balance::BalancePlugin)
+        .add_plugin(camera::CameraPlugin)
+        .add_plugin(dust::DustPlugin)
+        .add_plugin(sound::SoundPlugin)
+        .add_plugin(impact::ImpactPlugin)
+        .add_plugin(shadow::ShadowPlugin)
+        .add_plugin(weather::SkyPlugin)
         .add_plugin(ui::UiPlugin)
-        .add_plugin(body::BodyPlugin)
+        .add_plugin(limb_swap::LimbSwapPlugin)
+        .add_plugin(auto::AutoPlugin)
+        .add_plugin(error::ErrorPlugin)
+        .add_plugin(ruler::RulerPlugin)
+        .add_plugin(preview::PreviewPlugin)
+        .add_plugin(tooltip_preview::TooltipPreviewPlugin)
+        .add_plugin(enemy_preview::EnemyPreviewPlugin)
+        .add_plugin(text_input::TextInputPlugin)
+        .add_plugin(theme::ThemePlugin)
+        .add_plugin(focus::FocusPlugin)
+        .add_plugin(menu::MenuPlugin)
+        .add_plugin(confirm::ConfirmPlugin)
+        .add_plugin(narration::NarrationPlugin)
+        .add_plugin(tips::TipsPlugin)
+        .add_plugin(palette::PalettePlugin)
+        .add_plugin(profile::ProfilePlugin)
+        .add_plugin(achievements::AchievementPlugin)
+        .add_plugin(history::HistoryPlugin)
+        .add_plugin(rivals::RivalsPlugin)
+        .add_plugin(daily::DailyPlugin)
+        .add_plugin(survival::SurvivalPlugin)
+        .add_plugin(decay::DecayPlugin)
+        .add_plugin(boss::BossPlugin)
+        .add_plugin(tutorial::TutorialPlugin)
+        .add_plugin(training::TrainingPlugin)
+        .add_plugin(hitbox_view::HitboxViewPlugin)
+        .add_plugin(ai_view::AiViewPlugin)
+        .add_plugin(intro::IntroPlugin)
+        .add_plugin(starter::StarterPlugin)
+        .add_plugin(campaign::CampaignPlugin)
+        .add_plugin(snapshot::SnapshotPlugin)
+        .add_plugin(telemetry::TelemetryPlugin)
+        .add_plugin(debug::DebugPlugin)
         .add_system(bevy::window::close_on_esc)
-        .add_startup_system(scene_setup_system)
-        .add_system(use_skill_system)
-        .add_system(dynamic_camera)
-        .run();
+        .add_startup_system(battle::arena_setup_system);
+    #[cfg(debug_assertions)]
+    app.add_plugin(console::ConsolePlugin);
+    #[cfg(feature = "net")]
+    app.add_plugin(net::NetPlugin);
+    app.run();
 }
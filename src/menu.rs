@@ -0,0 +1,2543 @@
+use bevy::{prelude::*, window::WindowFocused};
+
+use rand::{seq::SliceRandom, RngCore};
+
+use crate::{
+    achievements::ACHIEVEMENTS,
+    affinity::{affinities, SET_PARTS},
+    ai::{AiController, AiRegistry},
+    ammo::Ammo,
+    attachment::{self, Attachment},
+    balance::BalanceConfig,
+    body::{
+        Arm, Body, Limb, PaletteMode, PartKind, Stats, WeightClass, LOW_HEALTH, MAX_NAME_LENGTH,
+    },
+    booster::{ArmBooster, Booster, Boosters},
+    bounds::{bounded_stats, describe_capped},
+    campaign::{self, CampaignFile, CampaignHandle, CampaignRun, Dialogue, NodeStatus},
+    confirm::ConfirmDialog,
+    daily::{self, date_label, DailyRun, Stream},
+    energy,
+    error::{report, ErrorToast, GameError},
+    history::{self, RunRecord},
+    locale::{language_name, t, Locale, LANGUAGES},
+    mutators::{Mutator, Mutators},
+    profile::{Profile, Settings, UI_SCALES},
+    rivals,
+    snapshot::ExportBody,
+    survival::SurvivalRun,
+    swap::SwapBody,
+    text_input::{self, spawn_text_input, TextInput, TextInputSubmitted},
+    theme::{Theme, ThemeKind},
+    timer::TIMER_OPTIONS,
+    training,
+    turn::{BattleMode, Ruleset},
+    tutorial,
+    ui::Fonts,
+    undo::{EditHistory, EditorAction},
+    AppState, BattleSummary, Game, GameRng,
+};
+
+#[derive(Component)]
+struct MenuRoot;
+
+#[derive(Component, Clone, Copy, PartialEq)]
+enum MenuButton {
+    Mode(BattleMode),
+    Timer,
+    Fight,
+    PlayAgain,
+    CopyBuild,
+    // Saves a picture of the player's body.
+    ExportImage,
+    PasteBuild,
+    EquipBuild,
+    ResetProfile,
+    Achievements,
+    Back,
+    Survival,
+    Ai,
+    SwapPart,
+    NextWave,
+    Statistics,
+    RunHistory,
+    Interrupts,
+    Poise,
+    AutoFace,
+    Weather,
+    Pickups,
+    Mutator(Mutator),
+    Booster(Booster),
+    // Buys one.
+    Attachment(Attachment),
+    // Puts the next free attachment on the arm in the slot.
+    ArmAttachment(u8),
+    // Cycles the arm a Modular Chassis carries into battle, see `limb_swap`.
+    SpareArm,
+    Settings,
+    UiScale,
+    Palette,
+    Theme,
+    Language,
+    Tutorial,
+    Training,
+    // Picks a new body out of a rolled lineup.
+    Starter,
+    Narration,
+    Tips,
+    LowSpec,
+    Rivals,
+    SaveRival,
+    Rematch(usize),
+    DeleteRival(usize),
+    Undo,
+    Redo,
+    Daily,
+    DailyPractice,
+    DailyHistory,
+    Campaign,
+    Encounter(usize),
+    // Swaps in the body finishing the campaign unlocked.
+    UseArchetype,
+}
+
+// Text of a button whose label shows a ruleset or settings value.
+#[derive(Component)]
+struct ButtonLabel(MenuButton);
+
+#[derive(Component)]
+struct IntermissionLabel;
+
+#[derive(Component)]
+struct NameInput;
+
+fn on_off(value: bool) -> String {
+    if value {
+        t!("common.on")
+    } else {
+        t!("common.off")
+    }
+}
+
+fn ruleset_label(button: MenuButton, ruleset: &Ruleset) -> Option<String> {
+    match button {
+        MenuButton::Timer => Some(match ruleset.timer {
+            Some(seconds) => t!("menu.timer", seconds = seconds),
+            None => t!("menu.timer_off"),
+        }),
+        MenuButton::Survival => Some(t!("menu.survival", value = on_off(ruleset.survival))),
+        MenuButton::Ai => Some(t!("menu.ai", name = ruleset.ai.clone())),
+        MenuButton::Interrupts => Some(t!("menu.interrupts", value = on_off(ruleset.interrupts))),
+        MenuButton::Poise => Some(t!("menu.poise", value = on_off(ruleset.poise))),
+        MenuButton::AutoFace => Some(t!("menu.auto_face", value = on_off(ruleset.auto_face))),
+        MenuButton::Weather => Some(t!("menu.weather", value = on_off(ruleset.weather))),
+        MenuButton::Pickups => Some(t!("menu.pickups", value = on_off(ruleset.pickups))),
+        MenuButton::Mutator(mutator) => Some(t!(
+            "menu.mutator",
+            mutator = mutator.name(),
+            value = on_off(ruleset.mutators.contains(mutator))
+        )),
+        _ => None,
+    }
+}
+
+#[derive(Component)]
+struct StatusLabel;
+
+#[derive(Default)]
+struct PastedBuild(Option<Body>);
+
+// Sent by the confirmation dialog.
+struct EquipBuild;
+struct ResetProfile;
+struct StartFight;
+struct RemoveRival(usize);
+
+fn setting_label(button: MenuButton, settings: &Settings) -> Option<String> {
+    match button {
+        MenuButton::UiScale => Some(t!("settings.ui_scale", percent = settings.ui_scale * 100.0)),
+        MenuButton::Palette => Some(t!("settings.palette", palette = settings.palette.name())),
+        MenuButton::Theme => Some(t!("settings.theme", theme = settings.theme.name())),
+        MenuButton::Language => Some(t!(
+            "settings.language",
+            language = language_name(&settings.language)
+        )),
+        MenuButton::Narration => Some(t!("settings.narration", value = on_off(settings.narration))),
+        MenuButton::Tips => Some(t!("settings.tips", value = on_off(settings.tips))),
+        MenuButton::LowSpec => Some(t!("settings.low_spec", value = on_off(settings.low_spec))),
+        _ => None,
+    }
+}
+
+fn booster_label(booster: Booster, boosters: &Boosters, profile: &Profile) -> String {
+    t!(
+        "menu.booster",
+        booster = booster.name(),
+        stock = profile.boosters.get(&booster).copied().unwrap_or(0),
+        value = on_off(boosters.is_armed(booster))
+    )
+}
+
+fn spawn_booster_row(
+    parent: &mut ChildBuilder,
+    fonts: &Fonts,
+    theme: &Theme,
+    boosters: &Boosters,
+    profile: &Profile,
+) {
+    parent
+        .spawn_bundle(NodeBundle {
+            color: Color::NONE.into(),
+            ..default()
+        })
+        .with_children(|parent| {
+            for booster in Booster::ALL {
+                let label = booster_label(booster, boosters, profile);
+                spawn_button(
+                    parent,
+                    fonts,
+                    theme,
+                    MenuButton::Booster(booster),
+                    &label,
+                    theme.normal_button,
+                );
+            }
+        });
+}
+
+fn attachment_label(attachment: Attachment, profile: &Profile, body: &Body) -> String {
+    t!(
+        "menu.attachment",
+        attachment = attachment.name(),
+        stock = attachment::free(&profile.attachments, body, attachment),
+        price = attachment.price()
+    )
+}
+
+fn arm_attachment_label(slot: u8, body: &Body) -> String {
+    let attachment = match body.arm(slot) {
+        Some(arm) => arm
+            .meta()
+            .attachment()
+            .map_or_else(|| t!("attachment.none"), Attachment::name),
+        None => t!("attachment.no_arm"),
+    };
+    t!(
+        "menu.arm_attachment",
+        arm = Limb::Arm(slot).badge(),
+        attachment = attachment
+    )
+}
+
+fn spare_arm_label(profile: &Profile) -> String {
+    let arm = profile
+        .spare_arm
+        .as_ref()
+        .map_or_else(|| t!("attachment.none"), |arm| arm.name().to_string());
+    t!("menu.spare_arm", arm = arm)
+}
+
+// The spare is a copy of one of the body's arms, clicking goes through them
+// in order and back to none.
+fn next_spare(spare: Option<&Arm>, body: &Body) -> Option<Arm> {
+    let Some(spare) = spare else {
+        return body.arms().first().cloned();
+    };
+    let index = body
+        .arms()
+        .iter()
+        .position(|arm| arm.name() == spare.name())?;
+    body.arms().get(index + 1).cloned()
+}
+
+// The shop, then a button for each of the torso's arm slots and the spare
+// for a Modular Chassis.
+fn spawn_attachment_rows(
+    parent: &mut ChildBuilder,
+    fonts: &Fonts,
+    theme: &Theme,
+    profile: &Profile,
+    body: &Body,
+) {
+    let shop = Attachment::ALL.map(|attachment| {
+        (
+            MenuButton::Attachment(attachment),
+            attachment_label(attachment, profile, body),
+        )
+    });
+    let mut arms = (0..body.torso().meta().arm_slots() as u8)
+        .map(|slot| {
+            (
+                MenuButton::ArmAttachment(slot),
+                arm_attachment_label(slot, body),
+            )
+        })
+        .collect::<Vec<_>>();
+    if body.torso().meta().modular() {
+        arms.push((MenuButton::SpareArm, spare_arm_label(profile)));
+    }
+    for row in [&shop[..], &arms] {
+        parent
+            .spawn_bundle(NodeBundle {
+                color: Color::NONE.into(),
+                ..default()
+            })
+            .with_children(|parent| {
+                for (button, label) in row {
+                    spawn_button(parent, fonts, theme, *button, label, theme.normal_button);
+                }
+            });
+    }
+}
+
+fn record_label(profile: &Profile) -> String {
+    t!(
+        "menu.record",
+        wins = profile.wins,
+        losses = profile.losses,
+        draws = profile.draws
+    )
+}
+
+fn build_preview(stats: &Stats) -> String {
+    let mut preview = t!(
+        "menu.build_preview",
+        health = stats.max_health,
+        energy = stats.max_energy,
+        weight = stats.weight,
+        class = stats.class().name(),
+        speed = stats.speed,
+        skills = stats.skills.len()
+    );
+    for line in describe_capped(stats) {
+        preview += "\n";
+        preview += &line;
+    }
+    preview
+}
+
+const WARNING: Color = Color::rgb(0.95, 0.3, 0.25);
+
+// Shows what the player's body can afford, refreshed whenever its parts change.
+#[derive(Component)]
+struct EnergyPanel;
+
+// One line each, the ones to worry about flagged.
+fn energy_lines(balance: &BalanceConfig, mutators: Mutators, stats: &Stats) -> Vec<(String, bool)> {
+    let regen = energy::regen_per_second(balance, mutators, stats);
+    let mut lines = vec![(
+        t!("energy.summary", max = stats.max_energy, regen = regen),
+        false,
+    )];
+    for skill in &stats.skills {
+        let cost = energy::skill_cost(skill);
+        if cost <= 0.0 {
+            continue;
+        }
+        let usable = energy::usable(stats, skill);
+        let line = if usable {
+            t!(
+                "energy.skill",
+                skill = skill.get_name(),
+                cost = cost,
+                actions = energy::actions_per(10.0, balance, mutators, stats, skill)
+            )
+        } else {
+            t!("energy.unusable", skill = skill.get_name(), cost = cost)
+        };
+        lines.push((line, !usable));
+    }
+    if regen <= 0.0 {
+        lines.push((t!("energy.no_regen"), true));
+    }
+    lines
+}
+
+fn spawn_energy_panel(parent: &mut ChildBuilder) {
+    parent
+        .spawn_bundle(TextBundle::default().with_style(Style {
+            margin: UiRect::all(Val::Px(10.0)),
+            ..default()
+        }))
+        .insert(EnergyPanel);
+}
+
+// The class the panel last showed and whether the player went up into it.
+// An equip that moves the player up a class is flagged until the menu is
+// opened again, it changes who they'll be matched against.
+fn energy_panel_system(
+    game: Res<Game>,
+    fonts: Res<Fonts>,
+    balance: Res<BalanceConfig>,
+    ruleset: Res<Ruleset>,
+    mut shown: Local<Option<(WeightClass, bool)>>,
+    stats: Query<(&Body, &Stats, ChangeTrackers<Stats>)>,
+    mut panels: Query<(&mut Text, ChangeTrackers<EnergyPanel>)>,
+) {
+    let Ok((body, stats, stats_changes)) = stats.get(game.player) else {
+        return;
+    };
+    for (mut text, panel) in &mut panels {
+        if !(panel.is_added()
+            || stats_changes.is_changed()
+            || balance.is_changed()
+            || ruleset.is_changed())
+        {
+            continue;
+        }
+        let class = stats.class();
+        let moved_up = match *shown {
+            Some((last, _)) if last != class => class > last,
+            Some((_, moved_up)) => moved_up && !panel.is_added(),
+            None => false,
+        };
+        *shown = Some((class, moved_up));
+        let mut lines = vec![(
+            t!(
+                "weight_class.current",
+                weight = stats.weight,
+                class = class.name()
+            ),
+            false,
+        )];
+        if moved_up {
+            lines.push((t!("weight_class.moved_up", class = class.name()), true));
+        }
+        for part in body.rusting_parts() {
+            lines.push((
+                t!(
+                    "decay.rusts_next",
+                    part = part,
+                    percent = LOW_HEALTH * 100.0
+                ),
+                true,
+            ));
+        }
+        // Sets on and the ones a part away, "2/3 Steel parts".
+        lines.extend(
+            affinities(body)
+                .into_iter()
+                .filter(|affinity| affinity.tier().is_some() || affinity.parts + 1 >= SET_PARTS)
+                .map(|affinity| (affinity.describe(), false)),
+        );
+        lines.extend(energy_lines(&balance, ruleset.mutators, stats));
+        text.sections = lines
+            .into_iter()
+            .map(|(line, warning)| {
+                let color = if warning { WARNING } else { Color::WHITE };
+                TextSection::new(line + "\n", text_style(&fonts, 18.0, color))
+            })
+            .collect();
+    }
+}
+
+fn text_style(fonts: &Fonts, font_size: f32, color: Color) -> TextStyle {
+    TextStyle {
+        font: fonts.bold(),
+        font_size,
+        color,
+    }
+}
+
+fn spawn_screen(
+    commands: &mut Commands,
+    fonts: &Fonts,
+    title: &str,
+    background: Color,
+    children: impl FnOnce(&mut ChildBuilder),
+) {
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                position_type: PositionType::Absolute,
+                flex_direction: FlexDirection::ColumnReverse,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            color: background.into(),
+            ..default()
+        })
+        .insert(MenuRoot)
+        .with_children(|parent| {
+            parent.spawn_bundle(
+                TextBundle::from_section(title, text_style(fonts, 48.0, Color::WHITE)).with_style(
+                    Style {
+                        margin: UiRect::all(Val::Px(20.0)),
+                        ..default()
+                    },
+                ),
+            );
+            children(parent);
+        });
+}
+
+fn spawn_button(
+    parent: &mut ChildBuilder,
+    fonts: &Fonts,
+    theme: &Theme,
+    button: MenuButton,
+    label: &str,
+    color: Color,
+) {
+    parent
+        .spawn_bundle(ButtonBundle {
+            style: Style {
+                size: Size::new(Val::Px(250.0), Val::Px(60.0)),
+                margin: UiRect::all(Val::Px(5.0)),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            color: color.into(),
+            ..default()
+        })
+        .insert(button)
+        .with_children(|parent| {
+            let mut text = parent.spawn_bundle(TextBundle::from_section(
+                label,
+                text_style(fonts, 28.0, theme.button_text),
+            ));
+            text.insert(ButtonLabel(button));
+        });
+}
+
+fn spawn_menu_system(
+    theme: Res<Theme>,
+    mut commands: Commands,
+    fonts: Res<Fonts>,
+    ruleset: Res<Ruleset>,
+    boosters: Res<Boosters>,
+    profile: Res<Profile>,
+    game: Res<Game>,
+    bodies: Query<&Body>,
+) {
+    spawn_screen(
+        &mut commands,
+        &fonts,
+        &t!("menu.choose_ruleset"),
+        theme.backdrop(0.6),
+        |parent| {
+            if let Ok(body) = bodies.get(game.player) {
+                let input = TextInput::new(
+                    body.custom_name().unwrap_or_default(),
+                    body.generated_name(),
+                    MAX_NAME_LENGTH,
+                );
+                spawn_text_input(parent, &fonts, input).insert(NameInput);
+            }
+            spawn_energy_panel(parent);
+            for mode in [BattleMode::RealTime, BattleMode::TurnBased] {
+                let button = MenuButton::Mode(mode);
+                spawn_button(
+                    parent,
+                    &fonts,
+                    &theme,
+                    button,
+                    &mode.name(),
+                    button_color(&theme, button, &ruleset, Interaction::None),
+                );
+            }
+            for row in [
+                &[MenuButton::Timer, MenuButton::Survival, MenuButton::Ai][..],
+                &[
+                    MenuButton::Interrupts,
+                    MenuButton::Poise,
+                    MenuButton::AutoFace,
+                    MenuButton::Weather,
+                    MenuButton::Pickups,
+                ],
+                &Mutator::ALL.map(MenuButton::Mutator),
+            ] {
+                parent
+                    .spawn_bundle(NodeBundle {
+                        color: Color::NONE.into(),
+                        ..default()
+                    })
+                    .with_children(|parent| {
+                        for button in row {
+                            let label = ruleset_label(*button, &ruleset).unwrap_or_default();
+                            spawn_button(
+                                parent,
+                                &fonts,
+                                &theme,
+                                *button,
+                                &label,
+                                theme.normal_button,
+                            );
+                        }
+                    });
+            }
+            spawn_booster_row(parent, &fonts, &theme, &boosters, &profile);
+            if let Ok(body) = bodies.get(game.player) {
+                spawn_attachment_rows(parent, &fonts, &theme, &profile, body);
+            }
+            parent
+                .spawn_bundle(NodeBundle {
+                    color: Color::NONE.into(),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    for (button, label) in [
+                        (MenuButton::Daily, "menu.daily"),
+                        (MenuButton::DailyPractice, "menu.daily_practice"),
+                        (MenuButton::DailyHistory, "menu.daily_history"),
+                    ] {
+                        spawn_button(
+                            parent,
+                            &fonts,
+                            &theme,
+                            button,
+                            &t!(label),
+                            theme.normal_button,
+                        );
+                    }
+                });
+            for (button, label) in [
+                (MenuButton::Fight, "menu.fight"),
+                (MenuButton::Tutorial, "menu.tutorial"),
+                (MenuButton::Training, "menu.training"),
+                (MenuButton::Starter, "menu.starter"),
+                (MenuButton::Campaign, "menu.campaign"),
+                (MenuButton::Achievements, "menu.achievements"),
+                (MenuButton::Statistics, "menu.statistics"),
+                (MenuButton::RunHistory, "menu.run_history"),
+                (MenuButton::Rivals, "menu.rivals"),
+                (MenuButton::Settings, "menu.settings"),
+            ] {
+                spawn_button(
+                    parent,
+                    &fonts,
+                    &theme,
+                    button,
+                    &t!(label),
+                    theme.normal_button,
+                );
+            }
+            parent
+                .spawn_bundle(NodeBundle {
+                    style: Style {
+                        margin: UiRect::all(Val::Px(10.0)),
+                        ..default()
+                    },
+                    color: Color::NONE.into(),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    for (button, label) in [
+                        (MenuButton::Undo, "menu.undo"),
+                        (MenuButton::Redo, "menu.redo"),
+                        (MenuButton::CopyBuild, "menu.copy_build"),
+                        (MenuButton::ExportImage, "menu.export_image"),
+                        (MenuButton::PasteBuild, "menu.paste_build"),
+                        (MenuButton::EquipBuild, "menu.equip_build"),
+                        (MenuButton::ResetProfile, "menu.reset_profile"),
+                    ] {
+                        spawn_button(
+                            parent,
+                            &fonts,
+                            &theme,
+                            button,
+                            &t!(label),
+                            theme.normal_button,
+                        );
+                    }
+                });
+            parent
+                .spawn_bundle(TextBundle::from_section(
+                    record_label(&profile),
+                    text_style(&fonts, 20.0, Color::WHITE),
+                ))
+                .insert(StatusLabel);
+        },
+    );
+}
+
+fn spawn_pause_system(mut commands: Commands, fonts: Res<Fonts>, theme: Res<Theme>) {
+    spawn_screen(
+        &mut commands,
+        &fonts,
+        &t!("pause.title"),
+        theme.backdrop(0.4),
+        |parent| {
+            parent.spawn_bundle(TextBundle::from_section(
+                t!("pause.hint"),
+                text_style(&fonts, 24.0, Color::WHITE),
+            ));
+        },
+    );
+}
+
+fn spawn_game_over_system(
+    theme: Res<Theme>,
+    mut commands: Commands,
+    fonts: Res<Fonts>,
+    game: Res<Game>,
+    summary: Res<BattleSummary>,
+    survival: Option<Res<SurvivalRun>>,
+    daily: Option<Res<DailyRun>>,
+    profile: Res<Profile>,
+    bodies: Query<(&Body, &Stats, Option<&Ammo>)>,
+) {
+    let title = match summary.winner {
+        Some(winner) if winner == game.player => t!("game_over.win"),
+        Some(_) => t!("game_over.lose"),
+        None => t!("game_over.draw"),
+    };
+    spawn_screen(
+        &mut commands,
+        &fonts,
+        &title,
+        theme.backdrop(0.6),
+        |parent| {
+            if let Ok([(player, player_stats, ammo), (enemy, enemy_stats, _)]) =
+                bodies.get_many([game.player, game.enemy])
+            {
+                parent.spawn_bundle(TextBundle::from_section(
+                    t!(
+                        "game_over.matchup",
+                        player = player.name(),
+                        player_class = player_stats.class().name(),
+                        enemy = enemy.name(),
+                        enemy_class = enemy_stats.class().name()
+                    ),
+                    text_style(&fonts, 24.0, Color::WHITE),
+                ));
+                if let Some(ammo) = ammo.filter(|ammo| ammo.fired > 0) {
+                    parent.spawn_bundle(TextBundle::from_section(
+                        t!("game_over.shots", fired = ammo.fired, hit = ammo.hit),
+                        text_style(&fonts, 20.0, Color::WHITE),
+                    ));
+                }
+            }
+            if let Some(run) = &survival {
+                parent.spawn_bundle(TextBundle::from_section(
+                    t!(
+                        "game_over.reached_wave",
+                        wave = run.wave,
+                        best = profile.best_wave
+                    ),
+                    text_style(&fonts, 28.0, Color::WHITE),
+                ));
+                if let Some(record) = profile.history.last() {
+                    spawn_run_summary(parent, &fonts, record);
+                }
+            }
+            if let Some(run) = &daily {
+                let result = run.result();
+                let row = t!(
+                    "daily.row",
+                    date = date_label(result.day),
+                    rounds = result.rounds_cleared,
+                    seconds = result.time
+                );
+                let key = if run.practice {
+                    "daily.practice_result"
+                } else {
+                    "daily.result"
+                };
+                parent.spawn_bundle(TextBundle::from_section(
+                    t!(key, row = row),
+                    text_style(&fonts, 24.0, Color::GOLD),
+                ));
+            }
+            if summary.sudden_death {
+                parent.spawn_bundle(TextBundle::from_section(
+                    t!("game_over.sudden_death"),
+                    text_style(&fonts, 24.0, Color::RED),
+                ));
+            }
+            let name = |entity: Entity| {
+                bodies
+                    .get(entity)
+                    .map_or_else(|_| String::new(), |(body, _, _)| body.name().to_string())
+            };
+            for (loser, source) in &summary.finishing_blows {
+                let cause = source.describe(name, |entity, skill| {
+                    Some(bodies.get(entity).ok()?.1.skill(skill)?.get_name())
+                });
+                parent.spawn_bundle(TextBundle::from_section(
+                    t!("game_over.finished", loser = name(*loser), cause = cause),
+                    text_style(&fonts, 24.0, Color::GRAY),
+                ));
+            }
+            if !summary.boosters.is_empty() {
+                let names = summary
+                    .boosters
+                    .iter()
+                    .map(|booster| booster.name())
+                    .collect::<Vec<_>>();
+                parent.spawn_bundle(TextBundle::from_section(
+                    t!("game_over.boosters", boosters = names.join(", ")),
+                    text_style(&fonts, 24.0, Color::GRAY),
+                ));
+            }
+            if !summary.mutators.is_empty() {
+                let names = summary
+                    .mutators
+                    .iter()
+                    .map(Mutator::name)
+                    .collect::<Vec<_>>();
+                parent.spawn_bundle(TextBundle::from_section(
+                    t!("game_over.mutators", mutators = names.join(", ")),
+                    text_style(&fonts, 24.0, Color::GRAY),
+                ));
+            }
+            spawn_button(
+                parent,
+                &fonts,
+                &theme,
+                MenuButton::PlayAgain,
+                &t!("game_over.play_again"),
+                theme.normal_button,
+            );
+            spawn_button(
+                parent,
+                &fonts,
+                &theme,
+                MenuButton::SaveRival,
+                &t!("game_over.save_rival"),
+                theme.normal_button,
+            );
+            spawn_button(
+                parent,
+                &fonts,
+                &theme,
+                MenuButton::ExportImage,
+                &t!("menu.export_image"),
+                theme.normal_button,
+            );
+            parent
+                .spawn_bundle(TextBundle::from_section(
+                    "",
+                    text_style(&fonts, 20.0, Color::WHITE),
+                ))
+                .insert(StatusLabel);
+            if survival.is_some() {
+                spawn_button(
+                    parent,
+                    &fonts,
+                    &theme,
+                    MenuButton::RunHistory,
+                    &t!("menu.run_history"),
+                    theme.normal_button,
+                );
+            }
+        },
+    );
+}
+
+fn spawn_intermission_system(
+    theme: Res<Theme>,
+    mut commands: Commands,
+    fonts: Res<Fonts>,
+    boosters: Res<Boosters>,
+    profile: Res<Profile>,
+) {
+    spawn_screen(
+        &mut commands,
+        &fonts,
+        &t!("intermission.title"),
+        theme.backdrop(0.6),
+        |parent| {
+            parent
+                .spawn_bundle(TextBundle::from_section(
+                    "",
+                    text_style(&fonts, 28.0, Color::WHITE),
+                ))
+                .insert(IntermissionLabel);
+            spawn_energy_panel(parent);
+            spawn_button(
+                parent,
+                &fonts,
+                &theme,
+                MenuButton::SwapPart,
+                &t!("intermission.swap"),
+                theme.normal_button,
+            );
+            spawn_booster_row(parent, &fonts, &theme, &boosters, &profile);
+            spawn_button(
+                parent,
+                &fonts,
+                &theme,
+                MenuButton::NextWave,
+                &t!("intermission.next_wave"),
+                theme.normal_button,
+            );
+            parent
+                .spawn_bundle(TextBundle::from_section(
+                    "",
+                    text_style(&fonts, 20.0, Color::WHITE),
+                ))
+                .insert(StatusLabel);
+        },
+    );
+}
+
+fn intermission_label_system(
+    run: Res<SurvivalRun>,
+    mut labels: Query<&mut Text, With<IntermissionLabel>>,
+) {
+    for mut text in &mut labels {
+        text.sections[0].value = t!(
+            "intermission.countdown",
+            wave = run.wave,
+            seconds = run.intermission.ceil()
+        );
+    }
+}
+
+// Survival has no inventory yet, so a swap replaces one random part with a
+// freshly generated one. The old part is salvaged for scrap.
+fn swap_part_system(
+    buttons: Query<(&Interaction, &MenuButton), Changed<Interaction>>,
+    game: Res<Game>,
+    mut run: ResMut<SurvivalRun>,
+    mut profile: ResMut<Profile>,
+    mut rng: ResMut<GameRng>,
+    daily: Option<Res<DailyRun>>,
+    mut bodies: Query<&mut Body>,
+    mut labels: Query<&mut Text, With<StatusLabel>>,
+) {
+    for (interaction, button) in &buttons {
+        if *interaction != Interaction::Clicked || *button != MenuButton::SwapPart {
+            continue;
+        }
+        let message = if run.swapped {
+            t!("intermission.one_swap")
+        } else if let Ok(mut body) = bodies.get_mut(game.player) {
+            // A daily challenge offers everyone the same part on a wave.
+            let mut seeded = daily
+                .as_ref()
+                .map(|daily| daily.rng(Stream::Offer, run.wave));
+            let mut rng: &mut dyn RngCore = match &mut seeded {
+                Some(seeded) => seeded,
+                None => &mut **rng,
+            };
+            let kind = *PartKind::ALL.choose(&mut rng).unwrap();
+            run.swapped = true;
+            let before = bounded_stats(&body).class();
+            let (part, salvage) = body.reroll_part(kind, &mut rng);
+            profile.history.record_swap(run.wave, &part);
+            let mut message = t!("intermission.equipped", part = part);
+            if salvage > 0 {
+                profile.scrap += salvage;
+                message += "\n";
+                message += &t!("intermission.salvaged", scrap = salvage);
+            }
+            let after = bounded_stats(&body).class();
+            if after > before {
+                message += "\n";
+                message += &t!("weight_class.moved_up", class = after.name());
+            }
+            message
+        } else {
+            continue;
+        };
+        for mut text in &mut labels {
+            text.sections[0].value = message.clone();
+        }
+    }
+}
+
+fn spawn_statistics_system(
+    theme: Res<Theme>,
+    mut commands: Commands,
+    fonts: Res<Fonts>,
+    profile: Res<Profile>,
+    game: Res<Game>,
+    stats: Query<&Stats>,
+) {
+    let mut rows = Vec::new();
+    if let Ok(stats) = stats.get(game.player) {
+        for skill in &stats.skills {
+            let id = skill.id();
+            let usage = profile.skill_stats.get(&id).cloned().unwrap_or_default();
+            if !rows.iter().any(|(row_id, _, _)| *row_id == id) {
+                rows.push((id, skill.get_name(), usage));
+            }
+        }
+    }
+    let total_damage = rows.iter().map(|(_, _, usage)| usage.damage).sum::<f32>();
+    let archived = profile
+        .skill_stats
+        .keys()
+        .filter(|id| !rows.iter().any(|(row_id, _, _)| row_id == *id))
+        .count();
+    let fmt = |value: Option<f32>, f: fn(f32) -> String| value.map_or("-".to_string(), f);
+
+    spawn_screen(
+        &mut commands,
+        &fonts,
+        &t!("statistics.title"),
+        theme.backdrop(0.8),
+        |parent| {
+            for (_, name, usage) in &rows {
+                let line = t!(
+                    "statistics.row",
+                    name = name,
+                    uses = usage.uses,
+                    hits = usage.hits,
+                    damage = usage.damage,
+                    average = fmt(usage.average_damage(), |v| format!("{:.1}", v)),
+                    whiff = fmt(usage.whiff_rate(), |v| format!("{:.0}%", v * 100.0)),
+                );
+                parent.spawn_bundle(TextBundle::from_section(
+                    line,
+                    text_style(&fonts, 22.0, Color::WHITE),
+                ));
+                let share = if total_damage > 0.0 {
+                    usage.damage / total_damage
+                } else {
+                    0.0
+                };
+                parent
+                    .spawn_bundle(NodeBundle {
+                        style: Style {
+                            size: Size::new(Val::Px(400.0), Val::Px(10.0)),
+                            margin: UiRect::new(
+                                Val::Px(0.0),
+                                Val::Px(0.0),
+                                Val::Px(2.0),
+                                Val::Px(10.0),
+                            ),
+                            ..default()
+                        },
+                        color: Color::rgb(0.2, 0.2, 0.2).into(),
+                        ..default()
+                    })
+                    .with_children(|parent| {
+                        parent.spawn_bundle(NodeBundle {
+                            style: Style {
+                                size: Size::new(Val::Percent(share * 100.0), Val::Percent(100.0)),
+                                ..default()
+                            },
+                            color: Color::ORANGE.into(),
+                            ..default()
+                        });
+                    });
+            }
+            if total_damage == 0.0 {
+                parent.spawn_bundle(TextBundle::from_section(
+                    t!("statistics.empty"),
+                    text_style(&fonts, 22.0, Color::GRAY),
+                ));
+            }
+            if archived > 0 {
+                parent.spawn_bundle(TextBundle::from_section(
+                    t!("statistics.archived", count = archived),
+                    text_style(&fonts, 18.0, Color::GRAY),
+                ));
+            }
+            spawn_button(
+                parent,
+                &fonts,
+                &theme,
+                MenuButton::Back,
+                &t!("common.back"),
+                theme.normal_button,
+            );
+        },
+    );
+}
+
+// Label and value of every number runs are compared by.
+fn run_numbers(run: &RunRecord) -> [(String, String); 5] {
+    [
+        (t!("runs.rounds"), run.rounds_cleared.to_string()),
+        (t!("runs.damage_dealt"), format!("{:.0}", run.damage_dealt)),
+        (t!("runs.damage_taken"), format!("{:.0}", run.damage_taken)),
+        (t!("runs.scrap_earned"), run.scrap_earned.to_string()),
+        (t!("runs.scrap_spent"), run.scrap_spent.to_string()),
+    ]
+}
+
+fn spawn_run_summary(parent: &mut ChildBuilder, fonts: &Fonts, run: &RunRecord) {
+    let mut lines = run_numbers(run)
+        .into_iter()
+        .map(|(label, value)| (t!("runs.row", label = label, value = value), Color::WHITE))
+        .collect::<Vec<_>>();
+    let swaps = run
+        .swaps
+        .iter()
+        .map(|swap| t!("runs.swap", round = swap.round, part = &swap.part))
+        .collect::<Vec<_>>();
+    lines.push(if swaps.is_empty() {
+        (t!("runs.no_swaps"), Color::GRAY)
+    } else {
+        (t!("runs.swaps", swaps = swaps.join(", ")), Color::WHITE)
+    });
+    let materials = run
+        .material_shares()
+        .into_iter()
+        .map(|(material, share)| {
+            t!(
+                "runs.material_share",
+                material = material.name(),
+                percent = share * 100.0
+            )
+        })
+        .collect::<Vec<_>>();
+    if !materials.is_empty() {
+        lines.push((
+            t!("runs.materials", materials = materials.join(", ")),
+            Color::WHITE,
+        ));
+    }
+    if run.partial {
+        lines.push((t!("runs.partial"), Color::GRAY));
+    }
+    // Long runs swap a lot of parts, the lines wrap instead of running off
+    // the screen.
+    for (line, color) in lines {
+        parent.spawn_bundle(
+            TextBundle::from_section(line, text_style(fonts, 20.0, color)).with_style(Style {
+                max_size: Size::new(Val::Px(900.0), Val::Undefined),
+                ..default()
+            }),
+        );
+    }
+}
+
+fn spawn_run_history_system(
+    mut commands: Commands,
+    fonts: Res<Fonts>,
+    profile: Res<Profile>,
+    theme: Res<Theme>,
+) {
+    let history = &profile.history;
+    let heading = |parent: &mut ChildBuilder, text: String| {
+        parent.spawn_bundle(
+            TextBundle::from_section(text, text_style(&fonts, 28.0, Color::WHITE)).with_style(
+                Style {
+                    margin: UiRect::new(Val::Px(0.0), Val::Px(0.0), Val::Px(12.0), Val::Px(4.0)),
+                    ..default()
+                },
+            ),
+        );
+    };
+    spawn_screen(
+        &mut commands,
+        &fonts,
+        &t!("runs.title"),
+        theme.backdrop(0.8),
+        |parent| {
+            match (history.last(), history.best()) {
+                (Some(last), Some(best)) => {
+                    heading(parent, t!("runs.last", count = history.runs().len()));
+                    spawn_run_summary(parent, &fonts, last);
+                    heading(parent, t!("runs.compare"));
+                    if std::ptr::eq(last, best) {
+                        parent.spawn_bundle(TextBundle::from_section(
+                            t!("runs.last_is_best"),
+                            text_style(&fonts, 20.0, Color::GOLD),
+                        ));
+                    } else {
+                        for ((label, last), (_, best)) in
+                            run_numbers(last).into_iter().zip(run_numbers(best))
+                        {
+                            parent.spawn_bundle(TextBundle::from_section(
+                                t!("runs.compare_row", label = label, last = last, best = best),
+                                text_style(&fonts, 20.0, Color::WHITE),
+                            ));
+                        }
+                    }
+                }
+                _ => {
+                    parent.spawn_bundle(TextBundle::from_section(
+                        t!("runs.empty"),
+                        text_style(&fonts, 22.0, Color::GRAY),
+                    ));
+                }
+            }
+            heading(
+                parent,
+                t!(
+                    "runs.streak",
+                    streak = history.streak,
+                    best = history.best_streak
+                ),
+            );
+            spawn_button(
+                parent,
+                &fonts,
+                &theme,
+                MenuButton::Back,
+                &t!("common.back"),
+                theme.normal_button,
+            );
+        },
+    );
+}
+
+fn spawn_daily_history_system(
+    mut commands: Commands,
+    fonts: Res<Fonts>,
+    profile: Res<Profile>,
+    theme: Res<Theme>,
+) {
+    let history = &profile.daily;
+    let best = history.best();
+    spawn_screen(
+        &mut commands,
+        &fonts,
+        &t!("daily.title"),
+        theme.backdrop(0.8),
+        |parent| {
+            let today = daily::today().ok();
+            if let Some(today) = today {
+                let key = if history.played(today) {
+                    "daily.today_played"
+                } else {
+                    "daily.today_open"
+                };
+                parent.spawn_bundle(TextBundle::from_section(
+                    t!(key, date = date_label(today)),
+                    text_style(&fonts, 24.0, Color::WHITE),
+                ));
+            }
+            // Newest first, the best day stands out.
+            for result in history.results().iter().rev() {
+                let mut line = t!(
+                    "daily.row",
+                    date = date_label(result.day),
+                    rounds = result.rounds_cleared,
+                    seconds = result.time
+                );
+                if !result.finished && Some(result.day) != today {
+                    line += " ";
+                    line += &t!("daily.left");
+                }
+                let color = if best.is_some_and(|best| std::ptr::eq(best, result)) {
+                    Color::GOLD
+                } else {
+                    Color::WHITE
+                };
+                parent.spawn_bundle(TextBundle::from_section(
+                    line,
+                    text_style(&fonts, 20.0, color),
+                ));
+            }
+            if history.results().is_empty() {
+                parent.spawn_bundle(TextBundle::from_section(
+                    t!("daily.empty"),
+                    text_style(&fonts, 22.0, Color::GRAY),
+                ));
+            }
+            spawn_button(
+                parent,
+                &fonts,
+                &theme,
+                MenuButton::Back,
+                &t!("common.back"),
+                theme.normal_button,
+            );
+        },
+    );
+}
+
+fn spawn_rivals_system(
+    mut commands: Commands,
+    fonts: Res<Fonts>,
+    profile: Res<Profile>,
+    registry: Res<AiRegistry>,
+    theme: Res<Theme>,
+) {
+    spawn_screen(
+        &mut commands,
+        &fonts,
+        &t!("rivals.title"),
+        theme.backdrop(0.8),
+        |parent| {
+            for (index, rival) in profile.rivals.list().iter().enumerate() {
+                let record = t!(
+                    "rivals.record",
+                    wins = rival.wins,
+                    losses = rival.losses,
+                    draws = rival.draws
+                );
+                let (line, color) = match rival.body(&registry) {
+                    Ok(body) => (
+                        t!(
+                            "rivals.row",
+                            name = &rival.name,
+                            class = bounded_stats(&body).class().name(),
+                            ai = &rival.ai,
+                            record = record
+                        ),
+                        Color::WHITE,
+                    ),
+                    Err(reason) => (
+                        t!(
+                            "rivals.incompatible",
+                            name = &rival.name,
+                            reason = reason,
+                            record = record
+                        ),
+                        Color::GRAY,
+                    ),
+                };
+                parent
+                    .spawn_bundle(NodeBundle {
+                        style: Style {
+                            align_items: AlignItems::Center,
+                            ..default()
+                        },
+                        color: Color::NONE.into(),
+                        ..default()
+                    })
+                    .with_children(|parent| {
+                        parent.spawn_bundle(
+                            TextBundle::from_section(line, text_style(&fonts, 20.0, color))
+                                .with_style(Style {
+                                    max_size: Size::new(Val::Px(600.0), Val::Undefined),
+                                    margin: UiRect::all(Val::Px(5.0)),
+                                    ..default()
+                                }),
+                        );
+                        for (button, label) in [
+                            (MenuButton::Rematch(index), "rivals.rematch"),
+                            (MenuButton::DeleteRival(index), "rivals.delete"),
+                        ] {
+                            spawn_button(
+                                parent,
+                                &fonts,
+                                &theme,
+                                button,
+                                &t!(label),
+                                theme.normal_button,
+                            );
+                        }
+                    });
+            }
+            if profile.rivals.list().is_empty() {
+                parent.spawn_bundle(TextBundle::from_section(
+                    t!("rivals.empty"),
+                    text_style(&fonts, 22.0, Color::GRAY),
+                ));
+            }
+            parent
+                .spawn_bundle(TextBundle::from_section(
+                    "",
+                    text_style(&fonts, 20.0, Color::WHITE),
+                ))
+                .insert(StatusLabel);
+            spawn_button(
+                parent,
+                &fonts,
+                &theme,
+                MenuButton::Back,
+                &t!("common.back"),
+                theme.normal_button,
+            );
+        },
+    );
+}
+
+fn spawn_campaign_system(
+    mut commands: Commands,
+    fonts: Res<Fonts>,
+    profile: Res<Profile>,
+    theme: Res<Theme>,
+    handle: Res<CampaignHandle>,
+    files: Res<Assets<CampaignFile>>,
+) {
+    let campaign = files.get(&handle.0).map(|file| &file.0);
+    let title = match campaign {
+        Some(Ok(campaign)) => t!(format!("campaign.{}.name", campaign.id)),
+        _ => t!("menu.campaign"),
+    };
+    spawn_screen(
+        &mut commands,
+        &fonts,
+        &title,
+        theme.backdrop(0.8),
+        |parent| {
+            match campaign {
+                Some(Ok(campaign)) => {
+                    for (index, encounter) in campaign.encounters.iter().enumerate() {
+                        let status = campaign.status(&profile.campaign, index);
+                        let arena = encounter.arena.name();
+                        let mutators = encounter.mutators.iter().map(|mutator| mutator.name());
+                        let rules = [arena].into_iter().chain(mutators).collect::<Vec<_>>();
+                        let (line, color) = match status {
+                            NodeStatus::Locked => {
+                                (t!("campaign.locked_row", number = index + 1), Color::GRAY)
+                            }
+                            _ => (
+                                t!(
+                                    "campaign.row",
+                                    number = index + 1,
+                                    title = encounter.title(),
+                                    rules = rules.join(", "),
+                                    reward = encounter.reward.describe()
+                                ),
+                                if status == NodeStatus::Cleared {
+                                    Color::GREEN
+                                } else {
+                                    Color::WHITE
+                                },
+                            ),
+                        };
+                        parent
+                            .spawn_bundle(NodeBundle {
+                                style: Style {
+                                    align_items: AlignItems::Center,
+                                    ..default()
+                                },
+                                color: Color::NONE.into(),
+                                ..default()
+                            })
+                            .with_children(|parent| {
+                                parent.spawn_bundle(
+                                    TextBundle::from_section(line, text_style(&fonts, 20.0, color))
+                                        .with_style(Style {
+                                            max_size: Size::new(Val::Px(600.0), Val::Undefined),
+                                            margin: UiRect::all(Val::Px(5.0)),
+                                            ..default()
+                                        }),
+                                );
+                                let label = match status {
+                                    NodeStatus::Cleared => "campaign.replay",
+                                    NodeStatus::Open => "campaign.fight",
+                                    NodeStatus::Locked => return,
+                                };
+                                spawn_button(
+                                    parent,
+                                    &fonts,
+                                    &theme,
+                                    MenuButton::Encounter(index),
+                                    &t!(label),
+                                    theme.normal_button,
+                                );
+                            });
+                    }
+                    if campaign.completed(&profile.campaign) {
+                        spawn_button(
+                            parent,
+                            &fonts,
+                            &theme,
+                            MenuButton::UseArchetype,
+                            &t!("campaign.use_archetype", name = campaign.archetype.name()),
+                            theme.normal_button,
+                        );
+                    }
+                }
+                Some(Err(err)) => {
+                    parent.spawn_bundle(
+                        TextBundle::from_section(
+                            t!("campaign.unavailable", error = err.to_string()),
+                            text_style(&fonts, 20.0, Color::GRAY),
+                        )
+                        .with_style(Style {
+                            max_size: Size::new(Val::Px(600.0), Val::Undefined),
+                            ..default()
+                        }),
+                    );
+                }
+                None => {
+                    parent.spawn_bundle(TextBundle::from_section(
+                        t!("campaign.loading"),
+                        text_style(&fonts, 22.0, Color::GRAY),
+                    ));
+                }
+            }
+            parent
+                .spawn_bundle(TextBundle::from_section(
+                    "",
+                    text_style(&fonts, 20.0, Color::WHITE),
+                ))
+                .insert(StatusLabel);
+            spawn_button(
+                parent,
+                &fonts,
+                &theme,
+                MenuButton::Back,
+                &t!("common.back"),
+                theme.normal_button,
+            );
+        },
+    );
+}
+
+// Opened before the campaign finished loading, the screen is built again
+// once it has.
+fn campaign_loaded_system(
+    mut events: EventReader<AssetEvent<CampaignFile>>,
+    mut state: ResMut<State<AppState>>,
+) {
+    if events
+        .iter()
+        .any(|event| matches!(event, AssetEvent::Created { .. }))
+    {
+        let _ = state.restart();
+    }
+}
+
+fn campaign_button_system(
+    mut commands: Commands,
+    buttons: Query<(&Interaction, &MenuButton), Changed<Interaction>>,
+    game: Res<Game>,
+    handle: Res<CampaignHandle>,
+    files: Res<Assets<CampaignFile>>,
+    mut profile: ResMut<Profile>,
+    mut ruleset: ResMut<Ruleset>,
+    mut state: ResMut<State<AppState>>,
+    mut swaps: EventWriter<SwapBody>,
+    mut errors: EventWriter<ErrorToast>,
+    bodies: Query<&Body>,
+    mut labels: Query<&mut Text, With<StatusLabel>>,
+    dialogue: Option<Res<Dialogue>>,
+    run: Option<Res<CampaignRun>>,
+) {
+    // An encounter's already under way, a second click would start another
+    // on top of it.
+    if dialogue.is_some() || run.is_some() {
+        return;
+    }
+    let Some(CampaignFile(Ok(campaign))) = files.get(&handle.0) else {
+        return;
+    };
+    for (interaction, button) in &buttons {
+        if *interaction != Interaction::Clicked {
+            continue;
+        }
+        match *button {
+            MenuButton::Encounter(index) => {
+                if campaign.status(&profile.campaign, index) == NodeStatus::Locked {
+                    continue;
+                }
+                let Ok([player, enemy]) = bodies.get_many([game.player, game.enemy]) else {
+                    continue;
+                };
+                let encounter = &campaign.encounters[index];
+                let opened = campaign::open_dialogue(
+                    &mut commands,
+                    &encounter.before,
+                    [player, &encounter.enemy],
+                    Some(AppState::Battle),
+                );
+                campaign::begin(
+                    &mut commands,
+                    &mut ruleset,
+                    &mut swaps,
+                    &game,
+                    enemy.clone(),
+                    (index, encounter),
+                );
+                if !opened {
+                    let _ = state.set(AppState::Battle);
+                }
+            }
+            MenuButton::UseArchetype => {
+                campaign::pilot_archetype(campaign, &game, &mut profile, &mut swaps, &mut errors);
+                let message = t!("campaign.piloting", name = campaign.archetype.name());
+                for mut text in &mut labels {
+                    text.sections[0].value = message.clone();
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+// Saves whoever the player just fought, with the controller it fought with.
+fn save_rival_system(
+    buttons: Query<(&Interaction, &MenuButton), Changed<Interaction>>,
+    game: Res<Game>,
+    ruleset: Res<Ruleset>,
+    mut profile: ResMut<Profile>,
+    enemies: Query<(&Body, Option<&AiController>)>,
+    mut labels: Query<&mut Text, With<StatusLabel>>,
+    mut errors: EventWriter<ErrorToast>,
+) {
+    for (interaction, button) in &buttons {
+        if *interaction != Interaction::Clicked || *button != MenuButton::SaveRival {
+            continue;
+        }
+        let Ok((body, controller)) = enemies.get(game.enemy) else {
+            continue;
+        };
+        let ai = controller.map_or(ruleset.ai.as_str(), AiController::name);
+        let message = match profile.rivals.add(body, ai) {
+            Ok(()) => {
+                report(&mut errors, profile.save());
+                t!("rivals.saved", name = body.name())
+            }
+            Err(err) => err.to_string(),
+        };
+        for mut text in &mut labels {
+            text.sections[0].value = message.clone();
+        }
+    }
+}
+
+fn daily_button_system(
+    mut commands: Commands,
+    buttons: Query<(&Interaction, &MenuButton), Changed<Interaction>>,
+    game: Res<Game>,
+    mut profile: ResMut<Profile>,
+    mut ruleset: ResMut<Ruleset>,
+    mut state: ResMut<State<AppState>>,
+    mut swaps: EventWriter<SwapBody>,
+    bodies: Query<&Body>,
+    mut labels: Query<&mut Text, With<StatusLabel>>,
+) {
+    for (interaction, button) in &buttons {
+        let practice = match (interaction, *button) {
+            (Interaction::Clicked, MenuButton::Daily) => false,
+            (Interaction::Clicked, MenuButton::DailyPractice) => true,
+            _ => continue,
+        };
+        let Ok(body) = bodies.get(game.player) else {
+            continue;
+        };
+        let begun = daily::begin(
+            &mut commands,
+            &mut ruleset,
+            &mut profile,
+            &mut swaps,
+            &game,
+            body.clone(),
+            practice,
+        );
+        match begun {
+            Ok(()) => {
+                let _ = state.set(AppState::Starter);
+            }
+            Err(err) => {
+                for mut text in &mut labels {
+                    text.sections[0].value = err.to_string();
+                }
+            }
+        }
+    }
+}
+
+fn rivals_button_system(
+    mut commands: Commands,
+    buttons: Query<(&Interaction, &MenuButton), Changed<Interaction>>,
+    game: Res<Game>,
+    profile: Res<Profile>,
+    registry: Res<AiRegistry>,
+    mut ruleset: ResMut<Ruleset>,
+    mut dialog: ResMut<ConfirmDialog>,
+    mut state: ResMut<State<AppState>>,
+    mut swaps: EventWriter<SwapBody>,
+    bodies: Query<&Body>,
+    mut labels: Query<&mut Text, With<StatusLabel>>,
+) {
+    for (interaction, button) in &buttons {
+        if *interaction != Interaction::Clicked {
+            continue;
+        }
+        let message = match *button {
+            MenuButton::Rematch(index) => {
+                let (Some(rival), Ok(enemy)) =
+                    (profile.rivals.list().get(index), bodies.get(game.enemy))
+                else {
+                    continue;
+                };
+                match rival.body(&registry) {
+                    Ok(body) => {
+                        rivals::begin(
+                            &mut commands,
+                            &mut ruleset,
+                            &mut swaps,
+                            &game,
+                            enemy.clone(),
+                            index,
+                            (body, &rival.ai),
+                        );
+                        let _ = state.set(AppState::Battle);
+                        continue;
+                    }
+                    Err(reason) => t!("rivals.cant_rematch", name = &rival.name, reason = reason),
+                }
+            }
+            MenuButton::DeleteRival(index) => {
+                if let Some(rival) = profile.rivals.list().get(index) {
+                    dialog.open(
+                        t!("confirm.delete_rival", name = &rival.name),
+                        RemoveRival(index),
+                    );
+                }
+                continue;
+            }
+            _ => continue,
+        };
+        for mut text in &mut labels {
+            text.sections[0].value = message.clone();
+        }
+    }
+}
+
+// The screen is built again without it.
+fn remove_rival_system(
+    mut removed: EventReader<RemoveRival>,
+    mut profile: ResMut<Profile>,
+    mut state: ResMut<State<AppState>>,
+    mut errors: EventWriter<ErrorToast>,
+) {
+    let mut changed = false;
+    for RemoveRival(index) in removed.iter() {
+        changed |= profile.rivals.remove(*index).is_some();
+    }
+    if changed {
+        report(&mut errors, profile.save());
+        let _ = state.restart();
+    }
+}
+
+fn spawn_achievements_system(
+    mut commands: Commands,
+    fonts: Res<Fonts>,
+    profile: Res<Profile>,
+    theme: Res<Theme>,
+) {
+    spawn_screen(
+        &mut commands,
+        &fonts,
+        &t!("achievements.title"),
+        theme.backdrop(0.8),
+        |parent| {
+            for achievement in ACHIEVEMENTS {
+                let unlocked = profile.achievements.contains(achievement.id);
+                let mut line = format!(
+                    "[{}] {} - {}",
+                    if unlocked { "x" } else { " " },
+                    achievement.name(),
+                    achievement.description()
+                );
+                if let (false, Some(progress)) = (unlocked, achievement.progress) {
+                    let (current, goal) = progress(&profile);
+                    line.push_str(&format!(" ({}/{})", current, goal));
+                }
+                let color = if unlocked { Color::GOLD } else { Color::GRAY };
+                parent.spawn_bundle(
+                    TextBundle::from_section(line, text_style(&fonts, 24.0, color)).with_style(
+                        Style {
+                            margin: UiRect::all(Val::Px(4.0)),
+                            ..default()
+                        },
+                    ),
+                );
+            }
+            spawn_button(
+                parent,
+                &fonts,
+                &theme,
+                MenuButton::Back,
+                &t!("common.back"),
+                theme.normal_button,
+            );
+        },
+    );
+}
+
+fn spawn_settings_system(
+    mut commands: Commands,
+    fonts: Res<Fonts>,
+    profile: Res<Profile>,
+    theme: Res<Theme>,
+) {
+    spawn_screen(
+        &mut commands,
+        &fonts,
+        &t!("settings.title"),
+        theme.backdrop(0.8),
+        |parent| {
+            for button in [
+                MenuButton::UiScale,
+                MenuButton::Palette,
+                MenuButton::Theme,
+                MenuButton::Language,
+                MenuButton::Narration,
+                MenuButton::Tips,
+                MenuButton::LowSpec,
+            ] {
+                let label = setting_label(button, &profile.settings).unwrap_or_default();
+                spawn_button(parent, &fonts, &theme, button, &label, theme.normal_button);
+            }
+            spawn_button(
+                parent,
+                &fonts,
+                &theme,
+                MenuButton::Back,
+                &t!("common.back"),
+                theme.normal_button,
+            );
+        },
+    );
+}
+
+fn name_input_system(
+    game: Res<Game>,
+    mut submitted: EventReader<TextInputSubmitted>,
+    mut inputs: Query<&mut TextInput, With<NameInput>>,
+    mut history: ResMut<EditHistory>,
+    mut bodies: Query<&mut Body>,
+) {
+    let Ok(mut body) = bodies.get_mut(game.player) else {
+        return;
+    };
+    for event in submitted.iter() {
+        // Only touch the body on an actual change, that rebuilds it.
+        let current = body.custom_name().unwrap_or_default();
+        if inputs.contains(event.entity) && current != event.value.trim() {
+            let previous = body.custom_name().map(str::to_string);
+            history.push(EditorAction::Rename(previous));
+            body.set_name(&event.value);
+        }
+    }
+    // Pasted or reset builds bring their own names.
+    if body.is_changed() {
+        for mut input in &mut inputs {
+            input.value = body.custom_name().unwrap_or_default().to_string();
+            input.placeholder = body.generated_name().to_string();
+        }
+    }
+}
+
+// Ctrl+Z and Ctrl+Y work as well as the buttons. The text input swallows the
+// keys while the name is being typed.
+fn edit_history_system(
+    buttons: Query<(&Interaction, &MenuButton), Changed<Interaction>>,
+    keys: Res<Input<KeyCode>>,
+    game: Res<Game>,
+    bodies: Query<&Body>,
+    mut history: ResMut<EditHistory>,
+    mut swaps: EventWriter<SwapBody>,
+    mut labels: Query<&mut Text, With<StatusLabel>>,
+) {
+    let ctrl = keys.any_pressed([KeyCode::LControl, KeyCode::RControl]);
+    let mut undo = ctrl && keys.just_pressed(KeyCode::Z);
+    let mut redo = ctrl && keys.just_pressed(KeyCode::Y);
+    for (interaction, button) in &buttons {
+        if *interaction == Interaction::Clicked {
+            undo |= *button == MenuButton::Undo;
+            redo |= *button == MenuButton::Redo;
+        }
+    }
+    if !(undo || redo) {
+        return;
+    }
+    let Ok(body) = bodies.get(game.player) else {
+        return;
+    };
+    let mut body = body.clone();
+    let message = if undo {
+        match history.undo(&mut body) {
+            Some(edit) => t!("edit.undone", edit = edit),
+            None => t!("edit.nothing_to_undo"),
+        }
+    } else {
+        match history.redo(&mut body) {
+            Some(edit) => t!("edit.redone", edit = edit),
+            None => t!("edit.nothing_to_redo"),
+        }
+    };
+    swaps.send(SwapBody {
+        entity: game.player,
+        body,
+    });
+    for mut text in &mut labels {
+        text.sections[0].value = message.clone();
+    }
+}
+
+fn clear_edit_history_system(mut history: ResMut<EditHistory>) {
+    history.clear();
+}
+
+fn settings_button_system(
+    buttons: Query<(&Interaction, &MenuButton), Changed<Interaction>>,
+    mut labels: Query<(&ButtonLabel, &mut Text)>,
+    mut profile: ResMut<Profile>,
+    mut locale: ResMut<Locale>,
+    mut state: ResMut<State<AppState>>,
+    mut errors: EventWriter<ErrorToast>,
+) {
+    let mut changed = false;
+    for (interaction, button) in &buttons {
+        if *interaction != Interaction::Clicked {
+            continue;
+        }
+        let settings = &mut profile.settings;
+        match *button {
+            MenuButton::UiScale => {
+                let next = UI_SCALES
+                    .iter()
+                    .position(|scale| *scale == settings.ui_scale)
+                    .map_or(0, |i| (i + 1) % UI_SCALES.len());
+                settings.ui_scale = UI_SCALES[next];
+                changed = true;
+            }
+            MenuButton::Palette => {
+                let next = PaletteMode::ALL
+                    .iter()
+                    .position(|palette| *palette == settings.palette)
+                    .map_or(0, |i| (i + 1) % PaletteMode::ALL.len());
+                settings.palette = PaletteMode::ALL[next];
+                changed = true;
+            }
+            MenuButton::Theme => {
+                let next = ThemeKind::ALL
+                    .iter()
+                    .position(|theme| *theme == settings.theme)
+                    .map_or(0, |i| (i + 1) % ThemeKind::ALL.len());
+                settings.theme = ThemeKind::ALL[next];
+                // The screen's own backdrop is themed too.
+                let _ = state.restart();
+                changed = true;
+            }
+            MenuButton::Language => {
+                let next = LANGUAGES
+                    .iter()
+                    .position(|(code, _)| *code == settings.language)
+                    .map_or(0, |i| (i + 1) % LANGUAGES.len());
+                settings.language = LANGUAGES[next].0.to_string();
+                // Every label on the screen changes, so build it again.
+                locale.set(&settings.language);
+                let _ = state.restart();
+                changed = true;
+            }
+            MenuButton::Narration => {
+                settings.narration = !settings.narration;
+                changed = true;
+            }
+            MenuButton::Tips => {
+                settings.tips = !settings.tips;
+                changed = true;
+            }
+            MenuButton::LowSpec => {
+                settings.low_spec = !settings.low_spec;
+                changed = true;
+            }
+            _ => {}
+        }
+    }
+    if changed {
+        for (label, mut text) in &mut labels {
+            if let Some(value) = setting_label(label.0, &profile.settings) {
+                text.sections[0].value = value;
+            }
+        }
+        report(&mut errors, profile.save());
+    }
+}
+
+fn button_color(
+    theme: &Theme,
+    button: MenuButton,
+    ruleset: &Ruleset,
+    interaction: Interaction,
+) -> Color {
+    match (button, interaction) {
+        (_, Interaction::Clicked) => theme.pressed_button,
+        (MenuButton::Mode(mode), _) if mode == ruleset.mode => theme.pressed_button,
+        (_, Interaction::Hovered) => theme.hovered_button,
+        (_, Interaction::None) => theme.normal_button,
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn copy_text(text: String) -> Result<(), GameError> {
+    arboard::Clipboard::new()
+        .and_then(|mut clipboard| clipboard.set_text(text))
+        .map_err(|err| GameError::Clipboard(err.to_string()))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn paste_text() -> Result<String, GameError> {
+    arboard::Clipboard::new()
+        .and_then(|mut clipboard| clipboard.get_text())
+        .map_err(|err| GameError::Clipboard(err.to_string()))
+}
+
+// The browser only hands out the clipboard asynchronously, share codes stay
+// desktop-only for now.
+#[cfg(target_arch = "wasm32")]
+fn copy_text(_text: String) -> Result<(), GameError> {
+    Err(GameError::Clipboard(
+        "no clipboard in the browser".to_string(),
+    ))
+}
+
+#[cfg(target_arch = "wasm32")]
+fn paste_text() -> Result<String, GameError> {
+    Err(GameError::Clipboard(
+        "no clipboard in the browser".to_string(),
+    ))
+}
+
+fn menu_button_system(
+    theme: Res<Theme>,
+    mut commands: Commands,
+    mut buttons: Query<(&Interaction, &MenuButton, &mut UiColor), Changed<Interaction>>,
+    mut ruleset: ResMut<Ruleset>,
+    mut state: ResMut<State<AppState>>,
+    mut dialog: ResMut<ConfirmDialog>,
+    registry: Res<AiRegistry>,
+    game: Res<Game>,
+    mut exports: EventWriter<ExportBody>,
+) {
+    for (interaction, button, _) in &buttons {
+        if *interaction == Interaction::Clicked {
+            match *button {
+                MenuButton::Mode(mode) => ruleset.mode = mode,
+                MenuButton::Timer => {
+                    let next = TIMER_OPTIONS
+                        .iter()
+                        .position(|timer| *timer == ruleset.timer)
+                        .map_or(0, |i| (i + 1) % TIMER_OPTIONS.len());
+                    ruleset.timer = TIMER_OPTIONS[next];
+                }
+                // Stuck bodies make for a long fight, it's worth a second look.
+                MenuButton::Fight if ruleset.mutators.strands() => {
+                    dialog.open(t!("confirm.stranded"), StartFight);
+                }
+                MenuButton::Fight => {
+                    let _ = state.set(AppState::Battle);
+                }
+                MenuButton::Tutorial => {
+                    tutorial::begin(&mut commands, &mut ruleset);
+                    let _ = state.set(AppState::Battle);
+                }
+                MenuButton::Training => {
+                    training::begin(&mut commands, &mut ruleset);
+                    let _ = state.set(AppState::Battle);
+                }
+                MenuButton::PlayAgain => {
+                    let _ = state.set(AppState::PreBattle);
+                }
+                MenuButton::Achievements => {
+                    let _ = state.set(AppState::Achievements);
+                }
+                MenuButton::Statistics => {
+                    let _ = state.set(AppState::Statistics);
+                }
+                MenuButton::RunHistory => {
+                    let _ = state.set(AppState::RunHistory);
+                }
+                MenuButton::Rivals => {
+                    let _ = state.set(AppState::Rivals);
+                }
+                MenuButton::DailyHistory => {
+                    let _ = state.set(AppState::DailyHistory);
+                }
+                MenuButton::Starter => {
+                    let _ = state.set(AppState::Starter);
+                }
+                MenuButton::Campaign => {
+                    let _ = state.set(AppState::Campaign);
+                }
+                MenuButton::ExportImage => exports.send(ExportBody(game.player)),
+                MenuButton::Settings => {
+                    let _ = state.set(AppState::Settings);
+                }
+                MenuButton::Back => {
+                    let _ = state.set(AppState::PreBattle);
+                }
+                MenuButton::Survival => ruleset.survival = !ruleset.survival,
+                MenuButton::Ai => {
+                    let names = registry.names().collect::<Vec<_>>();
+                    let next = names
+                        .iter()
+                        .position(|name| *name == ruleset.ai)
+                        .map_or(0, |i| (i + 1) % names.len());
+                    ruleset.ai = names[next].to_string();
+                }
+                MenuButton::Interrupts => ruleset.interrupts = !ruleset.interrupts,
+                MenuButton::Poise => ruleset.poise = !ruleset.poise,
+                MenuButton::AutoFace => ruleset.auto_face = !ruleset.auto_face,
+                MenuButton::Weather => ruleset.weather = !ruleset.weather,
+                MenuButton::Pickups => ruleset.pickups = !ruleset.pickups,
+                MenuButton::Mutator(mutator) => ruleset.mutators.toggle(mutator),
+                MenuButton::NextWave => {
+                    let _ = state.set(AppState::Battle);
+                }
+                MenuButton::SwapPart
+                | MenuButton::Booster(_)
+                | MenuButton::Attachment(_)
+                | MenuButton::ArmAttachment(_)
+                | MenuButton::SpareArm => {}
+                MenuButton::CopyBuild
+                | MenuButton::PasteBuild
+                | MenuButton::EquipBuild
+                | MenuButton::ResetProfile
+                | MenuButton::UiScale
+                | MenuButton::Palette
+                | MenuButton::Theme
+                | MenuButton::Language
+                | MenuButton::Narration
+                | MenuButton::Tips
+                | MenuButton::LowSpec
+                | MenuButton::SaveRival
+                | MenuButton::Rematch(_)
+                | MenuButton::DeleteRival(_)
+                | MenuButton::Undo
+                | MenuButton::Redo
+                | MenuButton::Daily
+                | MenuButton::DailyPractice
+                | MenuButton::Encounter(_)
+                | MenuButton::UseArchetype => {}
+            }
+        }
+    }
+    for (interaction, button, mut color) in &mut buttons {
+        color.0 = button_color(&theme, *button, &ruleset, *interaction);
+    }
+}
+
+fn share_button_system(
+    buttons: Query<(&Interaction, &MenuButton), Changed<Interaction>>,
+    game: Res<Game>,
+    bodies: Query<&Body>,
+    stats: Query<&Stats>,
+    mut pasted: ResMut<PastedBuild>,
+    mut dialog: ResMut<ConfirmDialog>,
+    mut labels: Query<&mut Text, With<StatusLabel>>,
+    mut errors: EventWriter<ErrorToast>,
+) {
+    for (interaction, button) in &buttons {
+        if *interaction != Interaction::Clicked {
+            continue;
+        }
+        // What went wrong is toasted with its details, the label only keeps
+        // what was there from showing as if it still applied.
+        let message = match *button {
+            MenuButton::CopyBuild => match bodies.get(game.player) {
+                Ok(body) => match report(&mut errors, copy_text(body.to_share_code())) {
+                    Some(()) => t!("share.copied"),
+                    None => String::new(),
+                },
+                Err(_) => continue,
+            },
+            MenuButton::PasteBuild => {
+                match report(
+                    &mut errors,
+                    paste_text().and_then(|code| Body::from_share_code(&code)),
+                ) {
+                    Some(body) => {
+                        let pasted_stats = bounded_stats(&body);
+                        let mut preview = build_preview(&pasted_stats);
+                        let class = pasted_stats.class();
+                        if stats
+                            .get(game.player)
+                            .is_ok_and(|stats| class > stats.class())
+                        {
+                            preview += "\n";
+                            preview += &t!("weight_class.moved_up", class = class.name());
+                        }
+                        pasted.0 = Some(body);
+                        preview
+                    }
+                    None => {
+                        pasted.0 = None;
+                        String::new()
+                    }
+                }
+            }
+            MenuButton::EquipBuild if pasted.0.is_some() => {
+                dialog.open(t!("confirm.equip_build"), EquipBuild);
+                continue;
+            }
+            MenuButton::EquipBuild => t!("share.paste_first"),
+            MenuButton::ResetProfile => {
+                dialog.open(t!("confirm.reset_profile"), ResetProfile);
+                continue;
+            }
+            _ => continue,
+        };
+        for mut text in &mut labels {
+            text.sections[0].value = message.clone();
+        }
+    }
+}
+
+fn confirmed_action_system(
+    game: Res<Game>,
+    mut pasted: ResMut<PastedBuild>,
+    mut profile: ResMut<Profile>,
+    mut history: ResMut<EditHistory>,
+    bodies: Query<&Body>,
+    mut equip: EventReader<EquipBuild>,
+    mut reset: EventReader<ResetProfile>,
+    mut fight: EventReader<StartFight>,
+    mut swaps: EventWriter<SwapBody>,
+    mut state: ResMut<State<AppState>>,
+    mut labels: Query<&mut Text, With<StatusLabel>>,
+    mut errors: EventWriter<ErrorToast>,
+) {
+    if fight.iter().count() > 0 {
+        let _ = state.set(AppState::Battle);
+    }
+    let mut message = None;
+    if equip.iter().count() > 0 {
+        if let Some(mut body) = pasted.0.take() {
+            // A build's attachments only come along if the player has them.
+            body.fit_attachments(&profile.attachments);
+            if let Ok(current) = bodies.get(game.player) {
+                history.push(EditorAction::Equip(Box::new(current.clone())));
+            }
+            swaps.send(SwapBody {
+                entity: game.player,
+                body,
+            });
+            message = Some(t!("share.equipped"));
+        }
+    }
+    if reset.iter().count() > 0 {
+        report(&mut errors, profile.reset());
+        history.clear();
+        swaps.send(SwapBody {
+            entity: game.player,
+            body: Body::default(),
+        });
+        message = Some(t!("menu.profile_reset", record = record_label(&profile)));
+    }
+    if let Some(message) = message {
+        for mut text in &mut labels {
+            text.sections[0].value = message.clone();
+        }
+    }
+}
+
+// Buys one first when there's none left, replacing an armed booster of the
+// same category goes through the confirmation dialog.
+fn booster_button_system(
+    buttons: Query<(&Interaction, &MenuButton), Changed<Interaction>>,
+    mut boosters: ResMut<Boosters>,
+    mut profile: ResMut<Profile>,
+    mut dialog: ResMut<ConfirmDialog>,
+    mut labels: Query<&mut Text, With<StatusLabel>>,
+) {
+    for (interaction, button) in &buttons {
+        let (Interaction::Clicked, MenuButton::Booster(booster)) = (interaction, *button) else {
+            continue;
+        };
+        let name = booster.name();
+        let mut message = String::new();
+        if boosters.is_armed(booster) {
+            boosters.disarm(booster);
+            message = t!("booster.disarmed", booster = name);
+        } else if profile.boosters.get(&booster).copied().unwrap_or(0) == 0
+            && profile.scrap < booster.price()
+        {
+            message = t!(
+                "booster.no_scrap",
+                booster = name,
+                price = booster.price(),
+                scrap = profile.scrap
+            );
+        } else {
+            let stock = profile.boosters.get(&booster).copied().unwrap_or(0);
+            if stock == 0 {
+                profile.scrap -= booster.price();
+                *profile.boosters.entry(booster).or_default() += 1;
+                message = t!("booster.bought", booster = &name, price = booster.price()) + "\n";
+            }
+            match boosters.replaces(booster) {
+                Some(armed) => dialog.open(
+                    t!(
+                        "confirm.replace_booster",
+                        booster = &name,
+                        armed = armed.name()
+                    ),
+                    ArmBooster(booster),
+                ),
+                None => {
+                    boosters.arm(booster);
+                    message += &t!("booster.armed", booster = name);
+                }
+            }
+        }
+        for mut text in &mut labels {
+            text.sections[0].value = message.clone();
+        }
+    }
+}
+
+// Buying one leaves it in the inventory, clicking an arm's button cycles
+// through the free ones and back to none.
+fn attachment_button_system(
+    buttons: Query<(&Interaction, &MenuButton), Changed<Interaction>>,
+    game: Res<Game>,
+    mut profile: ResMut<Profile>,
+    mut history: ResMut<EditHistory>,
+    mut bodies: Query<&mut Body>,
+    mut labels: Query<&mut Text, With<StatusLabel>>,
+) {
+    for (interaction, button) in &buttons {
+        if *interaction != Interaction::Clicked {
+            continue;
+        }
+        let message = match *button {
+            MenuButton::Attachment(attachment) if profile.scrap < attachment.price() => t!(
+                "attachment.no_scrap",
+                attachment = attachment.name(),
+                price = attachment.price(),
+                scrap = profile.scrap
+            ),
+            MenuButton::Attachment(attachment) => {
+                profile.scrap -= attachment.price();
+                *profile.attachments.entry(attachment).or_default() += 1;
+                t!(
+                    "attachment.bought",
+                    attachment = attachment.name(),
+                    price = attachment.price()
+                )
+            }
+            MenuButton::ArmAttachment(slot) => {
+                let Ok(mut body) = bodies.get_mut(game.player) else {
+                    continue;
+                };
+                if body.arm(slot).is_none() {
+                    t!("attachment.no_arm")
+                } else {
+                    let next = attachment::next(&profile.attachments, &body, slot);
+                    let previous = body.attach(slot, next);
+                    history.push(EditorAction::Attach(slot, previous));
+                    match (next, previous) {
+                        (Some(next), _) => t!(
+                            "attachment.attached",
+                            attachment = next.name(),
+                            arm = Limb::Arm(slot).badge()
+                        ),
+                        (None, Some(previous)) => {
+                            t!("attachment.removed", attachment = previous.name())
+                        }
+                        (None, None) => t!("attachment.none_free"),
+                    }
+                }
+            }
+            MenuButton::SpareArm => {
+                let Ok(body) = bodies.get_mut(game.player) else {
+                    continue;
+                };
+                profile.spare_arm = next_spare(profile.spare_arm.as_ref(), &body);
+                spare_arm_label(&profile)
+            }
+            _ => continue,
+        };
+        for mut text in &mut labels {
+            text.sections[0].value = message.clone();
+        }
+    }
+}
+
+// Undoing and pasted builds change what's on the arms too.
+fn attachment_label_system(
+    game: Res<Game>,
+    profile: Res<Profile>,
+    bodies: Query<&Body>,
+    changed: Query<(), Changed<Body>>,
+    mut labels: Query<(&ButtonLabel, &mut Text)>,
+) {
+    if !(profile.is_changed() || changed.contains(game.player)) {
+        return;
+    }
+    let Ok(body) = bodies.get(game.player) else {
+        return;
+    };
+    for (label, mut text) in &mut labels {
+        match label.0 {
+            MenuButton::Attachment(attachment) => {
+                text.sections[0].value = attachment_label(attachment, &profile, body);
+            }
+            MenuButton::ArmAttachment(slot) => {
+                text.sections[0].value = arm_attachment_label(slot, body);
+            }
+            MenuButton::SpareArm => text.sections[0].value = spare_arm_label(&profile),
+            _ => {}
+        }
+    }
+}
+
+fn booster_label_system(
+    boosters: Res<Boosters>,
+    profile: Res<Profile>,
+    mut labels: Query<(&ButtonLabel, &mut Text)>,
+) {
+    if !(boosters.is_changed() || profile.is_changed()) {
+        return;
+    }
+    for (label, mut text) in &mut labels {
+        if let MenuButton::Booster(booster) = label.0 {
+            text.sections[0].value = booster_label(booster, &boosters, &profile);
+        }
+    }
+}
+
+fn mode_button_color_system(
+    theme: Res<Theme>,
+    ruleset: Res<Ruleset>,
+    mut buttons: Query<(&Interaction, &MenuButton, &mut UiColor)>,
+    mut labels: Query<(&ButtonLabel, &mut Text)>,
+) {
+    if ruleset.is_changed() {
+        for (interaction, button, mut color) in &mut buttons {
+            color.0 = button_color(&theme, *button, &ruleset, *interaction);
+        }
+        for (label, mut text) in &mut labels {
+            if let Some(value) = ruleset_label(label.0, &ruleset) {
+                text.sections[0].value = value;
+            }
+        }
+    }
+}
+
+fn pause_system(mut keys: ResMut<Input<KeyCode>>, mut state: ResMut<State<AppState>>) {
+    if keys.just_pressed(KeyCode::P) {
+        let result = match state.current() {
+            AppState::Battle | AppState::Intro => state.push(AppState::Paused),
+            AppState::Paused => state.pop(),
+            _ => return,
+        };
+        if result.is_ok() {
+            keys.reset(KeyCode::P);
+        }
+    }
+}
+
+// Tabbing out pauses the battle, coming back leaves the pause menu up.
+fn focus_pause_system(mut focus: EventReader<WindowFocused>, mut state: ResMut<State<AppState>>) {
+    let lost = focus.iter().any(|event| !event.focused);
+    if lost && matches!(state.current(), AppState::Battle | AppState::Intro) {
+        let _ = state.push(AppState::Paused);
+    }
+}
+
+fn despawn_menu_system(mut commands: Commands, roots: Query<Entity, With<MenuRoot>>) {
+    for root in &roots {
+        commands.entity(root).despawn_recursive();
+    }
+}
+
+pub struct MenuPlugin;
+
+impl Plugin for MenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PastedBuild>()
+            .init_resource::<EditHistory>()
+            .add_event::<EquipBuild>()
+            .add_event::<ResetProfile>()
+            .add_event::<StartFight>()
+            .add_event::<RemoveRival>()
+            .add_system(pause_system)
+            .add_system(focus_pause_system)
+            .add_system_set(SystemSet::on_enter(AppState::PreBattle).with_system(spawn_menu_system))
+            .add_system_set(
+                SystemSet::on_update(AppState::PreBattle)
+                    .with_system(menu_button_system)
+                    .with_system(share_button_system)
+                    .with_system(confirmed_action_system)
+                    .with_system(name_input_system.after(text_input::focus_system))
+                    .with_system(mode_button_color_system.after(menu_button_system))
+                    .with_system(booster_button_system)
+                    .with_system(booster_label_system.after(booster_button_system))
+                    .with_system(attachment_button_system)
+                    .with_system(attachment_label_system.after(attachment_button_system))
+                    .with_system(energy_panel_system)
+                    .with_system(edit_history_system)
+                    .with_system(daily_button_system),
+            )
+            .add_system_set(
+                SystemSet::on_exit(AppState::PreBattle)
+                    .with_system(despawn_menu_system)
+                    .with_system(clear_edit_history_system),
+            )
+            .add_system_set(SystemSet::on_enter(AppState::Paused).with_system(spawn_pause_system))
+            .add_system_set(SystemSet::on_exit(AppState::Paused).with_system(despawn_menu_system))
+            .add_system_set(
+                SystemSet::on_enter(AppState::GameOver)
+                    .with_system(spawn_game_over_system.after(history::end_battle_system)),
+            )
+            .add_system_set(
+                SystemSet::on_update(AppState::GameOver)
+                    .with_system(menu_button_system)
+                    .with_system(save_rival_system),
+            )
+            .add_system_set(SystemSet::on_exit(AppState::GameOver).with_system(despawn_menu_system))
+            .add_system_set(
+                SystemSet::on_enter(AppState::Achievements).with_system(spawn_achievements_system),
+            )
+            .add_system_set(
+                SystemSet::on_update(AppState::Achievements).with_system(menu_button_system),
+            )
+            .add_system_set(
+                SystemSet::on_exit(AppState::Achievements).with_system(despawn_menu_system),
+            )
+            .add_system_set(
+                SystemSet::on_enter(AppState::Statistics).with_system(spawn_statistics_system),
+            )
+            .add_system_set(
+                SystemSet::on_update(AppState::Statistics).with_system(menu_button_system),
+            )
+            .add_system_set(
+                SystemSet::on_exit(AppState::Statistics).with_system(despawn_menu_system),
+            )
+            .add_system_set(
+                SystemSet::on_enter(AppState::RunHistory).with_system(spawn_run_history_system),
+            )
+            .add_system_set(
+                SystemSet::on_update(AppState::RunHistory).with_system(menu_button_system),
+            )
+            .add_system_set(
+                SystemSet::on_exit(AppState::RunHistory).with_system(despawn_menu_system),
+            )
+            .add_system_set(
+                SystemSet::on_enter(AppState::DailyHistory).with_system(spawn_daily_history_system),
+            )
+            .add_system_set(
+                SystemSet::on_update(AppState::DailyHistory).with_system(menu_button_system),
+            )
+            .add_system_set(
+                SystemSet::on_exit(AppState::DailyHistory).with_system(despawn_menu_system),
+            )
+            .add_system_set(SystemSet::on_enter(AppState::Rivals).with_system(spawn_rivals_system))
+            .add_system_set(
+                SystemSet::on_update(AppState::Rivals)
+                    .with_system(menu_button_system)
+                    .with_system(rivals_button_system)
+                    .with_system(remove_rival_system),
+            )
+            .add_system_set(SystemSet::on_exit(AppState::Rivals).with_system(despawn_menu_system))
+            .add_system_set(
+                SystemSet::on_enter(AppState::Campaign).with_system(spawn_campaign_system),
+            )
+            .add_system_set(
+                SystemSet::on_update(AppState::Campaign)
+                    .with_system(menu_button_system)
+                    .with_system(campaign_button_system)
+                    .with_system(campaign_loaded_system),
+            )
+            .add_system_set(SystemSet::on_exit(AppState::Campaign).with_system(despawn_menu_system))
+            .add_system_set(
+                SystemSet::on_enter(AppState::Settings).with_system(spawn_settings_system),
+            )
+            .add_system_set(
+                SystemSet::on_update(AppState::Settings)
+                    .with_system(menu_button_system)
+                    .with_system(settings_button_system),
+            )
+            .add_system_set(SystemSet::on_exit(AppState::Settings).with_system(despawn_menu_system))
+            .add_system_set(
+                SystemSet::on_enter(AppState::Intermission).with_system(spawn_intermission_system),
+            )
+            .add_system_set(
+                SystemSet::on_update(AppState::Intermission)
+                    .with_system(menu_button_system)
+                    .with_system(swap_part_system)
+                    .with_system(booster_button_system)
+                    .with_system(booster_label_system.after(booster_button_system))
+                    .with_system(intermission_label_system)
+                    .with_system(energy_panel_system),
+            )
+            .add_system_set(
+                SystemSet::on_exit(AppState::Intermission).with_system(despawn_menu_system),
+            );
+    }
+}
@@ -0,0 +1,233 @@
+use std::str::FromStr;
+
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::{
+    body::{update_body_system, Body, Stats},
+    bounds::StatModifiers,
+    effects::{ActiveEffect, Polarity},
+    locale::t,
+    turn::Ruleset,
+    AppState, Game,
+};
+
+const GLASS_CANNON_DAMAGE: f32 = 2.0;
+const GLASS_CANNON_HEALTH: f32 = 0.5;
+const LOW_GRAVITY_JUMP: f32 = 3.0;
+
+// Optional twists picked before a battle, they apply to both bodies.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum Mutator {
+    GlassCannon,
+    LowGravity,
+    NoLegsDay,
+    EnergyCrisis,
+}
+
+impl Mutator {
+    pub const ALL: [Mutator; 4] = [
+        Mutator::GlassCannon,
+        Mutator::LowGravity,
+        Mutator::NoLegsDay,
+        Mutator::EnergyCrisis,
+    ];
+
+    fn bit(self) -> u8 {
+        1 << self as u8
+    }
+
+    // Also picks the `effect.<id>.name` strings, the battle shows mutators
+    // with the status effects.
+    pub fn id(self) -> &'static str {
+        match self {
+            Mutator::GlassCannon => "glass_cannon",
+            Mutator::LowGravity => "low_gravity",
+            Mutator::NoLegsDay => "no_legs_day",
+            Mutator::EnergyCrisis => "energy_crisis",
+        }
+    }
+
+    pub fn name(self) -> String {
+        t!(format!("effect.{}.name", self.id()))
+    }
+}
+
+impl FromStr for Mutator {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Mutator::ALL
+            .into_iter()
+            .find(|mutator| mutator.id() == s)
+            .ok_or_else(|| format!("unknown mutator `{}`", s))
+    }
+}
+
+// The mutators on for a battle, one bit each.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Mutators(u8);
+
+impl Mutators {
+    pub fn contains(self, mutator: Mutator) -> bool {
+        self.0 & mutator.bit() != 0
+    }
+
+    pub fn toggle(&mut self, mutator: Mutator) {
+        self.0 ^= mutator.bit();
+    }
+
+    pub fn iter(self) -> impl Iterator<Item = Mutator> {
+        Mutator::ALL
+            .into_iter()
+            .filter(move |mutator| self.contains(*mutator))
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    // Nothing but legs moves a body yet, dashes included and no jumps, so
+    // without legs every body is stuck where it starts.
+    pub fn strands(self) -> bool {
+        self.contains(Mutator::NoLegsDay)
+    }
+
+    // Damage one body deals another, hazards like sudden death aren't
+    // affected.
+    pub fn scale_damage(self, amount: f32) -> f32 {
+        if self.contains(Mutator::GlassCannon) && amount > 0.0 {
+            amount * GLASS_CANNON_DAMAGE
+        } else {
+            amount
+        }
+    }
+
+    pub fn regen(self, regen: f32) -> f32 {
+        if self.contains(Mutator::EnergyCrisis) {
+            regen.min(0.0)
+        } else {
+            regen
+        }
+    }
+
+    // What the mutators do to freshly computed stats, see
+    // `mutator_stats_system`.
+    pub fn apply(self, stats: &mut Stats) {
+        if self.contains(Mutator::GlassCannon) {
+            stats.max_health *= GLASS_CANNON_HEALTH;
+            stats.health *= GLASS_CANNON_HEALTH;
+        }
+        if self.contains(Mutator::LowGravity) {
+            stats.jump_force *= LOW_GRAVITY_JUMP;
+        }
+        if self.contains(Mutator::NoLegsDay) {
+            stats.speed = 0.0;
+        }
+    }
+}
+
+// Comma separated ids, like `glass_cannon,energy_crisis`.
+impl FromStr for Mutators {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut mutators = Mutators::default();
+        for id in s.split(',').map(str::trim).filter(|id| !id.is_empty()) {
+            let mutator = id.parse::<Mutator>()?;
+            if !mutators.contains(mutator) {
+                mutators.toggle(mutator);
+            }
+        }
+        Ok(mutators)
+    }
+}
+
+// Rebuilds the stats without the old mutators when the set changes, like
+// the weather does.
+fn rebuild_system(
+    ruleset: Res<Ruleset>,
+    mut applied: Local<Mutators>,
+    mut bodies: Query<&mut Body>,
+) {
+    if ruleset.mutators == *applied {
+        return;
+    }
+    *applied = ruleset.mutators;
+    for mut body in &mut bodies {
+        body.set_changed();
+    }
+}
+
+fn mutator_stats_system(ruleset: Res<Ruleset>, mut bodies: Query<&mut Stats, Changed<Body>>) {
+    for mut stats in &mut bodies {
+        ruleset.mutators.apply(&mut stats);
+    }
+}
+
+fn mutator_effect_system(
+    game: Res<Game>,
+    ruleset: Res<Ruleset>,
+    mut effects: EventWriter<ActiveEffect>,
+) {
+    for mutator in ruleset.mutators.iter() {
+        for target in [game.player, game.enemy] {
+            effects.send(ActiveEffect {
+                target,
+                id: mutator.id(),
+                polarity: Polarity::Debuff,
+                remaining: None,
+                stacks: 1,
+            });
+        }
+    }
+}
+
+pub struct MutatorsPlugin;
+
+impl Plugin for MutatorsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(rebuild_system.before(update_body_system))
+            .add_system(
+                mutator_stats_system
+                    .after(update_body_system)
+                    .label(StatModifiers),
+            )
+            .add_system_set(
+                SystemSet::on_update(AppState::Battle).with_system(mutator_effect_system),
+            );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::body::compute_stats;
+
+    #[test]
+    fn mutators_parse_and_apply() {
+        let mutators = "glass_cannon, no_legs_day".parse::<Mutators>().unwrap();
+        assert_eq!(
+            mutators.iter().collect::<Vec<_>>(),
+            [Mutator::GlassCannon, Mutator::NoLegsDay]
+        );
+        assert!("glass_cannon,gravity".parse::<Mutators>().is_err());
+        assert!("".parse::<Mutators>().unwrap().is_empty());
+
+        let clear = compute_stats(&Body::default());
+        let mut stats = clear.clone();
+        mutators.apply(&mut stats);
+        assert_eq!(stats.max_health, clear.max_health / 2.0);
+        assert_eq!(stats.health, stats.max_health);
+        assert_eq!(stats.speed, 0.0);
+        assert!(mutators.strands());
+        assert_eq!(mutators.scale_damage(5.0), 10.0);
+        // Healing and energy drains go through untouched.
+        assert_eq!(mutators.scale_damage(-5.0), -5.0);
+        let mut crisis = Mutators::default();
+        crisis.toggle(Mutator::EnergyCrisis);
+        assert_eq!(crisis.regen(3.0), 0.0);
+        assert_eq!(crisis.regen(-1.0), -1.0);
+        assert_eq!(mutators.regen(3.0), 3.0);
+    }
+}
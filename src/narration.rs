@@ -0,0 +1,355 @@
+use bevy::prelude::*;
+
+use crate::{
+    battle::Animation,
+    body::{Body, SkillId, Stats},
+    locale::t,
+    profile::Profile,
+    theme::Theme,
+    ui::{Fonts, Scaled},
+    AppState, BattleSummary, DamageEvent, Game,
+};
+
+// At most one line this often, anything in between is summed up.
+const MIN_INTERVAL: f32 = 1.5;
+// How long a line stays on the banner.
+const LINE_TIME: f32 = 3.0;
+
+// Where narration goes once it's been put into words. A text-to-speech
+// backend would implement this too.
+pub trait Narrator: Send + Sync {
+    fn narrate(&mut self, line: &str);
+
+    // The line the banner should show, if any.
+    fn banner(&self) -> Option<&str> {
+        None
+    }
+}
+
+// Only shows the latest line on the banner.
+#[derive(Default)]
+pub struct BannerNarrator {
+    line: Option<String>,
+}
+
+impl Narrator for BannerNarrator {
+    fn narrate(&mut self, line: &str) {
+        self.line = Some(line.to_string());
+    }
+
+    fn banner(&self) -> Option<&str> {
+        self.line.as_deref()
+    }
+}
+
+// Something that happened in the battle, kept structured until it's spoken so
+// a flurry can be summed up instead.
+enum Beat {
+    Skill {
+        actor: String,
+        skill: String,
+    },
+    Hit {
+        // What dealt it in words, see `DamageSource::describe`.
+        cause: String,
+        target: Entity,
+        target_name: String,
+        amount: f32,
+    },
+    End {
+        winner: Option<String>,
+    },
+}
+
+impl Beat {
+    fn line(&self) -> String {
+        match self {
+            Beat::Skill { actor, skill } => t!("narration.skill", actor = actor, skill = skill),
+            Beat::Hit {
+                cause,
+                target_name,
+                amount,
+                ..
+            } => t!(
+                "narration.hit",
+                cause = cause,
+                target = target_name,
+                amount = *amount
+            ),
+            Beat::End {
+                winner: Some(winner),
+            } => t!("narration.victory", winner = winner),
+            Beat::End { winner: None } => t!("narration.draw"),
+        }
+    }
+}
+
+pub struct Narration {
+    pub narrator: Box<dyn Narrator>,
+    pending: Vec<Beat>,
+    since_line: f32,
+}
+
+impl Default for Narration {
+    fn default() -> Self {
+        Self {
+            narrator: Box::<BannerNarrator>::default(),
+            pending: Vec::new(),
+            since_line: LINE_TIME,
+        }
+    }
+}
+
+impl Narration {
+    fn speak(&mut self, game: &Game, bodies: &Query<&Body>) {
+        let line = match self.pending.as_slice() {
+            [] => return,
+            [beat] => beat.line(),
+            beats => {
+                let taken = |entity: Entity| {
+                    beats
+                        .iter()
+                        .map(|beat| match beat {
+                            Beat::Hit { target, amount, .. } if *target == entity => *amount,
+                            _ => 0.0,
+                        })
+                        .sum::<f32>()
+                };
+                t!(
+                    "narration.summary",
+                    count = beats.len(),
+                    player = name(bodies, game.player),
+                    player_damage = taken(game.player),
+                    enemy = name(bodies, game.enemy),
+                    enemy_damage = taken(game.enemy)
+                )
+            }
+        };
+        self.narrator.narrate(&line);
+        self.pending.clear();
+        self.since_line = 0.0;
+    }
+}
+
+#[derive(Component)]
+struct NarrationBanner;
+
+fn name(bodies: &Query<&Body>, entity: Entity) -> String {
+    bodies
+        .get(entity)
+        .map_or_else(|_| String::new(), |body| body.name().to_string())
+}
+
+fn skill_name(stats: &Query<&Stats>, entity: Entity, skill: &SkillId) -> Option<String> {
+    Some(stats.get(entity).ok()?.skill(skill)?.get_name())
+}
+
+fn hit_beat(
+    event: &DamageEvent,
+    name: impl Fn(Entity) -> String,
+    skill: impl Fn(Entity, &SkillId) -> Option<String>,
+) -> Beat {
+    Beat::Hit {
+        cause: event.source.describe(&name, skill),
+        target: event.target,
+        target_name: name(event.target),
+        amount: event.amount,
+    }
+}
+
+fn collect_system(
+    profile: Res<Profile>,
+    mut narration: ResMut<Narration>,
+    mut damage: EventReader<DamageEvent>,
+    started: Query<(Entity, &Animation), Added<Animation>>,
+    bodies: Query<&Body>,
+    stats: Query<&Stats>,
+) {
+    let hits = damage.iter().collect::<Vec<_>>();
+    if !profile.settings.narration {
+        return;
+    }
+    for (entity, animation) in &started {
+        if let Some(skill) = skill_name(&stats, entity, &animation.skill) {
+            narration.pending.push(Beat::Skill {
+                actor: name(&bodies, entity),
+                skill,
+            });
+        }
+    }
+    for event in hits {
+        narration.pending.push(hit_beat(
+            event,
+            |entity| name(&bodies, entity),
+            |entity, skill| skill_name(&stats, entity, skill),
+        ));
+    }
+}
+
+// The result is said right away, the last blows leading up to it can wait.
+fn end_system(
+    game: Res<Game>,
+    profile: Res<Profile>,
+    summary: Res<BattleSummary>,
+    mut narration: ResMut<Narration>,
+    bodies: Query<&Body>,
+) {
+    if !profile.settings.narration {
+        return;
+    }
+    narration.pending.clear();
+    narration.pending.push(Beat::End {
+        winner: summary.winner.map(|winner| name(&bodies, winner)),
+    });
+    narration.speak(&game, &bodies);
+}
+
+fn speak_system(
+    time: Res<Time>,
+    game: Res<Game>,
+    mut narration: ResMut<Narration>,
+    bodies: Query<&Body>,
+) {
+    narration.since_line += time.delta_seconds();
+    if narration.since_line >= MIN_INTERVAL {
+        narration.speak(&game, &bodies);
+    }
+}
+
+fn banner_system(
+    profile: Res<Profile>,
+    theme: Res<Theme>,
+    narration: Res<Narration>,
+    mut banners: Query<(&mut UiColor, &Children), With<NarrationBanner>>,
+    mut texts: Query<&mut Text>,
+) {
+    let line = narration
+        .narrator
+        .banner()
+        .filter(|_| profile.settings.narration && narration.since_line < LINE_TIME)
+        .unwrap_or_default();
+    for (mut color, children) in &mut banners {
+        let background = if line.is_empty() {
+            Color::NONE
+        } else {
+            theme.backdrop(0.7)
+        };
+        if color.0 != background {
+            color.0 = background;
+        }
+        for child in children {
+            if let Ok(mut text) = texts.get_mut(*child) {
+                if text.sections[0].value != line {
+                    text.sections[0].value = line.to_string();
+                }
+                if text.sections[0].style.color != theme.text {
+                    text.sections[0].style.color = theme.text;
+                }
+            }
+        }
+    }
+}
+
+fn banner_startup_system(mut commands: Commands, fonts: Res<Fonts>) {
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    left: Val::Percent(10.0),
+                    right: Val::Percent(10.0),
+                    top: Val::Px(60.0),
+                    ..default()
+                },
+                justify_content: JustifyContent::Center,
+                padding: UiRect::all(Val::Px(8.0)),
+                ..default()
+            },
+            color: Color::NONE.into(),
+            ..default()
+        })
+        .insert_bundle((
+            NarrationBanner,
+            Scaled {
+                top: Some(60.0),
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn_bundle(TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font: fonts.bold(),
+                        font_size: 36.0,
+                        color: Color::WHITE,
+                    },
+                ))
+                .insert(Scaled::font(36.0));
+        });
+}
+
+pub struct NarrationPlugin;
+
+impl Plugin for NarrationPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Narration>()
+            .add_startup_system_to_stage(StartupStage::PostStartup, banner_startup_system)
+            .add_system_set(
+                SystemSet::on_update(AppState::Battle)
+                    .with_system(collect_system.after(crate::battle::damage_system)),
+            )
+            .add_system_set(SystemSet::on_enter(AppState::GameOver).with_system(end_system))
+            .add_system(speak_system.after(collect_system))
+            .add_system_to_stage(CoreStage::PostUpdate, banner_system);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{body::Skill, DamageSource};
+
+    #[test]
+    fn every_source_is_named_in_its_hit_line() {
+        let [player, enemy] = [0, 1].map(Entity::from_raw);
+        let name = |entity: Entity| ["Ada", "Bolt"][entity.id() as usize].to_string();
+        let skill = |_: Entity, skill: &SkillId| {
+            (*skill == Skill::Reload.id()).then(|| "Reload".to_string())
+        };
+        let line = |source: DamageSource| {
+            let event = DamageEvent {
+                target: enemy,
+                source,
+                amount: 4.0,
+                emp: false,
+                impact: None,
+            };
+            hit_beat(&event, name, skill).line()
+        };
+
+        let reload = DamageSource::Attack {
+            attacker: player,
+            skill: Skill::Reload.id(),
+        };
+        assert_eq!(line(reload), "Ada's Reload hits Bolt for 4");
+        let gone = DamageSource::Attack {
+            attacker: player,
+            skill: Skill::SwapLimb.id(),
+        };
+        assert_eq!(line(gone), "Ada hits Bolt for 4");
+        let burn = DamageSource::Status {
+            id: "periodic_health",
+            from: Some(player),
+        };
+        assert_eq!(line(burn), "Health over time hits Bolt for 4");
+        assert_eq!(
+            line(DamageSource::SuddenDeath),
+            "Sudden death hits Bolt for 4"
+        );
+        assert_eq!(
+            line(DamageSource::Console),
+            "The debug console hits Bolt for 4"
+        );
+    }
+}
@@ -0,0 +1,963 @@
+use std::{
+    collections::BTreeMap,
+    io::{self, Read, Write},
+    net::{SocketAddr, TcpListener, TcpStream},
+    sync::{
+        mpsc::{self, Receiver, TryRecvError},
+        Mutex,
+    },
+    time::Duration,
+};
+
+use bevy::{prelude::*, time::TimeSystem, utils::Instant};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    ai::AiController,
+    ammo,
+    balance::BalanceConfig,
+    battle::{reset_battle_system, Animation, ExternalInput},
+    body::{Body, Facing, Skill, SkillId, Stats},
+    energy,
+    interrupt::Recovery,
+    locale::t,
+    runtime::{self, SkillRuntime},
+    swap::SwapBody,
+    text_input::{spawn_text_input, TextInput},
+    theme::Theme,
+    timer::TimeScale,
+    turn::Ruleset,
+    ui::{Fonts, Toast, UseSkill},
+    AppState, Game, GameRng,
+};
+
+// Bumped whenever a message or the simulation changes in a way two builds
+// can't play each other across.
+const PROTOCOL: u32 = 1;
+const DEFAULT_ADDRESS: &str = "127.0.0.1:7777";
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(3);
+// Both sides step the battle by exactly this much, never by their own frame
+// time, so the same inputs play out the same.
+const TICK: f32 = 1.0 / 60.0;
+// Ticks between picking a skill and it starting, time for it to reach the
+// other side before that tick comes up.
+const INPUT_DELAY: u64 = 6;
+const CHECKSUM_INTERVAL: u64 = 60;
+// Largest frame accepted, a body's share code is far below it.
+const MAX_FRAME: usize = 64 * 1024;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+enum Message {
+    Hello {
+        protocol: u32,
+        balance: u64,
+        body: String,
+        // Only the host sends one, the guest plays with it.
+        seed: Option<u64>,
+    },
+    // The skill `Skill::id` a side starts on `tick`, sent for every tick so a
+    // missing one always means it's still on its way.
+    Input {
+        tick: u64,
+        skill: Option<SkillId>,
+    },
+    Checksum {
+        tick: u64,
+        hash: u64,
+    },
+}
+
+// A length prefixed bincode frame.
+fn encode(message: &Message) -> Vec<u8> {
+    let data = bincode::serialize(message).expect("messages are always serializable");
+    let mut frame = (data.len() as u32).to_le_bytes().to_vec();
+    frame.extend(data);
+    frame
+}
+
+// Takes every whole frame off the front of `buffer`, leaving a partial one.
+fn decode(buffer: &mut Vec<u8>) -> io::Result<Vec<Message>> {
+    let mut messages = Vec::new();
+    let mut start = 0;
+    while let Some(header) = buffer.get(start..start + 4) {
+        let length = u32::from_le_bytes(header.try_into().unwrap()) as usize;
+        if length > MAX_FRAME {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("frame of {} bytes", length),
+            ));
+        }
+        let Some(data) = buffer.get(start + 4..start + 4 + length) else {
+            break;
+        };
+        messages.push(
+            bincode::deserialize(data)
+                .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?,
+        );
+        start += 4 + length;
+    }
+    buffer.drain(..start);
+    Ok(messages)
+}
+
+// Never blocks, whatever can't be written yet waits for the next flush.
+struct Connection {
+    stream: TcpStream,
+    incoming: Vec<u8>,
+    outgoing: Vec<u8>,
+    closed: bool,
+}
+
+impl Connection {
+    fn new(stream: TcpStream) -> io::Result<Self> {
+        stream.set_nonblocking(true)?;
+        stream.set_nodelay(true)?;
+        Ok(Self {
+            stream,
+            incoming: Vec::new(),
+            outgoing: Vec::new(),
+            closed: false,
+        })
+    }
+
+    fn send(&mut self, message: &Message) {
+        self.outgoing.extend(encode(message));
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        while !self.outgoing.is_empty() {
+            match self.stream.write(&self.outgoing) {
+                Ok(0) => return Err(io::ErrorKind::WriteZero.into()),
+                Ok(written) => {
+                    self.outgoing.drain(..written);
+                }
+                Err(error) if error.kind() == io::ErrorKind::WouldBlock => break,
+                Err(error) if error.kind() == io::ErrorKind::Interrupted => {}
+                Err(error) => return Err(error),
+            }
+        }
+        Ok(())
+    }
+
+    // Everything that arrived since the last call. Messages sent before the
+    // other side hung up still come through, `closed` says it did.
+    fn receive(&mut self) -> io::Result<Vec<Message>> {
+        let mut buffer = [0; 4096];
+        while !self.closed {
+            match self.stream.read(&mut buffer) {
+                Ok(0) => self.closed = true,
+                Ok(read) => self.incoming.extend(&buffer[..read]),
+                Err(error) if error.kind() == io::ErrorKind::WouldBlock => break,
+                Err(error) if error.kind() == io::ErrorKind::Interrupted => {}
+                Err(error) => return Err(error),
+            }
+        }
+        decode(&mut self.incoming)
+    }
+}
+
+fn fnv(bytes: impl IntoIterator<Item = u8>) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in bytes {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+// Two builds tuned differently would drift apart from the first hit.
+fn balance_hash(balance: &BalanceConfig) -> u64 {
+    fnv(format!("{:?}", balance).into_bytes())
+}
+
+// The skill each seat starts on each tick. Seat 0 is the host's body, on the
+// left on both screens.
+#[derive(Default)]
+struct InputLog {
+    seats: [BTreeMap<u64, Option<SkillId>>; 2],
+}
+
+impl InputLog {
+    fn record(&mut self, seat: usize, tick: u64, skill: Option<SkillId>) {
+        self.seats[seat].insert(tick, skill);
+    }
+
+    // Nothing was picked before the first inputs could arrive.
+    fn ready(&self, tick: u64) -> bool {
+        tick < INPUT_DELAY || self.seats.iter().all(|seat| seat.contains_key(&tick))
+    }
+
+    fn take(&mut self, tick: u64) -> [Option<SkillId>; 2] {
+        [0, 1].map(|seat| self.seats[seat].remove(&tick).flatten())
+    }
+}
+
+// A networked battle. Both sides run the whole simulation and only send what
+// their player picks, the battle only moves on once both picks for a tick are
+// in.
+struct Lockstep {
+    connection: Option<Connection>,
+    seat: usize,
+    // The bodies in seat order, the same two entities `Game` had before.
+    seats: [Entity; 2],
+    local_body: Body,
+    remote_body: Body,
+    tick: u64,
+    inputs: InputLog,
+    // Picks waiting for their body to finish what it's doing.
+    pending: [Option<SkillId>; 2],
+    checksums: [BTreeMap<u64, u64>; 2],
+    // Real time not yet stepped.
+    lag: f32,
+    last_real: Option<Instant>,
+}
+
+impl Lockstep {
+    fn remote(&self) -> usize {
+        1 - self.seat
+    }
+
+    // Files what the other side sent by tick.
+    fn read(&mut self, messages: impl IntoIterator<Item = Message>) {
+        let remote = self.remote();
+        for message in messages {
+            match message {
+                Message::Input { tick, skill } => self.inputs.record(remote, tick, skill),
+                Message::Checksum { tick, hash } => {
+                    self.checksums[remote].insert(tick, hash);
+                }
+                Message::Hello { .. } => {}
+            }
+        }
+    }
+}
+
+enum Lobby {
+    Listening(TcpListener),
+    // The guest's connection attempt, see `connect`.
+    Connecting(Mutex<Receiver<io::Result<TcpStream>>>),
+    // `seed` is the one the host sent, None on the guest's side.
+    Connected {
+        connection: Connection,
+        seed: Option<u64>,
+    },
+    // The connection went to the match, the lobby goes at the end of the frame.
+    Started,
+}
+
+#[derive(Component)]
+struct NetPanel;
+
+#[derive(Component, Clone, Copy)]
+enum NetButton {
+    Host,
+    Join,
+}
+
+#[derive(Component)]
+struct AddressInput;
+
+#[derive(Component)]
+struct NetStatus;
+
+fn spawn_panel_system(mut commands: Commands, fonts: Res<Fonts>, theme: Res<Theme>) {
+    let style = TextStyle {
+        font: fonts.bold(),
+        font_size: 22.0,
+        color: Color::BLACK,
+    };
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    left: Val::Px(10.0),
+                    bottom: Val::Px(10.0),
+                    ..default()
+                },
+                flex_direction: FlexDirection::ColumnReverse,
+                align_items: AlignItems::FlexStart,
+                ..default()
+            },
+            color: Color::NONE.into(),
+            ..default()
+        })
+        .insert(NetPanel)
+        .with_children(|parent| {
+            let input = TextInput::new(DEFAULT_ADDRESS, &t!("net.address"), 64);
+            spawn_text_input(parent, &fonts, input).insert(AddressInput);
+            parent
+                .spawn_bundle(NodeBundle {
+                    color: Color::NONE.into(),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    for (button, label) in
+                        [(NetButton::Host, "net.host"), (NetButton::Join, "net.join")]
+                    {
+                        parent
+                            .spawn_bundle(ButtonBundle {
+                                style: Style {
+                                    size: Size::new(Val::Px(150.0), Val::Px(45.0)),
+                                    margin: UiRect::all(Val::Px(5.0)),
+                                    justify_content: JustifyContent::Center,
+                                    align_items: AlignItems::Center,
+                                    ..default()
+                                },
+                                color: theme.normal_button.into(),
+                                ..default()
+                            })
+                            .insert(button)
+                            .with_children(|parent| {
+                                parent.spawn_bundle(TextBundle::from_section(
+                                    t!(label),
+                                    style.clone(),
+                                ));
+                            });
+                    }
+                });
+            parent
+                .spawn_bundle(TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        color: Color::WHITE,
+                        ..style.clone()
+                    },
+                ))
+                .insert(NetStatus);
+        });
+}
+
+fn despawn_panel_system(mut commands: Commands, panels: Query<Entity, With<NetPanel>>) {
+    for panel in &panels {
+        commands.entity(panel).despawn_recursive();
+    }
+    // Leaving the menu any other way than by starting the match gives up on it.
+    commands.remove_resource::<Lobby>();
+}
+
+fn set_status(statuses: &mut Query<&mut Text, With<NetStatus>>, status: String) {
+    for mut text in statuses {
+        text.sections[0].value = status.clone();
+    }
+}
+
+fn hello(balance: &BalanceConfig, body: &Body, seed: Option<u64>) -> Message {
+    Message::Hello {
+        protocol: PROTOCOL,
+        balance: balance_hash(balance),
+        body: body.to_share_code(),
+        seed,
+    }
+}
+
+// Connects on its own thread, an unreachable host would otherwise freeze the
+// game for the whole timeout.
+fn connect(address: SocketAddr) -> Lobby {
+    let (sender, receiver) = mpsc::channel();
+    std::thread::spawn(move || {
+        let _ = sender.send(TcpStream::connect_timeout(&address, CONNECT_TIMEOUT));
+    });
+    Lobby::Connecting(Mutex::new(receiver))
+}
+
+fn net_button_system(
+    theme: Res<Theme>,
+    mut commands: Commands,
+    mut buttons: Query<(&Interaction, &NetButton, &mut UiColor), Changed<Interaction>>,
+    addresses: Query<&TextInput, With<AddressInput>>,
+    mut statuses: Query<&mut Text, With<NetStatus>>,
+) {
+    for (interaction, button, mut color) in &mut buttons {
+        match interaction {
+            Interaction::Clicked => color.0 = theme.pressed_button,
+            Interaction::Hovered => {
+                color.0 = theme.hovered_button;
+                continue;
+            }
+            Interaction::None => {
+                color.0 = theme.normal_button;
+                continue;
+            }
+        }
+        let address = addresses
+            .get_single()
+            .map_or(DEFAULT_ADDRESS, |input| input.value.trim())
+            .to_string();
+        let started = match button {
+            NetButton::Host => TcpListener::bind(&address).and_then(|listener| {
+                listener.set_nonblocking(true)?;
+                Ok(Lobby::Listening(listener))
+            }),
+            NetButton::Join => address
+                .parse::<SocketAddr>()
+                .map_err(|error| io::Error::new(io::ErrorKind::InvalidInput, error))
+                .map(connect),
+        };
+        match started {
+            Ok(lobby) => {
+                let status = match lobby {
+                    Lobby::Listening(_) => t!("net.waiting", address = &address),
+                    _ => t!("net.connecting", address = &address),
+                };
+                set_status(&mut statuses, status);
+                commands.insert_resource(lobby);
+            }
+            Err(error) => {
+                set_status(&mut statuses, t!("net.failed", error = error.to_string()));
+                commands.remove_resource::<Lobby>();
+            }
+        }
+    }
+}
+
+// Waits for the other side and its Hello, then starts the match.
+fn lobby_system(
+    mut commands: Commands,
+    lobby: Option<ResMut<Lobby>>,
+    game: Res<Game>,
+    balance: Res<BalanceConfig>,
+    mut rng: ResMut<GameRng>,
+    mut state: ResMut<State<AppState>>,
+    mut swaps: EventWriter<SwapBody>,
+    bodies: Query<&Body>,
+    mut statuses: Query<&mut Text, With<NetStatus>>,
+) {
+    let Some(mut lobby) = lobby else {
+        return;
+    };
+    let Ok(local_body) = bodies.get(game.player) else {
+        return;
+    };
+    let mut fail = |statuses: &mut Query<&mut Text, With<NetStatus>>, status: String| {
+        set_status(statuses, status);
+        commands.remove_resource::<Lobby>();
+    };
+    if let Lobby::Listening(listener) = &*lobby {
+        match listener
+            .accept()
+            .and_then(|(stream, _)| Connection::new(stream))
+        {
+            Ok(mut connection) => {
+                let seed = rng.gen();
+                connection.send(&hello(&balance, local_body, Some(seed)));
+                *lobby = Lobby::Connected {
+                    connection,
+                    seed: Some(seed),
+                };
+            }
+            Err(error) if error.kind() == io::ErrorKind::WouldBlock => return,
+            Err(error) => {
+                return fail(&mut statuses, t!("net.failed", error = error.to_string()));
+            }
+        }
+    }
+    if let Lobby::Connecting(receiver) = &mut *lobby {
+        let connected = match receiver.get_mut().map(|receiver| receiver.try_recv()) {
+            Ok(Ok(connected)) => connected,
+            Ok(Err(TryRecvError::Empty)) => return,
+            _ => Err(io::ErrorKind::ConnectionAborted.into()),
+        };
+        match connected.and_then(Connection::new) {
+            Ok(mut connection) => {
+                connection.send(&hello(&balance, local_body, None));
+                *lobby = Lobby::Connected {
+                    connection,
+                    seed: None,
+                };
+            }
+            Err(error) => {
+                return fail(&mut statuses, t!("net.failed", error = error.to_string()));
+            }
+        }
+    }
+    let Lobby::Connected { connection, seed } = &mut *lobby else {
+        return;
+    };
+    let host = seed.is_some();
+    let messages = match connection.flush().and_then(|_| connection.receive()) {
+        Ok(messages) => messages,
+        Err(error) => return fail(&mut statuses, t!("net.failed", error = error.to_string())),
+    };
+    // The other side may already be ticking, whatever came after its Hello
+    // goes to the match.
+    let mut messages = messages.into_iter();
+    let Some(Message::Hello {
+        protocol,
+        balance: their_balance,
+        body,
+        seed: their_seed,
+    }) = messages.next()
+    else {
+        if connection.closed {
+            fail(&mut statuses, t!("net.disconnected"));
+        }
+        return;
+    };
+    if protocol != PROTOCOL || their_balance != balance_hash(&balance) {
+        return fail(&mut statuses, t!("net.mismatch"));
+    }
+    let remote_body = match Body::from_share_code(&body) {
+        Ok(body) => body,
+        Err(error) => {
+            return fail(
+                &mut statuses,
+                t!("net.failed", error = format!("{:?}", error)),
+            );
+        }
+    };
+    // Exactly one side picked the seed.
+    let seed = match (*seed, their_seed) {
+        (Some(seed), None) | (None, Some(seed)) => seed,
+        _ => return fail(&mut statuses, t!("net.mismatch")),
+    };
+    let Lobby::Connected { connection, .. } = std::mem::replace(&mut *lobby, Lobby::Started) else {
+        unreachable!();
+    };
+    commands.remove_resource::<Lobby>();
+
+    // The same two entities play on both sides, so components iterate in the
+    // same order. Only which one is `Game::player` differs.
+    let seat = if host { 0 } else { 1 };
+    let seats = [game.player, game.enemy];
+    let mut bodies = [local_body.clone(), remote_body.clone()];
+    if !host {
+        bodies.reverse();
+    }
+    for (entity, body) in seats.into_iter().zip(bodies) {
+        swaps.send(SwapBody { entity, body });
+        commands
+            .entity(entity)
+            .insert(ExternalInput)
+            .remove::<AiController>();
+    }
+    commands.insert_resource(Game {
+        player: seats[seat],
+        enemy: seats[1 - seat],
+    });
+    commands.insert_resource(GameRng::new(seed));
+    commands.insert_resource(Ruleset::default());
+    let mut lockstep = Lockstep {
+        connection: Some(connection),
+        seat,
+        seats,
+        local_body: local_body.clone(),
+        remote_body,
+        tick: 0,
+        inputs: InputLog::default(),
+        pending: [None, None],
+        checksums: [BTreeMap::new(), BTreeMap::new()],
+        lag: 0.0,
+        last_real: None,
+    };
+    lockstep.read(messages);
+    commands.insert_resource(lockstep);
+    let _ = state.set(AppState::Battle);
+}
+
+// The host's body starts on the left on both screens.
+fn seat_system(lockstep: Option<Res<Lockstep>>, mut bodies: Query<(&mut Transform, &mut Facing)>) {
+    let Some(lockstep) = lockstep else {
+        return;
+    };
+    for (entity, x, side) in [
+        (lockstep.seats[0], -4.0, Facing::Right),
+        (lockstep.seats[1], 4.0, Facing::Left),
+    ] {
+        if let Ok((mut transform, mut facing)) = bodies.get_mut(entity) {
+            transform.translation.x = x;
+            *facing = side;
+        }
+    }
+}
+
+fn state_hash(seats: &[Entity; 2], bodies: &Query<(&Stats, &Transform)>) -> u64 {
+    fnv(seats.iter().flat_map(|entity| {
+        let (stats, transform) = bodies.get(*entity).ok().unzip();
+        [
+            stats.map_or(0, |stats| stats.health.to_bits()),
+            stats.map_or(0, |stats| stats.energy.to_bits()),
+            transform.map_or(0, |transform| transform.translation.x.to_bits()),
+        ]
+        .into_iter()
+        .flat_map(u32::to_le_bytes)
+    }))
+}
+
+// Reads what arrived and works out whether this frame gets a tick, an error
+// ends the match.
+fn step(
+    lockstep: &mut Lockstep,
+    battle: bool,
+    now: Instant,
+    pick: Option<SkillId>,
+    commands: &mut Commands,
+    bodies: &Query<(&Stats, &Transform)>,
+    runtimes: &Query<&SkillRuntime>,
+    busy: &Query<(), Or<(With<Animation>, With<Recovery>)>>,
+) -> Result<bool, String> {
+    let (local, remote) = (lockstep.seat, lockstep.remote());
+    let Some(connection) = lockstep.connection.as_mut() else {
+        return Ok(false);
+    };
+    let messages = connection
+        .receive()
+        .map_err(|error| t!("net.failed", error = error.to_string()))?;
+    lockstep.read(messages);
+    let connection = lockstep.connection.as_mut().expect("checked above");
+    let desynced = lockstep.checksums[local].iter().find(|(tick, hash)| {
+        lockstep.checksums[remote]
+            .get(tick)
+            .is_some_and(|theirs| theirs != *hash)
+    });
+    if let Some((tick, _)) = desynced {
+        error!(
+            "net: battle state differs from the other side's at tick {}",
+            tick
+        );
+        return Err(t!("net.desync", tick = *tick));
+    }
+
+    let real = lockstep.last_real.map_or(0.0, |last| {
+        now.saturating_duration_since(last).as_secs_f32()
+    });
+    lockstep.last_real = Some(now);
+    if !battle {
+        return Ok(false);
+    }
+    // Never more than a tick a frame, so a stall doesn't end in a burst.
+    lockstep.lag = (lockstep.lag + real).min(TICK * 2.0);
+    if lockstep.lag < TICK || !lockstep.inputs.ready(lockstep.tick) {
+        if connection.closed {
+            return Err(t!("net.disconnected"));
+        }
+        connection.flush().map_err(|error| error.to_string())?;
+        return Ok(false);
+    }
+    lockstep.lag -= TICK;
+
+    let tick = lockstep.tick;
+    connection.send(&Message::Input {
+        tick: tick + INPUT_DELAY,
+        skill: pick.clone(),
+    });
+    lockstep.inputs.record(local, tick + INPUT_DELAY, pick);
+    for (pending, pick) in lockstep.pending.iter_mut().zip(lockstep.inputs.take(tick)) {
+        if pick.is_some() {
+            *pending = pick;
+        }
+    }
+    if tick.is_multiple_of(CHECKSUM_INTERVAL) {
+        let hash = state_hash(&lockstep.seats, bodies);
+        lockstep.checksums[local].insert(tick, hash);
+        connection.send(&Message::Checksum { tick, hash });
+    }
+    connection.flush().map_err(|error| error.to_string())?;
+    // Same rules as use_skill_system, only for both bodies. The spare arm of a
+    // swap is only known on its owner's side, the other couldn't follow it.
+    for (entity, pending) in lockstep.seats.iter().zip(&mut lockstep.pending) {
+        let Some(skill) = pending.as_ref() else {
+            continue;
+        };
+        if busy.contains(*entity) {
+            continue;
+        }
+        if let Ok((stats, _)) = bodies.get(*entity) {
+            if stats.skill(skill).is_some_and(|found| {
+                let runtime = runtimes.get(*entity).ok();
+                !matches!(found, Skill::SwapLimb)
+                    && energy::can_afford(stats, found)
+                    && ammo::loaded(runtime, found)
+                    && runtime::ready(runtime, found)
+            }) {
+                commands
+                    .entity(*entity)
+                    .insert(Animation::new(skill.clone()));
+            }
+        }
+        *pending = None;
+    }
+    lockstep.tick += 1;
+    Ok(true)
+}
+
+// Runs right after bevy measures the frame and decides how much battle time it
+// gets: one tick once both sides' inputs for it are in, none otherwise.
+fn lockstep_system(
+    mut commands: Commands,
+    lockstep: Option<ResMut<Lockstep>>,
+    mut time: ResMut<Time>,
+    mut time_scale: ResMut<TimeScale>,
+    mut state: ResMut<State<AppState>>,
+    mut use_skill: ResMut<UseSkill>,
+    mut toasts: EventWriter<Toast>,
+    bodies: Query<(&Stats, &Transform)>,
+    runtimes: Query<&SkillRuntime>,
+    busy: Query<(), Or<(With<Animation>, With<Recovery>)>>,
+) {
+    let Some(mut lockstep) = lockstep else {
+        return;
+    };
+    let Some(now) = time.last_update() else {
+        return;
+    };
+    if lockstep.connection.is_none() {
+        return;
+    }
+    let battle = *state.current() == AppState::Battle;
+    // Picks only count on a tick, between them they wait here.
+    let pick = if battle { use_skill.clone() } else { None };
+    match step(
+        &mut lockstep,
+        battle,
+        now,
+        pick,
+        &mut commands,
+        &bodies,
+        &runtimes,
+        &busy,
+    ) {
+        Ok(true) => {
+            **use_skill = None;
+            time_scale.0 = 1.0;
+            time.update_with_instant(now + Duration::from_secs_f32(TICK));
+        }
+        Ok(false) => time.update_with_instant(now),
+        Err(message) => {
+            lockstep.connection = None;
+            time.update_with_instant(now);
+            toasts.send(Toast(message));
+            let _ = state.replace(AppState::PreBattle);
+        }
+    }
+}
+
+// A match ends with the battle, the bodies go back to how they were before it.
+fn hang_up_system(lockstep: Option<ResMut<Lockstep>>) {
+    if let Some(mut lockstep) = lockstep {
+        lockstep.connection = None;
+    }
+}
+
+fn restore_system(mut commands: Commands, lockstep: Option<Res<Lockstep>>) {
+    let Some(lockstep) = lockstep else {
+        return;
+    };
+    let [player, enemy] = lockstep.seats;
+    commands
+        .entity(player)
+        .insert(lockstep.local_body.clone())
+        .remove::<ExternalInput>();
+    commands
+        .entity(enemy)
+        .insert(lockstep.remote_body.clone())
+        .insert(AiController::default())
+        .remove::<ExternalInput>();
+    commands.insert_resource(Game { player, enemy });
+    commands.remove_resource::<Lockstep>();
+}
+
+pub struct NetPlugin;
+
+impl Plugin for NetPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_to_stage(CoreStage::First, lockstep_system.after(TimeSystem))
+            .add_system_set(
+                SystemSet::on_enter(AppState::PreBattle)
+                    .with_system(restore_system)
+                    .with_system(spawn_panel_system),
+            )
+            .add_system_set(
+                SystemSet::on_update(AppState::PreBattle)
+                    .with_system(net_button_system)
+                    .with_system(lobby_system.after(net_button_system)),
+            )
+            .add_system_set(
+                SystemSet::on_exit(AppState::PreBattle).with_system(despawn_panel_system),
+            )
+            .add_system_set(
+                SystemSet::on_enter(AppState::Battle)
+                    .with_system(seat_system.after(reset_battle_system)),
+            )
+            .add_system_set(SystemSet::on_enter(AppState::GameOver).with_system(hang_up_system));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::SystemState;
+
+    use super::*;
+    use crate::body::compute_stats;
+
+    #[test]
+    fn frames_survive_arriving_in_pieces() {
+        let messages = [
+            Message::Hello {
+                protocol: PROTOCOL,
+                balance: 7,
+                body: Body::default().to_share_code(),
+                seed: Some(42),
+            },
+            Message::Input {
+                tick: 3,
+                skill: Some(Skill::WalkForward.id()),
+            },
+            Message::Input {
+                tick: 4,
+                skill: None,
+            },
+            Message::Checksum { tick: 60, hash: 9 },
+        ];
+        let bytes = messages.iter().flat_map(encode).collect::<Vec<_>>();
+        for chunk in [1, 3, 5, 64] {
+            let mut buffer = Vec::new();
+            let mut received = Vec::new();
+            for piece in bytes.chunks(chunk) {
+                buffer.extend(piece);
+                received.extend(decode(&mut buffer).unwrap());
+            }
+            assert_eq!(received, messages);
+            assert!(buffer.is_empty());
+        }
+        let mut garbage = u32::MAX.to_le_bytes().to_vec();
+        assert!(decode(&mut garbage).is_err());
+    }
+
+    #[test]
+    fn lockstep_waits_for_the_other_side() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let mut guest = Connection::new(TcpStream::connect(address).unwrap()).unwrap();
+        let mut host = Connection::new(listener.accept().unwrap().0).unwrap();
+
+        let mut log = InputLog::default();
+        // Nobody could have picked anything for the first ticks.
+        assert!((0..INPUT_DELAY).all(|tick| log.ready(tick)));
+        log.record(0, INPUT_DELAY, Some(Skill::WalkForward.id()));
+        assert!(!log.ready(INPUT_DELAY));
+
+        guest.send(&Message::Input {
+            tick: INPUT_DELAY,
+            skill: None,
+        });
+        guest.flush().unwrap();
+        let mut received = Vec::new();
+        for _ in 0..100 {
+            received.extend(host.receive().unwrap());
+            if !received.is_empty() {
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        for message in received {
+            if let Message::Input { tick, skill } = message {
+                log.record(1, tick, skill);
+            }
+        }
+        assert!(log.ready(INPUT_DELAY));
+        assert_eq!(log.take(INPUT_DELAY), [Some(Skill::WalkForward.id()), None]);
+
+        drop(guest);
+        for _ in 0..100 {
+            host.receive().unwrap();
+            if host.closed {
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        assert!(host.closed);
+    }
+
+    // Steps one tick with both seats' picks due, what each seat started.
+    fn started(stats: &Stats, runtimes: [SkillRuntime; 2], pick: SkillId) -> [Option<SkillId>; 2] {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let _guest = Connection::new(TcpStream::connect(address).unwrap()).unwrap();
+        let host = Connection::new(listener.accept().unwrap().0).unwrap();
+
+        let mut world = World::new();
+        let seats = runtimes.map(|runtime| {
+            world
+                .spawn()
+                .insert_bundle((stats.clone(), Transform::default(), runtime))
+                .id()
+        });
+        let mut lockstep = Lockstep {
+            connection: Some(host),
+            seat: 0,
+            seats,
+            local_body: Body::default(),
+            remote_body: Body::default(),
+            tick: 0,
+            inputs: InputLog::default(),
+            pending: [Some(pick.clone()), Some(pick)],
+            checksums: [BTreeMap::new(), BTreeMap::new()],
+            lag: TICK,
+            last_real: None,
+        };
+        let mut state: SystemState<(
+            Commands,
+            Query<(&Stats, &Transform)>,
+            Query<&SkillRuntime>,
+            Query<(), Or<(With<Animation>, With<Recovery>)>>,
+        )> = SystemState::new(&mut world);
+        let (mut commands, bodies, runtimes, busy) = state.get_mut(&mut world);
+        let stepped = step(
+            &mut lockstep,
+            true,
+            Instant::now(),
+            None,
+            &mut commands,
+            &bodies,
+            &runtimes,
+            &busy,
+        );
+        assert_eq!(stepped, Ok(true));
+        state.apply(&mut world);
+        // A pick that can't start is dropped rather than kept for later.
+        assert_eq!(lockstep.pending, [None, None]);
+        seats.map(|seat| {
+            world
+                .get::<Animation>(seat)
+                .map(|animation| animation.skill.clone())
+        })
+    }
+
+    #[test]
+    fn picks_on_cooldown_are_dropped_on_both_sides() {
+        let stats = compute_stats(&Body::default());
+        let jab = stats
+            .skills
+            .iter()
+            .find(|skill| matches!(skill, Skill::BasicMelee(_)))
+            .unwrap()
+            .clone();
+        // Only the first seat is still cooling down from its last jab.
+        let runtimes = [1.0, 0.0].map(|cooldown| SkillRuntime::with_cooldown(&jab, cooldown));
+        assert_eq!(started(&stats, runtimes, jab.id()), [None, Some(jab.id())]);
+    }
+
+    #[test]
+    fn limb_swaps_never_start_in_a_match() {
+        let mut stats = compute_stats(&Body::default());
+        stats.skills.push(Skill::SwapLimb);
+        let runtimes = [SkillRuntime::default(), SkillRuntime::default()];
+        assert_eq!(
+            started(&stats, runtimes, Skill::SwapLimb.id()),
+            [None, None]
+        );
+    }
+
+    #[test]
+    fn joining_connects_off_the_main_thread() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let Lobby::Connecting(receiver) = connect(listener.local_addr().unwrap()) else {
+            panic!("joining went straight to a result");
+        };
+        let receiver = receiver.into_inner().unwrap();
+        let stream = receiver.recv_timeout(CONNECT_TIMEOUT * 2).unwrap().unwrap();
+        assert_eq!(stream.peer_addr().unwrap(), listener.local_addr().unwrap());
+    }
+}
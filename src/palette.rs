@@ -0,0 +1,133 @@
+use std::collections::HashMap;
+
+use bevy::{
+    prelude::*,
+    render::render_resource::{Extent3d, TextureDimension, TextureFormat},
+};
+
+use crate::{
+    body::{Material, PaletteMode, PartMaterial},
+    profile::Profile,
+};
+
+const PATTERN_SIZE: u32 = 16;
+// Fraction of the part's shorter side covered by its badge.
+const BADGE_SIZE: f32 = 0.6;
+
+#[derive(Component)]
+struct PatternBadge;
+
+// Whether a pixel of the material's pattern is lit, matches `Material::pattern`.
+fn lit(material: Material, x: u32, y: u32) -> bool {
+    match material {
+        Material::Wood => y % 4 < 2,
+        Material::Stone => x % 4 == 1 && y % 4 == 1,
+        Material::Plastic => {
+            let (dx, dy) = ((x % 8) as i32 - 4, (y % 8) as i32 - 4);
+            (5..=10).contains(&(dx * dx + dy * dy))
+        }
+        Material::Bronze => (x + y) % 4 < 2,
+        Material::Aluminum => x.is_multiple_of(4) || y.is_multiple_of(4),
+        Material::Steel => x % 4 < 2,
+        Material::Carbon => (x + y).is_multiple_of(4) || (x + 4 - y % 4).is_multiple_of(4),
+        Material::Rust => {
+            let wave = ((x as f32 * 0.8).sin() * 1.5).round() as i32;
+            (y as i32 + wave).rem_euclid(5) == 0
+        }
+    }
+}
+
+fn render_pattern(material: Material) -> Image {
+    let mut data = Vec::with_capacity((PATTERN_SIZE * PATTERN_SIZE * 4) as usize);
+    for y in 0..PATTERN_SIZE {
+        for x in 0..PATTERN_SIZE {
+            // Light lines on a dark backing read on any part color.
+            data.extend(if lit(material, x, y) {
+                [255, 255, 255, 255]
+            } else {
+                [0, 0, 0, 140]
+            });
+        }
+    }
+    Image::new(
+        Extent3d {
+            width: PATTERN_SIZE,
+            height: PATTERN_SIZE,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+    )
+}
+
+fn sync_palette_system(profile: Res<Profile>, mut palette: ResMut<PaletteMode>) {
+    if profile.is_changed() && *palette != profile.settings.palette {
+        *palette = profile.settings.palette;
+    }
+}
+
+// Recolors the sprites in place, rebuilding the bodies would reset their stats.
+fn tint_system(palette: Res<PaletteMode>, mut parts: Query<(&PartMaterial, &mut Sprite)>) {
+    if !palette.is_changed() {
+        return;
+    }
+    for (part, mut sprite) in &mut parts {
+        sprite.color = part.color(*palette);
+    }
+}
+
+fn pattern_badge_system(
+    mut commands: Commands,
+    palette: Res<PaletteMode>,
+    mut images: ResMut<Assets<Image>>,
+    mut textures: Local<HashMap<Material, Handle<Image>>>,
+    parts: Query<(Entity, &PartMaterial, &Transform)>,
+    added: Query<(), Added<PartMaterial>>,
+    badges: Query<Entity, With<PatternBadge>>,
+) {
+    if palette.is_changed() {
+        for badge in &badges {
+            commands.entity(badge).despawn_recursive();
+        }
+    }
+    // The default palette keeps the original look, patterns are opt-in.
+    if *palette == PaletteMode::Default {
+        return;
+    }
+    for (entity, part, transform) in &parts {
+        if !palette.is_changed() && !added.contains(entity) {
+            continue;
+        }
+        let texture = textures
+            .entry(part.material)
+            .or_insert_with(|| images.add(render_pattern(part.material)))
+            .clone();
+        // Undo the part's own stretch so the badge stays square.
+        let scale = transform.scale;
+        let side = scale.x.min(scale.y) * BADGE_SIZE;
+        commands.entity(entity).with_children(|parent| {
+            parent
+                .spawn_bundle(SpriteBundle {
+                    texture,
+                    transform: Transform::from_xyz(0.0, 0.5, 0.01).with_scale(Vec3::new(
+                        side / scale.x / PATTERN_SIZE as f32,
+                        side / scale.y / PATTERN_SIZE as f32,
+                        1.0,
+                    )),
+                    ..default()
+                })
+                .insert(PatternBadge);
+        });
+    }
+}
+
+pub struct PalettePlugin;
+
+impl Plugin for PalettePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(sync_palette_system)
+            .add_system(tint_system.after(sync_palette_system))
+            .add_system(pattern_badge_system.after(sync_palette_system));
+    }
+}
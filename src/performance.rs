@@ -0,0 +1,195 @@
+use bevy::prelude::*;
+
+use crate::{
+    churn::{DeferredSpawner, CHURN_BUDGET},
+    profile::Profile,
+};
+
+const LOW_SPEC_PARTICLES: usize = 100;
+const LOW_SPEC_CHURN_BUDGET: usize = 24;
+const LOW_SPEC_DEBUG_REFRESH: f32 = 0.5;
+
+// How much the windowed game may do each frame. Read by every system that
+// can be turned down, picked from the low-spec setting.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PerformanceProfile {
+    // Sparks, dust puffs and weather particles alive at once.
+    pub max_particles: Option<usize>,
+    pub shadows: bool,
+    // Rain and sandstorm particles, the sky still changes color.
+    pub weather_ambience: bool,
+    // Seconds between rebuilds of the debug overlay's text.
+    pub debug_refresh: f32,
+    // See `DeferredSpawner::budget`.
+    pub churn_budget: usize,
+}
+
+impl Default for PerformanceProfile {
+    fn default() -> Self {
+        Self {
+            max_particles: None,
+            shadows: true,
+            weather_ambience: true,
+            debug_refresh: 0.0,
+            churn_budget: CHURN_BUDGET,
+        }
+    }
+}
+
+impl PerformanceProfile {
+    pub fn low_spec() -> Self {
+        Self {
+            max_particles: Some(LOW_SPEC_PARTICLES),
+            shadows: false,
+            weather_ambience: false,
+            debug_refresh: LOW_SPEC_DEBUG_REFRESH,
+            churn_budget: LOW_SPEC_CHURN_BUDGET,
+        }
+    }
+
+    pub fn for_settings(low_spec: bool) -> Self {
+        if low_spec {
+            Self::low_spec()
+        } else {
+            Self::default()
+        }
+    }
+}
+
+// On every particle, whichever module spawned it.
+#[derive(Component)]
+pub struct PooledParticle;
+
+// Particles alive, counted at the start of the frame. Spawners take what
+// they spawn out of it, so together they stay under the cap.
+#[derive(Default)]
+pub struct ParticlePool {
+    alive: usize,
+}
+
+impl ParticlePool {
+    // How many of `wanted` particles can be spawned, taken from the pool.
+    pub fn take(&mut self, profile: &PerformanceProfile, wanted: usize) -> usize {
+        let room = profile
+            .max_particles
+            .map_or(wanted, |max| max.saturating_sub(self.alive));
+        let taken = wanted.min(room);
+        self.alive += taken;
+        taken
+    }
+}
+
+fn sync_performance_system(profile: Res<Profile>, mut performance: ResMut<PerformanceProfile>) {
+    let wanted = PerformanceProfile::for_settings(profile.settings.low_spec);
+    if profile.is_changed() && *performance != wanted {
+        *performance = wanted;
+    }
+}
+
+fn count_particles_system(
+    mut pool: ResMut<ParticlePool>,
+    particles: Query<(), With<PooledParticle>>,
+) {
+    pool.alive = particles.iter().count();
+}
+
+fn churn_budget_system(performance: Res<PerformanceProfile>, mut spawner: ResMut<DeferredSpawner>) {
+    if performance.is_changed() {
+        spawner.budget = performance.churn_budget;
+    }
+}
+
+// After BattlePlugin, it sets the budget of the spawner that adds.
+pub struct PerformancePlugin;
+
+impl Plugin for PerformancePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PerformanceProfile>()
+            .init_resource::<ParticlePool>()
+            .add_system_to_stage(CoreStage::PreUpdate, sync_performance_system)
+            .add_system_to_stage(CoreStage::First, count_particles_system)
+            .add_system(churn_budget_system);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        battle::{Ground, StrideEvent},
+        body::{update_body_system, Body, BodyBundle, PaletteMode},
+        churn::ChurnPlugin,
+        dust::DustPlugin,
+        fixtures::seeded_bodies,
+        weather::{SkyPlugin, Weather},
+    };
+
+    #[test]
+    fn a_low_spec_flurry_stays_within_the_limits() {
+        let mut app = App::new();
+        let mut profile = Profile::default();
+        profile.settings.low_spec = true;
+        app.init_resource::<PaletteMode>()
+            .init_resource::<Time>()
+            .init_resource::<Ground>()
+            .insert_resource(ClearColor::default())
+            .insert_resource(Weather::Rain)
+            .insert_resource(profile)
+            .add_event::<StrideEvent>()
+            .add_plugin(ChurnPlugin)
+            .add_plugin(PerformancePlugin)
+            .add_plugin(DustPlugin)
+            .add_plugin(SkyPlugin)
+            .add_system(update_body_system);
+        let entities = seeded_bodies(50, 4, 4)
+            .into_iter()
+            .map(|body| {
+                app.world
+                    .spawn()
+                    .insert_bundle(BodyBundle { body, ..default() })
+                    .id()
+            })
+            .collect::<Vec<_>>();
+
+        let limits = PerformanceProfile::low_spec();
+        let max_particles = limits.max_particles.unwrap();
+        let mut most_particles = 0;
+        for frame in 0..60 {
+            // Every body steps and gets rebuilt every frame.
+            for (i, entity) in entities.iter().enumerate() {
+                app.world
+                    .get_mut::<Body>(*entity)
+                    .unwrap()
+                    .set_name(&format!("Flurry {}", frame));
+                app.world.send_event(StrideEvent {
+                    entity: *entity,
+                    leg_index: 0,
+                    position: Vec2::new(i as f32, 0.0),
+                });
+            }
+            let before = app.world.entities().len();
+            app.update();
+            let stats = app.world.resource::<DeferredSpawner>().stats();
+            assert!(stats.spawned + stats.despawned <= limits.churn_budget);
+            let particles = app
+                .world
+                .query_filtered::<(), With<PooledParticle>>()
+                .iter(&app.world)
+                .count();
+            assert!(particles <= max_particles, "{} particles", particles);
+            most_particles = most_particles.max(particles);
+            // Particles and the spawner's share are all a frame adds.
+            let added = app.world.entities().len().saturating_sub(before) as usize;
+            assert!(
+                added <= max_particles + limits.churn_budget,
+                "{} added",
+                added
+            );
+        }
+        assert_eq!(most_particles, max_particles);
+        assert_eq!(
+            *app.world.resource::<PerformanceProfile>(),
+            PerformanceProfile::low_spec()
+        );
+    }
+}
@@ -0,0 +1,364 @@
+use bevy::prelude::*;
+
+use crate::{
+    battle::damage_system,
+    body::Stats,
+    effects::{ActiveEffect, Polarity},
+    timer::{SimClock, SUDDEN_DEATH},
+    AppState, DamageEvent, DamageSource, Invulnerable,
+};
+
+// Every periodic effect advances in steps of this size, whatever the frame rate
+// and time scale, so a tick lands at the same moment of the fight either way.
+pub const STEP: f32 = 1.0 / 60.0;
+
+// Ticks a little early rather than a step late when steps don't add up exactly
+// to the interval.
+const EPSILON: f32 = 1e-4;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TickPayload {
+    // Health taken every tick, negative heals.
+    Damage(f32),
+    // The nth tick takes n times this much health.
+    RampingDamage(f32),
+    // Energy given every tick, negative drains.
+    Energy(f32),
+}
+
+impl TickPayload {
+    pub fn polarity(self) -> Polarity {
+        match self {
+            TickPayload::Damage(amount) | TickPayload::RampingDamage(amount) if amount > 0.0 => {
+                Polarity::Debuff
+            }
+            TickPayload::Energy(amount) if amount < 0.0 => Polarity::Debuff,
+            _ => Polarity::Buff,
+        }
+    }
+}
+
+// An effect ticking on `target`. Lives on its own entity so a body can have any
+// number of them, and goes away after the last tick.
+#[derive(Component, Clone, Debug)]
+pub struct PeriodicEffect {
+    pub target: Entity,
+    pub source: Option<Entity>,
+    // Picks the strings shown for it, see ActiveEffect.
+    pub id: &'static str,
+    pub interval: f32,
+    // None for effects lasting until removed.
+    pub remaining_ticks: Option<u32>,
+    pub payload: TickPayload,
+    // How much of a full interval and payload the last tick gets, below 1 when
+    // the duration isn't a whole number of intervals.
+    last_tick: f32,
+    since_tick: f32,
+    fired: u32,
+}
+
+impl PeriodicEffect {
+    pub fn until_removed(
+        target: Entity,
+        id: &'static str,
+        interval: f32,
+        payload: TickPayload,
+    ) -> Self {
+        Self {
+            target,
+            source: None,
+            id,
+            interval,
+            remaining_ticks: None,
+            payload,
+            last_tick: 1.0,
+            since_tick: 0.0,
+            fired: 0,
+        }
+    }
+
+    // Ticks every `interval` for `duration` seconds. A duration that isn't a
+    // whole number of intervals ends on a shorter, weaker tick. Only the debug
+    // console hands these out so far.
+    #[cfg_attr(not(debug_assertions), allow(dead_code))]
+    pub fn lasting(
+        target: Entity,
+        id: &'static str,
+        duration: f32,
+        interval: f32,
+        payload: TickPayload,
+    ) -> Self {
+        let intervals = duration / interval;
+        let ticks = (intervals - EPSILON).ceil().max(1.0);
+        Self {
+            remaining_ticks: Some(ticks as u32),
+            last_tick: (intervals - (ticks - 1.0)).clamp(EPSILON, 1.0),
+            ..Self::until_removed(target, id, interval, payload)
+        }
+    }
+
+    pub fn finished(&self) -> bool {
+        self.remaining_ticks == Some(0)
+    }
+
+    fn on_last_tick(&self) -> bool {
+        self.remaining_ticks == Some(1)
+    }
+
+    // Seconds until the last tick.
+    pub fn remaining(&self) -> Option<f32> {
+        self.remaining_ticks.map(|ticks| match ticks {
+            0 => 0.0,
+            ticks => {
+                (ticks - 1) as f32 * self.interval + self.interval * self.last_tick
+                    - self.since_tick
+            }
+        })
+    }
+
+    // Health and energy gained per second at the next tick's strength, what
+    // `advance` hands out spread over the interval. Damage counts as lost health.
+    pub fn per_second(&self) -> (f32, f32) {
+        let (health, energy) = match self.payload {
+            TickPayload::Damage(amount) => (-amount, 0.0),
+            TickPayload::RampingDamage(amount) => (-amount * (self.fired + 1) as f32, 0.0),
+            TickPayload::Energy(amount) => (0.0, amount),
+        };
+        (health / self.interval, energy / self.interval)
+    }
+
+    // Moves the effect `dt` seconds on and returns what every tick in that time
+    // does, ramping already worked out, so never a RampingDamage.
+    pub fn advance(&mut self, dt: f32) -> Vec<TickPayload> {
+        let mut ticks = Vec::new();
+        self.since_tick += dt;
+        while !self.finished() {
+            let scale = if self.on_last_tick() {
+                self.last_tick
+            } else {
+                1.0
+            };
+            if self.since_tick + EPSILON < self.interval * scale {
+                break;
+            }
+            self.since_tick -= self.interval * scale;
+            self.fired += 1;
+            if let Some(ticks) = self.remaining_ticks.as_mut() {
+                *ticks -= 1;
+            }
+            ticks.push(match self.payload {
+                TickPayload::Damage(amount) => TickPayload::Damage(amount * scale),
+                TickPayload::RampingDamage(amount) => {
+                    TickPayload::Damage(amount * self.fired as f32 * scale)
+                }
+                TickPayload::Energy(amount) => TickPayload::Energy(amount * scale),
+            });
+        }
+        ticks
+    }
+}
+
+// Scaled time not yet spent on a whole step.
+#[derive(Default)]
+struct PeriodicClock(f32);
+
+// Whole steps `dt` more seconds make up, keeping the rest for later frames.
+fn fixed_steps(clock: &mut f32, dt: f32) -> u32 {
+    *clock += dt;
+    let steps = ((*clock + EPSILON) / STEP).floor().max(0.0);
+    *clock = (*clock - steps * STEP).max(0.0);
+    steps as u32
+}
+
+// Only runs during the battle, so pausing holds every effect where it is.
+fn periodic_system(
+    mut commands: Commands,
+    sim_clock: Res<SimClock>,
+    mut clock: ResMut<PeriodicClock>,
+    mut effects: Query<(Entity, &mut PeriodicEffect)>,
+    mut stats: Query<&mut Stats, Without<Invulnerable>>,
+    mut damage: EventWriter<DamageEvent>,
+) {
+    let steps = fixed_steps(&mut clock.0, sim_clock.delta());
+    for (entity, mut effect) in &mut effects {
+        for _ in 0..steps {
+            for tick in effect.advance(STEP) {
+                match tick {
+                    TickPayload::Damage(amount) | TickPayload::RampingDamage(amount) => {
+                        damage.send(DamageEvent {
+                            target: effect.target,
+                            source: if effect.id == SUDDEN_DEATH {
+                                DamageSource::SuddenDeath
+                            } else {
+                                DamageSource::Status {
+                                    id: effect.id,
+                                    from: effect.source,
+                                }
+                            },
+                            amount,
+                            emp: false,
+                            impact: None,
+                        });
+                    }
+                    TickPayload::Energy(amount) => {
+                        if let Ok(mut stats) = stats.get_mut(effect.target) {
+                            stats.energy =
+                                (stats.energy + amount).clamp(0.0, stats.max_energy.max(0.0));
+                        }
+                    }
+                }
+            }
+        }
+        if effect.finished() {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+fn orphan_system(
+    mut commands: Commands,
+    effects: Query<(Entity, &PeriodicEffect)>,
+    targets: Query<(), With<Stats>>,
+) {
+    for (entity, effect) in &effects {
+        if !targets.contains(effect.target) {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+fn clear_system(
+    mut commands: Commands,
+    mut clock: ResMut<PeriodicClock>,
+    effects: Query<Entity, With<PeriodicEffect>>,
+) {
+    clock.0 = 0.0;
+    for entity in &effects {
+        commands.entity(entity).despawn();
+    }
+}
+
+fn periodic_display_system(effects: Query<&PeriodicEffect>, mut active: EventWriter<ActiveEffect>) {
+    for effect in &effects {
+        active.send(ActiveEffect {
+            target: effect.target,
+            id: effect.id,
+            polarity: effect.payload.polarity(),
+            remaining: effect.remaining(),
+            stacks: match effect.payload {
+                // The next tick hits this many times as hard as the first.
+                TickPayload::RampingDamage(_) => effect.fired + 1,
+                _ => 1,
+            },
+        });
+    }
+}
+
+pub struct PeriodicPlugin;
+
+impl Plugin for PeriodicPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PeriodicClock>()
+            .add_system_set(SystemSet::on_enter(AppState::Battle).with_system(clear_system))
+            .add_system_set(
+                SystemSet::on_update(AppState::Battle)
+                    .with_system(periodic_system.before(damage_system))
+                    .with_system(orphan_system)
+                    .with_system(periodic_display_system),
+            );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(effects: &mut [PeriodicEffect], seconds: f32) -> Vec<Vec<(u32, TickPayload)>> {
+        let mut ticks = vec![Vec::new(); effects.len()];
+        for step in 0..(seconds / STEP).round() as u32 {
+            for (effect, ticks) in effects.iter_mut().zip(&mut ticks) {
+                ticks.extend(
+                    effect
+                        .advance(STEP)
+                        .into_iter()
+                        .map(|tick| (step + 1, tick)),
+                );
+            }
+        }
+        ticks
+    }
+
+    #[test]
+    fn partial_final_interval_ticks_early_and_weaker() {
+        let target = Entity::from_raw(0);
+        let mut effect =
+            PeriodicEffect::lasting(target, "burn", 2.5, 1.0, TickPayload::Damage(4.0));
+        assert_eq!(effect.remaining_ticks, Some(3));
+        assert!((effect.remaining().unwrap() - 2.5).abs() < 1e-4);
+
+        let ticks = run(std::slice::from_mut(&mut effect), 4.0).remove(0);
+        let steps = ticks.iter().map(|(step, _)| *step).collect::<Vec<_>>();
+        assert_eq!(steps, [60, 120, 150]);
+        let total = ticks
+            .iter()
+            .map(|(_, tick)| match tick {
+                TickPayload::Damage(amount) => *amount,
+                _ => panic!("unexpected tick {:?}", tick),
+            })
+            .sum::<f32>();
+        assert!((total - 10.0).abs() < 1e-4, "{}", total);
+        assert!(effect.finished());
+        assert_eq!(effect.remaining(), Some(0.0));
+    }
+
+    #[test]
+    fn effects_ending_together_both_fire_their_last_tick() {
+        let target = Entity::from_raw(0);
+        let mut effects = [
+            PeriodicEffect::lasting(target, "burn", 2.0, 1.0, TickPayload::Damage(1.0)),
+            PeriodicEffect::lasting(target, "charge", 2.0, 0.5, TickPayload::Energy(1.0)),
+            PeriodicEffect::lasting(target, "repair", 2.0, 0.6, TickPayload::Damage(-1.0)),
+        ];
+        let ticks = run(&mut effects, 1.9);
+        assert!(effects.iter().all(|effect| !effect.finished()));
+        let more = run(&mut effects, 0.1);
+        assert!(effects.iter().all(PeriodicEffect::finished));
+        assert_eq!(ticks.iter().map(Vec::len).collect::<Vec<_>>(), [1, 3, 3]);
+        assert_eq!(more.iter().map(Vec::len).collect::<Vec<_>>(), [1, 1, 1]);
+        // Nothing ticks once finished.
+        assert!(run(&mut effects, 5.0).iter().all(Vec::is_empty));
+    }
+
+    #[test]
+    fn ramping_damage_grows_every_tick() {
+        let target = Entity::from_raw(0);
+        let mut effect = PeriodicEffect::until_removed(
+            target,
+            "sudden_death",
+            1.0,
+            TickPayload::RampingDamage(2.0),
+        );
+        let ticks = run(std::slice::from_mut(&mut effect), 3.0).remove(0);
+        let amounts = ticks.iter().map(|(_, tick)| *tick).collect::<Vec<_>>();
+        assert_eq!(amounts, [2.0, 4.0, 6.0].map(TickPayload::Damage).to_vec());
+        assert_eq!(effect.remaining(), None);
+    }
+
+    #[test]
+    fn changing_the_time_scale_never_adds_steps() {
+        let mut clock = 0.0;
+        // A second at normal speed, then at 4x, then a frame at 2x.
+        let steps = (0..60).map(|_| fixed_steps(&mut clock, STEP)).sum::<u32>()
+            + (0..15)
+                .map(|_| fixed_steps(&mut clock, STEP * 4.0))
+                .sum::<u32>()
+            + fixed_steps(&mut clock, STEP * 2.0);
+        assert_eq!(steps, 122);
+        // Uneven frames carry their remainder over instead of rounding up.
+        let mut clock = 0.0;
+        let steps = (0..100)
+            .map(|_| fixed_steps(&mut clock, STEP * 0.7))
+            .sum::<u32>();
+        assert_eq!(steps, 70);
+    }
+}
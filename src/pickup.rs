@@ -0,0 +1,351 @@
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::{
+    balance::BalanceConfig,
+    body::{Body, Facing, Skill, Stats},
+    layer::RenderLayer,
+    locale::t,
+    profile::Profile,
+    timer::SimClock,
+    turn::Ruleset,
+    AppState, Game, GameRng,
+};
+
+// A spawn is rolled this often, with `BalanceConfig::pickup_chance`.
+const ROLL_INTERVAL: f32 = 10.0;
+const LIFETIME: f32 = 15.0;
+// Pickups land anywhere the bodies can walk to without leaving the screen.
+const SPAWN_RANGE: f32 = 6.0;
+const SIZE: f32 = 0.3;
+const ENERGY_CELL: f32 = 25.0;
+const REPAIR_KIT: f32 = 15.0;
+const SCRAP_BUNDLE: u32 = 10;
+// How much a pickup has to be worth per unit walked to it before the AI goes
+// out of its way, and how much more while it's in reach to hit instead.
+const DETOUR_COST: f32 = 0.03;
+const PRESSING_COST: f32 = 3.0;
+const SPARKLES: usize = 6;
+const SPARKLE_TIME: f32 = 0.4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PickupKind {
+    EnergyCell,
+    RepairKit,
+    Scrap,
+}
+
+impl PickupKind {
+    const ALL: [PickupKind; 3] = [
+        PickupKind::EnergyCell,
+        PickupKind::RepairKit,
+        PickupKind::Scrap,
+    ];
+
+    pub fn name(self) -> String {
+        t!(match self {
+            PickupKind::EnergyCell => "pickup.energy_cell",
+            PickupKind::RepairKit => "pickup.repair_kit",
+            PickupKind::Scrap => "pickup.scrap",
+        })
+    }
+
+    fn color(self) -> Color {
+        match self {
+            PickupKind::EnergyCell => Color::rgb(0.3, 0.8, 1.0),
+            PickupKind::RepairKit => Color::rgb(0.3, 1.0, 0.4),
+            PickupKind::Scrap => Color::rgb(0.8, 0.7, 0.5),
+        }
+    }
+
+    // Share of the body's maximum it would actually get back, scrap is
+    // nothing to a body in the middle of a fight.
+    fn worth(self, stats: &Stats) -> f32 {
+        let gain = |amount: f32, current: f32, max: f32| {
+            if max > 0.0 {
+                amount.min(max - current).max(0.0) / max
+            } else {
+                0.0
+            }
+        };
+        match self {
+            PickupKind::EnergyCell => gain(ENERGY_CELL, stats.energy, stats.max_energy),
+            PickupKind::RepairKit => gain(REPAIR_KIT, stats.health, stats.max_health),
+            PickupKind::Scrap => 0.0,
+        }
+    }
+}
+
+// Lies on the ground until a body walks over it or it runs out.
+#[derive(Component)]
+pub struct Pickup {
+    pub kind: PickupKind,
+    remaining: f32,
+}
+
+pub struct PickupEvent {
+    pub entity: Entity,
+    pub kind: PickupKind,
+}
+
+// Seconds of battle until the next spawn roll.
+#[derive(Default)]
+struct PickupTimer(f32);
+
+#[derive(Component)]
+struct Sparkle {
+    remaining: f32,
+    velocity: Vec2,
+}
+
+// Whether a body standing at `x` is over a pickup at `pickup_x`.
+fn overlaps(x: f32, width: f32, pickup_x: f32) -> bool {
+    (x - pickup_x).abs() <= (width + SIZE) / 2.0
+}
+
+// A walk towards the pickup most worth its distance, if any is, and what it's
+// worth after the walk. `pressing` is whether the body could be attacking
+// instead, it takes a better pickup to give that up.
+pub fn detour_skill(
+    stats: &Stats,
+    x: f32,
+    facing: Facing,
+    pickups: &[(PickupKind, f32)],
+    pressing: bool,
+) -> Option<(usize, f32)> {
+    let cost = DETOUR_COST * if pressing { PRESSING_COST } else { 1.0 };
+    let (score, ahead) = pickups
+        .iter()
+        .filter(|(_, pickup_x)| !overlaps(x, stats.width, *pickup_x))
+        .map(|(kind, pickup_x)| {
+            let score = kind.worth(stats) - (pickup_x - x).abs() * cost;
+            (score, Facing::towards(x, *pickup_x) == facing)
+        })
+        .filter(|(score, _)| *score > 0.0)
+        .max_by(|(a, _), (b, _)| a.total_cmp(b))?;
+    let walk = stats.skills.iter().position(|skill| {
+        if ahead {
+            matches!(skill, Skill::WalkForward)
+        } else {
+            matches!(skill, Skill::WalkBackward)
+        }
+    })?;
+    Some((walk, score))
+}
+
+fn reset_system(
+    mut commands: Commands,
+    mut timer: ResMut<PickupTimer>,
+    pickups: Query<Entity, Or<(With<Pickup>, With<Sparkle>)>>,
+) {
+    timer.0 = ROLL_INTERVAL;
+    for entity in &pickups {
+        commands.entity(entity).despawn();
+    }
+}
+
+fn spawn_system(
+    mut commands: Commands,
+    clock: Res<SimClock>,
+    ruleset: Res<Ruleset>,
+    balance: Res<BalanceConfig>,
+    mut timer: ResMut<PickupTimer>,
+    mut rng: ResMut<GameRng>,
+) {
+    // Not rolled at all when off, so the battle's RNG is what it would be
+    // without pickups.
+    if !ruleset.spawns_pickups() {
+        return;
+    }
+    timer.0 -= clock.delta();
+    if timer.0 > 0.0 {
+        return;
+    }
+    timer.0 += ROLL_INTERVAL;
+    if !rng.gen_bool(balance.pickup_chance.clamp(0.0, 1.0) as f64) {
+        return;
+    }
+    let kind = PickupKind::ALL[rng.gen_range(0..PickupKind::ALL.len())];
+    let x = rng.gen_range(-SPAWN_RANGE..=SPAWN_RANGE);
+    info!("A {:?} dropped at {:.1}", kind, x);
+    commands
+        .spawn_bundle(SpriteBundle {
+            sprite: Sprite {
+                color: kind.color(),
+                ..default()
+            },
+            transform: Transform::from_xyz(x, SIZE / 2.0, RenderLayer::Pickup.z())
+                .with_scale(Vec3::splat(SIZE)),
+            ..default()
+        })
+        .insert(Pickup {
+            kind,
+            remaining: LIFETIME,
+        });
+}
+
+// The player gets first pick when both bodies are over it.
+fn collect_system(
+    mut commands: Commands,
+    clock: Res<SimClock>,
+    game: Res<Game>,
+    mut profile: Option<ResMut<Profile>>,
+    mut events: EventWriter<PickupEvent>,
+    mut pickups: Query<(Entity, &mut Pickup, &Transform)>,
+    mut bodies: Query<(&Body, &mut Stats, &Transform)>,
+) {
+    let dt = clock.delta();
+    for (entity, mut pickup, transform) in &mut pickups {
+        let x = transform.translation.x;
+        let collector = [game.player, game.enemy].into_iter().find(|body| {
+            bodies
+                .get(*body)
+                .is_ok_and(|(_, stats, body)| overlaps(body.translation.x, stats.width, x))
+        });
+        let Some(collector) = collector else {
+            pickup.remaining -= dt;
+            if pickup.remaining <= 0.0 {
+                commands.entity(entity).despawn();
+            }
+            continue;
+        };
+        let Ok((body, mut stats, _)) = bodies.get_mut(collector) else {
+            continue;
+        };
+        match pickup.kind {
+            PickupKind::EnergyCell => {
+                stats.energy = (stats.energy + ENERGY_CELL).min(stats.max_energy);
+            }
+            // Health is kept for the whole body, there's no limb to pick.
+            PickupKind::RepairKit => {
+                stats.health = (stats.health + REPAIR_KIT).min(stats.max_health);
+            }
+            PickupKind::Scrap => {
+                if let Some(profile) = profile.as_mut().filter(|_| collector == game.player) {
+                    profile.scrap += SCRAP_BUNDLE;
+                }
+            }
+        }
+        info!("{} picked up a {:?}", body.name(), pickup.kind);
+        events.send(PickupEvent {
+            entity: collector,
+            kind: pickup.kind,
+        });
+        commands.entity(entity).despawn();
+        // Spread out evenly, nothing about the effect may touch the battle's
+        // RNG.
+        for i in 0..SPARKLES {
+            let angle = std::f32::consts::TAU * i as f32 / SPARKLES as f32;
+            commands
+                .spawn_bundle(SpriteBundle {
+                    sprite: Sprite {
+                        color: pickup.kind.color(),
+                        ..default()
+                    },
+                    transform: Transform::from_translation(
+                        transform
+                            .translation
+                            .truncate()
+                            .extend(RenderLayer::Particle.z()),
+                    )
+                    .with_scale(Vec3::splat(SIZE / 3.0)),
+                    ..default()
+                })
+                .insert(Sparkle {
+                    remaining: SPARKLE_TIME,
+                    velocity: Vec2::from_angle(angle) * 1.5,
+                });
+        }
+    }
+}
+
+fn sparkle_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut sparkles: Query<(Entity, &mut Sparkle, &mut Transform, &mut Sprite)>,
+) {
+    for (entity, mut sparkle, mut transform, mut sprite) in &mut sparkles {
+        sparkle.remaining -= time.delta_seconds();
+        if sparkle.remaining <= 0.0 {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        transform.translation += (sparkle.velocity * time.delta_seconds()).extend(0.0);
+        sprite.color.set_a(sparkle.remaining / SPARKLE_TIME);
+    }
+}
+
+pub struct PickupPlugin;
+
+impl Plugin for PickupPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PickupTimer>()
+            .add_event::<PickupEvent>()
+            .add_system_set(SystemSet::on_enter(AppState::Battle).with_system(reset_system))
+            .add_system_set(
+                SystemSet::on_update(AppState::Battle)
+                    .with_system(spawn_system)
+                    .with_system(collect_system.after(spawn_system)),
+            )
+            .add_system(sparkle_system);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::body::compute_stats;
+
+    #[test]
+    fn detours_only_for_pickups_worth_the_walk() {
+        let mut stats = compute_stats(&Body::default());
+        let detour_skill = |stats: &Stats, x, facing, pickups: &[_], pressing| {
+            detour_skill(stats, x, facing, pickups, pressing).map(|(walk, _)| walk)
+        };
+        let walk = |forward: bool| {
+            stats.skills.iter().position(|skill| {
+                matches!(skill, Skill::WalkForward) == forward
+                    && matches!(skill, Skill::WalkForward | Skill::WalkBackward)
+            })
+        };
+        let (forward, backward) = (walk(true), walk(false));
+        // Nothing to gain while full.
+        let cell = [(PickupKind::EnergyCell, 2.0)];
+        assert_eq!(detour_skill(&stats, 0.0, Facing::Right, &cell, false), None);
+
+        stats.energy = 0.0;
+        assert_eq!(
+            detour_skill(&stats, 0.0, Facing::Right, &cell, false),
+            forward
+        );
+        assert_eq!(
+            detour_skill(&stats, 0.0, Facing::Left, &cell, false),
+            backward
+        );
+        // Too far to be worth it, or already underfoot.
+        let far = [(PickupKind::EnergyCell, 100.0)];
+        assert_eq!(detour_skill(&stats, 0.0, Facing::Right, &far, false), None);
+        let underfoot = [(PickupKind::EnergyCell, 0.0)];
+        assert_eq!(
+            detour_skill(&stats, 0.0, Facing::Right, &underfoot, false),
+            None
+        );
+        // Scrap never pulls a body away from a fight.
+        let scrap = [(PickupKind::Scrap, 1.0)];
+        assert_eq!(
+            detour_skill(&stats, 0.0, Facing::Right, &scrap, false),
+            None
+        );
+
+        // A walk that's worth it on its own isn't while there's a hit to land.
+        stats.energy = stats.max_energy - ENERGY_CELL / 2.0;
+        let x = (ENERGY_CELL / 2.0 / stats.max_energy) / DETOUR_COST / 2.0;
+        let near = [(PickupKind::EnergyCell, x)];
+        assert!(!overlaps(0.0, stats.width, x));
+        assert_eq!(
+            detour_skill(&stats, 0.0, Facing::Right, &near, false),
+            forward
+        );
+        assert_eq!(detour_skill(&stats, 0.0, Facing::Right, &near, true), None);
+    }
+}
@@ -0,0 +1,130 @@
+use bevy::{prelude::*, sprite::Anchor};
+
+use crate::{
+    balance::BalanceConfig,
+    battle::{absorb, expected_damage, walk_destination, Animation},
+    body::{Body, Facing, Stats},
+    interrupt::Recovery,
+    layer::RenderLayer,
+    turn::Ruleset,
+    ui::{SkillButton, UseSkill},
+    Game, Invulnerable,
+};
+
+const GHOST_COLOR: Color = Color::rgba(1.0, 1.0, 1.0, 0.25);
+
+// What the hovered skill would do if it was used now. Worked out with the same
+// functions the skill runs through, so it can't disagree with the real thing.
+#[derive(Default, PartialEq)]
+pub struct SkillPreview {
+    // The body that would be hit and the health and overshield it would be
+    // left with, together.
+    pub health: Option<(Entity, f32)>,
+    // Where the player would stand after a walk.
+    pub destination: Option<f32>,
+}
+
+fn preview_system(
+    game: Res<Game>,
+    ruleset: Res<Ruleset>,
+    balance: Res<BalanceConfig>,
+    use_skill: Res<UseSkill>,
+    mut preview: ResMut<SkillPreview>,
+    buttons: Query<(&Interaction, &SkillButton)>,
+    bodies: Query<(&Transform, &Facing, &Stats)>,
+    busy: Query<(), Or<(With<Animation>, With<Recovery>)>>,
+    invulnerable: Query<(), With<Invulnerable>>,
+) {
+    let hovered = buttons
+        .iter()
+        .find(|(interaction, _)| **interaction == Interaction::Hovered)
+        .map(|(_, button)| &button.0)
+        .filter(|_| use_skill.is_none() && !busy.contains(game.player));
+    let mut next = SkillPreview::default();
+    if let (Some(skill), Ok([(player, facing, stats), (enemy, _, enemy_stats)])) =
+        (hovered, bodies.get_many([game.player, game.enemy]))
+    {
+        if let Some(skill) = stats.skill(skill) {
+            // Attacks turn around first with auto-face, like the ruler shows.
+            let attack_facing = if ruleset.auto_face {
+                Facing::towards(player.translation.x, enemy.translation.x)
+            } else {
+                *facing
+            };
+            next.health = expected_damage(
+                &balance,
+                skill,
+                player,
+                attack_facing,
+                stats,
+                enemy,
+                enemy_stats,
+            )
+            .filter(|damage| *damage > 0.0 && !invulnerable.contains(game.enemy))
+            .map(|damage| {
+                let damage = ruleset.mutators.scale_damage(damage);
+                let emp = skill.ability().is_some_and(|ability| ability.emp);
+                let (overshield, health) =
+                    absorb(enemy_stats.overshield, enemy_stats.health, damage, emp);
+                (game.enemy, overshield + health)
+            });
+            next.destination =
+                walk_destination(&balance, player, *facing, stats, skill, enemy, enemy_stats);
+        }
+    }
+    if *preview != next {
+        *preview = next;
+    }
+}
+
+fn ghost_system(
+    mut commands: Commands,
+    game: Res<Game>,
+    preview: Res<SkillPreview>,
+    mut ghost: Local<Option<Entity>>,
+    bodies: Query<(&Transform, &Stats, &Body)>,
+    mut transforms: Query<&mut Transform, Without<Body>>,
+) {
+    let shown = preview
+        .destination
+        .zip(bodies.get(game.player).ok())
+        .filter(|(destination, (transform, ..))| *destination != transform.translation.x);
+    let Some((destination, (transform, stats, body))) = shown else {
+        if let Some(ghost) = ghost.take() {
+            commands.entity(ghost).despawn();
+        }
+        return;
+    };
+    let ghost = *ghost.get_or_insert_with(|| {
+        commands
+            .spawn_bundle(SpriteBundle {
+                sprite: Sprite {
+                    color: GHOST_COLOR,
+                    anchor: Anchor::BottomCenter,
+                    ..default()
+                },
+                // Placed on the next frame, hidden until then.
+                transform: Transform::from_scale(Vec3::ZERO),
+                ..default()
+            })
+            .id()
+    });
+    if let Ok(mut ghost) = transforms.get_mut(ghost) {
+        ghost.translation = Vec3::new(
+            destination,
+            transform.translation.y,
+            RenderLayer::Floater.z(),
+        );
+        ghost.scale = Vec3::new(stats.width, body.height(), 1.0);
+    }
+}
+
+pub struct PreviewPlugin;
+
+impl Plugin for PreviewPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SkillPreview>()
+            .add_system(preview_system)
+            .add_system(ghost_system.after(preview_system));
+    }
+}
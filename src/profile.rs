@@ -0,0 +1,221 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use bevy::{app::AppExit, prelude::*};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    attachment::Attachment,
+    body::{Arm, Body, PaletteMode, SkillId},
+    booster::Booster,
+    campaign::CampaignProgress,
+    daily::{DailyHistory, DailyRun},
+    error::{report, ErrorToast, GameError},
+    history::RunHistory,
+    rivals::Rivals,
+    storage,
+    swap::SwapBody,
+    telemetry::SkillUsage,
+    theme::ThemeKind,
+    AppState, BattleSummary, Game,
+};
+
+const FILE_NAME: &str = "profile.ron";
+
+pub const UI_SCALES: &[f32] = &[0.75, 1.0, 1.25, 1.5, 2.0];
+
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    // Multiplied with the automatic factor from the window height.
+    pub ui_scale: f32,
+    pub palette: PaletteMode,
+    pub theme: ThemeKind,
+    pub language: String,
+    // Describe every combat event on a banner.
+    pub narration: bool,
+    // Explain a mechanic the first time it comes up, see `tips`.
+    pub tips: bool,
+    // Fewer particles and effects, see `PerformanceProfile`.
+    pub low_spec: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            ui_scale: 1.0,
+            palette: PaletteMode::Default,
+            theme: ThemeKind::Grey,
+            language: "en".to_string(),
+            narration: false,
+            tips: true,
+            low_spec: false,
+        }
+    }
+}
+
+// Every field has a default so profiles written by older versions still load.
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Profile {
+    pub body: Option<Body>,
+    pub scrap: u32,
+    pub wins: u32,
+    pub losses: u32,
+    pub draws: u32,
+    pub best_combo: u32,
+    pub best_wave: u32,
+    pub tutorial_done: bool,
+    pub achievements: BTreeSet<String>,
+    // Keyed by `Skill::id`. Entries for skills the body no longer has are
+    // kept so rerolling a part doesn't lose its history.
+    pub skill_stats: BTreeMap<SkillId, SkillUsage>,
+    // The player's skill bar order by `Skill::id`, see `ui::bar_order`.
+    pub skill_order: Vec<SkillId>,
+    // Boosters bought and not used up yet.
+    pub boosters: BTreeMap<Booster, u32>,
+    // Every attachment bought, the ones on the body's arms too, see
+    // `attachment::free`.
+    pub attachments: BTreeMap<Attachment, u32>,
+    // Carried into battle for a Modular Chassis to swap in, see `limb_swap`.
+    pub spare_arm: Option<Arm>,
+    // The last survival runs, for the run history screen.
+    pub history: RunHistory,
+    // Enemies saved to fight again, see `rivals`.
+    pub rivals: Rivals,
+    // Encounters cleared and campaigns finished, see `campaign`.
+    pub campaign: CampaignProgress,
+    // One result per daily challenge played, see `daily`.
+    pub daily: DailyHistory,
+    // Triggers of the tip cards already shown.
+    pub tips_seen: BTreeSet<String>,
+    pub settings: Settings,
+}
+
+impl Profile {
+    // None saved yet is a fresh profile. A corrupt one is moved aside before
+    // saying so, the next save would write over it otherwise.
+    pub fn load() -> Result<Self, GameError> {
+        let Some(contents) = storage::read(FILE_NAME)? else {
+            return Ok(Self::default());
+        };
+        ron::from_str(&contents).map_err(|err| {
+            let backup = format!("{}.bak", FILE_NAME);
+            let kept = match storage::rename(FILE_NAME, &backup) {
+                Ok(()) => format!("kept as {}", storage::describe(&backup)),
+                Err(err) => format!("couldn't be kept: {}", err),
+            };
+            GameError::Parse {
+                file: FILE_NAME.to_string(),
+                line: err.position.line,
+                reason: format!("{}, {}", err.code, kept),
+            }
+        })
+    }
+
+    pub fn save(&self) -> Result<(), GameError> {
+        let contents = ron::ser::to_string_pretty(self, default())
+            .map_err(|err| GameError::io(FILE_NAME, err))?;
+        storage::write(FILE_NAME, &contents)
+    }
+
+    // Progress is erased but settings are kept.
+    pub fn reset(&mut self) -> Result<(), GameError> {
+        *self = Self {
+            settings: self.settings.clone(),
+            ..default()
+        };
+        self.save()
+    }
+}
+
+fn apply_profile_system(profile: Res<Profile>, game: Res<Game>, mut swaps: EventWriter<SwapBody>) {
+    if let Some(body) = profile.body.clone() {
+        swaps.send(SwapBody {
+            entity: game.player,
+            body,
+        });
+    }
+}
+
+// During a daily challenge the player fights with a body of the day's, their
+// own is kept.
+fn store_body(
+    profile: &mut Profile,
+    game: &Game,
+    daily: Option<Res<DailyRun>>,
+    bodies: &Query<&Body>,
+) {
+    if let Some(run) = daily {
+        profile.body = Some(run.body.clone());
+    } else if let Ok(body) = bodies.get(game.player) {
+        profile.body = Some(body.clone());
+    }
+}
+
+fn save_on_fight_system(
+    mut profile: ResMut<Profile>,
+    game: Res<Game>,
+    daily: Option<Res<DailyRun>>,
+    bodies: Query<&Body>,
+    mut errors: EventWriter<ErrorToast>,
+) {
+    store_body(&mut profile, &game, daily, &bodies);
+    report(&mut errors, profile.save());
+}
+
+pub fn record_result_system(
+    mut profile: ResMut<Profile>,
+    game: Res<Game>,
+    summary: Res<BattleSummary>,
+    daily: Option<Res<DailyRun>>,
+    bodies: Query<&Body>,
+    mut errors: EventWriter<ErrorToast>,
+) {
+    match summary.winner {
+        Some(winner) if winner == game.player => profile.wins += 1,
+        Some(_) => profile.losses += 1,
+        None => profile.draws += 1,
+    }
+    store_body(&mut profile, &game, daily, &bodies);
+    report(&mut errors, profile.save());
+}
+
+// The window is on its way out, there's nowhere left to show a failure.
+fn save_on_exit_system(
+    mut exit: EventReader<AppExit>,
+    mut profile: ResMut<Profile>,
+    game: Res<Game>,
+    daily: Option<Res<DailyRun>>,
+    bodies: Query<&Body>,
+) {
+    if exit.iter().last().is_some() {
+        store_body(&mut profile, &game, daily, &bodies);
+        if let Err(err) = profile.save() {
+            error!("{}", err);
+        }
+    }
+}
+
+pub struct ProfilePlugin;
+
+impl Plugin for ProfilePlugin {
+    fn build(&self, app: &mut App) {
+        // Shown once the window is up, the game starts on a fresh profile.
+        let profile = Profile::load().unwrap_or_else(|err| {
+            error!("{}", err);
+            app.add_event::<ErrorToast>()
+                .world
+                .send_event(ErrorToast(err));
+            Profile::default()
+        });
+        app.insert_resource(profile)
+            .add_startup_system_to_stage(StartupStage::PostStartup, apply_profile_system)
+            .add_system_set(
+                SystemSet::on_exit(AppState::PreBattle).with_system(save_on_fight_system),
+            )
+            .add_system_set(
+                SystemSet::on_enter(AppState::GameOver).with_system(record_result_system),
+            )
+            .add_system_to_stage(CoreStage::Last, save_on_exit_system);
+    }
+}
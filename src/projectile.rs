@@ -0,0 +1,216 @@
+use bevy::prelude::*;
+
+use crate::{
+    ammo::Ammo,
+    balance::BalanceConfig,
+    battle::{damage_system, Ground},
+    body::{Body, SkillId},
+    hitbox::BodyGeometry,
+    layer::RenderLayer,
+    timer::SimClock,
+    AppState, DamageEvent, DamageSource, Impact,
+};
+
+const PROJECTILE_SIZE: f32 = 0.15;
+const PROJECTILE_COLOR: Color = Color::rgb(1.0, 0.75, 0.3);
+// Long enough to cross the arena, a shot that somehow never comes down goes.
+const MAX_FLIGHT: f32 = 10.0;
+// Time step the AI samples a flight path at.
+pub const PATH_STEP: f32 = 1.0 / 30.0;
+// Furthest apart the points checked for a hit along one frame's flight are, so
+// a fast frame can't carry a shot through a thin limb.
+const HIT_SPACING: f32 = 0.05;
+
+// A shot lobbed by a ranged skill. It's aimed at where the target stood when it
+// left, so walking away from that spot can dodge it.
+#[derive(Component, Clone, Debug)]
+pub struct Projectile {
+    pub source: Entity,
+    // The skill that fired it, the shooter may be on to another by the time
+    // it lands.
+    pub skill: SkillId,
+    pub target: Entity,
+    pub damage: f32,
+    pub emp: bool,
+    pub velocity: Vec2,
+    // Seconds since it was fired, bodies only react once they've seen it.
+    pub age: f32,
+    // Where it was last frame, everything in between can be hit.
+    previous: Vec2,
+}
+
+impl Projectile {
+    // Where the shot was a frame ago, `impact_system` checks the whole way
+    // from there.
+    pub fn previous(&self) -> Vec2 {
+        self.previous
+    }
+}
+
+// Speed a shot from `from` needs to come back down to the same height at `to`.
+pub fn aim(balance: &BalanceConfig, from: Vec2, to_x: f32) -> Vec2 {
+    let dx = to_x - from.x;
+    let flight = dx.abs() / balance.projectile_speed;
+    Vec2::new(
+        dx.signum() * balance.projectile_speed,
+        balance.projectile_gravity * flight / 2.0,
+    )
+}
+
+// Where a shot at `position` moving at `velocity` will be `t` seconds later.
+pub fn position_after(balance: &BalanceConfig, position: Vec2, velocity: Vec2, t: f32) -> Vec2 {
+    position + velocity * t - Vec2::Y * balance.projectile_gravity * t * t / 2.0
+}
+
+// Points along the rest of a flight with the time until each, until it hits
+// the ground.
+pub fn flight_path(
+    balance: &BalanceConfig,
+    ground: Ground,
+    position: Vec2,
+    velocity: Vec2,
+) -> impl Iterator<Item = (f32, Vec2)> + '_ {
+    (1..)
+        .map(|i| i as f32 * PATH_STEP)
+        .take_while(|t| *t < MAX_FLIGHT)
+        .map(move |t| (t, position_after(balance, position, velocity, t)))
+        .take_while(move |(_, point)| !ground.grounded(point.y))
+}
+
+pub fn launch(
+    commands: &mut Commands,
+    balance: &BalanceConfig,
+    source: Entity,
+    skill: SkillId,
+    target: Entity,
+    damage: f32,
+    emp: bool,
+    from: Vec2,
+    to_x: f32,
+) {
+    commands
+        .spawn_bundle(SpriteBundle {
+            transform: Transform::from_translation(from.extend(RenderLayer::Projectile.z()))
+                .with_scale(Vec3::splat(PROJECTILE_SIZE)),
+            sprite: Sprite {
+                color: PROJECTILE_COLOR,
+                ..default()
+            },
+            ..default()
+        })
+        .insert(Projectile {
+            source,
+            skill,
+            target,
+            damage,
+            emp,
+            velocity: aim(balance, from, to_x),
+            age: 0.0,
+            previous: from,
+        });
+}
+
+fn flight_system(
+    clock: Res<SimClock>,
+    balance: Res<BalanceConfig>,
+    mut projectiles: Query<(&mut Projectile, &mut Transform)>,
+) {
+    let dt = clock.delta();
+    for (mut projectile, mut transform) in &mut projectiles {
+        let position = transform.translation.truncate();
+        projectile.previous = position;
+        let next = position_after(&balance, position, projectile.velocity, dt);
+        projectile.velocity.y -= balance.projectile_gravity * dt;
+        projectile.age += dt;
+        transform.translation = next.extend(transform.translation.z);
+    }
+}
+
+// Only the target can be hit, a shot flies through anything else.
+fn impact_system(
+    mut commands: Commands,
+    geometry: BodyGeometry,
+    ground: Res<Ground>,
+    projectiles: Query<(Entity, &Projectile, &Transform)>,
+    bodies: Query<&Body>,
+    mut shooters: Query<&mut Ammo>,
+    mut damage: EventWriter<DamageEvent>,
+) {
+    for (entity, projectile, transform) in &projectiles {
+        let position = transform.translation.truncate();
+        let samples = ((projectile.previous.distance(position) / HIT_SPACING).ceil() as u32).max(1);
+        let hit = (1..=samples).find_map(|i| {
+            let point = projectile
+                .previous
+                .lerp(position, i as f32 / samples as f32);
+            Some((geometry.body_hit_test(projectile.target, point)?, point))
+        });
+        if let Some((part, point)) = hit {
+            damage.send(DamageEvent {
+                target: projectile.target,
+                source: DamageSource::Attack {
+                    attacker: projectile.source,
+                    skill: projectile.skill.clone(),
+                },
+                amount: projectile.damage,
+                emp: projectile.emp,
+                impact: bodies
+                    .get(projectile.target)
+                    .ok()
+                    .and_then(|body| Impact::on(body, part, point)),
+            });
+            if let Ok(mut ammo) = shooters.get_mut(projectile.source) {
+                ammo.hit += 1;
+            }
+            commands.entity(entity).despawn();
+        } else if ground.grounded(position.y) || projectile.age > MAX_FLIGHT {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+fn clear_system(mut commands: Commands, projectiles: Query<Entity, With<Projectile>>) {
+    for entity in &projectiles {
+        commands.entity(entity).despawn();
+    }
+}
+
+pub struct ProjectilePlugin;
+
+impl Plugin for ProjectilePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(SystemSet::on_enter(AppState::Battle).with_system(clear_system))
+            .add_system_set(
+                SystemSet::on_update(AppState::Battle)
+                    .with_system(flight_system)
+                    .with_system(impact_system.after(flight_system).before(damage_system)),
+            );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shots_come_down_where_they_were_aimed() {
+        let balance = BalanceConfig::default();
+        for (from, to_x) in [(Vec2::new(-3.0, 1.5), 4.0), (Vec2::new(2.0, 1.0), -6.0)] {
+            let velocity = aim(&balance, from, to_x);
+            let flight = (to_x - from.x).abs() / balance.projectile_speed;
+            let landing = position_after(&balance, from, velocity, flight);
+            assert!(
+                landing.abs_diff_eq(Vec2::new(to_x, from.y), 1e-4),
+                "{}",
+                landing
+            );
+            // The path keeps going past the aim point until the ground.
+            let ground = Ground::default();
+            let (_, last) = flight_path(&balance, ground, from, velocity)
+                .last()
+                .unwrap();
+            assert!(!ground.grounded(last.y) && last.y < from.y);
+            assert!((last.x - from.x).abs() > (to_x - from.x).abs());
+        }
+    }
+}
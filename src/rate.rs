@@ -0,0 +1,123 @@
+use bevy::prelude::*;
+
+use crate::{timer::SimClock, AppState};
+
+// However modifiers stack, an animation never stalls or skips past its hits.
+pub const MIN_RATE: f32 = 0.1;
+pub const MAX_RATE: f32 = 4.0;
+
+// Sent by whatever slows down or speeds up a body's animations, every frame it
+// applies, like ActiveEffect.
+#[derive(Clone, Copy, Debug)]
+pub struct RateModifier {
+    pub target: Entity,
+    // What the multiplier comes from, shown in the debug overlay.
+    pub source: &'static str,
+    pub multiplier: f32,
+}
+
+// How fast a body's animations play this frame. TimeScale isn't one of the
+// modifiers, it speeds up the whole fight and goes past MAX_RATE in the sim.
+#[derive(Component, Clone, Debug)]
+pub struct AnimationRate {
+    modifiers: Vec<(&'static str, f32)>,
+    rate: f32,
+    // Seconds of animation the body gets this frame.
+    dt: f32,
+}
+
+impl Default for AnimationRate {
+    fn default() -> Self {
+        Self {
+            modifiers: Vec::new(),
+            rate: 1.0,
+            dt: 0.0,
+        }
+    }
+}
+
+impl AnimationRate {
+    pub fn rate(&self) -> f32 {
+        self.rate
+    }
+
+    pub fn dt(&self) -> f32 {
+        self.dt
+    }
+
+    pub fn modifiers(&self) -> &[(&'static str, f32)] {
+        &self.modifiers
+    }
+}
+
+// Multiplies the modifiers together and only clamps the result, so the order
+// they arrive in never matters.
+pub fn fold_rate(multipliers: impl IntoIterator<Item = f32>) -> f32 {
+    multipliers
+        .into_iter()
+        .filter(|multiplier| multiplier.is_finite())
+        .map(|multiplier| multiplier.max(0.0))
+        .product::<f32>()
+        .clamp(MIN_RATE, MAX_RATE)
+}
+
+// Runs before anything that animates, the rest of the battle only reads `dt`.
+pub fn animation_rate_system(
+    clock: Res<SimClock>,
+    mut modifiers: EventReader<RateModifier>,
+    mut bodies: Query<(Entity, &mut AnimationRate)>,
+) {
+    let modifiers = modifiers.iter().collect::<Vec<_>>();
+    let dt = clock.delta();
+    for (entity, mut rate) in &mut bodies {
+        rate.modifiers = modifiers
+            .iter()
+            .filter(|modifier| modifier.target == entity)
+            .map(|modifier| (modifier.source, modifier.multiplier))
+            .collect();
+        rate.rate = fold_rate(rate.modifiers.iter().map(|(_, multiplier)| *multiplier));
+        rate.dt = dt * rate.rate;
+    }
+}
+
+pub struct RatePlugin;
+
+impl Plugin for RatePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<RateModifier>().add_system_set(
+            SystemSet::on_update(AppState::Battle).with_system(animation_rate_system),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn modifiers_fold_in_any_order() {
+        let multipliers = [1.5, 0.5, 2.0, 0.8];
+        let expected = fold_rate(multipliers);
+        assert!((expected - 1.2).abs() < 1e-5, "{}", expected);
+        for rotation in 0..multipliers.len() {
+            let mut shuffled = multipliers;
+            shuffled.rotate_left(rotation);
+            shuffled.swap(0, 2);
+            assert!((fold_rate(shuffled) - expected).abs() < 1e-5);
+        }
+        assert_eq!(fold_rate([]), 1.0);
+    }
+
+    #[test]
+    fn stacked_modifiers_stay_in_range() {
+        // Three heavy slows would otherwise all but freeze the animation.
+        assert_eq!(fold_rate([0.2, 0.2, 0.2]), MIN_RATE);
+        assert_eq!(fold_rate([0.0]), MIN_RATE);
+        assert_eq!(fold_rate([3.0, 3.0]), MAX_RATE);
+        // The clamp applies to the total, a slow can still cancel a boost
+        // that alone would go past the cap.
+        assert_eq!(fold_rate([10.0, 0.2]), 2.0);
+        assert_eq!(fold_rate([0.2, 10.0]), 2.0);
+        assert_eq!(fold_rate([f32::NAN, 2.0]), 2.0);
+    }
+}
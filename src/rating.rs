@@ -0,0 +1,101 @@
+use std::collections::HashSet;
+
+pub const DEFAULT_RATING: f64 = 1500.0;
+pub const K_FACTOR: f64 = 32.0;
+
+pub fn expected_score(rating: f64, opponent: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf((opponent - rating) / 400.0))
+}
+
+// `score` is 1 for a win, 0.5 for a draw and 0 for a loss, from `a`'s view.
+pub fn update(a: f64, b: f64, score: f64, k: f64) -> (f64, f64) {
+    let delta = k * (score - expected_score(a, b));
+    (a + delta, b - delta)
+}
+
+pub fn round_robin(players: usize) -> Vec<(usize, usize)> {
+    (0..players)
+        .flat_map(|a| (a + 1..players).map(move |b| (a, b)))
+        .collect()
+}
+
+// Pairs players with similar ratings, skipping rematches where possible. With
+// an odd number of players the lowest rated one without a pair sits out.
+pub fn swiss_round(ratings: &[f64], played: &HashSet<(usize, usize)>) -> Vec<(usize, usize)> {
+    let mut order = (0..ratings.len()).collect::<Vec<_>>();
+    order.sort_by(|a, b| ratings[*b].total_cmp(&ratings[*a]));
+    let key = |a: usize, b: usize| (a.min(b), a.max(b));
+
+    let mut pairs = Vec::new();
+    while let Some(a) = order.first().copied() {
+        order.remove(0);
+        let opponent = order
+            .iter()
+            .position(|b| !played.contains(&key(a, *b)))
+            .or_else(|| (!order.is_empty()).then_some(0));
+        if let Some(i) = opponent {
+            pairs.push(key(a, order.remove(i)));
+        }
+    }
+    pairs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expected_scores_sum_to_one() {
+        for (a, b) in [(1500.0, 1500.0), (1800.0, 1400.0), (1200.0, 2000.0)] {
+            let sum = expected_score(a, b) + expected_score(b, a);
+            assert!((sum - 1.0).abs() < 1e-9);
+        }
+        assert_eq!(expected_score(1500.0, 1500.0), 0.5);
+    }
+
+    #[test]
+    fn update_is_zero_sum() {
+        let (a, b) = update(1600.0, 1400.0, 0.0, K_FACTOR);
+        assert!((a + b - 3000.0).abs() < 1e-9);
+        assert!(a < 1600.0 && b > 1400.0);
+    }
+
+    #[test]
+    fn draw_between_equals_changes_nothing() {
+        assert_eq!(update(1500.0, 1500.0, 0.5, K_FACTOR), (1500.0, 1500.0));
+    }
+
+    #[test]
+    fn upset_moves_ratings_more() {
+        let (favorite_win, _) = update(1800.0, 1400.0, 1.0, K_FACTOR);
+        let (_, underdog_win) = update(1800.0, 1400.0, 0.0, K_FACTOR);
+        assert!(underdog_win - 1400.0 > favorite_win - 1800.0);
+    }
+
+    #[test]
+    fn round_robin_pairs_everyone_once() {
+        let pairs = round_robin(5);
+        assert_eq!(pairs.len(), 10);
+        let unique = pairs.iter().copied().collect::<HashSet<_>>();
+        assert_eq!(unique.len(), 10);
+        assert!(pairs.iter().all(|(a, b)| a < b && *b < 5));
+    }
+
+    #[test]
+    fn swiss_pairs_by_rating_and_avoids_rematches() {
+        let ratings = [1500.0, 1700.0, 1600.0, 1400.0];
+        let pairs = swiss_round(&ratings, &HashSet::new());
+        assert_eq!(pairs, vec![(1, 2), (0, 3)]);
+
+        let played = pairs.into_iter().collect::<HashSet<_>>();
+        let pairs = swiss_round(&ratings, &played);
+        assert!(pairs.iter().all(|pair| !played.contains(pair)));
+        assert_eq!(pairs.len(), 2);
+    }
+
+    #[test]
+    fn swiss_gives_a_bye_with_odd_players() {
+        let pairs = swiss_round(&[1500.0, 1600.0, 1400.0], &HashSet::new());
+        assert_eq!(pairs, vec![(0, 1)]);
+    }
+}
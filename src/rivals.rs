@@ -0,0 +1,248 @@
+use bevy::{ecs::entity::Entities, prelude::*};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    ai::AiRegistry,
+    body::Body,
+    locale::t,
+    profile::{record_result_system, Profile},
+    swap::SwapBody,
+    turn::Ruleset,
+    AppState, BattleSummary, Game,
+};
+
+// None are pushed out past this many, the player deletes one to make room.
+pub const MAX_RIVALS: usize = 10;
+
+// An enemy saved to fight again. The body is saved the way the profile's own
+// is, so it keeps loading when the share code format changes.
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Rival {
+    pub name: String,
+    // None for one saved as a share code, before bodies were kept as they are.
+    body: Option<Body>,
+    // Name its controller is registered under, see AiRegistry.
+    pub ai: String,
+    pub wins: u32,
+    pub losses: u32,
+    pub draws: u32,
+}
+
+impl Rival {
+    fn new(body: &Body, ai: &str) -> Self {
+        Self {
+            name: body.name().to_string(),
+            body: Some(body.clone()),
+            ai: ai.to_string(),
+            ..default()
+        }
+    }
+
+    // The body as it was saved, or why it can't be fought any more.
+    pub fn body(&self, registry: &AiRegistry) -> Result<Body, String> {
+        if !registry.contains(&self.ai) {
+            return Err(t!("rivals.unknown_ai", ai = &self.ai));
+        }
+        let body = self.body.clone().ok_or_else(|| t!("rivals.stale"))?;
+        body.validate().map_err(|err| err.to_string())?;
+        Ok(body)
+    }
+
+    fn share_code(&self) -> Option<String> {
+        self.body.as_ref().map(Body::to_share_code)
+    }
+}
+
+pub enum RivalError {
+    Full,
+    Known(String),
+}
+
+impl std::fmt::Display for RivalError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let message = match self {
+            RivalError::Full => t!("rivals.full", max = MAX_RIVALS),
+            RivalError::Known(name) => t!("rivals.known", name = name),
+        };
+        f.write_str(&message)
+    }
+}
+
+// Oldest first, the player's record against each is kept with it.
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Rivals(Vec<Rival>);
+
+impl Rivals {
+    pub fn list(&self) -> &[Rival] {
+        &self.0
+    }
+
+    pub fn add(&mut self, body: &Body, ai: &str) -> Result<(), RivalError> {
+        let rival = Rival::new(body, ai);
+        if let Some(known) = self
+            .0
+            .iter()
+            .find(|known| known.share_code() == rival.share_code())
+        {
+            return Err(RivalError::Known(known.name.clone()));
+        }
+        if self.0.len() >= MAX_RIVALS {
+            return Err(RivalError::Full);
+        }
+        self.0.push(rival);
+        Ok(())
+    }
+
+    pub fn remove(&mut self, index: usize) -> Option<Rival> {
+        (index < self.0.len()).then(|| self.0.remove(index))
+    }
+
+    // `won` is `None` for a draw.
+    fn record(&mut self, index: usize, won: Option<bool>) {
+        let Some(rival) = self.0.get_mut(index) else {
+            return;
+        };
+        match won {
+            Some(true) => rival.wins += 1,
+            Some(false) => rival.losses += 1,
+            None => rival.draws += 1,
+        }
+    }
+}
+
+// A rematch in progress. The enemy and rules from before it are put back
+// once the player is back on the pre-battle screen.
+pub struct Rematch {
+    index: usize,
+    enemy: Body,
+    ruleset: Ruleset,
+}
+
+// Swaps the rival in for the current enemy, the caller starts the battle.
+// The player's own rules stay apart from survival, a rival is one fight.
+pub fn begin(
+    commands: &mut Commands,
+    ruleset: &mut Ruleset,
+    swaps: &mut EventWriter<SwapBody>,
+    game: &Game,
+    enemy: Body,
+    index: usize,
+    rival: (Body, &str),
+) {
+    let (body, ai) = rival;
+    let rules = Ruleset {
+        survival: false,
+        ai: ai.to_string(),
+        ..*ruleset
+    };
+    commands.insert_resource(Rematch {
+        index,
+        enemy,
+        ruleset: std::mem::replace(ruleset, rules),
+    });
+    swaps.send(SwapBody {
+        entity: game.enemy,
+        body,
+    });
+}
+
+// Before the profile is saved with the result.
+fn record_rematch_system(
+    game: Res<Game>,
+    summary: Res<BattleSummary>,
+    rematch: Option<Res<Rematch>>,
+    mut profile: ResMut<Profile>,
+) {
+    let Some(rematch) = rematch else {
+        return;
+    };
+    let won = summary.winner.map(|winner| winner == game.player);
+    profile.rivals.record(rematch.index, won);
+}
+
+fn leave_rematch_system(
+    mut commands: Commands,
+    game: Res<Game>,
+    entities: &Entities,
+    rematch: Option<ResMut<Rematch>>,
+    mut ruleset: ResMut<Ruleset>,
+    mut swaps: EventWriter<SwapBody>,
+) {
+    let Some(mut rematch) = rematch else {
+        return;
+    };
+    *ruleset = std::mem::take(&mut rematch.ruleset);
+    commands.remove_resource::<Rematch>();
+    if entities.contains(game.enemy) {
+        swaps.send(SwapBody {
+            entity: game.enemy,
+            body: std::mem::take(&mut rematch.enemy),
+        });
+    }
+}
+
+pub struct RivalsPlugin;
+
+impl Plugin for RivalsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(
+            SystemSet::on_enter(AppState::GameOver)
+                .with_system(record_rematch_system.before(record_result_system)),
+        )
+        .add_system_set(SystemSet::on_enter(AppState::PreBattle).with_system(leave_rematch_system));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{body::Limb, fixtures::seeded_bodies};
+
+    #[test]
+    fn rivals_fill_up_and_broken_ones_are_flagged() {
+        let registry = AiRegistry::default();
+        let bodies = seeded_bodies(MAX_RIVALS + 1, 2, 2);
+        let mut rivals = Rivals::default();
+        for body in &bodies[..MAX_RIVALS] {
+            assert!(rivals.add(body, "aggressive").is_ok());
+        }
+        assert!(matches!(
+            rivals.add(&bodies[0], "aggressive"),
+            Err(RivalError::Known(_))
+        ));
+        assert!(matches!(
+            rivals.add(&bodies[MAX_RIVALS], "aggressive"),
+            Err(RivalError::Full)
+        ));
+        rivals.record(0, Some(true));
+        rivals.record(0, None);
+        let rival = &rivals.list()[0];
+        assert_eq!((rival.wins, rival.losses, rival.draws), (1, 0, 1));
+        // Survives a save and load as it was.
+        let saved = ron::to_string(&rivals).unwrap();
+        let loaded = ron::from_str::<Rivals>(&saved).unwrap();
+        for (loaded, rival) in loaded.list().iter().zip(rivals.list()) {
+            assert_eq!(loaded.name, rival.name);
+            assert_eq!(loaded.share_code(), rival.share_code());
+        }
+        let body = loaded.list()[0].body(&registry).unwrap();
+        assert_eq!(body.to_share_code(), bodies[0].to_share_code());
+        // Saved as a share code by an older version.
+        let stale = r#"([(name: "Old", code: "AAAA", ai: "random", wins: 3)])"#;
+        let stale = ron::from_str::<Rivals>(stale).unwrap();
+        assert_eq!(stale.list()[0].wins, 3);
+        assert!(stale.list()[0].body(&registry).is_err());
+        // Edited by hand into a body that can't stand.
+        let mut broken = bodies[0].clone();
+        broken.destroy(Limb::Leg(0));
+        let broken = Rival::new(&broken, "random");
+        assert!(broken.body(&registry).is_err());
+        let unknown = Rival {
+            ai: "retired".to_string(),
+            ..rivals.list()[0].clone()
+        };
+        assert!(unknown.body(&registry).is_err());
+    }
+}
@@ -0,0 +1,128 @@
+use bevy::{prelude::*, sprite::Anchor, ui::FocusPolicy};
+
+use crate::{
+    battle::{edge_gap, melee_connects},
+    body::{Facing, Stats},
+    camera::MainCamera,
+    layer::RenderLayer,
+    turn::Ruleset,
+    ui::{Fonts, SkillButton},
+    Game,
+};
+
+const LINE_THICKNESS: f32 = 0.05;
+const IN_REACH: Color = Color::rgb(0.2, 0.9, 0.3);
+const OUT_OF_REACH: Color = Color::rgb(0.95, 0.25, 0.2);
+
+// Shown while an attack's button is hovered, gone as soon as it isn't.
+struct Ruler {
+    line: Entity,
+    readout: Entity,
+}
+
+fn ruler_system(
+    mut commands: Commands,
+    game: Res<Game>,
+    ruleset: Res<Ruleset>,
+    fonts: Res<Fonts>,
+    mut ruler: Local<Option<Ruler>>,
+    buttons: Query<(&Interaction, &SkillButton)>,
+    bodies: Query<(&Transform, &Facing, &Stats)>,
+    cameras: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    mut lines: Query<(&mut Transform, &mut Sprite), Without<Stats>>,
+    mut readouts: Query<(&mut Style, &mut Text, &mut Visibility)>,
+) {
+    let hovered = buttons
+        .iter()
+        .find(|(interaction, _)| **interaction != Interaction::None)
+        .map(|(_, button)| &button.0);
+    let measured = hovered.and_then(|skill| {
+        let [(player, facing, stats), (enemy, _, enemy_stats)] =
+            bodies.get_many([game.player, game.enemy]).ok()?;
+        let skill = stats.skill(skill)?;
+        let reach = stats.melee_reach(skill)?;
+        Some((skill, reach, player, *facing, stats, enemy, enemy_stats))
+    });
+    let Some((skill, reach, player, facing, stats, enemy, enemy_stats)) = measured else {
+        if let Some(ruler) = ruler.take() {
+            commands.entity(ruler.line).despawn();
+            commands.entity(ruler.readout).despawn();
+        }
+        return;
+    };
+    let ruler = ruler.get_or_insert_with(|| spawn_ruler(&mut commands, &fonts));
+
+    // How far the hovered skill's arm swings out, the same pose the hit tests
+    // use. With auto-face the attack turns first, so only the distance
+    // matters.
+    let facing = if ruleset.auto_face {
+        Facing::towards(player.translation.x, enemy.translation.x)
+    } else {
+        facing
+    };
+    let connects = melee_connects(skill, player, facing, stats, enemy, enemy_stats);
+    let edge = player.translation.x + facing.sign() * stats.width / 2.0;
+    if let Ok((mut transform, mut sprite)) = lines.get_mut(ruler.line) {
+        transform.translation = Vec3::new(edge, player.translation.y, RenderLayer::Floater.z());
+        transform.scale = Vec3::new(facing.sign() * reach, LINE_THICKNESS, 1.0);
+        sprite.color = if connects { IN_REACH } else { OUT_OF_REACH };
+    }
+
+    let gap = edge_gap(player, stats, enemy, enemy_stats).max(0.0);
+    let between = (player.translation + enemy.translation) / 2.0;
+    let screen = cameras
+        .get_single()
+        .ok()
+        .and_then(|(camera, transform)| camera.world_to_viewport(transform, between));
+    if let Ok((mut style, mut text, mut visibility)) = readouts.get_mut(ruler.readout) {
+        visibility.is_visible = screen.is_some();
+        if let Some(screen) = screen {
+            style.position = UiRect {
+                left: Val::Px(screen.x - 20.0),
+                bottom: Val::Px(screen.y - 30.0),
+                ..default()
+            };
+        }
+        text.sections[0].value = format!("{:.2}", gap);
+    }
+}
+
+fn spawn_ruler(commands: &mut Commands, fonts: &Fonts) -> Ruler {
+    let line = commands
+        .spawn_bundle(SpriteBundle {
+            sprite: Sprite {
+                anchor: Anchor::CenterLeft,
+                ..default()
+            },
+            // Placed on the next frame, hidden until then.
+            transform: Transform::from_scale(Vec3::ZERO),
+            ..default()
+        })
+        .id();
+    let readout = commands
+        .spawn_bundle(
+            TextBundle::from_section(
+                "",
+                TextStyle {
+                    font: fonts.bold(),
+                    font_size: 20.0,
+                    color: Color::WHITE,
+                },
+            )
+            .with_style(Style {
+                position_type: PositionType::Absolute,
+                ..default()
+            }),
+        )
+        .insert_bundle((FocusPolicy::Pass, Visibility { is_visible: false }))
+        .id();
+    Ruler { line, readout }
+}
+
+pub struct RulerPlugin;
+
+impl Plugin for RulerPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(ruler_system);
+    }
+}
@@ -0,0 +1,219 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+
+use crate::{
+    battle::{animation_system, Animation, Walking},
+    body::{Skill, SkillId, Stats},
+    timer::SimClock,
+    AppState, Game,
+};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct SkillState {
+    // Seconds before the skill can start again.
+    pub cooldown: f32,
+    // Shots taken since the last reload.
+    pub spent: u8,
+}
+
+// What each skill is up to in a battle, keyed by `Skill::id` like animations.
+// Kept apart from Stats so the body being rebuilt, for a coat of paint or a
+// lost arm, leaves the skills it still has where they were.
+#[derive(Component, Default, Debug, Clone, PartialEq)]
+pub struct SkillRuntime(HashMap<SkillId, SkillState>);
+
+impl SkillRuntime {
+    #[cfg(all(test, feature = "net"))]
+    pub fn with_cooldown(skill: &Skill, cooldown: f32) -> Self {
+        let state = SkillState {
+            cooldown,
+            ..default()
+        };
+        Self(HashMap::from([(skill.id(), state)]))
+    }
+
+    pub fn state(&self, id: &SkillId) -> Option<&SkillState> {
+        self.0.get(id)
+    }
+
+    pub fn cooldown(&self, skill: &Skill) -> f32 {
+        self.state(&skill.id()).map_or(0.0, |state| state.cooldown)
+    }
+
+    // Shots left before `skill` needs a reload, None if it doesn't use ammo.
+    pub fn remaining(&self, skill: &Skill) -> Option<u8> {
+        let capacity = skill.ability()?.ammo?;
+        let spent = self.state(&skill.id()).map_or(0, |state| state.spent);
+        Some(capacity.saturating_sub(spent))
+    }
+
+    pub fn loaded(&self, skill: &Skill) -> bool {
+        self.remaining(skill) != Some(0)
+    }
+
+    // Whether every ranged skill the body has is out, the time to reload.
+    pub fn empty(&self, stats: &Stats) -> bool {
+        let mut ranged = stats
+            .skills
+            .iter()
+            .filter_map(|skill| self.remaining(skill))
+            .peekable();
+        ranged.peek().is_some() && ranged.all(|remaining| remaining == 0)
+    }
+
+    pub fn reload(&mut self) {
+        for state in self.0.values_mut() {
+            state.spent = 0;
+        }
+    }
+
+    // Keeps the skills still on the body as they were, drops the ones that
+    // went with a part and starts new ones fresh.
+    pub fn reconcile(&mut self, stats: &Stats) {
+        self.0
+            .retain(|id, _| stats.skills.iter().any(|skill| skill.id() == *id));
+        for skill in &stats.skills {
+            self.0.entry(skill.id()).or_default();
+        }
+    }
+
+    fn reset(&mut self) {
+        for state in self.0.values_mut() {
+            *state = SkillState::default();
+        }
+    }
+}
+
+// Whether `skill` is off cooldown, bodies without a SkillRuntime never wait.
+pub fn ready(runtime: Option<&SkillRuntime>, skill: &Skill) -> bool {
+    runtime.is_none_or(|runtime| runtime.cooldown(skill) <= 0.0)
+}
+
+fn reset_system(game: Res<Game>, mut bodies: Query<&mut SkillRuntime>) {
+    for entity in [game.player, game.enemy] {
+        if let Ok(mut runtime) = bodies.get_mut(entity) {
+            runtime.reset();
+        }
+    }
+}
+
+fn tick_system(clock: Res<SimClock>, mut bodies: Query<&mut SkillRuntime>) {
+    let dt = clock.delta();
+    for mut runtime in &mut bodies {
+        // Only touched while something cools down, so change detection
+        // means something.
+        if runtime.0.values().any(|state| state.cooldown > 0.0) {
+            for state in runtime.0.values_mut() {
+                state.cooldown = (state.cooldown - dt).max(0.0);
+            }
+        }
+    }
+}
+
+// A skill that plays to the end goes on cooldown, an interrupted one can be
+// tried again straight away.
+fn cooldown_system(
+    mut bodies: Query<(
+        &Stats,
+        Option<&Animation>,
+        Option<&Walking>,
+        &mut SkillRuntime,
+    )>,
+) {
+    for (stats, animation, walking, mut runtime) in &mut bodies {
+        for animation in animation
+            .into_iter()
+            .chain(walking.map(|walking| &walking.0))
+        {
+            if animation.progress <= 1.0 {
+                continue;
+            }
+            let Some(ability) = stats.skill(&animation.skill).and_then(Skill::ability) else {
+                continue;
+            };
+            if ability.cooldown > 0.0 {
+                runtime
+                    .0
+                    .entry(animation.skill.clone())
+                    .or_default()
+                    .cooldown = ability.cooldown;
+            }
+        }
+    }
+}
+
+// Takes the shot the frame the skill starts, like energy.
+fn spend_system(mut started: Query<(&Animation, &Stats, &mut SkillRuntime), Added<Animation>>) {
+    for (animation, stats, mut runtime) in &mut started {
+        if let Some(skill) = stats.skill(&animation.skill) {
+            if runtime.remaining(skill).is_some() {
+                runtime.0.entry(skill.id()).or_default().spent += 1;
+            }
+        }
+    }
+}
+
+pub struct RuntimePlugin;
+
+impl Plugin for RuntimePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(SystemSet::on_enter(AppState::Battle).with_system(reset_system))
+            .add_system_set(
+                SystemSet::on_update(AppState::Battle)
+                    .with_system(tick_system.before(cooldown_system))
+                    .with_system(cooldown_system.after(animation_system))
+                    .with_system(spend_system),
+            );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::body::{Ability, Limb};
+
+    fn shot(limb: u8, ammo: u8) -> Skill {
+        Skill::BasicRanged(Ability {
+            meta: 5.0,
+            time: 1.0,
+            cooldown: 0.0,
+            energy_cost: 1.0,
+            limb: Limb::Arm(limb),
+            name: format!("Shot {}", limb),
+            interrupting: false,
+            ammo: Some(ammo),
+            emp: false,
+        })
+    }
+
+    #[test]
+    fn reload_refills_every_ranged_skill() {
+        let stats = Stats {
+            skills: vec![Skill::WalkForward, shot(0, 2), shot(1, 3), Skill::Reload],
+            ..default()
+        };
+        let mut runtime = SkillRuntime::default();
+        assert!(!runtime.empty(&stats));
+        assert_eq!(runtime.remaining(&Skill::WalkForward), None);
+        for skill in [&stats.skills[1], &stats.skills[1], &stats.skills[2]] {
+            runtime.0.entry(skill.id()).or_default().spent += 1;
+        }
+        assert!(!runtime.loaded(&stats.skills[1]));
+        assert_eq!(runtime.remaining(&stats.skills[2]), Some(2));
+        assert!(!runtime.empty(&stats));
+        for _ in 0..2 {
+            runtime.0.entry(stats.skills[2].id()).or_default().spent += 1;
+        }
+        assert!(runtime.empty(&stats));
+        runtime.reload();
+        assert_eq!(runtime.remaining(&stats.skills[1]), Some(2));
+        assert_eq!(runtime.remaining(&stats.skills[2]), Some(3));
+        // Nothing to reload on a body that never shoots.
+        let melee = Stats {
+            skills: vec![Skill::WalkForward],
+            ..default()
+        };
+        assert!(!runtime.empty(&melee));
+    }
+}
@@ -0,0 +1,123 @@
+use bevy::{
+    prelude::*,
+    render::render_resource::{Extent3d, TextureDimension, TextureFormat},
+};
+
+use crate::{
+    battle::Ground,
+    body::{Body, Stats},
+    layer::RenderLayer,
+    performance::PerformanceProfile,
+};
+
+const TEXTURE_SIZE: u32 = 32;
+// Wider than the torso, the legs and arms stick out past it.
+const WIDTH_FACTOR: f32 = 1.6;
+const HEIGHT: f32 = 0.15;
+const ALPHA: f32 = 0.5;
+// Jump height the shadow has faded to its lightest at.
+const FADE_HEIGHT: f32 = 3.0;
+const MIN_ALPHA: f32 = 0.1;
+
+struct ShadowTexture(Handle<Image>);
+
+impl FromWorld for ShadowTexture {
+    fn from_world(world: &mut World) -> Self {
+        let mut images = world.resource_mut::<Assets<Image>>();
+        Self(images.add(render_ellipse()))
+    }
+}
+
+// A blob on the ground line under a body, so it reads as standing on the
+// floor. Rebuilt with the body's other children.
+#[derive(Component)]
+struct Shadow;
+
+fn render_ellipse() -> Image {
+    let mut data = Vec::with_capacity((TEXTURE_SIZE * TEXTURE_SIZE * 4) as usize);
+    let radius = TEXTURE_SIZE as f32 / 2.0;
+    for y in 0..TEXTURE_SIZE {
+        for x in 0..TEXTURE_SIZE {
+            let offset = (Vec2::new(x as f32, y as f32) + 0.5 - radius) / radius;
+            // Soft towards the rim.
+            let alpha = (1.0 - offset.length_squared()).clamp(0.0, 1.0).sqrt();
+            data.extend([0, 0, 0, (alpha * 255.0) as u8]);
+        }
+    }
+    Image::new(
+        Extent3d {
+            width: TEXTURE_SIZE,
+            height: TEXTURE_SIZE,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+    )
+}
+
+fn shadow_alpha(height: f32) -> f32 {
+    ALPHA * (1.0 - height.max(0.0) / FADE_HEIGHT).max(MIN_ALPHA)
+}
+
+// Rebuilds only replace the part sprites, the shadow stays for the body's life.
+fn spawn_shadow_system(
+    mut commands: Commands,
+    texture: Res<ShadowTexture>,
+    bodies: Query<(Entity, &RenderLayer), Added<Body>>,
+) {
+    for (entity, layer) in &bodies {
+        commands.entity(entity).with_children(|parent| {
+            parent
+                .spawn_bundle(SpriteBundle {
+                    texture: texture.0.clone(),
+                    sprite: Sprite {
+                        custom_size: Some(Vec2::ONE),
+                        ..default()
+                    },
+                    transform: Transform::from_xyz(
+                        0.0,
+                        0.0,
+                        RenderLayer::Shadow.relative_to(*layer),
+                    ),
+                    ..default()
+                })
+                .insert(Shadow);
+        });
+    }
+}
+
+// Stays on the ground line while the body jumps, and fades the higher it is.
+// Turned off they're only hidden, there's one per body.
+fn shadow_system(
+    ground: Res<Ground>,
+    performance: Res<PerformanceProfile>,
+    bodies: Query<(&Transform, &Stats), Without<Shadow>>,
+    mut shadows: Query<(&Parent, &mut Transform, &mut Sprite, &mut Visibility), With<Shadow>>,
+) {
+    for (parent, mut transform, mut sprite, mut visibility) in &mut shadows {
+        if visibility.is_visible != performance.shadows {
+            visibility.is_visible = performance.shadows;
+        }
+        if !performance.shadows {
+            continue;
+        }
+        let Ok((body, stats)) = bodies.get(parent.get()) else {
+            continue;
+        };
+        let height = body.translation.y - ground.y;
+        transform.translation.y = -height / body.scale.y;
+        transform.scale = Vec3::new(stats.width * WIDTH_FACTOR, HEIGHT, 1.0);
+        sprite.color.set_a(shadow_alpha(height));
+    }
+}
+
+pub struct ShadowPlugin;
+
+impl Plugin for ShadowPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ShadowTexture>()
+            .add_system(spawn_shadow_system)
+            .add_system(shadow_system);
+    }
+}
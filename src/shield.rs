@@ -0,0 +1,54 @@
+use bevy::prelude::*;
+
+use crate::{
+    balance::BalanceConfig, battle::damage_system, body::Stats, timer::SimClock, AppState,
+    DamageEvent,
+};
+
+// Seconds since the body last took damage. The overshield stays down until
+// the body has gone `overshield_delay` without a hit.
+#[derive(Component, Default)]
+pub struct ShieldTimer(f32);
+
+// Shield parts that draw more energy bring their shield back faster.
+pub fn recharge_per_second(balance: &BalanceConfig, stats: &Stats) -> f32 {
+    balance.overshield_recharge * stats.overshield_draw
+}
+
+fn recharge_system(
+    clock: Res<SimClock>,
+    balance: Res<BalanceConfig>,
+    mut events: EventReader<DamageEvent>,
+    mut bodies: Query<(Entity, &mut Stats, &mut ShieldTimer)>,
+) {
+    let dt = clock.delta();
+    let hit = events
+        .iter()
+        .filter(|event| event.amount > 0.0)
+        .map(|event| event.target)
+        .collect::<Vec<_>>();
+    for (entity, mut stats, mut timer) in &mut bodies {
+        if hit.contains(&entity) {
+            timer.0 = 0.0;
+            continue;
+        }
+        timer.0 += dt;
+        // Only touches Stats while there's something to refill, so bodies
+        // without a shield don't look changed every frame.
+        if timer.0 >= balance.overshield_delay && stats.overshield < stats.max_overshield {
+            let refill = recharge_per_second(&balance, &stats) * dt;
+            stats.overshield = (stats.overshield + refill).min(stats.max_overshield);
+        }
+    }
+}
+
+pub struct ShieldPlugin;
+
+impl Plugin for ShieldPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(
+            SystemSet::on_update(AppState::Battle)
+                .with_system(recharge_system.after(damage_system)),
+        );
+    }
+}
@@ -0,0 +1,1472 @@
+use std::{
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::Duration,
+};
+
+use bevy::{core::CorePlugin, prelude::*, utils::Instant};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::{
+    ai::{AiRegistry, Decision},
+    balance::BalanceConfig,
+    battle::{spawn_combatants, BattlePlugin},
+    body::{random_body, Body, Stats, WeightClass},
+    mutators::Mutators,
+    timeline::{Timeline, TimelinePlugin},
+    timer::{SimClock, TimeScale, TIMER_OPTIONS},
+    tournament,
+    turn::Ruleset,
+    AppState, BattleSummary, Game, GameRng,
+};
+
+const FRAME: f64 = 1.0 / 60.0;
+// Sudden death ends every timed battle long before this, it only guards
+// against battles that can never end.
+const MAX_FRAMES: u32 = 60 * 60 * 10;
+
+pub struct SimOptions {
+    pub fast: bool,
+    pub speed: f32,
+    pub seed: Option<u64>,
+    // Picked from AiRegistry by name, both sides get one.
+    pub ai: String,
+    pub pool: Option<PathBuf>,
+    pub generate: usize,
+    // Only bodies in this class are generated and entered.
+    pub class: Option<WeightClass>,
+    pub matches: u32,
+    pub swiss: Option<u32>,
+    pub out: PathBuf,
+    pub timer: Option<f32>,
+    pub interrupts: bool,
+    pub poise: bool,
+    pub pickups: bool,
+    pub mutators: Mutators,
+    pub balance: BalanceConfig,
+    // Prints a snapshot every second and every event after the result.
+    pub timeline: bool,
+    // Prints only the moment at this many seconds in, see `print_moment`.
+    pub at: Option<f32>,
+}
+
+fn value<T: FromStr>(args: &mut impl Iterator<Item = String>, flag: &str) -> Option<T> {
+    let value = args.next().and_then(|value| value.parse().ok());
+    if value.is_none() {
+        eprintln!("{} expects a valid value", flag);
+    }
+    value
+}
+
+impl SimOptions {
+    pub fn from_args(args: impl Iterator<Item = String>) -> Option<Self> {
+        let mut sim = false;
+        let mut options = SimOptions {
+            fast: false,
+            speed: 1.0,
+            seed: None,
+            ai: "random".to_string(),
+            pool: None,
+            generate: 0,
+            class: None,
+            matches: 2,
+            swiss: None,
+            out: PathBuf::from("."),
+            timer: TIMER_OPTIONS[0],
+            interrupts: false,
+            poise: false,
+            pickups: false,
+            mutators: Mutators::default(),
+            balance: BalanceConfig::default(),
+            timeline: false,
+            at: None,
+        };
+        let mut args = args.peekable();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--sim" => sim = true,
+                "--fast" => options.fast = true,
+                "--speed" => options.speed = value(&mut args, &arg).unwrap_or(options.speed),
+                "--seed" => options.seed = value(&mut args, &arg).or(options.seed),
+                "--ai" => match args.next() {
+                    Some(name) if AiRegistry::default().contains(&name) => options.ai = name,
+                    _ => eprintln!(
+                        "{} expects one of {}",
+                        arg,
+                        AiRegistry::default().names().collect::<Vec<_>>().join(", ")
+                    ),
+                },
+                "--pool" => options.pool = value(&mut args, &arg).or(options.pool),
+                "--generate" => {
+                    options.generate = value(&mut args, &arg).unwrap_or(options.generate)
+                }
+                "--class" => options.class = value(&mut args, &arg).or(options.class),
+                "--matches" => options.matches = value(&mut args, &arg).unwrap_or(options.matches),
+                "--swiss" => options.swiss = value(&mut args, &arg).or(options.swiss),
+                "--out" => options.out = value(&mut args, &arg).unwrap_or(options.out),
+                "--no-timer" => options.timer = None,
+                "--interrupts" => options.interrupts = true,
+                "--poise" => options.poise = true,
+                "--pickups" => options.pickups = true,
+                "--timeline" => options.timeline = true,
+                "--at" => {
+                    options.at = value(&mut args, &arg).or(options.at);
+                    options.timeline |= options.at.is_some();
+                }
+                "--mutators" => {
+                    options.mutators = value(&mut args, &arg).unwrap_or(options.mutators)
+                }
+                "--balance" => {
+                    if let Some(path) = value::<PathBuf>(&mut args, &arg) {
+                        options.balance = load_balance(&path);
+                    }
+                }
+                _ => eprintln!("Unknown argument `{}`", arg),
+            }
+        }
+        sim.then_some(options)
+    }
+}
+
+// A bad balance file would quietly turn an experiment into a run on the
+// defaults, so it stops the sim instead.
+fn load_balance(path: &Path) -> BalanceConfig {
+    let loaded = std::fs::read_to_string(path)
+        .map_err(|err| err.to_string())
+        .and_then(|contents| BalanceConfig::parse(&contents));
+    match loaded {
+        Ok(balance) => balance,
+        Err(err) => {
+            eprintln!("{}: {}", path.display(), err);
+            std::process::exit(1);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Player,
+    Enemy,
+    Draw,
+}
+
+pub struct MatchResult {
+    pub outcome: Outcome,
+    pub sudden_death: bool,
+    pub duration: f64,
+    // The battle was still running after `MAX_FRAMES`.
+    pub timed_out: bool,
+    // What each side's AI last thought, the player's first.
+    pub decisions: Vec<(&'static str, Decision)>,
+    pub timeline: Option<Timeline>,
+}
+
+struct SimSetup {
+    bodies: Option<(Body, Body)>,
+    ai: String,
+}
+
+// The simulation advances by a fixed step every frame, so `--fast` only
+// removes the wait between frames and never changes the outcome.
+fn fixed_time_system(mut time: ResMut<Time>) {
+    let last = time.last_update().unwrap_or_else(Instant::now);
+    time.update_with_instant(last + Duration::from_secs_f64(FRAME));
+}
+
+fn sim_setup_system(
+    mut commands: Commands,
+    mut setup: ResMut<SimSetup>,
+    registry: Res<AiRegistry>,
+) {
+    let (player, enemy) = setup.bodies.take().expect("bodies are only taken once");
+    let game = spawn_combatants(&mut commands, player, enemy);
+    for entity in [game.player, game.enemy] {
+        let controller = registry
+            .create(&setup.ai)
+            .expect("the AI is checked when parsing options");
+        commands.entity(entity).insert(controller);
+    }
+    commands.insert_resource(game);
+}
+
+fn sim_start_system(game: Res<Game>, stats: Query<&Stats>, mut state: ResMut<State<AppState>>) {
+    if game.targets_self() {
+        return;
+    }
+    if let Ok([player, enemy]) = stats.get_many([game.player, game.enemy]) {
+        if player.max_health > 0.0 && enemy.max_health > 0.0 {
+            let _ = state.set(AppState::Battle);
+        }
+    }
+}
+
+fn build_app(player: Body, enemy: Body, options: &SimOptions, seed: u64) -> App {
+    let mut app = App::new();
+    app.add_plugin(CorePlugin)
+        .init_resource::<Time>()
+        .add_system_to_stage(CoreStage::First, fixed_time_system)
+        .insert_resource(TimeScale(options.speed))
+        .insert_resource(GameRng::new(seed))
+        .insert_resource(options.balance.clone())
+        .insert_resource(Ruleset {
+            timer: options.timer,
+            interrupts: options.interrupts,
+            poise: options.poise,
+            mutators: options.mutators,
+            // Balance runs are fought in clear weather.
+            weather: false,
+            pickups: options.pickups,
+            ai: options.ai.clone(),
+            ..default()
+        })
+        .insert_resource(SimSetup {
+            bodies: Some((player, enemy)),
+            ai: options.ai.clone(),
+        })
+        .add_plugin(BattlePlugin)
+        .add_startup_system(sim_setup_system)
+        .add_system_set(SystemSet::on_update(AppState::PreBattle).with_system(sim_start_system));
+    if options.timeline {
+        app.add_plugin(TimelinePlugin);
+    }
+    app
+}
+
+pub fn simulate(player: Body, enemy: Body, options: &SimOptions, seed: u64) -> MatchResult {
+    let mut app = build_app(player, enemy, options, seed);
+
+    let mut outcome = Outcome::Draw;
+    let mut sudden_death = false;
+    let mut timed_out = true;
+    let mut decisions = Vec::new();
+    for _ in 0..MAX_FRAMES {
+        app.update();
+        if let Some(summary) = app.world.get_resource::<BattleSummary>() {
+            let game = app.world.resource::<Game>();
+            outcome = match summary.winner {
+                Some(winner) if winner == game.player => Outcome::Player,
+                Some(_) => Outcome::Enemy,
+                None => Outcome::Draw,
+            };
+            sudden_death = summary.sudden_death;
+            for (side, entity) in [("player", game.player), ("enemy", game.enemy)] {
+                if let Some((_, decision)) = summary.decisions.iter().find(|(e, _)| *e == entity) {
+                    decisions.push((side, decision.clone()));
+                }
+            }
+            timed_out = false;
+            break;
+        }
+        if !options.fast {
+            std::thread::sleep(Duration::from_secs_f64(FRAME));
+        }
+    }
+    MatchResult {
+        outcome,
+        sudden_death,
+        duration: app.world.resource::<SimClock>().elapsed() as f64,
+        timed_out,
+        decisions,
+        timeline: app.world.remove_resource::<Timeline>(),
+    }
+}
+
+pub fn run(options: SimOptions) {
+    if let Some(pool) = &options.pool {
+        tournament::run(&options, pool);
+        return;
+    }
+    let seed = options.seed.unwrap_or_else(|| rand::thread_rng().gen());
+    let enemy = random_body(&mut StdRng::seed_from_u64(seed));
+    let result = simulate(Body::default(), enemy, &options, seed);
+    let winner = match result.outcome {
+        Outcome::Player => "player",
+        Outcome::Enemy => "enemy",
+        Outcome::Draw => "draw",
+    };
+    println!(
+        "winner: {}, sudden death: {}, duration: {:.1}s, seed: {}",
+        winner, result.sudden_death, result.duration, seed
+    );
+    if result.timed_out {
+        println!("battle did not finish within {} frames", MAX_FRAMES);
+    }
+    for (side, decision) in &result.decisions {
+        println!("{} last decided: {}", side, decision);
+    }
+    match (&result.timeline, options.at) {
+        (Some(timeline), Some(time)) => print_moment(timeline, time),
+        (Some(timeline), None) => print!("{}", timeline),
+        (None, _) => {}
+    }
+}
+
+// The bodies as of the last snapshot before `time`, who had the player's body
+// and what happened within a second either side.
+fn print_moment(timeline: &Timeline, time: f32) {
+    match timeline.snapshot_at(time) {
+        Some(snapshot) => println!("{}", snapshot),
+        None => println!("nothing recorded before {:.1}s", time),
+    }
+    if let Some(ai) = timeline.controller_at(time) {
+        println!("player handed over to {}", ai);
+    }
+    for event in timeline.events_around(time, 1.0) {
+        println!("{:6.1}s    {}", event.time, event.text);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::ecs::{
+        event::{Events, ManualEventReader},
+        system::CommandQueue,
+    };
+
+    use crate::{
+        ai::{AiController, AiRegistry},
+        approach::Approach,
+        attachment::{ArmMeta, Attachment},
+        auto::Autopilot,
+        battle::{
+            expected_damage, melee_in_reach, walk_destination, Animation, GaitPhase, Walking,
+        },
+        body::{compute_stats, part_sprites, Ability, Arm, Facing, Limb, Skill},
+        booster::{Booster, Boosters},
+        boss::BossDef,
+        fixtures::seeded_bodies,
+        hitbox_view::ShowHitboxes,
+        interrupt::Recovery,
+        locks::LimbLocks,
+        pickup::Pickup,
+        profile::Profile,
+        projectile::Projectile,
+        runtime::SkillRuntime,
+        swap::SwapBody,
+        telemetry::TelemetryPlugin,
+        theme::Theme,
+        training::{self, Training, TrainingPlugin},
+        ui::UseSkill,
+        DamageEvent, DamageSource, Invulnerable,
+    };
+
+    fn foundry_golem() -> Body {
+        let path = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/assets/bosses/foundry_golem.boss.ron"
+        );
+        let contents = std::fs::read_to_string(path).unwrap();
+        ron::from_str::<BossDef>(&contents).unwrap().body
+    }
+
+    fn test_options() -> SimOptions {
+        let mut options = SimOptions::from_args(["--sim".to_string()].into_iter()).unwrap();
+        options.fast = true;
+        options.ai = "aggressive".to_string();
+        options
+    }
+
+    // Steps a headless battle where nobody acts unless told to. The player
+    // goes through `UseSkill` like a button press, the enemy starts its
+    // animations the way the AI does.
+    struct ScriptedFight {
+        app: App,
+        game: Game,
+    }
+
+    impl ScriptedFight {
+        fn new(player: Body, enemy: Body) -> Self {
+            Self::with_options(player, enemy, &test_options())
+        }
+
+        fn with_options(player: Body, enemy: Body, options: &SimOptions) -> Self {
+            let mut app = build_app(player, enemy, options, 7);
+            app.update();
+            let game = *app.world.resource::<Game>();
+            for entity in [game.player, game.enemy] {
+                app.world.entity_mut(entity).remove::<AiController>();
+            }
+            assert_eq!(
+                app.world.resource::<State<AppState>>().current(),
+                &AppState::Battle
+            );
+            Self { app, game }
+        }
+
+        fn stats(&self, entity: Entity) -> &Stats {
+            self.app.world.get::<Stats>(entity).unwrap()
+        }
+
+        fn x(&self, entity: Entity) -> f32 {
+            self.app
+                .world
+                .get::<Transform>(entity)
+                .unwrap()
+                .translation
+                .x
+        }
+
+        fn busy(&self, entity: Entity) -> bool {
+            let entity = self.app.world.entity(entity);
+            entity.contains::<Animation>()
+                || entity.contains::<Walking>()
+                || entity.contains::<Recovery>()
+        }
+
+        fn in_reach(&self, attacker: Entity) -> bool {
+            let target = self.game.opponent(attacker);
+            let world = &self.app.world;
+            melee_in_reach(
+                world.get::<Transform>(attacker).unwrap(),
+                *world.get::<Facing>(attacker).unwrap(),
+                self.stats(attacker),
+                world.get::<Transform>(target).unwrap(),
+                self.stats(target),
+            )
+        }
+
+        fn expected_damage(&self, attacker: Entity, skill: usize) -> f32 {
+            let target = self.game.opponent(attacker);
+            let world = &self.app.world;
+            expected_damage(
+                world.resource::<BalanceConfig>(),
+                &self.stats(attacker).skills[skill],
+                world.get::<Transform>(attacker).unwrap(),
+                *world.get::<Facing>(attacker).unwrap(),
+                self.stats(attacker),
+                world.get::<Transform>(target).unwrap(),
+                self.stats(target),
+            )
+            .unwrap_or(0.0)
+        }
+
+        fn skill(&self, entity: Entity, find: fn(&Skill) -> bool) -> usize {
+            self.stats(entity).skills.iter().position(find).unwrap()
+        }
+
+        fn start(&mut self, entity: Entity, skill: usize) {
+            let skill = self.stats(entity).skills[skill].id();
+            if entity == self.game.player {
+                **self.app.world.resource_mut::<UseSkill>() = Some(skill);
+            } else {
+                self.app
+                    .world
+                    .entity_mut(entity)
+                    .insert(Animation::new(skill));
+            }
+        }
+
+        fn cooling(&self, entity: Entity) -> bool {
+            let runtime = self.app.world.get::<SkillRuntime>(entity).unwrap();
+            self.stats(entity)
+                .skills
+                .iter()
+                .any(|skill| runtime.cooldown(skill) > 0.0)
+        }
+
+        // Ticks fixed frames until both bodies are done with what they were
+        // doing and can do it again.
+        fn settle(&mut self) {
+            for _ in 0..60 * 10 {
+                self.app.update();
+                if [self.game.player, self.game.enemy]
+                    .iter()
+                    .all(|entity| !self.busy(*entity) && !self.cooling(*entity))
+                {
+                    return;
+                }
+            }
+            panic!("the bodies never stopped moving");
+        }
+    }
+
+    #[test]
+    fn scripted_fight_checkpoints() {
+        let mut fight = ScriptedFight::new(Body::default(), Body::default());
+        let Game { player, enemy } = fight.game;
+        let max_health = fight.stats(player).max_health;
+        assert_eq!(fight.stats(enemy).health, max_health);
+
+        // Checkpoint 1: walking closes the gap and nobody gets hurt.
+        let walk = fight.skill(player, |skill| matches!(skill, Skill::WalkForward));
+        let mut walks = 0;
+        while !fight.in_reach(player) {
+            let before = fight.x(player);
+            fight.start(player, walk);
+            fight.settle();
+            assert!(fight.x(player) > before, "walk {} went nowhere", walks);
+            assert!(fight.x(player) < fight.x(enemy));
+            walks += 1;
+            assert!(walks < 20, "never got in reach");
+        }
+        assert!(walks > 0);
+        assert_eq!(fight.stats(player).health, max_health);
+        assert_eq!(fight.stats(enemy).health, max_health);
+
+        // Checkpoint 2: a jab in reach takes exactly its damage.
+        let jab = fight.skill(player, |skill| matches!(skill, Skill::BasicMelee(_)));
+        let damage = fight.expected_damage(player, jab);
+        assert!(damage > 0.0);
+        fight.start(player, jab);
+        fight.settle();
+        assert_eq!(fight.stats(enemy).health, max_health - damage);
+        assert_eq!(fight.stats(player).health, max_health);
+
+        // Checkpoint 3: the enemy answers and both land at the same time.
+        let enemy_jab = fight.skill(enemy, |skill| matches!(skill, Skill::BasicMelee(_)));
+        let answer = fight.expected_damage(enemy, enemy_jab);
+        assert!(fight.in_reach(enemy) && answer > 0.0);
+        fight.start(player, jab);
+        fight.start(enemy, enemy_jab);
+        fight.settle();
+        assert_eq!(fight.stats(enemy).health, max_health - damage * 2.0);
+        assert_eq!(fight.stats(player).health, max_health - answer);
+
+        // Checkpoint 4: walking back leaves reach, the enemy swings at air.
+        let back = fight.skill(player, |skill| matches!(skill, Skill::WalkBackward));
+        let mut walks = 0;
+        while fight.in_reach(enemy) {
+            fight.start(player, back);
+            fight.settle();
+            walks += 1;
+            assert!(walks < 20, "never left reach");
+        }
+        fight.start(enemy, enemy_jab);
+        fight.settle();
+        assert_eq!(fight.stats(player).health, max_health - answer);
+        assert!(fight.x(player) < fight.x(enemy));
+        assert_eq!(
+            fight.app.world.resource::<State<AppState>>().current(),
+            &AppState::Battle
+        );
+    }
+
+    // Puts the jab's arm just inside and just outside its predicted reach and
+    // checks the jab connects only the first time, the preview agreeing both
+    // times. Returns the reach.
+    fn jab_lands_at_its_reach(player_body: Body) -> f32 {
+        let mut fight = ScriptedFight::new(player_body, Body::default());
+        let Game { player, enemy } = fight.game;
+        let jab = fight.skill(player, |skill| matches!(skill, Skill::BasicMelee(_)));
+        let reach = fight
+            .stats(player)
+            .melee_reach(&fight.stats(player).skills[jab])
+            .unwrap();
+        // The enemy faces the player, its front arm hangs out towards it.
+        let widths = (fight.stats(player).width + fight.stats(enemy).width) / 2.0
+            + fight.stats(enemy).guard(true);
+        for (gap, lands) in [(reach - 0.02, true), (reach + 0.02, false)] {
+            let health = fight.stats(enemy).health;
+            let x = fight.x(enemy) - widths - gap;
+            let mut transform = fight.app.world.get_mut::<Transform>(player).unwrap();
+            transform.translation.x = x;
+            assert_eq!(
+                fight.expected_damage(player, jab) > 0.0,
+                lands,
+                "gap {}",
+                gap
+            );
+            fight.start(player, jab);
+            fight.settle();
+            assert_eq!(fight.stats(enemy).health < health, lands, "gap {}", gap);
+        }
+        reach
+    }
+
+    #[test]
+    fn predicted_reach_is_where_the_jab_lands() {
+        let bare = jab_lands_at_its_reach(Body::default());
+
+        // A rod on the jabbing arm reaches further, and still right where
+        // the prediction says.
+        let mut rodded = Body::default();
+        let jab = compute_stats(&rodded)
+            .skills
+            .iter()
+            .find_map(|skill| match skill.hits()?.0.limb {
+                Limb::Arm(slot) if matches!(skill, Skill::BasicMelee(_)) => Some(slot),
+                _ => None,
+            })
+            .unwrap();
+        rodded.attach(jab, Some(Attachment::ExtensionRod));
+        assert!(jab_lands_at_its_reach(rodded) > bare);
+    }
+
+    #[test]
+    fn selection_is_dropped_when_the_body_loses_the_skill() {
+        let mut fight = ScriptedFight::new(Body::default(), Body::default());
+        let Game { player, enemy } = fight.game;
+        let max_health = fight.stats(enemy).max_health;
+        let jab = fight.skill(player, |skill| matches!(skill, Skill::BasicMelee(_)));
+        fight.start(player, jab);
+
+        // The arms lose their jab between the click and the animation.
+        let default = Body::default();
+        let stats = default.arms()[0].stats().clone().with_skills(Vec::new());
+        let bare_arm = Arm::new("Bare Arm", stats, ArmMeta::default());
+        let body = Body::builder()
+            .torso(default.torso().clone())
+            .head(default.head().clone())
+            .arm(bare_arm.clone())
+            .arm(bare_arm)
+            .leg(default.legs()[0].clone())
+            .leg(default.legs()[1].clone())
+            .build()
+            .unwrap();
+        *fight.app.world.get_mut::<Body>(player).unwrap() = body;
+        fight.settle();
+        assert!(fight.app.world.resource::<UseSkill>().is_none());
+        assert_eq!(fight.stats(enemy).health, max_health);
+
+        // Nothing is stuck, the next pick plays.
+        let before = fight.x(player);
+        let back = fight.skill(player, |skill| matches!(skill, Skill::WalkBackward));
+        fight.start(player, back);
+        fight.settle();
+        assert!(fight.x(player) < before);
+        assert!(fight.app.world.resource::<UseSkill>().is_none());
+    }
+
+    fn runtime(fight: &ScriptedFight, entity: Entity) -> SkillRuntime {
+        fight.app.world.get::<SkillRuntime>(entity).unwrap().clone()
+    }
+
+    #[test]
+    fn cooldowns_outlive_a_repaint() {
+        let mut fight = ScriptedFight::new(Body::default(), Body::default());
+        let Game { player, .. } = fight.game;
+        let jab_index = fight.skill(player, |skill| matches!(skill, Skill::BasicMelee(_)));
+        let jab = fight.stats(player).skills[jab_index].clone();
+        fight.start(player, jab_index);
+        while runtime(&fight, player).cooldown(&jab) == 0.0 {
+            assert!(fight.busy(player), "the jab never went on cooldown");
+            fight.app.update();
+        }
+
+        // A new coat of paint rebuilds the stats, the jab keeps cooling.
+        let cooling = runtime(&fight, player).cooldown(&jab);
+        {
+            let mut body = fight.app.world.get_mut::<Body>(player).unwrap();
+            let arm = &mut body.arms_mut()[0];
+            let stats = arm.stats().clone().with_color(Color::PINK);
+            *arm = Arm::new(arm.name(), stats, ArmMeta::default()).with_slot(arm.slot());
+        }
+        fight.app.update();
+        let repainted = runtime(&fight, player).cooldown(&jab);
+        assert!(repainted > 0.0 && repainted <= cooling);
+        fight.start(player, jab_index);
+        fight.app.update();
+        assert!(!fight.app.world.entity(player).contains::<Animation>());
+    }
+
+    #[test]
+    fn losing_an_arm_drops_its_skill_state() {
+        let mut fight = ScriptedFight::new(Body::default(), Body::default());
+        let Game { player, .. } = fight.game;
+        let shot_index = fight.skill(player, |skill| matches!(skill, Skill::BasicRanged(_)));
+        let shot = fight.stats(player).skills[shot_index].clone();
+        let jab = fight.skill(player, |skill| matches!(skill, Skill::BasicMelee(_)));
+        let jab = fight.stats(player).skills[jab].clone();
+        fight.start(player, shot_index);
+        fight.app.update();
+        fight.app.update();
+        let spent = runtime(&fight, player).state(&shot.id()).unwrap().spent;
+        assert_eq!(spent, 1);
+
+        fight
+            .app
+            .world
+            .get_mut::<Body>(player)
+            .unwrap()
+            .destroy(Limb::Arm(1));
+        fight.settle();
+        let after = runtime(&fight, player);
+        assert!(after.state(&shot.id()).is_none());
+        assert!(after.state(&jab.id()).is_some());
+    }
+
+    #[test]
+    fn jabs_land_on_the_move() {
+        let mut fight = ScriptedFight::new(Body::default(), Body::default());
+        let Game { player, .. } = fight.game;
+        let walk = fight.skill(player, |skill| matches!(skill, Skill::WalkForward));
+        let jab = fight.skill(player, |skill| matches!(skill, Skill::BasicMelee(_)));
+        let holder = |fight: &ScriptedFight, limb| {
+            let locks = fight.app.world.get::<LimbLocks>(player).unwrap();
+            locks.holder(limb).cloned()
+        };
+
+        // The jab swaps in as the action and the walk carries on under it.
+        let before = fight.x(player);
+        fight.start(player, walk);
+        fight.app.update();
+        fight.start(player, jab);
+        fight.app.update();
+        fight.app.update();
+        let world = &fight.app.world;
+        let jab_id = fight.stats(player).skills[jab].id();
+        let walk_id = fight.stats(player).skills[walk].id();
+        assert_eq!(world.get::<Animation>(player).unwrap().skill, jab_id);
+        assert_eq!(world.get::<Walking>(player).unwrap().0.skill, walk_id);
+        assert_eq!(holder(&fight, Limb::Leg(0)), Some(walk_id.clone()));
+        assert_eq!(holder(&fight, Limb::Arm(0)), Some(jab_id));
+        fight.settle();
+        fight.app.update();
+        assert!(fight.x(player) > before);
+        assert_eq!(holder(&fight, Limb::Leg(0)), None);
+        assert_eq!(holder(&fight, Limb::Arm(0)), None);
+        assert!(fight.app.world.resource::<UseSkill>().is_none());
+
+        // Losing the jab mid-walk frees its arm, the legs stay busy.
+        fight.start(player, walk);
+        fight.app.update();
+        fight.start(player, jab);
+        fight.app.update();
+        fight.app.update();
+        let default = Body::default();
+        let stats = default.arms()[0].stats().clone().with_skills(Vec::new());
+        let bare_arm = Arm::new("Bare Arm", stats, ArmMeta::default());
+        let body = Body::builder()
+            .torso(default.torso().clone())
+            .head(default.head().clone())
+            .arm(bare_arm.clone())
+            .arm(bare_arm)
+            .leg(default.legs()[0].clone())
+            .leg(default.legs()[1].clone())
+            .build()
+            .unwrap();
+        *fight.app.world.get_mut::<Body>(player).unwrap() = body;
+        fight.app.update();
+        fight.app.update();
+        assert!(fight.app.world.get::<Walking>(player).is_some());
+        assert_eq!(holder(&fight, Limb::Arm(0)), None);
+        assert_eq!(holder(&fight, Limb::Leg(0)), Some(walk_id));
+        fight.settle();
+        fight.app.update();
+        assert_eq!(holder(&fight, Limb::Leg(0)), None);
+    }
+
+    #[test]
+    fn an_interrupted_jab_frees_its_arm_and_the_walk_carries_on() {
+        // Every jab the enemy has breaks actions.
+        let mut heavy = Body::default();
+        for arm in heavy.arms_mut() {
+            let mut skills = arm.stats().skills().to_vec();
+            for ability in skills.iter_mut().filter_map(Skill::ability_mut) {
+                ability.interrupting = true;
+            }
+            let stats = arm.stats().clone().with_skills(skills);
+            *arm = Arm::new(arm.name(), stats, arm.meta().clone()).with_slot(arm.slot());
+        }
+        let mut options = test_options();
+        options.interrupts = true;
+        let mut fight = ScriptedFight::with_options(Body::default(), heavy, &options);
+        let Game { player, enemy } = fight.game;
+        let walk = fight.skill(player, |skill| matches!(skill, Skill::WalkForward));
+        let jab = fight.skill(player, |skill| matches!(skill, Skill::BasicMelee(_)));
+        let enemy_jab = fight.skill(enemy, |skill| matches!(skill, Skill::BasicMelee(_)));
+        let walk_id = fight.stats(player).skills[walk].id();
+        let holder = |fight: &ScriptedFight, limb| {
+            let locks = fight.app.world.get::<LimbLocks>(player).unwrap();
+            locks.holder(limb).cloned()
+        };
+
+        fight.start(player, walk);
+        fight.app.update();
+        fight.start(player, jab);
+        fight.app.update();
+        fight.app.update();
+        assert!(holder(&fight, Limb::Arm(0)).is_some());
+        let skill = fight.stats(enemy).skills[enemy_jab].id();
+        fight.app.world.send_event(DamageEvent {
+            target: player,
+            source: DamageSource::Attack {
+                attacker: enemy,
+                skill,
+            },
+            amount: 1.0,
+            emp: false,
+            impact: None,
+        });
+        fight.app.update();
+        fight.app.update();
+
+        // The jab is broken and lets go of its arm, the legs keep walking.
+        let world = &fight.app.world;
+        assert!(world.get::<Recovery>(player).is_some());
+        assert!(world.get::<Animation>(player).is_none());
+        assert_eq!(world.get::<Walking>(player).unwrap().0.skill, walk_id);
+        assert_eq!(holder(&fight, Limb::Arm(0)), None);
+        assert_eq!(holder(&fight, Limb::Leg(0)), Some(walk_id));
+        let before = fight.x(player);
+        fight.settle();
+        fight.app.update();
+        assert!(fight.x(player) > before);
+        assert_eq!(holder(&fight, Limb::Leg(0)), None);
+    }
+
+    #[test]
+    fn despawned_enemy_mid_animation_does_not_panic() {
+        let mut app = build_app(Body::default(), Body::default(), &test_options(), 7);
+        let enemy = (0..MAX_FRAMES)
+            .find_map(|_| {
+                app.update();
+                let game = app.world.get_resource::<Game>()?;
+                let enemy = game.enemy;
+                app.world.get::<Animation>(enemy).map(|_| enemy)
+            })
+            .expect("the enemy never started an animation");
+        app.world.entity_mut(enemy).despawn_recursive();
+        for _ in 0..120 {
+            app.update();
+        }
+    }
+
+    #[test]
+    fn self_targeting_battle_is_rejected() {
+        let mut app = build_app(Body::default(), Body::default(), &test_options(), 7);
+        app.update();
+        let player = app.world.resource::<Game>().player;
+        app.world.resource_mut::<Game>().enemy = player;
+        // The first frame already started a battle, leave it before retrying.
+        for state in [AppState::GameOver, AppState::Battle] {
+            app.world
+                .resource_mut::<State<AppState>>()
+                .set(state)
+                .unwrap();
+            app.update();
+        }
+        for _ in 0..120 {
+            app.update();
+        }
+        assert_eq!(
+            app.world.resource::<State<AppState>>().current(),
+            &AppState::PreBattle
+        );
+    }
+
+    #[test]
+    fn overlapping_bodies_are_pushed_apart() {
+        let mut app = build_app(Body::default(), Body::default(), &test_options(), 7);
+        app.update();
+        let game = *app.world.resource::<Game>();
+        for _ in 0..10 {
+            app.world
+                .get_mut::<Transform>(game.enemy)
+                .unwrap()
+                .translation
+                .x = 1.0;
+            app.world
+                .get_mut::<Transform>(game.player)
+                .unwrap()
+                .translation
+                .x = 1.0;
+            app.update();
+            let x = |entity| app.world.get::<Transform>(entity).unwrap().translation.x;
+            let (player, enemy) = (x(game.player), x(game.enemy));
+            assert!(player.is_finite() && enemy.is_finite());
+            assert!(player < enemy, "{} should be left of {}", player, enemy);
+        }
+    }
+
+    // Both bodies open with an interrupting slam, so without care they can
+    // keep knocking each other out of every attack.
+    #[test]
+    fn interrupt_rules_never_softlock() {
+        for (interrupts, poise) in [(false, false), (false, true), (true, false), (true, true)] {
+            let mut options = test_options();
+            options.timer = None;
+            options.interrupts = interrupts;
+            options.poise = poise;
+            let result = simulate(foundry_golem(), foundry_golem(), &options, 7);
+            assert!(
+                !result.timed_out,
+                "interrupts: {}, poise: {} never finished",
+                interrupts, poise
+            );
+        }
+    }
+
+    // Recording only reads, the battle has to end the same way without it.
+    #[test]
+    fn timelines_leave_the_battle_alone() {
+        let mut options = test_options();
+        let plain = simulate(foundry_golem(), foundry_golem(), &options, 7);
+        options.timeline = true;
+        let recorded = simulate(foundry_golem(), foundry_golem(), &options, 7);
+        assert_eq!(recorded.outcome, plain.outcome);
+        // The first frame waits on the wall clock, the rest are fixed.
+        assert!((recorded.duration - plain.duration).abs() < FRAME / 2.0);
+        assert!(plain.timeline.is_none());
+
+        let timeline = recorded.timeline.unwrap();
+        let snapshots = timeline.snapshots();
+        assert_eq!(snapshots.len(), recorded.duration.ceil() as usize);
+        assert!(snapshots.iter().all(|snapshot| snapshot.bodies.len() == 2));
+        let last = snapshots.last().unwrap();
+        assert_eq!(timeline.snapshot_at(last.time + 0.5), Some(last));
+        assert_eq!(
+            timeline.snapshot_at(snapshots[1].time - 0.01),
+            snapshots.first()
+        );
+
+        let events = timeline.events();
+        assert!(events.windows(2).all(|pair| pair[0].time <= pair[1].time));
+        let hit = events
+            .iter()
+            .find(|event| event.text.contains("damage"))
+            .unwrap();
+        assert!(timeline.events_around(hit.time, 0.1).contains(hit));
+        assert!(!timeline.events_around(hit.time + 60.0, 0.1).contains(hit));
+    }
+
+    // The AI playing the player's body goes through UseSkill like the bar. A
+    // click takes the place of its pick, and the pick still waiting when the
+    // player takes over is kept.
+    #[test]
+    fn auto_battle_queues_like_a_click() {
+        let mut options = test_options();
+        options.timeline = true;
+        let mut fight = ScriptedFight::with_options(Body::default(), Body::default(), &options);
+        let player = fight.game.player;
+        let ai_pick = |fight: &mut ScriptedFight| {
+            for _ in 0..60 * 10 {
+                fight.app.update();
+                let world = &fight.app.world;
+                let use_skill = world.resource::<UseSkill>();
+                if world.get::<Autopilot>(player).unwrap().queued(use_skill) {
+                    return use_skill.as_ref().unwrap().clone();
+                }
+            }
+            panic!("the AI never picked anything");
+        };
+        let controller = fight
+            .app
+            .world
+            .resource::<AiRegistry>()
+            .create("aggressive")
+            .unwrap();
+        fight
+            .app
+            .world
+            .entity_mut(player)
+            .insert(controller)
+            .insert(Autopilot::new(7));
+        let picked = ai_pick(&mut fight);
+        assert!(fight.stats(player).skill(&picked).is_some());
+
+        let walk = Skill::WalkForward.id();
+        let clicked = fight
+            .stats(player)
+            .skills
+            .iter()
+            .map(Skill::id)
+            .find(|id| *id != picked && *id != walk)
+            .unwrap();
+        **fight.app.world.resource_mut::<UseSkill>() = Some(clicked.clone());
+        fight.app.update();
+        let world = &fight.app.world;
+        let playing = world
+            .get::<Animation>(player)
+            .is_some_and(|animation| animation.skill == clicked);
+        assert!(playing || world.resource::<UseSkill>().picked(&clicked));
+        assert!(!world
+            .get::<Autopilot>(player)
+            .unwrap()
+            .queued(world.resource::<UseSkill>()));
+        fight.settle();
+
+        let kept = ai_pick(&mut fight);
+        let mut body = fight.app.world.entity_mut(player);
+        body.remove::<AiController>();
+        body.remove::<Autopilot>();
+        fight.app.update();
+        let world = &fight.app.world;
+        let playing = world
+            .get::<Animation>(player)
+            .is_some_and(|animation| animation.skill == kept);
+        assert!(playing || world.resource::<UseSkill>().picked(&kept));
+        fight.settle();
+        for _ in 0..30 {
+            fight.app.update();
+        }
+        assert!(fight.app.world.resource::<UseSkill>().is_none());
+
+        let timeline = fight.app.world.resource::<Timeline>();
+        let switches = timeline.switches();
+        assert_eq!(switches.len(), 2);
+        assert_eq!(switches[0].ai.as_deref(), Some("aggressive"));
+        assert_eq!(switches[1].ai, None);
+        let during = (switches[0].time + switches[1].time) / 2.0;
+        assert_eq!(timeline.controller_at(during), Some("aggressive"));
+        assert_eq!(timeline.controller_at(switches[1].time + 0.1), None);
+        assert_eq!(timeline.controller_at(switches[0].time - 0.1), None);
+        assert!(timeline
+            .events()
+            .iter()
+            .any(|event| event.text == "player hands over to aggressive"));
+    }
+
+    // Both golems close in and trade slams, which is what they last decided
+    // on.
+    #[test]
+    fn battles_keep_each_ai_last_decision() {
+        let result = simulate(foundry_golem(), foundry_golem(), &test_options(), 7);
+        let sides = result
+            .decisions
+            .iter()
+            .map(|(side, _)| *side)
+            .collect::<Vec<_>>();
+        assert_eq!(sides, ["player", "enemy"]);
+        for (_, decision) in &result.decisions {
+            assert_eq!(decision.band, "melee");
+            assert!(!decision.considered.is_empty());
+            assert!(decision
+                .chosen
+                .is_none_or(|chosen| chosen < decision.considered.len()));
+            assert!(decision.to_string().starts_with("gap "));
+        }
+    }
+
+    // The ghost marker shows `walk_destination`, it has to be where the walk
+    // actually stops, both in the open and when the other body is in the way.
+    #[test]
+    fn walk_preview_matches_the_walk() {
+        for enemy_x in [4.0, 1.5] {
+            let mut app = build_app(Body::default(), Body::default(), &test_options(), 7);
+            app.update();
+            let game = *app.world.resource::<Game>();
+            for (entity, x) in [(game.player, -1.0), (game.enemy, enemy_x)] {
+                let mut entity = app.world.entity_mut(entity);
+                entity.remove::<AiController>();
+                entity.remove::<Animation>();
+                entity.remove::<Approach>();
+                entity.get_mut::<Transform>().unwrap().translation.x = x;
+            }
+            app.world.entity_mut(game.player).insert(Facing::Right);
+            let stats = app.world.get::<Stats>(game.player).unwrap();
+            let walk = stats
+                .skills
+                .iter()
+                .position(|skill| matches!(skill, Skill::WalkForward))
+                .unwrap();
+            let speed = stats.speed;
+            let [player, enemy] = [game.player, game.enemy].map(|entity| {
+                (
+                    *app.world.get::<Transform>(entity).unwrap(),
+                    app.world.get::<Stats>(entity).unwrap(),
+                )
+            });
+            let expected = walk_destination(
+                app.world.resource::<BalanceConfig>(),
+                &player.0,
+                Facing::Right,
+                player.1,
+                &player.1.skills[walk],
+                &enemy.0,
+                enemy.1,
+            )
+            .unwrap();
+            app.world
+                .entity_mut(game.player)
+                .insert(Animation::new(Skill::WalkForward.id()));
+            while app.world.get::<Animation>(game.player).is_some() {
+                app.update();
+            }
+            let x = app
+                .world
+                .get::<Transform>(game.player)
+                .unwrap()
+                .translation
+                .x;
+            // The last frame overshoots the end of the animation a little.
+            assert!(
+                (x - expected).abs() <= speed * FRAME as f32 * 2.0,
+                "walked to {} but the preview showed {}",
+                x,
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn chained_steps_keep_their_stride() {
+        let mut app = build_app(Body::default(), Body::default(), &test_options(), 7);
+        app.update();
+        let game = *app.world.resource::<Game>();
+        for (entity, x) in [(game.player, -12.0), (game.enemy, 40.0)] {
+            let mut entity = app.world.entity_mut(entity);
+            entity.remove::<AiController>();
+            entity.remove::<Animation>();
+            entity.remove::<Approach>();
+            entity.get_mut::<Transform>().unwrap().translation.x = x;
+        }
+        app.world.entity_mut(game.player).insert(Facing::Right);
+        let mut positions = Vec::new();
+        let mut phases = Vec::new();
+        for _ in 0..5 {
+            app.world
+                .entity_mut(game.player)
+                .insert(Animation::new(Skill::WalkForward.id()));
+            while app.world.get::<Animation>(game.player).is_some() {
+                app.update();
+            }
+            positions.push(
+                app.world
+                    .get::<Transform>(game.player)
+                    .unwrap()
+                    .translation
+                    .x,
+            );
+            phases.push(app.world.get::<GaitPhase>(game.player).unwrap().phase());
+        }
+        // Where the same five steps ended up when every step restarted the
+        // gait.
+        let before = [-6.91668, -1.8333428, 3.2499902, 8.333329, 13.416643];
+        for (x, expected) in positions.iter().zip(before) {
+            assert!((x - expected).abs() < 1e-4, "{:?}", positions);
+        }
+        // The swing carried on from one step to the next.
+        for (i, phase) in phases.iter().enumerate() {
+            let expected = phases[0] * (i + 1) as f32;
+            assert!((phase - expected).abs() < phases[0] * 0.05, "{:?}", phases);
+        }
+        for _ in 0..60 {
+            app.update();
+        }
+        assert_eq!(
+            app.world.get::<GaitPhase>(game.player).unwrap().phase(),
+            0.0
+        );
+    }
+
+    // The default body with both arms swapped for free slings.
+    fn ranged_body() -> Body {
+        let default = Body::default();
+        let shot = Skill::BasicRanged(Ability {
+            meta: 4.0,
+            time: 1.0,
+            cooldown: 0.0,
+            energy_cost: 0.0,
+            limb: Limb::Arm(0),
+            name: "Sling".to_string(),
+            interrupting: false,
+            ammo: None,
+            emp: false,
+        });
+        let stats = default.arms()[0].stats().clone().with_skills(vec![shot]);
+        let sling = Arm::new("Sling Arm", stats, ArmMeta::default());
+        Body::builder()
+            .torso(default.torso().clone())
+            .head(default.head().clone())
+            .arm(sling.clone())
+            .arm(sling)
+            .leg(default.legs()[0].clone())
+            .leg(default.legs()[1].clone())
+            .build()
+            .unwrap()
+    }
+
+    // The enemy shoots at a Defensive player from across the arena over and
+    // over, returns how many of the shots land.
+    fn shots_landed(reaction_time: f32, exchanges: u32) -> u32 {
+        let mut fight = ScriptedFight::new(Body::default(), ranged_body());
+        // Shots are hit tested along their whole path, so a fast clock only
+        // saves frames.
+        fight.app.world.insert_resource(TimeScale(4.0));
+        let Game { player, enemy } = fight.game;
+        fight.settle();
+        for entity in [player, enemy] {
+            fight.app.world.entity_mut(entity).insert(Invulnerable);
+        }
+        fight
+            .app
+            .world
+            .entity_mut(player)
+            .insert(AiRegistry::default().create("defensive").unwrap());
+        let shoot = fight.skill(enemy, |skill| matches!(skill, Skill::BasicRanged(_)));
+        let mut reader = ManualEventReader::<DamageEvent>::default();
+        let mut landed = 0;
+        for exchange in 0..exchanges {
+            for (entity, x, facing) in [(player, -4.0, Facing::Right), (enemy, 4.0, Facing::Left)] {
+                let mut entity = fight.app.world.entity_mut(entity);
+                entity.remove::<Animation>();
+                entity.get_mut::<Transform>().unwrap().translation.x = x;
+                entity.insert(facing);
+                entity.get_mut::<Stats>().unwrap().reaction_time = reaction_time;
+            }
+            fight.start(enemy, shoot);
+            let mut fired = false;
+            let mut frames = 0;
+            loop {
+                fight.app.update();
+                let events = fight.app.world.resource::<Events<DamageEvent>>();
+                landed += reader
+                    .iter(events)
+                    .filter(|event| event.source.attacker() == Some(enemy))
+                    .count() as u32;
+                let flying = fight
+                    .app
+                    .world
+                    .query::<&Projectile>()
+                    .iter(&fight.app.world)
+                    .count();
+                fired |= flying > 0;
+                if fired && flying == 0 {
+                    break;
+                }
+                frames += 1;
+                assert!(frames < 600, "exchange {} never ended", exchange);
+            }
+        }
+        landed
+    }
+
+    #[test]
+    fn quick_defensive_bodies_dodge_more_shots() {
+        let exchanges = 500;
+        let quick = shots_landed(0.05, exchanges);
+        let slow = shots_landed(1.0, exchanges);
+        // Seeing a shot after it has flown a second leaves no time to move.
+        assert!(slow > exchanges * 3 / 4, "slow body was hit {} times", slow);
+        assert!(quick < slow / 2, "quick: {}, slow: {}", quick, slow);
+    }
+
+    // Every swap lands mid-walk, with the legs locked and the part sprites
+    // moving. Nothing of the old body may outlive it.
+    #[test]
+    fn swapping_bodies_mid_animation_leaves_nothing_behind() {
+        let mut fight = ScriptedFight::new(Body::default(), Body::default());
+        fight.settle();
+        let player = fight.game.player;
+        let children = |fight: &ScriptedFight| {
+            fight
+                .app
+                .world
+                .get::<Children>(player)
+                .map_or(0, |children| children.len())
+        };
+        let baseline = fight.app.world.entities().len() - children(&fight) as u32;
+        let bodies = seeded_bodies(5, 2, 2)
+            .into_iter()
+            .zip(seeded_bodies(5, 3, 3))
+            .flat_map(|(small, big)| [small, big]);
+        for (i, body) in bodies.enumerate() {
+            let walk = fight.skill(player, |skill| matches!(skill, Skill::WalkForward));
+            fight.start(player, walk);
+            for _ in 0..5 {
+                fight.app.update();
+            }
+            assert!(fight.app.world.entity(player).contains::<Animation>());
+            let parts = part_sprites(&body).len() as u32;
+            let stats = compute_stats(&body);
+            fight.app.world.send_event(SwapBody {
+                entity: player,
+                body,
+            });
+            fight.app.update();
+            let swapped = fight.app.world.entity(player);
+            assert!(!fight.busy(player), "swap {} kept the walk", i);
+            assert_eq!(swapped.get::<LimbLocks>(), Some(&LimbLocks::default()));
+            assert_eq!(fight.stats(player).max_health, stats.max_health);
+            assert_eq!(children(&fight) as u32, parts);
+            assert_eq!(
+                fight.app.world.entities().len(),
+                baseline + parts,
+                "swap {} left entities behind",
+                i
+            );
+        }
+    }
+
+    // Replays only hold the seed, every drop has to land the same way again.
+    #[test]
+    fn pickups_drop_the_same_for_the_same_seed() {
+        let mut options = test_options();
+        options.pickups = true;
+        options.balance.pickup_chance = 1.0;
+        let drops = || {
+            let mut app = build_app(Body::default(), Body::default(), &options, 7);
+            for _ in 0..60 * 11 {
+                app.update();
+            }
+            app.world
+                .query::<(&Pickup, &Transform)>()
+                .iter(&app.world)
+                .map(|(pickup, transform)| (pickup.kind, transform.translation.x))
+                .collect::<Vec<_>>()
+        };
+        let first = drops();
+        assert_eq!(first.len(), 1);
+        assert_eq!(first, drops());
+    }
+
+    // The same battle with a pause of a random length dropped into the first
+    // animation the player plays. Nothing on the battle's clock may move
+    // while it's up.
+    #[test]
+    fn a_pause_mid_animation_leaves_the_battle_unchanged() {
+        let options = test_options();
+        let enemy = random_body(&mut StdRng::seed_from_u64(3));
+        let play = |pause: u32| {
+            let mut app = build_app(Body::default(), enemy.clone(), &options, 3);
+            let mut paused = pause == 0;
+            for _ in 0..MAX_FRAMES {
+                app.update();
+                if app.world.contains_resource::<BattleSummary>() {
+                    break;
+                }
+                let game = *app.world.resource::<Game>();
+                let animating = app
+                    .world
+                    .get::<Animation>(game.player)
+                    .is_some_and(|animation| animation.progress > 0.0);
+                if paused || !animating {
+                    continue;
+                }
+                paused = true;
+                let before = app.world.resource::<SimClock>().elapsed();
+                let mut state = app.world.resource_mut::<State<AppState>>();
+                state.push(AppState::Paused).unwrap();
+                for _ in 0..pause {
+                    app.update();
+                }
+                assert_eq!(app.world.resource::<SimClock>().elapsed(), before);
+                let mut state = app.world.resource_mut::<State<AppState>>();
+                state.pop().unwrap();
+            }
+            assert!(paused, "the player never played an animation");
+            let game = *app.world.resource::<Game>();
+            let winner = app.world.resource::<BattleSummary>().winner;
+            let health = [game.player, game.enemy]
+                .map(|entity| app.world.get::<Stats>(entity).unwrap().health);
+            (
+                winner == Some(game.player),
+                app.world.resource::<SimClock>().elapsed(),
+                health,
+            )
+        };
+        let pause = StdRng::seed_from_u64(11).gen_range(1..600);
+        assert_eq!(play(0), play(pause));
+    }
+
+    #[test]
+    fn a_blow_without_an_attacker_still_ends_the_battle_and_is_named() {
+        let options = test_options();
+        let enemy = random_body(&mut StdRng::seed_from_u64(3));
+        let finish = |sources: &[(bool, DamageSource)]| {
+            let mut app = build_app(Body::default(), enemy.clone(), &options, 3);
+            app.update();
+            let game = *app.world.resource::<Game>();
+            for (on_player, source) in sources {
+                let target = if *on_player { game.player } else { game.enemy };
+                app.world.get_mut::<Stats>(target).unwrap().health = 1.0;
+                app.world.send_event(DamageEvent {
+                    target,
+                    source: source.clone(),
+                    amount: 5.0,
+                    emp: false,
+                    impact: None,
+                });
+            }
+            app.update();
+            let summary = app.world.resource::<BattleSummary>();
+            let blows = summary
+                .finishing_blows
+                .iter()
+                .map(|(entity, source)| (*entity == game.player, source.clone()))
+                .collect::<Vec<_>>();
+            (summary.winner.map(|winner| winner == game.player), blows)
+        };
+
+        let burn = DamageSource::Status {
+            id: "periodic_health",
+            from: None,
+        };
+        let burned = [(false, burn)];
+        assert_eq!(finish(&burned), (Some(true), burned.to_vec()));
+        let both = [
+            (true, DamageSource::SuddenDeath),
+            (false, DamageSource::SuddenDeath),
+        ];
+        assert_eq!(finish(&both), (None, both.to_vec()));
+    }
+
+    #[test]
+    fn armor_wax_resists_hits_but_not_heals() {
+        let mut fight = ScriptedFight::new(Body::default(), Body::default());
+        let player = fight.game.player;
+        fight
+            .app
+            .insert_resource(Boosters::with_active(&[Booster::ArmorWax]));
+        let max_health = fight.stats(player).max_health;
+        fight.app.world.get_mut::<Stats>(player).unwrap().health = max_health - 20.0;
+        let mut hurt = |amount| {
+            fight.app.world.send_event(DamageEvent {
+                target: player,
+                source: DamageSource::Status {
+                    id: "periodic_health",
+                    from: None,
+                },
+                amount,
+                emp: false,
+                impact: None,
+            });
+            fight.app.update();
+            fight.stats(player).health
+        };
+        assert_eq!(hurt(-10.0), max_health - 10.0);
+        assert!((hurt(10.0) - (max_health - 19.0)).abs() < 1e-4);
+    }
+
+    #[test]
+    fn a_training_knockout_counts_for_nothing() {
+        let mut fight = ScriptedFight::new(Body::default(), Body::default());
+        let Game { player, enemy } = fight.game;
+        fight
+            .app
+            .insert_resource(Profile::default())
+            .insert_resource(Theme::default())
+            .init_resource::<ShowHitboxes>()
+            .add_plugin(TrainingPlugin)
+            .add_plugin(TelemetryPlugin);
+        let mut queue = CommandQueue::default();
+        let mut ruleset = std::mem::take(&mut *fight.app.world.resource_mut::<Ruleset>());
+        training::begin(
+            &mut Commands::new(&mut queue, &fight.app.world),
+            &mut ruleset,
+        );
+        queue.apply(&mut fight.app.world);
+        fight.app.insert_resource(ruleset);
+        fight
+            .app
+            .world
+            .resource_mut::<Training>()
+            .set_infinite_health(false);
+
+        let walk = fight.skill(player, |skill| matches!(skill, Skill::WalkForward));
+        let mut walks = 0;
+        while !fight.in_reach(player) {
+            fight.start(player, walk);
+            fight.settle();
+            walks += 1;
+            assert!(walks < 20, "never got in reach");
+        }
+        fight.app.world.get_mut::<Stats>(enemy).unwrap().health = 1.0;
+        let jab = fight.skill(player, |skill| matches!(skill, Skill::BasicMelee(_)));
+        fight.start(player, jab);
+        fight.settle();
+
+        // The dummy got back up and the battle goes on.
+        assert_eq!(fight.stats(enemy).health, fight.stats(enemy).max_health);
+        assert_eq!(
+            fight.app.world.resource::<State<AppState>>().current(),
+            &AppState::Battle
+        );
+        assert!(fight.app.world.get_resource::<BattleSummary>().is_none());
+        let profile = fight.app.world.resource::<Profile>();
+        assert_eq!((profile.wins, profile.losses, profile.draws), (0, 0, 0));
+        assert!(profile.skill_stats.is_empty());
+    }
+}
@@ -0,0 +1,509 @@
+use std::{
+    num::NonZeroU32,
+    sync::{
+        atomic::{AtomicU8, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use bevy::{
+    core_pipeline::clear_color::ClearColorConfig,
+    prelude::*,
+    render::{
+        camera::{RenderTarget, ScalingMode},
+        main_graph::node::CAMERA_DRIVER,
+        render_asset::RenderAssets,
+        render_graph::{self, NodeRunError, RenderGraph, RenderGraphContext},
+        render_resource::{
+            Buffer, BufferDescriptor, BufferUsages, Extent3d, ImageCopyBuffer, ImageDataLayout,
+            MapMode, TextureDescriptor, TextureDimension, TextureFormat, TextureUsages,
+        },
+        renderer::{RenderContext, RenderDevice},
+        texture::BevyDefault,
+        view::RenderLayers,
+        Extract, RenderApp, RenderStage,
+    },
+};
+
+use crate::{
+    body::{still_sprites, Body, PaletteMode},
+    error::{report, ErrorToast, GameError},
+    layer::RenderLayer,
+    locale::t,
+    theme::Theme,
+    ui::{Fonts, Toast},
+};
+
+// Pictures come out this big whatever the window is.
+pub const SNAPSHOT_SIZE: u32 = 1024;
+// Only the snapshot camera draws this layer, and it draws nothing else, so
+// the HUD and tooltips stay out of the picture.
+const SNAPSHOT_LAYER: u8 = 7;
+// Out of the way of the arena, the main camera doesn't draw the layer anyway.
+const SNAPSHOT_ORIGIN: Vec2 = Vec2::new(0.0, -400.0);
+const NAME_SIZE: f32 = 56.0;
+// World units between the top of the body and its name.
+const NAME_GAP: f32 = 0.35;
+const MARGIN: f32 = 0.15;
+// Frames the scene is up before it's read back, the name needs one to be laid
+// out and another to be drawn.
+const SETTLE_FRAMES: u8 = 3;
+
+// Saves a picture of the body on the entity, see `Snapshot`.
+pub struct ExportBody(pub Entity);
+
+// On the camera drawing a picture, until it's been read back and saved.
+#[derive(Component)]
+pub struct Snapshot {
+    name: String,
+    image: Handle<Image>,
+    // The body and its name, drawn only by this camera.
+    scene: Entity,
+    frames: u8,
+}
+
+// Pictures read back from the GPU, by their camera. None when reading failed.
+#[derive(Clone, Default)]
+struct Captured(Arc<Mutex<Vec<(Entity, Option<Vec<u8>>)>>>);
+
+fn target_image(size: u32) -> Image {
+    let size = Extent3d {
+        width: size,
+        height: size,
+        ..default()
+    };
+    let mut image = Image {
+        texture_descriptor: TextureDescriptor {
+            label: Some("snapshot"),
+            size,
+            dimension: TextureDimension::D2,
+            format: TextureFormat::bevy_default(),
+            mip_level_count: 1,
+            sample_count: 1,
+            usage: TextureUsages::TEXTURE_BINDING
+                | TextureUsages::COPY_SRC
+                | TextureUsages::COPY_DST
+                | TextureUsages::RENDER_ATTACHMENT,
+        },
+        ..default()
+    };
+    image.resize(size);
+    image
+}
+
+// Where the camera looks and the height it shows, the body in idle with its
+// name over it and some room around both.
+fn snapshot_view(body: &Body) -> (Vec2, f32) {
+    let (low, high) = body.bounds();
+    let top = high.y + NAME_GAP + 0.3;
+    let span = (high.x - low.x).max(top - low.y) * (1.0 + 2.0 * MARGIN);
+    (Vec2::new((low.x + high.x) / 2.0, (low.y + top) / 2.0), span)
+}
+
+fn start_snapshot_system(
+    mut commands: Commands,
+    mut exports: EventReader<ExportBody>,
+    mut images: ResMut<Assets<Image>>,
+    fonts: Res<Fonts>,
+    theme: Res<Theme>,
+    palette: Res<PaletteMode>,
+    bodies: Query<&Body>,
+    pending: Query<(), With<Snapshot>>,
+) {
+    // One at a time, a second click while saving does nothing.
+    let Some(ExportBody(entity)) = exports.iter().last() else {
+        return;
+    };
+    if !pending.is_empty() {
+        return;
+    }
+    let Ok(body) = bodies.get(*entity) else {
+        return;
+    };
+    let layer = RenderLayers::layer(SNAPSHOT_LAYER);
+    let (center, span) = snapshot_view(body);
+    let (_, high) = body.bounds();
+    let scene = commands
+        .spawn_bundle(SpatialBundle::from_transform(Transform::from_translation(
+            SNAPSHOT_ORIGIN.extend(RenderLayer::NearBody.z()),
+        )))
+        .with_children(|parent| {
+            for sprite in still_sprites(body, *palette) {
+                parent.spawn_bundle(sprite).insert(layer);
+            }
+            // Text is laid out in pixels, scaled back down to world units.
+            parent
+                .spawn_bundle(Text2dBundle {
+                    text: Text::from_section(
+                        body.name(),
+                        TextStyle {
+                            font: fonts.bold(),
+                            font_size: NAME_SIZE,
+                            color: theme.text,
+                        },
+                    )
+                    .with_alignment(TextAlignment::CENTER),
+                    transform: Transform::from_xyz(center.x, high.y + NAME_GAP, 0.0)
+                        .with_scale(Vec3::splat(span / SNAPSHOT_SIZE as f32)),
+                    ..default()
+                })
+                .insert(layer);
+        })
+        .id();
+    let image = images.add(target_image(SNAPSHOT_SIZE));
+    let mut camera = Camera2dBundle {
+        camera: Camera {
+            target: RenderTarget::Image(image.clone()),
+            // Before the main camera, so it's never drawn over the window.
+            priority: -1,
+            ..default()
+        },
+        camera_2d: Camera2d {
+            clear_color: ClearColorConfig::Custom(Color::NONE),
+        },
+        ..default()
+    };
+    camera.projection.scaling_mode = ScalingMode::FixedVertical(span);
+    let look_at = SNAPSHOT_ORIGIN + center;
+    camera.transform.translation.x = look_at.x;
+    camera.transform.translation.y = look_at.y;
+    commands
+        .spawn_bundle(camera)
+        .insert_bundle((layer, UiCameraConfig { show_ui: false }))
+        .insert(Snapshot {
+            name: body.name().to_string(),
+            image,
+            scene,
+            frames: SETTLE_FRAMES,
+        });
+}
+
+fn settle_system(mut snapshots: Query<&mut Snapshot>) {
+    for mut snapshot in &mut snapshots {
+        snapshot.frames = snapshot.frames.saturating_sub(1);
+    }
+}
+
+// `name` as it can go in a file name, with when it was taken if that's known.
+fn file_name(name: &str, unix_seconds: Option<u64>) -> String {
+    let name = name
+        .chars()
+        .map(|c| match c {
+            c if c.is_alphanumeric() || c == '-' => c,
+            _ => '_',
+        })
+        .collect::<String>();
+    let name = if name.is_empty() { "body" } else { &name };
+    let Some(unix_seconds) = unix_seconds else {
+        return format!("{}.png", name);
+    };
+    let seconds = unix_seconds % 86_400;
+    format!(
+        "{}_{}_{:02}-{:02}-{:02}.png",
+        name,
+        crate::daily::date_label((unix_seconds / 86_400) as u32),
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
+
+fn encode_png(pixels: &[u8]) -> Result<Vec<u8>, String> {
+    use image::ImageEncoder;
+
+    let mut png = Vec::new();
+    image::codecs::png::PngEncoder::new(&mut png)
+        .write_image(
+            pixels,
+            SNAPSHOT_SIZE,
+            SNAPSHOT_SIZE,
+            image::ColorType::Rgba8,
+        )
+        .map_err(|err| err.to_string())?;
+    Ok(png)
+}
+
+// Writes it to the screenshots directory, the toast says where.
+#[cfg(not(target_arch = "wasm32"))]
+fn save(name: &str, png: &[u8]) -> Result<String, GameError> {
+    let unix_seconds = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .ok()
+        .map(|since| since.as_secs());
+    let key = format!("screenshots/{}", file_name(name, unix_seconds));
+    crate::storage::write_bytes(&key, png)?;
+    Ok(t!("snapshot.saved", path = crate::storage::describe(&key)))
+}
+
+// There's no disk to write to in the browser, the file is downloaded instead.
+// The clock isn't reachable from there either, see `daily::today`.
+#[cfg(target_arch = "wasm32")]
+fn save(name: &str, png: &[u8]) -> Result<String, GameError> {
+    use wasm_bindgen::JsCast;
+
+    let file = file_name(name, None);
+    let fail = |err: wasm_bindgen::JsValue| GameError::io(&file, format!("{:?}", err));
+    let parts = js_sys::Array::of1(&js_sys::Uint8Array::from(png));
+    let mut options = web_sys::BlobPropertyBag::new();
+    options.type_("image/png");
+    let blob =
+        web_sys::Blob::new_with_u8_array_sequence_and_options(&parts, &options).map_err(fail)?;
+    let url = web_sys::Url::create_object_url_with_blob(&blob).map_err(fail)?;
+    let document = web_sys::window()
+        .and_then(|window| window.document())
+        .ok_or_else(|| GameError::io(&file, "no document"))?;
+    let link = document
+        .create_element("a")
+        .map_err(fail)?
+        .dyn_into::<web_sys::HtmlAnchorElement>()
+        .map_err(|element| fail(element.into()))?;
+    link.set_href(&url);
+    link.set_download(&file);
+    link.click();
+    web_sys::Url::revoke_object_url(&url).map_err(fail)?;
+    Ok(t!("snapshot.downloaded", file = file))
+}
+
+fn save_snapshot_system(
+    mut commands: Commands,
+    captured: Res<Captured>,
+    mut images: ResMut<Assets<Image>>,
+    mut toasts: EventWriter<Toast>,
+    mut errors: EventWriter<ErrorToast>,
+    snapshots: Query<&Snapshot>,
+) {
+    let done = std::mem::take(&mut *captured.0.lock().unwrap());
+    for (entity, pixels) in done {
+        let Ok(snapshot) = snapshots.get(entity) else {
+            continue;
+        };
+        commands.entity(entity).despawn_recursive();
+        commands.entity(snapshot.scene).despawn_recursive();
+        images.remove(&snapshot.image);
+        let saved = pixels
+            .ok_or_else(|| "the picture couldn't be read back".to_string())
+            .and_then(|pixels| encode_png(&pixels))
+            .map_err(|reason| GameError::io(&file_name(&snapshot.name, None), reason))
+            .and_then(|png| save(&snapshot.name, &png));
+        if let Some(message) = report(&mut errors, saved) {
+            toasts.send(Toast(message));
+        }
+    }
+}
+
+// Reading a picture back takes a few frames on the render side: a buffer is
+// made for it, the rendered texture is copied in, and once the GPU is done the
+// buffer is mapped and read.
+enum Step {
+    Waiting,
+    Copy(Buffer),
+    Mapping(Buffer, Arc<AtomicU8>),
+}
+
+const MAP_PENDING: u8 = 0;
+const MAP_DONE: u8 = 1;
+const MAP_FAILED: u8 = 2;
+
+struct Capture {
+    camera: Entity,
+    image: Handle<Image>,
+    step: Step,
+}
+
+#[derive(Default)]
+struct Captures {
+    pending: Vec<Capture>,
+    // Cameras already being read, so a snapshot is only read once.
+    seen: Vec<Entity>,
+}
+
+fn padded_row(width: u32) -> usize {
+    RenderDevice::align_copy_bytes_per_row(width as usize * 4)
+}
+
+fn extract_snapshots_system(
+    mut captures: ResMut<Captures>,
+    snapshots: Extract<Query<(Entity, &Snapshot)>>,
+) {
+    captures.seen.retain(|camera| snapshots.contains(*camera));
+    for (camera, snapshot) in snapshots.iter() {
+        if snapshot.frames == 0 && !captures.seen.contains(&camera) {
+            captures.seen.push(camera);
+            captures.pending.push(Capture {
+                camera,
+                image: snapshot.image.clone(),
+                step: Step::Waiting,
+            });
+        }
+    }
+}
+
+fn prepare_captures_system(
+    mut captures: ResMut<Captures>,
+    device: Res<RenderDevice>,
+    images: Res<RenderAssets<Image>>,
+) {
+    for capture in &mut captures.pending {
+        if matches!(capture.step, Step::Waiting) && images.contains_key(&capture.image) {
+            capture.step = Step::Copy(device.create_buffer(&BufferDescriptor {
+                label: Some("snapshot"),
+                size: (padded_row(SNAPSHOT_SIZE) * SNAPSHOT_SIZE as usize) as u64,
+                usage: BufferUsages::MAP_READ | BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            }));
+        }
+    }
+}
+
+// After the cameras have drawn, copies the pictures into their buffers.
+struct CopyNode;
+
+impl render_graph::Node for CopyNode {
+    fn run(
+        &self,
+        _graph: &mut RenderGraphContext,
+        render_context: &mut RenderContext,
+        world: &World,
+    ) -> Result<(), NodeRunError> {
+        let images = world.resource::<RenderAssets<Image>>();
+        for capture in &world.resource::<Captures>().pending {
+            let (Step::Copy(buffer), Some(image)) = (&capture.step, images.get(&capture.image))
+            else {
+                continue;
+            };
+            render_context.command_encoder.copy_texture_to_buffer(
+                image.texture.as_image_copy(),
+                ImageCopyBuffer {
+                    buffer,
+                    layout: ImageDataLayout {
+                        offset: 0,
+                        bytes_per_row: NonZeroU32::new(padded_row(SNAPSHOT_SIZE) as u32),
+                        rows_per_image: None,
+                    },
+                },
+                Extent3d {
+                    width: SNAPSHOT_SIZE,
+                    height: SNAPSHOT_SIZE,
+                    depth_or_array_layers: 1,
+                },
+            );
+        }
+        Ok(())
+    }
+}
+
+// Rows of `width` pixels without the padding the copy needed, in RGBA
+// whatever order the texture had them in.
+fn unpad(data: &[u8], width: u32, padded_row: usize, bgra: bool) -> Vec<u8> {
+    let row = width as usize * 4;
+    let mut pixels = Vec::with_capacity(data.len() / padded_row * row);
+    for padded in data.chunks(padded_row) {
+        pixels.extend_from_slice(&padded[..row]);
+    }
+    if bgra {
+        for pixel in pixels.chunks_mut(4) {
+            pixel.swap(0, 2);
+        }
+    }
+    pixels
+}
+
+// The copy was submitted by now, the buffer is mapped and read once the GPU
+// has finished with it, which the next submit finds out.
+fn read_captures_system(
+    mut captures: ResMut<Captures>,
+    captured: Res<Captured>,
+    device: Res<RenderDevice>,
+) {
+    let bgra = TextureFormat::bevy_default() == TextureFormat::Bgra8UnormSrgb;
+    captures.pending.retain_mut(|capture| {
+        match std::mem::replace(&mut capture.step, Step::Waiting) {
+            Step::Waiting => true,
+            Step::Copy(buffer) => {
+                let state = Arc::new(AtomicU8::new(MAP_PENDING));
+                let mapped = state.clone();
+                device.map_buffer(&buffer.slice(..), MapMode::Read, move |result| {
+                    let done = if result.is_ok() { MAP_DONE } else { MAP_FAILED };
+                    mapped.store(done, Ordering::Release);
+                });
+                capture.step = Step::Mapping(buffer, state);
+                true
+            }
+            Step::Mapping(buffer, state) => {
+                let pixels = match state.load(Ordering::Acquire) {
+                    MAP_PENDING => {
+                        capture.step = Step::Mapping(buffer, state);
+                        return true;
+                    }
+                    MAP_DONE => {
+                        let data = buffer.slice(..).get_mapped_range();
+                        let pixels = unpad(&data, SNAPSHOT_SIZE, padded_row(SNAPSHOT_SIZE), bgra);
+                        drop(data);
+                        buffer.unmap();
+                        Some(pixels)
+                    }
+                    _ => None,
+                };
+                captured.0.lock().unwrap().push((capture.camera, pixels));
+                false
+            }
+        }
+    });
+}
+
+// Only the windowed game has the buttons for it.
+pub struct SnapshotPlugin;
+
+impl Plugin for SnapshotPlugin {
+    fn build(&self, app: &mut App) {
+        let captured = Captured::default();
+        app.add_event::<ExportBody>()
+            .insert_resource(captured.clone())
+            .add_system(start_snapshot_system)
+            .add_system(settle_system.before(start_snapshot_system))
+            .add_system(save_snapshot_system);
+        let Ok(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+        render_app
+            .insert_resource(captured)
+            .init_resource::<Captures>()
+            .add_system_to_stage(RenderStage::Extract, extract_snapshots_system)
+            .add_system_to_stage(RenderStage::Prepare, prepare_captures_system)
+            .add_system_to_stage(RenderStage::Cleanup, read_captures_system);
+        let mut graph = render_app.world.resource_mut::<RenderGraph>();
+        graph.add_node("snapshot_copy", CopyNode);
+        graph
+            .add_node_edge(CAMERA_DRIVER, "snapshot_copy")
+            .expect("the camera driver is always in the main graph");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pictures_come_back_unpadded_in_rgba_and_named_for_disk() {
+        // Two rows of two pixels, padded out to eight bytes.
+        let data = [
+            1, 2, 3, 4, 5, 6, 7, 8, 0, 0, 9, 10, 11, 12, 13, 14, 15, 16, 0, 0,
+        ];
+        assert_eq!(
+            unpad(&data, 2, 10, false),
+            [1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16]
+        );
+        assert_eq!(unpad(&data[..10], 2, 10, true), [3, 2, 1, 4, 7, 6, 5, 8]);
+
+        // 2026-10-15 13:45:01 UTC.
+        let when = 20_741 * 86_400 + 13 * 3600 + 45 * 60 + 1;
+        assert_eq!(
+            file_name("Rusty Bolt/2", Some(when)),
+            "Rusty_Bolt_2_2026-10-15_13-45-01.png"
+        );
+        assert_eq!(file_name("", None), "body.png");
+
+        let png = encode_png(&vec![255; (SNAPSHOT_SIZE * SNAPSHOT_SIZE * 4) as usize]).unwrap();
+        assert_eq!(&png[1..4], b"PNG");
+    }
+}
@@ -0,0 +1,191 @@
+use std::sync::Arc;
+
+use bevy::{
+    asset::{AssetLoader, BoxedFuture, LoadContext, LoadedAsset},
+    audio::{play_queued_audio_system, AudioOutput, Decodable},
+    prelude::*,
+    reflect::TypeUuid,
+};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use rodio::buffer::SamplesBuffer;
+use serde::Deserialize;
+
+const SAMPLE_RATE: u32 = 44_100;
+// Longest a sound file may ask for, they're all knocks and clangs.
+const MAX_LENGTH: f32 = 2.0;
+
+// A sine that rings out on its own, `decay` is the seconds it takes to fall
+// to about a third.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+struct Tone {
+    frequency: f32,
+    amplitude: f32,
+    decay: f32,
+}
+
+// What a `.sound.ron` file holds. The game ships no recordings, every sound
+// is a few ringing tones over a burst of noise.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(default)]
+struct SoundDef {
+    length: f32,
+    volume: f32,
+    tones: Vec<Tone>,
+    noise: f32,
+    noise_decay: f32,
+    // Share the tones drop in pitch by the end, a thud sags where a bell
+    // holds.
+    pitch_drop: f32,
+}
+
+impl Default for SoundDef {
+    fn default() -> Self {
+        Self {
+            length: 0.3,
+            volume: 0.5,
+            tones: Vec::new(),
+            noise: 0.0,
+            noise_decay: 0.05,
+            pitch_drop: 0.0,
+        }
+    }
+}
+
+impl SoundDef {
+    fn validate(&self) -> Result<(), String> {
+        if !(self.length > 0.0 && self.length <= MAX_LENGTH) {
+            return Err(format!("length must be in (0, {}]", MAX_LENGTH));
+        }
+        if !(0.0..=1.0).contains(&self.pitch_drop) {
+            return Err("pitch_drop must be in [0, 1]".to_string());
+        }
+        let nyquist = SAMPLE_RATE as f32 / 2.0;
+        if let Some(tone) = self
+            .tones
+            .iter()
+            .find(|tone| !(tone.frequency > 0.0 && tone.frequency < nyquist && tone.decay > 0.0))
+        {
+            return Err(format!("tone at {} Hz can't be played", tone.frequency));
+        }
+        if self.noise_decay <= 0.0 {
+            return Err("noise_decay must be positive".to_string());
+        }
+        Ok(())
+    }
+
+    // Mono samples, kept within -1..=1 however loud the parts add up to.
+    fn render(&self) -> Vec<f32> {
+        // The same file always sounds the same.
+        let mut rng = StdRng::seed_from_u64(0);
+        let count = (self.length * SAMPLE_RATE as f32) as usize;
+        (0..count)
+            .map(|i| {
+                let t = i as f32 / SAMPLE_RATE as f32;
+                // Integrated over the drop so the pitch slides instead of
+                // jumping.
+                let stretch = t - self.pitch_drop * t * t / (2.0 * self.length);
+                let tones = self
+                    .tones
+                    .iter()
+                    .map(|tone| {
+                        let phase = std::f32::consts::TAU * tone.frequency * stretch;
+                        tone.amplitude * (-t / tone.decay).exp() * phase.sin()
+                    })
+                    .sum::<f32>();
+                let noise = self.noise * (-t / self.noise_decay).exp() * rng.gen_range(-1.0..=1.0);
+                // Faded over the last few milliseconds so nothing clicks.
+                let tail = ((self.length - t) * 200.0).min(1.0);
+                ((tones + noise) * self.volume * tail).clamp(-1.0, 1.0)
+            })
+            .collect()
+    }
+}
+
+#[derive(TypeUuid)]
+#[uuid = "b6d2a0f4-1c3e-4f7a-9e58-7a4c2d91e0b3"]
+pub struct SynthSound {
+    samples: Arc<[f32]>,
+}
+
+impl Decodable for SynthSound {
+    type Decoder = SamplesBuffer<f32>;
+    type DecoderItem = f32;
+
+    fn decoder(&self) -> Self::Decoder {
+        SamplesBuffer::new(1, SAMPLE_RATE, self.samples.to_vec())
+    }
+}
+
+#[derive(Default)]
+struct SynthLoader;
+
+impl AssetLoader for SynthLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let def = ron::de::from_bytes::<SoundDef>(bytes)?;
+            def.validate().map_err(bevy::asset::Error::msg)?;
+            let samples = def.render().into();
+            load_context.set_default_asset(LoadedAsset::new(SynthSound { samples }));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["sound.ron"]
+    }
+}
+
+// Plays `SynthSound`s through `Audio<SynthSound>` the way bevy's own plugin
+// plays files. Only the windowed game has it, the sim stays silent.
+pub struct SoundPlugin;
+
+impl Plugin for SoundPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_asset::<SynthSound>()
+            .init_asset_loader::<SynthLoader>()
+            .init_non_send_resource::<AudioOutput<SynthSound>>()
+            .init_resource::<Audio<SynthSound>>()
+            .add_system_to_stage(
+                CoreStage::PostUpdate,
+                play_queued_audio_system::<SynthSound>,
+            );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_sound_file_renders_audibly() {
+        let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/assets/sounds");
+        let mut files = 0;
+        for entry in std::fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            let contents = std::fs::read_to_string(&path).unwrap();
+            let def = ron::from_str::<SoundDef>(&contents).unwrap();
+            assert_eq!(def.validate(), Ok(()), "{}", path.display());
+            let samples = def.render();
+            assert_eq!(samples.len(), (def.length * SAMPLE_RATE as f32) as usize);
+            assert!(samples.iter().all(|sample| sample.abs() <= 1.0));
+            let peak = samples
+                .iter()
+                .fold(0.0f32, |peak, sample| peak.max(sample.abs()));
+            assert!(peak > 0.05, "{} is silent", path.display());
+            // Nothing clicks when it stops.
+            assert!(samples.last().unwrap().abs() < 0.01);
+            files += 1;
+        }
+        assert!(files > 0);
+        assert!(SoundDef {
+            length: 5.0,
+            ..default()
+        }
+        .validate()
+        .is_err());
+    }
+}
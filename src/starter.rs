@@ -0,0 +1,574 @@
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::{
+    body::{
+        grid_position, random_body, still_sprites, Body, PaletteMode, Skill, Stats, BASE_ENERGY,
+    },
+    bounds::bounded_stats,
+    camera::{self, dynamic_camera, MainCamera},
+    daily::{DailyRun, Stream},
+    error::{report, ErrorToast},
+    layer::RenderLayer,
+    locale::t,
+    profile::Profile,
+    swap::SwapBody,
+    theme::Theme,
+    ui::{Fonts, Scaled},
+    AppState, Game, GameRng,
+};
+
+pub const LINEUP_SIZE: usize = 6;
+// Rerolls of the whole lineup a pick gets.
+pub const REROLLS: u32 = 2;
+const LINEUP_SPACING: Vec2 = Vec2::new(3.0, 0.0);
+// Far above the arena, so the camera shows the lineup and nothing else.
+const LINEUP_ORIGIN: Vec2 = Vec2::new(0.0, 200.0);
+// Share of the view at the bottom the stat cards cover, the bodies are framed
+// above them.
+const CARD_ROOM: f32 = 0.3;
+
+// The bodies on offer. They're only data until one is taken, what's shown of
+// them are sprites nothing in the battle looks at.
+pub struct Lineup {
+    bodies: Vec<Body>,
+    selected: usize,
+    rerolls_left: u32,
+    // Lineups rolled so far, a daily challenge draws each from its own
+    // generator.
+    rolls: u32,
+}
+
+impl Lineup {
+    // From the run's generator, or from the day's during a daily challenge so
+    // everyone is offered the same bodies.
+    fn roll(rng: &mut GameRng, daily: Option<&DailyRun>, rolls: u32) -> Vec<Body> {
+        match daily {
+            Some(run) => generate(&mut run.rng(Stream::Body, rolls)),
+            None => generate(&mut **rng),
+        }
+    }
+
+    pub fn selected(&self) -> &Body {
+        &self.bodies[self.selected]
+    }
+
+    fn select(&mut self, index: usize) {
+        self.selected = index.min(self.bodies.len() - 1);
+    }
+}
+
+pub fn generate(rng: &mut impl Rng) -> Vec<Body> {
+    (0..LINEUP_SIZE).map(|_| random_body(rng)).collect()
+}
+
+// The one thing that stands out about a body, first match wins.
+fn quirk(body: &Body, stats: &Stats) -> String {
+    let abilities = || stats.skills.iter().filter_map(Skill::ability);
+    let key = if stats.max_overshield > 0.0 {
+        "starter.quirk.overshield"
+    } else if abilities().any(|ability| ability.emp) {
+        "starter.quirk.emp"
+    } else if abilities().any(|ability| ability.interrupting) {
+        "starter.quirk.heavy_hitter"
+    } else if abilities().any(|ability| ability.ammo.is_some()) {
+        "starter.quirk.ranged"
+    } else if body.arms().len() < 2 {
+        "starter.quirk.one_arm"
+    } else if !stats.skills.contains(&Skill::WalkBackward) {
+        "starter.quirk.no_backstep"
+    } else if stats.max_energy < BASE_ENERGY {
+        "starter.quirk.power_hungry"
+    } else {
+        "starter.quirk.none"
+    };
+    t!(key)
+}
+
+fn card_sections(body: &Body, fonts: &Fonts, theme: &Theme) -> Vec<TextSection> {
+    let stats = bounded_stats(body);
+    let class = stats.class();
+    let style = |color| TextStyle {
+        font: fonts.bold(),
+        font_size: 18.0,
+        color,
+    };
+    let skills = stats
+        .skills
+        .iter()
+        .filter(|s| s.ability().is_some())
+        .count();
+    vec![
+        TextSection::new(body.name(), style(theme.button_text)),
+        TextSection::new(format!("\n{}", class.name()), style(class.color())),
+        TextSection::new(
+            format!(
+                "\n{}\n{}",
+                t!("starter.card", speed = stats.speed, skills = skills),
+                quirk(body, &stats)
+            ),
+            style(theme.button_text),
+        ),
+    ]
+}
+
+// One per body, parent of its sprites.
+#[derive(Component)]
+struct LineupRoot;
+
+// Shown under the selected body.
+#[derive(Component)]
+struct LineupPlate(usize);
+
+#[derive(Component)]
+struct LineupUi;
+
+#[derive(Component)]
+struct LineupCard(usize);
+
+#[derive(Component)]
+struct LineupCardText(usize);
+
+#[derive(Component, Clone, Copy, PartialEq, Eq)]
+enum LineupButton {
+    Reroll,
+    Confirm,
+    Back,
+}
+
+#[derive(Component)]
+struct RerollLabel;
+
+fn lineup_position(index: usize) -> Vec3 {
+    (LINEUP_ORIGIN + grid_position(index, LINEUP_SIZE, LINEUP_SPACING))
+        .extend(RenderLayer::Overlay.z())
+}
+
+fn spawn_lineup(commands: &mut Commands, lineup: &Lineup, palette: PaletteMode, plate: Color) {
+    for (index, body) in lineup.bodies.iter().enumerate() {
+        commands
+            .spawn_bundle(SpatialBundle::from_transform(Transform::from_translation(
+                lineup_position(index),
+            )))
+            .insert(LineupRoot)
+            .with_children(|parent| {
+                parent
+                    .spawn_bundle(SpriteBundle {
+                        sprite: Sprite {
+                            color: plate,
+                            ..default()
+                        },
+                        transform: Transform::from_xyz(0.0, -0.05, -0.01)
+                            .with_scale(Vec3::new(2.0, 0.1, 1.0)),
+                        visibility: Visibility {
+                            is_visible: index == lineup.selected,
+                        },
+                        ..default()
+                    })
+                    .insert(LineupPlate(index));
+                for sprite in still_sprites(body, palette) {
+                    parent.spawn_bundle(sprite);
+                }
+            });
+    }
+}
+
+fn reroll_label(lineup: &Lineup) -> String {
+    t!("starter.reroll", left = lineup.rerolls_left)
+}
+
+fn spawn_lineup_ui(commands: &mut Commands, fonts: &Fonts, theme: &Theme, lineup: &Lineup) {
+    let text = |size, color| TextStyle {
+        font: fonts.bold(),
+        font_size: size,
+        color,
+    };
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                position_type: PositionType::Absolute,
+                flex_direction: FlexDirection::ColumnReverse,
+                justify_content: JustifyContent::SpaceBetween,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            color: Color::NONE.into(),
+            ..default()
+        })
+        .insert(LineupUi)
+        .with_children(|parent| {
+            parent
+                .spawn_bundle(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::ColumnReverse,
+                        align_items: AlignItems::Center,
+                        margin: UiRect::all(Val::Px(20.0)),
+                        ..default()
+                    },
+                    color: Color::NONE.into(),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn_bundle(TextBundle::from_section(
+                        t!("starter.title"),
+                        text(48.0, Color::WHITE),
+                    ));
+                    parent.spawn_bundle(TextBundle::from_section(
+                        t!("starter.hint"),
+                        text(20.0, Color::WHITE),
+                    ));
+                });
+            parent
+                .spawn_bundle(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::ColumnReverse,
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    color: Color::NONE.into(),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent
+                        .spawn_bundle(NodeBundle {
+                            color: Color::NONE.into(),
+                            ..default()
+                        })
+                        .with_children(|parent| {
+                            for (button, label) in [
+                                (LineupButton::Reroll, reroll_label(lineup)),
+                                (LineupButton::Confirm, t!("starter.confirm")),
+                                (LineupButton::Back, t!("common.back")),
+                            ] {
+                                parent
+                                    .spawn_bundle(ButtonBundle {
+                                        style: Style {
+                                            size: Size::new(Val::Px(220.0), Val::Px(50.0)),
+                                            margin: UiRect::all(Val::Px(5.0)),
+                                            justify_content: JustifyContent::Center,
+                                            align_items: AlignItems::Center,
+                                            ..default()
+                                        },
+                                        color: theme.normal_button.into(),
+                                        ..default()
+                                    })
+                                    .insert_bundle((button, Scaled::size(220.0, 50.0)))
+                                    .with_children(|parent| {
+                                        let mut label =
+                                            parent.spawn_bundle(TextBundle::from_section(
+                                                label,
+                                                text(24.0, theme.button_text),
+                                            ));
+                                        label.insert(Scaled::font(24.0));
+                                        if button == LineupButton::Reroll {
+                                            label.insert(RerollLabel);
+                                        }
+                                    });
+                            }
+                        });
+                    parent
+                        .spawn_bundle(NodeBundle {
+                            style: Style {
+                                size: Size::new(Val::Percent(100.0), Val::Auto),
+                                justify_content: JustifyContent::SpaceEvenly,
+                                ..default()
+                            },
+                            color: Color::NONE.into(),
+                            ..default()
+                        })
+                        .with_children(|parent| {
+                            for (index, body) in lineup.bodies.iter().enumerate() {
+                                parent
+                                    .spawn_bundle(ButtonBundle {
+                                        style: Style {
+                                            size: Size::new(Val::Percent(15.0), Val::Auto),
+                                            margin: UiRect::all(Val::Px(5.0)),
+                                            padding: UiRect::all(Val::Px(8.0)),
+                                            ..default()
+                                        },
+                                        color: theme.normal_button.into(),
+                                        ..default()
+                                    })
+                                    .insert(LineupCard(index))
+                                    .with_children(|parent| {
+                                        parent
+                                            .spawn_bundle(TextBundle::from_sections(card_sections(
+                                                body, fonts, theme,
+                                            )))
+                                            .insert(LineupCardText(index));
+                                    });
+                            }
+                        });
+                });
+        });
+}
+
+fn enter_lineup_system(
+    mut commands: Commands,
+    mut rng: ResMut<GameRng>,
+    daily: Option<Res<DailyRun>>,
+    palette: Res<PaletteMode>,
+    fonts: Res<Fonts>,
+    theme: Res<Theme>,
+) {
+    let lineup = Lineup {
+        bodies: Lineup::roll(&mut rng, daily.as_deref(), 0),
+        selected: 0,
+        rerolls_left: REROLLS,
+        rolls: 1,
+    };
+    spawn_lineup(&mut commands, &lineup, *palette, theme.outline);
+    spawn_lineup_ui(&mut commands, &fonts, &theme, &lineup);
+    commands.insert_resource(lineup);
+}
+
+// Arrows or a click pick a body, Enter or clicking the picked one again takes
+// it. R rerolls.
+fn lineup_input_system(
+    keys: Res<Input<KeyCode>>,
+    mut lineup: ResMut<Lineup>,
+    cards: Query<(&Interaction, &LineupCard), Changed<Interaction>>,
+    buttons: Query<(&Interaction, &LineupButton), Changed<Interaction>>,
+    mut pressed: EventWriter<LineupButton>,
+) {
+    if keys.just_pressed(KeyCode::Left) {
+        let index = lineup.selected.saturating_sub(1);
+        lineup.select(index);
+    }
+    if keys.just_pressed(KeyCode::Right) {
+        let index = lineup.selected + 1;
+        lineup.select(index);
+    }
+    if keys.any_just_pressed([KeyCode::Return, KeyCode::Space]) {
+        pressed.send(LineupButton::Confirm);
+    }
+    if keys.just_pressed(KeyCode::R) {
+        pressed.send(LineupButton::Reroll);
+    }
+    for (interaction, card) in &cards {
+        if *interaction == Interaction::Clicked {
+            if lineup.selected == card.0 {
+                pressed.send(LineupButton::Confirm);
+            }
+            lineup.select(card.0);
+        }
+    }
+    for (interaction, button) in &buttons {
+        if *interaction == Interaction::Clicked {
+            pressed.send(*button);
+        }
+    }
+}
+
+fn reroll_system(
+    mut commands: Commands,
+    mut pressed: EventReader<LineupButton>,
+    mut rng: ResMut<GameRng>,
+    daily: Option<Res<DailyRun>>,
+    palette: Res<PaletteMode>,
+    fonts: Res<Fonts>,
+    theme: Res<Theme>,
+    mut lineup: ResMut<Lineup>,
+    roots: Query<Entity, With<LineupRoot>>,
+    mut cards: Query<(&mut Text, &LineupCardText)>,
+    mut label: Query<&mut Text, (With<RerollLabel>, Without<LineupCardText>)>,
+) {
+    let rerolls = pressed
+        .iter()
+        .filter(|button| **button == LineupButton::Reroll)
+        .count();
+    if rerolls == 0 || lineup.rerolls_left == 0 {
+        return;
+    }
+    lineup.rerolls_left -= 1;
+    lineup.bodies = Lineup::roll(&mut rng, daily.as_deref(), lineup.rolls);
+    lineup.rolls += 1;
+    for root in &roots {
+        commands.entity(root).despawn_recursive();
+    }
+    spawn_lineup(&mut commands, &lineup, *palette, theme.outline);
+    for (mut text, card) in &mut cards {
+        text.sections = card_sections(&lineup.bodies[card.0], &fonts, &theme);
+    }
+    for mut text in &mut label {
+        text.sections[0].value = reroll_label(&lineup);
+    }
+}
+
+// The one taken goes into the player's slot, the rest are despawned on the way
+// out.
+fn confirm_system(
+    mut pressed: EventReader<LineupButton>,
+    game: Res<Game>,
+    daily: Option<Res<DailyRun>>,
+    lineup: Res<Lineup>,
+    mut profile: ResMut<Profile>,
+    mut state: ResMut<State<AppState>>,
+    mut swaps: EventWriter<SwapBody>,
+    mut errors: EventWriter<ErrorToast>,
+) {
+    let pressed = pressed.iter().copied().collect::<Vec<_>>();
+    if pressed.contains(&LineupButton::Back) {
+        // A daily attempt already counts, leaving puts the player's own body
+        // back like losing would.
+        let _ = state.set(AppState::PreBattle);
+        return;
+    }
+    if !pressed.contains(&LineupButton::Confirm) {
+        return;
+    }
+    let body = lineup.selected().clone();
+    swaps.send(SwapBody {
+        entity: game.player,
+        body: body.clone(),
+    });
+    if daily.is_some() {
+        let _ = state.set(AppState::Battle);
+    } else {
+        profile.body = Some(body);
+        report(&mut errors, profile.save());
+        let _ = state.set(AppState::PreBattle);
+    }
+}
+
+fn highlight_system(
+    lineup: Res<Lineup>,
+    theme: Res<Theme>,
+    mut cards: Query<(&Interaction, &LineupCard, &mut UiColor)>,
+    mut plates: Query<(&LineupPlate, &mut Visibility)>,
+) {
+    for (interaction, card, mut color) in &mut cards {
+        *color = if card.0 == lineup.selected {
+            theme.pressed_button
+        } else if *interaction == Interaction::Hovered {
+            theme.hovered_button
+        } else {
+            theme.normal_button
+        }
+        .into();
+    }
+    for (plate, mut visibility) in &mut plates {
+        visibility.is_visible = plate.0 == lineup.selected;
+    }
+}
+
+// Frames the lineup over the cards, after the battle's framing so this wins.
+fn lineup_camera_system(
+    lineup: Res<Lineup>,
+    windows: Res<Windows>,
+    mut camera: Query<&mut Transform, With<MainCamera>>,
+) {
+    let Ok(mut transform) = camera.get_single_mut() else {
+        return;
+    };
+    let aspect = camera::aspect(&windows);
+    let bodies = lineup
+        .bodies
+        .iter()
+        .enumerate()
+        .map(|(index, body)| (body, lineup_position(index)))
+        .collect::<Vec<_>>();
+    let (look_at, scale) = camera::framing(&bodies, aspect);
+    let scale = scale / (1.0 - CARD_ROOM);
+    let look_at = look_at - Vec2::Y * camera::view_height(scale, aspect) * CARD_ROOM / 2.0;
+    transform.translation.x = look_at.x;
+    transform.translation.y = look_at.y;
+    transform.scale = Vec3::new(scale, scale, 1.0);
+}
+
+fn exit_lineup_system(
+    mut commands: Commands,
+    entities: Query<Entity, Or<(With<LineupRoot>, With<LineupUi>)>>,
+) {
+    for entity in &entities {
+        commands.entity(entity).despawn_recursive();
+    }
+    commands.remove_resource::<Lineup>();
+}
+
+// A fresh profile has no body of its own yet, it starts by picking one. Only
+// offered once a session so backing out doesn't loop.
+fn first_body_system(
+    profile: Res<Profile>,
+    daily: Option<Res<DailyRun>>,
+    mut offered: Local<bool>,
+    mut state: ResMut<State<AppState>>,
+) {
+    if *offered {
+        return;
+    }
+    *offered = true;
+    if profile.body.is_none() && daily.is_none() {
+        let _ = state.set(AppState::Starter);
+    }
+}
+
+pub struct StarterPlugin;
+
+impl Plugin for StarterPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<LineupButton>()
+            .add_system_set(SystemSet::on_enter(AppState::PreBattle).with_system(first_body_system))
+            .add_system_set(SystemSet::on_enter(AppState::Starter).with_system(enter_lineup_system))
+            .add_system_set(
+                SystemSet::on_update(AppState::Starter)
+                    .with_system(lineup_input_system)
+                    .with_system(reroll_system.after(lineup_input_system))
+                    .with_system(confirm_system.after(reroll_system))
+                    .with_system(highlight_system.after(reroll_system))
+                    .with_system(lineup_camera_system.after(dynamic_camera)),
+            )
+            .add_system_set(SystemSet::on_exit(AppState::Starter).with_system(exit_lineup_system));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::ecs::system::CommandQueue;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn lineups_roll_the_same_from_a_seed_and_stay_inert() {
+        let codes = |seed| {
+            generate(&mut StdRng::seed_from_u64(seed))
+                .iter()
+                .map(Body::to_share_code)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(codes(4), codes(4));
+        assert_ne!(codes(4), codes(5));
+
+        let lineup = Lineup {
+            bodies: generate(&mut StdRng::seed_from_u64(4)),
+            selected: 2,
+            rerolls_left: REROLLS,
+            rolls: 1,
+        };
+        let mut world = World::new();
+        let mut queue = CommandQueue::default();
+        spawn_lineup(
+            &mut Commands::new(&mut queue, &world),
+            &lineup,
+            PaletteMode::default(),
+            Color::WHITE,
+        );
+        queue.apply(&mut world);
+        assert_eq!(
+            world.query::<&LineupRoot>().iter(&world).count(),
+            LINEUP_SIZE
+        );
+        assert!(world.query::<&Sprite>().iter(&world).count() > LINEUP_SIZE);
+        // Nothing the battle steps, hits or targets.
+        assert_eq!(world.query::<&Body>().iter(&world).count(), 0);
+        assert_eq!(world.query::<&Stats>().iter(&world).count(), 0);
+        let shown = world
+            .query::<(&LineupPlate, &Visibility)>()
+            .iter(&world)
+            .filter(|(_, visibility)| visibility.is_visible)
+            .map(|(plate, _)| plate.0)
+            .collect::<Vec<_>>();
+        assert_eq!(shown, [2]);
+    }
+}
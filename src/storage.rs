@@ -0,0 +1,97 @@
+// Where saved data lives: files in the data directory on desktop, the
+// browser's local storage on the web. Keys are plain file names, and what goes
+// wrong is a `GameError::Io` naming the key.
+
+#[cfg(not(target_arch = "wasm32"))]
+mod backend {
+    use std::{fs, io, path::PathBuf};
+
+    use directories::ProjectDirs;
+
+    use crate::error::GameError;
+
+    fn path(key: &str) -> Result<PathBuf, GameError> {
+        ProjectDirs::from("", "", "combine")
+            .map(|dirs| dirs.data_dir().join(key))
+            .ok_or_else(|| GameError::io(key, "no data directory"))
+    }
+
+    // None when nothing was saved under `key` yet.
+    pub fn read(key: &str) -> Result<Option<String>, GameError> {
+        match fs::read_to_string(path(key)?) {
+            Ok(contents) => Ok(Some(contents)),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(GameError::io(key, err)),
+        }
+    }
+
+    pub fn write(key: &str, contents: &str) -> Result<(), GameError> {
+        write_bytes(key, contents.as_bytes())
+    }
+
+    pub fn write_bytes(key: &str, contents: &[u8]) -> Result<(), GameError> {
+        let path = path(key)?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|err| GameError::io(key, err))?;
+        }
+        // Write to a temporary file first so a crash mid-write can't leave a
+        // truncated file behind.
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, contents).map_err(|err| GameError::io(key, err))?;
+        fs::rename(&tmp, &path).map_err(|err| GameError::io(key, err))
+    }
+
+    pub fn rename(from: &str, to: &str) -> Result<(), GameError> {
+        fs::rename(path(from)?, path(to)?).map_err(|err| GameError::io(from, err))
+    }
+
+    pub fn describe(key: &str) -> String {
+        path(key).map_or_else(|_| key.to_string(), |path| path.display().to_string())
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+mod backend {
+    use web_sys::Storage;
+
+    use crate::error::GameError;
+
+    fn storage(key: &str) -> Result<Storage, GameError> {
+        web_sys::window()
+            .and_then(|window| window.local_storage().ok().flatten())
+            .ok_or_else(|| GameError::io(key, "local storage is unavailable"))
+    }
+
+    fn key_for(key: &str) -> String {
+        format!("combine/{}", key)
+    }
+
+    pub fn read(key: &str) -> Result<Option<String>, GameError> {
+        storage(key)?
+            .get_item(&key_for(key))
+            .map_err(|err| GameError::io(key, format!("{:?}", err)))
+    }
+
+    pub fn write(key: &str, contents: &str) -> Result<(), GameError> {
+        storage(key)?
+            .set_item(&key_for(key), contents)
+            .map_err(|err| GameError::io(key, format!("{:?}", err)))
+    }
+
+    pub fn rename(from: &str, to: &str) -> Result<(), GameError> {
+        let contents = read(from)?.ok_or_else(|| GameError::io(from, "missing"))?;
+        write(to, &contents)?;
+        storage(from)?
+            .remove_item(&key_for(from))
+            .map_err(|err| GameError::io(from, format!("{:?}", err)))
+    }
+
+    pub fn describe(key: &str) -> String {
+        format!("local storage `{}`", key_for(key))
+    }
+}
+
+pub use backend::{describe, read, rename, write};
+// Only desktop saves files that aren't text, the browser downloads them.
+#[cfg(not(target_arch = "wasm32"))]
+pub use backend::write_bytes;
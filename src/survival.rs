@@ -0,0 +1,250 @@
+use bevy::{ecs::entity::Entities, prelude::*};
+use rand::RngCore;
+
+use crate::{
+    body::{compute_stats, random_body, random_body_in, Body, Stats, WeightClass},
+    boss::{Boss, BossDef, BossLibrary},
+    daily::{DailyRun, Stream},
+    error::{report, ErrorToast},
+    locale::t,
+    profile::{record_result_system, Profile},
+    swap::SwapBody,
+    turn::Ruleset,
+    ui::Toast,
+    AppState, Game, GameRng,
+};
+
+pub struct SurvivalConfig {
+    pub intermission: f32,
+    // Enemy power grows linearly per wave until it reaches `max_power`.
+    pub power_growth: f32,
+    pub max_power: f32,
+    pub carry_health: bool,
+    pub refill_energy: bool,
+    pub heal_every: u32,
+    pub heal_fraction: f32,
+    pub boss_every: u32,
+    // Boss parts are this much weaker once the player owns them.
+    pub boss_loot_factor: f32,
+}
+
+impl Default for SurvivalConfig {
+    fn default() -> Self {
+        Self {
+            intermission: 10.0,
+            power_growth: 0.12,
+            max_power: 3.0,
+            carry_health: true,
+            refill_energy: true,
+            heal_every: 3,
+            heal_fraction: 0.25,
+            boss_every: 5,
+            boss_loot_factor: 0.8,
+        }
+    }
+}
+
+impl SurvivalConfig {
+    pub fn enemy_power(&self, wave: u32) -> f32 {
+        (1.0 + self.power_growth * wave.saturating_sub(1) as f32).min(self.max_power)
+    }
+
+    pub fn heals_on(&self, wave: u32) -> bool {
+        self.heal_every > 0 && wave > 1 && (wave - 1).is_multiple_of(self.heal_every)
+    }
+
+    pub fn boss_on(&self, wave: u32) -> bool {
+        self.boss_every > 0 && wave.is_multiple_of(self.boss_every)
+    }
+}
+
+pub struct SurvivalRun {
+    pub wave: u32,
+    pub intermission: f32,
+    pub swapped: bool,
+    health: f32,
+    energy: f32,
+}
+
+fn start_wave_system(
+    mut commands: Commands,
+    config: Res<SurvivalConfig>,
+    ruleset: Res<Ruleset>,
+    game: Res<Game>,
+    run: Option<Res<SurvivalRun>>,
+    mut stats: Query<&mut Stats>,
+    mut toasts: EventWriter<Toast>,
+) {
+    let run = match run {
+        Some(run) => run,
+        None => {
+            if ruleset.survival {
+                commands.insert_resource(SurvivalRun {
+                    wave: 1,
+                    intermission: 0.0,
+                    swapped: false,
+                    health: 0.0,
+                    energy: 0.0,
+                });
+                toasts.send(Toast(t!("survival.wave", wave = 1u32)));
+            }
+            return;
+        }
+    };
+    if let Ok(mut stats) = stats.get_mut(game.player) {
+        if config.carry_health {
+            stats.health = run.health.min(stats.max_health);
+        }
+        if !config.refill_energy {
+            stats.energy = run.energy.min(stats.max_energy);
+        }
+        if config.heals_on(run.wave) {
+            stats.health =
+                (stats.health + stats.max_health * config.heal_fraction).min(stats.max_health);
+            toasts.send(Toast(t!("survival.repair_kit")));
+        }
+    }
+    toasts.send(Toast(t!("survival.wave", wave = run.wave)));
+}
+
+fn begin_intermission_system(
+    mut commands: Commands,
+    config: Res<SurvivalConfig>,
+    game: Res<Game>,
+    entities: &Entities,
+    library: Res<BossLibrary>,
+    boss_defs: Res<Assets<BossDef>>,
+    mut run: ResMut<SurvivalRun>,
+    mut rng: ResMut<GameRng>,
+    daily: Option<Res<DailyRun>>,
+    mut profile: ResMut<Profile>,
+    mut toasts: EventWriter<Toast>,
+    mut swaps: EventWriter<SwapBody>,
+    stats: Query<&Stats>,
+    bosses: Query<&Boss>,
+    mut bodies: Query<&mut Body>,
+) {
+    if let Ok(stats) = stats.get(game.player) {
+        run.health = stats.health;
+        run.energy = stats.energy;
+    }
+    if !entities.contains(game.enemy) {
+        warn!("survival enemy {:?} no longer exists", game.enemy);
+        return;
+    }
+    if let Ok(boss) = bosses.get(game.enemy) {
+        if let Ok([mut player, enemy]) = bodies.get_many_mut([game.player, game.enemy]) {
+            let part = player.graft(&enemy, boss.loot, config.boss_loot_factor);
+            profile.history.record_swap(run.wave + 1, &part);
+            toasts.send(Toast(t!("survival.looted", part = part)));
+        }
+        commands.entity(game.enemy).remove::<Boss>();
+    }
+    run.wave += 1;
+    run.intermission = config.intermission;
+    run.swapped = false;
+
+    // A daily challenge's enemies are the same whatever happened in battle.
+    let mut seeded = daily.map(|daily| daily.rng(Stream::Enemy, run.wave));
+    let mut rng: &mut dyn RngCore = match &mut seeded {
+        Some(seeded) => seeded,
+        None => &mut **rng,
+    };
+    let power = config.enemy_power(run.wave);
+    let boss = config
+        .boss_on(run.wave)
+        .then(|| library.choose(&boss_defs, &mut rng))
+        .flatten();
+    match boss {
+        Some(def) => {
+            let mut enemy = def.body.clone();
+            enemy.scale_power(power);
+            swaps.send(SwapBody {
+                entity: game.enemy,
+                body: enemy,
+            });
+            commands.entity(game.enemy).insert(Boss {
+                name: def.name.clone(),
+                loot: def.loot,
+            });
+            toasts.send(Toast(t!("survival.boss", name = &def.name)));
+        }
+        None => {
+            // Matched to the player as they are after any loot.
+            let class = bodies
+                .get(game.player)
+                .map_or(WeightClass::Middle, |body| compute_stats(body).class());
+            let mut enemy = random_body_in(&mut rng, class);
+            enemy.scale_power(power);
+            swaps.send(SwapBody {
+                entity: game.enemy,
+                body: enemy,
+            });
+        }
+    }
+}
+
+fn intermission_system(
+    time: Res<Time>,
+    mut run: ResMut<SurvivalRun>,
+    mut state: ResMut<State<AppState>>,
+) {
+    run.intermission -= time.delta_seconds();
+    if run.intermission <= 0.0 {
+        let _ = state.set(AppState::Battle);
+    }
+}
+
+fn end_run_system(
+    run: Option<Res<SurvivalRun>>,
+    mut profile: ResMut<Profile>,
+    mut errors: EventWriter<ErrorToast>,
+) {
+    if let Some(run) = run {
+        profile.best_wave = profile.best_wave.max(run.wave);
+        report(&mut errors, profile.save());
+    }
+}
+
+fn leave_run_system(
+    mut commands: Commands,
+    game: Res<Game>,
+    entities: &Entities,
+    run: Option<Res<SurvivalRun>>,
+    mut rng: ResMut<GameRng>,
+    mut swaps: EventWriter<SwapBody>,
+) {
+    if run.is_some() {
+        commands.remove_resource::<SurvivalRun>();
+        if entities.contains(game.enemy) {
+            commands.entity(game.enemy).remove::<Boss>();
+            swaps.send(SwapBody {
+                entity: game.enemy,
+                body: random_body(&mut **rng),
+            });
+        }
+    }
+}
+
+pub struct SurvivalPlugin;
+
+impl Plugin for SurvivalPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SurvivalConfig>()
+            .add_system_set(
+                SystemSet::on_enter(AppState::Battle)
+                    .with_system(start_wave_system.after(crate::battle::reset_battle_system)),
+            )
+            .add_system_set(
+                SystemSet::on_enter(AppState::Intermission).with_system(begin_intermission_system),
+            )
+            .add_system_set(
+                SystemSet::on_update(AppState::Intermission).with_system(intermission_system),
+            )
+            .add_system_set(
+                SystemSet::on_enter(AppState::GameOver)
+                    .with_system(end_run_system.after(record_result_system)),
+            )
+            .add_system_set(SystemSet::on_enter(AppState::PreBattle).with_system(leave_run_system));
+    }
+}
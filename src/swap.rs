@@ -0,0 +1,79 @@
+use bevy::prelude::*;
+
+use crate::{
+    approach::Approach,
+    battle::{Animation, GaitPhase, Walking},
+    body::Body,
+    interrupt::Recovery,
+    knowledge::Knowledge,
+    locks::LimbLocks,
+    runtime::SkillRuntime,
+    shield::ShieldTimer,
+    ui::UseSkill,
+    Game,
+};
+
+// Replaces a body that's already in the world. Whatever the old body was in
+// the middle of is dropped with it, nothing keyed to its parts carries over.
+pub struct SwapBody {
+    pub entity: Entity,
+    pub body: Body,
+}
+
+// Runs before anything else in the frame so no system sees the new body with
+// the old one's animation or locks. In order: what the body was doing, state
+// kept per skill or limb and then the body itself. Its stats and sprites are
+// rebuilt by update_body_system as for any changed body, the old sprites stay
+// up until the new ones are spawned.
+fn swap_body_system(world: &mut World) {
+    let swaps = world
+        .resource_mut::<Events<SwapBody>>()
+        .drain()
+        .collect::<Vec<_>>();
+    for SwapBody { entity, body } in swaps {
+        if world.get_entity(entity).is_none() {
+            warn!("Can't swap the body of {:?}, it no longer exists", entity);
+            continue;
+        }
+        if world
+            .get_resource::<Game>()
+            .is_some_and(|game| game.player == entity)
+        {
+            **world.resource_mut::<UseSkill>() = None;
+        }
+        let mut swapped = world.entity_mut(entity);
+        swapped.remove::<Animation>();
+        swapped.remove::<Walking>();
+        swapped.remove::<Approach>();
+        swapped.remove::<Recovery>();
+        if let Some(mut locks) = swapped.get_mut::<LimbLocks>() {
+            *locks = default();
+        }
+        // Ammo's shots fired still count towards the battle's accuracy.
+        if let Some(mut runtime) = swapped.get_mut::<SkillRuntime>() {
+            *runtime = default();
+        }
+        if let Some(mut timer) = swapped.get_mut::<ShieldTimer>() {
+            *timer = default();
+        }
+        // The new limbs start out at rest.
+        if let Some(mut gait) = swapped.get_mut::<GaitPhase>() {
+            *gait = default();
+        }
+        swapped.insert(body);
+        // Whatever was scanned was the old body.
+        let mut knowledge = world.query::<&mut Knowledge>();
+        for mut knowledge in knowledge.iter_mut(world) {
+            knowledge.forget(entity);
+        }
+    }
+}
+
+pub struct SwapPlugin;
+
+impl Plugin for SwapPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<SwapBody>()
+            .add_system(swap_body_system.exclusive_system().at_start());
+    }
+}
@@ -0,0 +1,102 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    battle::Animation,
+    body::{SkillId, Stats},
+    profile::Profile,
+    training::Training,
+    AppState, DamageEvent, DamageSource, Game,
+};
+
+#[derive(Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SkillUsage {
+    pub name: String,
+    pub attack: bool,
+    pub uses: u32,
+    pub hits: u32,
+    pub damage: f32,
+    // Uses that landed at least one hit.
+    pub connected: u32,
+}
+
+impl SkillUsage {
+    pub fn average_damage(&self) -> Option<f32> {
+        (self.hits > 0).then(|| self.damage / self.hits as f32)
+    }
+
+    pub fn whiff_rate(&self) -> Option<f32> {
+        (self.attack && self.uses > 0).then(|| 1.0 - self.connected as f32 / self.uses as f32)
+    }
+}
+
+#[derive(Default)]
+struct ActiveSkill {
+    id: Option<SkillId>,
+    connected: bool,
+}
+
+fn telemetry_system(
+    game: Res<Game>,
+    training: Option<Res<Training>>,
+    mut profile: ResMut<Profile>,
+    mut active: Local<ActiveSkill>,
+    started: Query<&Animation, Added<Animation>>,
+    stats: Query<&Stats>,
+    mut damage: EventReader<DamageEvent>,
+) {
+    // Practice on the dummy would skew the numbers.
+    if training.is_some() {
+        damage.clear();
+        return;
+    }
+    let stats = match stats.get(game.player) {
+        Ok(stats) => stats,
+        Err(_) => return,
+    };
+    if let Some(skill) = started
+        .get(game.player)
+        .ok()
+        .and_then(|animation| stats.skill(&animation.skill))
+    {
+        let id = skill.id();
+        let usage = profile.skill_stats.entry(id.clone()).or_default();
+        usage.name = skill.get_name();
+        usage.attack = skill.hits().is_some();
+        usage.uses += 1;
+        *active = ActiveSkill {
+            id: Some(id),
+            connected: false,
+        };
+    }
+    // Credited to the skill that landed it, a shot can land after the next
+    // skill started.
+    for event in damage.iter() {
+        let DamageSource::Attack { attacker, skill } = &event.source else {
+            continue;
+        };
+        if *attacker != game.player {
+            continue;
+        }
+        if let Some(usage) = profile.skill_stats.get_mut(skill) {
+            usage.hits += 1;
+            usage.damage += event.amount;
+            if active.id.as_ref() == Some(skill) && !active.connected {
+                usage.connected += 1;
+                active.connected = true;
+            }
+        }
+    }
+}
+
+pub struct TelemetryPlugin;
+
+impl Plugin for TelemetryPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(
+            SystemSet::on_update(AppState::Battle)
+                .with_system(telemetry_system.after(crate::battle::animation_system)),
+        );
+    }
+}
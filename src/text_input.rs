@@ -0,0 +1,157 @@
+use bevy::{ecs::system::EntityCommands, input::InputSystem, prelude::*};
+
+use crate::ui::Fonts;
+
+const CARET_BLINK: f64 = 0.5;
+const TEXT_COLOR: Color = Color::BLACK;
+const PLACEHOLDER_COLOR: Color = Color::rgb(0.45, 0.45, 0.45);
+
+// A single line text field. Click it to focus, click anywhere else or press
+// Enter to commit, which sends a `TextInputSubmitted`.
+#[derive(Component)]
+pub struct TextInput {
+    pub value: String,
+    // Shown greyed out while the field is empty and unfocused.
+    pub placeholder: String,
+    pub max_length: usize,
+    focused: bool,
+}
+
+impl TextInput {
+    pub fn new(value: &str, placeholder: &str, max_length: usize) -> Self {
+        Self {
+            value: value.chars().take(max_length).collect(),
+            placeholder: placeholder.to_string(),
+            max_length,
+            focused: false,
+        }
+    }
+
+    pub fn focused(&self) -> bool {
+        self.focused
+    }
+}
+
+pub struct TextInputSubmitted {
+    pub entity: Entity,
+    pub value: String,
+}
+
+pub fn spawn_text_input<'w, 's, 'a>(
+    parent: &'a mut ChildBuilder<'w, 's, '_>,
+    fonts: &Fonts,
+    input: TextInput,
+) -> EntityCommands<'w, 's, 'a> {
+    let mut entity = parent.spawn_bundle(ButtonBundle {
+        style: Style {
+            size: Size::new(Val::Px(420.0), Val::Px(50.0)),
+            margin: UiRect::all(Val::Px(5.0)),
+            padding: UiRect::new(Val::Px(10.0), Val::Px(10.0), Val::Px(0.0), Val::Px(0.0)),
+            align_items: AlignItems::Center,
+            ..default()
+        },
+        color: Color::rgb(0.9, 0.9, 0.9).into(),
+        ..default()
+    });
+    entity.insert(input).with_children(|parent| {
+        parent.spawn_bundle(TextBundle::from_section(
+            "",
+            TextStyle {
+                font: fonts.normal(),
+                font_size: 26.0,
+                color: TEXT_COLOR,
+            },
+        ));
+    });
+    entity
+}
+
+pub fn focus_system(
+    mouse: Res<Input<MouseButton>>,
+    mut inputs: Query<(Entity, &Interaction, &mut TextInput)>,
+    mut submitted: EventWriter<TextInputSubmitted>,
+) {
+    if !mouse.just_pressed(MouseButton::Left) {
+        return;
+    }
+    for (entity, interaction, mut input) in &mut inputs {
+        let focused = *interaction == Interaction::Clicked;
+        if input.focused && !focused {
+            submitted.send(TextInputSubmitted {
+                entity,
+                value: input.value.clone(),
+            });
+        }
+        if input.focused != focused {
+            input.focused = focused;
+        }
+    }
+}
+
+// Runs right after input is collected, like the console, and swallows every
+// key while a field is focused so hotkeys don't fire while typing.
+fn typing_system(
+    mut keys: ResMut<Input<KeyCode>>,
+    mut chars: EventReader<ReceivedCharacter>,
+    mut inputs: Query<(Entity, &mut TextInput)>,
+    mut submitted: EventWriter<TextInputSubmitted>,
+) {
+    let Some((entity, mut input)) = inputs.iter_mut().find(|(_, input)| input.focused) else {
+        return;
+    };
+    for event in chars.iter() {
+        match event.char {
+            '\r' | '\n' => {
+                input.focused = false;
+                submitted.send(TextInputSubmitted {
+                    entity,
+                    value: input.value.clone(),
+                });
+                break;
+            }
+            '\u{8}' => {
+                input.value.pop();
+            }
+            c if !c.is_control() && input.value.chars().count() < input.max_length => {
+                input.value.push(c);
+            }
+            _ => {}
+        }
+    }
+    keys.reset_all();
+}
+
+fn display_system(
+    time: Res<Time>,
+    inputs: Query<(&TextInput, &Children)>,
+    mut texts: Query<&mut Text>,
+) {
+    let caret_on = ((time.seconds_since_startup() / CARET_BLINK) as u64).is_multiple_of(2);
+    for (input, children) in &inputs {
+        let (value, color) = match (input.value.is_empty(), input.focused) {
+            (true, false) => (input.placeholder.clone(), PLACEHOLDER_COLOR),
+            (_, true) if caret_on => (format!("{}|", input.value), TEXT_COLOR),
+            _ => (input.value.clone(), TEXT_COLOR),
+        };
+        for child in children {
+            if let Ok(mut text) = texts.get_mut(*child) {
+                let section = &mut text.sections[0];
+                if section.value != value {
+                    section.value = value.clone();
+                }
+                section.style.color = color;
+            }
+        }
+    }
+}
+
+pub struct TextInputPlugin;
+
+impl Plugin for TextInputPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<TextInputSubmitted>()
+            .add_system_to_stage(CoreStage::PreUpdate, typing_system.after(InputSystem))
+            .add_system(focus_system)
+            .add_system_to_stage(CoreStage::PostUpdate, display_system);
+    }
+}
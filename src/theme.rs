@@ -0,0 +1,199 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{locale::t, profile::Profile};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ThemeKind {
+    Grey,
+    HighContrast,
+}
+
+impl ThemeKind {
+    pub const ALL: &[ThemeKind] = &[ThemeKind::Grey, ThemeKind::HighContrast];
+
+    pub fn name(self) -> String {
+        t!(match self {
+            ThemeKind::Grey => "theme.grey",
+            ThemeKind::HighContrast => "theme.high_contrast",
+        })
+    }
+
+    pub fn theme(self) -> Theme {
+        match self {
+            ThemeKind::Grey => Theme {
+                kind: self,
+                normal_button: Color::rgb(0.75, 0.75, 0.75),
+                hovered_button: Color::rgb(1.0, 1.0, 1.0),
+                pressed_button: Color::rgb(1.0, 0.75, 0.75),
+                disabled_button: Color::rgb(0.1, 0.1, 0.1),
+                auto_button: Color::rgb(0.6, 0.85, 1.0),
+                button_text: Color::BLACK,
+                panel: Color::BLACK,
+                min_panel_alpha: 0.0,
+                text: Color::WHITE,
+                tooltip: Color::rgba_u8(65, 70, 72, 120),
+                tooltip_header: Color::WHITE,
+                outline: Color::WHITE,
+                outline_width: 2.0,
+            },
+            // Bright buttons on opaque black. Skill icons are tinted with
+            // the button color, so the buttons can't go dark themselves.
+            ThemeKind::HighContrast => Theme {
+                kind: self,
+                normal_button: Color::WHITE,
+                hovered_button: Color::rgb(1.0, 0.95, 0.55),
+                pressed_button: Color::rgb(0.45, 0.8, 1.0),
+                disabled_button: Color::rgb(0.4, 0.4, 0.4),
+                auto_button: Color::rgb(0.55, 1.0, 0.55),
+                button_text: Color::BLACK,
+                panel: Color::BLACK,
+                min_panel_alpha: 0.9,
+                text: Color::WHITE,
+                tooltip: Color::BLACK,
+                tooltip_header: Color::rgb(1.0, 0.85, 0.2),
+                outline: Color::rgb(1.0, 0.85, 0.2),
+                outline_width: 6.0,
+            },
+        }
+    }
+}
+
+// The colors every screen and HUD element is drawn with, follows
+// `Settings::theme`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Theme {
+    pub kind: ThemeKind,
+    pub normal_button: Color,
+    pub hovered_button: Color,
+    pub pressed_button: Color,
+    pub disabled_button: Color,
+    // What an AI playing the player's body picked, see auto.rs.
+    pub auto_button: Color,
+    pub button_text: Color,
+    panel: Color,
+    // The see-through screens let through no more of the battle than this.
+    min_panel_alpha: f32,
+    pub text: Color,
+    pub tooltip: Color,
+    pub tooltip_header: Color,
+    // Drawn around the button picked with the keyboard or a gamepad.
+    pub outline: Color,
+    pub outline_width: f32,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        ThemeKind::Grey.theme()
+    }
+}
+
+impl Theme {
+    // Background of a screen or banner over the battle, `alpha` is how much
+    // of it the screen hides.
+    pub fn backdrop(&self, alpha: f32) -> Color {
+        *self.panel.clone().set_a(alpha.max(self.min_panel_alpha))
+    }
+
+    // The same role's color in `other`, for recoloring what's already on
+    // screen. Colors that aren't a button's are left alone.
+    fn button_color(&self, other: &Theme, color: Color) -> Option<Color> {
+        [
+            (self.normal_button, other.normal_button),
+            (self.hovered_button, other.hovered_button),
+            (self.pressed_button, other.pressed_button),
+            (self.disabled_button, other.disabled_button),
+            (self.auto_button, other.auto_button),
+        ]
+        .into_iter()
+        .find(|(from, _)| *from == color)
+        .map(|(_, to)| to)
+    }
+}
+
+fn sync_theme_system(profile: Res<Profile>, mut theme: ResMut<Theme>) {
+    if profile.is_changed() && theme.kind != profile.settings.theme {
+        *theme = profile.settings.theme.theme();
+    }
+}
+
+// Buttons only get a new color when their interaction changes, so the ones
+// already spawned are moved over to the new theme in place.
+fn recolor_system(
+    theme: Res<Theme>,
+    mut previous: Local<Theme>,
+    mut buttons: Query<(&mut UiColor, Option<&Children>), With<Button>>,
+    mut texts: Query<&mut Text>,
+) {
+    if !theme.is_changed() || *previous == *theme {
+        return;
+    }
+    for (mut color, children) in &mut buttons {
+        if let Some(new) = previous.button_color(&theme, color.0) {
+            color.0 = new;
+        }
+        for child in children.into_iter().flatten() {
+            let Ok(mut text) = texts.get_mut(*child) else {
+                continue;
+            };
+            for section in &mut text.sections {
+                if section.style.color == previous.button_text {
+                    section.style.color = theme.button_text;
+                }
+            }
+        }
+    }
+    *previous = *theme;
+}
+
+pub struct ThemePlugin;
+
+impl Plugin for ThemePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Theme>()
+            .add_system_to_stage(CoreStage::PreUpdate, sync_theme_system)
+            .add_system_to_stage(
+                CoreStage::PreUpdate,
+                recolor_system.after(sync_theme_system),
+            );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn themes_tell_every_button_state_apart() {
+        for kind in ThemeKind::ALL {
+            let theme = kind.theme();
+            let states = [
+                theme.normal_button,
+                theme.hovered_button,
+                theme.pressed_button,
+                theme.disabled_button,
+                theme.auto_button,
+            ];
+            for (i, a) in states.iter().enumerate() {
+                assert!(states[i + 1..].iter().all(|b| a != b), "{:?}", kind);
+                assert_ne!(*a, theme.button_text, "{:?}", kind);
+            }
+            for other in ThemeKind::ALL {
+                let other = other.theme();
+                for (state, to) in states.iter().zip([
+                    other.normal_button,
+                    other.hovered_button,
+                    other.pressed_button,
+                    other.disabled_button,
+                    other.auto_button,
+                ]) {
+                    assert_eq!(theme.button_color(&other, *state), Some(to));
+                }
+                assert_eq!(theme.button_color(&other, Color::ORANGE), None);
+            }
+        }
+        let high_contrast = ThemeKind::HighContrast.theme();
+        assert_eq!(high_contrast.backdrop(0.4).a(), 0.9);
+        assert_eq!(ThemeKind::Grey.theme().backdrop(0.4).a(), 0.4);
+    }
+}
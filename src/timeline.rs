@@ -0,0 +1,230 @@
+use std::fmt;
+
+use bevy::prelude::*;
+
+use crate::{
+    ai::AiController,
+    auto::Autopilot,
+    battle::Animation,
+    body::{Facing, Stats},
+    timer::SimClock,
+    DamageEvent, Game,
+};
+
+// Seconds of battle between two snapshots.
+const SNAPSHOT_INTERVAL: f32 = 1.0;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BodySnapshot {
+    pub x: f32,
+    pub facing: Facing,
+    pub health: f32,
+    pub energy: f32,
+    // Name of the skill it was playing.
+    pub skill: Option<String>,
+}
+
+// Where the player and the enemy were at `time`, in that order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Snapshot {
+    pub time: f32,
+    pub bodies: Vec<BodySnapshot>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimelineEvent {
+    pub time: f32,
+    pub text: String,
+}
+
+// The player's body was handed to `ai` at `time`, or taken back with None.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ControllerSwitch {
+    pub time: f32,
+    pub ai: Option<String>,
+}
+
+// What happened in a battle, built while it runs headlessly. Snapshots and
+// events are kept in time order, so looking one up is a binary search.
+#[derive(Default)]
+pub struct Timeline {
+    next_snapshot: f32,
+    snapshots: Vec<Snapshot>,
+    events: Vec<TimelineEvent>,
+    switches: Vec<ControllerSwitch>,
+}
+
+impl Timeline {
+    #[cfg(test)]
+    pub fn snapshots(&self) -> &[Snapshot] {
+        &self.snapshots
+    }
+
+    #[cfg(test)]
+    pub fn events(&self) -> &[TimelineEvent] {
+        &self.events
+    }
+
+    // The last snapshot taken at or before `time`, the one to pose the
+    // bodies from and play on from.
+    pub fn snapshot_at(&self, time: f32) -> Option<&Snapshot> {
+        let after = self
+            .snapshots
+            .partition_point(|snapshot| snapshot.time <= time);
+        after.checked_sub(1).map(|i| &self.snapshots[i])
+    }
+
+    #[cfg(test)]
+    pub fn switches(&self) -> &[ControllerSwitch] {
+        &self.switches
+    }
+
+    // The AI playing the player's body at `time`, None while they played it
+    // themselves.
+    pub fn controller_at(&self, time: f32) -> Option<&str> {
+        let after = self.switches.partition_point(|switch| switch.time <= time);
+        after
+            .checked_sub(1)
+            .and_then(|i| self.switches[i].ai.as_deref())
+    }
+
+    // Every event within `window` seconds either side of `time`.
+    pub fn events_around(&self, time: f32, window: f32) -> &[TimelineEvent] {
+        let from = self
+            .events
+            .partition_point(|event| event.time < time - window);
+        let to = self
+            .events
+            .partition_point(|event| event.time <= time + window);
+        &self.events[from..to]
+    }
+}
+
+impl fmt::Display for Snapshot {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:6.1}s", self.time)?;
+        for body in &self.bodies {
+            write!(
+                f,
+                "  x {:6.2} hp {:5.1} en {:5.1} {}",
+                body.x,
+                body.health,
+                body.energy,
+                body.skill.as_deref().unwrap_or("-")
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Timeline {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for snapshot in &self.snapshots {
+            writeln!(f, "{}", snapshot)?;
+            let until = snapshot.time + SNAPSHOT_INTERVAL;
+            for event in self
+                .events
+                .iter()
+                .filter(|event| event.time >= snapshot.time && event.time < until)
+            {
+                writeln!(f, "{:6.1}s    {}", event.time, event.text)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// Runs last in the frame and only reads, a battle plays out the same with or
+// without a timeline being built. Times are on the battle's clock like the
+// sim's duration.
+fn record_system(
+    clock: Res<SimClock>,
+    game: Res<Game>,
+    mut timeline: ResMut<Timeline>,
+    mut damage: EventReader<DamageEvent>,
+    started: Query<(Entity, &Animation), Added<Animation>>,
+    bodies: Query<(&Transform, &Facing, &Stats, Option<&Animation>)>,
+    autopilots: Query<&AiController, With<Autopilot>>,
+) {
+    let now = clock.elapsed();
+    let name = |entity: Entity| {
+        if entity == game.player {
+            "player"
+        } else {
+            "enemy"
+        }
+    };
+    let skill = |entity: Entity, animation: &Animation| {
+        bodies
+            .get(entity)
+            .ok()
+            .and_then(|(_, _, stats, _)| stats.skill(&animation.skill))
+            .map(|skill| skill.get_name())
+    };
+    let controller = autopilots
+        .get(game.player)
+        .ok()
+        .map(|controller| controller.name().to_string());
+    let last = timeline
+        .switches
+        .last()
+        .and_then(|switch| switch.ai.clone());
+    if controller != last {
+        timeline.events.push(TimelineEvent {
+            time: now,
+            text: match &controller {
+                Some(ai) => format!("player hands over to {}", ai),
+                None => "player takes back control".to_string(),
+            },
+        });
+        timeline.switches.push(ControllerSwitch {
+            time: now,
+            ai: controller,
+        });
+    }
+    for (entity, animation) in &started {
+        if let Some(skill) = skill(entity, animation) {
+            timeline.events.push(TimelineEvent {
+                time: now,
+                text: format!("{} uses {}", name(entity), skill),
+            });
+        }
+    }
+    for event in damage.iter() {
+        timeline.events.push(TimelineEvent {
+            time: now,
+            text: format!("{} takes {:.1} damage", name(event.target), event.amount),
+        });
+    }
+    if now < timeline.next_snapshot {
+        return;
+    }
+    timeline.next_snapshot += SNAPSHOT_INTERVAL;
+    let snapshot = Snapshot {
+        time: now,
+        bodies: [game.player, game.enemy]
+            .into_iter()
+            .filter_map(|entity| {
+                let (transform, facing, stats, animation) = bodies.get(entity).ok()?;
+                Some(BodySnapshot {
+                    x: transform.translation.x,
+                    facing: *facing,
+                    health: stats.health,
+                    energy: stats.energy,
+                    skill: animation.and_then(|animation| skill(entity, animation)),
+                })
+            })
+            .collect(),
+    };
+    timeline.snapshots.push(snapshot);
+}
+
+// Only the sim adds it, for `--timeline`.
+pub struct TimelinePlugin;
+
+impl Plugin for TimelinePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Timeline>()
+            .add_system_to_stage(CoreStage::Last, record_system);
+    }
+}
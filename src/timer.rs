@@ -0,0 +1,155 @@
+use bevy::prelude::*;
+
+use crate::{
+    balance::BalanceConfig,
+    periodic::{PeriodicEffect, TickPayload},
+    turn::{Ruleset, Turns},
+    AppState, Game,
+};
+
+pub const TIMER_OPTIONS: &[Option<f32>] = &[Some(99.0), Some(60.0), Some(30.0), None];
+
+pub const SPEED_STEPS: &[f32] = &[1.0, 2.0, 4.0];
+
+// The id of the effect ticking on both bodies once time is up.
+pub const SUDDEN_DEATH: &str = "sudden_death";
+
+pub struct TimeScale(pub f32);
+
+impl Default for TimeScale {
+    fn default() -> Self {
+        Self(1.0)
+    }
+}
+
+impl TimeScale {
+    pub fn cycle(&mut self) {
+        let next = SPEED_STEPS
+            .iter()
+            .position(|speed| *speed == self.0)
+            .map_or(0, |i| (i + 1) % SPEED_STEPS.len());
+        self.0 = SPEED_STEPS[next];
+    }
+
+    pub fn reset(&mut self) {
+        if self.0 != 1.0 {
+            self.0 = 1.0;
+        }
+    }
+}
+
+// A frame longer than this, a stall or the window coming back into focus,
+// only moves the battle on this much.
+const MAX_FRAME_TIME: f32 = 0.1;
+
+// Battle time, read by everything in the battle that counts down or moves
+// over time instead of `Time`. It only moves while the battle is the current
+// state, so a pause of any length leaves every animation, cooldown and
+// effect where it was.
+#[derive(Default)]
+pub struct SimClock {
+    delta: f32,
+    elapsed: f32,
+}
+
+impl SimClock {
+    // Seconds the battle moved on this frame, already scaled by TimeScale.
+    pub fn delta(&self) -> f32 {
+        self.delta
+    }
+
+    pub fn elapsed(&self) -> f32 {
+        self.elapsed
+    }
+
+    fn advance(&mut self, delta: f32) {
+        self.delta = delta;
+        self.elapsed += delta;
+    }
+}
+
+// Runs at the start of the battle's frame, and only in frames the battle
+// runs in. Frames spent paused or in a menu never reach the clock.
+fn sim_clock_system(time: Res<Time>, time_scale: Res<TimeScale>, mut clock: ResMut<SimClock>) {
+    clock.advance(time.delta_seconds().min(MAX_FRAME_TIME) * time_scale.0);
+}
+
+fn reset_clock_system(mut clock: ResMut<SimClock>) {
+    *clock = SimClock::default();
+}
+
+#[derive(Default)]
+pub struct BattleTimer {
+    pub remaining: Option<f32>,
+    pub sudden_death: bool,
+}
+
+fn start_timer_system(mut commands: Commands, ruleset: Res<Ruleset>) {
+    commands.insert_resource(BattleTimer {
+        remaining: ruleset.timer,
+        ..default()
+    });
+}
+
+fn timer_system(
+    mut commands: Commands,
+    clock: Res<SimClock>,
+    balance: Res<BalanceConfig>,
+    game: Res<Game>,
+    mut timer: ResMut<BattleTimer>,
+) {
+    let dt = clock.delta();
+    let Some(remaining) = timer.remaining.as_mut() else {
+        return;
+    };
+    *remaining = (*remaining - dt).max(0.0);
+    if *remaining == 0.0 && !timer.sudden_death {
+        timer.sudden_death = true;
+        for target in [game.player, game.enemy] {
+            commands.spawn().insert(PeriodicEffect::until_removed(
+                target,
+                SUDDEN_DEATH,
+                balance.sudden_death_interval,
+                TickPayload::RampingDamage(balance.sudden_death_damage),
+            ));
+        }
+    }
+}
+
+fn player_decision_system(
+    game: Res<Game>,
+    turns: Option<Res<Turns>>,
+    mut time_scale: ResMut<TimeScale>,
+) {
+    if let Some(turns) = turns {
+        if turns.is_changed() && turns.can_act(game.player) {
+            time_scale.reset();
+        }
+    }
+}
+
+fn pause_reset_system(mut time_scale: ResMut<TimeScale>) {
+    time_scale.reset();
+}
+
+pub struct TimerPlugin;
+
+impl Plugin for TimerPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<BattleTimer>()
+            .init_resource::<TimeScale>()
+            .init_resource::<SimClock>()
+            .add_system_set(
+                SystemSet::on_enter(AppState::Battle)
+                    .with_system(start_timer_system)
+                    .with_system(reset_clock_system),
+            )
+            .add_system_set(
+                SystemSet::on_update(AppState::Battle)
+                    .with_system(sim_clock_system.exclusive_system().at_start())
+                    .with_system(timer_system)
+                    .with_system(player_decision_system),
+            )
+            .add_system_set(SystemSet::on_enter(AppState::Paused).with_system(pause_reset_system));
+    }
+}
@@ -0,0 +1,276 @@
+use std::collections::BTreeSet;
+
+use bevy::prelude::*;
+
+use crate::{
+    ai::AiController,
+    effects::ActiveEffect,
+    error::{report, ErrorToast},
+    locale::t,
+    profile::Profile,
+    theme::Theme,
+    timer::TimeScale,
+    ui::{Fonts, Scaled},
+    AppState, Game,
+};
+
+// The battle runs this much slower while a card is up.
+const SLOW_MOTION: f32 = 0.2;
+
+// What triggers a card, an `ActiveEffect` id, and the string it shows.
+const CARDS: &[(&str, &str)] = &[
+    ("recovery", "tip.recovery"),
+    ("sudden_death", "tip.sudden_death"),
+    ("periodic_health", "tip.periodic_health"),
+    ("periodic_energy", "tip.periodic_energy"),
+];
+
+// One-time explanations of mechanics, shown the first time one affects the
+// player. A new mechanic lists its effect id in CARDS or registers it from its
+// own plugin.
+pub struct TipCards(Vec<(&'static str, &'static str)>);
+
+impl Default for TipCards {
+    fn default() -> Self {
+        Self(CARDS.to_vec())
+    }
+}
+
+impl TipCards {
+    // Replaces the card of a trigger that already has one.
+    pub fn register(&mut self, trigger: &'static str, key: &'static str) {
+        self.0.retain(|(existing, _)| *existing != trigger);
+        self.0.push((trigger, key));
+    }
+
+    // The first trigger in `triggers` with a card the player hasn't seen.
+    fn unseen<'a>(
+        &self,
+        seen: &BTreeSet<String>,
+        mut triggers: impl Iterator<Item = &'a str>,
+    ) -> Option<(&'static str, &'static str)> {
+        triggers.find_map(|trigger| {
+            self.0
+                .iter()
+                .find(|(card, _)| *card == trigger && !seen.contains(trigger))
+                .copied()
+        })
+    }
+}
+
+// The card on screen and the speed to go back to once it's dismissed.
+#[derive(Default)]
+struct OpenTip(Option<(&'static str, f32)>);
+
+#[derive(Component)]
+struct TipRoot;
+
+#[derive(Component, Clone, Copy)]
+enum TipButton {
+    Dismiss,
+    // Turns tips off for good, see `Settings::tips`.
+    Disable,
+}
+
+// Sims don't add the plugin, a body the AI plays gets no tips either.
+fn trigger_system(
+    game: Res<Game>,
+    cards: Res<TipCards>,
+    mut profile: ResMut<Profile>,
+    mut open: ResMut<OpenTip>,
+    mut time_scale: ResMut<TimeScale>,
+    mut effects: EventReader<ActiveEffect>,
+    controllers: Query<(), With<AiController>>,
+    mut errors: EventWriter<ErrorToast>,
+) {
+    let triggers = effects
+        .iter()
+        .filter(|effect| effect.target == game.player)
+        .map(|effect| effect.id)
+        .collect::<Vec<_>>();
+    if open.0.is_some() || !profile.settings.tips || controllers.contains(game.player) {
+        return;
+    }
+    let Some((trigger, key)) = cards.unseen(&profile.tips_seen, triggers.into_iter()) else {
+        return;
+    };
+    profile.tips_seen.insert(trigger.to_string());
+    report(&mut errors, profile.save());
+    open.0 = Some((key, time_scale.0));
+    time_scale.0 *= SLOW_MOTION;
+}
+
+fn close(open: &mut OpenTip, time_scale: &mut TimeScale) {
+    if let Some((_, speed)) = open.0.take() {
+        time_scale.0 = speed;
+    }
+}
+
+fn button_system(
+    theme: Res<Theme>,
+    mut profile: ResMut<Profile>,
+    mut open: ResMut<OpenTip>,
+    mut time_scale: ResMut<TimeScale>,
+    mut buttons: Query<(&Interaction, &TipButton, &mut UiColor), Changed<Interaction>>,
+    mut errors: EventWriter<ErrorToast>,
+) {
+    for (interaction, button, mut color) in &mut buttons {
+        color.0 = match interaction {
+            Interaction::Clicked => {
+                if let TipButton::Disable = button {
+                    profile.settings.tips = false;
+                    report(&mut errors, profile.save());
+                }
+                close(&mut open, &mut time_scale);
+                theme.pressed_button
+            }
+            Interaction::Hovered => theme.hovered_button,
+            Interaction::None => theme.normal_button,
+        };
+    }
+}
+
+// Leaving the battle takes the card with it.
+fn exit_system(mut open: ResMut<OpenTip>, mut time_scale: ResMut<TimeScale>) {
+    close(&mut open, &mut time_scale);
+}
+
+fn card_ui_system(
+    mut commands: Commands,
+    theme: Res<Theme>,
+    fonts: Res<Fonts>,
+    open: Res<OpenTip>,
+    roots: Query<Entity, With<TipRoot>>,
+) {
+    if !open.is_changed() {
+        return;
+    }
+    for root in &roots {
+        commands.entity(root).despawn_recursive();
+    }
+    let Some((key, _)) = open.0 else {
+        return;
+    };
+    // Below the narration banner, the battle stays visible around it.
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    left: Val::Percent(25.0),
+                    right: Val::Percent(25.0),
+                    top: Val::Px(120.0),
+                    ..default()
+                },
+                flex_direction: FlexDirection::ColumnReverse,
+                align_items: AlignItems::Center,
+                padding: UiRect::all(Val::Px(12.0)),
+                ..default()
+            },
+            color: theme.backdrop(0.85).into(),
+            ..default()
+        })
+        .insert_bundle((
+            TipRoot,
+            Scaled {
+                top: Some(120.0),
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn_bundle(
+                    TextBundle::from_section(
+                        t!(key),
+                        TextStyle {
+                            font: fonts.normal(),
+                            font_size: 24.0,
+                            color: theme.text,
+                        },
+                    )
+                    .with_style(Style {
+                        margin: UiRect::all(Val::Px(6.0)),
+                        ..default()
+                    }),
+                )
+                .insert(Scaled::font(24.0));
+            parent
+                .spawn_bundle(NodeBundle {
+                    color: Color::NONE.into(),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    for (button, label) in [
+                        (TipButton::Dismiss, t!("tip.dismiss")),
+                        (TipButton::Disable, t!("tip.disable")),
+                    ] {
+                        parent
+                            .spawn_bundle(ButtonBundle {
+                                style: Style {
+                                    padding: UiRect::all(Val::Px(8.0)),
+                                    margin: UiRect::all(Val::Px(5.0)),
+                                    justify_content: JustifyContent::Center,
+                                    align_items: AlignItems::Center,
+                                    ..default()
+                                },
+                                color: theme.normal_button.into(),
+                                ..default()
+                            })
+                            .insert(button)
+                            .with_children(|parent| {
+                                parent
+                                    .spawn_bundle(TextBundle::from_section(
+                                        label,
+                                        TextStyle {
+                                            font: fonts.bold(),
+                                            font_size: 20.0,
+                                            color: theme.button_text,
+                                        },
+                                    ))
+                                    .insert(Scaled::font(20.0));
+                            });
+                    }
+                });
+        });
+}
+
+pub struct TipsPlugin;
+
+impl Plugin for TipsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TipCards>()
+            .init_resource::<OpenTip>()
+            .add_system_set(SystemSet::on_update(AppState::Battle).with_system(trigger_system))
+            .add_system_set(SystemSet::on_exit(AppState::Battle).with_system(exit_system))
+            .add_system(button_system)
+            .add_system_to_stage(CoreStage::PostUpdate, card_ui_system);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn each_card_shows_once() {
+        let mut cards = TipCards::default();
+        cards.register("overheat", "tip.overheat");
+        let mut seen = BTreeSet::new();
+        let triggers = ["invulnerable", "recovery", "overheat"];
+        assert_eq!(
+            cards.unseen(&seen, triggers.into_iter()),
+            Some(("recovery", "tip.recovery"))
+        );
+        seen.insert("recovery".to_string());
+        assert_eq!(
+            cards.unseen(&seen, triggers.into_iter()),
+            Some(("overheat", "tip.overheat"))
+        );
+        seen.insert("overheat".to_string());
+        assert_eq!(cards.unseen(&seen, triggers.into_iter()), None);
+        // Every built-in card has its text.
+        for (_, key) in CARDS {
+            assert_ne!(t!(*key), *key, "{} has no text", key);
+        }
+    }
+}
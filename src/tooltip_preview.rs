@@ -0,0 +1,311 @@
+use bevy::{
+    core_pipeline::clear_color::ClearColorConfig,
+    prelude::*,
+    render::{
+        camera::RenderTarget,
+        render_resource::{
+            Extent3d, TextureDescriptor, TextureDimension, TextureFormat, TextureUsages,
+        },
+        view::RenderLayers,
+    },
+};
+
+use crate::{
+    battle::skill_pose,
+    body::{part_sprites, Body, HitPart, Limb, PaletteMode, PartSprite, SkillId, Stats},
+    hitbox::part_corners,
+    theme::Theme,
+};
+
+// Seconds a skill's tooltip stays open before its preview plays.
+const PREVIEW_DELAY: f32 = 1.0;
+// One play of the skill and a rest before it loops. The same for every body,
+// so the previews compare the motion and not the animation rates.
+const PREVIEW_PLAY: f32 = 0.8;
+const PREVIEW_REST: f32 = 0.4;
+// Side of the square image the preview is rendered to.
+const IMAGE_SIZE: u32 = 128;
+// Side of the preview in the tooltip before the UI scale.
+pub const FRAME_SIZE: f32 = 96.0;
+// Seen by nothing but the preview camera.
+const PREVIEW_LAYER: u8 = 1;
+// Room left around the body's reach.
+const MARGIN: f32 = 1.1;
+
+// Where the tooltip shows the preview of `skill` on `owner`. Hidden until
+// the preview plays.
+#[derive(Component)]
+pub struct PreviewFrame {
+    owner: Entity,
+    skill: SkillId,
+}
+
+impl PreviewFrame {
+    pub fn new(owner: Entity, skill: SkillId) -> Self {
+        Self { owner, skill }
+    }
+}
+
+#[derive(Component)]
+struct PreviewLimb(Limb);
+
+struct Playing {
+    figure: Entity,
+    camera: Entity,
+    time: f32,
+}
+
+#[derive(Default)]
+struct TooltipPreview {
+    // The skill of the open tooltip and how long it has been open.
+    shown: Option<(Entity, SkillId)>,
+    open: f32,
+    playing: Option<Playing>,
+    // Rendered to by whichever preview is playing.
+    image: Option<Handle<Image>>,
+}
+
+fn render_target() -> Image {
+    let size = Extent3d {
+        width: IMAGE_SIZE,
+        height: IMAGE_SIZE,
+        ..default()
+    };
+    let mut image = Image {
+        texture_descriptor: TextureDescriptor {
+            label: None,
+            size,
+            dimension: TextureDimension::D2,
+            format: TextureFormat::Bgra8UnormSrgb,
+            mip_level_count: 1,
+            sample_count: 1,
+            usage: TextureUsages::TEXTURE_BINDING
+                | TextureUsages::COPY_DST
+                | TextureUsages::RENDER_ATTACHMENT,
+        },
+        ..default()
+    };
+    image.resize(size);
+    image
+}
+
+// The center and side of a square holding the body at rest with its limbs
+// swung any way, in the body's own space.
+fn framing(sprites: &[PartSprite]) -> (Vec2, f32) {
+    let (mut low, mut high) = (Vec2::splat(f32::INFINITY), Vec2::splat(f32::NEG_INFINITY));
+    for sprite in sprites {
+        let corners = match sprite.part {
+            HitPart::Limb(_) => {
+                let reach = sprite.scale.truncate().length();
+                let pivot = sprite.translation.truncate();
+                [pivot - Vec2::splat(reach), pivot + Vec2::splat(reach)]
+            }
+            HitPart::Torso | HitPart::Head => {
+                let part = Transform::from_translation(sprite.translation).with_scale(sprite.scale);
+                let corners = part_corners(&Transform::identity(), &part, &sprite.anchor);
+                [corners[0], corners[2]]
+            }
+        };
+        for corner in corners {
+            low = low.min(corner);
+            high = high.max(corner);
+        }
+    }
+    ((low + high) / 2.0, (high - low).max_element() * MARGIN)
+}
+
+fn spawn_figure(
+    commands: &mut Commands,
+    body: &Body,
+    palette: PaletteMode,
+    image: Handle<Image>,
+    background: Color,
+) -> Playing {
+    let layer = RenderLayers::layer(PREVIEW_LAYER);
+    let sprites = part_sprites(body);
+    let (center, side) = framing(&sprites);
+    let figure = commands
+        .spawn_bundle(SpatialBundle::default())
+        .insert(layer)
+        .with_children(|parent| {
+            for sprite in sprites {
+                let mut part = parent.spawn_bundle(SpriteBundle {
+                    sprite: Sprite {
+                        color: sprite.material.color(palette),
+                        anchor: sprite.anchor,
+                        ..default()
+                    },
+                    transform: Transform::from_translation(sprite.translation)
+                        .with_scale(sprite.scale),
+                    ..default()
+                });
+                part.insert(layer);
+                if let HitPart::Limb(limb) = sprite.part {
+                    part.insert(PreviewLimb(limb));
+                }
+            }
+        })
+        .id();
+    let mut camera = Camera2dBundle {
+        camera: Camera {
+            target: RenderTarget::Image(image),
+            // Before the arena camera, they'd clash on the same priority.
+            priority: -1,
+            ..default()
+        },
+        camera_2d: Camera2d {
+            clear_color: ClearColorConfig::Custom(background),
+        },
+        ..default()
+    };
+    camera.projection.scale = side / IMAGE_SIZE as f32;
+    camera.transform.translation.x = center.x;
+    camera.transform.translation.y = center.y;
+    let camera = commands
+        .spawn_bundle(camera)
+        .insert(layer)
+        .insert(UiCameraConfig { show_ui: false })
+        .id();
+    Playing {
+        figure,
+        camera,
+        time: 0.0,
+    }
+}
+
+// Starts the preview once a skill's tooltip has been open for PREVIEW_DELAY
+// and takes it down with the tooltip.
+fn preview_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    theme: Res<Theme>,
+    palette: Res<PaletteMode>,
+    mut preview: ResMut<TooltipPreview>,
+    mut images: ResMut<Assets<Image>>,
+    mut frames: Query<(&PreviewFrame, &mut UiImage, &mut Visibility)>,
+    bodies: Query<(&Body, &Stats)>,
+) {
+    let mut frame = frames.iter_mut().next();
+    let shown = frame
+        .as_ref()
+        .map(|(frame, ..)| (frame.owner, frame.skill.clone()));
+    if shown != preview.shown {
+        if let Some(playing) = preview.playing.take() {
+            commands.entity(playing.figure).despawn_recursive();
+            commands.entity(playing.camera).despawn_recursive();
+        }
+        preview.shown = shown;
+        preview.open = 0.0;
+    }
+    let Some((frame, image, visibility)) = frame.as_mut() else {
+        return;
+    };
+    preview.open += time.delta_seconds();
+    if preview.playing.is_none() && preview.open >= PREVIEW_DELAY {
+        let Ok((body, stats)) = bodies.get(frame.owner) else {
+            return;
+        };
+        if stats.skill(&frame.skill).is_none() {
+            return;
+        }
+        let target = preview
+            .image
+            .get_or_insert_with(|| images.add(render_target()))
+            .clone();
+        preview.playing = Some(spawn_figure(
+            &mut commands,
+            body,
+            *palette,
+            target,
+            theme.tooltip,
+        ));
+    }
+    // A tooltip rebuilt under the same skill gets a fresh frame.
+    if let (Some(_), Some(target)) = (&preview.playing, &preview.image) {
+        image.0 = target.clone();
+        visibility.is_visible = true;
+    }
+}
+
+// Only ever moves the preview's own limbs, nothing that plays out in the
+// battle like damage, strides or sounds comes of it.
+fn animate_preview_system(
+    time: Res<Time>,
+    mut preview: ResMut<TooltipPreview>,
+    bodies: Query<&Stats>,
+    mut limbs: Query<(&PreviewLimb, &mut Transform)>,
+) {
+    let preview = &mut *preview;
+    let (Some((owner, id)), Some(playing)) = (&preview.shown, &mut preview.playing) else {
+        return;
+    };
+    let Some((stats, skill)) = bodies
+        .get(*owner)
+        .ok()
+        .and_then(|stats| Some((stats, stats.skill(id)?)))
+    else {
+        return;
+    };
+    playing.time = (playing.time + time.delta_seconds()) % (PREVIEW_PLAY + PREVIEW_REST);
+    let pose = skill_pose(skill, stats, (playing.time / PREVIEW_PLAY).min(1.0));
+    for (limb, mut transform) in &mut limbs {
+        transform.rotation = pose
+            .iter()
+            .find(|(posed, _)| *posed == limb.0)
+            .map_or(Quat::IDENTITY, |(_, rotation)| *rotation);
+    }
+}
+
+pub struct TooltipPreviewPlugin;
+
+impl Plugin for TooltipPreviewPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TooltipPreview>()
+            .add_system(preview_system)
+            .add_system(animate_preview_system.after(preview_system));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::seeded_bodies;
+
+    // However an ability swings a limb, the whole body stays in the frame.
+    #[test]
+    fn every_pose_fits_the_frame() {
+        for body in [(2, 2), (4, 4), (8, 8)]
+            .into_iter()
+            .flat_map(|(arms, legs)| seeded_bodies(10, arms, legs))
+        {
+            let sprites = part_sprites(&body);
+            let (center, side) = framing(&sprites);
+            let stats = crate::bounds::bounded_stats(&body);
+            for skill in stats
+                .skills
+                .iter()
+                .filter(|skill| skill.ability().is_some())
+            {
+                for step in 0..=20 {
+                    let pose = skill_pose(skill, &stats, step as f32 / 20.0);
+                    for sprite in &sprites {
+                        let rotation = match sprite.part {
+                            HitPart::Limb(limb) => pose
+                                .iter()
+                                .find(|(posed, _)| *posed == limb)
+                                .map_or(Quat::IDENTITY, |(_, rotation)| *rotation),
+                            _ => Quat::IDENTITY,
+                        };
+                        let part = Transform::from_translation(sprite.translation)
+                            .with_rotation(rotation)
+                            .with_scale(sprite.scale);
+                        for corner in part_corners(&Transform::identity(), &part, &sprite.anchor) {
+                            let offset = (corner - center).abs();
+                            assert!(offset.max_element() <= side / 2.0 + 1e-4);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
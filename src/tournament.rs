@@ -0,0 +1,253 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Write as _,
+    fs,
+    path::Path,
+};
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::{
+    body::{compute_stats, random_body_with, Body, GenerationConfig, WeightClass},
+    error::GameError,
+    rating::{self, DEFAULT_RATING, K_FACTOR},
+    sim::{simulate, Outcome, SimOptions},
+};
+
+struct Entry {
+    name: String,
+    body: Body,
+    class: WeightClass,
+    rating: f64,
+    wins: u32,
+    losses: u32,
+    draws: u32,
+}
+
+#[derive(Default)]
+struct Matchup {
+    wins: [u32; 2],
+    draws: u32,
+}
+
+fn generate_pool(
+    dir: &Path,
+    count: usize,
+    class: Option<WeightClass>,
+    rng: &mut impl Rng,
+) -> Result<(), String> {
+    fs::create_dir_all(dir).map_err(|err| err.to_string())?;
+    let config = GenerationConfig {
+        class,
+        ..Default::default()
+    };
+    for i in 0..count {
+        let body = random_body_with(rng, &config);
+        let contents =
+            ron::ser::to_string_pretty(&body, Default::default()).map_err(|err| err.to_string())?;
+        let path = dir.join(format!("random_{:03}.ron", i));
+        fs::write(&path, contents).map_err(|err| format!("{}: {}", path.display(), err))?;
+    }
+    Ok(())
+}
+
+fn load_pool(dir: &Path) -> Result<Vec<Entry>, GameError> {
+    let mut paths = fs::read_dir(dir)
+        .map_err(|err| GameError::io(&dir.display().to_string(), err))?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "ron"))
+        .collect::<Vec<_>>();
+    paths.sort();
+    paths
+        .into_iter()
+        .map(|path| {
+            let file = path.display().to_string();
+            let contents = fs::read_to_string(&path).map_err(|err| GameError::io(&file, err))?;
+            let body =
+                ron::from_str::<Body>(&contents).map_err(|err| GameError::parse(&file, err))?;
+            let problems = body.problems();
+            if !problems.is_empty() {
+                return Err(GameError::Validation(problems));
+            }
+            Ok(Entry {
+                name: path
+                    .file_stem()
+                    .map_or_else(String::new, |stem| stem.to_string_lossy().into_owned()),
+                class: compute_stats(&body).class(),
+                body,
+                rating: DEFAULT_RATING,
+                wins: 0,
+                losses: 0,
+                draws: 0,
+            })
+        })
+        .collect()
+}
+
+fn play(
+    entries: &mut [Entry],
+    matchups: &mut HashMap<(usize, usize), Matchup>,
+    (a, b): (usize, usize),
+    options: &SimOptions,
+    rng: &mut StdRng,
+) {
+    for i in 0..options.matches {
+        // Alternate sides so neither body keeps the starting position.
+        let (player, enemy) = if i % 2 == 0 { (a, b) } else { (b, a) };
+        let result = simulate(
+            entries[player].body.clone(),
+            entries[enemy].body.clone(),
+            options,
+            rng.gen(),
+        );
+        let winner = match result.outcome {
+            Outcome::Player => Some(player),
+            Outcome::Enemy => Some(enemy),
+            Outcome::Draw => None,
+        };
+        let matchup = matchups.entry((a, b)).or_default();
+        let score = match winner {
+            Some(winner) if winner == a => {
+                matchup.wins[0] += 1;
+                entries[a].wins += 1;
+                entries[b].losses += 1;
+                1.0
+            }
+            Some(_) => {
+                matchup.wins[1] += 1;
+                entries[b].wins += 1;
+                entries[a].losses += 1;
+                0.0
+            }
+            None => {
+                matchup.draws += 1;
+                entries[a].draws += 1;
+                entries[b].draws += 1;
+                0.5
+            }
+        };
+        let (rating_a, rating_b) =
+            rating::update(entries[a].rating, entries[b].rating, score, K_FACTOR);
+        entries[a].rating = rating_a;
+        entries[b].rating = rating_b;
+    }
+}
+
+fn write_reports(
+    entries: &[Entry],
+    matchups: &HashMap<(usize, usize), Matchup>,
+    options: &SimOptions,
+) -> Result<(), String> {
+    let mut ranked = (0..entries.len()).collect::<Vec<_>>();
+    ranked.sort_by(|a, b| entries[*b].rating.total_cmp(&entries[*a].rating));
+
+    let mut leaderboard = "rank,name,class,rating,wins,losses,draws\n".to_string();
+    for (rank, i) in ranked.iter().enumerate() {
+        let entry = &entries[*i];
+        let _ = writeln!(
+            leaderboard,
+            "{},{},{:?},{:.1},{},{},{}",
+            rank + 1,
+            entry.name,
+            entry.class,
+            entry.rating,
+            entry.wins,
+            entry.losses,
+            entry.draws
+        );
+    }
+
+    let mut pairs = matchups.keys().copied().collect::<Vec<_>>();
+    pairs.sort();
+    let mut report = "a,b,a_wins,b_wins,draws,a_win_rate\n".to_string();
+    for (a, b) in pairs {
+        let matchup = &matchups[&(a, b)];
+        let total = matchup.wins[0] + matchup.wins[1] + matchup.draws;
+        let _ = writeln!(
+            report,
+            "{},{},{},{},{},{:.3}",
+            entries[a].name,
+            entries[b].name,
+            matchup.wins[0],
+            matchup.wins[1],
+            matchup.draws,
+            matchup.wins[0] as f64 / total.max(1) as f64
+        );
+    }
+
+    print!("{}", leaderboard);
+    fs::create_dir_all(&options.out).map_err(|err| err.to_string())?;
+    for (file, contents) in [("leaderboard.csv", leaderboard), ("matchups.csv", report)] {
+        let path = options.out.join(file);
+        fs::write(&path, contents).map_err(|err| format!("{}: {}", path.display(), err))?;
+    }
+    Ok(())
+}
+
+pub fn run(options: &SimOptions, pool: &Path) {
+    let seed = options.seed.unwrap_or_else(|| rand::thread_rng().gen());
+    let mut rng = StdRng::seed_from_u64(seed);
+    if options.generate > 0 {
+        if let Err(err) = generate_pool(pool, options.generate, options.class, &mut rng) {
+            eprintln!("Failed to generate pool: {}", err);
+            return;
+        }
+    }
+    let mut entries = match load_pool(pool) {
+        Ok(entries) => entries,
+        Err(err) => {
+            eprintln!("Failed to load pool: {}", err);
+            if let Some(details) = err.details() {
+                eprintln!("{}", details);
+            }
+            return;
+        }
+    };
+    if let Some(class) = options.class {
+        entries.retain(|entry| entry.class == class);
+    }
+    if entries.len() < 2 {
+        eprintln!("The pool needs at least two bodies");
+        return;
+    }
+    println!(
+        "{} bodies, {} matches per pairing, seed {}",
+        entries.len(),
+        options.matches,
+        seed
+    );
+
+    // Bodies only meet others in their weight class, each class is its own
+    // bracket.
+    let mut matchups = HashMap::new();
+    for class in WeightClass::ALL {
+        let bracket = (0..entries.len())
+            .filter(|i| entries[*i].class == class)
+            .collect::<Vec<_>>();
+        let pair = |(a, b): (usize, usize)| (bracket[a], bracket[b]);
+        match options.swiss {
+            Some(rounds) => {
+                let mut played = HashSet::new();
+                for _ in 0..rounds {
+                    let ratings = bracket
+                        .iter()
+                        .map(|i| entries[*i].rating)
+                        .collect::<Vec<_>>();
+                    for local in rating::swiss_round(&ratings, &played) {
+                        play(&mut entries, &mut matchups, pair(local), options, &mut rng);
+                        played.insert(local);
+                    }
+                }
+            }
+            None => {
+                for local in rating::round_robin(bracket.len()) {
+                    play(&mut entries, &mut matchups, pair(local), options, &mut rng);
+                }
+            }
+        }
+    }
+
+    if let Err(err) = write_reports(&entries, &matchups, options) {
+        eprintln!("Failed to write reports: {}", err);
+    }
+}
@@ -0,0 +1,637 @@
+use bevy::{ecs::entity::Entities, prelude::*, ui::FocusPolicy};
+use rand::Rng;
+
+use crate::{
+    ai::AiController,
+    approach::Approach,
+    battle::{
+        animation_system, damage_system, dealt_damage, defeat_system, reset_battle_system,
+        Animation, Walking, SPAWN_X,
+    },
+    body::{Facing, Skill, Stats},
+    energy::SpendEvent,
+    hitbox_view::{self, ShowHitboxes},
+    locale::t,
+    rate::AnimationRate,
+    theme::Theme,
+    timer::SimClock,
+    turn::{BattleMode, Ruleset},
+    ui::{Fonts, Scaled, UseSkill},
+    AppState, DamageEvent, Game, GameRng, Invulnerable,
+};
+
+// How long the blocking dummy holds its guard up or down before it rolls
+// again.
+const GUARD_TIME: (f32, f32) = (0.5, 1.5);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+enum Dummy {
+    #[default]
+    Stand,
+    // Paces back and forth around where it started.
+    Walk,
+    // Randomly raises a guard that nothing gets through.
+    Block,
+}
+
+impl Dummy {
+    fn next(self) -> Self {
+        match self {
+            Dummy::Stand => Dummy::Walk,
+            Dummy::Walk => Dummy::Block,
+            Dummy::Block => Dummy::Stand,
+        }
+    }
+
+    fn name(self) -> String {
+        t!(match self {
+            Dummy::Stand => "training.dummy.stand",
+            Dummy::Walk => "training.dummy.walk",
+            Dummy::Block => "training.dummy.block",
+        })
+    }
+}
+
+// One attack as the battle played it, timed on the attacker's own animation
+// clock from the frame it started.
+#[derive(Debug, Clone, Default, PartialEq)]
+struct FrameData {
+    skill: String,
+    elapsed: f32,
+    progress: f32,
+    // When each strike or shot came out.
+    active: Vec<f32>,
+    // Set once the animation is over, however it ended.
+    total: Option<f32>,
+    damage: f32,
+    blocked: bool,
+    energy: f32,
+}
+
+impl FrameData {
+    fn new(skill: String) -> Self {
+        Self { skill, ..default() }
+    }
+
+    // One frame of the animation. `points` are where the skill strikes or
+    // lets go, crossed the same way `animation_system` crosses them.
+    fn advance(&mut self, dt: f32, progress: f32, points: &[f32]) {
+        self.elapsed += dt;
+        for point in points {
+            if self.progress < *point && progress >= *point {
+                self.active.push(self.elapsed);
+            }
+        }
+        self.progress = progress;
+    }
+
+    fn finish(&mut self) {
+        self.total.get_or_insert(self.elapsed);
+    }
+
+    fn startup(&self) -> Option<f32> {
+        self.active.first().copied()
+    }
+
+    fn recovery(&self) -> Option<f32> {
+        Some(self.total? - self.active.last()?)
+    }
+
+    fn describe(&self) -> String {
+        let seconds =
+            |time: Option<f32>| time.map_or("-".to_string(), |time| format!("{:.2}s", time));
+        let active = self
+            .active
+            .iter()
+            .map(|time| seconds(Some(*time)))
+            .collect::<Vec<_>>();
+        let damage = if self.blocked {
+            t!("training.blocked", damage = self.damage)
+        } else {
+            t!("training.damage", damage = self.damage)
+        };
+        t!(
+            "training.readout",
+            skill = &self.skill,
+            startup = seconds(self.startup()),
+            active = if active.is_empty() {
+                "-".to_string()
+            } else {
+                active.join(", ")
+            },
+            recovery = seconds(self.recovery()),
+            damage = damage,
+            energy = self.energy,
+        )
+    }
+}
+
+// Where an attack strikes or fires in its animation, None for anything that
+// isn't an attack.
+fn strike_points(skill: &Skill) -> Option<Vec<f32>> {
+    match (skill.hits(), skill.release()) {
+        (Some((_, hits)), _) => Some(hits.to_vec()),
+        (None, Some((_, release))) => Some(vec![release]),
+        (None, None) => None,
+    }
+}
+
+// Present while the training room is open.
+pub struct Training {
+    dummy: Dummy,
+    infinite_health: bool,
+    // Time left before the blocking dummy rolls its guard again.
+    guard: f32,
+    // The player's latest attack, kept after it ends for shots still in the
+    // air.
+    frames: Option<FrameData>,
+    // The player's own rules, put back when training ends.
+    ruleset: Ruleset,
+}
+
+impl Training {
+    #[cfg(test)]
+    pub fn set_infinite_health(&mut self, on: bool) {
+        self.infinite_health = on;
+    }
+}
+
+// Swaps in the training rules, the caller starts the battle. The player's
+// combat rules stay so the numbers are the ones they fight with, but
+// nothing ends the session on its own.
+pub fn begin(commands: &mut Commands, ruleset: &mut Ruleset) {
+    let rules = Ruleset {
+        mode: BattleMode::RealTime,
+        timer: None,
+        survival: false,
+        weather: false,
+        pickups: false,
+        ai: ruleset.ai.clone(),
+        ..*ruleset
+    };
+    commands.insert_resource(Training {
+        dummy: Dummy::default(),
+        infinite_health: true,
+        guard: 0.0,
+        frames: None,
+        ruleset: std::mem::replace(ruleset, rules),
+    });
+}
+
+#[derive(Component, Clone, Copy, PartialEq, Eq)]
+enum TrainingButton {
+    RefillEnergy,
+    ResetPositions,
+    Hitboxes,
+    Dummy,
+    InfiniteHealth,
+    Leave,
+}
+
+impl TrainingButton {
+    const ALL: [TrainingButton; 6] = [
+        TrainingButton::RefillEnergy,
+        TrainingButton::ResetPositions,
+        TrainingButton::Hitboxes,
+        TrainingButton::Dummy,
+        TrainingButton::InfiniteHealth,
+        TrainingButton::Leave,
+    ];
+
+    fn label(self, training: &Training, hitboxes: bool) -> String {
+        let on_off = |value| t!(if value { "common.on" } else { "common.off" });
+        match self {
+            TrainingButton::RefillEnergy => t!("training.refill_energy"),
+            TrainingButton::ResetPositions => t!("training.reset_positions"),
+            TrainingButton::Hitboxes => t!("training.hitboxes", value = on_off(hitboxes)),
+            TrainingButton::Dummy => t!("training.dummy", dummy = training.dummy.name()),
+            TrainingButton::InfiniteHealth => t!(
+                "training.infinite_health",
+                value = on_off(training.infinite_health)
+            ),
+            TrainingButton::Leave => t!("training.leave"),
+        }
+    }
+}
+
+#[derive(Component)]
+struct TrainingPanel;
+
+#[derive(Component)]
+struct ButtonLabel(TrainingButton);
+
+#[derive(Component)]
+struct Readout;
+
+fn start_training_system(
+    theme: Res<Theme>,
+    mut commands: Commands,
+    game: Res<Game>,
+    fonts: Res<Fonts>,
+    training: Option<Res<Training>>,
+    entities: &Entities,
+) {
+    if training.is_none() {
+        return;
+    }
+    if entities.contains(game.enemy) {
+        commands.entity(game.enemy).remove::<AiController>();
+    }
+    let style = |size| TextStyle {
+        font: fonts.bold(),
+        font_size: size,
+        color: Color::BLACK,
+    };
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.0), Val::Auto),
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    top: Val::Px(110.0),
+                    ..default()
+                },
+                flex_direction: FlexDirection::ColumnReverse,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            color: Color::NONE.into(),
+            focus_policy: FocusPolicy::Pass,
+            ..default()
+        })
+        .insert_bundle((
+            TrainingPanel,
+            Scaled {
+                top: Some(110.0),
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn_bundle(NodeBundle {
+                    color: Color::NONE.into(),
+                    focus_policy: FocusPolicy::Pass,
+                    ..default()
+                })
+                .with_children(|parent| {
+                    // Nothing to toggle where hitboxes aren't drawn.
+                    let buttons = TrainingButton::ALL.into_iter().filter(|button| {
+                        *button != TrainingButton::Hitboxes || hitbox_view::ENABLED
+                    });
+                    for button in buttons {
+                        parent
+                            .spawn_bundle(ButtonBundle {
+                                style: Style {
+                                    padding: UiRect::all(Val::Px(6.0)),
+                                    margin: UiRect::all(Val::Px(4.0)),
+                                    ..default()
+                                },
+                                color: theme.normal_button.into(),
+                                ..default()
+                            })
+                            .insert(button)
+                            .with_children(|parent| {
+                                parent
+                                    .spawn_bundle(TextBundle::from_section("", style(18.0)))
+                                    .insert_bundle((ButtonLabel(button), Scaled::font(18.0)));
+                            });
+                    }
+                });
+            parent
+                .spawn_bundle(TextBundle::from_section(
+                    t!("training.hint"),
+                    TextStyle {
+                        color: Color::WHITE,
+                        ..style(22.0)
+                    },
+                ))
+                .insert_bundle((Readout, Scaled::font(22.0)));
+        });
+}
+
+fn button_system(
+    theme: Res<Theme>,
+    mut commands: Commands,
+    game: Res<Game>,
+    training: Option<ResMut<Training>>,
+    mut hitboxes: ResMut<ShowHitboxes>,
+    mut state: ResMut<State<AppState>>,
+    mut use_skill: ResMut<UseSkill>,
+    mut buttons: Query<(&Interaction, &TrainingButton, &mut UiColor), Changed<Interaction>>,
+    mut bodies: Query<(&mut Stats, &mut Transform, &mut Facing)>,
+) {
+    let Some(mut training) = training else {
+        return;
+    };
+    for (interaction, button, mut color) in &mut buttons {
+        color.0 = match interaction {
+            Interaction::Clicked => theme.pressed_button,
+            Interaction::Hovered => theme.hovered_button,
+            Interaction::None => theme.normal_button,
+        };
+        if *interaction != Interaction::Clicked {
+            continue;
+        }
+        match button {
+            TrainingButton::RefillEnergy => {
+                if let Ok((mut stats, _, _)) = bodies.get_mut(game.player) {
+                    stats.energy = stats.max_energy;
+                }
+            }
+            // Everything stops where it is, like the start of a battle.
+            TrainingButton::ResetPositions => {
+                **use_skill = None;
+                for (entity, x) in [(game.player, -SPAWN_X), (game.enemy, SPAWN_X)] {
+                    commands
+                        .entity(entity)
+                        .remove::<Animation>()
+                        .remove::<Walking>()
+                        .remove::<Approach>();
+                    if let Ok((_, mut transform, mut facing)) = bodies.get_mut(entity) {
+                        transform.translation.x = x;
+                        *facing = Facing::towards(x, 0.0);
+                    }
+                }
+            }
+            TrainingButton::Hitboxes => hitboxes.0 = !hitboxes.0,
+            TrainingButton::Dummy => {
+                training.dummy = training.dummy.next();
+                training.guard = 0.0;
+                commands.entity(game.enemy).remove::<Invulnerable>();
+            }
+            TrainingButton::InfiniteHealth => training.infinite_health = !training.infinite_health,
+            TrainingButton::Leave => {
+                let _ = state.set(AppState::PreBattle);
+            }
+        }
+    }
+}
+
+fn label_system(
+    training: Option<Res<Training>>,
+    hitboxes: Res<ShowHitboxes>,
+    mut labels: Query<(&mut Text, &ButtonLabel)>,
+) {
+    let Some(training) = training else {
+        return;
+    };
+    for (mut text, label) in &mut labels {
+        let value = label.0.label(&training, hitboxes.0);
+        if text.sections[0].value != value {
+            text.sections[0].value = value;
+        }
+    }
+}
+
+fn dummy_system(
+    mut commands: Commands,
+    clock: Res<SimClock>,
+    game: Res<Game>,
+    training: Option<ResMut<Training>>,
+    mut rng: ResMut<GameRng>,
+    dummies: Query<(&Stats, &Transform, &Facing, Option<&Animation>)>,
+) {
+    let Some(mut training) = training else {
+        return;
+    };
+    let Ok((stats, transform, facing, animation)) = dummies.get(game.enemy) else {
+        return;
+    };
+    match training.dummy {
+        Dummy::Stand => {}
+        // Walks back towards where it started whenever it's got ahead of it.
+        Dummy::Walk if animation.is_none() => {
+            let ahead = (transform.translation.x - SPAWN_X) * facing.sign() > 0.0;
+            let walk = stats.skills.iter().find(|skill| match skill {
+                Skill::WalkBackward => ahead,
+                Skill::WalkForward => !ahead,
+                _ => false,
+            });
+            if let Some(walk) = walk {
+                commands
+                    .entity(game.enemy)
+                    .insert(Animation::new(walk.id()));
+            }
+        }
+        Dummy::Walk => {}
+        Dummy::Block => {
+            training.guard -= clock.delta();
+            if training.guard <= 0.0 {
+                training.guard = rng.gen_range(GUARD_TIME.0..=GUARD_TIME.1);
+                if rng.gen_bool(0.5) {
+                    commands.entity(game.enemy).insert(Invulnerable);
+                } else {
+                    commands.entity(game.enemy).remove::<Invulnerable>();
+                }
+            }
+        }
+    }
+}
+
+// Follows the player's attacks through the battle's own animation and
+// damage events.
+fn frame_data_system(
+    game: Res<Game>,
+    ruleset: Res<Ruleset>,
+    training: Option<ResMut<Training>>,
+    mut spent: EventReader<SpendEvent>,
+    mut damage: EventReader<DamageEvent>,
+    players: Query<(
+        &Stats,
+        Option<&Animation>,
+        Option<ChangeTrackers<Animation>>,
+        &AnimationRate,
+    )>,
+    blocking: Query<(), With<Invulnerable>>,
+) {
+    // Read every frame so nothing from before training counts.
+    let spent = spent
+        .iter()
+        .filter(|event| event.entity == game.player)
+        .map(|event| event.amount)
+        .sum::<f32>();
+    let hits = damage
+        .iter()
+        .filter(|event| event.source.attacker() == Some(game.player))
+        .map(|event| {
+            (
+                dealt_damage(&ruleset, event),
+                blocking.contains(event.target),
+            )
+        })
+        .collect::<Vec<_>>();
+    let (Some(mut training), Ok((stats, animation, changes, rate))) =
+        (training, players.get(game.player))
+    else {
+        return;
+    };
+    let running = animation.and_then(|animation| Some((animation, stats.skill(&animation.skill)?)));
+    let attack = running
+        .and_then(|(animation, skill)| Some((animation, skill.get_name(), strike_points(skill)?)));
+    // A scan or a walk gets no row, and what it cost isn't the last attack's.
+    let other = running.is_some() && attack.is_none();
+    match (attack, &mut training.frames) {
+        (Some((animation, name, points)), frames) => {
+            let frames = match frames {
+                Some(frames) if !changes.is_some_and(|changes| changes.is_added()) => frames,
+                _ => frames.insert(FrameData::new(name)),
+            };
+            if frames.total.is_none() {
+                frames.advance(rate.dt(), animation.progress, &points);
+                if animation.progress > 1.0 {
+                    frames.finish();
+                }
+            }
+        }
+        // Cancelled or interrupted, it still gets a readout.
+        (None, Some(frames)) => frames.finish(),
+        (None, None) => {}
+    }
+    if let Some(frames) = &mut training.frames {
+        if !other {
+            frames.energy += spent;
+        }
+        for (amount, blocked) in hits {
+            if blocked {
+                frames.blocked = true;
+            } else {
+                frames.damage += amount;
+            }
+        }
+    }
+}
+
+fn readout_system(training: Option<Res<Training>>, mut readouts: Query<&mut Text, With<Readout>>) {
+    let Some(frames) = training
+        .as_ref()
+        .and_then(|training| training.frames.as_ref())
+    else {
+        return;
+    };
+    let value = frames.describe();
+    for mut text in &mut readouts {
+        if text.sections[0].value != value {
+            text.sections[0].value = value.clone();
+        }
+    }
+}
+
+// Tops the dummy back up after every hit with infinite health on, and stands
+// a knocked out body back up either way. Both happen before a defeat is
+// checked, so a session never ends in a result that counts.
+fn infinite_health_system(
+    game: Res<Game>,
+    training: Option<Res<Training>>,
+    mut stats: Query<&mut Stats>,
+) {
+    let Some(training) = training else {
+        return;
+    };
+    for entity in [game.player, game.enemy] {
+        let Ok(mut stats) = stats.get_mut(entity) else {
+            continue;
+        };
+        let topped_up = entity == game.enemy && training.infinite_health;
+        let hurt = stats.health < stats.max_health || stats.overshield < stats.max_overshield;
+        if (topped_up && hurt) || stats.health <= 0.0 {
+            stats.health = stats.max_health;
+            stats.overshield = stats.max_overshield;
+        }
+    }
+}
+
+fn despawn_panel_system(mut commands: Commands, panels: Query<Entity, With<TrainingPanel>>) {
+    for panel in &panels {
+        commands.entity(panel).despawn_recursive();
+    }
+}
+
+// Whichever way training ended, the player's rules and a fighting enemy come
+// back for the next battle.
+fn leave_training_system(
+    mut commands: Commands,
+    game: Res<Game>,
+    entities: &Entities,
+    training: Option<ResMut<Training>>,
+    mut ruleset: ResMut<Ruleset>,
+    mut hitboxes: ResMut<ShowHitboxes>,
+) {
+    let Some(mut training) = training else {
+        return;
+    };
+    *ruleset = std::mem::take(&mut training.ruleset);
+    commands.remove_resource::<Training>();
+    hitboxes.0 = false;
+    if entities.contains(game.enemy) {
+        commands
+            .entity(game.enemy)
+            .insert(AiController::default())
+            .remove::<Invulnerable>();
+    }
+}
+
+pub struct TrainingPlugin;
+
+impl Plugin for TrainingPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(
+            SystemSet::on_enter(AppState::Battle)
+                .with_system(start_training_system.after(reset_battle_system)),
+        )
+        .add_system_set(
+            SystemSet::on_update(AppState::Battle)
+                .with_system(button_system)
+                .with_system(label_system)
+                .with_system(dummy_system)
+                .with_system(frame_data_system.after(animation_system))
+                .with_system(readout_system.after(frame_data_system))
+                .with_system(
+                    infinite_health_system
+                        .after(damage_system)
+                        .before(defeat_system),
+                ),
+        )
+        .add_system_set(SystemSet::on_exit(AppState::Battle).with_system(despawn_panel_system))
+        .add_system_set(
+            SystemSet::on_enter(AppState::PreBattle).with_system(leave_training_system),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frame_data_times_a_three_hit_slam() {
+        let points = [0.25, 0.5, 0.75];
+        let mut frames = FrameData::new("Slam".to_string());
+        // Frames of 0.05s moving the slam an eighth along, after a 0.1s
+        // turn that doesn't move it at all.
+        frames.advance(0.05, 0.0, &points);
+        frames.advance(0.05, 0.0, &points);
+        let mut progress = 0.0;
+        while progress <= 1.0 {
+            progress += 0.125;
+            frames.advance(0.05, progress, &points);
+        }
+        frames.finish();
+        // Counted on the frame that reaches each point.
+        let close = |a: Option<f32>, b: f32| a.is_some_and(|a| (a - b).abs() < 1e-4);
+        assert_eq!(frames.active.len(), 3);
+        assert!(close(frames.startup(), 0.2));
+        assert!(close(frames.active.last().copied(), 0.4));
+        assert!(close(frames.total, 0.55));
+        assert!(close(frames.recovery(), 0.15));
+        // Finishing twice keeps the first end.
+        frames.advance(0.05, 1.2, &points);
+        frames.finish();
+        assert!(close(frames.total, 0.55));
+
+        let mut cut = FrameData::new("Jab".to_string());
+        cut.advance(0.1, 0.2, &[0.5]);
+        cut.finish();
+        assert_eq!(cut.startup(), None);
+        assert_eq!(cut.recovery(), None);
+    }
+}
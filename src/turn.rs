@@ -0,0 +1,180 @@
+use std::cmp::Ordering;
+
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::{
+    battle::Animation, body::Stats, locale::t, mutators::Mutators, timer::TIMER_OPTIONS,
+    weather::Weather, AppState, Game, GameRng,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BattleMode {
+    #[default]
+    RealTime,
+    TurnBased,
+}
+
+impl BattleMode {
+    pub fn name(&self) -> String {
+        t!(match self {
+            BattleMode::RealTime => "mode.real_time",
+            BattleMode::TurnBased => "mode.turn_based",
+        })
+    }
+}
+
+pub struct Ruleset {
+    pub mode: BattleMode,
+    pub timer: Option<f32>,
+    pub survival: bool,
+    pub interrupts: bool,
+    pub poise: bool,
+    // Attacks started while facing away turn around first.
+    pub auto_face: bool,
+    pub weather: bool,
+    // Weather every battle is fought in instead of a rolled one, see
+    // `campaign`.
+    pub arena: Option<Weather>,
+    // Items dropped on the ground now and then, see pickup.rs.
+    pub pickups: bool,
+    pub mutators: Mutators,
+    // Name the enemy's controller is registered under, see AiRegistry.
+    pub ai: String,
+    // The player may hand their body to an AI mid-battle, see auto.rs.
+    pub auto_battle: bool,
+}
+
+impl Default for Ruleset {
+    fn default() -> Self {
+        Self {
+            mode: BattleMode::default(),
+            timer: TIMER_OPTIONS[0],
+            survival: false,
+            interrupts: false,
+            poise: false,
+            auto_face: true,
+            weather: true,
+            arena: None,
+            pickups: true,
+            mutators: Mutators::default(),
+            ai: "random".to_string(),
+            auto_battle: true,
+        }
+    }
+}
+
+impl Ruleset {
+    pub fn turn_based(&self) -> bool {
+        self.mode == BattleMode::TurnBased
+    }
+
+    // Turn-based fights are decided on the numbers, weather stays out of them.
+    pub fn rolls_weather(&self) -> bool {
+        self.weather && !self.turn_based()
+    }
+
+    // Turns can't walk over to anything in time, pickups stay out of them too.
+    pub fn spawns_pickups(&self) -> bool {
+        self.pickups && !self.turn_based()
+    }
+}
+
+pub struct Turns {
+    pub round: u32,
+    order: [Entity; 2],
+    index: usize,
+    acting: bool,
+}
+
+impl Turns {
+    pub fn current(&self) -> Entity {
+        self.order[self.index]
+    }
+
+    pub fn can_act(&self, entity: Entity) -> bool {
+        self.current() == entity && !self.acting
+    }
+}
+
+fn roll_initiative(rng: &mut impl Rng, stats: &Stats) -> i32 {
+    rng.gen_range(1..=20) + (stats.speed / 2.0).round() as i32
+        - (stats.reaction_time * 10.0).round() as i32
+}
+
+// Ties go to the lighter body.
+fn initiative_order(
+    rng: &mut impl Rng,
+    (a, a_stats): (Entity, &Stats),
+    (b, b_stats): (Entity, &Stats),
+) -> [Entity; 2] {
+    let a_roll = roll_initiative(rng, a_stats);
+    let b_roll = roll_initiative(rng, b_stats);
+    let a_first = match a_roll.cmp(&b_roll) {
+        Ordering::Greater => true,
+        Ordering::Less => false,
+        Ordering::Equal => a_stats.weight <= b_stats.weight,
+    };
+    if a_first {
+        [a, b]
+    } else {
+        [b, a]
+    }
+}
+
+fn start_turns_system(
+    mut commands: Commands,
+    ruleset: Res<Ruleset>,
+    game: Res<Game>,
+    mut rng: ResMut<GameRng>,
+    stats: Query<&Stats>,
+) {
+    if !ruleset.turn_based() {
+        commands.remove_resource::<Turns>();
+        return;
+    }
+    if let Ok([player, enemy]) = stats.get_many([game.player, game.enemy]) {
+        commands.insert_resource(Turns {
+            round: 1,
+            order: initiative_order(&mut **rng, (game.player, player), (game.enemy, enemy)),
+            index: 0,
+            acting: false,
+        });
+    }
+}
+
+fn turn_system(
+    turns: Option<ResMut<Turns>>,
+    game: Res<Game>,
+    mut rng: ResMut<GameRng>,
+    stats: Query<&Stats>,
+    animations: Query<(), With<Animation>>,
+) {
+    let mut turns = match turns {
+        Some(turns) => turns,
+        None => return,
+    };
+    let animating = animations.contains(turns.current());
+    if !turns.acting && animating {
+        turns.acting = true;
+    } else if turns.acting && !animating {
+        turns.acting = false;
+        if turns.index == 0 {
+            turns.index = 1;
+        } else if let Ok([player, enemy]) = stats.get_many([game.player, game.enemy]) {
+            turns.round += 1;
+            turns.index = 0;
+            turns.order = initiative_order(&mut **rng, (game.player, player), (game.enemy, enemy));
+        }
+    }
+}
+
+pub struct TurnPlugin;
+
+impl Plugin for TurnPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Ruleset>()
+            .add_system_set(SystemSet::on_enter(AppState::Battle).with_system(start_turns_system))
+            .add_system_set(SystemSet::on_update(AppState::Battle).with_system(turn_system));
+    }
+}
@@ -0,0 +1,475 @@
+use bevy::{ecs::entity::Entities, prelude::*, ui::FocusPolicy};
+
+use crate::{
+    ai::AiController,
+    battle::{melee_in_reach, Animation},
+    body::{Facing, Skill, SkillId, Stats},
+    error::{report, ErrorToast},
+    knowledge::Knowledge,
+    locale::t,
+    mutators::Mutators,
+    profile::Profile,
+    projectile::Projectile,
+    theme::Theme,
+    timer::BattleTimer,
+    turn::{BattleMode, Ruleset},
+    ui::{Fonts, Scaled, SkillButton, UseSkill},
+    AppState, BattleSummary, DamageEvent, Game,
+};
+
+// The enemy starts with this much of its health and never moves.
+const ENEMY_HEALTH: f32 = 0.3;
+const OUTLINE_WIDTH: f32 = 4.0;
+const OUTLINE_COLOR: Color = Color::rgb(1.0, 0.85, 0.2);
+const PULSE_SPEED: f32 = 4.0;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TutorialStep {
+    Scan,
+    Shoot,
+    Approach,
+    Attack,
+    Inspect,
+    Finish,
+}
+
+impl TutorialStep {
+    fn next(self) -> Option<Self> {
+        match self {
+            TutorialStep::Scan => Some(TutorialStep::Shoot),
+            TutorialStep::Shoot => Some(TutorialStep::Approach),
+            TutorialStep::Approach => Some(TutorialStep::Attack),
+            TutorialStep::Attack => Some(TutorialStep::Inspect),
+            TutorialStep::Inspect => Some(TutorialStep::Finish),
+            TutorialStep::Finish => None,
+        }
+    }
+
+    fn kinds(self) -> &'static [fn(&Skill) -> bool] {
+        match self {
+            TutorialStep::Scan => &[|skill| matches!(skill, Skill::Scan(_))],
+            TutorialStep::Shoot => &[
+                |skill| matches!(skill, Skill::BasicRanged(_)),
+                |skill| matches!(skill, Skill::Reload),
+            ],
+            TutorialStep::Approach => &[
+                |skill| matches!(skill, Skill::Dash(_)),
+                |skill| matches!(skill, Skill::WalkForward),
+            ],
+            TutorialStep::Attack => &[|skill| skill.hits().is_some()],
+            TutorialStep::Inspect | TutorialStep::Finish => &[],
+        }
+    }
+
+    // The skills this step asks for, the starter body has one of each. The
+    // first is highlighted while the step runs.
+    fn skills(self, stats: &Stats) -> Vec<usize> {
+        self.kinds()
+            .iter()
+            .filter_map(|kind| stats.skills.iter().position(kind))
+            .collect()
+    }
+
+    // A body built without the skills a step asks for goes on without it.
+    fn skipped(self, stats: &Stats) -> bool {
+        !self.kinds().is_empty() && self.skills(stats).is_empty()
+    }
+
+    fn allows(self, stats: &Stats, skill: &SkillId) -> bool {
+        match self {
+            TutorialStep::Inspect => false,
+            TutorialStep::Finish => true,
+            _ => self
+                .skills(stats)
+                .into_iter()
+                .any(|index| stats.skills[index].id() == *skill),
+        }
+    }
+
+    fn prompt(self, stats: &Stats) -> String {
+        let names = self
+            .skills(stats)
+            .into_iter()
+            .map(|index| stats.skills[index].get_name())
+            .collect::<Vec<_>>();
+        let skill = names.first().cloned().unwrap_or_default();
+        match self {
+            TutorialStep::Scan => t!("tutorial.scan", skill = skill),
+            TutorialStep::Shoot => t!("tutorial.shoot", skill = skill),
+            TutorialStep::Approach if names.len() > 1 => {
+                t!("tutorial.dash", skill = skill, walk = names[1].clone())
+            }
+            TutorialStep::Approach => t!("tutorial.approach", skill = skill),
+            TutorialStep::Attack => t!("tutorial.attack", skill = skill),
+            TutorialStep::Inspect => t!("tutorial.inspect"),
+            TutorialStep::Finish => t!("tutorial.finish"),
+        }
+    }
+}
+
+// Present while the tutorial battle runs.
+pub struct Tutorial {
+    step: TutorialStep,
+    // The player's own rules, put back when the tutorial ends.
+    ruleset: Ruleset,
+}
+
+// Swaps in the tutorial's rules, the caller starts the battle.
+pub fn begin(commands: &mut Commands, ruleset: &mut Ruleset) {
+    let rules = Ruleset {
+        mode: BattleMode::RealTime,
+        timer: None,
+        survival: false,
+        interrupts: false,
+        poise: false,
+        auto_face: true,
+        weather: false,
+        arena: None,
+        pickups: false,
+        mutators: Mutators::default(),
+        ai: "random".to_string(),
+        // It's teaching the bar, nobody else gets to press it.
+        auto_battle: false,
+    };
+    commands.insert_resource(Tutorial {
+        step: TutorialStep::Scan,
+        ruleset: std::mem::replace(ruleset, rules),
+    });
+}
+
+#[derive(Component)]
+struct TutorialBanner;
+
+#[derive(Component)]
+struct TutorialPrompt;
+
+#[derive(Component)]
+struct SkipButton;
+
+#[derive(Component)]
+struct TutorialOutline {
+    button: Entity,
+}
+
+fn start_tutorial_system(
+    theme: Res<Theme>,
+    mut commands: Commands,
+    game: Res<Game>,
+    fonts: Res<Fonts>,
+    tutorial: Option<Res<Tutorial>>,
+    entities: &Entities,
+    mut stats: Query<&mut Stats>,
+) {
+    if tutorial.is_none() {
+        return;
+    }
+    if entities.contains(game.enemy) {
+        commands.entity(game.enemy).remove::<AiController>();
+    }
+    if let Ok(mut stats) = stats.get_mut(game.enemy) {
+        stats.health = stats.max_health * ENEMY_HEALTH;
+    }
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.0), Val::Auto),
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    top: Val::Px(110.0),
+                    ..default()
+                },
+                flex_direction: FlexDirection::ColumnReverse,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            color: Color::NONE.into(),
+            focus_policy: FocusPolicy::Pass,
+            ..default()
+        })
+        .insert_bundle((
+            TutorialBanner,
+            Scaled {
+                top: Some(110.0),
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn_bundle(TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font: fonts.bold(),
+                        font_size: 30.0,
+                        color: Color::WHITE,
+                    },
+                ))
+                .insert_bundle((TutorialPrompt, Scaled::font(30.0)));
+            parent
+                .spawn_bundle(ButtonBundle {
+                    style: Style {
+                        padding: UiRect::all(Val::Px(6.0)),
+                        margin: UiRect::all(Val::Px(6.0)),
+                        ..default()
+                    },
+                    color: theme.normal_button.into(),
+                    ..default()
+                })
+                .insert(SkipButton)
+                .with_children(|parent| {
+                    parent
+                        .spawn_bundle(TextBundle::from_section(
+                            t!("tutorial.skip"),
+                            TextStyle {
+                                font: fonts.bold(),
+                                font_size: 18.0,
+                                color: theme.button_text,
+                            },
+                        ))
+                        .insert(Scaled::font(18.0));
+                });
+        });
+}
+
+fn advance_system(
+    game: Res<Game>,
+    tutorial: Option<ResMut<Tutorial>>,
+    mut timer: ResMut<BattleTimer>,
+    mut damage: EventReader<DamageEvent>,
+    bodies: Query<(&Transform, &Stats)>,
+    knowledge: Query<&Knowledge>,
+    shots: Query<&Projectile>,
+    animating: Query<(), With<Animation>>,
+    buttons: Query<&Interaction, With<SkillButton>>,
+) {
+    // Read every frame so old hits don't count for a later step.
+    let hit = damage
+        .iter()
+        .any(|event| event.source.attacker() == Some(game.player));
+    let Some(mut tutorial) = tutorial else {
+        return;
+    };
+    // Nothing should end the fight except the player.
+    if timer.remaining.is_some() {
+        timer.remaining = None;
+    }
+    let Ok((_, stats)) = bodies.get(game.player) else {
+        return;
+    };
+    if tutorial.step.skipped(stats) {
+        tutorial.step = tutorial.step.next().unwrap_or(TutorialStep::Finish);
+        return;
+    }
+    let next = match tutorial.step {
+        TutorialStep::Scan => knowledge
+            .get(game.player)
+            .is_ok_and(|knowledge| knowledge.tier(game.enemy) > 0)
+            .then_some(TutorialStep::Shoot),
+        // Shots can miss, letting one go is enough.
+        TutorialStep::Shoot => shots
+            .iter()
+            .any(|shot| shot.source == game.player)
+            .then_some(TutorialStep::Approach),
+        TutorialStep::Approach => {
+            let in_reach = bodies.get_many([game.player, game.enemy]).is_ok_and(
+                |[(player, stats), (enemy, enemy_stats)]| {
+                    let facing = Facing::towards(player.translation.x, enemy.translation.x);
+                    melee_in_reach(player, facing, stats, enemy, enemy_stats)
+                },
+            );
+            (in_reach && !animating.contains(game.player)).then_some(TutorialStep::Attack)
+        }
+        TutorialStep::Attack => hit.then_some(TutorialStep::Inspect),
+        TutorialStep::Inspect => buttons
+            .iter()
+            .any(|interaction| *interaction == Interaction::Hovered)
+            .then_some(TutorialStep::Finish),
+        TutorialStep::Finish => None,
+    };
+    if let Some(next) = next {
+        tutorial.step = next;
+    }
+}
+
+// Drops skills the current step doesn't ask for before they start.
+fn gate_system(
+    game: Res<Game>,
+    tutorial: Option<Res<Tutorial>>,
+    mut use_skill: ResMut<UseSkill>,
+    stats: Query<&Stats>,
+) {
+    let (Some(tutorial), Some(skill), Ok(stats)) = (tutorial, &**use_skill, stats.get(game.player))
+    else {
+        return;
+    };
+    if !tutorial.step.allows(stats, skill) {
+        **use_skill = None;
+    }
+}
+
+fn prompt_system(
+    game: Res<Game>,
+    tutorial: Option<Res<Tutorial>>,
+    stats: Query<&Stats>,
+    mut prompts: Query<&mut Text, With<TutorialPrompt>>,
+) {
+    let (Some(tutorial), Ok(stats)) = (tutorial, stats.get(game.player)) else {
+        return;
+    };
+    let prompt = tutorial.step.prompt(stats);
+    for mut text in &mut prompts {
+        if text.sections[0].value != prompt {
+            text.sections[0].value = prompt.clone();
+        }
+    }
+}
+
+// Pulses an outline over the button the current step asks for.
+fn outline_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    game: Res<Game>,
+    tutorial: Option<Res<Tutorial>>,
+    stats: Query<&Stats>,
+    buttons: Query<(Entity, &SkillButton)>,
+    mut outlines: Query<(Entity, &TutorialOutline, &mut UiColor)>,
+) {
+    let target = tutorial
+        .zip(stats.get(game.player).ok())
+        .and_then(|(tutorial, stats)| {
+            let first = *tutorial.step.skills(stats).first()?;
+            Some(stats.skills[first].id())
+        })
+        .and_then(|skill| buttons.iter().find(|(_, button)| button.0 == skill))
+        .map(|(entity, _)| entity);
+    let alpha = 0.6 + 0.4 * (time.seconds_since_startup() as f32 * PULSE_SPEED).sin();
+    let mut outlined = false;
+    for (entity, outline, mut color) in &mut outlines {
+        if Some(outline.button) == target {
+            outlined = true;
+            color.0 = *OUTLINE_COLOR.clone().set_a(alpha);
+        } else {
+            commands.entity(entity).despawn();
+        }
+    }
+    let Some(button) = target.filter(|_| !outlined) else {
+        return;
+    };
+    let full = Val::Percent(100.0);
+    let edge = Val::Px(OUTLINE_WIDTH);
+    let zero = Val::Px(0.0);
+    commands.entity(button).with_children(|parent| {
+        for (size, position) in [
+            (
+                Size::new(full, edge),
+                UiRect::new(zero, Val::Auto, zero, Val::Auto),
+            ),
+            (
+                Size::new(full, edge),
+                UiRect::new(zero, Val::Auto, Val::Auto, zero),
+            ),
+            (
+                Size::new(edge, full),
+                UiRect::new(zero, Val::Auto, zero, Val::Auto),
+            ),
+            (
+                Size::new(edge, full),
+                UiRect::new(Val::Auto, zero, zero, Val::Auto),
+            ),
+        ] {
+            parent
+                .spawn_bundle(NodeBundle {
+                    style: Style {
+                        size,
+                        position,
+                        position_type: PositionType::Absolute,
+                        ..default()
+                    },
+                    color: OUTLINE_COLOR.into(),
+                    focus_policy: FocusPolicy::Pass,
+                    ..default()
+                })
+                .insert(TutorialOutline { button });
+        }
+    });
+}
+
+fn skip_button_system(
+    theme: Res<Theme>,
+    mut state: ResMut<State<AppState>>,
+    mut buttons: Query<(&Interaction, &mut UiColor), (With<SkipButton>, Changed<Interaction>)>,
+) {
+    for (interaction, mut color) in &mut buttons {
+        color.0 = match interaction {
+            Interaction::Clicked => {
+                let _ = state.set(AppState::PreBattle);
+                theme.pressed_button
+            }
+            Interaction::Hovered => theme.hovered_button,
+            Interaction::None => theme.normal_button,
+        };
+    }
+}
+
+fn despawn_banner_system(mut commands: Commands, banners: Query<Entity, With<TutorialBanner>>) {
+    for banner in &banners {
+        commands.entity(banner).despawn_recursive();
+    }
+}
+
+fn complete_tutorial_system(
+    game: Res<Game>,
+    tutorial: Option<Res<Tutorial>>,
+    summary: Res<BattleSummary>,
+    mut profile: ResMut<Profile>,
+    mut errors: EventWriter<ErrorToast>,
+) {
+    if tutorial.is_some() && summary.winner == Some(game.player) && !profile.tutorial_done {
+        profile.tutorial_done = true;
+        report(&mut errors, profile.save());
+    }
+}
+
+// Whichever way the tutorial ended, the player's rules and a fighting enemy
+// come back for the next battle.
+fn leave_tutorial_system(
+    mut commands: Commands,
+    game: Res<Game>,
+    entities: &Entities,
+    tutorial: Option<ResMut<Tutorial>>,
+    mut ruleset: ResMut<Ruleset>,
+) {
+    let Some(mut tutorial) = tutorial else {
+        return;
+    };
+    *ruleset = std::mem::take(&mut tutorial.ruleset);
+    commands.remove_resource::<Tutorial>();
+    if entities.contains(game.enemy) {
+        commands.entity(game.enemy).insert(AiController::default());
+    }
+}
+
+pub struct TutorialPlugin;
+
+impl Plugin for TutorialPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(
+            SystemSet::on_enter(AppState::Battle)
+                .with_system(start_tutorial_system.after(crate::battle::reset_battle_system)),
+        )
+        .add_system_set(
+            SystemSet::on_update(AppState::Battle)
+                .with_system(advance_system.after(crate::battle::animation_system))
+                .with_system(gate_system.before(crate::battle::use_skill_system))
+                .with_system(prompt_system)
+                .with_system(outline_system)
+                .with_system(skip_button_system),
+        )
+        .add_system_set(SystemSet::on_exit(AppState::Battle).with_system(despawn_banner_system))
+        .add_system_set(
+            SystemSet::on_enter(AppState::GameOver).with_system(complete_tutorial_system),
+        )
+        .add_system_set(
+            SystemSet::on_enter(AppState::PreBattle).with_system(leave_tutorial_system),
+        );
+    }
+}
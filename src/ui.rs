@@ -1,12 +1,116 @@
-use std::ops::{Deref, DerefMut};
+use std::{
+    f32::consts::PI,
+    ops::{Deref, DerefMut},
+};
 
-use bevy::{prelude::*, ui::FocusPolicy};
+use bevy::{
+    prelude::*,
+    render::render_resource::{Extent3d, TextureDimension, TextureFormat},
+    ui::FocusPolicy,
+    window::WindowResized,
+};
 
-use crate::{body::{Stats, Skill}, Game};
+use crate::{
+    affinity::affinities,
+    ammo,
+    approach::ApproachEvent,
+    auto::Autopilot,
+    balance::BalanceConfig,
+    battle::{Animation, Walking},
+    body::{stat_breakdown, Body, Limb, Material, PaletteMode, Skill, SkillId, Stats, BASE_ENERGY},
+    bounds::Stat,
+    camera::{self, MainCamera},
+    effects::{ActiveEffect, Polarity},
+    energy,
+    hitbox::BodyGeometry,
+    icons::SkillIcons,
+    interrupt::Recovery,
+    knowledge::{self, Knowledge},
+    limb_swap,
+    locale::{t, Locale},
+    locks::{self, LimbLocks},
+    mutators::Mutators,
+    periodic::PeriodicEffect,
+    pickup::PickupEvent,
+    preview::SkillPreview,
+    profile::Profile,
+    runtime::SkillRuntime,
+    shield,
+    theme::Theme,
+    timer::{BattleTimer, TimeScale},
+    tooltip_preview::{PreviewFrame, FRAME_SIZE},
+    turn::{Ruleset, Turns},
+    weather::Weather,
+    AppState, Game,
+};
 
-const NORMAL_BUTTON: Color = Color::rgb(0.75, 0.75, 0.75);
-const HOVERED_BUTTON: Color = Color::rgb(1.0, 1.0, 1.0);
-const PRESSED_BUTTON: Color = Color::rgb(1.0, 0.75, 0.75);
+pub const FONT_NORMAL: &str = "fonts/FiraMono-Medium.ttf";
+pub const FONT_BOLD: &str = "fonts/FiraSans-Bold.ttf";
+
+// Window height the HUD sizes were designed for.
+const REFERENCE_HEIGHT: f32 = 720.0;
+const SKILL_BUTTON_SIZE: f32 = 100.0;
+const ENEMY_SKILL_BUTTON_SIZE: f32 = 50.0;
+const TOOLTIP_SIZE: f32 = 200.0;
+const NAMEPLATE_WIDTH: f32 = 400.0;
+const EFFECT_ICON_SIZE: f32 = 34.0;
+const HEALTH_BAR_WIDTH: f32 = 160.0;
+const HEALTH_COLOR: Color = Color::rgb(0.3, 0.8, 0.35);
+const ENERGY_COLOR: Color = Color::rgb(0.3, 0.55, 0.95);
+const SHIELD_COLOR: Color = Color::rgb(0.55, 0.85, 1.0);
+const BUFF_COLOR: Color = Color::rgb(0.2, 0.55, 0.3);
+const DEBUFF_COLOR: Color = Color::rgb(0.6, 0.2, 0.2);
+
+#[derive(Clone, Copy, PartialEq)]
+pub struct UiScale {
+    pub setting: f32,
+    auto: f32,
+}
+
+impl Default for UiScale {
+    fn default() -> Self {
+        Self {
+            setting: 1.0,
+            auto: 1.0,
+        }
+    }
+}
+
+impl UiScale {
+    pub fn factor(&self) -> f32 {
+        self.setting * self.auto
+    }
+
+    pub fn px(&self, value: f32) -> Val {
+        Val::Px(value * self.factor())
+    }
+}
+
+// Pixel sizes of a HUD node at scale 1, reapplied whenever the scale changes.
+#[derive(Component, Default, Clone, Copy)]
+pub struct Scaled {
+    pub width: Option<f32>,
+    pub height: Option<f32>,
+    pub top: Option<f32>,
+    pub font: Option<f32>,
+}
+
+impl Scaled {
+    pub fn font(font: f32) -> Self {
+        Self {
+            font: Some(font),
+            ..default()
+        }
+    }
+
+    pub fn size(width: f32, height: f32) -> Self {
+        Self {
+            width: Some(width),
+            height: Some(height),
+            ..default()
+        }
+    }
+}
 
 pub struct Fonts {
     normal: Handle<Font>,
@@ -15,20 +119,153 @@ pub struct Fonts {
 
 impl Fonts {
     pub fn normal(&self) -> Handle<Font> {
-        return self.normal.clone();
+        self.normal.clone()
     }
     pub fn bold(&self) -> Handle<Font> {
-        return self.bold.clone();
+        self.bold.clone()
+    }
+}
+
+// A clickable button of the player's own bar, holding the skill's
+// `Skill::id` so it keeps pointing at the same skill if the body changes
+// under it.
+#[derive(Component)]
+pub struct SkillButton(pub SkillId);
+
+const HOTKEYS: [KeyCode; 9] = [
+    KeyCode::Key1,
+    KeyCode::Key2,
+    KeyCode::Key3,
+    KeyCode::Key4,
+    KeyCode::Key5,
+    KeyCode::Key6,
+    KeyCode::Key7,
+    KeyCode::Key8,
+    KeyCode::Key9,
+];
+
+// Indices into `skills` in the order the bar shows them. Skills in
+// `preferred` keep their saved order, the rest go to the end of their
+// category, and ids that aren't on the body anymore are skipped.
+pub fn bar_order(skills: &[Skill], preferred: &[SkillId]) -> Vec<usize> {
+    let ids = skills.iter().map(Skill::id).collect::<Vec<_>>();
+    let mut order = Vec::with_capacity(skills.len());
+    for id in preferred {
+        if let Some(i) = ids.iter().position(|other| other == id) {
+            if !order.contains(&i) {
+                order.push(i);
+            }
+        }
+    }
+    for (i, skill) in skills.iter().enumerate() {
+        if order.contains(&i) {
+            continue;
+        }
+        let category = skill.order();
+        let at = order
+            .iter()
+            .rposition(|j| skills[*j].order() <= category)
+            .map_or(0, |last| last + 1);
+        order.insert(at, i);
     }
+    order
+}
+
+// Every button of every skill bar, whoever owns it.
+#[derive(Component)]
+pub struct SkillSlot {
+    pub owner: Entity,
+    pub skill: SkillId,
+}
+
+#[derive(Component)]
+struct TurnBanner;
+
+#[derive(Component)]
+struct TimerText;
+
+#[derive(Component)]
+struct SpeedButton;
+
+// Floats above the player's body, or the enemy's if false.
+#[derive(Component)]
+struct Nameplate {
+    player: bool,
+}
+
+#[derive(Component)]
+struct HealthFill {
+    player: bool,
+}
+
+#[derive(Component)]
+struct HealthPreview {
+    player: bool,
+}
+
+// The overshield, a segment right after the health fill.
+#[derive(Component)]
+struct ShieldFill {
+    player: bool,
+}
+
+#[derive(Component)]
+struct EnergyFill {
+    player: bool,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum BarStat {
+    Health,
+    Energy,
+}
+
+// One of the player's bars, hovering it explains the numbers behind it.
+#[derive(Component)]
+struct StatBar(BarStat);
+
+// Part of the enemy's name plate that stays hidden until the player's scans
+// reach this tier.
+#[derive(Component)]
+struct Fogged(u8);
+
+// The enemy's health and speed as numbers, once scanned.
+#[derive(Component)]
+struct EnemyReadout;
+
+// Follows the cursor so a hovered part's tooltip has somewhere to sit.
+#[derive(Component)]
+struct PartHover;
+
+// Row of effect icons under a name plate.
+#[derive(Component)]
+struct EffectTray {
+    player: bool,
+}
+
+// Shots left on a ranged skill's button.
+#[derive(Component)]
+struct AmmoBadge {
+    owner: Entity,
+    skill: usize,
+    empty: bool,
 }
 
 #[derive(Component)]
-struct SkillButton(usize);
+struct EffectIcon {
+    id: &'static str,
+    stacks: u32,
+    countdown: Entity,
+    name: String,
+    description: String,
+}
 
 struct Hovered {
     entity: Entity,
     header: String,
     description: String,
+    // An ability's body and id, see `tooltip_preview`.
+    preview: Option<(Entity, SkillId)>,
 }
 
 struct Tooltip {
@@ -37,10 +274,17 @@ struct Tooltip {
 }
 
 #[derive(Default)]
-pub struct UseSkill(Option<usize>);
+// The `Skill::id` the player picked, waiting for use_skill_system.
+pub struct UseSkill(Option<SkillId>);
+
+impl UseSkill {
+    pub fn picked(&self, skill: &SkillId) -> bool {
+        self.0.as_ref() == Some(skill)
+    }
+}
 
 impl Deref for UseSkill {
-    type Target = Option<usize>;
+    type Target = Option<SkillId>;
 
     fn deref(&self) -> &Self::Target {
         &self.0
@@ -53,67 +297,531 @@ impl DerefMut for UseSkill {
     }
 }
 
+// What the movement skills' numbers are derived from besides the body.
+struct MovementRules<'a> {
+    balance: &'a BalanceConfig,
+    weather: Weather,
+    auto_face: bool,
+}
+
+impl<'a> MovementRules<'a> {
+    fn new(balance: &'a BalanceConfig, weather: &Weather, ruleset: &Ruleset) -> Self {
+        Self {
+            balance,
+            weather: *weather,
+            auto_face: ruleset.auto_face,
+        }
+    }
+
+    // The same numbers the battle moves the body by, see `walk_destination`.
+    // The speed already has the weather and everything else on it.
+    fn describe(&self, skill: &Skill, stats: &Stats) -> Option<String> {
+        let balance = self.balance;
+        let mut lines = Vec::new();
+        match skill {
+            Skill::WalkForward | Skill::WalkBackward | Skill::Dash(_) => {
+                let distance = balance.walk_distance(stats, skill)?.abs();
+                lines.push(t!(
+                    "tooltip.walk",
+                    distance = distance,
+                    speed = stats.speed * balance.walk_speed_multiplier,
+                    time = balance.skill_time()
+                ));
+                lines.extend(
+                    stats
+                        .capped
+                        .iter()
+                        .filter(|capped| capped.stat == Stat::Speed)
+                        .map(|capped| capped.describe(stats)),
+                );
+                if matches!(skill, Skill::WalkBackward) {
+                    lines.push(t!(
+                        "tooltip.walk_backward",
+                        multiplier = balance.walk_backward
+                    ));
+                }
+                // Only an approach loops a walk and pays for it.
+                if let Skill::Dash(ability) = skill {
+                    lines.push(t!("tooltip.dash_energy", energy = ability.energy_cost));
+                } else if matches!(skill, Skill::WalkForward) && balance.approach_energy > 0.0 {
+                    lines.push(t!(
+                        "tooltip.walk_energy",
+                        energy = distance * balance.approach_energy
+                    ));
+                } else {
+                    lines.push(t!("tooltip.walk_free"));
+                }
+                let ground = self.weather.walk_multiplier();
+                if ground != 1.0 {
+                    lines.push(t!(
+                        "tooltip.walk_weather",
+                        weather = self.weather.name(),
+                        multiplier = ground
+                    ));
+                }
+            }
+            Skill::TurnAround => {
+                lines.push(t!("tooltip.turn_around", time = balance.skill_time()));
+                lines.push(if self.auto_face {
+                    t!("tooltip.auto_face_on", time = balance.quick_turn)
+                } else {
+                    t!("tooltip.auto_face_off")
+                });
+            }
+            _ => return None,
+        }
+        Some(lines.join("\n"))
+    }
+}
+
+// Uses the stats of whichever body owns the skill, `owner`. The limb's material is
+// part knowledge and a movement skill's numbers come with the body's,
+// `known` is the player's scan tier for that body.
+fn skill_tooltip(
+    entity: Entity,
+    owner: Entity,
+    skill: &Skill,
+    (body, stats, runtime): (&Body, &Stats, Option<&SkillRuntime>),
+    known: u8,
+    rules: &MovementRules,
+) -> Hovered {
+    let mut description = match skill {
+        Skill::Reload => t!("tooltip.reload", time = ammo::RELOAD_TIME),
+        Skill::SwapLimb => t!("tooltip.swap_limb", time = limb_swap::SWAP_LIMB_TIME),
+        _ if skill.walks() || matches!(skill, Skill::TurnAround) => Some(skill)
+            .filter(|_| known >= knowledge::NUMBERS)
+            .and_then(|skill| rules.describe(skill, stats))
+            .unwrap_or_else(|| t!("tooltip.movement")),
+        _ => skill
+            .ability()
+            .and_then(|ability| {
+                let material = body.limb_material(ability.limb)?;
+                Some(if known < knowledge::PARTS {
+                    t!(
+                        "tooltip.ability_unscanned",
+                        energy = ability.energy_cost,
+                        time = ability.time,
+                        cooldown = ability.cooldown,
+                    )
+                } else {
+                    t!(
+                        "tooltip.ability",
+                        energy = ability.energy_cost,
+                        time = ability.time,
+                        cooldown = ability.cooldown,
+                        material = material.name(),
+                        pattern = material.pattern(),
+                    )
+                })
+            })
+            .unwrap_or_else(|| t!("tooltip.movement")),
+    };
+    if skill.ability().is_some_and(|ability| ability.emp) {
+        description += &t!("tooltip.emp");
+    }
+    if let (Some(capacity), Some(runtime)) =
+        (skill.ability().and_then(|ability| ability.ammo), runtime)
+    {
+        description += &t!(
+            "tooltip.ammo",
+            remaining = runtime.remaining(skill).unwrap_or(capacity),
+            capacity = capacity
+        );
+    }
+    // On a line of its own, apart from what the arm has by itself.
+    let attachment = match skill.ability().map(|ability| ability.limb) {
+        Some(Limb::Arm(slot)) => body.arm(slot).and_then(|arm| arm.meta().attachment()),
+        _ => None,
+    };
+    if let Some(attachment) = attachment {
+        description += "\n";
+        description += &t!(
+            "tooltip.attachment",
+            attachment = attachment.name(),
+            effect = attachment.describe()
+        );
+    }
+    Hovered {
+        entity,
+        header: skill.get_name(),
+        description,
+        preview: skill.ability().map(|_| (owner, skill.id())),
+    }
+}
+
+// Seconds until a stat at `current` going at `rate` a second is full or empty,
+// and whether it's filling. None while it holds steady.
+fn time_to_settle(current: f32, max: f32, rate: f32) -> Option<(bool, f32)> {
+    if rate > 0.0 && current < max {
+        Some((true, (max - current) / rate))
+    } else if rate < 0.0 && current > 0.0 {
+        Some((false, current / -rate))
+    } else {
+        None
+    }
+}
+
+// The rates are the ones regen_system and the periodic effects apply, so the
+// timings match what the bar does.
+fn bar_tooltip(
+    entity: Entity,
+    stat: BarStat,
+    balance: &BalanceConfig,
+    mutators: Mutators,
+    body: &Body,
+    stats: &Stats,
+    effects: &[&PeriodicEffect],
+) -> Hovered {
+    let (header, current, max) = match stat {
+        BarStat::Health => (t!("bar.health"), stats.health, stats.max_health),
+        BarStat::Energy => (t!("bar.energy"), stats.energy, stats.max_energy),
+    };
+    let mut lines = vec![t!("bar.current", current = current, max = max)];
+    if stat == BarStat::Health && stats.max_overshield > 0.0 {
+        lines.push(t!(
+            "bar.overshield",
+            current = stats.overshield,
+            max = stats.max_overshield,
+            rate = shield::recharge_per_second(balance, stats),
+            delay = balance.overshield_delay,
+        ));
+    }
+    if stat == BarStat::Energy {
+        lines.push(t!("bar.part", part = t!("bar.base"), value = BASE_ENERGY));
+    }
+    for part in stat_breakdown(body) {
+        let value = match stat {
+            BarStat::Health => part.health,
+            BarStat::Energy => part.energy,
+        };
+        lines.push(t!("bar.part", part = part.part, value = value));
+    }
+    let bounded = match stat {
+        BarStat::Health => Stat::MaxHealth,
+        BarStat::Energy => Stat::MaxEnergy,
+    };
+    lines.extend(
+        stats
+            .capped
+            .iter()
+            .filter(|capped| capped.stat == bounded)
+            .map(|capped| capped.describe(stats)),
+    );
+    let mut rates = Vec::new();
+    if stat == BarStat::Energy {
+        // A Wood set's share of the regen is listed on its own.
+        let regen = energy::regen_per_second(balance, mutators, stats);
+        let bonus = regen * stats.regen_bonus / (1.0 + stats.regen_bonus);
+        rates.push((t!("bar.regen"), regen - bonus));
+        if let Some(set) = affinities(body)
+            .into_iter()
+            .find(|affinity| affinity.material == Material::Wood && affinity.tier().is_some())
+        {
+            rates.push((set.name(), bonus));
+        }
+    }
+    for effect in effects {
+        let (health, energy) = effect.per_second();
+        let rate = match stat {
+            BarStat::Health => health,
+            BarStat::Energy => energy,
+        };
+        if rate != 0.0 {
+            rates.push((t!(format!("effect.{}.name", effect.id)), rate));
+        }
+    }
+    let total = rates.iter().map(|(_, rate)| rate).sum::<f32>();
+    lines.push(t!("bar.rate", rate = total));
+    for (source, rate) in rates {
+        lines.push(t!("bar.source", source = source, rate = rate));
+    }
+    lines.push(match time_to_settle(current, max, total) {
+        Some((true, seconds)) => t!("bar.full_in", seconds = seconds),
+        Some((false, seconds)) => t!("bar.empty_in", seconds = seconds),
+        None => t!("bar.steady"),
+    });
+    Hovered {
+        entity,
+        header,
+        description: lines.join("\n"),
+        preview: None,
+    }
+}
+
+// What the player's body is up to, for the buttons to follow.
+type PlayerState<'a> = (
+    &'a Stats,
+    &'a Body,
+    Option<&'a SkillRuntime>,
+    Option<&'a Animation>,
+    Option<&'a Walking>,
+    Option<&'a LimbLocks>,
+);
+
+// A pick goes through while nothing else is picked, or when it can play
+// alongside what the body is already doing, like a jab on the move. Under
+// auto-battle a click also takes the place of the AI's pick.
+fn accepts(
+    use_skill: &UseSkill,
+    autopilot: Option<&Autopilot>,
+    (stats, body, _, animation, walking, locks): PlayerState,
+    skill: &Skill,
+) -> bool {
+    use_skill.is_none()
+        || autopilot.is_some_and(|autopilot| autopilot.queued(use_skill))
+        || locks.is_some_and(|locks| {
+            locks::track_for(skill, body, stats, locks, animation, walking).is_some()
+        })
+}
+
 fn button_system(
+    theme: Res<Theme>,
     mut interaction_query: Query<
         (Entity, &Interaction, &mut UiColor, &SkillButton),
         Changed<Interaction>,
     >,
     mut use_skill: ResMut<UseSkill>,
     mut tooltip: ResMut<Tooltip>,
-    stats_query: Query<&Stats>,
+    player: Query<PlayerState>,
     game: Res<Game>,
+    turns: Option<Res<Turns>>,
+    balance: Res<BalanceConfig>,
+    weather: Res<Weather>,
+    ruleset: Res<Ruleset>,
+    autopilots: Query<&Autopilot>,
 ) {
-    if use_skill.is_none() {
-        for (entity, interaction, mut color, skill_button) in &mut interaction_query {
-            match *interaction {
-                Interaction::Clicked => {
-                    **use_skill = Some(skill_button.0);
-                    *color = PRESSED_BUTTON.into();
-                }
-                Interaction::Hovered => {
-                    if let Some(skill) = stats_query
-                        .get(game.player)
-                        .ok()
-                        .and_then(|stats| stats.skills.get(skill_button.0))
-                    {
-                        tooltip.currently_hovering = Some(Hovered {
+    let may_act = turns.is_none_or(|turns| turns.can_act(game.player));
+    if !may_act {
+        return;
+    }
+    let autopilot = autopilots.get(game.player).ok();
+    let idle = use_skill.is_none();
+    for (entity, interaction, mut color, skill_button) in &mut interaction_query {
+        match *interaction {
+            Interaction::Clicked => {
+                let Ok(state) = player.get(game.player) else {
+                    continue;
+                };
+                // An empty arm stays greyed out, see ammo_badge_system.
+                let Some(skill) = state
+                    .0
+                    .skill(&skill_button.0)
+                    .filter(|skill| ammo::loaded(state.2, skill))
+                    .filter(|skill| accepts(&use_skill, autopilot, state, skill))
+                else {
+                    continue;
+                };
+                **use_skill = Some(skill.id());
+                *color = theme.pressed_button.into();
+            }
+            // Hovering is left alone while the body is busy, the colors say
+            // what can still be picked.
+            _ if !idle => {}
+            Interaction::Hovered => {
+                if let Ok((stats, body, runtime, ..)) = player.get(game.player) {
+                    if let Some(skill) = stats.skill(&skill_button.0) {
+                        tooltip.currently_hovering = Some(skill_tooltip(
                             entity,
-                            header: skill.get_name().to_string(),
-                            description: "Cool Foo You Got There lmaoaoaoaoao".to_string(),
-                        });
+                            game.player,
+                            skill,
+                            (body, stats, runtime),
+                            knowledge::PARTS,
+                            &MovementRules::new(&balance, &weather, &ruleset),
+                        ));
                     }
-                    *color = HOVERED_BUTTON.into();
                 }
-                Interaction::None => {
-                    if tooltip
-                        .currently_hovering
-                        .as_ref()
-                        .map_or(false, |e| e.entity == entity)
-                    {
-                        tooltip.currently_hovering = None;
-                    }
-                    *color = NORMAL_BUTTON.into();
+                *color = theme.hovered_button.into();
+            }
+            Interaction::None => {
+                if tooltip
+                    .currently_hovering
+                    .as_ref()
+                    .is_some_and(|e| e.entity == entity)
+                {
+                    tooltip.currently_hovering = None;
                 }
+                *color = theme.normal_button.into();
             }
         }
     }
 }
 
-fn button_disable_system(mut interaction_query: Query<(&mut UiColor, &SkillButton)>, use_skill: Res<UseSkill>, mut tooltip: ResMut<Tooltip>) {
-    if use_skill.is_changed() {
-        if let Some(skill) = **use_skill {
+// Right-clicking the enemy walks up to it, again stops the walk.
+fn approach_click_system(
+    game: Res<Game>,
+    mouse: Res<Input<MouseButton>>,
+    windows: Res<Windows>,
+    cameras: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    geometry: BodyGeometry,
+    interactions: Query<&Interaction>,
+    mut approach: EventWriter<ApproachEvent>,
+) {
+    if !mouse.just_pressed(MouseButton::Right) || game.targets_self() {
+        return;
+    }
+    let over_ui = interactions
+        .iter()
+        .any(|interaction| *interaction != Interaction::None);
+    let on_enemy = (!over_ui)
+        .then(|| {
+            let (camera, transform) = cameras.get_single().ok()?;
+            let point = camera::cursor_world(camera, transform, windows.get_primary()?)?;
+            geometry.body_hit_test(game.enemy, point)
+        })
+        .flatten()
+        .is_some();
+    if on_enemy {
+        approach.send(ApproachEvent(game.player));
+    }
+}
+
+// Number keys press the bar's buttons from the left.
+fn hotkey_system(
+    keys: Res<Input<KeyCode>>,
+    mut use_skill: ResMut<UseSkill>,
+    profile: Res<Profile>,
+    player: Query<PlayerState>,
+    game: Res<Game>,
+    turns: Option<Res<Turns>>,
+    autopilots: Query<&Autopilot>,
+) {
+    let may_act = turns.is_none_or(|turns| turns.can_act(game.player));
+    let autopilot = autopilots.get(game.player).ok();
+    let Some(slot) = HOTKEYS.iter().position(|key| keys.just_pressed(*key)) else {
+        return;
+    };
+    let Ok(state) = player.get(game.player) else {
+        return;
+    };
+    let stats = state.0;
+    if let Some(i) = bar_order(&stats.skills, &profile.skill_order).get(slot) {
+        if may_act && accepts(&use_skill, autopilot, state, &stats.skills[*i]) {
+            **use_skill = Some(stats.skills[*i].id());
+        }
+    }
+}
+
+// The skill Left and Right last moved, and the one that took its slot under
+// the cursor. The bar rebuilds after every move, so while the cursor stays
+// on that button the same skill keeps going.
+#[derive(Default)]
+struct Moving {
+    skill: Option<SkillId>,
+    under_cursor: Option<SkillId>,
+}
+
+// The saved order after moving the skill picked from `hovered` by `step`,
+// None when it's already at that end. The whole order is saved, so skills
+// that are gone by then drop out of it.
+fn reorder(
+    skills: &[Skill],
+    saved: &[SkillId],
+    moving: &mut Moving,
+    hovered: &SkillId,
+    step: isize,
+) -> Option<Vec<SkillId>> {
+    let skill = match (&moving.skill, &moving.under_cursor) {
+        (Some(skill), Some(under)) if under == hovered => skill.clone(),
+        _ => hovered.clone(),
+    };
+    let mut order = bar_order(skills, saved);
+    let slot = |id: &SkillId| order.iter().position(|i| skills[*i].id() == *id);
+    let (from, cursor) = (slot(&skill)?, slot(hovered)?);
+    let to = from as isize + step;
+    if to < 0 || to as usize >= order.len() {
+        return None;
+    }
+    order.swap(from, to as usize);
+    *moving = Moving {
+        under_cursor: Some(skills[order[cursor]].id()),
+        skill: Some(skill),
+    };
+    Some(order.into_iter().map(|i| skills[i].id()).collect())
+}
+
+// Left and right move the hovered button along the bar.
+fn reorder_system(
+    keys: Res<Input<KeyCode>>,
+    mut profile: ResMut<Profile>,
+    mut moving: Local<Moving>,
+    stats: Query<&Stats>,
+    game: Res<Game>,
+    buttons: Query<(&Interaction, &SkillButton)>,
+) {
+    let step = match (
+        keys.just_pressed(KeyCode::Left),
+        keys.just_pressed(KeyCode::Right),
+    ) {
+        (true, false) => -1,
+        (false, true) => 1,
+        _ => return,
+    };
+    let Some((_, hovered)) = buttons
+        .iter()
+        .find(|(interaction, _)| **interaction == Interaction::Hovered)
+    else {
+        return;
+    };
+    let Ok(stats) = stats.get(game.player) else {
+        return;
+    };
+    if let Some(order) = reorder(
+        &stats.skills,
+        &profile.skill_order,
+        &mut moving,
+        &hovered.0,
+        step,
+    ) {
+        profile.skill_order = order;
+    }
+}
+
+fn button_disable_system(
+    theme: Res<Theme>,
+    mut interaction_query: Query<(&mut UiColor, &SkillButton)>,
+    use_skill: Res<UseSkill>,
+    mut tooltip: ResMut<Tooltip>,
+    game: Res<Game>,
+    turns: Option<Res<Turns>>,
+    player: Query<PlayerState>,
+    relocked: Query<(), Changed<LimbLocks>>,
+    autopilots: Query<&Autopilot>,
+    handed_over: Query<(), Added<Autopilot>>,
+    taken_back: RemovedComponents<Autopilot>,
+) {
+    let turn_changed = turns.as_ref().is_some_and(|turns| turns.is_changed());
+    let switched =
+        handed_over.contains(game.player) || taken_back.iter().any(|entity| entity == game.player);
+    if use_skill.is_changed() || turn_changed || relocked.contains(game.player) || switched {
+        let may_act = turns.is_none_or(|turns| turns.can_act(game.player));
+        if use_skill.is_changed() && use_skill.is_some() {
+            tooltip.currently_hovering = None;
+        }
+        let Ok(state) = player.get(game.player) else {
+            return;
+        };
+        let (stats, _, _, animation, walking, _) = state;
+        let autopilot = autopilots.get(game.player).ok();
+        let ai_pick = autopilot.is_some_and(|autopilot| autopilot.queued(&use_skill));
+        for (mut color, skill_btn) in interaction_query.iter_mut() {
+            let Some(skill) = stats.skill(&skill_btn.0) else {
+                continue;
+            };
+            let id = skill.id();
+            color.0 = if !may_act {
+                theme.disabled_button
+            } else if ai_pick && use_skill.picked(&id) {
+                theme.auto_button
+            } else if use_skill.picked(&id) || locks::playing(&id, animation, walking) {
+                theme.pressed_button
+            } else if accepts(&use_skill, autopilot, state, skill) {
+                theme.normal_button
+            } else {
+                theme.disabled_button
+            };
+        }
+        if !may_act {
             tooltip.currently_hovering = None;
-            for (mut color, skill_btn) in interaction_query.iter_mut() {
-                if skill == skill_btn.0 {
-                    color.0 = PRESSED_BUTTON;
-                } else {
-                    color.0 = Color::rgb(0.1, 0.1, 0.1);
-                }
-            }
-        } else {
-            for (mut color, _) in interaction_query.iter_mut() {
-                color.0 = NORMAL_BUTTON;
-            }
         }
     }
 }
@@ -121,24 +829,31 @@ fn button_disable_system(mut interaction_query: Query<(&mut UiColor, &SkillButto
 fn tooltip_system(
     mut commands: Commands,
     mut tooltip: ResMut<Tooltip>,
+    theme: Res<Theme>,
     transforms: Query<(&GlobalTransform, &Node)>,
     mut styles: Query<&mut Style>,
     fonts: Res<Fonts>,
+    scale: Res<UiScale>,
+    windows: Res<Windows>,
 ) {
-    if tooltip.is_changed() {
+    if tooltip.is_changed() || scale.is_changed() || theme.is_changed() {
         let e = tooltip.entity;
         let mut commands = commands.entity(e);
         commands.despawn_descendants();
         if let Some(ref mut hovered) = tooltip.currently_hovering {
-            if let (Ok(mut a), Ok([(_, a_n), (b, b_n)])) =
-                (styles.get_mut(e), transforms.get_many([e, hovered.entity]))
-            {
-                a.position = UiRect::new(
-                    Val::Px(b.translation().x - a_n.size.x / 2.0),
-                    Val::Auto,
-                    Val::Auto,
-                    Val::Px(b.translation().y + b_n.size.y / 2.0),
-                );
+            if let (Ok(mut a), Ok((b, b_n))) = (styles.get_mut(e), transforms.get(hovered.entity)) {
+                // Keep the whole tooltip inside the window.
+                let size = TOOLTIP_SIZE * scale.factor();
+                let (width, height) = windows
+                    .get_primary()
+                    .map_or((f32::MAX, f32::MAX), |window| {
+                        (window.width(), window.height())
+                    });
+                let left = (b.translation().x - size / 2.0).clamp(0.0, (width - size).max(0.0));
+                let bottom =
+                    (b.translation().y + b_n.size.y / 2.0).clamp(0.0, (height - size).max(0.0));
+                a.size = Size::new(Val::Px(size), Val::Px(size));
+                a.position = UiRect::new(Val::Px(left), Val::Auto, Val::Auto, Val::Px(bottom));
             }
 
             commands.add_children(|commands| {
@@ -148,7 +863,7 @@ fn tooltip_system(
                             size: Size::new(Val::Percent(100.0), Val::Undefined),
                             ..default()
                         },
-                        color: Color::rgba_u8(65, 70, 72, 120).into(),
+                        color: theme.tooltip.into(),
                         focus_policy: FocusPolicy::Pass,
                         ..default()
                     })
@@ -191,115 +906,1550 @@ fn tooltip_system(
                                 &hovered.header,
                                 TextStyle {
                                     font: fonts.bold(),
-                                    font_size: 32.0,
-                                    color: Color::WHITE,
+                                    font_size: 32.0 * scale.factor(),
+                                    color: theme.tooltip_header,
                                 },
                             ),
                             TextSection::new(
                                 formatted_string,
                                 TextStyle {
                                     font: fonts.normal(),
-                                    font_size: 12.0,
-                                    color: Color::WHITE,
+                                    font_size: 12.0 * scale.factor(),
+                                    color: theme.text,
                                 },
                             ),
                         ]));
                     });
+                // Sits on top of the tooltip.
+                if let Some((owner, skill)) = &hovered.preview {
+                    let size = FRAME_SIZE * scale.factor();
+                    commands
+                        .spawn_bundle(ImageBundle {
+                            style: Style {
+                                position_type: PositionType::Absolute,
+                                position: UiRect {
+                                    left: Val::Px(0.0),
+                                    bottom: Val::Percent(100.0),
+                                    ..default()
+                                },
+                                size: Size::new(Val::Px(size), Val::Px(size)),
+                                ..default()
+                            },
+                            focus_policy: FocusPolicy::Pass,
+                            visibility: Visibility { is_visible: false },
+                            ..default()
+                        })
+                        .insert(PreviewFrame::new(*owner, skill.clone()));
+                }
             });
         }
     }
 }
 
 fn update_ui_system(
+    theme: Res<Theme>,
     mut commands: Commands,
     game: Res<Game>,
-    player: Query<&Stats, Changed<Stats>>,
+    bodies: Query<(&Stats, &Body, Option<&Knowledge>)>,
+    changed: Query<(), Or<(Changed<Stats>, Changed<Knowledge>)>>,
     asset_server: Res<AssetServer>,
-    existing: Local<Option<Entity>>,
+    fonts: Res<Fonts>,
+    scale: Res<UiScale>,
+    palette: Res<PaletteMode>,
+    locale: Res<Locale>,
+    windows: Res<Windows>,
+    profile: Res<Profile>,
+    mut images: ResMut<Assets<Image>>,
+    mut icons: ResMut<SkillIcons>,
+    mut existing: Local<[Option<Entity>; 2]>,
+    mut shown_order: Local<Vec<SkillId>>,
 ) {
-    if let Ok(stats) = player.get(game.player) {
-        if let Some(existing) = *existing {
-            commands.entity(existing).despawn();
+    let restyled = scale.is_changed() || palette.is_changed() || locale.is_changed();
+    let reordered = *shown_order != profile.skill_order;
+    if reordered {
+        *shown_order = profile.skill_order.clone();
+    }
+    for (bar, owner) in [game.player, game.enemy].into_iter().enumerate() {
+        let interactive = owner == game.player;
+        // A scan changes the player's Knowledge, not the enemy.
+        let rebuild = changed.contains(owner)
+            || restyled
+            || if interactive {
+                reordered
+            } else {
+                changed.contains(game.player)
+            };
+        if !rebuild {
+            continue;
         }
-        commands
-            .spawn_bundle(NodeBundle {
-                style: Style {
-                    size: Size::new(Val::Percent(100.0), Val::Percent(20.0)),
+        let Ok((stats, body, _)) = bodies.get(owner) else {
+            continue;
+        };
+        if let Some(existing) = existing[bar].take() {
+            commands.entity(existing).despawn_recursive();
+        }
+        let learned = bodies
+            .get(game.player)
+            .ok()
+            .and_then(|(.., knowledge)| knowledge);
+        if knowledge::known(&game, learned, owner) < knowledge::SKILLS {
+            continue;
+        }
+        // Shrink the buttons when they wouldn't fit side by side.
+        let width = windows
+            .get_primary()
+            .map_or(f32::MAX, |window| window.width());
+        let (size, room, root_style) = if interactive {
+            (
+                SKILL_BUTTON_SIZE,
+                0.95,
+                Style {
                     justify_content: JustifyContent::SpaceAround,
                     align_items: AlignItems::FlexEnd,
                     ..default()
                 },
+            )
+        } else {
+            (
+                ENEMY_SKILL_BUTTON_SIZE,
+                0.4,
+                Style {
+                    position_type: PositionType::Absolute,
+                    position: UiRect {
+                        right: Val::Px(10.0),
+                        top: Val::Px(10.0),
+                        ..default()
+                    },
+                    ..default()
+                },
+            )
+        };
+        let button_size =
+            (size * scale.factor()).min(width * room / stats.skills.len().max(1) as f32);
+        let root = commands
+            .spawn_bundle(NodeBundle {
+                style: Style {
+                    size: Size::new(
+                        if interactive {
+                            Val::Percent(100.0)
+                        } else {
+                            Val::Auto
+                        },
+                        Val::Px(button_size + 10.0 * scale.factor()),
+                    ),
+                    ..root_style
+                },
                 color: Color::NONE.into(),
                 ..default()
             })
             .with_children(|parent| {
-                for (i, skill) in stats.skills.iter().enumerate() {
-                    let image = match skill {
-                        Skill::WalkBackward => "textures/arrow_left.png",
-                        Skill::WalkForward => "textures/arrow_right.png",
-                        Skill::TurnAround => "textures/round_arrow.png",
-                        Skill::BasicMelee(_) => "textures/fist.png",
-                        Skill::BasicRanged(_) => todo!(),
-                        Skill::Scan(_) => todo!(),
-                    };
-                    parent
-                        .spawn_bundle(ButtonBundle {
-                            style: Style {
-                                size: Size::new(Val::Px(100.0), Val::Px(100.0)),
-                                // horizontally center child text
-                                justify_content: JustifyContent::Center,
-                                // vertically center child text
-                                align_items: AlignItems::Center,
-                                ..default()
-                            },
-                            image: UiImage(asset_server.load(image)),
-                            color: NORMAL_BUTTON.into(),
+                let order = if interactive {
+                    bar_order(&stats.skills, &profile.skill_order)
+                } else {
+                    (0..stats.skills.len()).collect()
+                };
+                for i in order {
+                    let skill = &stats.skills[i];
+                    let limb = skill.ability().map(|ability| ability.limb);
+                    let material = limb
+                        .and_then(|limb| body.limb_material(limb))
+                        .unwrap_or(Material::Rust);
+                    let image = icons.get(&mut images, &asset_server, skill, material, *palette);
+                    let mut button = parent.spawn_bundle(ButtonBundle {
+                        style: Style {
+                            size: Size::new(Val::Px(button_size), Val::Px(button_size)),
+                            margin: UiRect::all(Val::Px(if interactive { 0.0 } else { 2.0 })),
+                            // horizontally center child text
+                            justify_content: JustifyContent::Center,
+                            // vertically center child text
+                            align_items: AlignItems::Center,
                             ..default()
-                        })
-                        .insert(SkillButton(i));
+                        },
+                        image: UiImage(image),
+                        color: theme.normal_button.into(),
+                        ..default()
+                    });
+                    button.insert(SkillSlot {
+                        owner,
+                        skill: skill.id(),
+                    });
+                    // Only the player's own buttons can queue a skill.
+                    if interactive {
+                        button.insert(SkillButton(skill.id()));
+                    }
+                    button.with_children(|parent| {
+                        if skill
+                            .ability()
+                            .is_some_and(|ability| ability.ammo.is_some())
+                        {
+                            parent
+                                .spawn_bundle(
+                                    TextBundle::from_section(
+                                        "",
+                                        TextStyle {
+                                            font: fonts.bold(),
+                                            font_size: 0.2 * button_size,
+                                            color: Color::WHITE,
+                                        },
+                                    )
+                                    .with_style(Style {
+                                        position_type: PositionType::Absolute,
+                                        position: UiRect {
+                                            right: Val::Px(6.0),
+                                            top: Val::Px(4.0),
+                                            ..default()
+                                        },
+                                        ..default()
+                                    }),
+                                )
+                                .insert(AmmoBadge {
+                                    owner,
+                                    skill: i,
+                                    empty: false,
+                                });
+                        }
+                        if let Some(limb) = limb {
+                            parent.spawn_bundle(
+                                TextBundle::from_section(
+                                    limb.badge(),
+                                    TextStyle {
+                                        font: fonts.bold(),
+                                        font_size: 0.2 * button_size,
+                                        color: Color::WHITE,
+                                    },
+                                )
+                                .with_style(Style {
+                                    position_type: PositionType::Absolute,
+                                    position: UiRect {
+                                        left: Val::Px(6.0),
+                                        top: Val::Px(4.0),
+                                        ..default()
+                                    },
+                                    ..default()
+                                }),
+                            );
+                        }
+                    });
                 }
-            });
+            })
+            .id();
+        existing[bar] = Some(root);
     }
 }
 
-fn ui_startup_system(mut commands: Commands, asset_server: Res<AssetServer>) {
-    commands.insert_resource(Fonts {
-        normal: asset_server.load("fonts/FiraMono-Medium.ttf"),
-        bold: asset_server.load("fonts/FiraSans-Bold.ttf"),
-    });
-
-    let hover = commands
-        .spawn_bundle(NodeBundle {
-            style: Style {
-                size: Size::new(Val::Px(200.0), Val::Px(200.0)),
-                position_type: PositionType::Absolute,
-                ..default()
-            },
-            color: Color::NONE.into(),
-            focus_policy: FocusPolicy::Pass,
-            ..default()
-        })
-        .id();
+// The read-only bars light up the skill their body is using and grey out
+// while it can't start another.
+fn read_only_bar_system(
+    theme: Res<Theme>,
+    game: Res<Game>,
+    balance: Res<BalanceConfig>,
+    weather: Res<Weather>,
+    ruleset: Res<Ruleset>,
+    mut tooltip: ResMut<Tooltip>,
+    bodies: Query<(
+        &Stats,
+        &Body,
+        Option<&SkillRuntime>,
+        Option<&Animation>,
+        Option<&Recovery>,
+        Option<&Knowledge>,
+    )>,
+    changed: Query<(), Changed<Interaction>>,
+    mut slots: Query<(Entity, &SkillSlot, &Interaction, &mut UiColor), Without<SkillButton>>,
+) {
+    for (entity, slot, interaction, mut color) in &mut slots {
+        let Ok((stats, body, runtime, animation, recovery, _)) = bodies.get(slot.owner) else {
+            continue;
+        };
+        if changed.contains(entity) {
+            let hovering_this = tooltip
+                .currently_hovering
+                .as_ref()
+                .is_some_and(|hovered| hovered.entity == entity);
+            match (interaction, stats.skill(&slot.skill)) {
+                (Interaction::None, _) if hovering_this => tooltip.currently_hovering = None,
+                (Interaction::Hovered | Interaction::Clicked, Some(skill)) => {
+                    let learned = bodies
+                        .get(game.player)
+                        .ok()
+                        .and_then(|(.., knowledge)| knowledge);
+                    let known = knowledge::known(&game, learned, slot.owner);
+                    tooltip.currently_hovering = Some(skill_tooltip(
+                        entity,
+                        slot.owner,
+                        skill,
+                        (body, stats, runtime),
+                        known,
+                        &MovementRules::new(&balance, &weather, &ruleset),
+                    ));
+                }
+                _ => {}
+            }
+        }
+        let next = match animation {
+            _ if *interaction != Interaction::None => theme.hovered_button,
+            Some(animation) if animation.skill == slot.skill => theme.pressed_button,
+            Some(_) => theme.disabled_button,
+            None if recovery.is_some() => theme.disabled_button,
+            None => theme.normal_button,
+        };
+        if color.0 != next {
+            color.0 = next;
+        }
+    }
+}
 
-    commands.insert_resource(Tooltip {
-        entity: hover,
-        currently_hovering: None,
-    });
+// Keeps the badges counting down and greys out a button while its arm is
+// empty, until a reload fills it back up.
+fn ammo_badge_system(
+    theme: Res<Theme>,
+    bodies: Query<(&Stats, &SkillRuntime)>,
+    mut badges: Query<(&mut AmmoBadge, &mut Text, &Parent)>,
+    mut buttons: Query<&mut UiColor>,
+) {
+    for (mut badge, mut text, parent) in &mut badges {
+        let Ok((stats, runtime)) = bodies.get(badge.owner) else {
+            continue;
+        };
+        let Some(remaining) = stats
+            .skills
+            .get(badge.skill)
+            .and_then(|skill| runtime.remaining(skill))
+        else {
+            continue;
+        };
+        let count = remaining.to_string();
+        if text.sections[0].value != count {
+            text.sections[0].value = count;
+        }
+        let empty = remaining == 0;
+        text.sections[0].style.color = if empty { Color::RED } else { Color::WHITE };
+        if let Ok(mut color) = buttons.get_mut(parent.get()) {
+            if empty {
+                color.0 = theme.disabled_button;
+            } else if badge.empty {
+                color.0 = theme.normal_button;
+            }
+        }
+        badge.empty = empty;
+    }
 }
 
-pub struct UiPlugin;
+fn turn_banner_system(
+    game: Res<Game>,
+    turns: Option<Res<Turns>>,
+    mut banner: Query<(&mut Text, &mut Visibility), With<TurnBanner>>,
+) {
+    for (mut text, mut visibility) in &mut banner {
+        match turns.as_ref() {
+            Some(turns) => {
+                visibility.is_visible = true;
+                text.sections[0].value = if turns.current() == game.player {
+                    t!("hud.your_turn")
+                } else {
+                    t!("hud.enemy_turn")
+                };
+            }
+            None => visibility.is_visible = false,
+        }
+    }
+}
 
-impl Plugin for UiPlugin {
-    fn build(&self, app: &mut App) {
-        app.add_system_set_to_stage(
-            CoreStage::PostUpdate,
-            SystemSet::new()
-                .with_system(update_ui_system)
-                .with_system(button_system)
-                .with_system(tooltip_system)
-                .with_system(button_disable_system),
-        )
-        .init_resource::<UseSkill>()
-        .add_startup_system(ui_startup_system);
+fn timer_text_system(
+    time: Res<Time>,
+    scale: Res<UiScale>,
+    timer: Res<BattleTimer>,
+    state: Res<State<AppState>>,
+    mut texts: Query<(&mut Text, &mut Visibility), With<TimerText>>,
+) {
+    let in_battle = matches!(state.current(), AppState::Battle | AppState::Paused);
+    for (mut text, mut visibility) in &mut texts {
+        let section = &mut text.sections[0];
+        match timer.remaining {
+            _ if !in_battle => visibility.is_visible = false,
+            _ if timer.sudden_death => {
+                visibility.is_visible = true;
+                section.value = t!("hud.sudden_death");
+                section.style.color = Color::RED;
+                section.style.font_size = 48.0 * scale.factor();
+            }
+            Some(remaining) => {
+                visibility.is_visible = true;
+                section.value = format!("{}", remaining.ceil());
+                if remaining <= 10.0 {
+                    let pulse = (time.seconds_since_startup() as f32 * 2.0 * PI).sin().abs();
+                    section.style.color = Color::RED;
+                    section.style.font_size = 48.0 * scale.factor() * (1.0 + 0.2 * pulse);
+                } else {
+                    section.style.color = Color::WHITE;
+                    section.style.font_size = 48.0 * scale.factor();
+                }
+            }
+            None => visibility.is_visible = false,
+        }
+    }
+}
+
+fn speed_button_system(
+    theme: Res<Theme>,
+    mut time_scale: ResMut<TimeScale>,
+    keys: Res<Input<KeyCode>>,
+    state: Res<State<AppState>>,
+    mut buttons: Query<(&Interaction, &mut UiColor, &mut Visibility, &Children), With<SpeedButton>>,
+    mut texts: Query<&mut Text>,
+) {
+    let in_battle = *state.current() == AppState::Battle;
+    if in_battle && keys.just_pressed(KeyCode::F) {
+        time_scale.cycle();
+    }
+    for (interaction, mut color, mut visibility, children) in &mut buttons {
+        visibility.is_visible = in_battle;
+        if !in_battle {
+            continue;
+        }
+        match *interaction {
+            Interaction::Clicked => {
+                if color.0 != theme.pressed_button {
+                    time_scale.cycle();
+                }
+                color.0 = theme.pressed_button;
+            }
+            Interaction::Hovered => color.0 = theme.hovered_button,
+            Interaction::None => color.0 = theme.normal_button,
+        }
+        if time_scale.is_changed() {
+            for child in children {
+                if let Ok(mut text) = texts.get_mut(*child) {
+                    text.sections[0].value = format!("{}×", time_scale.0);
+                }
+            }
+        }
+    }
+}
+
+const TOAST_TIME: f32 = 3.0;
+
+pub struct Toast(pub String);
+
+#[derive(Component)]
+struct ToastRoot;
+
+fn pickup_toast_system(
+    mut events: EventReader<PickupEvent>,
+    mut toasts: EventWriter<Toast>,
+    bodies: Query<&Body>,
+) {
+    for event in events.iter() {
+        if let Ok(body) = bodies.get(event.entity) {
+            toasts.send(Toast(t!(
+                "pickup.collected",
+                name = body.name(),
+                item = event.kind.name()
+            )));
+        }
+    }
+}
+
+#[derive(Component)]
+struct ToastEntry(f32);
+
+fn toast_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    fonts: Res<Fonts>,
+    mut toasts: EventReader<Toast>,
+    roots: Query<Entity, With<ToastRoot>>,
+    mut entries: Query<(Entity, &mut ToastEntry)>,
+) {
+    for (entity, mut entry) in &mut entries {
+        entry.0 -= time.delta_seconds();
+        if entry.0 <= 0.0 {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+    for root in &roots {
+        for toast in toasts.iter() {
+            let entry = commands
+                .spawn_bundle(NodeBundle {
+                    style: Style {
+                        padding: UiRect::all(Val::Px(10.0)),
+                        margin: UiRect::all(Val::Px(5.0)),
+                        ..default()
+                    },
+                    color: Color::rgba(0.0, 0.0, 0.0, 0.8).into(),
+                    ..default()
+                })
+                .insert(ToastEntry(TOAST_TIME))
+                .with_children(|parent| {
+                    parent
+                        .spawn_bundle(TextBundle::from_section(
+                            toast.0.clone(),
+                            TextStyle {
+                                font: fonts.bold(),
+                                font_size: 22.0,
+                                color: Color::WHITE,
+                            },
+                        ))
+                        .insert(Scaled::font(22.0));
+                })
+                .id();
+            commands.entity(root).add_child(entry);
+        }
+    }
+}
+
+fn ui_startup_system(
+    theme: Res<Theme>,
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    let fonts = Fonts {
+        normal: asset_server.load(FONT_NORMAL),
+        bold: asset_server.load(FONT_BOLD),
+    };
+    let hatching = images.add(hatching_image());
+
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.0), Val::Auto),
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    top: Val::Px(10.0),
+                    ..default()
+                },
+                flex_direction: FlexDirection::ColumnReverse,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            color: Color::NONE.into(),
+            focus_policy: FocusPolicy::Pass,
+            ..default()
+        })
+        .with_children(|parent| {
+            parent
+                .spawn_bundle(TextBundle {
+                    text: Text::from_section(
+                        "",
+                        TextStyle {
+                            font: fonts.bold(),
+                            font_size: 48.0,
+                            color: Color::WHITE,
+                        },
+                    ),
+                    visibility: Visibility { is_visible: false },
+                    ..default()
+                })
+                .insert(TimerText);
+            parent
+                .spawn_bundle(ButtonBundle {
+                    style: Style {
+                        size: Size::new(Val::Px(50.0), Val::Px(30.0)),
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    color: theme.normal_button.into(),
+                    visibility: Visibility { is_visible: false },
+                    ..default()
+                })
+                .insert_bundle((SpeedButton, Scaled::size(50.0, 30.0)))
+                .with_children(|parent| {
+                    parent
+                        .spawn_bundle(TextBundle::from_section(
+                            "1×",
+                            TextStyle {
+                                font: fonts.bold(),
+                                font_size: 20.0,
+                                color: theme.button_text,
+                            },
+                        ))
+                        .insert(Scaled::font(20.0));
+                });
+            parent
+                .spawn_bundle(TextBundle {
+                    text: Text::from_section(
+                        "",
+                        TextStyle {
+                            font: fonts.bold(),
+                            font_size: 40.0,
+                            color: Color::WHITE,
+                        },
+                    ),
+                    visibility: Visibility { is_visible: false },
+                    ..default()
+                })
+                .insert_bundle((TurnBanner, Scaled::font(40.0)));
+        });
+    let hover = commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Px(200.0), Val::Px(200.0)),
+                position_type: PositionType::Absolute,
+                ..default()
+            },
+            color: Color::NONE.into(),
+            focus_policy: FocusPolicy::Pass,
+            ..default()
+        })
+        .id();
+
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    right: Val::Px(10.0),
+                    bottom: Val::Px(10.0),
+                    ..default()
+                },
+                flex_direction: FlexDirection::ColumnReverse,
+                align_items: AlignItems::FlexEnd,
+                ..default()
+            },
+            color: Color::NONE.into(),
+            focus_policy: FocusPolicy::Pass,
+            ..default()
+        })
+        .insert(ToastRoot);
+
+    for player in [true, false] {
+        commands
+            .spawn_bundle(NodeBundle {
+                style: Style {
+                    size: Size::new(Val::Px(NAMEPLATE_WIDTH), Val::Auto),
+                    position_type: PositionType::Absolute,
+                    flex_direction: FlexDirection::ColumnReverse,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                color: Color::NONE.into(),
+                focus_policy: FocusPolicy::Pass,
+                visibility: Visibility { is_visible: false },
+                ..default()
+            })
+            .insert(Nameplate { player })
+            .with_children(|parent| {
+                // The name, then the weight class badge in its color.
+                let style = TextStyle {
+                    font: fonts.bold(),
+                    font_size: 20.0,
+                    color: Color::WHITE,
+                };
+                parent
+                    .spawn_bundle(TextBundle::from_sections([
+                        TextSection::new("", style.clone()),
+                        TextSection::new("", style),
+                    ]))
+                    .insert(Scaled::font(20.0));
+                let mut health_bar = parent.spawn_bundle(NodeBundle {
+                    style: Style {
+                        size: Size::new(Val::Px(HEALTH_BAR_WIDTH), Val::Px(8.0)),
+                        margin: UiRect::all(Val::Px(2.0)),
+                        ..default()
+                    },
+                    color: Color::rgb(0.1, 0.1, 0.1).into(),
+                    focus_policy: FocusPolicy::Pass,
+                    ..default()
+                });
+                health_bar.with_children(|parent| {
+                    parent
+                        .spawn_bundle(NodeBundle {
+                            style: Style {
+                                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                                ..default()
+                            },
+                            color: HEALTH_COLOR.into(),
+                            focus_policy: FocusPolicy::Pass,
+                            ..default()
+                        })
+                        .insert(HealthFill { player });
+                    parent
+                        .spawn_bundle(NodeBundle {
+                            style: Style {
+                                size: Size::new(Val::Percent(0.0), Val::Percent(100.0)),
+                                position_type: PositionType::Absolute,
+                                ..default()
+                            },
+                            color: SHIELD_COLOR.into(),
+                            focus_policy: FocusPolicy::Pass,
+                            ..default()
+                        })
+                        .insert(ShieldFill { player });
+                    // The part of the fill the hovered attack would take off.
+                    parent
+                        .spawn_bundle(ImageBundle {
+                            style: Style {
+                                size: Size::new(Val::Percent(0.0), Val::Percent(100.0)),
+                                position_type: PositionType::Absolute,
+                                ..default()
+                            },
+                            image: hatching.clone().into(),
+                            focus_policy: FocusPolicy::Pass,
+                            ..default()
+                        })
+                        .insert(HealthPreview { player });
+                });
+                if player {
+                    health_bar.insert_bundle((Interaction::default(), StatBar(BarStat::Health)));
+                }
+                let mut energy_bar = parent.spawn_bundle(NodeBundle {
+                    style: Style {
+                        size: Size::new(Val::Px(HEALTH_BAR_WIDTH), Val::Px(5.0)),
+                        margin: UiRect::all(Val::Px(2.0)),
+                        ..default()
+                    },
+                    color: Color::rgb(0.1, 0.1, 0.1).into(),
+                    focus_policy: FocusPolicy::Pass,
+                    ..default()
+                });
+                energy_bar.with_children(|parent| {
+                    parent
+                        .spawn_bundle(NodeBundle {
+                            style: Style {
+                                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                                ..default()
+                            },
+                            color: ENERGY_COLOR.into(),
+                            focus_policy: FocusPolicy::Pass,
+                            ..default()
+                        })
+                        .insert(EnergyFill { player });
+                });
+                if player {
+                    energy_bar.insert_bundle((Interaction::default(), StatBar(BarStat::Energy)));
+                } else {
+                    energy_bar.insert(Fogged(knowledge::NUMBERS));
+                    parent
+                        .spawn_bundle(TextBundle::from_section(
+                            "",
+                            TextStyle {
+                                font: fonts.normal(),
+                                font_size: 14.0,
+                                color: Color::WHITE,
+                            },
+                        ))
+                        .insert_bundle((
+                            Scaled::font(14.0),
+                            EnemyReadout,
+                            Fogged(knowledge::NUMBERS),
+                        ));
+                }
+                parent
+                    .spawn_bundle(NodeBundle {
+                        color: Color::NONE.into(),
+                        focus_policy: FocusPolicy::Pass,
+                        ..default()
+                    })
+                    .insert(EffectTray { player });
+            });
+    }
+
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Px(1.0), Val::Px(1.0)),
+                position_type: PositionType::Absolute,
+                ..default()
+            },
+            color: Color::NONE.into(),
+            focus_policy: FocusPolicy::Pass,
+            ..default()
+        })
+        .insert(PartHover);
+
+    commands.insert_resource(Tooltip {
+        entity: hover,
+        currently_hovering: None,
+    });
+    commands.insert_resource(fonts);
+}
+
+// Diagonal stripes, to tell predicted damage apart from the bar itself.
+fn hatching_image() -> Image {
+    const SIZE: u32 = 8;
+    let data = (0..SIZE * SIZE)
+        .flat_map(|i| {
+            if (i % SIZE + i / SIZE) % 4 < 2 {
+                [255, 255, 255, 220]
+            } else {
+                [200, 40, 40, 160]
+            }
+        })
+        .collect();
+    Image::new(
+        Extent3d {
+            width: SIZE,
+            height: SIZE,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+    )
+}
+
+fn health_bar_system(
+    game: Res<Game>,
+    preview: Res<SkillPreview>,
+    stats: Query<&Stats>,
+    learned: Query<&Knowledge>,
+    mut fills: Query<
+        (&HealthFill, &mut Style),
+        (
+            Without<HealthPreview>,
+            Without<EnergyFill>,
+            Without<ShieldFill>,
+        ),
+    >,
+    mut previews: Query<
+        (&HealthPreview, &mut Style),
+        (
+            Without<HealthFill>,
+            Without<EnergyFill>,
+            Without<ShieldFill>,
+        ),
+    >,
+    mut shields: Query<
+        (&ShieldFill, &mut Style),
+        (
+            Without<HealthFill>,
+            Without<HealthPreview>,
+            Without<EnergyFill>,
+        ),
+    >,
+    mut energy_fills: Query<
+        (&EnergyFill, &mut Style),
+        (
+            Without<HealthFill>,
+            Without<HealthPreview>,
+            Without<ShieldFill>,
+        ),
+    >,
+) {
+    // The bar spans health and overshield together, without a shield it's
+    // just the health.
+    let fraction = |entity: Entity, amount: f32| {
+        stats.get(entity).map_or(0.0, |stats| {
+            let pool = stats.max_health + stats.max_overshield;
+            (amount / pool.max(1.0)).clamp(0.0, 1.0) * 100.0
+        })
+    };
+    let pools = |entity: Entity| {
+        stats
+            .get(entity)
+            .map_or((0.0, 0.0), |stats| (stats.health, stats.overshield))
+    };
+    let body = |player: bool| if player { game.player } else { game.enemy };
+    let known = |entity: Entity| knowledge::known(&game, learned.get(game.player).ok(), entity);
+    for (fill, mut style) in &mut fills {
+        let entity = body(fill.player);
+        let (health, overshield) = pools(entity);
+        // Unscanned, the bar only moves a quarter at a time and the shield
+        // looks like health.
+        let width = if known(entity) < knowledge::NUMBERS {
+            (fraction(entity, health + overshield) / 25.0).ceil() * 25.0
+        } else {
+            fraction(entity, health)
+        };
+        style.size.width = Val::Percent(width);
+    }
+    for (fill, mut style) in &mut shields {
+        let entity = body(fill.player);
+        let (health, overshield) = pools(entity);
+        let width = if known(entity) < knowledge::NUMBERS {
+            0.0
+        } else {
+            fraction(entity, overshield)
+        };
+        style.position.left = Val::Percent(fraction(entity, health));
+        style.size.width = Val::Percent(width);
+    }
+    for (bar, mut style) in &mut previews {
+        let entity = body(bar.player);
+        let (left, width) = match preview.health {
+            Some((target, after)) if target == entity && known(entity) >= knowledge::NUMBERS => {
+                let (health, overshield) = pools(entity);
+                let now = fraction(entity, health + overshield);
+                let after = fraction(entity, after);
+                (after, now - after)
+            }
+            _ => (0.0, 0.0),
+        };
+        style.position.left = Val::Percent(left);
+        style.size.width = Val::Percent(width);
+    }
+    for (fill, mut style) in &mut energy_fills {
+        let energy = stats.get(body(fill.player)).map_or(0.0, |stats| {
+            (stats.energy / stats.max_energy.max(1.0)).clamp(0.0, 1.0) * 100.0
+        });
+        style.size.width = Val::Percent(energy);
+    }
+}
+
+// Picking up what changed under a hovered skill, like rain starting or a
+// booster wearing off while the walk's numbers are shown.
+fn skill_hover_refresh_system(
+    game: Res<Game>,
+    balance: Res<BalanceConfig>,
+    weather: Res<Weather>,
+    ruleset: Res<Ruleset>,
+    mut tooltip: ResMut<Tooltip>,
+    buttons: Query<&SkillButton>,
+    slots: Query<&SkillSlot>,
+    bodies: Query<(&Body, &Stats, Option<&SkillRuntime>, Option<&Knowledge>)>,
+) {
+    let Some(entity) = tooltip
+        .currently_hovering
+        .as_ref()
+        .map(|shown| shown.entity)
+    else {
+        return;
+    };
+    let (owner, id) = match (buttons.get(entity), slots.get(entity)) {
+        (Ok(SkillButton(id)), _) => (game.player, id),
+        (_, Ok(slot)) => (slot.owner, &slot.skill),
+        _ => return,
+    };
+    let Ok((body, stats, runtime, _)) = bodies.get(owner) else {
+        return;
+    };
+    let Some(skill) = stats.skill(id) else {
+        return;
+    };
+    let learned = bodies
+        .get(game.player)
+        .ok()
+        .and_then(|(.., knowledge)| knowledge);
+    let hovered = skill_tooltip(
+        entity,
+        owner,
+        skill,
+        (body, stats, runtime),
+        knowledge::known(&game, learned, owner),
+        &MovementRules::new(&balance, &weather, &ruleset),
+    );
+    let unchanged = tooltip.currently_hovering.as_ref().is_some_and(|shown| {
+        shown.header == hovered.header && shown.description == hovered.description
+    });
+    if !unchanged {
+        tooltip.currently_hovering = Some(hovered);
+    }
+}
+
+// Refreshed every frame while hovered, the rates and timings keep moving.
+fn stat_bar_hover_system(
+    game: Res<Game>,
+    balance: Res<BalanceConfig>,
+    ruleset: Res<Ruleset>,
+    mut tooltip: ResMut<Tooltip>,
+    bars: Query<(Entity, &StatBar, &Interaction)>,
+    bodies: Query<(&Body, &Stats)>,
+    effects: Query<&PeriodicEffect>,
+) {
+    for (entity, StatBar(stat), interaction) in &bars {
+        let hovering_this = tooltip
+            .currently_hovering
+            .as_ref()
+            .is_some_and(|hovered| hovered.entity == entity);
+        if *interaction == Interaction::None {
+            if hovering_this {
+                tooltip.currently_hovering = None;
+            }
+            continue;
+        }
+        let Ok((body, stats)) = bodies.get(game.player) else {
+            continue;
+        };
+        let effects = effects
+            .iter()
+            .filter(|effect| effect.target == game.player)
+            .collect::<Vec<_>>();
+        let hovered = bar_tooltip(
+            entity,
+            *stat,
+            &balance,
+            ruleset.mutators,
+            body,
+            stats,
+            &effects,
+        );
+        let unchanged = hovering_this
+            && tooltip.currently_hovering.as_ref().is_some_and(|shown| {
+                shown.header == hovered.header && shown.description == hovered.description
+            });
+        if !unchanged {
+            tooltip.currently_hovering = Some(hovered);
+        }
+    }
+}
+
+// Shows as much of the enemy's name plate as the player has scanned.
+fn fog_system(
+    game: Res<Game>,
+    locale: Res<Locale>,
+    bodies: Query<(&Stats, Option<&Knowledge>)>,
+    mut fogged: Query<(&Fogged, &mut Style)>,
+    mut readouts: Query<&mut Text, With<EnemyReadout>>,
+) {
+    let learned = bodies
+        .get(game.player)
+        .ok()
+        .and_then(|(_, knowledge)| knowledge);
+    let known = knowledge::known(&game, learned, game.enemy);
+    for (Fogged(tier), mut style) in &mut fogged {
+        let display = if known >= *tier {
+            Display::Flex
+        } else {
+            Display::None
+        };
+        if style.display != display {
+            style.display = display;
+        }
+    }
+    let Ok((stats, _)) = bodies.get(game.enemy) else {
+        return;
+    };
+    let readout = t!(
+        "hud.enemy_numbers",
+        health = stats.health.max(0.0),
+        max = stats.max_health,
+        speed = stats.speed
+    );
+    for mut text in &mut readouts {
+        if text.sections[0].value != readout || locale.is_changed() {
+            text.sections[0].value = readout.clone();
+        }
+    }
+}
+
+// Hovering a body explains the part under the cursor, though the enemy's
+// parts stay unknown until a full scan.
+fn part_hover_system(
+    game: Res<Game>,
+    windows: Res<Windows>,
+    mut tooltip: ResMut<Tooltip>,
+    cameras: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    geometry: BodyGeometry,
+    bodies: Query<(&Body, Option<&Knowledge>)>,
+    interactions: Query<&Interaction>,
+    mut anchors: Query<(Entity, &mut Style), With<PartHover>>,
+) {
+    let Ok((anchor, mut style)) = anchors.get_single_mut() else {
+        return;
+    };
+    let hovering_this = tooltip
+        .currently_hovering
+        .as_ref()
+        .is_some_and(|hovered| hovered.entity == anchor);
+    // The HUD sits in front of the bodies.
+    let over_ui = interactions
+        .iter()
+        .any(|interaction| *interaction != Interaction::None);
+    let window = windows.get_primary();
+    // Kept on the cursor all along, layout lags a frame and the tooltip is
+    // only placed when what's hovered changes.
+    if let Some(cursor) = window.and_then(Window::cursor_position) {
+        let position = UiRect {
+            left: Val::Px(cursor.x),
+            bottom: Val::Px(cursor.y),
+            ..default()
+        };
+        if style.position != position {
+            style.position = position;
+        }
+    }
+    let hit = (!over_ui)
+        .then(|| {
+            let (camera, transform) = cameras.get_single().ok()?;
+            let window = window?;
+            let point = camera::cursor_world(camera, transform, window)?;
+            [game.enemy, game.player]
+                .into_iter()
+                .find_map(|entity| Some((entity, geometry.body_hit_test(entity, point)?)))
+        })
+        .flatten();
+    let Some((entity, part)) = hit else {
+        if hovering_this {
+            tooltip.currently_hovering = None;
+        }
+        return;
+    };
+    let learned = bodies
+        .get(game.player)
+        .ok()
+        .and_then(|(_, knowledge)| knowledge);
+    let Some((name, stats)) = bodies
+        .get(entity)
+        .ok()
+        .and_then(|(body, _)| body.part(part))
+    else {
+        return;
+    };
+    let hovered = if knowledge::known(&game, learned, entity) < knowledge::PARTS {
+        Hovered {
+            entity: anchor,
+            header: t!("tooltip.unknown_part"),
+            description: t!("tooltip.scan_parts"),
+            preview: None,
+        }
+    } else {
+        let mut description = t!(
+            "tooltip.part",
+            material = stats.material().name(),
+            pattern = stats.material().pattern(),
+            health = stats.health(),
+            energy = stats.energy(),
+            weight = stats.weight(),
+        );
+        if stats.overshield() > 0.0 {
+            description += &t!("tooltip.part_overshield", overshield = stats.overshield());
+        }
+        Hovered {
+            entity: anchor,
+            header: name.to_string(),
+            description,
+            preview: None,
+        }
+    };
+    let unchanged = hovering_this
+        && tooltip.currently_hovering.as_ref().is_some_and(|shown| {
+            shown.header == hovered.header && shown.description == hovered.description
+        });
+    if !unchanged {
+        tooltip.currently_hovering = Some(hovered);
+    }
+}
+
+fn nameplate_system(
+    game: Res<Game>,
+    state: Res<State<AppState>>,
+    windows: Res<Windows>,
+    cameras: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    bodies: Query<(&Body, &Stats, &GlobalTransform)>,
+    mut plates: Query<(&Nameplate, &Node, &mut Style, &mut Visibility, &Children)>,
+    descendants: Query<&Children>,
+    mut nodes: Query<&mut Visibility, Without<Nameplate>>,
+    mut texts: Query<&mut Text>,
+) {
+    let camera = cameras.get_single().ok();
+    let window = windows
+        .get_primary()
+        .map(|window| Vec2::new(window.width(), window.height()));
+    for (plate, node, mut style, mut visibility, children) in &mut plates {
+        let entity = if plate.player {
+            game.player
+        } else {
+            game.enemy
+        };
+        let screen = bodies
+            .get(entity)
+            .ok()
+            .and_then(|(body, stats, transform)| {
+                let (camera, camera_transform) = camera?;
+                let head = transform.translation() + Vec3::Y * (body.height() + 0.2);
+                Some((
+                    body,
+                    stats,
+                    camera.world_to_viewport(camera_transform, head)?,
+                ))
+            });
+        let visible = *state.current() != AppState::Loading && screen.is_some();
+        if visibility.is_visible != visible {
+            visibility.is_visible = visible;
+        }
+        // Children don't inherit visibility in this version.
+        let mut stack = children.to_vec();
+        while let Some(node) = stack.pop() {
+            if let Ok(mut visibility) = nodes.get_mut(node) {
+                if visibility.is_visible != visible {
+                    visibility.is_visible = visible;
+                }
+            }
+            if let Ok(children) = descendants.get(node) {
+                stack.extend(children.iter());
+            }
+        }
+        let Some((body, stats, screen)) = screen else {
+            continue;
+        };
+        // Kept on screen over a body too big to fit or a camera looking
+        // elsewhere, so it stays labeled.
+        let mut at = Vec2::new(screen.x - NAMEPLATE_WIDTH / 2.0, screen.y);
+        if let Some(window) = window {
+            at = at
+                .min(window - Vec2::new(NAMEPLATE_WIDTH, node.size.y))
+                .max(Vec2::ZERO);
+        }
+        style.position = UiRect {
+            left: Val::Px(at.x),
+            bottom: Val::Px(at.y),
+            ..default()
+        };
+        for child in children {
+            if let Ok(mut text) = texts.get_mut(*child) {
+                if text.sections[0].value != body.name() {
+                    text.sections[0].value = body.name().to_string();
+                }
+                let class = stats.class();
+                let badge = format!("  {}", class.name());
+                if text.sections[1].value != badge {
+                    text.sections[1].value = badge;
+                    text.sections[1].style.color = class.color();
+                }
+            }
+        }
+    }
+}
+
+fn effect_tray_system(
+    mut commands: Commands,
+    game: Res<Game>,
+    fonts: Res<Fonts>,
+    locale: Res<Locale>,
+    mut tooltip: ResMut<Tooltip>,
+    mut events: EventReader<ActiveEffect>,
+    trays: Query<(Entity, &EffectTray, Option<&Children>)>,
+    icons: Query<&EffectIcon>,
+    mut texts: Query<&mut Text>,
+) {
+    let effects = events.iter().collect::<Vec<_>>();
+    for (tray, EffectTray { player }, children) in &trays {
+        let target = if *player { game.player } else { game.enemy };
+        let shown = effects
+            .iter()
+            .filter(|effect| effect.target == target)
+            .collect::<Vec<_>>();
+        let current = children
+            .map(|children| icons.iter_many(children).collect::<Vec<_>>())
+            .unwrap_or_default();
+        let unchanged = !locale.is_changed()
+            && current.len() == shown.len()
+            && current
+                .iter()
+                .zip(&shown)
+                .all(|(icon, effect)| icon.id == effect.id && icon.stacks == effect.stacks);
+        if unchanged {
+            for (icon, effect) in current.iter().zip(&shown) {
+                if let Ok(mut text) = texts.get_mut(icon.countdown) {
+                    let value = countdown(effect.remaining);
+                    if text.sections[0].value != value {
+                        text.sections[0].value = value;
+                    }
+                }
+            }
+            continue;
+        }
+
+        // Rebuilt whenever an effect comes or goes, so an expired effect's icon
+        // is gone the same frame.
+        if let Some(children) = children {
+            if tooltip
+                .currently_hovering
+                .as_ref()
+                .is_some_and(|hovered| children.contains(&hovered.entity))
+            {
+                tooltip.currently_hovering = None;
+            }
+        }
+        let mut tray = commands.entity(tray);
+        tray.despawn_descendants();
+        tray.with_children(|parent| {
+            for effect in shown {
+                spawn_effect_icon(parent, &fonts, effect);
+            }
+        });
+    }
+}
+
+fn countdown(remaining: Option<f32>) -> String {
+    remaining.map_or("∞".to_string(), |remaining| format!("{:.1}", remaining))
+}
+
+fn spawn_effect_icon(parent: &mut ChildBuilder, fonts: &Fonts, effect: &ActiveEffect) {
+    let name = effect.name();
+    let color = match effect.polarity {
+        Polarity::Buff => BUFF_COLOR,
+        Polarity::Debuff => DEBUFF_COLOR,
+    };
+    let style = |size: f32| TextStyle {
+        font: fonts.bold(),
+        font_size: size,
+        color: Color::WHITE,
+    };
+    let mut icon = parent.spawn_bundle(ButtonBundle {
+        style: Style {
+            size: Size::new(Val::Px(EFFECT_ICON_SIZE), Val::Px(EFFECT_ICON_SIZE)),
+            margin: UiRect::all(Val::Px(2.0)),
+            flex_direction: FlexDirection::ColumnReverse,
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            ..default()
+        },
+        color: color.into(),
+        ..default()
+    });
+    let mut countdown_text = None;
+    icon.with_children(|parent| {
+        let initial = name.chars().next().map(String::from).unwrap_or_default();
+        parent.spawn_bundle(TextBundle::from_section(initial, style(16.0)));
+        countdown_text = Some(
+            parent
+                .spawn_bundle(TextBundle::from_section(
+                    countdown(effect.remaining),
+                    style(11.0),
+                ))
+                .id(),
+        );
+        if effect.stacks > 1 {
+            parent.spawn_bundle(
+                TextBundle::from_section(format!("x{}", effect.stacks), style(11.0)).with_style(
+                    Style {
+                        position_type: PositionType::Absolute,
+                        position: UiRect {
+                            right: Val::Px(1.0),
+                            top: Val::Px(0.0),
+                            ..default()
+                        },
+                        ..default()
+                    },
+                ),
+            );
+        }
+    });
+    icon.insert(EffectIcon {
+        id: effect.id,
+        stacks: effect.stacks,
+        countdown: countdown_text.unwrap(),
+        name,
+        description: effect.description(),
+    });
+}
+
+fn effect_icon_hover_system(
+    mut tooltip: ResMut<Tooltip>,
+    icons: Query<(Entity, &Interaction, &EffectIcon), Changed<Interaction>>,
+) {
+    for (entity, interaction, icon) in &icons {
+        if *interaction == Interaction::None {
+            if tooltip
+                .currently_hovering
+                .as_ref()
+                .is_some_and(|hovered| hovered.entity == entity)
+            {
+                tooltip.currently_hovering = None;
+            }
+        } else {
+            tooltip.currently_hovering = Some(Hovered {
+                entity,
+                header: icon.name.clone(),
+                description: icon.description.clone(),
+                preview: None,
+            });
+        }
+    }
+}
+
+fn ui_scale_system(
+    mut resized: EventReader<WindowResized>,
+    windows: Res<Windows>,
+    profile: Res<Profile>,
+    mut scale: ResMut<UiScale>,
+) {
+    if resized.iter().count() == 0 && !profile.is_changed() && !scale.is_added() {
+        return;
+    }
+    let height = windows
+        .get_primary()
+        .map_or(REFERENCE_HEIGHT, |window| window.height());
+    let updated = UiScale {
+        setting: profile.settings.ui_scale,
+        auto: (height / REFERENCE_HEIGHT).clamp(0.75, 3.0),
+    };
+    if *scale != updated {
+        *scale = updated;
+    }
+}
+
+fn apply_scale_system(
+    scale: Res<UiScale>,
+    mut nodes: Query<(
+        &Scaled,
+        ChangeTrackers<Scaled>,
+        Option<&mut Style>,
+        Option<&mut Text>,
+    )>,
+) {
+    for (scaled, tracker, style, text) in &mut nodes {
+        if !scale.is_changed() && !tracker.is_added() {
+            continue;
+        }
+        if let Some(mut style) = style {
+            if let Some(width) = scaled.width {
+                style.size.width = scale.px(width);
+            }
+            if let Some(height) = scaled.height {
+                style.size.height = scale.px(height);
+            }
+            if let Some(top) = scaled.top {
+                style.position.top = scale.px(top);
+            }
+        }
+        if let (Some(mut text), Some(font)) = (text, scaled.font) {
+            for section in &mut text.sections {
+                section.style.font_size = font * scale.factor();
+            }
+        }
+    }
+}
+
+pub struct UiPlugin;
+
+impl Plugin for UiPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<Toast>()
+            .init_resource::<SkillIcons>()
+            .init_resource::<UiScale>()
+            .add_system(toast_system)
+            .add_system(pickup_toast_system.before(toast_system))
+            .add_system(ui_scale_system)
+            .add_system_to_stage(CoreStage::PostUpdate, apply_scale_system)
+            .add_system_set_to_stage(
+                CoreStage::PostUpdate,
+                SystemSet::new()
+                    .with_system(update_ui_system)
+                    .with_system(tooltip_system)
+                    .with_system(turn_banner_system)
+                    .with_system(timer_text_system)
+                    .with_system(speed_button_system)
+                    .with_system(nameplate_system)
+                    .with_system(health_bar_system)
+                    .with_system(fog_system)
+                    .with_system(part_hover_system.before(tooltip_system))
+                    .with_system(stat_bar_hover_system)
+                    .with_system(effect_tray_system)
+                    .with_system(effect_icon_hover_system)
+                    .with_system(read_only_bar_system)
+                    .with_system(
+                        skill_hover_refresh_system
+                            .after(read_only_bar_system)
+                            .after(button_system)
+                            .before(tooltip_system),
+                    )
+                    .with_system(
+                        ammo_badge_system
+                            .after(read_only_bar_system)
+                            .after(button_system)
+                            .after(button_disable_system),
+                    )
+                    .with_system(reorder_system),
+            )
+            .add_system_set_to_stage(
+                CoreStage::PostUpdate,
+                SystemSet::new()
+                    .with_run_criteria(State::on_update(AppState::Battle))
+                    .with_system(button_system)
+                    .with_system(hotkey_system)
+                    .with_system(approach_click_system)
+                    .with_system(button_disable_system),
+            )
+            .add_startup_system(ui_startup_system);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::body::{compute_stats, Ability, Limb};
+
+    fn melee(name: &str, limb: u8) -> Skill {
+        Skill::BasicMelee(Ability {
+            meta: 5.0,
+            time: 1.0,
+            cooldown: 0.2,
+            energy_cost: 3.0,
+            limb: Limb::Arm(limb),
+            name: name.to_string(),
+            interrupting: false,
+            ammo: None,
+            emp: false,
+        })
+    }
+
+    #[test]
+    fn pinned_skills_keep_their_slot() {
+        let mut skills = compute_stats(&Body::default()).skills;
+        // The scan sorts after every attack, leave it out.
+        skills.retain(|skill| !matches!(skill, Skill::Scan(_)));
+        let jab = skills
+            .iter()
+            .position(|skill| matches!(skill, Skill::BasicMelee(_)))
+            .unwrap();
+        // The player moved the jab to the front.
+        let mut pinned = bar_order(&skills, &[]);
+        pinned.retain(|i| *i != jab);
+        pinned.insert(0, jab);
+        let pinned = pinned.iter().map(|i| skills[*i].id()).collect::<Vec<_>>();
+        assert_eq!(bar_order(&skills, &pinned)[0], jab);
+
+        // A new arm skill sorts ahead of the jab by default but joins the end
+        // of the melee skills on the bar.
+        let mut edited = skills.clone();
+        edited.insert(jab, melee("Hook", 3));
+        let order = bar_order(&edited, &pinned);
+        assert_eq!(edited[order[0]], skills[jab]);
+        assert_eq!(edited[*order.last().unwrap()], melee("Hook", 3));
+
+        // Skills that went away drop out, nothing else moves.
+        let mut removed = skills.clone();
+        removed.retain(|skill| !matches!(skill, Skill::WalkBackward));
+        let order = bar_order(&removed, &pinned);
+        assert_eq!(order.len(), removed.len());
+        assert_eq!(removed[order[0]], skills[jab]);
+    }
+
+    #[test]
+    fn repeated_presses_keep_moving_the_same_skill() {
+        let skills = vec![
+            Skill::WalkBackward,
+            Skill::WalkForward,
+            melee("Jab", 0),
+            melee("Hook", 1),
+        ];
+        let ids = skills.iter().map(Skill::id).collect::<Vec<_>>();
+        let mut moving = Moving::default();
+        // The cursor stays on the second slot while Right is pressed twice.
+        let saved = reorder(&skills, &[], &mut moving, &ids[1], 1).unwrap();
+        assert_eq!(
+            saved,
+            [&ids[0], &ids[2], &ids[1], &ids[3]].map(Clone::clone)
+        );
+        let saved = reorder(&skills, &saved, &mut moving, &ids[2], 1).unwrap();
+        assert_eq!(
+            saved,
+            [&ids[0], &ids[2], &ids[3], &ids[1]].map(Clone::clone)
+        );
+        // Already at the end.
+        assert_eq!(reorder(&skills, &saved, &mut moving, &ids[2], 1), None);
+        // Hovering another button picks that one instead.
+        let saved = reorder(&skills, &saved, &mut moving, &ids[0], 1).unwrap();
+        assert_eq!(
+            saved,
+            [&ids[2], &ids[0], &ids[3], &ids[1]].map(Clone::clone)
+        );
+    }
+
+    #[test]
+    fn unknown_skills_follow_the_categories() {
+        let skills = vec![
+            Skill::WalkBackward,
+            Skill::WalkForward,
+            melee("Jab", 0),
+            melee("Hook", 1),
+        ];
+        assert_eq!(bar_order(&skills, &[]), [0, 1, 2, 3]);
+        let preferred = [skills[3].id(), skills[0].id()];
+        assert_eq!(bar_order(&skills, &preferred), [3, 0, 1, 2]);
     }
 }
@@ -0,0 +1,131 @@
+use crate::{attachment::Attachment, body::Body, locale::t};
+
+// Edits kept to undo, the oldest fall off past this.
+pub const MAX_UNDO: usize = 50;
+
+// An edit made on the pre-battle screen, holding what it replaced so it can
+// be taken back. Applying one gives the action that reverses it.
+pub enum EditorAction {
+    // A pasted build went on, with the body it replaced.
+    Equip(Box<Body>),
+    // The custom name from before, `None` for the generated one.
+    Rename(Option<String>),
+    // What the arm in the slot had on before, the inventory follows the body.
+    Attach(u8, Option<Attachment>),
+}
+
+impl EditorAction {
+    pub fn apply(self, body: &mut Body) -> EditorAction {
+        match self {
+            EditorAction::Equip(other) => {
+                EditorAction::Equip(Box::new(std::mem::replace(body, *other)))
+            }
+            EditorAction::Rename(name) => {
+                let old = body.custom_name().map(str::to_string);
+                body.set_name(name.as_deref().unwrap_or_default());
+                EditorAction::Rename(old)
+            }
+            EditorAction::Attach(slot, attachment) => {
+                EditorAction::Attach(slot, body.attach(slot, attachment))
+            }
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            EditorAction::Equip(_) => t!("edit.equip"),
+            EditorAction::Rename(_) => t!("edit.rename"),
+            EditorAction::Attach(..) => t!("edit.attach"),
+        }
+    }
+}
+
+// Cleared whenever the player leaves the pre-battle screen, a battle or
+// another screen may have changed the body since.
+#[derive(Default)]
+pub struct EditHistory {
+    undo: Vec<EditorAction>,
+    redo: Vec<EditorAction>,
+}
+
+impl EditHistory {
+    // Called once the edit is made. Anything undone before can't be redone
+    // on top of it.
+    pub fn push(&mut self, action: EditorAction) {
+        self.redo.clear();
+        if self.undo.len() >= MAX_UNDO {
+            self.undo.remove(0);
+        }
+        self.undo.push(action);
+    }
+
+    // What was taken back, nothing when there's no edit left.
+    pub fn undo(&mut self, body: &mut Body) -> Option<String> {
+        let action = self.undo.pop()?;
+        let describe = action.describe();
+        self.redo.push(action.apply(body));
+        Some(describe)
+    }
+
+    pub fn redo(&mut self, body: &mut Body) -> Option<String> {
+        let action = self.redo.pop()?;
+        let describe = action.describe();
+        self.undo.push(action.apply(body));
+        Some(describe)
+    }
+
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    use super::*;
+    use crate::fixtures::seeded_bodies;
+
+    #[test]
+    fn undoing_every_edit_restores_the_body() {
+        let builds = seeded_bodies(8, 2, 2);
+        let names = [None, Some("Crusher"), Some("  Tin Can  "), Some("")];
+        let mut rng = StdRng::seed_from_u64(3);
+        let mut random_edit = || {
+            if rng.gen_bool(0.4) {
+                EditorAction::Equip(Box::new(builds[rng.gen_range(0..builds.len())].clone()))
+            } else if rng.gen_bool(0.3) {
+                let attachment = Attachment::ALL[rng.gen_range(0..Attachment::ALL.len())];
+                EditorAction::Attach(rng.gen_range(0..2), Some(attachment))
+            } else {
+                let name = names[rng.gen_range(0..names.len())];
+                EditorAction::Rename(name.map(str::to_string))
+            }
+        };
+        let mut body = builds[0].clone();
+        let start = ron::to_string(&body).unwrap();
+        let mut history = EditHistory::default();
+        for _ in 0..MAX_UNDO {
+            history.push(random_edit().apply(&mut body));
+        }
+        let edited = ron::to_string(&body).unwrap();
+        while history.undo(&mut body).is_some() {}
+        assert_eq!(ron::to_string(&body).unwrap(), start);
+        while history.redo(&mut body).is_some() {}
+        assert_eq!(ron::to_string(&body).unwrap(), edited);
+
+        // Past the cap the oldest edits are dropped.
+        for _ in 0..10 {
+            history.push(random_edit().apply(&mut body));
+        }
+        let mut undone = 0;
+        while history.undo(&mut body).is_some() {
+            undone += 1;
+        }
+        assert_eq!(undone, MAX_UNDO);
+        // A new edit drops what was undone.
+        history.push(EditorAction::Rename(Some("Other".to_string())).apply(&mut body));
+        assert!(history.redo(&mut body).is_none());
+    }
+}
@@ -0,0 +1,254 @@
+use crate::body::{BodyPart, BodyPartMeta, PartStats, Skill};
+
+// Highest tier a part can be upgraded to, shown as "V3".
+pub const MAX_TIER: u8 = 3;
+pub const UPGRADE_COST: u32 = 50;
+// What each upgrade adds to a part's health, energy, overshield and hit
+// strength.
+const UPGRADE_FACTOR: f32 = 1.1;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum UpgradeError {
+    MaxTier,
+    // The duplicate given up has to be the same material as the part.
+    WrongMaterial,
+    NotEnoughScrap { cost: u32, scrap: u32 },
+}
+
+impl std::fmt::Display for UpgradeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UpgradeError::MaxTier => write!(f, "the part is already V{}", MAX_TIER),
+            UpgradeError::WrongMaterial => {
+                write!(f, "the duplicate has to be the same material")
+            }
+            UpgradeError::NotEnoughScrap { cost, scrap } => {
+                write!(f, "upgrading costs {} scrap, only {} left", cost, scrap)
+            }
+        }
+    }
+}
+
+// Generated names end in a model code, upgrades count up a "V" after it.
+fn next_name(name: &str, tier: u8) -> String {
+    match name.strip_suffix(&format!("V{}", tier)) {
+        Some(base) => format!("{}V{}", base, tier + 1),
+        None => format!("{} V{}", name, tier + 1),
+    }
+}
+
+// The part one tier up, strengthened by UPGRADE_FACTOR. Drains aren't made
+// worse, and size, weight, color and wear stay as they were.
+pub fn upgraded<M: BodyPartMeta + Clone>(part: &BodyPart<M>) -> Result<BodyPart<M>, UpgradeError> {
+    if part.tier() >= MAX_TIER {
+        return Err(UpgradeError::MaxTier);
+    }
+    let stats = part.stats();
+    let skills = stats
+        .skills()
+        .iter()
+        .cloned()
+        .map(|mut skill| {
+            if let Skill::BasicMelee(ability) | Skill::BasicRanged(ability) | Skill::Slam(ability) =
+                &mut skill
+            {
+                ability.meta *= UPGRADE_FACTOR;
+            }
+            skill
+        })
+        .collect();
+    let energy = if stats.energy() > 0.0 {
+        stats.energy() * UPGRADE_FACTOR
+    } else {
+        stats.energy()
+    };
+    let stats = PartStats::new(
+        stats.material(),
+        stats.size(),
+        stats.weight(),
+        stats.health() * UPGRADE_FACTOR,
+        energy,
+    )
+    .with_skills(skills)
+    .with_color(stats.color())
+    .with_overshield(stats.overshield() * UPGRADE_FACTOR)
+    .with_wear_of(stats);
+    Ok(BodyPart::new(
+        next_name(part.name(), part.tier()),
+        stats,
+        part.meta().clone(),
+    )
+    .with_tier(part.tier() + 1)
+    .with_slot(part.slot()))
+}
+
+// Upgrades `part` in place, using up `duplicate` and UPGRADE_COST of `scrap`.
+// Nothing changes on an error. Upgrading a part of a body goes through the
+// changed-Body path like any other edit, so its stats follow. Only the debug
+// console upgrades parts so far, there's no shop yet.
+#[cfg_attr(not(debug_assertions), allow(dead_code))]
+pub fn upgrade<M: BodyPartMeta + Clone>(
+    part: &mut BodyPart<M>,
+    duplicate: BodyPart<M>,
+    scrap: &mut u32,
+) -> Result<(), UpgradeError> {
+    if duplicate.stats().material() != part.stats().material() {
+        return Err(UpgradeError::WrongMaterial);
+    }
+    if *scrap < UPGRADE_COST {
+        return Err(UpgradeError::NotEnoughScrap {
+            cost: UPGRADE_COST,
+            scrap: *scrap,
+        });
+    }
+    *part = upgraded(part)?;
+    *scrap -= UPGRADE_COST;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    use crate::{
+        attachment::Attachment,
+        body::{compute_stats, Body, Material},
+        telemetry::SkillUsage,
+        ui::bar_order,
+    };
+
+    #[test]
+    fn upgrades_add_ten_percent_up_to_v3() {
+        let mut body = Body::default();
+        let before = compute_stats(&body);
+        let arm = body.arms()[0].clone();
+        let color = arm.stats().color();
+        let mut scrap = UPGRADE_COST * 4;
+
+        upgrade(&mut body.arms_mut()[0], arm.clone(), &mut scrap).unwrap();
+        let upgraded_arm = &body.arms()[0];
+        assert_eq!(upgraded_arm.name(), "Typical Rusty Arm - V1");
+        assert_eq!(upgraded_arm.tier(), 1);
+        assert_eq!(upgraded_arm.stats().color(), color);
+        assert_eq!(upgraded_arm.stats().weight(), arm.stats().weight());
+        // Energy drains don't grow.
+        assert_eq!(upgraded_arm.stats().energy(), arm.stats().energy());
+        let after = compute_stats(&body);
+        let health = arm.stats().health() * (UPGRADE_FACTOR - 1.0);
+        assert!((after.max_health - before.max_health - health).abs() < 1e-4);
+        let meta = |stats: &crate::body::Stats| {
+            stats
+                .skills
+                .iter()
+                .filter_map(Skill::ability)
+                .map(|ability| ability.meta)
+                .sum::<f32>()
+        };
+        let jab = arm.stats().skills().iter().filter_map(Skill::ability);
+        let jab = jab.map(|ability| ability.meta).sum::<f32>();
+        assert!((meta(&after) - meta(&before) - jab * (UPGRADE_FACTOR - 1.0)).abs() < 1e-4);
+        assert_eq!(scrap, UPGRADE_COST * 3);
+
+        for _ in 0..2 {
+            upgrade(&mut body.arms_mut()[0], arm.clone(), &mut scrap).unwrap();
+        }
+        assert_eq!(body.arms()[0].name(), "Typical Rusty Arm - V3");
+        assert_eq!(
+            upgrade(&mut body.arms_mut()[0], arm.clone(), &mut scrap),
+            Err(UpgradeError::MaxTier)
+        );
+        assert_eq!(scrap, UPGRADE_COST);
+
+        // Nothing is spent on a refused upgrade.
+        let mut leg = body.legs()[0].clone();
+        let wrong = BodyPart::new(
+            "Other",
+            PartStats::new(Material::Wood, 1.0, 1.0, 1.0, 1.0),
+            leg.meta().clone(),
+        );
+        assert_eq!(
+            upgrade(&mut leg, wrong, &mut scrap),
+            Err(UpgradeError::WrongMaterial)
+        );
+        let mut broke = 0;
+        assert_eq!(
+            upgrade(&mut leg, body.legs()[0].clone(), &mut broke),
+            Err(UpgradeError::NotEnoughScrap {
+                cost: UPGRADE_COST,
+                scrap: 0
+            })
+        );
+        assert_eq!(leg.tier(), 0);
+        // Generated names without a version get one.
+        assert_eq!(next_name("Shiny arm - AB12", 0), "Shiny arm - AB12 V1");
+    }
+
+    #[test]
+    fn upgrades_grow_the_overshield_too() {
+        let torso = Body::default().torso().clone();
+        let stats = torso.stats().clone().with_overshield(20.0);
+        let shielded = BodyPart::new(torso.name(), stats, torso.meta().clone());
+        let upgraded = upgraded(&shielded).unwrap();
+        assert!((upgraded.stats().overshield() - 20.0 * UPGRADE_FACTOR).abs() < 1e-4);
+    }
+
+    #[test]
+    fn upgraded_arms_keep_their_pin() {
+        let mut body = Body::default();
+        let ids = |body: &Body| {
+            let stats = compute_stats(body);
+            let order = bar_order(&stats.skills, &[]);
+            order
+                .into_iter()
+                .map(|i| stats.skills[i].id())
+                .collect::<Vec<_>>()
+        };
+        // The player dragged the bar into reverse, attacks before the walks.
+        let pinned = ids(&body).into_iter().rev().collect::<Vec<_>>();
+
+        let arm = body.arms()[0].clone();
+        let mut scrap = UPGRADE_COST;
+        upgrade(&mut body.arms_mut()[0], arm, &mut scrap).unwrap();
+        let after = compute_stats(&body);
+        let order = bar_order(&after.skills, &pinned);
+        let shown = order
+            .iter()
+            .map(|i| after.skills[*i].id())
+            .collect::<Vec<_>>();
+        assert_eq!(shown, pinned);
+    }
+
+    #[test]
+    fn attachments_keep_the_pin_and_the_stats() {
+        let mut body = Body::default();
+        let before = compute_stats(&body);
+        let pinned = bar_order(&before.skills, &[])
+            .into_iter()
+            .rev()
+            .map(|i| before.skills[i].id())
+            .collect::<Vec<_>>();
+        let mut usage = BTreeMap::new();
+        for id in &pinned {
+            usage.insert(id.clone(), SkillUsage::default());
+        }
+
+        let slot = body.arms()[0].slot();
+        body.attach(slot, Some(Attachment::Capacitor));
+        let after = compute_stats(&body);
+        // The capacitor changed the numbers, not the skill.
+        assert!(after.skills.iter().zip(&before.skills).any(|(a, b)| a
+            .ability()
+            .zip(b.ability())
+            .is_some_and(|(a, b)| a.cooldown != b.cooldown)));
+        let shown = bar_order(&after.skills, &pinned)
+            .into_iter()
+            .map(|i| after.skills[i].id())
+            .collect::<Vec<_>>();
+        assert_eq!(shown, pinned);
+        assert!(after
+            .skills
+            .iter()
+            .all(|skill| usage.contains_key(&skill.id())));
+    }
+}
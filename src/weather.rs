@@ -0,0 +1,296 @@
+use bevy::prelude::*;
+use rand::Rng;
+use serde::Deserialize;
+
+use crate::{
+    battle::{Ground, GroundSprite},
+    body::{update_body_system, Body, Stats},
+    bounds::StatModifiers,
+    layer::RenderLayer,
+    locale::t,
+    performance::{ParticlePool, PerformanceProfile, PooledParticle},
+    periodic::{PeriodicEffect, TickPayload},
+    tips::TipCards,
+    turn::Ruleset,
+    AppState, Game, GameRng,
+};
+
+// Energy a sandstorm takes from each body every second.
+const SANDSTORM_DRAIN: f32 = 1.0;
+const RAIN_SLIDE: f32 = 1.2;
+const NIGHT_ACCURACY: f32 = 0.8;
+// Particles spawned per second across the view.
+const RAIN_RATE: f32 = 120.0;
+const DUST_RATE: f32 = 40.0;
+const PARTICLE_WIDTH: f32 = 30.0;
+const PARTICLE_TOP: f32 = 12.0;
+
+// Rolled for every battle. Mostly looks, each kind but Clear also bends one
+// rule, see `apply` and `roll_weather_system`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+pub enum Weather {
+    #[default]
+    Clear,
+    Rain,
+    Night,
+    Sandstorm,
+}
+
+impl Weather {
+    fn id(&self) -> &'static str {
+        match self {
+            Weather::Clear => "clear",
+            Weather::Rain => "rain",
+            Weather::Night => "night",
+            Weather::Sandstorm => "sandstorm",
+        }
+    }
+
+    pub fn name(&self) -> String {
+        t!(format!("weather.{}.name", self.id()))
+    }
+
+    pub fn description(&self) -> String {
+        t!(format!("weather.{}.description", self.id()))
+    }
+
+    // What the ground does to every walk, wet ground slides further.
+    pub fn walk_multiplier(&self) -> f32 {
+        match self {
+            Weather::Rain => RAIN_SLIDE,
+            _ => 1.0,
+        }
+    }
+
+    fn sky(&self) -> Color {
+        match self {
+            Weather::Clear => ClearColor::default().0,
+            Weather::Rain => Color::rgb(0.3, 0.33, 0.38),
+            Weather::Night => Color::rgb(0.05, 0.06, 0.12),
+            Weather::Sandstorm => Color::rgb(0.55, 0.45, 0.3),
+        }
+    }
+
+    fn ground(&self) -> Color {
+        match self {
+            Weather::Clear => Color::BLACK,
+            Weather::Rain => Color::rgb(0.02, 0.04, 0.08),
+            Weather::Night => Color::rgb(0.0, 0.0, 0.03),
+            Weather::Sandstorm => Color::rgb(0.12, 0.09, 0.04),
+        }
+    }
+}
+
+// Clear half the time, the rest split evenly. Always clear where the rules
+// keep it out, unless the arena is set.
+pub fn roll(ruleset: &Ruleset, rng: &mut impl Rng) -> Weather {
+    if let Some(arena) = ruleset.arena {
+        return arena;
+    }
+    if !ruleset.rolls_weather() {
+        return Weather::Clear;
+    }
+    match rng.gen_range(0..6) {
+        0 => Weather::Rain,
+        1 => Weather::Night,
+        2 => Weather::Sandstorm,
+        _ => Weather::Clear,
+    }
+}
+
+// What the weather does to a body's stats. Only ever applied to freshly
+// computed stats, see `weather_stats_system`.
+pub fn apply(weather: Weather, stats: &mut Stats) {
+    match weather {
+        Weather::Rain => stats.speed *= weather.walk_multiplier(),
+        Weather::Night => stats.far_accuracy *= NIGHT_ACCURACY,
+        Weather::Clear | Weather::Sandstorm => {}
+    }
+}
+
+fn roll_weather_system(
+    mut commands: Commands,
+    game: Res<Game>,
+    ruleset: Res<Ruleset>,
+    mut rng: ResMut<GameRng>,
+    mut weather: ResMut<Weather>,
+    mut bodies: Query<&mut Body>,
+) {
+    let next = roll(&ruleset, &mut **rng);
+    if *weather != next {
+        *weather = next;
+        // Rebuilds the stats without the last battle's weather.
+        for mut body in &mut bodies {
+            body.set_changed();
+        }
+    }
+    if *weather == Weather::Sandstorm {
+        for target in [game.player, game.enemy] {
+            commands.spawn().insert(PeriodicEffect::until_removed(
+                target,
+                "sandstorm",
+                1.0,
+                TickPayload::Energy(-SANDSTORM_DRAIN),
+            ));
+        }
+    }
+}
+
+// Stats are rebuilt from scratch whenever the body changes, the weather goes
+// on top every time.
+fn weather_stats_system(weather: Res<Weather>, mut bodies: Query<&mut Stats, Changed<Body>>) {
+    for mut stats in &mut bodies {
+        apply(*weather, &mut stats);
+    }
+}
+
+// Rain streaks fall straight down, sandstorm dust blows across.
+#[derive(Component)]
+struct Particle {
+    velocity: Vec2,
+}
+
+fn sky_system(
+    weather: Res<Weather>,
+    mut clear: ResMut<ClearColor>,
+    mut grounds: Query<&mut Sprite, With<GroundSprite>>,
+) {
+    if !weather.is_changed() {
+        return;
+    }
+    clear.0 = weather.sky();
+    for mut sprite in &mut grounds {
+        sprite.color = weather.ground();
+    }
+}
+
+fn spawn_particles_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    weather: Res<Weather>,
+    ground: Res<Ground>,
+    performance: Res<PerformanceProfile>,
+    mut pool: ResMut<ParticlePool>,
+    cameras: Query<&Transform, With<Camera>>,
+) {
+    if !performance.weather_ambience {
+        return;
+    }
+    let (rate, size, color, velocity) = match *weather {
+        Weather::Rain => (
+            RAIN_RATE,
+            Vec2::new(0.02, 0.4),
+            Color::rgba(0.7, 0.8, 1.0, 0.5),
+            Vec2::new(-0.5, -14.0),
+        ),
+        Weather::Sandstorm => (
+            DUST_RATE,
+            Vec2::splat(0.06),
+            Color::rgba(0.8, 0.65, 0.4, 0.6),
+            Vec2::new(-6.0, -0.3),
+        ),
+        Weather::Clear | Weather::Night => return,
+    };
+    let centre = cameras
+        .get_single()
+        .map_or(0.0, |camera| camera.translation.x);
+    let mut rng = rand::thread_rng();
+    let count = rate * time.delta_seconds() + rng.gen::<f32>();
+    for _ in 0..pool.take(&performance, count as usize) {
+        let x = centre + rng.gen_range(-PARTICLE_WIDTH..=PARTICLE_WIDTH) / 2.0;
+        let y = ground.y + rng.gen_range(0.0..=PARTICLE_TOP);
+        commands
+            .spawn_bundle(SpriteBundle {
+                transform: Transform::from_xyz(x, y, RenderLayer::Particle.z())
+                    .with_scale(size.extend(1.0)),
+                sprite: Sprite { color, ..default() },
+                ..default()
+            })
+            .insert_bundle((Particle { velocity }, PooledParticle));
+    }
+}
+
+fn particle_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    weather: Res<Weather>,
+    ground: Res<Ground>,
+    mut particles: Query<(Entity, &Particle, &mut Transform)>,
+) {
+    for (entity, particle, mut transform) in &mut particles {
+        transform.translation += (particle.velocity * time.delta_seconds()).extend(0.0);
+        if ground.grounded(transform.translation.y) || *weather == Weather::Clear {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+// The rules, added by the battle so headless runs roll weather too.
+pub struct WeatherPlugin;
+
+impl Plugin for WeatherPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Weather>()
+            .add_system(
+                weather_stats_system
+                    .after(update_body_system)
+                    .label(StatModifiers),
+            )
+            .add_system_set(SystemSet::on_enter(AppState::Battle).with_system(roll_weather_system));
+    }
+}
+
+// What the weather looks like.
+pub struct SkyPlugin;
+
+impl Plugin for SkyPlugin {
+    fn build(&self, app: &mut App) {
+        app.world
+            .get_resource_or_insert_with(TipCards::default)
+            .register("sandstorm", "tip.sandstorm");
+        // Keeps raining behind the menus until the next battle rolls.
+        app.add_system(sky_system)
+            .add_system(spawn_particles_system)
+            .add_system(particle_system);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{body::compute_stats, turn::BattleMode};
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn weather_stays_out_where_the_rules_say() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let rolled = (0..200)
+            .map(|_| roll(&Ruleset::default(), &mut rng))
+            .collect::<Vec<_>>();
+        for weather in [Weather::Rain, Weather::Night, Weather::Sandstorm] {
+            assert!(rolled.contains(&weather), "{:?} never rolled", weather);
+        }
+        for ruleset in [
+            Ruleset {
+                weather: false,
+                ..default()
+            },
+            Ruleset {
+                mode: BattleMode::TurnBased,
+                ..default()
+            },
+        ] {
+            assert!((0..50).all(|_| roll(&ruleset, &mut rng) == Weather::Clear));
+        }
+
+        let mut clear = compute_stats(&Body::default());
+        clear.far_accuracy = 0.5;
+        let mut night = clear.clone();
+        apply(Weather::Night, &mut night);
+        assert_eq!(night.far_accuracy, clear.far_accuracy * NIGHT_ACCURACY);
+        assert_eq!(night.speed, clear.speed);
+        let mut rain = clear.clone();
+        apply(Weather::Rain, &mut rain);
+        assert!(rain.speed > clear.speed);
+    }
+}